@@ -5,13 +5,17 @@ use std::cmp::Ordering;
 /// Shared module for detecting Claude Code binary installations
 /// Supports NVM installations, aliased paths, version-based selection, and bundled sidecars
 /// Cross-platform support for Windows and macOS
-use std::collections::HashSet;
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Mutex;
 #[cfg(target_os = "windows")]
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Manager;
 
+use crate::error::{AppError, AppResult};
+
 /// 运行时环境信息（替换单纯的 #[cfg] 检测，支持容器/WSL/架构）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuntimeEnvironment {
@@ -23,11 +27,33 @@ pub struct RuntimeEnvironment {
 }
 
 /// 用户自定义二进制搜索配置 (~/.claude/binaries.json)
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BinarySearchConfig {
     pub claude: Option<BinarySearchSection>,
     pub codex: Option<BinarySearchSection>,
     pub gemini: Option<BinarySearchSection>,
+    /// 选择安装时跳过预发布版本（如 2.1.0-beta.1），除非没有任何正式版
+    #[serde(default = "default_prefer_stable")]
+    pub prefer_stable: bool,
+    /// 指定 node 可执行文件（PATH 中没有 node 的便携 / 受限环境），用于执行 .cmd 包装的脚本
+    #[serde(default)]
+    pub node_path: Option<String>,
+}
+
+fn default_prefer_stable() -> bool {
+    true
+}
+
+impl Default for BinarySearchConfig {
+    fn default() -> Self {
+        Self {
+            claude: None,
+            codex: None,
+            gemini: None,
+            prefer_stable: default_prefer_stable(),
+            node_path: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -37,6 +63,9 @@ pub struct BinarySearchSection {
     /// 额外搜索路径（目录或完整文件路径）
     #[serde(default)]
     pub search_paths: Vec<String>,
+    /// 启动该工具时注入的环境变量（优先级最高）
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
 }
 
 /// Get user home directory (cross-platform)
@@ -119,6 +148,76 @@ fn load_binary_search_config() -> BinarySearchConfig {
     BinarySearchConfig::default()
 }
 
+/// binaries.json 中配置的 node 可执行文件；首次使用时执行 `--version` 校验，校验失败则忽略该配置
+pub fn configured_node_path() -> Option<String> {
+    static CHECKED: Lazy<Mutex<HashMap<String, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    let node_path = load_binary_search_config().node_path?.trim().to_string();
+    if node_path.is_empty() {
+        return None;
+    }
+
+    let mut checked = CHECKED.lock().ok()?;
+    let valid = *checked.entry(node_path.clone()).or_insert_with(|| {
+        let mut cmd = Command::new(&node_path);
+        cmd.arg("--version");
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000);
+        }
+        match cmd.output() {
+            Ok(output) if output.status.success() => {
+                info!(
+                    "Using configured node {} ({})",
+                    node_path,
+                    String::from_utf8_lossy(&output.stdout).trim()
+                );
+                true
+            }
+            Ok(output) => {
+                warn!(
+                    "Configured node_path {} failed to run --version ({}), ignoring it",
+                    node_path, output.status
+                );
+                false
+            }
+            Err(e) => {
+                warn!(
+                    "Configured node_path {} could not be executed ({}), ignoring it",
+                    node_path, e
+                );
+                false
+            }
+        }
+    });
+    valid.then_some(node_path)
+}
+
+/// 配置了 node_path 时返回把其所在目录放到最前面的 PATH
+pub fn path_with_configured_node(current_path: &str) -> Option<String> {
+    let node_path = configured_node_path()?;
+    let node_dir = std::path::Path::new(&node_path).parent()?;
+    prepend_to_path(node_dir, current_path)
+}
+
+fn prepend_to_path(dir: &std::path::Path, current_path: &str) -> Option<String> {
+    let mut paths: Vec<PathBuf> = std::env::split_paths(current_path)
+        .filter(|path| !path.as_os_str().is_empty() && path.as_path() != dir)
+        .collect();
+    paths.insert(0, dir.to_path_buf());
+    std::env::join_paths(paths)
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+/// 读取 binaries.json 中为指定工具显式配置的环境变量
+pub fn load_tool_env(tool: &str) -> BTreeMap<String, String> {
+    pick_section(&load_binary_search_config(), tool)
+        .map(|section| section.env)
+        .unwrap_or_default()
+}
+
 fn pick_section(cfg: &BinarySearchConfig, key: &str) -> Option<BinarySearchSection> {
     match key {
         "claude" => cfg.claude.clone(),
@@ -821,6 +920,67 @@ fn find_fnm_multishell_candidates(_local_appdata: &str, _aliases: &[String]) ->
     Vec::new()
 }
 
+#[cfg(all(test, not(target_os = "windows")))]
+mod installation_source_tests {
+    use super::{
+        infer_installation_source, is_prerelease, prepend_to_path, select_best_installation,
+        ClaudeInstallation, InstallationType,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn prefer_stable_skips_prereleases_unless_nothing_else_exists() {
+        let install = |path: &str, version: &str| ClaudeInstallation {
+            path: path.to_string(),
+            version: Some(version.to_string()),
+            source: "PATH".to_string(),
+            installation_type: InstallationType::System,
+        };
+        assert!(is_prerelease("2.1.0-beta.1 (Claude Code)"));
+        assert!(!is_prerelease("2.0.5 (Claude Code)"));
+        assert!(!is_prerelease("v1.0.0+build.7"));
+
+        let candidates = vec![
+            install("/a/claude", "2.1.0-beta.1"),
+            install("/b/claude", "2.0.5"),
+        ];
+        let best = select_best_installation(candidates.clone(), true).unwrap();
+        assert_eq!(best.path, "/b/claude");
+        let best = select_best_installation(candidates, false).unwrap();
+        assert_eq!(best.path, "/a/claude");
+
+        let only_beta = vec![install("/a/claude", "2.1.0-beta.1")];
+        assert!(select_best_installation(only_beta, true).is_some());
+    }
+
+    #[test]
+    fn prepends_node_directory_once() {
+        let dir = Path::new("/opt/node/bin");
+        assert_eq!(
+            prepend_to_path(dir, "/usr/bin:/opt/node/bin:/bin").as_deref(),
+            Some("/opt/node/bin:/usr/bin:/bin")
+        );
+        assert_eq!(prepend_to_path(dir, "").as_deref(), Some("/opt/node/bin"));
+    }
+
+    #[test]
+    fn infers_package_manager_from_path() {
+        assert_eq!(
+            infer_installation_source("/Users/me/.nvm/versions/node/v20.10.0/bin/claude"),
+            Some("nvm")
+        );
+        assert_eq!(
+            infer_installation_source("/opt/homebrew/bin/claude"),
+            Some("homebrew")
+        );
+        assert_eq!(
+            infer_installation_source("/home/me/.volta/bin/claude"),
+            Some("volta")
+        );
+        assert_eq!(infer_installation_source("/srv/tools/claude"), None);
+    }
+}
+
 #[cfg(all(test, target_os = "windows"))]
 mod fnm_multishell_tests {
     use super::find_fnm_multishell_candidates;
@@ -855,10 +1015,53 @@ mod fnm_multishell_tests {
     }
 }
 
+/// 版本号是否带预发布标识（`1.2.3-beta.1`；`+build` 元数据不算）
+fn is_prerelease(version: &str) -> bool {
+    version
+        .split_whitespace()
+        .find(|token| {
+            token
+                .trim_start_matches('v')
+                .starts_with(|c: char| c.is_ascii_digit())
+        })
+        .and_then(|token| token.split('+').next())
+        .is_some_and(|core| core.contains('-'))
+}
+
+/// prefer_stable 时去掉预发布版本；没有任何正式版（或版本未知的安装）时保留全部候选
+fn skip_prereleases<T>(
+    candidates: Vec<T>,
+    prefer_stable: bool,
+    installation: impl Fn(&T) -> &ClaudeInstallation,
+) -> Vec<T> {
+    let is_pre = |candidate: &T| {
+        installation(candidate)
+            .version
+            .as_deref()
+            .is_some_and(is_prerelease)
+    };
+    if !prefer_stable || candidates.iter().all(is_pre) {
+        return candidates;
+    }
+
+    let (prereleases, stable): (Vec<T>, Vec<T>) = candidates.into_iter().partition(is_pre);
+    for skipped in &prereleases {
+        let skipped = installation(skipped);
+        info!(
+            "Skipping pre-release installation {} ({}) because a stable version is available (prefer_stable)",
+            skipped.path,
+            skipped.version.as_deref().unwrap_or_default()
+        );
+    }
+    stable
+}
+
 /// 按优先级 -> 版本降序选择最佳安装
 fn select_best_with_priority(
-    mut installations: Vec<PrioritizedInstallation>,
+    installations: Vec<PrioritizedInstallation>,
+    prefer_stable: bool,
 ) -> Option<ClaudeInstallation> {
+    let mut installations = skip_prereleases(installations, prefer_stable, |p| &p.installation);
     installations.sort_by(|a, b| {
         a.priority.cmp(&b.priority).then_with(|| {
             match (&a.installation.version, &b.installation.version) {
@@ -884,7 +1087,7 @@ pub fn detect_binary_for_tool(
     let user_section = pick_section(&user_cfg, config_key);
 
     let prioritized = collect_runtime_candidates(tool, env_var, &runtime_env, user_section);
-    let best = select_best_with_priority(prioritized);
+    let best = select_best_with_priority(prioritized, user_cfg.prefer_stable);
     (runtime_env, best)
 }
 
@@ -1037,10 +1240,72 @@ fn query_registry_paths(_tool: &str) -> Vec<String> {
     Vec::new()
 }
 
+/// 根据可执行文件路径推断包管理器 / 安装方式，无法判断时返回 None
+/// 会先解析符号链接（例如 /usr/local/bin/claude 实际指向 Homebrew 或 nvm 目录）
+pub fn infer_installation_source(path: &str) -> Option<&'static str> {
+    const PATTERNS: &[(&str, &str)] = &[
+        ("/.nvm/", "nvm"),
+        ("/nvm/", "nvm"),
+        ("/fnm_multishells/", "fnm-multishells"),
+        ("/fnm/", "fnm"),
+        ("/.volta/", "volta"),
+        ("/opt/homebrew/", "homebrew"),
+        ("/cellar/", "homebrew"),
+        ("/linuxbrew/", "homebrew"),
+        ("/.bun/", "bun"),
+        ("/.yarn/", "yarn"),
+        ("/.config/yarn/", "yarn"),
+        ("/.npm-global/", "npm-global"),
+        ("/appdata/roaming/npm/", "npm-global"),
+        ("/.claude/local/", "claude-local"),
+        ("/.local/bin/", "local-bin"),
+        ("/node_modules/", "node-modules"),
+        ("/usr/local/bin/", "system"),
+        ("/usr/bin/", "system"),
+    ];
+
+    let resolved = std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    [resolved.as_str(), path].iter().find_map(|candidate| {
+        let normalized = candidate.replace('\\', "/").to_lowercase();
+        PATTERNS
+            .iter()
+            .find(|(pattern, _)| normalized.contains(pattern))
+            .map(|(_, source)| *source)
+    })
+}
+
+/// 为已知路径构造安装信息；来源较笼统（PATH / cached 等）时附上从路径推断出的安装方式
+pub fn installation_for_path(
+    path: &str,
+    source: &str,
+    installation_type: InstallationType,
+) -> ClaudeInstallation {
+    ClaudeInstallation {
+        path: path.to_string(),
+        version: get_binary_version_generic(path),
+        source: describe_source(source, path),
+        installation_type,
+    }
+}
+
+fn describe_source(source: &str, path: &str) -> String {
+    match infer_installation_source(path) {
+        Some(inferred) if !source.starts_with(inferred) => format!("{} ({})", source, inferred),
+        _ => source.to_string(),
+    }
+}
+
 /// Main function to find the Claude binary - Cross-platform version
 /// Supports Windows and macOS, only uses system-installed Claude CLI
 /// 🔥 增强：添加详细日志，支持多 Node 版本场景
-pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, String> {
+pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> AppResult<String> {
+    find_claude_installation(app_handle).map(|installation| installation.path)
+}
+
+/// 与 `find_claude_binary` 相同的查找流程，但返回完整的安装信息（路径 / 版本 / 来源 / 类型）
+pub fn find_claude_installation(app_handle: &tauri::AppHandle) -> AppResult<ClaudeInstallation> {
     info!("========================================");
     info!("Starting Claude CLI binary search...");
     info!("========================================");
@@ -1098,7 +1363,22 @@ pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
                             // Test if the binary is actually executable
                             if test_claude_binary(&stored_path) {
                                 info!("✅ Using cached Claude CLI path: {}", stored_path);
-                                return Ok(stored_path);
+                                // 用户手动设置的路径同时记录在 binaries.json 中
+                                let is_custom = claude_override_path()
+                                    .is_some_and(|override_path| override_path == stored_path);
+                                return Ok(if is_custom {
+                                    installation_for_path(
+                                        &stored_path,
+                                        "custom",
+                                        InstallationType::Custom,
+                                    )
+                                } else {
+                                    installation_for_path(
+                                        &stored_path,
+                                        "cached",
+                                        InstallationType::System,
+                                    )
+                                });
                             } else {
                                 warn!(
                                     "❌ Cached claude path exists but is not executable: {}",
@@ -1146,7 +1426,9 @@ pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
 
     if prioritized.is_empty() {
         error!("❌ Could not find Claude CLI in any location (runtime detection empty)");
-        return Err("Claude CLI not found. 请安装 'npm install -g @anthropic-ai/claude-code' 或检查 CLAUDE_PATH 设置".to_string());
+        return Err(AppError::not_found(
+            "Claude CLI not found. 请安装 'npm install -g @anthropic-ai/claude-code' 或检查 CLAUDE_PATH 设置",
+        ));
     }
 
     info!(
@@ -1154,7 +1436,7 @@ pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
         prioritized.len()
     );
 
-    if let Some(best) = select_best_with_priority(prioritized) {
+    if let Some(best) = select_best_with_priority(prioritized, user_cfg.prefer_stable) {
         info!("========================================");
         info!("✅ Selected Claude CLI: {}", best.path);
         info!(
@@ -1169,10 +1451,13 @@ pub fn find_claude_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
             warn!("Failed to store claude path in database: {}", e);
         }
 
-        Ok(best.path)
+        let source = describe_source(&best.source, &best.path);
+        Ok(ClaudeInstallation { source, ..best })
     } else {
         error!("❌ No working Claude CLI installation found");
-        Err("No working Claude CLI installation found".to_string())
+        Err(AppError::not_found(
+            "No working Claude CLI installation found",
+        ))
     }
 }
 
@@ -1186,17 +1471,7 @@ fn store_claude_path(app_handle: &tauri::AppHandle, path: &str) -> Result<(), St
         let db_path = app_data_dir.join("agents.db");
         match rusqlite::Connection::open(&db_path) {
             Ok(conn) => {
-                // Create table if it doesn't exist
-                if let Err(e) = conn.execute(
-                    "CREATE TABLE IF NOT EXISTS app_settings (
-                        key TEXT PRIMARY KEY,
-                        value TEXT NOT NULL
-                    )",
-                    [],
-                ) {
-                    return Err(format!("Failed to create settings table: {}", e));
-                }
-
+                // app_settings is created by the migrations in init_database
                 // Store the path
                 if let Err(e) = conn.execute(
                     "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
@@ -1215,6 +1490,31 @@ fn store_claude_path(app_handle: &tauri::AppHandle, path: &str) -> Result<(), St
     }
 }
 
+/// binaries.json 中用户为 Claude 手动设置的路径
+pub fn claude_override_path() -> Option<String> {
+    pick_section(&load_binary_search_config(), "claude").and_then(|section| section.override_path)
+}
+
+/// 清除数据库中缓存的 Claude CLI 路径，下次查找时重新检测（例如 CLI 更新之后）
+pub fn clear_cached_claude_path(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let db_path = app_data_dir.join("agents.db");
+    if !db_path.exists() {
+        return Ok(());
+    }
+    let conn = rusqlite::Connection::open(&db_path)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    conn.execute(
+        "DELETE FROM app_settings WHERE key = 'claude_binary_path'",
+        [],
+    )
+    .map_err(|e| format!("Failed to clear cached claude path: {}", e))?;
+    Ok(())
+}
+
 /// Test if a Claude binary is actually functional (cross-platform)
 fn test_claude_binary(path: &str) -> bool {
     debug!("Testing Claude binary at: {}", path);
@@ -2007,7 +2307,7 @@ fn get_claude_version(path: &str) -> Result<Option<String>, String> {
 }
 
 /// Extract version string from command output
-fn extract_version_from_output(stdout: &[u8]) -> Option<String> {
+pub(crate) fn extract_version_from_output(stdout: &[u8]) -> Option<String> {
     let output_str = String::from_utf8_lossy(stdout);
 
     // Debug log the raw output
@@ -2038,11 +2338,15 @@ fn extract_version_from_output(stdout: &[u8]) -> Option<String> {
 
 /// Select the best installation based on version
 /// 🔥 增强：优先选择最新版本的 Claude CLI，并添加详细日志
-fn select_best_installation(installations: Vec<ClaudeInstallation>) -> Option<ClaudeInstallation> {
+fn select_best_installation(
+    installations: Vec<ClaudeInstallation>,
+    prefer_stable: bool,
+) -> Option<ClaudeInstallation> {
     if installations.is_empty() {
         warn!("No Claude installations to select from");
         return None;
     }
+    let installations = skip_prereleases(installations, prefer_stable, |i| i);
 
     info!(
         "Selecting best Claude installation from {} candidates",
@@ -2148,7 +2452,7 @@ fn select_best_installation(installations: Vec<ClaudeInstallation>) -> Option<Cl
 /// Windows-specific: Resolve .cmd wrapper to actual Node.js script path
 /// Returns (node_path, script_path) if successful
 #[cfg(target_os = "windows")]
-fn resolve_cmd_wrapper(cmd_path: &str) -> Option<(String, String)> {
+pub(crate) fn resolve_cmd_wrapper(cmd_path: &str) -> Option<(String, String)> {
     use std::fs;
 
     debug!("Attempting to resolve .cmd wrapper: {}", cmd_path);
@@ -2179,7 +2483,8 @@ fn resolve_cmd_wrapper(cmd_path: &str) -> Option<(String, String)> {
                         // Verify the script exists
                         if PathBuf::from(&script_path).exists() {
                             debug!("Resolved .cmd wrapper to script: {}", script_path);
-                            return Some(("node".to_string(), script_path));
+                            let node = configured_node_path().unwrap_or_else(|| "node".to_string());
+                            return Some((node, script_path));
                         }
                     }
                 }
@@ -2192,12 +2497,12 @@ fn resolve_cmd_wrapper(cmd_path: &str) -> Option<(String, String)> {
 }
 
 #[cfg(not(target_os = "windows"))]
-fn resolve_cmd_wrapper(_cmd_path: &str) -> Option<(String, String)> {
+pub(crate) fn resolve_cmd_wrapper(_cmd_path: &str) -> Option<(String, String)> {
     None
 }
 
 /// Compare two version strings
-fn compare_versions(a: &str, b: &str) -> Ordering {
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
     // Simple semantic version comparison
     let a_parts: Vec<u32> = a
         .split('.')
@@ -2292,7 +2597,7 @@ pub fn create_command_with_env(program: &str) -> Command {
             || key == "TMP";
 
         if should_pass {
-            debug!("Inheriting env var: {}={}", key, value);
+            debug!("Inheriting env var: {}", key);
             cmd.env(&key, &value);
         }
     }
@@ -2317,6 +2622,17 @@ pub fn create_command_with_env(program: &str) -> Command {
         }
     }
 
+    // binaries.json 中指定的 node 优先于 PATH 中的其他版本
+    let current_path = cmd
+        .get_envs()
+        .find(|(key, _)| *key == "PATH")
+        .and_then(|(_, value)| value.map(|v| v.to_string_lossy().to_string()))
+        .unwrap_or_else(|| std::env::var("PATH").unwrap_or_default());
+    if let Some(new_path) = path_with_configured_node(&current_path) {
+        debug!("Prepending configured node directory to PATH");
+        cmd.env("PATH", new_path);
+    }
+
     // 🔥 新增：读取 ~/.claude/settings.json 中的自定义环境变量
     // 这些变量会覆盖系统环境变量，确保用户的自定义配置生效
     if let Some(home_dir) = dirs::home_dir() {
@@ -2331,7 +2647,7 @@ pub fn create_command_with_env(program: &str) -> Command {
                         );
                         for (key, value) in env_obj {
                             if let Some(value_str) = value.as_str() {
-                                info!("Setting custom env var: {}={}", key, value_str);
+                                info!("Setting custom env var: {}", key);
                                 cmd.env(key, value_str);
                             }
                         }
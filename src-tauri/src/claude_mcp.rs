@@ -192,13 +192,19 @@ pub fn delete_mcp_server(id: &str) -> Result<bool, String> {
 
 /// 验证命令是否在 PATH 中可用
 pub fn validate_command_in_path(cmd: &str) -> Result<bool, String> {
+    Ok(find_command_in_path(cmd).is_some())
+}
+
+/// 在 PATH 中查找命令的完整路径（Windows 下按 PATHEXT 补全扩展名）；包含路径分隔符时只检查该路径是否存在
+pub fn find_command_in_path(cmd: &str) -> Option<PathBuf> {
     if cmd.trim().is_empty() {
-        return Ok(false);
+        return None;
     }
 
     // 如果包含路径分隔符，直接判断是否存在可执行文件
     if cmd.contains('/') || cmd.contains('\\') {
-        return Ok(Path::new(cmd).exists());
+        let path = Path::new(cmd);
+        return path.exists().then(|| path.to_path_buf());
     }
 
     let path_var = std::env::var_os("PATH").unwrap_or_default();
@@ -214,7 +220,7 @@ pub fn validate_command_in_path(cmd: &str) -> Result<bool, String> {
     for p in paths {
         let candidate = p.join(cmd);
         if candidate.is_file() {
-            return Ok(true);
+            return Some(candidate);
         }
 
         #[cfg(windows)]
@@ -222,13 +228,13 @@ pub fn validate_command_in_path(cmd: &str) -> Result<bool, String> {
             for ext in &exts {
                 let cand = p.join(format!("{}{}", cmd, ext));
                 if cand.is_file() {
-                    return Ok(true);
+                    return Some(cand);
                 }
             }
         }
     }
 
-    Ok(false)
+    None
 }
 
 /// 读取 ~/.claude.json 中的 mcpServers 映射
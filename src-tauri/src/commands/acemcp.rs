@@ -81,6 +81,36 @@ pub struct EnhancementResult {
     pub error: Option<String>,
 }
 
+/// 注入提示词的单条上下文片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSnippet {
+    /// 文件路径（相对项目根目录）
+    pub file_path: String,
+    /// 注入的代码片段
+    pub excerpt: String,
+    /// 相关度（0-1，按检索结果排序推算，越靠前越相关）
+    pub relevance_score: f32,
+}
+
+/// 提示词增强预览
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnhancedPromptPreview {
+    /// 原始提示词
+    pub original_prompt: String,
+    /// 增强后的提示词（包含上下文）
+    pub enhanced_prompt: String,
+    /// 注入的上下文片段
+    pub snippets: Vec<ContextSnippet>,
+    /// 使用的检索查询
+    pub queries: Vec<String>,
+    /// 使用的上下文长度预算
+    pub max_context_length: usize,
+    /// 检索结果是否因超出预算被截断
+    pub truncated: bool,
+}
+
 // ============================================================================
 // 对话历史分析
 // ============================================================================
@@ -1007,6 +1037,148 @@ fn truncate_utf8_safe(s: &str, max_bytes: usize) -> &str {
     }
 }
 
+/// 最大提示词长度
+const MAX_PROMPT_LENGTH: usize = 80_000;
+
+/// 增强后提示词的最大长度
+const MAX_TOTAL_OUTPUT_LENGTH: usize = 150_000;
+
+/// 未配置 MAX_CONTEXT_LENGTH 时的上下文长度预算
+const DEFAULT_MAX_CONTEXT_LENGTH: usize = 3000;
+
+/// 读取配置中的上下文长度预算
+async fn configured_max_context_length() -> usize {
+    load_acemcp_config()
+        .await
+        .ok()
+        .and_then(|config| config.max_context_length)
+        .map(|len| len as usize)
+        .unwrap_or(DEFAULT_MAX_CONTEXT_LENGTH)
+}
+
+/// 启动 acemcp 并检索上下文（单轮或多轮），结束后关闭客户端
+async fn retrieve_context(
+    app: &AppHandle,
+    project_path: &str,
+    queries: &[String],
+    max_length: usize,
+    multi_round: bool,
+) -> Result<String, String> {
+    // 启动 acemcp 客户端
+    let mut client = AcemcpClient::start(app).await.map_err(|e| {
+        error!("Failed to start acemcp: {}", e);
+        format!("Failed to start acemcp: {}", e)
+    })?;
+
+    // 初始化 MCP 会话
+    if let Err(e) = client.initialize().await {
+        error!("Failed to initialize MCP session: {}", e);
+        let _ = client.shutdown().await;
+        return Err(format!("Failed to initialize MCP: {}", e));
+    }
+
+    // 🚀 执行搜索（单轮或多轮）
+    let result = if queries.len() > 1 && multi_round {
+        info!("🔄 Using multi-round search with {} queries", queries.len());
+        client
+            .multi_round_search(project_path, queries, max_length * 2)
+            .await
+            .map_err(|e| {
+                error!("Failed to perform multi-round search: {}", e);
+                format!("Failed to search context: {}", e)
+            })
+    } else {
+        info!("🔍 Using single-round search");
+        client
+            .search_context(project_path, &queries[0])
+            .await
+            .map_err(|e| {
+                error!("Failed to search context: {}", e);
+                format!("Failed to search context: {}", e)
+            })
+    };
+
+    // 关闭客户端
+    let _ = client.shutdown().await;
+
+    result
+}
+
+/// 按预算截断上下文并拼接到提示词后
+/// 返回 (增强后的提示词, 实际注入的上下文)；上下文完全放不下时返回错误
+fn build_enhanced_prompt(
+    prompt: &str,
+    context_result: &str,
+    max_length: usize,
+) -> Result<(String, String), String> {
+    // ⚡ 改进：智能处理上下文结果
+    let trimmed_context = if context_result.len() > max_length {
+        warn!(
+            "Context too long ({} chars), truncating to {} chars",
+            context_result.len(),
+            max_length
+        );
+        format!(
+            "{}...\n\n[上下文过长，已自动截断。建议在设置中降低 maxContextLength 参数]",
+            truncate_utf8_safe(context_result, max_length)
+        )
+    } else {
+        context_result.to_string()
+    };
+
+    if trimmed_context.trim().is_empty() {
+        // 如果没有找到相关上下文，返回原提示词
+        info!("No relevant context found");
+        return Ok((prompt.to_string(), trimmed_context));
+    }
+
+    // ⚡ 改进：格式化增强后的提示词，并验证总长度
+    let candidate = format!(
+        "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
+        prompt.trim(),
+        trimmed_context
+    );
+
+    // 检查最终输出长度
+    if candidate.len() <= MAX_TOTAL_OUTPUT_LENGTH {
+        return Ok((candidate, trimmed_context));
+    }
+
+    warn!(
+        "Enhanced prompt too long ({} chars), exceeds maximum ({})",
+        candidate.len(),
+        MAX_TOTAL_OUTPUT_LENGTH
+    );
+
+    // 动态调整上下文长度
+    let available_space = MAX_TOTAL_OUTPUT_LENGTH.saturating_sub(prompt.len() + 100); // 预留100字符给分隔符
+    if available_space > 1000 {
+        let adjusted_context = format!(
+            "{}...\n\n[上下文已自动调整以适应长度限制]",
+            truncate_utf8_safe(&trimmed_context, available_space)
+        );
+        let enhanced = format!(
+            "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
+            prompt.trim(),
+            adjusted_context
+        );
+        Ok((enhanced, adjusted_context))
+    } else {
+        // 如果连最小的上下文都放不下，返回带警告的原提示词
+        warn!(
+            "Cannot fit any context, prompt too long: {} chars",
+            prompt.len()
+        );
+        Err(format!(
+            "提示词太长（{} 字符），无法添加项目上下文。\n\
+            建议：\n\
+            1. 缩短提示词长度\n\
+            2. 直接使用原提示词，不添加上下文",
+            prompt.len()
+        ))
+    }
+}
+
 #[tauri::command]
 pub async fn enhance_prompt_with_context(
     app: AppHandle,
@@ -1025,11 +1197,10 @@ pub async fn enhance_prompt_with_context(
         enable_multi_round.unwrap_or(true)
     );
 
-    // ⚡ 添加长度限制配置
-    const MAX_PROMPT_LENGTH: usize = 80_000; // 最大提示词长度
-    const MAX_TOTAL_OUTPUT_LENGTH: usize = 150_000; // 最大输出长度
-
-    let max_length = max_context_length.unwrap_or(3000);
+    let max_length = match max_context_length {
+        Some(len) => len,
+        None => configured_max_context_length().await,
+    };
 
     // ⚡ 检查提示词长度
     if prompt.len() > MAX_PROMPT_LENGTH {
@@ -1146,156 +1317,44 @@ pub async fn enhance_prompt_with_context(
         debug!("  Query {}: {}", i + 1, q);
     }
 
-    // 启动 acemcp 客户端
-    let mut client = match AcemcpClient::start(&app).await {
-        Ok(c) => c,
+    let context_result = match retrieve_context(
+        &app,
+        &project_path,
+        &valid_queries,
+        max_length,
+        enable_multi_round.unwrap_or(true),
+    )
+    .await
+    {
+        Ok(ctx) => ctx,
         Err(e) => {
-            error!("Failed to start acemcp: {}", e);
             return Ok(EnhancementResult {
                 original_prompt: prompt.clone(),
                 enhanced_prompt: prompt,
                 context_count: 0,
                 acemcp_used: false,
-                error: Some(format!("Failed to start acemcp: {}", e)),
+                error: Some(e),
             });
         }
     };
 
-    // 初始化 MCP 会话
-    if let Err(e) = client.initialize().await {
-        error!("Failed to initialize MCP session: {}", e);
-        let _ = client.shutdown().await;
-        return Ok(EnhancementResult {
-            original_prompt: prompt.clone(),
-            enhanced_prompt: prompt,
-            context_count: 0,
-            acemcp_used: false,
-            error: Some(format!("Failed to initialize MCP: {}", e)),
-        });
-    }
-
-    // 🚀 执行搜索（单轮或多轮）
-    let context_result = if valid_queries.len() > 1 && enable_multi_round.unwrap_or(true) {
-        info!(
-            "🔄 Using multi-round search with {} queries",
-            valid_queries.len()
-        );
-        match client
-            .multi_round_search(&project_path, &valid_queries, max_length * 2)
-            .await
-        {
-            Ok(ctx) => ctx,
-            Err(e) => {
-                error!("Failed to perform multi-round search: {}", e);
-                let _ = client.shutdown().await;
-                return Ok(EnhancementResult {
-                    original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt,
-                    context_count: 0,
-                    acemcp_used: false,
-                    error: Some(format!("Failed to search context: {}", e)),
-                });
-            }
-        }
-    } else {
-        info!("🔍 Using single-round search");
-        match client
-            .search_context(&project_path, &valid_queries[0])
-            .await
-        {
-            Ok(ctx) => ctx,
+    let (enhanced_prompt, trimmed_context) =
+        match build_enhanced_prompt(&prompt, &context_result, max_length) {
+            Ok(built) => built,
             Err(e) => {
-                error!("Failed to search context: {}", e);
-                let _ = client.shutdown().await;
                 return Ok(EnhancementResult {
                     original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt,
+                    enhanced_prompt: prompt.clone(),
                     context_count: 0,
                     acemcp_used: false,
-                    error: Some(format!("Failed to search context: {}", e)),
+                    error: Some(e),
                 });
             }
-        }
-    };
-
-    // 关闭客户端
-    let _ = client.shutdown().await;
-
-    // ⚡ 改进：智能处理上下文结果
-    let trimmed_context = if context_result.len() > max_length {
-        warn!(
-            "Context too long ({} chars), truncating to {} chars",
-            context_result.len(),
-            max_length
-        );
-        format!(
-            "{}...\n\n[上下文过长，已自动截断。建议在设置中降低 maxContextLength 参数]",
-            truncate_utf8_safe(&context_result, max_length)
-        )
-    } else {
-        context_result.clone()
-    };
+        };
 
     // 统计上下文条目数（简单计数 "Path:" 出现次数）
     let context_count = trimmed_context.matches("Path:").count();
 
-    // ⚡ 改进：格式化增强后的提示词，并验证总长度
-    let enhanced_prompt = if !trimmed_context.trim().is_empty() {
-        let candidate = format!(
-            "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
-            prompt.trim(),
-            trimmed_context
-        );
-
-        // 检查最终输出长度
-        if candidate.len() > MAX_TOTAL_OUTPUT_LENGTH {
-            warn!(
-                "Enhanced prompt too long ({} chars), exceeds maximum ({})",
-                candidate.len(),
-                MAX_TOTAL_OUTPUT_LENGTH
-            );
-
-            // 动态调整上下文长度
-            let available_space = MAX_TOTAL_OUTPUT_LENGTH.saturating_sub(prompt.len() + 100); // 预留100字符给分隔符
-            if available_space > 1000 {
-                let adjusted_context = format!(
-                    "{}...\n\n[上下文已自动调整以适应长度限制]",
-                    truncate_utf8_safe(&trimmed_context, available_space)
-                );
-                format!(
-                    "{}\n\n--- 项目上下文 (来自 acemcp 语义搜索) ---\n{}",
-                    prompt.trim(),
-                    adjusted_context
-                )
-            } else {
-                // 如果连最小的上下文都放不下，返回带警告的原提示词
-                warn!(
-                    "Cannot fit any context, prompt too long: {} chars",
-                    prompt.len()
-                );
-                return Ok(EnhancementResult {
-                    original_prompt: prompt.clone(),
-                    enhanced_prompt: prompt.clone(),
-                    context_count: 0,
-                    acemcp_used: false,
-                    error: Some(format!(
-                        "提示词太长（{} 字符），无法添加项目上下文。\n\
-                        建议：\n\
-                        1. 缩短提示词长度\n\
-                        2. 直接使用原提示词，不添加上下文",
-                        prompt.len()
-                    )),
-                });
-            }
-        } else {
-            candidate
-        }
-    } else {
-        // 如果没有找到相关上下文，返回原提示词
-        info!("No relevant context found");
-        prompt.clone()
-    };
-
     info!(
         "Enhanced prompt: original_len={}, context_len={}, enhanced_len={}, context_count={}",
         prompt.len(),
@@ -1313,6 +1372,81 @@ pub async fn enhance_prompt_with_context(
     })
 }
 
+/// 将 acemcp 返回的上下文拆分为片段（以 "Path:" 行分隔）
+fn parse_context_snippets(context: &str) -> Vec<ContextSnippet> {
+    let mut snippets: Vec<(String, Vec<&str>)> = Vec::new();
+
+    for line in context.lines() {
+        if let Some(path) = line.trim_start().strip_prefix("Path:") {
+            snippets.push((path.trim().to_string(), Vec::new()));
+        } else if let Some((_, body)) = snippets.last_mut() {
+            body.push(line);
+        }
+    }
+
+    let total = snippets.len();
+    snippets
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (file_path, body))| ContextSnippet {
+            file_path,
+            excerpt: body.join("\n").trim().to_string(),
+            relevance_score: (total - rank) as f32 / total as f32,
+        })
+        .collect()
+}
+
+/// 预览提示词增强：返回增强后的提示词及注入的上下文片段，不修改任何内容
+#[tauri::command]
+pub async fn preview_enhanced_prompt(
+    app: AppHandle,
+    prompt: String,
+    project_path: String,
+) -> Result<EnhancedPromptPreview, String> {
+    info!(
+        "preview_enhanced_prompt: prompt_len={}, project={}",
+        prompt.len(),
+        project_path
+    );
+
+    if prompt.len() > MAX_PROMPT_LENGTH {
+        return Err(format!(
+            "提示词过长（{} 字符），超过最大限制（{} 字符）。",
+            prompt.len(),
+            MAX_PROMPT_LENGTH
+        ));
+    }
+
+    if !std::path::Path::new(&project_path).exists() {
+        return Err("Project path does not exist".to_string());
+    }
+
+    let max_length = configured_max_context_length().await;
+
+    let extracted = extract_keywords_v2(&prompt);
+    let queries: Vec<String> = generate_multi_round_queries(&extracted, true)
+        .into_iter()
+        .filter(|q| !q.trim().is_empty())
+        .collect();
+
+    if queries.is_empty() {
+        return Err("No keywords could be extracted from prompt".to_string());
+    }
+
+    let context_result = retrieve_context(&app, &project_path, &queries, max_length, true).await?;
+    let (enhanced_prompt, injected_context) =
+        build_enhanced_prompt(&prompt, &context_result, max_length)?;
+
+    Ok(EnhancedPromptPreview {
+        original_prompt: prompt,
+        enhanced_prompt,
+        snippets: parse_context_snippets(&injected_context),
+        queries,
+        max_context_length: max_length,
+        truncated: context_result.len() > max_length,
+    })
+}
+
 /// 测试 acemcp 是否可用
 #[tauri::command]
 pub async fn test_acemcp_availability(app: AppHandle) -> Result<bool, String> {
@@ -1347,6 +1481,9 @@ pub struct AcemcpConfigData {
     pub token: String,
     pub batch_size: Option<u32>,
     pub max_lines_per_blob: Option<u32>,
+    /// 注入提示词的上下文长度预算（字符）
+    #[serde(default)]
+    pub max_context_length: Option<u32>,
 }
 
 impl Default for AcemcpConfigData {
@@ -1356,6 +1493,7 @@ impl Default for AcemcpConfigData {
             token: String::new(),
             batch_size: Some(10),
             max_lines_per_blob: Some(800),
+            max_context_length: Some(DEFAULT_MAX_CONTEXT_LENGTH as u32),
         }
     }
 }
@@ -1368,8 +1506,8 @@ pub async fn save_acemcp_config(
     token: String,
     batch_size: Option<u32>,
     max_lines_per_blob: Option<u32>,
+    max_context_length: Option<u32>,
 ) -> Result<(), String> {
-    use std::collections::HashMap;
     use std::fs;
 
     info!("Saving acemcp config: base_url={}", base_url);
@@ -1392,72 +1530,96 @@ pub async fn save_acemcp_config(
         ));
     }
 
-    // 读取现有配置（如果存在）
+    let existing_content = if config_file.exists() {
+        fs::read_to_string(&config_file)
+            .map_err(|e| format!("Failed to read existing config: {}", e))?
+    } else {
+        String::new()
+    };
+    let toml_content = render_acemcp_config(
+        &existing_content,
+        &base_url,
+        &token,
+        batch_size,
+        max_lines_per_blob,
+        max_context_length,
+    );
+
+    fs::write(&config_file, toml_content).map_err(|e| format!("Failed to write config: {}", e))?;
+
+    info!("Acemcp config saved to: {:?}", config_file);
+    Ok(())
+}
+
+/// 用 UI 管理的字段更新现有的 config.toml 内容，保留其他配置（包括多行数组）。
+/// `max_context_length` 为 None 时保留已有的 MAX_CONTEXT_LENGTH
+fn render_acemcp_config(
+    existing_content: &str,
+    base_url: &str,
+    token: &str,
+    batch_size: Option<u32>,
+    max_lines_per_blob: Option<u32>,
+    max_context_length: Option<u32>,
+) -> String {
+    use std::collections::HashMap;
+
+    let is_managed = |key: &str| {
+        matches!(
+            key,
+            "BASE_URL" | "TOKEN" | "BATCH_SIZE" | "MAX_LINES_PER_BLOB"
+        ) || (key == "MAX_CONTEXT_LENGTH" && max_context_length.is_some())
+    };
+
     // 需要正确处理多行数组格式（如 TEXT_EXTENSIONS = [...] 和 EXCLUDE_PATTERNS = [...]）
     let mut existing_entries: HashMap<String, String> = HashMap::new();
     let mut other_lines = Vec::new();
 
-    if config_file.exists() {
-        let existing_content = fs::read_to_string(&config_file)
-            .map_err(|e| format!("Failed to read existing config: {}", e))?;
+    let lines: Vec<&str> = existing_content.lines().collect();
+    let mut i = 0;
 
-        let lines: Vec<&str> = existing_content.lines().collect();
-        let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
 
-        while i < lines.len() {
-            let line = lines[i];
-            let trimmed = line.trim();
-
-            // 空行和注释
-            if trimmed.is_empty() || trimmed.starts_with('#') {
-                other_lines.push(line.to_string());
-                i += 1;
-                continue;
-            }
+        // 空行和注释
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            other_lines.push(line.to_string());
+            i += 1;
+            continue;
+        }
 
-            // 提取键名
-            if let Some(eq_pos) = trimmed.find('=') {
-                let key = trimmed[..eq_pos].trim();
-                let value_part = trimmed[eq_pos + 1..].trim();
+        // 提取键名
+        if let Some(eq_pos) = trimmed.find('=') {
+            let key = trimmed[..eq_pos].trim();
+            let value_part = trimmed[eq_pos + 1..].trim();
 
-                // 检查是否是多行数组（以 [ 开头但不以 ] 结尾）
-                if value_part.starts_with('[') && !value_part.ends_with(']') {
-                    // 多行数组：收集直到找到 ]
-                    let mut multiline_content = line.to_string();
-                    i += 1;
+            // 检查是否是多行数组（以 [ 开头但不以 ] 结尾）
+            if value_part.starts_with('[') && !value_part.ends_with(']') {
+                // 多行数组：收集直到找到 ]
+                let mut multiline_content = line.to_string();
+                i += 1;
 
-                    while i < lines.len() {
-                        let array_line = lines[i];
-                        multiline_content.push('\n');
-                        multiline_content.push_str(array_line);
+                while i < lines.len() {
+                    let array_line = lines[i];
+                    multiline_content.push('\n');
+                    multiline_content.push_str(array_line);
 
-                        if array_line.trim().ends_with(']') {
-                            break;
-                        }
-                        i += 1;
+                    if array_line.trim().ends_with(']') {
+                        break;
                     }
+                    i += 1;
+                }
 
-                    // 保留非 UI 管理的字段
-                    if key != "BASE_URL"
-                        && key != "TOKEN"
-                        && key != "BATCH_SIZE"
-                        && key != "MAX_LINES_PER_BLOB"
-                    {
-                        existing_entries.insert(key.to_string(), multiline_content);
-                    }
-                } else {
-                    // 单行配置
-                    if key != "BASE_URL"
-                        && key != "TOKEN"
-                        && key != "BATCH_SIZE"
-                        && key != "MAX_LINES_PER_BLOB"
-                    {
-                        existing_entries.insert(key.to_string(), line.to_string());
-                    }
+                // 保留非 UI 管理的字段
+                if !is_managed(key) {
+                    existing_entries.insert(key.to_string(), multiline_content);
                 }
+            } else if !is_managed(key) {
+                // 单行配置
+                existing_entries.insert(key.to_string(), line.to_string());
             }
-            i += 1;
         }
+        i += 1;
     }
 
     // 构建新的 TOML 内容
@@ -1475,6 +1637,10 @@ pub async fn save_acemcp_config(
         toml_content.push_str(&format!("MAX_LINES_PER_BLOB = {}\n", max_lines));
     }
 
+    if let Some(max_context) = max_context_length {
+        toml_content.push_str(&format!("MAX_CONTEXT_LENGTH = {}\n", max_context));
+    }
+
     // 保留的其他配置（包括多行数组）
     for entry in existing_entries.values() {
         toml_content.push_str(entry);
@@ -1489,10 +1655,7 @@ pub async fn save_acemcp_config(
         }
     }
 
-    fs::write(&config_file, toml_content).map_err(|e| format!("Failed to write config: {}", e))?;
-
-    info!("Acemcp config saved to: {:?}", config_file);
-    Ok(())
+    toml_content
 }
 
 /// 加载 acemcp 配置从 ~/.acemcp/config.toml
@@ -1537,11 +1700,17 @@ pub async fn load_acemcp_config() -> Result<AcemcpConfigData, String> {
     let content =
         fs::read_to_string(&config_file).map_err(|e| format!("Failed to read config: {}", e))?;
 
-    // 简单的 TOML 解析（只解析我们需要的字段）
+    info!("Loaded acemcp config from: {:?}", config_file);
+    Ok(parse_acemcp_config(&content))
+}
+
+/// 简单的 TOML 解析（只解析我们需要的字段）
+fn parse_acemcp_config(content: &str) -> AcemcpConfigData {
     let mut base_url = String::new();
     let mut token = String::new();
     let mut batch_size = None;
     let mut max_lines_per_blob = None;
+    let mut max_context_length = None;
 
     for line in content.lines() {
         let line = line.trim();
@@ -1561,16 +1730,20 @@ pub async fn load_acemcp_config() -> Result<AcemcpConfigData, String> {
             if let Some(value) = extract_toml_number_value(line) {
                 max_lines_per_blob = Some(value);
             }
+        } else if line.starts_with("MAX_CONTEXT_LENGTH") {
+            if let Some(value) = extract_toml_number_value(line) {
+                max_context_length = Some(value);
+            }
         }
     }
 
-    info!("Loaded acemcp config from: {:?}", config_file);
-    Ok(AcemcpConfigData {
+    AcemcpConfigData {
         base_url,
         token,
         batch_size,
         max_lines_per_blob,
-    })
+        max_context_length,
+    }
 }
 
 /// 提取 TOML 字符串值
@@ -1599,13 +1772,571 @@ fn extract_toml_number_value(line: &str) -> Option<u32> {
 // 后台预索引
 // ============================================================================
 
+/// 预索引进度事件名
+const PREINDEX_PROGRESS_EVENT: &str = "acemcp-index-progress";
+
+/// 扫描阶段每隔多少个文件发送一次进度事件
+const PREINDEX_PROGRESS_INTERVAL: usize = 100;
+
+/// 未配置 TEXT_EXTENSIONS 时使用的默认扩展名（与 sidecar 默认配置一致）
+const DEFAULT_TEXT_EXTENSIONS: &[&str] = &[
+    ".py", ".js", ".ts", ".jsx", ".tsx", ".java", ".go", ".rs", ".cpp", ".c", ".h", ".hpp", ".cs",
+    ".rb", ".php", ".md", ".txt", ".json", ".yaml", ".yml", ".toml", ".xml", ".html", ".css",
+    ".scss", ".sql", ".sh", ".bash",
+];
+
+/// 未配置 EXCLUDE_PATTERNS 时使用的默认排除规则
+const DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[
+    "**/node_modules/**",
+    "**/*.min.js",
+    "**/*.min.css",
+    "**/dist/**",
+    "**/build/**",
+    "**/coverage/**",
+    "**/__pycache__/**",
+    "**/venv/**",
+    "**/.venv/**",
+    "**/.git/**",
+    "**/target/**",
+];
+
+/// 预索引任务阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreindexPhase {
+    /// 正在遍历项目文件
+    Scanning,
+    /// 文件遍历完成，正在上传变更文件
+    Indexing,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl PreindexPhase {
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            PreindexPhase::Completed | PreindexPhase::Cancelled | PreindexPhase::Failed
+        )
+    }
+}
+
+/// 预索引进度（同时作为 acemcp-index-progress 事件负载和状态查询结果）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreindexProgress {
+    pub job_id: String,
+    pub project_path: String,
+    pub phase: PreindexPhase,
+    /// 已扫描的可索引文件数
+    pub files_scanned: usize,
+    /// 可索引文件总数（扫描完成前未知）
+    pub total_files: Option<usize>,
+    /// 当前正在处理的文件
+    pub current_path: Option<String>,
+    /// 是否为增量模式
+    pub incremental: bool,
+    /// 与上次索引清单相比的变更统计（扫描完成后可用）
+    pub changes: Option<PreindexChanges>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 增量索引的变更统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreindexChanges {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// 索引清单中的单个文件记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    /// 修改时间（Unix 毫秒）
+    mtime: u64,
+    size: u64,
+    /// 该文件上传后对应的 blob 名称，增量索引时用于替换或移除旧 blob
+    #[serde(default)]
+    blobs: Vec<String>,
+}
+
+impl ManifestEntry {
+    fn is_same_file(&self, other: &Self) -> bool {
+        self.mtime == other.mtime && self.size == other.size
+    }
+}
+
+/// 索引清单：记录上次成功索引时每个文件的 mtime，用于增量索引
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreindexManifest {
+    project_path: String,
+    indexed_at: String,
+    files: std::collections::HashMap<String, ManifestEntry>,
+}
+
+impl PreindexManifest {
+    /// 清单文件路径：~/.acemcp/manifests/{md5(project_path)}.json
+    fn path_for(project_path: &str) -> Option<PathBuf> {
+        dirs::home_dir().map(|home| {
+            home.join(".acemcp")
+                .join("manifests")
+                .join(format!("{:x}.json", md5::compute(project_path)))
+        })
+    }
+
+    fn load(project_path: &str) -> Option<Self> {
+        let path = Self::path_for(project_path)?;
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path_for(&self.project_path)
+            .ok_or_else(|| anyhow::anyhow!("Cannot find home directory"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // 先写临时文件再重命名，避免留下写了一半的清单
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// 旧版本清单没有记录 blob 名称，无法用于增量索引
+    fn has_blobs(&self) -> bool {
+        self.files.values().all(|entry| !entry.blobs.is_empty())
+    }
+
+    /// 对比当前扫描结果与清单
+    fn diff(&self, current: &std::collections::HashMap<String, ManifestEntry>) -> PreindexChanges {
+        let mut changes = PreindexChanges::default();
+        for (path, entry) in current {
+            match self.files.get(path) {
+                None => changes.added += 1,
+                Some(previous) if !previous.is_same_file(entry) => changes.updated += 1,
+                Some(_) => changes.unchanged += 1,
+            }
+        }
+        changes.removed = self
+            .files
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .count();
+        changes
+    }
+}
+
+/// 预索引任务
+struct PreindexJob {
+    progress: PreindexProgress,
+    cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    cancel_notify: std::sync::Arc<tokio::sync::Notify>,
+}
+
+lazy_static::lazy_static! {
+    /// 预索引任务表（job_id → 任务）
+    static ref PREINDEX_JOBS: std::sync::Mutex<std::collections::HashMap<String, PreindexJob>> =
+        std::sync::Mutex::new(std::collections::HashMap::new());
+}
+
+/// 更新任务进度并发送事件
+fn update_preindex_progress(
+    app: &AppHandle,
+    job_id: &str,
+    update: impl FnOnce(&mut PreindexProgress),
+) {
+    use tauri::Emitter;
+
+    let snapshot = {
+        let mut jobs = PREINDEX_JOBS.lock().unwrap();
+        match jobs.get_mut(job_id) {
+            Some(job) => {
+                update(&mut job.progress);
+                job.progress.clone()
+            }
+            None => return,
+        }
+    };
+
+    if let Err(e) = app.emit(PREINDEX_PROGRESS_EVENT, &snapshot) {
+        warn!("Failed to emit pre-index progress: {}", e);
+    }
+}
+
+/// 从 ~/.acemcp/config.toml 读取扫描规则（扩展名、排除规则），缺失时使用默认值
+fn load_preindex_scan_rules() -> (Vec<String>, Vec<glob::Pattern>) {
+    let config: Option<toml::Value> = dirs::home_dir()
+        .map(|home| home.join(".acemcp").join("config.toml"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str(&content).ok());
+
+    let string_list = |key: &str, defaults: &[&str]| -> Vec<String> {
+        config
+            .as_ref()
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|i| i.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_else(|| defaults.iter().map(|s| s.to_string()).collect())
+    };
+
+    let extensions = string_list("TEXT_EXTENSIONS", DEFAULT_TEXT_EXTENSIONS)
+        .into_iter()
+        .map(|e| e.to_lowercase())
+        .collect();
+    let patterns = string_list("EXCLUDE_PATTERNS", DEFAULT_EXCLUDE_PATTERNS)
+        .iter()
+        .filter_map(|p| glob::Pattern::new(p).ok())
+        .collect();
+
+    (extensions, patterns)
+}
+
+/// 遍历项目，收集可索引文件及其 mtime；返回 None 表示已取消
+fn scan_project_files(
+    app: &AppHandle,
+    job_id: &str,
+    project_path: &str,
+    cancel_flag: &std::sync::atomic::AtomicBool,
+) -> Option<std::collections::HashMap<String, ManifestEntry>> {
+    use std::sync::atomic::Ordering;
+
+    let (extensions, exclude_patterns) = load_preindex_scan_rules();
+    let root = std::path::Path::new(project_path);
+    let relative = |path: &std::path::Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let is_excluded = |rel: &str| exclude_patterns.iter().any(|p| p.matches(rel));
+
+    let mut files = std::collections::HashMap::new();
+    let mut last_reported = 0;
+    let walker = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let rel = relative(entry.path());
+            if entry.file_type().is_dir() {
+                // 用一个虚拟子路径检测 "**/dir/**" 形式的目录排除规则
+                !is_excluded(&format!("{}/_", rel))
+            } else {
+                !is_excluded(&rel)
+            }
+        });
+
+    for entry in walker.flatten() {
+        if cancel_flag.load(Ordering::SeqCst) {
+            return None;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if !extensions.iter().any(|ext| name.ends_with(ext.as_str())) {
+            continue;
+        }
+
+        let rel = relative(entry.path());
+        let (mtime, size) = entry
+            .metadata()
+            .map(|meta| {
+                let mtime = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0);
+                (mtime, meta.len())
+            })
+            .unwrap_or((0, 0));
+        files.insert(
+            rel.clone(),
+            ManifestEntry {
+                mtime,
+                size,
+                blobs: Vec::new(),
+            },
+        );
+
+        let files_scanned = files.len();
+        if files_scanned - last_reported >= PREINDEX_PROGRESS_INTERVAL {
+            last_reported = files_scanned;
+            update_preindex_progress(app, job_id, |p| {
+                p.files_scanned = files_scanned;
+                p.current_path = Some(rel);
+            });
+        }
+    }
+
+    Some(files)
+}
+
+/// sidecar 索引记录文件（~/.acemcp/projects.json），记录每个项目已上传的 blob 名称
+fn acemcp_projects_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".acemcp").join("projects.json"))
+}
+
+fn load_projects_file() -> serde_json::Map<String, Value> {
+    acemcp_projects_file()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_projects_file(projects: &serde_json::Map<String, Value>) -> Result<()> {
+    let path =
+        acemcp_projects_file().ok_or_else(|| anyhow::anyhow!("Cannot find home directory"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // 先写临时文件再重命名，保证替换是原子的
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(projects)?)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// projects.json 中的项目键，与 sidecar 的 normalizeProjectPath 保持一致
+fn sidecar_project_key(project_path: &str) -> String {
+    let mut key = project_path.trim().replace('\\', "/");
+
+    // \\wsl$\Distro\home\... → /home/...
+    if let Some(rest) = key.strip_prefix("//wsl$/") {
+        let parts: Vec<&str> = rest.split('/').filter(|p| !p.is_empty()).collect();
+        if parts.len() >= 2 {
+            return format!("/{}", parts[1..].join("/"));
+        }
+    }
+
+    let min_len = if key.starts_with('/') { 1 } else { 3 };
+    if key.len() > min_len && key.ends_with('/') {
+        key.pop();
+    }
+    key
+}
+
+/// 按行切分并保留换行符（\n、\r\n、\r），与 sidecar 的切分方式一致
+fn split_lines_keep_ends(content: &str) -> Vec<&str> {
+    let bytes = content.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                lines.push(&content[start..=i]);
+                start = i + 1;
+            }
+            b'\r' => {
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                lines.push(&content[start..=i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < bytes.len() {
+        lines.push(&content[start..]);
+    }
+    lines
+}
+
+/// 待上传的 blob（字段与 sidecar 的 batch-upload 请求一致）
+#[derive(Debug, Clone, Serialize)]
+struct IndexBlob {
+    path: String,
+    content: String,
+}
+
+impl IndexBlob {
+    /// sha256(path + content)，与 sidecar 计算的 blob 名称一致
+    fn name(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.path.as_bytes());
+        hasher.update(self.content.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// 将文件内容拆分为 blob；超过 max_lines 行时分块，路径追加 `#chunkNofM`
+fn split_into_blobs(path: &str, content: &str, max_lines: usize) -> Vec<IndexBlob> {
+    let max_lines = max_lines.max(1);
+    let lines = split_lines_keep_ends(content);
+    if lines.len() <= max_lines {
+        return vec![IndexBlob {
+            path: path.to_string(),
+            content: content.to_string(),
+        }];
+    }
+
+    let chunks = lines.len().div_ceil(max_lines);
+    lines
+        .chunks(max_lines)
+        .enumerate()
+        .map(|(i, chunk)| IndexBlob {
+            path: format!("{}#chunk{}of{}", path, i + 1, chunks),
+            content: chunk.concat(),
+        })
+        .collect()
+}
+
+/// 上传失败时的最大尝试次数
+const UPLOAD_ATTEMPTS: u32 = 3;
+
+/// 直接调用 acemcp 上传接口，只上传新增或修改过的文件
+struct BlobUploader {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+    batch_size: usize,
+    max_lines_per_blob: usize,
+}
+
+impl BlobUploader {
+    async fn from_config() -> Result<Self> {
+        let config = load_acemcp_config().await.map_err(|e| anyhow::anyhow!(e))?;
+        let base_url = config.base_url.trim().trim_end_matches('/');
+        if base_url.is_empty() || config.token.trim().is_empty() {
+            return Err(anyhow::anyhow!(
+                "Acemcp BASE_URL or TOKEN is not configured"
+            ));
+        }
+
+        let base_url = if base_url.starts_with("http://") || base_url.starts_with("https://") {
+            base_url.to_string()
+        } else {
+            format!("https://{}", base_url)
+        };
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()?;
+
+        Ok(Self {
+            client,
+            url: format!("{}/batch-upload", base_url),
+            token: config.token,
+            batch_size: config.batch_size.unwrap_or(10).max(1) as usize,
+            max_lines_per_blob: config.max_lines_per_blob.unwrap_or(800) as usize,
+        })
+    }
+
+    /// 上传一批 blob，失败时退避重试；返回服务端确认的 blob 名称
+    async fn upload(&self, blobs: &[IndexBlob]) -> Result<Vec<String>> {
+        let mut last_error = None;
+        for attempt in 0..UPLOAD_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(std::time::Duration::from_secs(2 << (attempt - 1))).await;
+            }
+            match self.try_upload(blobs).await {
+                Ok(names) => return Ok(names),
+                Err(e) => {
+                    warn!(
+                        "Blob upload attempt {}/{} failed: {}",
+                        attempt + 1,
+                        UPLOAD_ATTEMPTS,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Blob upload failed")))
+    }
+
+    async fn try_upload(&self, blobs: &[IndexBlob]) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct UploadResponse {
+            blob_names: Vec<String>,
+        }
+
+        let response: UploadResponse = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.token)
+            .json(&json!({ "blobs": blobs }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if response.blob_names.len() != blobs.len() {
+            return Err(anyhow::anyhow!(
+                "Upload returned {} blob names for {} blobs",
+                response.blob_names.len(),
+                blobs.len()
+            ));
+        }
+        Ok(response.blob_names)
+    }
+}
+
+/// 读取文件并拆分为 blob；读取失败的文件跳过（与 sidecar 一致），返回 None 表示已取消
+fn read_project_blobs(
+    project_path: &str,
+    paths: &[String],
+    max_lines: usize,
+    cancel_flag: &std::sync::atomic::AtomicBool,
+) -> Option<Vec<(String, Vec<IndexBlob>)>> {
+    use std::sync::atomic::Ordering;
+
+    let root = std::path::Path::new(project_path);
+    let mut result = Vec::with_capacity(paths.len());
+    for rel in paths {
+        if cancel_flag.load(Ordering::SeqCst) {
+            return None;
+        }
+        match std::fs::read(root.join(rel)) {
+            Ok(bytes) => {
+                let content = String::from_utf8_lossy(&bytes);
+                result.push((rel.clone(), split_into_blobs(rel, &content, max_lines)));
+            }
+            Err(e) => warn!("Failed to read {} for indexing: {}", rel, e),
+        }
+    }
+    Some(result)
+}
+
 /// 后台预索引项目（不阻塞 UI）
-/// 在用户选择项目后自动调用，提前完成索引以加快后续搜索
+/// 在用户选择项目后自动调用，提前完成索引以加快后续搜索。
+/// 立即返回任务 ID，进度通过 `acemcp-index-progress` 事件推送。
+///
+/// `incremental` 为 true 时对比上次索引清单中的文件 mtime，
+/// 无变更则跳过索引，否则只上传新增/修改的文件并移除已删除文件的 blob；
+/// 首次运行（无清单）时执行完整索引并写入清单。上传失败时任务标记为失败
 #[tauri::command]
-pub async fn preindex_project(app: AppHandle, project_path: String) -> Result<(), String> {
+pub async fn preindex_project(
+    app: AppHandle,
+    project_path: String,
+    incremental: Option<bool>,
+) -> Result<String, String> {
+    let incremental = incremental.unwrap_or(false);
     info!(
-        "Starting background pre-indexing for project: {}",
-        project_path
+        "Starting background pre-indexing for project: {} (incremental: {})",
+        project_path, incremental
     );
 
     // 检查项目路径是否存在
@@ -1614,48 +2345,293 @@ pub async fn preindex_project(app: AppHandle, project_path: String) -> Result<()
             "Project path does not exist, skipping pre-index: {}",
             project_path
         );
-        return Ok(());
+        return Err(format!("Project path does not exist: {}", project_path));
     }
 
+    let job_id = {
+        let mut jobs = PREINDEX_JOBS.lock().unwrap();
+
+        // 同一项目已有进行中的任务时直接复用
+        if let Some((id, _)) = jobs.iter().find(|(_, job)| {
+            job.progress.project_path == project_path && !job.progress.phase.is_finished()
+        }) {
+            info!(
+                "Pre-indexing already running for {}: job {}",
+                project_path, id
+            );
+            return Ok(id.clone());
+        }
+
+        // 清理该项目已结束的旧任务
+        jobs.retain(|_, job| job.progress.project_path != project_path);
+
+        let job_id = uuid::Uuid::new_v4().to_string();
+        jobs.insert(
+            job_id.clone(),
+            PreindexJob {
+                progress: PreindexProgress {
+                    job_id: job_id.clone(),
+                    project_path: project_path.clone(),
+                    phase: PreindexPhase::Scanning,
+                    files_scanned: 0,
+                    total_files: None,
+                    current_path: None,
+                    incremental,
+                    changes: None,
+                    error: None,
+                },
+                cancel_flag: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+                cancel_notify: std::sync::Arc::new(tokio::sync::Notify::new()),
+            },
+        );
+        job_id
+    };
+
     // 启动后台任务进行索引
+    let task_job_id = job_id.clone();
     tauri::async_runtime::spawn(async move {
-        match preindex_project_internal(&app, &project_path).await {
-            Ok(_) => {
-                info!("✅ Background pre-indexing completed for: {}", project_path);
-            }
-            Err(e) => {
-                warn!(
-                    "⚠️ Background pre-indexing failed for {}: {}",
-                    project_path, e
-                );
-            }
-        }
+        let phase =
+            match preindex_project_internal(&app, &task_job_id, &project_path, incremental).await {
+                Ok(true) => {
+                    info!("✅ Background pre-indexing completed for: {}", project_path);
+                    PreindexPhase::Completed
+                }
+                Ok(false) => {
+                    info!("Background pre-indexing cancelled for: {}", project_path);
+                    PreindexPhase::Cancelled
+                }
+                Err(e) => {
+                    warn!(
+                        "⚠️ Background pre-indexing failed for {}: {}",
+                        project_path, e
+                    );
+                    update_preindex_progress(&app, &task_job_id, |p| p.error = Some(e.to_string()));
+                    PreindexPhase::Failed
+                }
+            };
+        update_preindex_progress(&app, &task_job_id, |p| {
+            p.phase = phase;
+            p.current_path = None;
+        });
     });
 
     // 立即返回，不等待索引完成
-    Ok(())
+    Ok(job_id)
 }
 
-/// 内部预索引实现
-async fn preindex_project_internal(app: &AppHandle, project_path: &str) -> Result<()> {
+/// 内部预索引实现；返回 Ok(false) 表示任务被取消
+///
+/// 增量模式下只读取并上传新增或修改过的文件，未变更文件沿用清单中记录的 blob；
+/// 已上传过的 blob（projects.json 中已存在）不会重复上传
+async fn preindex_project_internal(
+    app: &AppHandle,
+    job_id: &str,
+    project_path: &str,
+    incremental: bool,
+) -> Result<bool> {
+    use std::sync::atomic::Ordering;
+
     info!("🔄 Pre-indexing project: {}", project_path);
 
-    // 启动 acemcp 客户端
-    let mut client = AcemcpClient::start(app).await?;
+    let (cancel_flag, cancel_notify) = {
+        let jobs = PREINDEX_JOBS.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Pre-index job {} not found", job_id))?;
+        (job.cancel_flag.clone(), job.cancel_notify.clone())
+    };
 
-    // 初始化 MCP 会话
-    client.initialize().await?;
+    // 1. 遍历项目文件，推送扫描进度
+    let scan_app = app.clone();
+    let scan_job_id = job_id.to_string();
+    let scan_path = project_path.to_string();
+    let scan_flag = cancel_flag.clone();
+    let files = tokio::task::spawn_blocking(move || {
+        scan_project_files(&scan_app, &scan_job_id, &scan_path, &scan_flag)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Scan task failed: {}", e))?;
 
-    // 调用 search_context，触发自动索引
-    // 使用一个通用的查询来触发索引，不关心搜索结果
-    let _ = client
-        .search_context(project_path, "preindex initialization")
-        .await;
+    let Some(files) = files else {
+        return Ok(false);
+    };
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No text files found in project"));
+    }
 
-    // 关闭客户端
-    client.shutdown().await?;
+    // 与上次索引清单对比（首次运行时清单为空，所有文件都计为新增）
+    let previous = PreindexManifest::load(project_path).filter(PreindexManifest::has_blobs);
+    let changes = previous.as_ref().map_or_else(
+        || PreindexManifest::default().diff(&files),
+        |m| m.diff(&files),
+    );
+    let total = files.len();
+    let up_to_date = changes.added == 0 && changes.updated == 0 && changes.removed == 0;
 
-    Ok(())
+    info!(
+        "Pre-index scan for {}: {} files (added: {}, updated: {}, removed: {})",
+        project_path, total, changes.added, changes.updated, changes.removed
+    );
+
+    update_preindex_progress(app, job_id, |p| {
+        p.phase = PreindexPhase::Indexing;
+        p.files_scanned = total;
+        p.total_files = Some(total);
+        p.current_path = None;
+        p.changes = Some(changes);
+    });
+
+    if cancel_flag.load(Ordering::SeqCst) {
+        return Ok(false);
+    }
+
+    // 增量模式下无变更则跳过索引
+    if incremental && previous.is_some() && up_to_date {
+        info!("Index for {} is up to date, skipping", project_path);
+        return Ok(true);
+    }
+
+    let uploader = BlobUploader::from_config().await?;
+    let project_key = sidecar_project_key(project_path);
+    let mut projects = load_projects_file();
+    let existing: HashSet<String> = projects
+        .get(&project_key)
+        .and_then(|v| v.as_array())
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|n| n.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // 2. 确定需要重新读取的文件：增量模式下跳过未变更且 blob 仍在索引中的文件
+    let mut file_blobs: std::collections::HashMap<String, Vec<String>> =
+        std::collections::HashMap::new();
+    let mut to_index = Vec::new();
+    for (path, entry) in &files {
+        let reusable = previous
+            .as_ref()
+            .filter(|_| incremental)
+            .and_then(|m| m.files.get(path))
+            .filter(|old| {
+                old.is_same_file(entry) && old.blobs.iter().all(|b| existing.contains(b))
+            });
+        match reusable {
+            Some(old) => {
+                file_blobs.insert(path.clone(), old.blobs.clone());
+            }
+            None => to_index.push(path.clone()),
+        }
+    }
+
+    let reused = total - to_index.len();
+    let read_path = project_path.to_string();
+    let read_flag = cancel_flag.clone();
+    let max_lines = uploader.max_lines_per_blob;
+    let blobs = tokio::task::spawn_blocking(move || {
+        read_project_blobs(&read_path, &to_index, max_lines, &read_flag)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Read task failed: {}", e))?;
+
+    let Some(blobs) = blobs else {
+        return Ok(false);
+    };
+
+    // 3. 只上传索引中还没有的 blob
+    let mut pending = Vec::new();
+    for (path, file) in blobs {
+        let names: Vec<String> = file.iter().map(IndexBlob::name).collect();
+        for (index, (blob, name)) in file.into_iter().zip(&names).enumerate() {
+            if !existing.contains(name) {
+                pending.push((path.clone(), index, blob));
+            }
+        }
+        file_blobs.insert(path, names);
+    }
+
+    info!(
+        "Uploading {} blobs for {} ({} files reused from manifest)",
+        pending.len(),
+        project_path,
+        reused
+    );
+
+    for batch in pending.chunks(uploader.batch_size) {
+        if cancel_flag.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        let first_path = batch[0].0.clone();
+        update_preindex_progress(app, job_id, |p| p.current_path = Some(first_path));
+
+        let request: Vec<IndexBlob> = batch.iter().map(|(_, _, blob)| blob.clone()).collect();
+        let names = tokio::select! {
+            result = uploader.upload(&request) => result?,
+            _ = cancel_notify.notified() => return Ok(false),
+        };
+        for ((path, index, _), name) in batch.iter().zip(names) {
+            if let Some(slot) = file_blobs.get_mut(path).and_then(|n| n.get_mut(*index)) {
+                *slot = name;
+            }
+        }
+    }
+
+    // 4. 写回 sidecar 索引记录：只保留当前文件对应的 blob，已删除文件的 blob 随之移除
+    let mut all_blobs: Vec<String> = file_blobs.values().flatten().cloned().collect();
+    all_blobs.sort();
+    all_blobs.dedup();
+    projects.insert(project_key, json!(all_blobs));
+    save_projects_file(&projects)?;
+
+    // 5. 索引成功后写入清单，供下次增量索引使用
+    let manifest = PreindexManifest {
+        project_path: project_path.to_string(),
+        indexed_at: chrono::Utc::now().to_rfc3339(),
+        files: files
+            .into_iter()
+            .filter_map(|(path, mut entry)| {
+                entry.blobs = file_blobs.remove(&path)?;
+                Some((path, entry))
+            })
+            .collect(),
+    };
+    if let Err(e) = manifest.save() {
+        warn!("Failed to save pre-index manifest: {}", e);
+    }
+
+    Ok(true)
+}
+
+/// 取消预索引任务；返回 false 表示任务已结束
+#[tauri::command]
+pub async fn cancel_preindex(job_id: String) -> Result<bool, String> {
+    use std::sync::atomic::Ordering;
+
+    let jobs = PREINDEX_JOBS.lock().unwrap();
+    let job = jobs
+        .get(&job_id)
+        .ok_or_else(|| format!("Pre-index job not found: {}", job_id))?;
+
+    if job.progress.phase.is_finished() {
+        return Ok(false);
+    }
+
+    info!("Cancelling pre-index job {}", job_id);
+    job.cancel_flag.store(true, Ordering::SeqCst);
+    job.cancel_notify.notify_one();
+    Ok(true)
+}
+
+/// 查询预索引任务状态
+#[tauri::command]
+pub async fn get_preindex_status(job_id: String) -> Result<PreindexProgress, String> {
+    PREINDEX_JOBS
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .map(|job| job.progress.clone())
+        .ok_or_else(|| format!("Pre-index job not found: {}", job_id))
 }
 
 // ============================================================================
@@ -1741,3 +2717,116 @@ pub async fn get_extracted_sidecar_path() -> Result<Option<String>, String> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_without_max_context_length_keeps_the_configured_value() {
+        let existing = "BASE_URL = \"https://old\"\nTOKEN = \"t\"\nMAX_CONTEXT_LENGTH = 5000\nEXCLUDE_PATTERNS = [\n  \"node_modules\",\n]\n";
+
+        let saved = render_acemcp_config(existing, "https://new", "t2", Some(10), None, None);
+        let config = parse_acemcp_config(&saved);
+        assert_eq!(config.base_url, "https://new");
+        assert_eq!(config.token, "t2");
+        assert_eq!(config.max_context_length, Some(5000));
+        assert!(saved.contains("\"node_modules\""));
+        assert_eq!(saved.matches("MAX_CONTEXT_LENGTH").count(), 1);
+
+        let saved = render_acemcp_config(&saved, "https://new", "t2", Some(10), None, Some(8000));
+        assert_eq!(parse_acemcp_config(&saved).max_context_length, Some(8000));
+        assert_eq!(saved.matches("MAX_CONTEXT_LENGTH").count(), 1);
+    }
+
+    #[test]
+    fn splits_lines_like_sidecar() {
+        assert_eq!(
+            split_lines_keep_ends("a\nb\r\nc\rd"),
+            vec!["a\n", "b\r\n", "c\r", "d"]
+        );
+        assert!(split_lines_keep_ends("").is_empty());
+    }
+
+    #[test]
+    fn chunks_long_files_into_named_blobs() {
+        let blobs = split_into_blobs("src/lib.rs", "1\n2\n3\n4\n5\n", 2);
+        let paths: Vec<&str> = blobs.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "src/lib.rs#chunk1of3",
+                "src/lib.rs#chunk2of3",
+                "src/lib.rs#chunk3of3"
+            ]
+        );
+        assert_eq!(blobs[2].content, "5\n");
+
+        let single = split_into_blobs("a.md", "x\n", 800);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].path, "a.md");
+    }
+
+    #[test]
+    fn blob_name_hashes_path_and_content() {
+        let blob = IndexBlob {
+            path: "a.txt".to_string(),
+            content: "hello".to_string(),
+        };
+        // sha256("a.txthello")
+        assert_eq!(
+            blob.name(),
+            "59cf4ed07faff74096d2be40c3acbdea62c357484b265c8a6d922e2b0ca48602"
+        );
+    }
+
+    #[test]
+    fn normalizes_project_keys() {
+        assert_eq!(sidecar_project_key("/home/u/proj/"), "/home/u/proj");
+        assert_eq!(sidecar_project_key("C:\\work\\proj\\"), "C:/work/proj");
+        assert_eq!(sidecar_project_key("C:\\"), "C:/");
+        assert_eq!(
+            sidecar_project_key("\\\\wsl$\\Ubuntu\\home\\u\\proj"),
+            "/home/u/proj"
+        );
+    }
+
+    #[test]
+    fn diff_ignores_recorded_blobs() {
+        let entry = |mtime, blobs: &[&str]| ManifestEntry {
+            mtime,
+            size: 1,
+            blobs: blobs.iter().map(|b| b.to_string()).collect(),
+        };
+        let manifest = PreindexManifest {
+            project_path: "/p".to_string(),
+            indexed_at: String::new(),
+            files: [
+                ("same".to_string(), entry(1, &["b1"])),
+                ("changed".to_string(), entry(1, &["b2"])),
+                ("gone".to_string(), entry(1, &["b3"])),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let current = [
+            ("same".to_string(), entry(1, &[])),
+            ("changed".to_string(), entry(2, &[])),
+            ("new".to_string(), entry(1, &[])),
+        ]
+        .into_iter()
+        .collect();
+
+        let changes = manifest.diff(&current);
+        assert_eq!(
+            (
+                changes.added,
+                changes.updated,
+                changes.removed,
+                changes.unchanged
+            ),
+            (1, 1, 1, 1)
+        );
+        assert!(manifest.has_blobs());
+    }
+}
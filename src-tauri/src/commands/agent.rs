@@ -0,0 +1,543 @@
+//! 统一的 CLI 执行入口
+//!
+//! Claude / Codex / Gemini 的执行、续接、恢复、取消命令参数几乎相同；`execute_agent`
+//! 按 `tool` 分发到各自的运行器，原有的按工具划分的命令保持不变。
+//! 事件名统一为 `{tool}-{kind}`，带会话时为 `{tool}-{kind}:{session_id}`。
+//! 运行器同时发送带 `tool` 字段的 `agent-*` 事件，窗口只需订阅一路再按工具过滤。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::process::Command;
+
+use crate::commands::claude::{
+    build_claude_dry_run_command, cancel_claude_execution, continue_claude_code,
+    execute_claude_code, resume_claude_code, LaunchCommand,
+};
+use crate::commands::codex::session::{build_codex_command, resolve_codex_model};
+use crate::commands::codex::{
+    cancel_codex, execute_codex, resume_codex, resume_last_codex, CodexExecutionMode,
+    CodexExecutionOptions,
+};
+use crate::commands::gemini::session::{apply_slash_command_flag, build_gemini_command};
+use crate::commands::gemini::types::GeminiExecutionOptions;
+use crate::commands::gemini::{cancel_gemini, execute_gemini, resume_last_gemini};
+use crate::error::{AppError, AppResult};
+
+/// 支持的 CLI 工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AgentTool {
+    Claude,
+    Codex,
+    Gemini,
+}
+
+impl AgentTool {
+    pub(crate) fn parse(tool: &str) -> AppResult<Self> {
+        match tool.trim().to_ascii_lowercase().as_str() {
+            "claude" => Ok(Self::Claude),
+            "codex" => Ok(Self::Codex),
+            "gemini" => Ok(Self::Gemini),
+            other => Err(AppError::invalid_config(format!("Unknown tool: {}", other))),
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Claude => "claude",
+            Self::Codex => "codex",
+            Self::Gemini => "gemini",
+        }
+    }
+}
+
+/// 三个工具共用的执行参数
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteParams {
+    pub project_path: String,
+    pub prompt: String,
+    /// 为空时使用各工具的默认模型
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 要恢复的会话 ID
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 续接项目最近一次会话
+    #[serde(default)]
+    pub continue_session: bool,
+    /// 实际工作目录（仅 Claude；其余工具在 project_path 下运行）
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub tab_id: Option<String>,
+    /// Claude 计划模式
+    #[serde(default)]
+    pub plan_mode: Option<bool>,
+    /// Codex 执行模式（read-only / full-auto / danger-full-access）或 Gemini 审批模式
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
+/// 某个工具（及会话）对应的事件名，前端据此统一订阅
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentEventNames {
+    pub output: String,
+    pub complete: String,
+    pub error: String,
+    pub cancelled: String,
+    pub session_init: String,
+}
+
+/// 生成事件名：`{tool}-{kind}`，指定会话时为 `{tool}-{kind}:{session_id}`
+pub(crate) fn agent_event_name(tool: AgentTool, kind: &str, session_id: Option<&str>) -> String {
+    match session_id {
+        Some(sid) => format!("{}-{}:{}", tool.as_str(), kind, sid),
+        None => format!("{}-{}", tool.as_str(), kind),
+    }
+}
+
+fn agent_event_names(tool: AgentTool, session_id: Option<&str>) -> AgentEventNames {
+    AgentEventNames {
+        output: agent_event_name(tool, "output", session_id),
+        complete: agent_event_name(tool, "complete", session_id),
+        error: agent_event_name(tool, "error", session_id),
+        cancelled: agent_event_name(tool, "cancelled", session_id),
+        // 会话初始化事件只有全局版本
+        session_init: agent_event_name(tool, "session-init", None),
+    }
+}
+
+/// 某个工具将要执行的完整命令（不启动进程），用于排查启动问题
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchPlan {
+    pub tool: String,
+    pub binary: String,
+    pub args: Vec<String>,
+    /// 设置在命令上的环境变量，密钥已打码
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+    /// 经 stdin 传入的 prompt（按参数传递时为 None）
+    pub stdin: Option<String>,
+}
+
+impl LaunchPlan {
+    fn from_command(tool: AgentTool, cmd: &Command, stdin: Option<String>) -> Self {
+        let launch = LaunchCommand::from_command(cmd);
+        Self {
+            tool: tool.as_str().to_string(),
+            binary: launch.binary,
+            args: launch.args,
+            env: launch.env,
+            cwd: cmd
+                .as_std()
+                .get_current_dir()
+                .map(|dir| dir.to_string_lossy().to_string()),
+            stdin,
+        }
+    }
+}
+
+/// `agent-output` / `agent-error` 的负载
+#[derive(Debug, Serialize)]
+struct AgentLineEvent<'a> {
+    tool: &'static str,
+    session_id: Option<&'a str>,
+    /// 仅 Claude：新建会话拿到 session_id 之前，前端靠 tab_id 过滤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tab_id: Option<&'a str>,
+    /// 仅 Claude 的输出行：`json` / `text`，前端据此决定按 JSON 解析还是按纯文本展示
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<&'a str>,
+    line: &'a str,
+}
+
+/// `agent-complete` 的负载
+#[derive(Debug, Serialize)]
+struct AgentCompleteEvent<'a> {
+    tool: &'static str,
+    session_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tab_id: Option<&'a str>,
+    success: bool,
+}
+
+/// `agent-session-state` 的负载，`state` 为各工具原有的会话状态负载
+#[derive(Debug, Serialize)]
+struct AgentSessionStateEvent<'a> {
+    tool: &'static str,
+    session_id: Option<&'a str>,
+    state: &'a Value,
+}
+
+/// 发送统一的 `agent-output` 事件（与 `{tool}-output` 并行发送）
+pub(crate) fn emit_agent_output(
+    app: &AppHandle,
+    tool: AgentTool,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    kind: Option<&str>,
+    line: &str,
+) {
+    let event = AgentLineEvent {
+        tool: tool.as_str(),
+        session_id,
+        tab_id,
+        kind,
+        line,
+    };
+    if let Err(e) = app.emit("agent-output", &event) {
+        log::warn!("Failed to emit agent-output: {}", e);
+    }
+}
+
+/// 发送统一的 `agent-error` 事件（与 `{tool}-error` 并行发送）
+pub(crate) fn emit_agent_error(
+    app: &AppHandle,
+    tool: AgentTool,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    line: &str,
+) {
+    let event = AgentLineEvent {
+        tool: tool.as_str(),
+        session_id,
+        tab_id,
+        kind: None,
+        line,
+    };
+    if let Err(e) = app.emit("agent-error", &event) {
+        log::warn!("Failed to emit agent-error: {}", e);
+    }
+}
+
+/// 发送统一的 `agent-complete` 事件（与 `{tool}-complete` 并行发送）
+pub(crate) fn emit_agent_complete(
+    app: &AppHandle,
+    tool: AgentTool,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    success: bool,
+) {
+    let event = AgentCompleteEvent {
+        tool: tool.as_str(),
+        session_id,
+        tab_id,
+        success,
+    };
+    if let Err(e) = app.emit("agent-complete", &event) {
+        log::warn!("Failed to emit agent-complete: {}", e);
+    }
+}
+
+/// 发送统一的 `agent-session-state` 事件
+pub(crate) fn emit_agent_session_state(
+    app: &AppHandle,
+    tool: AgentTool,
+    session_id: Option<&str>,
+    state: &Value,
+) {
+    let event = AgentSessionStateEvent {
+        tool: tool.as_str(),
+        session_id,
+        state,
+    };
+    if let Err(e) = app.emit("agent-session-state", &event) {
+        log::warn!("Failed to emit agent-session-state: {}", e);
+    }
+}
+
+fn codex_options(params: ExecuteParams) -> AppResult<CodexExecutionOptions> {
+    let mode = match params.mode.as_deref() {
+        Some(mode) => serde_json::from_value::<CodexExecutionMode>(serde_json::json!(mode))
+            .map_err(|_| AppError::invalid_config(format!("Unknown Codex mode: {}", mode)))?,
+        None => CodexExecutionMode::default(),
+    };
+    Ok(CodexExecutionOptions {
+        project_path: params.project_path,
+        prompt: params.prompt,
+        mode,
+        model: params.model,
+        json: true,
+        output_schema: None,
+        output_file: None,
+        skip_git_repo_check: false,
+        api_key: None,
+        session_id: params.session_id,
+        resume_last: params.continue_session,
+    })
+}
+
+async fn execute_claude(app: AppHandle, params: ExecuteParams) -> AppResult<()> {
+    let model = params.model.unwrap_or_default();
+    if let Some(session_id) = params.session_id {
+        return resume_claude_code(
+            app,
+            params.project_path,
+            session_id,
+            params.prompt,
+            model,
+            params.plan_mode,
+            None,
+            params.tab_id,
+            None,
+            params.working_dir,
+        )
+        .await;
+    }
+    if params.continue_session {
+        return continue_claude_code(
+            app,
+            params.project_path,
+            params.prompt,
+            model,
+            params.plan_mode,
+            None,
+            params.tab_id,
+            None,
+            params.working_dir,
+        )
+        .await;
+    }
+    execute_claude_code(
+        app,
+        params.project_path,
+        params.prompt,
+        model,
+        params.plan_mode,
+        None,
+        params.tab_id,
+        None,
+        params.working_dir,
+        None,
+    )
+    .await
+}
+
+async fn execute_codex_agent(app: AppHandle, params: ExecuteParams) -> AppResult<()> {
+    let options = codex_options(params)?;
+    let result = match options.session_id.clone() {
+        Some(session_id) => resume_codex(session_id, options, app).await,
+        None if options.resume_last => resume_last_codex(options, app).await,
+        None => execute_codex(options, app).await,
+    };
+    result.map_err(AppError::External)
+}
+
+fn gemini_options(params: ExecuteParams) -> GeminiExecutionOptions {
+    let defaults = GeminiExecutionOptions::default();
+    GeminiExecutionOptions {
+        project_path: params.project_path,
+        prompt: params.prompt,
+        model: params.model.or(defaults.model),
+        approval_mode: params.mode.or(defaults.approval_mode),
+        session_id: params.session_id,
+        ..defaults
+    }
+}
+
+async fn execute_gemini_agent(app: AppHandle, params: ExecuteParams) -> AppResult<()> {
+    let continue_session = params.continue_session;
+    let options = gemini_options(params);
+    let result = if continue_session && options.session_id.is_none() {
+        resume_last_gemini(options, app).await.map(|_| ())
+    } else {
+        execute_gemini(options, app).await
+    };
+    result.map_err(AppError::External)
+}
+
+/// Runs a prompt with the given tool ("claude", "codex" or "gemini"); resumes `sessionId`
+/// or continues the last session when requested
+#[tauri::command]
+pub async fn execute_agent(app: AppHandle, tool: String, params: ExecuteParams) -> AppResult<()> {
+    let tool = AgentTool::parse(&tool)?;
+    log::info!(
+        "execute_agent called: tool={}, project_path={}, resume={}, continue={}",
+        tool.as_str(),
+        params.project_path,
+        params.session_id.is_some(),
+        params.continue_session
+    );
+    match tool {
+        AgentTool::Claude => execute_claude(app, params).await,
+        AgentTool::Codex => execute_codex_agent(app, params).await,
+        AgentTool::Gemini => execute_gemini_agent(app, params).await,
+    }
+}
+
+/// Cancels a running session of the given tool (all sessions of the tool when no ID is given)
+#[tauri::command]
+pub async fn cancel_agent(
+    app: AppHandle,
+    tool: String,
+    session_id: Option<String>,
+) -> AppResult<()> {
+    match AgentTool::parse(&tool)? {
+        AgentTool::Claude => cancel_claude_execution(app, session_id).await,
+        AgentTool::Codex => cancel_codex(session_id, app)
+            .await
+            .map_err(AppError::External),
+        AgentTool::Gemini => cancel_gemini(session_id, app)
+            .await
+            .map_err(AppError::External),
+    }
+}
+
+/// Shows the binary, args, env (secrets redacted) and cwd a new session of the tool would be
+/// launched with, built by the same code as the real launch; nothing is spawned
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn dry_run_launch(
+    app: AppHandle,
+    tool: String,
+    project_path: String,
+    prompt: String,
+    model: Option<String>,
+    flags: Option<Vec<String>>,
+    plan_mode: Option<bool>,
+    max_thinking_tokens: Option<u32>,
+    working_dir: Option<String>,
+) -> AppResult<LaunchPlan> {
+    let tool = AgentTool::parse(&tool)?;
+    let flags = flags.filter(|flags| !flags.is_empty());
+    if tool != AgentTool::Claude {
+        let claude_only = [
+            ("Extra flags are", flags.is_some()),
+            ("Plan mode is", plan_mode.is_some()),
+            ("Max thinking tokens are", max_thinking_tokens.is_some()),
+            ("A working directory is", working_dir.is_some()),
+        ];
+        if let Some((option, _)) = claude_only.iter().find(|(_, set)| *set) {
+            return Err(AppError::invalid_config(format!(
+                "{} not supported for {}",
+                option,
+                tool.as_str()
+            )));
+        }
+    }
+    let params = ExecuteParams {
+        project_path,
+        prompt,
+        model: model.filter(|m| !m.trim().is_empty()),
+        plan_mode,
+        working_dir,
+        ..Default::default()
+    };
+
+    match tool {
+        AgentTool::Claude => {
+            let (cmd, stdin) = build_claude_dry_run_command(
+                &app,
+                &params.project_path,
+                params.prompt,
+                params.model,
+                params.plan_mode.unwrap_or(false),
+                max_thinking_tokens,
+                params.working_dir,
+                flags,
+            )
+            .await?;
+            Ok(LaunchPlan::from_command(tool, &cmd, stdin))
+        }
+        AgentTool::Codex => {
+            let mut options = codex_options(params)?;
+            resolve_codex_model(&mut options);
+            let (cmd, stdin) = build_codex_command(&options, false, None)?;
+            Ok(LaunchPlan::from_command(tool, &cmd, stdin))
+        }
+        AgentTool::Gemini => {
+            let options = gemini_options(params);
+            let (mut cmd, _model) = build_gemini_command(&options)?;
+            let stdin =
+                (!apply_slash_command_flag(&mut cmd, &options.prompt)).then_some(options.prompt);
+            Ok(LaunchPlan::from_command(tool, &cmd, stdin))
+        }
+    }
+}
+
+/// Returns the event names emitted for a tool, scoped to a session when one is given
+#[tauri::command]
+pub async fn get_agent_event_names(
+    tool: String,
+    session_id: Option<String>,
+) -> AppResult<AgentEventNames> {
+    Ok(agent_event_names(
+        AgentTool::parse(&tool)?,
+        session_id.as_deref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names_follow_the_runner_scheme() {
+        let names = agent_event_names(AgentTool::parse("Codex").unwrap(), Some("s1"));
+        assert_eq!(names.output, "codex-output:s1");
+        assert_eq!(names.complete, "codex-complete:s1");
+        assert_eq!(names.session_init, "codex-session-init");
+        assert_eq!(
+            agent_event_name(AgentTool::Claude, "output", None),
+            "claude-output"
+        );
+        assert!(AgentTool::parse("cursor").is_err());
+    }
+
+    #[test]
+    fn unified_events_carry_the_tool() {
+        let event = AgentLineEvent {
+            tool: AgentTool::Gemini.as_str(),
+            session_id: Some("s1"),
+            tab_id: None,
+            kind: None,
+            line: "{}",
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "tool": "gemini", "session_id": "s1", "line": "{}" })
+        );
+
+        let claude_line = AgentLineEvent {
+            tool: AgentTool::Claude.as_str(),
+            session_id: None,
+            tab_id: Some("tab-1"),
+            kind: Some("text"),
+            line: "Update available",
+        };
+        assert_eq!(
+            serde_json::to_value(&claude_line).unwrap(),
+            serde_json::json!({
+                "tool": "claude",
+                "session_id": null,
+                "tab_id": "tab-1",
+                "kind": "text",
+                "line": "Update available"
+            })
+        );
+    }
+
+    #[test]
+    fn codex_options_map_shared_params() {
+        let options = codex_options(ExecuteParams {
+            project_path: "/p".to_string(),
+            prompt: "hi".to_string(),
+            continue_session: true,
+            mode: Some("full-auto".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(options.resume_last);
+        assert!(matches!(options.mode, CodexExecutionMode::FullAuto));
+        assert!(codex_options(ExecuteParams {
+            mode: Some("bogus".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
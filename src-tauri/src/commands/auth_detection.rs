@@ -0,0 +1,260 @@
+//! CLI 登录失效检测
+//!
+//! Claude / Codex / Gemini 需要重新登录时会打印登录提示并等待交互输入，而运行器的 stdin 是管道，
+//! 进程会一直挂起；stream-json 模式下 API Key 失效则以 `is_error` 的 result 事件报告。运行器把 stdout
+//! 和 stderr 交给 `AuthRequiredDetector`（JSON 行只检查错误事件的文本），匹配到登录提示后
+//! 发送 `{tool}-auth-required` 事件并结束进程，提示用户在终端里完成登录。
+//! 检测规则保存在 ~/.anycode/auth_detection.json，可按工具配置。
+
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::agent::{agent_event_name, AgentTool};
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// 各工具的登录提示正则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthDetectionConfig {
+    pub claude: Vec<String>,
+    pub codex: Vec<String>,
+    pub gemini: Vec<String>,
+}
+
+impl Default for AuthDetectionConfig {
+    fn default() -> Self {
+        let patterns =
+            |list: &[&str]| -> Vec<String> { list.iter().map(|p| p.to_string()).collect() };
+        Self {
+            claude: patterns(&[
+                r"(?i)please run /login",
+                r"(?i)\bnot logged in\b",
+                r"(?i)oauth token (?:has )?expired",
+                r"(?i)invalid api key",
+                r"(?i)paste code here if prompted",
+            ]),
+            codex: patterns(&[
+                r"(?i)\bnot logged in\b",
+                r"(?i)please (?:run )?`?codex login`?",
+                r"(?i)sign in with chatgpt",
+            ]),
+            gemini: patterns(&[
+                r"(?i)waiting for auth",
+                r"(?i)login with google",
+                r"(?i)please (?:set an auth method|authenticate)",
+                r"(?i)code authorization url",
+            ]),
+        }
+    }
+}
+
+impl AuthDetectionConfig {
+    fn patterns(&self, tool: AgentTool) -> &[String] {
+        match tool {
+            AgentTool::Claude => &self.claude,
+            AgentTool::Codex => &self.codex,
+            AgentTool::Gemini => &self.gemini,
+        }
+    }
+}
+
+/// `{tool}-auth-required` 事件的负载
+#[derive(Debug, Clone, Serialize)]
+struct AuthRequiredEvent<'a> {
+    tool: &'static str,
+    session_id: Option<&'a str>,
+    tab_id: Option<&'a str>,
+    /// 触发检测的输出行
+    line: &'a str,
+    login_command: &'static str,
+    message: String,
+}
+
+fn get_auth_detection_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("auth_detection.json"))
+}
+
+fn load_auth_detection_config() -> AuthDetectionConfig {
+    get_auth_detection_config_path()
+        .and_then(load_json_config)
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to load auth detection config: {}", e);
+            AuthDetectionConfig::default()
+        })
+}
+
+/// 各工具在终端中完成登录的命令
+pub(crate) fn login_command(tool: AgentTool) -> &'static str {
+    match tool {
+        AgentTool::Claude => "claude login",
+        AgentTool::Codex => "codex login",
+        // Gemini CLI 没有单独的 login 子命令，交互启动时会引导登录
+        AgentTool::Gemini => "gemini",
+    }
+}
+
+/// 单个进程的登录提示检测器；每个进程只触发一次
+pub(crate) struct AuthRequiredDetector {
+    patterns: Vec<Regex>,
+    triggered: bool,
+}
+
+impl AuthRequiredDetector {
+    pub(crate) fn new(tool: AgentTool) -> Self {
+        Self::with_patterns(load_auth_detection_config().patterns(tool))
+    }
+
+    fn with_patterns(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!(
+                        "Ignoring invalid auth detection pattern '{}': {}",
+                        pattern,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        Self {
+            patterns,
+            triggered: false,
+        }
+    }
+
+    /// 检查一行输出；JSON 事件行只检查错误事件的文本（助手消息里引用的登录提示不算）
+    pub(crate) fn check(&mut self, line: &str) -> bool {
+        if self.triggered || line.trim().is_empty() {
+            return false;
+        }
+        let trimmed = line.trim_start();
+        let text = if trimmed.starts_with('{') {
+            match serde_json::from_str::<serde_json::Value>(trimmed) {
+                Ok(event) => match json_error_text(&event) {
+                    Some(text) => text.to_string(),
+                    None => return false,
+                },
+                Err(_) => line.to_string(),
+            }
+        } else {
+            line.to_string()
+        };
+        self.triggered = self.patterns.iter().any(|regex| regex.is_match(&text));
+        self.triggered
+    }
+}
+
+/// stream-json 错误事件中的错误文本：`is_error` 的 result 事件（如 Claude 的
+/// "Invalid API key · Please run /login"）以及 `type: "error"` 事件
+fn json_error_text(event: &serde_json::Value) -> Option<&str> {
+    match event["type"].as_str()? {
+        "result" if event["is_error"].as_bool() == Some(true) => event["result"].as_str(),
+        "error" => event["message"]
+            .as_str()
+            .or_else(|| event["error"]["message"].as_str())
+            .or_else(|| event["error"].as_str()),
+        _ => None,
+    }
+}
+
+/// 发送 `{tool}-auth-required` 事件并结束等待登录的进程（管道 stdin 无法完成交互登录）
+pub(crate) fn handle_auth_required(
+    app: &AppHandle,
+    tool: AgentTool,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    pid: u32,
+    line: &str,
+) {
+    let login_command = login_command(tool);
+    log::warn!(
+        "{} requires authentication (session {:?}): {}",
+        tool.as_str(),
+        session_id,
+        line
+    );
+    let event = AuthRequiredEvent {
+        tool: tool.as_str(),
+        session_id,
+        tab_id,
+        line,
+        login_command,
+        message: format!(
+            "{} needs you to log in. Run `{}` in a terminal, then retry.",
+            tool.as_str(),
+            login_command
+        ),
+    };
+    if let Some(session_id) = session_id {
+        let _ = app.emit(
+            &agent_event_name(tool, "auth-required", Some(session_id)),
+            &event,
+        );
+    }
+    let _ = app.emit(&agent_event_name(tool, "auth-required", None), &event);
+
+    if pid != 0 {
+        if let Err(e) = crate::commands::claude::kill_process_tree(pid) {
+            log::warn!("Failed to stop process {} waiting for login: {}", pid, e);
+        }
+    }
+}
+
+/// Get the login-prompt patterns used to detect that a CLI needs re-authentication
+#[tauri::command]
+pub async fn get_auth_detection_config() -> Result<AuthDetectionConfig, String> {
+    Ok(load_auth_detection_config())
+}
+
+/// Save the login-prompt patterns; every pattern must be a valid regex
+#[tauri::command]
+pub async fn set_auth_detection_config(config: AuthDetectionConfig) -> Result<(), String> {
+    let invalid: Vec<String> = [&config.claude, &config.codex, &config.gemini]
+        .into_iter()
+        .flatten()
+        .filter_map(|pattern| {
+            Regex::new(pattern)
+                .err()
+                .map(|e| format!("{}: {}", pattern, e))
+        })
+        .collect();
+    if !invalid.is_empty() {
+        return Err(format!("Invalid patterns: {}", invalid.join("; ")));
+    }
+    save_json_config(&config, get_auth_detection_config_path()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_login_prompts_once() {
+        let config = AuthDetectionConfig::default();
+        let mut detector = AuthRequiredDetector::with_patterns(config.patterns(AgentTool::Claude));
+        assert!(!detector.check("Compiling..."));
+        // stream-json 事件里出现的文字不算登录提示
+        assert!(!detector.check(r#"{"type":"assistant","text":"Please run /login"}"#));
+        assert!(detector.check("Invalid API key · Please run /login"));
+        assert!(!detector.check("Invalid API key · Please run /login"));
+
+        // 登录失效时 `claude -p --output-format stream-json` 输出的 result 事件
+        let mut detector = AuthRequiredDetector::with_patterns(config.patterns(AgentTool::Claude));
+        assert!(!detector.check(
+            r#"{"type":"result","subtype":"success","is_error":false,"result":"Invalid API key · Please run /login","session_id":"abc"}"#
+        ));
+        assert!(detector.check(
+            r#"{"type":"result","subtype":"success","is_error":true,"duration_ms":312,"duration_api_ms":0,"num_turns":1,"result":"Invalid API key · Please run /login","session_id":"6f0c2e9a-1d2b-4c8e-9a51-3f4e5d6c7b8a","total_cost_usd":0,"usage":{"input_tokens":0,"output_tokens":0}}"#
+        ));
+
+        let mut gemini = AuthRequiredDetector::with_patterns(config.patterns(AgentTool::Gemini));
+        assert!(gemini.check("Waiting for auth... (Press ESC to cancel)"));
+    }
+}
@@ -0,0 +1,346 @@
+//! 应用数据备份与恢复
+//!
+//! 备份目录结构：
+//! ```text
+//! anycode-backup-20250101-120000/
+//!   manifest.json        # 版本、schema 版本以及每个文件的 sha256
+//!   agents.db            # 通过 SQLite backup API 导出，数据库使用中也能得到一致的快照
+//!   claude/settings.json
+//!   codex/config.toml
+//!   ...
+//! ```
+//!
+//! 恢复前会先把当前数据备份到应用数据目录下的 `pre-restore-backups/`，恢复出错或恢复了错误的备份时
+//! 可以用该快照再恢复回来。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::storage::{latest_schema_version, run_migrations, schema_version, AgentDb};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const DATABASE_FILE: &str = "agents.db";
+/// 恢复前快照所在的目录（位于应用数据目录下）
+const PRE_RESTORE_DIR: &str = "pre-restore-backups";
+
+/// 备份清单
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    created_at: String,
+    app_version: String,
+    schema_version: i64,
+    files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    /// 备份目录内的相对路径（使用 `/` 分隔）
+    path: String,
+    sha256: String,
+    size: u64,
+}
+
+/// 参与备份的配置文件：备份内相对路径 → 本机路径
+fn config_file_targets() -> Vec<(String, PathBuf)> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let mut targets = Vec::new();
+
+    let claude_dir = home.join(".claude");
+    for name in [
+        "settings.json",
+        "execution_config.json",
+        "binaries.json",
+        "hidden_projects.json",
+        "project_models.json",
+        "imported_projects.json",
+        "pinned_items.json",
+        "session_working_dirs.json",
+    ] {
+        targets.push((format!("claude/{}", name), claude_dir.join(name)));
+    }
+
+    let codex_dir = crate::commands::codex::config::get_codex_config_dir()
+        .unwrap_or_else(|_| home.join(".codex"));
+    for name in ["config.toml", "auth.json"] {
+        targets.push((format!("codex/{}", name), codex_dir.join(name)));
+    }
+    // providers.json 始终保存在本机 ~/.codex 中
+    targets.push((
+        "codex/providers.json".to_string(),
+        home.join(".codex").join("providers.json"),
+    ));
+
+    targets.push((
+        "anycode/gemini.json".to_string(),
+        home.join(".anycode").join("gemini.json"),
+    ));
+    targets
+}
+
+/// `restore_app_data` 的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    /// 已恢复的文件（备份内的相对路径）
+    pub restored: Vec<String>,
+    /// 恢复前自动创建的当前数据快照
+    pub snapshot_dir: String,
+}
+
+/// Backs up agents.db and the CLI config files into a timestamped folder; returns the included files
+#[tauri::command]
+pub async fn backup_app_data(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    dest_dir: String,
+) -> Result<Vec<String>, String> {
+    let (backup_dir, files) = create_backup(&app, &db, Path::new(&dest_dir), "anycode-backup")?;
+    log::info!(
+        "Backed up {} files to {}",
+        files.len(),
+        backup_dir.display()
+    );
+    Ok(files
+        .into_iter()
+        .map(|f| backup_dir.join(f).to_string_lossy().to_string())
+        .collect())
+}
+
+/// 在 parent 下创建 `<prefix>-<时间戳>` 备份目录；失败时删除不完整的目录
+fn create_backup(
+    app: &AppHandle,
+    db: &AgentDb,
+    parent: &Path,
+    prefix: &str,
+) -> Result<(PathBuf, Vec<String>), String> {
+    let backup_dir = parent.join(format!(
+        "{}-{}",
+        prefix,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    if backup_dir.exists() {
+        return Err(format!(
+            "Backup directory already exists: {}",
+            backup_dir.display()
+        ));
+    }
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let result = write_backup(app, db, &backup_dir);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&backup_dir);
+    }
+    Ok((backup_dir, result?))
+}
+
+fn write_backup(app: &AppHandle, db: &AgentDb, backup_dir: &Path) -> Result<Vec<String>, String> {
+    let db_version = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.backup(DatabaseName::Main, backup_dir.join(DATABASE_FILE), None)
+            .map_err(|e| format!("Failed to back up database: {}", e))?;
+        schema_version(&conn).map_err(|e| format!("Failed to read schema version: {}", e))?
+    };
+
+    let mut files = vec![DATABASE_FILE.to_string()];
+    for (relative, source) in config_file_targets() {
+        if !source.is_file() {
+            continue;
+        }
+        let target = backup_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(&source, &target)
+            .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+        files.push(relative);
+    }
+
+    let manifest = BackupManifest {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: app.package_info().version.to_string(),
+        schema_version: db_version,
+        files: files
+            .iter()
+            .map(|relative| describe_file(backup_dir, relative))
+            .collect::<Result<_, _>>()?,
+    };
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(backup_dir.join(MANIFEST_FILE), content)
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    Ok(files)
+}
+
+/// Restores a backup created by `backup_app_data` after validating it. The current data is
+/// snapshotted first so the restore can be undone; returns the restored files and the snapshot path
+#[tauri::command]
+pub async fn restore_app_data(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    src: String,
+) -> Result<RestoreResult, String> {
+    let backup_dir = PathBuf::from(&src);
+    let manifest = validate_backup(&backup_dir)?;
+
+    let snapshot_parent = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(PRE_RESTORE_DIR);
+    let (snapshot_dir, _) = create_backup(&app, &db, &snapshot_parent, "pre-restore")
+        .map_err(|e| format!("Failed to snapshot current data before restoring: {}", e))?;
+    log::info!(
+        "Saved current data to {} before restoring {}",
+        snapshot_dir.display(),
+        src
+    );
+
+    // 数据库通过 backup API 恢复到正在使用的连接，其他连接随后看到的是完整的新数据
+    if manifest.files.iter().any(|f| f.path == DATABASE_FILE) {
+        let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.restore(
+            DatabaseName::Main,
+            backup_dir.join(DATABASE_FILE),
+            None::<fn(rusqlite::backup::Progress)>,
+        )
+        .map_err(|e| format!("Failed to restore database: {}", e))?;
+        // 旧版本的备份需要补齐之后新增的迁移
+        run_migrations(&conn).map_err(|e| format!("Failed to migrate restored database: {}", e))?;
+    }
+
+    let targets = config_file_targets();
+    let mut restored = Vec::new();
+    for file in &manifest.files {
+        if file.path == DATABASE_FILE {
+            restored.push(file.path.clone());
+            continue;
+        }
+        let Some((_, target)) = targets.iter().find(|(relative, _)| *relative == file.path) else {
+            log::warn!("Skipping unknown file in backup: {}", file.path);
+            continue;
+        };
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(backup_dir.join(&file.path), target)
+            .map_err(|e| format!("Failed to restore {}: {}", file.path, e))?;
+        restored.push(file.path.clone());
+    }
+
+    log::info!("Restored {} files from {}", restored.len(), src);
+    Ok(RestoreResult {
+        restored,
+        snapshot_dir: snapshot_dir.to_string_lossy().to_string(),
+    })
+}
+
+/// 校验清单中每个文件的大小与 sha256、数据库完整性，以及 schema 版本不高于当前应用
+fn validate_backup(backup_dir: &Path) -> Result<BackupManifest, String> {
+    let content = fs::read_to_string(backup_dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Not a valid backup (missing {}): {}", MANIFEST_FILE, e))?;
+    let manifest: BackupManifest =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid backup manifest: {}", e))?;
+
+    for file in &manifest.files {
+        if file.path.contains("..") || Path::new(&file.path).is_absolute() {
+            return Err(format!("Invalid path in backup manifest: {}", file.path));
+        }
+        let actual = describe_file(backup_dir, &file.path)?;
+        if actual.size != file.size || actual.sha256 != file.sha256 {
+            return Err(format!("Backup file is corrupted: {}", file.path));
+        }
+    }
+
+    let latest = latest_schema_version();
+    if manifest.schema_version > latest {
+        return Err(format!(
+            "Backup schema version {} is newer than this app supports ({}); update the app first",
+            manifest.schema_version, latest
+        ));
+    }
+
+    if manifest.files.iter().any(|f| f.path == DATABASE_FILE) {
+        let conn = Connection::open_with_flags(
+            backup_dir.join(DATABASE_FILE),
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )
+        .map_err(|e| format!("Failed to open backup database: {}", e))?;
+        let integrity: String = conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to check backup database: {}", e))?;
+        if integrity != "ok" {
+            return Err(format!("Backup database is corrupted: {}", integrity));
+        }
+        let db_version = schema_version(&conn).unwrap_or(0);
+        if db_version > latest {
+            return Err(format!(
+                "Backup schema version {} is newer than this app supports ({}); update the app first",
+                db_version, latest
+            ));
+        }
+    }
+
+    Ok(manifest)
+}
+
+fn describe_file(base: &Path, relative: &str) -> Result<BackupFile, String> {
+    let content =
+        fs::read(base.join(relative)).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+    Ok(BackupFile {
+        path: relative.to_string(),
+        sha256: format!("{:x}", Sha256::digest(&content)),
+        size: content.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_manifest(dir: &Path, schema_version: i64, files: Vec<BackupFile>) {
+        let manifest = BackupManifest {
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            app_version: "0.0.0".to_string(),
+            schema_version,
+            files,
+        };
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            serde_json::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn validation_rejects_corrupted_or_newer_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("claude")).unwrap();
+        fs::write(dir.path().join("claude/settings.json"), "{}").unwrap();
+        let file = describe_file(dir.path(), "claude/settings.json").unwrap();
+
+        write_manifest(dir.path(), latest_schema_version(), vec![file]);
+        assert!(validate_backup(dir.path()).is_ok());
+
+        // Tampered file
+        fs::write(dir.path().join("claude/settings.json"), "{\"env\":{}}").unwrap();
+        let err = validate_backup(dir.path()).unwrap_err();
+        assert!(err.contains("corrupted"), "{}", err);
+
+        // Schema newer than the running app
+        let file = describe_file(dir.path(), "claude/settings.json").unwrap();
+        write_manifest(dir.path(), latest_schema_version() + 1, vec![file]);
+        let err = validate_backup(dir.path()).unwrap_err();
+        assert!(err.contains("newer"), "{}", err);
+    }
+}
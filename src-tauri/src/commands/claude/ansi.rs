@@ -0,0 +1,157 @@
+//! 终端输出中的 ANSI 控制序列处理
+//!
+//! CLI 输出彩色文本、进度条时会夹带 ANSI 转义序列，原样进入会话记录会显示成乱码（pty 模式下尤其常见）。
+//! 执行配置开启 `strip_ansi` 时，运行器在分类、转发每行输出之前通过 `clean_line` 去掉颜色、光标移动等
+//! 序列；回车和清行按终端语义处理，只保留该行最终显示的内容。JSON 行不含原始 ESC 字符，不受影响。
+
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// 读完 CSI 序列（ESC [ 参数... 结束字节 0x40-0x7E），返回参数与结束字节
+fn read_csi(chars: &mut Peekable<Chars<'_>>) -> (String, Option<char>) {
+    let mut params = String::new();
+    for c in chars.by_ref() {
+        if ('\u{40}'..='\u{7e}').contains(&c) {
+            return (params, Some(c));
+        }
+        params.push(c);
+    }
+    (params, None)
+}
+
+/// 跳过 OSC 序列（ESC ] ... 以 BEL 或 ESC \ 结束），例如终端标题、超链接
+fn skip_osc(chars: &mut Peekable<Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{7}' => return,
+            '\u{1b}' if chars.peek() == Some(&'\\') => {
+                chars.next();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 去掉一行输出中的 ANSI 序列和控制字符，保留制表符
+///
+/// 回车（`\r`）后的新内容覆盖此前的文本，`ESC [1K` / `ESC [2K` 清空当前行，
+/// 因此进度条这类反复重绘的行只保留最后一次的内容。
+pub(super) fn strip_ansi(line: &str) -> Cow<'_, str> {
+    if !line.chars().any(|c| c.is_control() && c != '\t') {
+        return Cow::Borrowed(line);
+    }
+
+    let mut cleaned = String::with_capacity(line.len());
+    // 回车之后再出现可见字符时才覆盖，行尾的回车不清空内容
+    let mut carriage_return = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                Some('[') => {
+                    let (params, final_byte) = read_csi(&mut chars);
+                    if final_byte == Some('K') && matches!(params.as_str(), "1" | "2") {
+                        cleaned.clear();
+                        carriage_return = false;
+                    }
+                }
+                Some(']') => skip_osc(&mut chars),
+                // 字符集选择（ESC ( B 等）多带一个字符
+                Some('(' | ')' | '*' | '+') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' => carriage_return = true,
+            '\u{8}' => {
+                cleaned.pop();
+            }
+            '\t' => cleaned.push(c),
+            c if c.is_control() => {}
+            c => {
+                if carriage_return {
+                    cleaned.clear();
+                    carriage_return = false;
+                }
+                cleaned.push(c);
+            }
+        }
+    }
+    Cow::Owned(cleaned)
+}
+
+/// 按 `strip_ansi` 设置处理一行输出；只包含控制序列（光标移动、清行）的行返回 None
+pub(super) fn clean_line(line: String, strip: bool) -> Option<String> {
+    if !strip {
+        return Some(line);
+    }
+    let cleaned = match strip_ansi(&line) {
+        Cow::Owned(cleaned) => Some(cleaned),
+        Cow::Borrowed(_) => None,
+    };
+    match cleaned {
+        Some(cleaned) if cleaned.is_empty() && !line.is_empty() => None,
+        Some(cleaned) => Some(cleaned),
+        None => Some(line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_colors_and_styles() {
+        assert_eq!(
+            strip_ansi("\u{1b}[1;31mError\u{1b}[0m: failed"),
+            "Error: failed"
+        );
+        assert_eq!(
+            strip_ansi("\u{1b}[38;5;208morange\u{1b}[39m \u{1b}[48;2;0;0;0mbg\u{1b}[m"),
+            "orange bg"
+        );
+    }
+
+    #[test]
+    fn strips_cursor_movement() {
+        assert_eq!(strip_ansi("\u{1b}[2A\u{1b}[10Gdone"), "done");
+        assert_eq!(strip_ansi("\u{1b}[?25lworking\u{1b}[?25h"), "working");
+        assert_eq!(strip_ansi("\u{1b}7saved\u{1b}8"), "saved");
+    }
+
+    #[test]
+    fn clear_line_and_carriage_return_keep_final_text() {
+        assert_eq!(
+            strip_ansi("Downloading 10%\r\u{1b}[2KDownloading 100%"),
+            "Downloading 100%"
+        );
+        assert_eq!(strip_ansi("spinner\u{1b}[2Kready"), "ready");
+        assert_eq!(strip_ansi("50%\r100%"), "100%");
+        assert_eq!(strip_ansi("text\u{1b}[K"), "text");
+        assert_eq!(strip_ansi("trailing\r"), "trailing");
+    }
+
+    #[test]
+    fn strips_osc_and_keeps_plain_lines() {
+        assert_eq!(
+            strip_ansi("\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{1b}\\"),
+            "link"
+        );
+        assert_eq!(strip_ansi("\u{1b}(Bplain"), "plain");
+
+        let json = r#"{"type":"assistant","text":"\u001b[31mred"}"#;
+        assert!(matches!(strip_ansi(json), Cow::Borrowed(_)));
+        assert_eq!(strip_ansi("a\tb"), "a\tb");
+    }
+
+    #[test]
+    fn clean_line_follows_setting() {
+        let colored = "\u{1b}[32mok\u{1b}[0m".to_string();
+        assert_eq!(clean_line(colored.clone(), false), Some(colored.clone()));
+        assert_eq!(clean_line(colored, true).as_deref(), Some("ok"));
+        assert_eq!(clean_line("\u{1b}[2K\u{1b}[1A".to_string(), true), None);
+        assert_eq!(clean_line(String::new(), true).as_deref(), Some(""));
+    }
+}
@@ -0,0 +1,282 @@
+//! Prompt 附件
+//!
+//! 把附加到 prompt 的文件和粘贴的图片整理成 Claude CLI 能读取的形式：文件校验后直接引用路径，
+//! base64 图片解码后写入临时目录。图片目录和项目目录之外的文件附件所在目录由运行器通过
+//! `--add-dir` 授权访问。临时目录由
+//! `StagedAttachments` 持有，会话进程退出后随之删除。
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use tempfile::TempDir;
+
+use super::Attachment;
+use crate::error::{AppError, AppResult};
+
+/// 单次 prompt 所有附件的总大小上限
+const MAX_TOTAL_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// 已整理好的附件；drop 时删除图片所在的临时目录
+#[derive(Debug, Default)]
+pub(super) struct StagedAttachments {
+    paths: Vec<PathBuf>,
+    /// 项目目录之外的文件附件所在目录（按规范化路径去重）
+    outside_dirs: Vec<PathBuf>,
+    temp_dir: Option<TempDir>,
+}
+
+impl StagedAttachments {
+    pub(super) fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// 图片所在的临时目录
+    pub(super) fn temp_dir(&self) -> Option<&Path> {
+        self.temp_dir.as_ref().map(TempDir::path)
+    }
+
+    /// 需要通过 `--add-dir` 让 CLI 可以读取的目录
+    pub(super) fn add_dirs(&self) -> Vec<&Path> {
+        self.temp_dir()
+            .into_iter()
+            .chain(self.outside_dirs.iter().map(PathBuf::as_path))
+            .collect()
+    }
+
+    /// 在 prompt 末尾列出附件路径，让 Claude 用 Read 工具读取
+    pub(super) fn append_to_prompt(&self, prompt: String) -> String {
+        if self.paths.is_empty() {
+            return prompt;
+        }
+        let list = self
+            .paths
+            .iter()
+            .map(|path| format!("- {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "{}\n\nAttached files (use the Read tool to view them):\n{}",
+            prompt.trim_end(),
+            list
+        )
+    }
+}
+
+fn image_extension(media_type: &str) -> Option<&'static str> {
+    match media_type {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        _ => None,
+    }
+}
+
+/// 解码 base64 图片，支持 `data:image/png;base64,...` 形式；返回内容和文件扩展名
+fn decode_image(data: &str, media_type: Option<&str>) -> AppResult<(Vec<u8>, &'static str)> {
+    let (header_type, encoded) = match data.strip_prefix("data:") {
+        Some(rest) => {
+            let (header, encoded) = rest
+                .split_once(',')
+                .ok_or_else(|| AppError::invalid_config("Malformed image data URL"))?;
+            (header.split(';').next().map(str::to_string), encoded)
+        }
+        None => (None, data),
+    };
+    let media_type = media_type
+        .map(str::to_string)
+        .or(header_type)
+        .unwrap_or_else(|| "image/png".to_string());
+    let extension = image_extension(&media_type).ok_or_else(|| {
+        AppError::invalid_config(format!("Unsupported image type: {}", media_type))
+    })?;
+    let encoded: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::invalid_config(format!("Invalid base64 image data: {}", e)))?;
+    Ok((bytes, extension))
+}
+
+/// 图片文件名只保留安全字符
+fn sanitize_name(name: &str) -> String {
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cleaned: String = stem
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if cleaned.is_empty() {
+        "image".to_string()
+    } else {
+        cleaned
+    }
+}
+
+fn add_to_total(total: &mut u64, bytes: u64) -> AppResult<()> {
+    *total += bytes;
+    if *total > MAX_TOTAL_ATTACHMENT_BYTES {
+        return Err(AppError::invalid_config(format!(
+            "Attachments exceed the {} MB limit",
+            MAX_TOTAL_ATTACHMENT_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(())
+}
+
+/// 校验文件附件、把图片写入临时目录，并检查总大小
+pub(super) fn stage_attachments(
+    project_path: &str,
+    attachments: &[Attachment],
+) -> AppResult<StagedAttachments> {
+    let mut staged = StagedAttachments::default();
+    let mut total_bytes = 0u64;
+    let project_root =
+        std::fs::canonicalize(project_path).unwrap_or_else(|_| PathBuf::from(project_path));
+    let mut outside_parents = std::collections::HashSet::new();
+
+    for (index, attachment) in attachments.iter().enumerate() {
+        match attachment {
+            Attachment::File { path } => {
+                let path = Path::new(path.trim());
+                let path = if path.is_absolute() {
+                    path.to_path_buf()
+                } else {
+                    Path::new(project_path).join(path)
+                };
+                let metadata = std::fs::metadata(&path).map_err(|_| {
+                    AppError::not_found(format!("Attachment not found: {}", path.display()))
+                })?;
+                if !metadata.is_file() {
+                    return Err(AppError::invalid_config(format!(
+                        "Attachment is not a file: {}",
+                        path.display()
+                    )));
+                }
+                add_to_total(&mut total_bytes, metadata.len())?;
+                // 按规范化后的路径判断，`../` 形式的相对路径也能识别为项目外
+                let resolved = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                if !resolved.starts_with(&project_root) {
+                    if let (Some(parent), Some(resolved_parent)) =
+                        (path.parent(), resolved.parent())
+                    {
+                        if outside_parents.insert(resolved_parent.to_path_buf()) {
+                            staged.outside_dirs.push(parent.to_path_buf());
+                        }
+                    }
+                }
+                staged.paths.push(path);
+            }
+            Attachment::Image {
+                data,
+                media_type,
+                name,
+            } => {
+                let (bytes, extension) = decode_image(data, media_type.as_deref())?;
+                add_to_total(&mut total_bytes, bytes.len() as u64)?;
+                if staged.temp_dir.is_none() {
+                    let dir = tempfile::Builder::new()
+                        .prefix("any-code-attachments-")
+                        .tempdir()
+                        .map_err(|e| {
+                            AppError::from_io("Failed to create attachment directory", e)
+                        })?;
+                    staged.temp_dir = Some(dir);
+                }
+                let dir = staged.temp_dir().expect("temp dir was just created");
+                let file_name = format!(
+                    "{}-{}.{}",
+                    index + 1,
+                    sanitize_name(name.as_deref().unwrap_or("image")),
+                    extension
+                );
+                let path = dir.join(file_name);
+                std::fs::write(&path, bytes)
+                    .map_err(|e| AppError::from_io("Failed to write image attachment", e))?;
+                staged.paths.push(path);
+            }
+        }
+    }
+
+    if !staged.is_empty() {
+        log::info!(
+            "Staged {} attachment(s), {} bytes total",
+            staged.paths.len(),
+            total_bytes
+        );
+    }
+    Ok(staged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_files_and_images_and_cleans_up() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("notes.md"), "hello").unwrap();
+        let project_path = project.path().to_string_lossy().to_string();
+
+        let attachments = vec![
+            Attachment::File {
+                path: "notes.md".to_string(),
+            },
+            Attachment::Image {
+                data: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                media_type: None,
+                name: Some("my screenshot.png".to_string()),
+            },
+        ];
+        let staged = stage_attachments(&project_path, &attachments).unwrap();
+        let image_dir = staged.temp_dir().unwrap().to_path_buf();
+        assert!(image_dir.join("2-myscreenshot.png").is_file());
+        // 项目内的文件不需要额外授权
+        assert_eq!(staged.add_dirs(), vec![image_dir.as_path()]);
+
+        let prompt = staged.append_to_prompt("Look at these".to_string());
+        assert!(prompt.contains("notes.md"));
+        assert!(prompt.contains("2-myscreenshot.png"));
+
+        drop(staged);
+        assert!(!image_dir.exists());
+
+        let missing = vec![Attachment::File {
+            path: "missing.txt".to_string(),
+        }];
+        assert!(stage_attachments(&project_path, &missing).is_err());
+    }
+
+    #[test]
+    fn files_outside_the_project_add_their_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let project = root.path().join("project");
+        let outside = root.path().join("outside");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(project.join("inside.md"), "a").unwrap();
+        std::fs::write(outside.join("one.md"), "b").unwrap();
+        std::fs::write(outside.join("two.md"), "c").unwrap();
+        let project_path = project.to_string_lossy().to_string();
+
+        let attachments = vec![
+            Attachment::File {
+                path: "inside.md".to_string(),
+            },
+            Attachment::File {
+                path: "../outside/one.md".to_string(),
+            },
+            Attachment::File {
+                path: outside.join("two.md").to_string_lossy().to_string(),
+            },
+        ];
+        let staged = stage_attachments(&project_path, &attachments).unwrap();
+        let dirs: Vec<PathBuf> = staged
+            .add_dirs()
+            .into_iter()
+            .map(|dir| std::fs::canonicalize(dir).unwrap())
+            .collect();
+        assert_eq!(dirs, vec![std::fs::canonicalize(&outside).unwrap()]);
+    }
+}
@@ -0,0 +1,218 @@
+//! CLAUDE.md 模板
+//!
+//! 模板以 `<name>.md` 保存在 `~/.claude/claude-md-templates/` 下，另外内置一个 `default` 模板
+//! （同名的用户模板优先）。生成时根据识别出的项目类型填充占位符：
+//! `{{project_name}}`、`{{primary_language}}`、`{{languages}}`、`{{frameworks}}`、
+//! `{{language_sections}}`。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::models::{ClaudeMdTemplate, ProjectType};
+use super::paths::{get_claude_dir, validate_file_name};
+use super::project_type::detect_in_dir;
+
+const TEMPLATES_DIR: &str = "claude-md-templates";
+const DEFAULT_TEMPLATE_NAME: &str = "default";
+
+const DEFAULT_TEMPLATE: &str = "# {{project_name}}
+
+## Overview
+
+<!-- Describe what this project does and who uses it. -->
+
+- Languages: {{languages}}
+- Frameworks: {{frameworks}}
+
+{{language_sections}}
+## Conventions
+
+<!-- Coding style, naming, error handling and anything Claude should always follow. -->
+
+## Notes
+
+<!-- Gotchas, generated files that must not be edited, external services, etc. -->
+";
+
+/// 各语言的常用命令段落
+fn language_section(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "Rust" => "## Rust\n\n- Build: `cargo build`\n- Test: `cargo test`\n- Lint: `cargo clippy --all-targets -- -D warnings`\n- Format: `cargo fmt`\n",
+        "JavaScript" | "TypeScript" => "## JavaScript / TypeScript\n\n- Install: `npm install`\n- Test: `npm test`\n- Lint: `npm run lint`\n",
+        "Python" => "## Python\n\n- Install: `pip install -e .`\n- Test: `pytest`\n- Lint: `ruff check .`\n",
+        "Go" => "## Go\n\n- Build: `go build ./...`\n- Test: `go test ./...`\n- Lint: `go vet ./...`\n",
+        "Java" | "Kotlin" => "## JVM\n\n- Build: `./gradlew build` or `mvn package`\n- Test: `./gradlew test` or `mvn test`\n",
+        "Ruby" => "## Ruby\n\n- Install: `bundle install`\n- Test: `bundle exec rspec`\n",
+        _ => return None,
+    })
+}
+
+fn templates_dir() -> Result<PathBuf, String> {
+    Ok(get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join(TEMPLATES_DIR))
+}
+
+fn load_template(name: &str) -> Result<String, String> {
+    let name = validate_file_name(name, "template name")?;
+    let path = templates_dir()?.join(format!("{}.md", name));
+    if path.is_file() {
+        return fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read template '{}': {}", name, e));
+    }
+    if name == DEFAULT_TEMPLATE_NAME {
+        return Ok(DEFAULT_TEMPLATE.to_string());
+    }
+    Err(format!("Template '{}' not found", name))
+}
+
+fn join_names(names: Vec<&str>) -> String {
+    if names.is_empty() {
+        "Unknown".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// 用项目信息替换模板中的占位符
+fn render_template(template: &str, project_name: &str, project_type: &ProjectType) -> String {
+    let languages: Vec<&str> = project_type
+        .languages
+        .iter()
+        .map(|l| l.name.as_str())
+        .collect();
+    let frameworks: Vec<&str> = project_type
+        .frameworks
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+
+    let mut sections: Vec<&str> = Vec::new();
+    for language in &languages {
+        if let Some(section) = language_section(language) {
+            if !sections.contains(&section) {
+                sections.push(section);
+            }
+        }
+    }
+
+    template
+        .replace("{{project_name}}", project_name)
+        .replace(
+            "{{primary_language}}",
+            project_type
+                .primary_language
+                .as_deref()
+                .unwrap_or("Unknown"),
+        )
+        .replace("{{languages}}", &join_names(languages))
+        .replace("{{frameworks}}", &join_names(frameworks))
+        .replace("{{language_sections}}", &sections.join("\n"))
+}
+
+/// Lists CLAUDE.md templates from ~/.claude/claude-md-templates plus the built-in default
+#[tauri::command]
+pub async fn list_claude_md_templates() -> Result<Vec<ClaudeMdTemplate>, String> {
+    let mut templates = Vec::new();
+    let dir = templates_dir()?;
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match fs::read_to_string(&path) {
+                Ok(content) => templates.push(ClaudeMdTemplate {
+                    name: name.to_string(),
+                    content,
+                    builtin: false,
+                }),
+                Err(e) => log::warn!("Skipping unreadable template {:?}: {}", path, e),
+            }
+        }
+    }
+
+    if !templates.iter().any(|t| t.name == DEFAULT_TEMPLATE_NAME) {
+        templates.push(ClaudeMdTemplate {
+            name: DEFAULT_TEMPLATE_NAME.to_string(),
+            content: DEFAULT_TEMPLATE.to_string(),
+            builtin: true,
+        });
+    }
+    templates.sort_by_key(|t| t.name.to_lowercase());
+    Ok(templates)
+}
+
+/// Writes CLAUDE.md into the project root from a template filled with the detected project type; returns its path
+#[tauri::command]
+pub async fn scaffold_claude_md(
+    project_path: String,
+    template_name: Option<String>,
+    force: Option<bool>,
+) -> Result<String, String> {
+    let root = Path::new(&project_path);
+    if !root.is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+    let target = root.join("CLAUDE.md");
+    if target.exists() && !force.unwrap_or(false) {
+        return Err(format!(
+            "CLAUDE.md already exists at {}; pass force to overwrite it",
+            target.display()
+        ));
+    }
+
+    let template_name = template_name.unwrap_or_else(|| DEFAULT_TEMPLATE_NAME.to_string());
+    let template = load_template(&template_name)?;
+    let project_name = root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Project");
+    let content = render_template(&template, project_name, &detect_in_dir(root));
+
+    fs::write(&target, content).map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+    log::info!(
+        "Scaffolded {} from template '{}'",
+        target.display(),
+        template_name
+    );
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::claude::DetectedTechnology;
+
+    fn technology(name: &str) -> DetectedTechnology {
+        DetectedTechnology {
+            name: name.to_string(),
+            confidence: 1.0,
+            evidence: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_and_language_sections_once() {
+        let project_type = ProjectType {
+            languages: vec![
+                technology("TypeScript"),
+                technology("JavaScript"),
+                technology("Rust"),
+            ],
+            frameworks: vec![technology("Tauri")],
+            primary_language: Some("TypeScript".to_string()),
+        };
+        let rendered = render_template(DEFAULT_TEMPLATE, "app", &project_type);
+
+        assert!(rendered.starts_with("# app\n"));
+        assert!(rendered.contains("- Languages: TypeScript, JavaScript, Rust"));
+        assert!(rendered.contains("- Frameworks: Tauri"));
+        assert_eq!(rendered.matches("## JavaScript / TypeScript").count(), 1);
+        assert!(rendered.contains("## Rust"));
+        assert!(!rendered.contains("{{"));
+    }
+}
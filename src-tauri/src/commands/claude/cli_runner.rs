@@ -1,20 +1,35 @@
-use std::fs;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::process::Stdio;
 use std::sync::Arc;
 
+use once_cell::sync::Lazy;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
 use crate::commands::permission_config::{
-    build_execution_args, ClaudeExecutionConfig, ClaudePermissionConfig,
+    append_extra_args, build_execution_args, ClaudeExecutionConfig, ClaudePermissionConfig,
 };
+use crate::commands::storage::{load_app_setting, store_app_setting};
+use crate::error::{AppError, AppResult};
 #[cfg(windows)]
 use crate::process::JobObject;
 
-use super::config::get_claude_execution_config;
-use super::paths::{encode_project_path, get_claude_dir};
+use super::attachments::{stage_attachments, StagedAttachments};
+use super::config::{
+    get_claude_execution_config, load_default_model, load_model_aliases, FALLBACK_MODEL,
+};
+use super::dangerous_skip::{check_dangerous_skip_launch, preview_dangerous_skip_launch};
+use super::paths::{encode_project_path, normalize_path_for_comparison};
+use super::permission_prompt;
 use super::platform;
+use super::project_env::custom_env;
+use super::prompt_prep::maybe_prepare_prompt;
+use super::rate_limit;
+use super::run_output;
+use super::slash_commands::{is_slash_command, known_slash_command_names};
+use super::Attachment;
 
 /// Global state to track current Claude process
 pub struct ClaudeProcessState {
@@ -82,7 +97,12 @@ impl Drop for ClaudeProcessState {
 /// Maps frontend model IDs to Claude CLI model aliases
 /// Converts frontend-friendly model names to official Claude Code model identifiers
 /// Updated to use Claude 4.1 Opus (released August 2025) as the latest Opus model
-pub(super) fn map_model_to_claude_alias(model: &str) -> String {
+/// User-defined aliases (set_model_aliases) are consulted first, then the built-in mapping
+pub(super) fn map_model_to_claude_alias(
+    model: &str,
+    user_aliases: &BTreeMap<String, String>,
+) -> String {
+    let model = user_aliases.get(model).map(String::as_str).unwrap_or(model);
     match model {
         "sonnet1m" => "sonnet[1m]".to_string(),
         "sonnet" => "sonnet".to_string(),
@@ -93,46 +113,32 @@ pub(super) fn map_model_to_claude_alias(model: &str) -> String {
     }
 }
 
+/// 解析实际使用的模型：前端传入空字符串时，依次使用项目覆盖 → 全局默认 → "sonnet"
+pub(super) fn resolve_claude_model(app: &AppHandle, project_path: &str, model: String) -> String {
+    if !model.trim().is_empty() {
+        return model;
+    }
+
+    let resolved = super::project_model_override(project_path, "claude")
+        .or_else(|| load_default_model(app))
+        .unwrap_or_else(|| FALLBACK_MODEL.to_string());
+    log::info!(
+        "Empty model requested for {}, resolved to: {}",
+        project_path,
+        resolved
+    );
+    resolved
+}
+
 // 🔥 已移除 escape_prompt_for_cli 函数
 // prompt 现在通过 stdin 管道传递，不再需要命令行转义
 // 这样可以避免操作系统命令行长度限制（Windows ~8KB, Linux/macOS ~128KB-2MB）
 
-/// Helper function to create a tokio Command with proper environment variables
-/// This ensures commands like Claude can find Node.js and other dependencies
-fn create_command_with_env(program: &str) -> Command {
-    // On Windows, if the program is a .cmd file, try to resolve it to direct Node.js invocation
-    // This prevents the cmd.exe window from appearing
-    #[cfg(target_os = "windows")]
-    let (final_program, extra_args) = {
-        if program.ends_with(".cmd") {
-            // Use the resolver from claude_binary module
-            if let Some((node_path, script_path)) = platform::resolve_cmd_wrapper(program) {
-                log::info!(
-                    "Resolved .cmd wrapper {} to Node.js script: {}",
-                    program,
-                    script_path
-                );
-                (node_path, vec![script_path])
-            } else {
-                (program.to_string(), vec![])
-            }
-        } else {
-            (program.to_string(), vec![])
-        }
-    };
+/// 从当前进程继承的环境变量（仅白名单），NVM 安装时把 Node.js 目录加入 PATH
+pub(crate) fn inherited_env(program: &str) -> Vec<(String, String)> {
+    let mut env = Vec::new();
 
-    #[cfg(not(target_os = "windows"))]
-    let (final_program, extra_args) = (program.to_string(), Vec::<String>::new());
-
-    // Create a new tokio Command from the resolved program path
-    let mut tokio_cmd = Command::new(&final_program);
-
-    // Add any extra arguments (e.g., script path when using node directly)
-    for arg in extra_args {
-        tokio_cmd.arg(arg);
-    }
-
-    // Copy over all environment variables
+    // Inherit whitelisted environment variables
     for (key, value) in std::env::vars() {
         if key == "PATH"
             || key == "HOME"
@@ -159,8 +165,8 @@ fn create_command_with_env(program: &str) -> Command {
             || key.starts_with("CLAUDE_CODE_")
             || key == "API_TIMEOUT_MS"
         {
-            log::debug!("Inheriting env var: {}={}", key, value);
-            tokio_cmd.env(&key, &value);
+            log::debug!("Inheriting env var: {}", key);
+            env.push((key, value));
         }
     }
 
@@ -177,48 +183,114 @@ fn create_command_with_env(program: &str) -> Command {
                 let separator = ":";
 
                 let new_path = format!("{}{}{}", node_bin_str, separator, current_path);
-                tokio_cmd.env("PATH", new_path);
+                env.retain(|(key, _)| key != "PATH");
+                env.push(("PATH".to_string(), new_path));
             }
         }
     }
 
-    // 🔥 新增：读取 ~/.claude/settings.json 中的自定义环境变量
-    // 这些变量会覆盖系统环境变量，确保用户的自定义配置生效
-    if let Ok(claude_dir) = get_claude_dir() {
-        let settings_path = claude_dir.join("settings.json");
-        if settings_path.exists() {
-            if let Ok(content) = fs::read_to_string(&settings_path) {
-                if let Ok(settings) = serde_json::from_str::<serde_json::Value>(&content) {
-                    if let Some(env_obj) = settings.get("env").and_then(|v| v.as_object()) {
-                        log::info!(
-                            "Loading {} custom environment variables from settings.json",
-                            env_obj.len()
-                        );
-                        for (key, value) in env_obj {
-                            if let Some(value_str) = value.as_str() {
-                                log::info!("Setting custom env var: {}={}", key, value_str);
-                                tokio_cmd.env(key, value_str);
-                            }
-                        }
-                    }
-                }
+    // binaries.json 中指定的 node 优先于 PATH 中的其他版本
+    let current_path = env
+        .iter()
+        .find(|(key, _)| key == "PATH")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+    if let Some(new_path) = crate::claude_binary::path_with_configured_node(&current_path) {
+        env.retain(|(key, _)| key != "PATH");
+        env.push(("PATH".to_string(), new_path));
+    }
+
+    env
+}
+
+/// Helper function to create a tokio Command with proper environment variables
+/// This ensures commands like Claude can find Node.js and other dependencies
+fn create_command_with_env(program: &str, project_path: Option<&str>) -> Command {
+    // On Windows, if the program is a .cmd file, try to resolve it to direct Node.js invocation
+    // This prevents the cmd.exe window from appearing
+    #[cfg(target_os = "windows")]
+    let (final_program, extra_args) = {
+        if program.ends_with(".cmd") {
+            // Use the resolver from claude_binary module
+            if let Some((node_path, script_path)) = platform::resolve_cmd_wrapper(program) {
+                log::info!(
+                    "Resolved .cmd wrapper {} to Node.js script: {}",
+                    program,
+                    script_path
+                );
+                (node_path, vec![script_path])
+            } else {
+                (program.to_string(), vec![])
             }
+        } else {
+            (program.to_string(), vec![])
         }
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let (final_program, extra_args) = (program.to_string(), Vec::<String>::new());
+
+    // Create a new tokio Command from the resolved program path
+    let mut tokio_cmd = Command::new(&final_program);
+
+    // Add any extra arguments (e.g., script path when using node directly)
+    for arg in extra_args {
+        tokio_cmd.arg(arg);
+    }
+
+    // 继承的系统环境变量，之后依次叠加 settings.json → .anycode.env → binaries.json 工具 env
+    for (key, value) in inherited_env(program) {
+        tokio_cmd.env(key, value);
+    }
+    for (key, value) in custom_env("claude", project_path) {
+        log::info!("Setting custom env var: {}", key);
+        tokio_cmd.env(key, value);
     }
 
     tokio_cmd
 }
 
+/// 往返测试用的 Claude 命令：与正式会话相同的环境变量叠加，但只带 `-p` 和输出格式参数；
+/// 在 `working_dir`（通常是 `ScratchProjectDir`）中执行
+pub(crate) fn create_roundtrip_command(
+    app: &AppHandle,
+    model: Option<&str>,
+    prompt: &str,
+    working_dir: &Path,
+) -> Result<Command, String> {
+    let claude_path = crate::claude_binary::find_claude_binary(app).map_err(|e| e.to_string())?;
+    if claude_path == "claude-code" {
+        return Err("The roundtrip test does not support the bundled sidecar".to_string());
+    }
+
+    let mut cmd = create_command_with_env(&claude_path, None);
+    cmd.args(["-p", prompt, "--output-format", "text"]);
+    if let Some(model) = model {
+        cmd.arg("--model")
+            .arg(map_model_to_claude_alias(model, &load_model_aliases(app)));
+    }
+    cmd.current_dir(working_dir);
+    Ok(cmd)
+}
+
 /// Helper function to spawn Claude process and handle streaming
 /// Enhanced for Windows compatibility with router support
 fn create_system_command(
     claude_path: &str,
     args: Vec<String>,
     project_path: &str,
+    working_dir: &str,
     model: Option<&str>,
-    _max_thinking_tokens: Option<u32>, // Keep parameter for compatibility but don't use it
-) -> Result<Command, String> {
-    create_windows_command(claude_path, args, project_path, model)
+    max_thinking_tokens: Option<u32>,
+) -> AppResult<Command> {
+    create_windows_command(
+        claude_path,
+        args,
+        project_path,
+        working_dir,
+        model,
+        max_thinking_tokens,
+    )
 }
 
 /// Create a Windows command
@@ -227,9 +299,11 @@ fn create_windows_command(
     claude_path: &str,
     args: Vec<String>,
     project_path: &str,
+    working_dir: &str,
     model: Option<&str>,
-) -> Result<Command, String> {
-    let mut cmd = create_command_with_env(claude_path);
+    max_thinking_tokens: Option<u32>,
+) -> AppResult<Command> {
+    let mut cmd = create_command_with_env(claude_path, Some(project_path));
 
     // 🔥 修复：设置ANTHROPIC_MODEL环境变量以确保模型选择生效
     if let Some(model_name) = model {
@@ -240,14 +314,21 @@ fn create_windows_command(
         cmd.env("ANTHROPIC_MODEL", model_name);
     }
 
-    // Note: MAX_THINKING_TOKENS is now controlled via settings.json env field
-    // See update_thinking_mode command for managing this setting
+    // MAX_THINKING_TOKENS 优先级：本次请求指定的值 > settings.json 的 env（update_thinking_mode 管理的全局默认值）
+    // 在 settings.json 的 env 叠加之后设置，只作用于这个进程，不修改 settings.json
+    if let Some(tokens) = max_thinking_tokens {
+        log::info!(
+            "Setting MAX_THINKING_TOKENS for this request to: {}",
+            tokens
+        );
+        cmd.env("MAX_THINKING_TOKENS", tokens.to_string());
+    }
 
     // Add all arguments
     cmd.args(&args);
 
     // Set working directory
-    cmd.current_dir(project_path);
+    cmd.current_dir(working_dir);
 
     // Configure stdio for capturing output
     // 🔥 添加 stdin pipe 以支持通过管道传递长文本 prompt
@@ -269,10 +350,139 @@ fn create_windows_command(
     Ok(cmd)
 }
 
+/// 追加执行配置中的默认附加参数和本次调用传入的附加参数
+fn apply_extra_args(
+    args: &mut Vec<String>,
+    execution_config: &ClaudeExecutionConfig,
+    extra_args: Option<Vec<String>>,
+) {
+    let mut combined = execution_config.extra_args.clone();
+    combined.extend(extra_args.unwrap_or_default());
+    if !combined.is_empty() {
+        append_extra_args(args, &combined);
+    }
+}
+
+/// 解析会话的工作目录覆盖：必须是已存在的目录，默认须位于项目路径之内
+/// 与项目路径相同时返回 None
+fn resolve_working_dir(
+    project_path: &str,
+    working_dir: Option<String>,
+    execution_config: &ClaudeExecutionConfig,
+) -> AppResult<Option<String>> {
+    let Some(working_dir) = working_dir.filter(|d| !d.trim().is_empty()) else {
+        return Ok(None);
+    };
+
+    let working_dir_path = std::path::Path::new(working_dir.trim());
+    if !working_dir_path.is_dir() {
+        return Err(AppError::invalid_config(format!(
+            "Working directory does not exist: {}",
+            working_dir
+        )));
+    }
+    let canonical_working_dir = working_dir_path
+        .canonicalize()
+        .map_err(|e| AppError::from_io("Failed to resolve working directory", e))?;
+    let canonical_project = std::path::Path::new(project_path)
+        .canonicalize()
+        .map_err(|e| AppError::from_io("Failed to resolve project path", e))?;
+
+    if canonical_working_dir == canonical_project {
+        return Ok(None);
+    }
+    if !canonical_working_dir.starts_with(&canonical_project)
+        && !execution_config.allow_working_dir_outside_project
+    {
+        return Err(AppError::invalid_config(format!(
+            "Working directory {} is outside project {}",
+            working_dir, project_path
+        )));
+    }
+
+    Ok(Some(working_dir.trim().to_string()))
+}
+
+/// 读取本次启动使用的执行配置，并叠加请求级的 thinking token 与 Plan Mode 设置
+async fn launch_execution_config(
+    app: &AppHandle,
+    project_path: &str,
+    plan_mode: bool,
+    max_thinking_tokens: Option<u32>,
+) -> ClaudeExecutionConfig {
+    // 获取当前执行配置
+    let mut execution_config =
+        get_claude_execution_config(app.clone(), Some(project_path.to_string()))
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to load execution config, using default: {}", e);
+                ClaudeExecutionConfig::default()
+            });
+
+    // 本次请求的 thinking token 数通过进程环境变量传递（见 create_windows_command），
+    // 不再同时传入执行配置中的 --max-thinking-tokens
+    if max_thinking_tokens.is_some() {
+        execution_config.max_thinking_tokens = None;
+    }
+
+    // 如果启用 Plan Mode，使用 Claude CLI 原生的 plan 权限模式
+    if plan_mode {
+        execution_config.permissions = ClaudePermissionConfig::plan_mode();
+    }
+    execution_config
+}
+
+/// 构建 execute 模式下将要启动的 Claude 命令但不启动进程，供 dry_run_launch 预览。
+/// 参数、环境变量与工作目录的构建与 execute_claude_code 相同，危险跳过权限的保护限制也同样生效；
+/// 不暂存附件，也不记录危险跳过的使用。返回命令和经 stdin 传入的 prompt
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn build_claude_dry_run_command(
+    app: &AppHandle,
+    project_path: &str,
+    prompt: String,
+    model: Option<String>,
+    plan_mode: bool,
+    max_thinking_tokens: Option<u32>,
+    working_dir: Option<String>,
+    extra_args: Option<Vec<String>>,
+) -> AppResult<(Command, Option<String>)> {
+    let model = resolve_claude_model(app, project_path, model.unwrap_or_default());
+    let claude_path = crate::claude_binary::find_claude_binary(app)?;
+    let mut execution_config =
+        launch_execution_config(app, project_path, plan_mode, max_thinking_tokens).await;
+    preview_dangerous_skip_launch(&mut execution_config);
+
+    let mapped_model = map_model_to_claude_alias(&model, &load_model_aliases(app));
+    let prompt = maybe_prepare_prompt(&execution_config, prompt);
+    let mut args = build_execution_args(&execution_config, &mapped_model);
+    apply_extra_args(&mut args, &execution_config, extra_args);
+    let working_dir = resolve_working_dir(project_path, working_dir, &execution_config)?;
+
+    let mut cmd = create_system_command(
+        &claude_path,
+        args,
+        project_path,
+        working_dir.as_deref().unwrap_or(project_path),
+        Some(&mapped_model),
+        max_thinking_tokens,
+    )?;
+    let app_approval = execution_config.permissions.uses_app_approval();
+    let passed_as_arg = apply_slash_command_flag(
+        &mut cmd,
+        &prompt,
+        project_path,
+        app_approval,
+        &execution_config,
+    )
+    .await;
+    Ok((cmd, (!passed_as_arg).then_some(prompt)))
+}
+
 /// Execute Claude Code session with project context resume and streaming output
 /// Always tries to resume project context first for better continuity
 /// Enhanced for Windows with better error handling
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_claude_code(
     app: AppHandle,
     project_path: String,
@@ -281,8 +491,12 @@ pub async fn execute_claude_code(
     plan_mode: Option<bool>,
     max_thinking_tokens: Option<u32>,
     tab_id: Option<String>,
-) -> Result<(), String> {
+    extra_args: Option<Vec<String>>,
+    working_dir: Option<String>,
+    attachments: Option<Vec<Attachment>>,
+) -> AppResult<()> {
     let plan_mode = plan_mode.unwrap_or(false);
+    let model = resolve_claude_model(&app, &project_path, model);
     log::info!(
         "Starting Claude Code session with project context resume in: {} with model: {}, plan_mode: {}",
         project_path,
@@ -292,24 +506,9 @@ pub async fn execute_claude_code(
 
     let claude_path = crate::claude_binary::find_claude_binary(&app)?;
 
-    // 获取当前执行配置
-    let mut execution_config = get_claude_execution_config(app.clone())
-        .await
-        .unwrap_or_else(|e| {
-            log::warn!("Failed to load execution config, using default: {}", e);
-            ClaudeExecutionConfig::default()
-        });
-
-    // 设置 maxThinkingTokens（如果提供）
-    if let Some(tokens) = max_thinking_tokens {
-        execution_config.max_thinking_tokens = Some(tokens);
-        log::info!("Setting maxThinkingTokens to {}", tokens);
-    }
-
-    // 如果启用 Plan Mode，使用 Claude CLI 原生的 plan 权限模式
-    if plan_mode {
-        execution_config.permissions = ClaudePermissionConfig::plan_mode();
-    }
+    let mut execution_config =
+        launch_execution_config(&app, &project_path, plan_mode, max_thinking_tokens).await;
+    check_dangerous_skip_launch(&mut execution_config, "execute", &project_path);
 
     log::info!("Using execution config: permissions_mode={:?}, dangerous_skip={}, plan_mode={}, max_thinking_tokens={:?}",
         execution_config.permissions.permission_mode,
@@ -320,23 +519,47 @@ pub async fn execute_claude_code(
 
     // 使用新的参数构建函数（先映射模型名称）
     // 🔥 修复：prompt 不再通过命令行参数传递，改为 stdin 管道传递
-    let mapped_model = map_model_to_claude_alias(&model);
-    let args = build_execution_args(&execution_config, &mapped_model);
+    let mapped_model = map_model_to_claude_alias(&model, &load_model_aliases(&app));
+    let prompt = maybe_prepare_prompt(&execution_config, prompt);
+    // 附件：文件直接引用路径，图片写入临时目录；临时目录和项目外文件所在目录授权 CLI 访问
+    let attachments = stage_attachments(&project_path, &attachments.unwrap_or_default())?;
+    let prompt = attachments.append_to_prompt(prompt);
+    let mut args = build_execution_args(&execution_config, &mapped_model);
+    for dir in attachments.add_dirs() {
+        args.push("--add-dir".to_string());
+        args.push(dir.to_string_lossy().to_string());
+    }
+    apply_extra_args(&mut args, &execution_config, extra_args);
+    let working_dir = resolve_working_dir(&project_path, working_dir, &execution_config)?;
 
     // Create command
     let cmd = create_system_command(
         &claude_path,
         args,
         &project_path,
+        working_dir.as_deref().unwrap_or(&project_path),
         Some(&mapped_model),
         max_thinking_tokens,
     )?;
-    spawn_claude_process(app, cmd, prompt, model, project_path, tab_id).await
+    let app_approval = execution_config.permissions.uses_app_approval();
+    spawn_claude_process(
+        app,
+        cmd,
+        prompt,
+        model,
+        project_path,
+        tab_id,
+        app_approval,
+        0,
+        attachments,
+    )
+    .await
 }
 
 /// Continue an existing Claude Code conversation with streaming output
 /// Enhanced for Windows with better error handling
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn continue_claude_code(
     app: AppHandle,
     project_path: String,
@@ -345,8 +568,11 @@ pub async fn continue_claude_code(
     plan_mode: Option<bool>,
     max_thinking_tokens: Option<u32>,
     tab_id: Option<String>,
-) -> Result<(), String> {
+    extra_args: Option<Vec<String>>,
+    working_dir: Option<String>,
+) -> AppResult<()> {
     let plan_mode = plan_mode.unwrap_or(false);
+    let model = resolve_claude_model(&app, &project_path, model);
     log::info!(
         "Continuing Claude Code conversation in: {} with model: {}, plan_mode: {}",
         project_path,
@@ -356,24 +582,9 @@ pub async fn continue_claude_code(
 
     let claude_path = crate::claude_binary::find_claude_binary(&app)?;
 
-    // 获取当前执行配置
-    let mut execution_config = get_claude_execution_config(app.clone())
-        .await
-        .unwrap_or_else(|e| {
-            log::warn!("Failed to load execution config, using default: {}", e);
-            ClaudeExecutionConfig::default()
-        });
-
-    // 设置 maxThinkingTokens（如果提供）
-    if let Some(tokens) = max_thinking_tokens {
-        execution_config.max_thinking_tokens = Some(tokens);
-        log::info!("Setting maxThinkingTokens to {}", tokens);
-    }
-
-    // 如果启用 Plan Mode，使用 Claude CLI 原生的 plan 权限模式
-    if plan_mode {
-        execution_config.permissions = ClaudePermissionConfig::plan_mode();
-    }
+    let mut execution_config =
+        launch_execution_config(&app, &project_path, plan_mode, max_thinking_tokens).await;
+    check_dangerous_skip_launch(&mut execution_config, "continue", &project_path);
 
     log::info!("Continuing with execution config: permissions_mode={:?}, dangerous_skip={}, plan_mode={}, max_thinking_tokens={:?}",
         execution_config.permissions.permission_mode,
@@ -384,26 +595,48 @@ pub async fn continue_claude_code(
 
     // 使用新的参数构建函数，添加 -c 标志用于继续对话（先映射模型名称）
     // 🔥 修复：prompt 不再通过命令行参数传递，改为 stdin 管道传递
-    let mapped_model = map_model_to_claude_alias(&model);
+    let mapped_model = map_model_to_claude_alias(&model, &load_model_aliases(&app));
+    let prompt = maybe_prepare_prompt(&execution_config, prompt);
     let mut args = build_execution_args(&execution_config, &mapped_model);
 
     // 在开头插入 -c 标志
     args.insert(0, "-c".to_string());
+    apply_extra_args(&mut args, &execution_config, extra_args);
+    // -c 接续的是运行目录中最近的会话：最近的会话运行在记录的工作目录中时，在该目录下继续
+    let working_dir = working_dir.or_else(|| {
+        most_recent_session_id(&project_path)
+            .and_then(|session_id| super::session_working_dir(&session_id, &project_path))
+    });
+    let working_dir = resolve_working_dir(&project_path, working_dir, &execution_config)?;
 
     // Create command
     let cmd = create_system_command(
         &claude_path,
         args,
         &project_path,
+        working_dir.as_deref().unwrap_or(&project_path),
         Some(&mapped_model),
         max_thinking_tokens,
     )?;
-    spawn_claude_process(app, cmd, prompt, model, project_path, tab_id).await
+    let app_approval = execution_config.permissions.uses_app_approval();
+    spawn_claude_process(
+        app,
+        cmd,
+        prompt,
+        model,
+        project_path,
+        tab_id,
+        app_approval,
+        0,
+        StagedAttachments::default(),
+    )
+    .await
 }
 
 /// Resume an existing Claude Code session by ID with streaming output
 /// Enhanced for Windows with better error handling
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn resume_claude_code(
     app: AppHandle,
     project_path: String,
@@ -413,8 +646,11 @@ pub async fn resume_claude_code(
     plan_mode: Option<bool>,
     max_thinking_tokens: Option<u32>,
     tab_id: Option<String>,
-) -> Result<(), String> {
+    extra_args: Option<Vec<String>>,
+    working_dir: Option<String>,
+) -> AppResult<()> {
     let plan_mode = plan_mode.unwrap_or(false);
+    let model = resolve_claude_model(&app, &project_path, model);
     log::info!(
         "Resuming Claude Code session: {} in: {} with model: {}, plan_mode: {}",
         session_id,
@@ -436,24 +672,9 @@ pub async fn resume_claude_code(
 
     let claude_path = crate::claude_binary::find_claude_binary(&app)?;
 
-    // 获取当前执行配置
-    let mut execution_config = get_claude_execution_config(app.clone())
-        .await
-        .unwrap_or_else(|e| {
-            log::warn!("Failed to load execution config, using default: {}", e);
-            ClaudeExecutionConfig::default()
-        });
-
-    // 设置 maxThinkingTokens（如果提供）
-    if let Some(tokens) = max_thinking_tokens {
-        execution_config.max_thinking_tokens = Some(tokens);
-        log::info!("Setting maxThinkingTokens to {}", tokens);
-    }
-
-    // 如果启用 Plan Mode，使用 Claude CLI 原生的 plan 权限模式
-    if plan_mode {
-        execution_config.permissions = ClaudePermissionConfig::plan_mode();
-    }
+    let mut execution_config =
+        launch_execution_config(&app, &project_path, plan_mode, max_thinking_tokens).await;
+    check_dangerous_skip_launch(&mut execution_config, "resume", &project_path);
 
     log::info!("Resuming with execution config: permissions_mode={:?}, dangerous_skip={}, plan_mode={}, max_thinking_tokens={:?}",
         execution_config.permissions.permission_mode,
@@ -464,12 +685,19 @@ pub async fn resume_claude_code(
 
     // 使用新的参数构建函数，添加 --resume 和 session_id（先映射模型名称）
     // 🔥 修复：prompt 不再通过命令行参数传递，改为 stdin 管道传递
-    let mapped_model = map_model_to_claude_alias(&model);
+    let mapped_model = map_model_to_claude_alias(&model, &load_model_aliases(&app));
+    let prompt = maybe_prepare_prompt(&execution_config, prompt);
     let mut args = build_execution_args(&execution_config, &mapped_model);
 
     // 为resume模式重新组织参数：--resume session_id 应该在最前面
     args.insert(0, "--resume".to_string());
     args.insert(1, session_id.clone());
+    apply_extra_args(&mut args, &execution_config, extra_args.clone());
+
+    // 未显式指定时沿用会话上次记录的工作目录（会话文件按工作目录存放）
+    let working_dir =
+        working_dir.or_else(|| super::session_working_dir(&session_id, &project_path));
+    let working_dir = resolve_working_dir(&project_path, working_dir, &execution_config)?;
 
     log::info!("Resume command: claude {}", args.join(" "));
 
@@ -478,11 +706,12 @@ pub async fn resume_claude_code(
         &claude_path,
         args,
         &project_path,
+        working_dir.as_deref().unwrap_or(&project_path),
         Some(&mapped_model),
         max_thinking_tokens,
     )?;
 
-    // Try to spawn the process - if it fails, fall back to continue mode
+    // Try to spawn the process - if it fails to start, fall back to continue mode
     match spawn_claude_process(
         app.clone(),
         cmd,
@@ -490,10 +719,17 @@ pub async fn resume_claude_code(
         model.clone(),
         project_path.clone(),
         tab_id.clone(),
+        execution_config.permissions.uses_app_approval(),
+        0,
+        StagedAttachments::default(),
     )
     .await
     {
         Ok(_) => Ok(()),
+        Err(resume_error) if !falls_back_to_continue(&resume_error) => {
+            log::info!("Resume was not started: {}", resume_error);
+            Err(resume_error)
+        }
         Err(resume_error) => {
             log::warn!(
                 "Resume failed: {}, trying continue mode as fallback",
@@ -508,23 +744,136 @@ pub async fn resume_claude_code(
                 Some(plan_mode),
                 max_thinking_tokens,
                 tab_id,
+                extra_args,
+                working_dir,
             )
             .await
         }
     }
 }
 
-/// Cancel the currently running Claude Code execution
+/// 恢复会话启动失败时是否改用 `-c` 继续：排队中被取消或因并发上限被拒绝时
+/// 用户并没有要求新的运行，直接返回错误
+fn falls_back_to_continue(err: &AppError) -> bool {
+    !matches!(err, AppError::Cancelled(_) | AppError::QueueFull(_))
+}
+
+/// 会话文件最后一条消息的时间
+fn last_message_time(path: &Path) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let last_activity = super::session_history::session_stats(path).last_activity?;
+    chrono::DateTime::parse_from_rfc3339(&last_activity).ok()
+}
+
+/// 目录中最近活动的会话：按最后一条消息的实际时间比较（不同时区的时间戳也能正确排序），
+/// 跳过子代理会话和没有消息的会话
+fn most_recent_session_in(project_dir: &Path) -> Option<String> {
+    std::fs::read_dir(project_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .filter_map(|path| {
+            let session_id = path.file_stem()?.to_str()?;
+            if session_id.starts_with("agent-") {
+                return None;
+            }
+            Some((last_message_time(&path)?, session_id.to_string()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, session_id)| session_id)
+}
+
+/// 项目中最近活动的会话（按会话文件最后一条消息的时间）
+fn most_recent_session_id(project_path: &str) -> Option<String> {
+    let store = super::project_store::ProjectStore::new().ok()?;
+    most_recent_session_in(&store.projects_dir().join(encode_project_path(project_path)))
+}
+
+/// Outcome of `resume_last_claude`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LastSessionResume {
+    /// The most recently active session was resumed
+    Resumed {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+    /// No previous session was found, so the run was started with `-c`;
+    /// its session id is reported by the run's init message like any new session
+    Continued,
+}
+
+/// Resume the most recently active Claude session of a project (falls back to `-c` when none is found)
 #[tauri::command]
-pub async fn cancel_claude_execution(
+pub async fn resume_last_claude(
     app: AppHandle,
-    session_id: Option<String>,
-) -> Result<(), String> {
+    project_path: String,
+    prompt: String,
+    model: String,
+    tab_id: Option<String>,
+) -> AppResult<LastSessionResume> {
+    let Some(session_id) = most_recent_session_id(&project_path) else {
+        log::info!(
+            "No previous session found in {}, continuing with -c",
+            project_path
+        );
+        continue_claude_code(
+            app,
+            project_path,
+            prompt,
+            model,
+            None,
+            None,
+            tab_id,
+            None,
+            None,
+        )
+        .await?;
+        return Ok(LastSessionResume::Continued);
+    };
+
+    log::info!("Resuming last session {} in {}", session_id, project_path);
+    resume_claude_code(
+        app,
+        project_path,
+        session_id.clone(),
+        prompt,
+        model,
+        None,
+        None,
+        tab_id,
+        None,
+        None,
+    )
+    .await?;
+    Ok(LastSessionResume::Resumed { session_id })
+}
+
+/// Cancel the currently running Claude Code execution
+#[tauri::command]
+pub async fn cancel_claude_execution(app: AppHandle, session_id: Option<String>) -> AppResult<()> {
     log::info!(
         "Cancelling Claude Code execution for session: {:?}",
         session_id
     );
 
+    // 仍在排队等待名额的会话直接移出队列，启动请求会以取消错误返回
+    if let Some(sid) = &session_id {
+        let limiter = app.state::<crate::process::SessionLimiterState>();
+        if limiter.0.cancel_queued(None, Some(sid)) {
+            log::info!("Removed queued session {} before it started", sid);
+            return Ok(());
+        }
+        // 限流后等待自动重试的会话：取消重试，没有需要结束的进程
+        if rate_limit::cancel_pending_retry(sid) {
+            log::info!("Cancelled pending rate limit retry for session {}", sid);
+            let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
+            let _ = app.emit("claude-cancelled", true);
+            run_output::emit_claude_complete(&app, Some(sid), None, false);
+            return Ok(());
+        }
+    }
+
     let mut killed = false;
     let mut attempted_methods = Vec::new();
 
@@ -636,17 +985,13 @@ pub async fn cancel_claude_execution(
         log::warn!("No active Claude process found to cancel");
     }
 
-    // Always emit cancellation events for UI consistency
-    if let Some(sid) = session_id {
+    // Always emit cancellation events for UI consistency (generic ones for backward compatibility)
+    if let Some(sid) = &session_id {
         let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = app.emit(&format!("claude-complete:{}", sid), false);
     }
-
-    // Also emit generic events for backward compatibility
     let _ = app.emit("claude-cancelled", true);
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    let _ = app.emit("claude-complete", false);
+    run_output::emit_claude_complete(&app, session_id.as_deref(), None, false);
 
     if killed {
         log::info!("Claude process cancellation completed successfully");
@@ -657,12 +1002,286 @@ pub async fn cancel_claude_execution(
     Ok(())
 }
 
-/// Get all running Claude sessions
+/// Kill every running Claude, Codex and Gemini session (panic button)
+///
+/// Registry / state locks are only held while collecting the targets, never while killing,
+/// so this cannot deadlock with the per-session wait tasks. Safe to call when nothing runs.
+/// Returns the number of sessions whose process tree was killed.
+#[tauri::command]
+pub async fn cancel_all_running_sessions(app: AppHandle) -> Result<usize, String> {
+    let killed = cancel_sessions(&app, None).await?;
+    log::info!("cancel_all_running_sessions: killed {} session(s)", killed);
+    Ok(killed)
+}
+
+/// 项目路径是否匹配（规范化后比较）；`project` 为 None 时匹配所有会话
+fn project_matches(project: Option<&str>, project_path: &str) -> bool {
+    match project {
+        Some(project) => {
+            normalize_path_for_comparison(project) == normalize_path_for_comparison(project_path)
+        }
+        None => true,
+    }
+}
+
+/// 结束 Claude / Codex / Gemini 会话，`project` 不为 None 时只结束该项目的会话
+async fn cancel_sessions(app: &AppHandle, project: Option<&str>) -> Result<usize, String> {
+    use crate::commands::codex::CodexProcessState;
+    use crate::commands::gemini::GeminiProcessState;
+
+    let mut killed = 0usize;
+
+    // Claude: snapshot the registry (lock released on return), then kill
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let claude_sessions = match project {
+        Some(project_path) => registry
+            .0
+            .get_running_claude_sessions_for_project(project_path)?,
+        None => registry.0.get_running_claude_sessions()?,
+    };
+    let claude_pids: Vec<u32> = claude_sessions.iter().map(|info| info.pid).collect();
+    let had_claude_sessions = !claude_sessions.is_empty();
+    for info in claude_sessions {
+        let crate::process::ProcessType::ClaudeSession { session_id } = &info.process_type else {
+            continue;
+        };
+
+        if info.pid != 0 {
+            match platform::kill_process_tree(info.pid) {
+                Ok(_) => killed += 1,
+                Err(e) => log::warn!(
+                    "Failed to kill Claude session {} (PID {}): {}",
+                    session_id,
+                    info.pid,
+                    e
+                ),
+            }
+        }
+        if let Err(e) = registry.0.unregister_process(info.run_id) {
+            log::warn!("Failed to unregister run {}: {}", info.run_id, e);
+        }
+
+        let _ = app.emit(
+            "claude-session-state",
+            &serde_json::json!({
+                "session_id": session_id,
+                "status": "stopped",
+                "success": false,
+            }),
+        );
+        let _ = app.emit(&format!("claude-cancelled:{}", session_id), true);
+        // 与 cancel_claude_execution 一样为每个会话发送结束事件（按项目结束时也不例外）
+        run_output::emit_claude_complete(app, Some(session_id), None, false);
+    }
+    {
+        let claude_state = app.state::<ClaudeProcessState>();
+        let mut last_spawned_pid = claude_state.last_spawned_pid.lock().await;
+        if project.is_none() || last_spawned_pid.is_some_and(|pid| claude_pids.contains(&pid)) {
+            *last_spawned_pid = None;
+        }
+    }
+
+    // Codex: take the matching handles out under the lock, kill after releasing it
+    // codex-complete is emitted by each session's own completion task once stdout closes
+    let codex_handles: Vec<_> = {
+        let state = app.state::<CodexProcessState>();
+        let mut processes = state.processes.lock().await;
+        let session_ids: Vec<String> = processes
+            .iter()
+            .filter(|(_, handle)| project_matches(project, &handle.project_path))
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        session_ids
+            .into_iter()
+            .filter_map(|session_id| processes.remove_entry(&session_id))
+            .collect()
+    };
+    let had_codex_sessions = !codex_handles.is_empty();
+    for (session_id, handle) in codex_handles {
+        match platform::kill_process_tree(handle.pid) {
+            Ok(_) => killed += 1,
+            Err(e) => {
+                log::warn!("Failed to kill Codex session {}: {}", session_id, e);
+                let mut child = handle.child;
+                if child.kill().await.is_ok() {
+                    killed += 1;
+                }
+            }
+        }
+        let _ = app.emit(&format!("codex-cancelled:{}", session_id), true);
+    }
+
+    // Gemini: same pattern; dropping the JobObject cleans up descendants on Windows
+    let gemini_handles: Vec<_> = {
+        let state = app.state::<GeminiProcessState>();
+        let mut processes = state.processes.lock().await;
+        let session_ids: Vec<String> = processes
+            .iter()
+            .filter(|(_, handle)| project_matches(project, &handle.project_path))
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        session_ids
+            .into_iter()
+            .filter_map(|session_id| processes.remove_entry(&session_id))
+            .collect()
+    };
+    let had_gemini_sessions = !gemini_handles.is_empty();
+    for (session_id, mut handle) in gemini_handles {
+        match platform::kill_process_tree(handle.pid) {
+            Ok(_) => killed += 1,
+            Err(e) => {
+                log::warn!("Failed to kill Gemini session {}: {}", session_id, e);
+                if handle.child.kill().await.is_ok() {
+                    killed += 1;
+                }
+            }
+        }
+        // Windows 上释放 Job Object 会结束其中的子进程（MCP 服务器等）
+        #[cfg(windows)]
+        drop(handle.job_object);
+        let _ = app.emit(&format!("gemini-cancelled:{}", session_id), true);
+    }
+
+    // 全局取消事件只在结束所有会话时发送，结束事件已在上面按会话发送
+    if project.is_some() {
+        return Ok(killed);
+    }
+    if had_claude_sessions {
+        let _ = app.emit("claude-cancelled", true);
+    }
+    if had_codex_sessions {
+        let _ = app.emit("codex-cancelled", true);
+    }
+    if had_gemini_sessions {
+        let _ = app.emit("gemini-cancelled", true);
+    }
+
+    Ok(killed)
+}
+
+/// A running Claude, Codex or Gemini session of a project
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProjectSessionInfo {
+    /// "claude", "codex" or "gemini"
+    pub engine: String,
+    pub session_id: String,
+    pub pid: u32,
+    pub project_path: String,
+}
+
+/// List the running Claude, Codex and Gemini sessions that were started in a project
+#[tauri::command]
+pub async fn list_running_sessions_by_project(
+    app: AppHandle,
+    project_path: String,
+) -> Result<Vec<ProjectSessionInfo>, String> {
+    use crate::commands::codex::CodexProcessState;
+    use crate::commands::gemini::GeminiProcessState;
+
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let mut sessions: Vec<ProjectSessionInfo> = registry
+        .0
+        .get_running_claude_sessions_for_project(&project_path)?
+        .into_iter()
+        .filter_map(|info| match info.process_type {
+            crate::process::ProcessType::ClaudeSession { session_id } => Some(ProjectSessionInfo {
+                engine: "claude".to_string(),
+                session_id,
+                pid: info.pid,
+                project_path: info.project_path,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    {
+        let state = app.state::<CodexProcessState>();
+        let processes = state.processes.lock().await;
+        sessions.extend(
+            processes
+                .iter()
+                .filter(|(_, handle)| project_matches(Some(&project_path), &handle.project_path))
+                .map(|(session_id, handle)| ProjectSessionInfo {
+                    engine: "codex".to_string(),
+                    session_id: session_id.clone(),
+                    pid: handle.pid,
+                    project_path: handle.project_path.clone(),
+                }),
+        );
+    }
+    {
+        let state = app.state::<GeminiProcessState>();
+        let processes = state.processes.lock().await;
+        sessions.extend(
+            processes
+                .iter()
+                .filter(|(_, handle)| project_matches(Some(&project_path), &handle.project_path))
+                .map(|(session_id, handle)| ProjectSessionInfo {
+                    engine: "gemini".to_string(),
+                    session_id: session_id.clone(),
+                    pid: handle.pid,
+                    project_path: handle.project_path.clone(),
+                }),
+        );
+    }
+
+    Ok(sessions)
+}
+
+/// Kill the running Claude, Codex and Gemini sessions of a project; returns how many were killed
+#[tauri::command]
+pub async fn cancel_project_sessions(
+    app: AppHandle,
+    project_path: String,
+) -> Result<usize, String> {
+    let killed = cancel_sessions(&app, Some(&project_path)).await?;
+    log::info!(
+        "cancel_project_sessions: killed {} session(s) in {}",
+        killed,
+        project_path
+    );
+    Ok(killed)
+}
+
+/// List processes left running by a previous (crashed) instance of the app
+///
+/// Pids come from the persisted process journal and are only reported when their
+/// start time and command line still match what was recorded. With `kill: true`
+/// the verified orphans are killed as well.
+#[tauri::command]
+pub async fn reap_orphaned_processes(
+    app: AppHandle,
+    kill: Option<bool>,
+) -> AppResult<Vec<crate::process::OrphanedProcess>> {
+    use crate::commands::codex::CodexProcessState;
+
+    // Codex processes live outside the registry but are journaled too
+    let codex_pids: Vec<u32> = {
+        let state = app.state::<CodexProcessState>();
+        let processes = state.processes.lock().await;
+        processes.values().map(|handle| handle.pid).collect()
+    };
+
+    let registry = app
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .clone();
+    let kill = kill.unwrap_or(false);
+    tokio::task::spawn_blocking(move || registry.reap_orphaned_processes(&codex_pids, kill))
+        .await
+        .map_err(|e| AppError::external(format!("Orphan scan task failed: {}", e)))?
+        .map_err(AppError::External)
+}
+
+/// Get all running Claude sessions, including how long each has been without output
 #[tauri::command]
 pub async fn list_running_claude_sessions(
     registry: tauri::State<'_, crate::process::ProcessRegistryState>,
-) -> Result<Vec<crate::process::ProcessInfo>, String> {
-    registry.0.get_running_claude_sessions()
+) -> AppResult<Vec<crate::process::RunningSessionInfo>> {
+    registry
+        .0
+        .get_running_claude_sessions_with_activity(chrono::Utc::now())
+        .map_err(AppError::External)
 }
 
 /// Get live output from a Claude session
@@ -670,26 +1289,260 @@ pub async fn list_running_claude_sessions(
 pub async fn get_claude_session_output(
     registry: tauri::State<'_, crate::process::ProcessRegistryState>,
     session_id: String,
-) -> Result<String, String> {
+) -> AppResult<String> {
     // Find the process by session ID
-    if let Some(process_info) = registry.0.get_claude_session_by_id(&session_id)? {
-        registry.0.get_live_output(process_info.run_id)
+    if let Some(process_info) = registry
+        .0
+        .get_claude_session_by_id(&session_id)
+        .map_err(AppError::External)?
+    {
+        registry
+            .0
+            .get_live_output(process_info.run_id)
+            .map_err(AppError::External)
     } else {
         Ok(String::new())
     }
 }
 
-/// Helper function to check if prompt is a slash command
-/// Slash commands start with '/' and are typically short (like /help, /compact, /clear)
-fn is_slash_command(prompt: &str) -> bool {
-    let trimmed = prompt.trim();
-    trimmed.starts_with('/') && !trimmed.contains('\n') && trimmed.len() < 256
+/// app_settings key holding the live output buffer caps (JSON)
+const LIVE_OUTPUT_LIMITS_SETTING: &str = "live_output_limits";
+
+/// Load persisted live output caps from app_settings
+pub fn load_live_output_limits(app: &AppHandle) -> Option<crate::process::LiveOutputLimits> {
+    load_app_setting(app, LIVE_OUTPUT_LIMITS_SETTING)
+}
+
+/// Get the caps of the live output buffer kept for running sessions
+#[tauri::command]
+pub async fn get_live_output_limits(
+    registry: tauri::State<'_, crate::process::ProcessRegistryState>,
+) -> AppResult<crate::process::LiveOutputLimits> {
+    Ok(registry.0.live_output_limits())
+}
+
+/// Set the caps of the live output buffer (oldest lines are dropped first) and persist them
+#[tauri::command]
+pub async fn set_live_output_limits(
+    app: AppHandle,
+    registry: tauri::State<'_, crate::process::ProcessRegistryState>,
+    max_lines: usize,
+    max_bytes: usize,
+) -> AppResult<crate::process::LiveOutputLimits> {
+    if max_lines == 0 {
+        return Err(AppError::invalid_config("max_lines must be at least 1"));
+    }
+    if max_bytes < 1024 {
+        return Err(AppError::invalid_config("max_bytes must be at least 1024"));
+    }
+
+    let limits = crate::process::LiveOutputLimits {
+        max_lines,
+        max_bytes,
+    };
+    registry
+        .0
+        .set_live_output_limits(limits)
+        .map_err(AppError::External)?;
+    store_app_setting(&app, LIVE_OUTPUT_LIMITS_SETTING, &limits)?;
+
+    log::info!(
+        "Live output limits set to {} lines / {} bytes",
+        max_lines,
+        max_bytes
+    );
+    Ok(limits)
+}
+
+/// Event carrying followed session output, emitted only to the subscribing window
+const SESSION_OUTPUT_STREAM_EVENT: &str = "claude-session-output-stream";
+
+/// A chunk of followed session output
+/// kind: "replay"（订阅时的完整缓冲）/ "line"（新增行）/ "lagged"（跟不上时丢弃的行数）/ "end"（会话结束）
+#[derive(Debug, Clone, serde::Serialize)]
+struct SessionOutputChunk {
+    subscription_id: String,
+    session_id: String,
+    kind: &'static str,
+    data: String,
+}
+
+/// An active output follower bound to a window
+struct OutputSubscription {
+    window_label: String,
+    task: tokio::task::JoinHandle<()>,
+}
+
+static OUTPUT_SUBSCRIPTIONS: Lazy<std::sync::Mutex<HashMap<String, OutputSubscription>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn emit_output_chunk(
+    app: &AppHandle,
+    window_label: &str,
+    chunk: &SessionOutputChunk,
+) -> tauri::Result<()> {
+    app.emit_to(window_label, SESSION_OUTPUT_STREAM_EVENT, chunk)
+}
+
+/// Replay the current output of a running session, then keep streaming new lines
+/// to the calling window until the session ends or the subscription is cancelled
+#[tauri::command]
+pub async fn subscribe_session_output(
+    window: tauri::WebviewWindow,
+    registry: tauri::State<'_, crate::process::ProcessRegistryState>,
+    session_id: String,
+) -> AppResult<String> {
+    let process_info = registry
+        .0
+        .get_claude_session_by_id(&session_id)
+        .map_err(AppError::External)?
+        .ok_or_else(|| AppError::not_found(format!("Session {} is not running", session_id)))?;
+    let (snapshot, mut receiver) = registry
+        .0
+        .subscribe_live_output(process_info.run_id)
+        .map_err(AppError::External)?
+        .ok_or_else(|| AppError::not_found(format!("Session {} is not running", session_id)))?;
+
+    let subscription_id = uuid::Uuid::new_v4().to_string();
+    let window_label = window.label().to_string();
+    let app = window.app_handle().clone();
+
+    emit_output_chunk(
+        &app,
+        &window_label,
+        &SessionOutputChunk {
+            subscription_id: subscription_id.clone(),
+            session_id: session_id.clone(),
+            kind: "replay",
+            data: snapshot,
+        },
+    )
+    .map_err(|e| AppError::external(format!("Failed to emit session output: {}", e)))?;
+
+    // 持有锁直到插入完成，避免任务先结束、再插入一个失效的订阅
+    let mut subscriptions = OUTPUT_SUBSCRIPTIONS.lock().unwrap();
+    let task_subscription_id = subscription_id.clone();
+    let task_window_label = window_label.clone();
+    let task = tokio::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let chunk = |kind: &'static str, data: String| SessionOutputChunk {
+            subscription_id: task_subscription_id.clone(),
+            session_id: session_id.clone(),
+            kind,
+            data,
+        };
+
+        loop {
+            let (next, finished) = match receiver.recv().await {
+                Ok(line) => (chunk("line", line), false),
+                Err(RecvError::Lagged(skipped)) => (chunk("lagged", skipped.to_string()), false),
+                Err(RecvError::Closed) => (chunk("end", String::new()), true),
+            };
+            // 窗口已不存在时停止跟随
+            if emit_output_chunk(&app, &task_window_label, &next).is_err() || finished {
+                break;
+            }
+        }
+
+        OUTPUT_SUBSCRIPTIONS
+            .lock()
+            .unwrap()
+            .remove(&task_subscription_id);
+        log::debug!(
+            "Session output subscription {} finished",
+            task_subscription_id
+        );
+    });
+    subscriptions.insert(
+        subscription_id.clone(),
+        OutputSubscription {
+            window_label: window_label.clone(),
+            task,
+        },
+    );
+
+    log::info!(
+        "Window {} subscribed to session output {} ({})",
+        window_label,
+        process_info.run_id,
+        subscription_id
+    );
+    Ok(subscription_id)
+}
+
+/// Stop following a session's output
+#[tauri::command]
+pub async fn unsubscribe_session_output(subscription_id: String) -> AppResult<bool> {
+    let removed = OUTPUT_SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .remove(&subscription_id);
+    Ok(match removed {
+        Some(subscription) => {
+            subscription.task.abort();
+            true
+        }
+        None => false,
+    })
+}
+
+/// Cancel every output subscription owned by a window (called when the window is destroyed)
+pub fn unsubscribe_window_output(window_label: &str) {
+    let mut subscriptions = OUTPUT_SUBSCRIPTIONS.lock().unwrap();
+    let before = subscriptions.len();
+    subscriptions.retain(|_, subscription| {
+        if subscription.window_label == window_label {
+            subscription.task.abort();
+            false
+        } else {
+            true
+        }
+    });
+    let removed = before - subscriptions.len();
+    if removed > 0 {
+        log::info!(
+            "Cancelled {} session output subscription(s) for closed window {}",
+            removed,
+            window_label
+        );
+    }
+}
+
+/// 斜杠命令改为通过 -p 参数传递；返回是否已按参数传递
+async fn apply_slash_command_flag(
+    cmd: &mut Command,
+    prompt: &str,
+    project_path: &str,
+    app_approval: bool,
+    execution_config: &ClaudeExecutionConfig,
+) -> bool {
+    // 🔥 关键修复：检测斜杠命令，通过 -p 参数传递以触发命令解析
+    // Claude CLI 只在 -p 参数中解析斜杠命令，stdin 管道不会触发
+    // 应用审批模式下 prompt 统一经 stream-json 写入 stdin
+    // 多行 / 较长的 prompt 只有首个 /word 是已知命令时才按命令处理
+    let use_p_flag = if app_approval || !prompt.trim_start().starts_with('/') {
+        false
+    } else {
+        let known_commands =
+            known_slash_command_names(Some(project_path.to_string()), execution_config).await;
+        is_slash_command(prompt, &known_commands)
+    };
+    if use_p_flag {
+        log::info!("Detected slash command, using -p flag: {}", prompt.trim());
+        cmd.arg("-p");
+        cmd.arg(prompt);
+    }
+    use_p_flag
 }
 
 /// Helper function to spawn Claude process and handle streaming
 /// 🔥 修复：斜杠命令通过 -p 参数传递（触发命令解析），普通 prompt 通过 stdin 管道传递
 /// 这样既支持斜杠命令，又避免操作系统命令行长度限制（Windows ~8KB, Linux/macOS ~128KB-2MB）
 /// 🔒 CRITICAL FIX: 添加 tab_id 参数，用于全局事件中标识消息来源，解决新建会话并发时的消息串扰
+/// `retry_attempt` 为限流自动重试的次数，首次执行为 0
+/// `attachments` 持有图片附件的临时目录，进程退出（且不再重试）后释放并删除
+#[allow(clippy::too_many_arguments)]
 async fn spawn_claude_process(
     app: AppHandle,
     mut cmd: Command,
@@ -697,27 +1550,123 @@ async fn spawn_claude_process(
     model: String,
     project_path: String,
     tab_id: Option<String>,
-) -> Result<(), String> {
-    use std::sync::Mutex;
+    app_approval: bool,
+    retry_attempt: u32,
+    attachments: StagedAttachments,
+) -> AppResult<()> {
     use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
-    // 🔥 关键修复：检测斜杠命令，通过 -p 参数传递以触发命令解析
-    // Claude CLI 只在 -p 参数中解析斜杠命令，stdin 管道不会触发
-    let use_p_flag = is_slash_command(&prompt);
-    if use_p_flag {
-        log::info!("Detected slash command, using -p flag: {}", prompt.trim());
-        cmd.arg("-p");
-        cmd.arg(&prompt);
+    let execution_config = get_claude_execution_config(app.clone(), Some(project_path.clone()))
+        .await
+        .unwrap_or_else(|_| ClaudeExecutionConfig::default());
+    // 限流自动重试需要原样重新启动，在追加 -p 参数之前复制命令
+    let retry = run_output::RateLimitRetry::new(
+        execution_config.rate_limit_retry,
+        retry_attempt,
+        &cmd,
+        app_approval,
+    );
+
+    let use_p_flag = apply_slash_command_flag(
+        &mut cmd,
+        &prompt,
+        &project_path,
+        app_approval,
+        &execution_config,
+    )
+    .await;
+
+    // pty 模式：CLI 认为自己连接在终端上；应用内审批需要通过 stdin 交换消息，仍使用管道
+    if execution_config.use_pty {
+        if app_approval {
+            log::info!("In-app approval needs piped stdin, ignoring pty mode");
+        } else {
+            #[cfg(feature = "pty")]
+            if super::pty_runner::prompt_fits_pty(&prompt, use_p_flag) {
+                return super::pty_runner::spawn_claude_pty(
+                    app,
+                    cmd,
+                    prompt,
+                    use_p_flag,
+                    model,
+                    project_path,
+                    tab_id,
+                    attachments,
+                    &execution_config,
+                    retry,
+                )
+                .await;
+            } else {
+                log::info!("Prompt is too long to pass to a pty, using pipes");
+            }
+            #[cfg(not(feature = "pty"))]
+            log::warn!("pty mode requested but this build has no `pty` feature, using pipes");
+        }
     }
 
+    // 记录最终的启动命令（含 build_execution_args 生成的参数），随 started 事件一起发送
+    let launch = platform::LaunchCommand::from_command(&cmd);
+
+    // 工作目录与项目路径不同时，在拿到会话 ID 后记录下来供恢复会话使用
+    let working_dir = cmd
+        .as_std()
+        .get_current_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .filter(|dir| *dir != project_path);
+    log::info!(
+        "Claude launch command: {} {:?}",
+        launch.binary,
+        launch.args_for_log(&prompt)
+    );
+
+    // 并发限制：持有名额直到进程退出（取消时进程被杀，同样会释放）
+    let resume_session_id = launch
+        .args
+        .iter()
+        .position(|arg| arg == "--resume")
+        .and_then(|index| launch.args.get(index + 1).cloned());
+    let session_permit = crate::commands::session_limits::acquire_session_permit(
+        &app,
+        "claude",
+        &project_path,
+        resume_session_id,
+        tab_id.clone(),
+    )
+    .await?;
+
     // Spawn the process
     let mut child = cmd
         .spawn()
-        .map_err(|e| format!("Failed to spawn Claude: {}", e))?;
+        .map_err(|e| AppError::from_io("Failed to spawn Claude", e))?;
 
     // 🔥 普通 prompt 通过 stdin 管道传递，避免命令行长度限制
     // 斜杠命令已通过 -p 参数传递，不需要 stdin
-    if !use_p_flag {
+    let mut approval_stdin: Option<permission_prompt::SharedStdin> = None;
+    if app_approval {
+        // 应用审批模式：prompt 作为 stream-json 用户消息写入，stdin 保持打开以回复权限请求
+        let stdin = child.stdin.take();
+        if stdin.is_none() {
+            log::warn!("Failed to get stdin handle, prompt may not be sent");
+        }
+        let shared_stdin = Arc::new(tokio::sync::Mutex::new(stdin));
+        let user_message = serde_json::json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{ "type": "text", "text": prompt }],
+            },
+        });
+        let stdin_for_prompt = shared_stdin.clone();
+        tokio::spawn(async move {
+            if let Some(stdin) = stdin_for_prompt.lock().await.as_mut() {
+                let line = format!("{}\n", user_message);
+                if let Err(e) = stdin.write_all(line.as_bytes()).await {
+                    log::error!("Failed to write prompt message to stdin: {}", e);
+                }
+            }
+        });
+        approval_stdin = Some(shared_stdin);
+    } else if !use_p_flag {
         if let Some(mut stdin) = child.stdin.take() {
             // 克隆 prompt 以便在 async 块中使用（避免生命周期问题）
             let prompt_for_stdin = prompt.clone();
@@ -751,8 +1700,14 @@ async fn spawn_claude_process(
     }
 
     // Get stdout and stderr
-    let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::io("Failed to get stdout"))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| AppError::io("Failed to get stderr"))?;
 
     // Get the child PID for logging
     let pid = child.id().unwrap_or(0);
@@ -789,16 +1744,21 @@ async fn spawn_claude_process(
         None
     };
 
-    // Create readers first (before moving child)
-    let stdout_reader = BufReader::new(stdout);
-    let stderr_reader = BufReader::new(stderr);
-
-    // We'll extract the session ID from Claude's init message
-    let session_id_holder: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    let run_id_holder: Arc<Mutex<Option<i64>>> = Arc::new(Mutex::new(None));
+    let run = run_output::ClaudeRun::new(
+        &app,
+        pid,
+        launch,
+        &execution_config,
+        project_path.clone(),
+        prompt,
+        model,
+        tab_id.clone(),
+        working_dir,
+    )
+    .with_approval_stdin(approval_stdin);
     #[cfg(windows)]
-    let job_object_holder: Arc<std::sync::Mutex<Option<Arc<JobObject>>>> =
-        Arc::new(std::sync::Mutex::new(job_object));
+    let run = run.with_job_object(job_object);
+    let run = Arc::new(run);
 
     // 🔒 CRITICAL FIX: 不再使用全局 ClaudeProcessState 管理进程生命周期
     // 原因：全局单例只能存储一个 child，多会话并发时会互相覆盖
@@ -820,271 +1780,38 @@ async fn spawn_claude_process(
         *last_pid = Some(pid);
     }
 
-    // Check if auto-compact state is available
-    let auto_compact_available = app
-        .try_state::<crate::commands::context_manager::AutoCompactState>()
-        .is_some();
-
     // Spawn tasks to read stdout and stderr
-    let app_handle = app.clone();
-    let session_id_holder_clone = session_id_holder.clone();
-    let run_id_holder_clone = run_id_holder.clone();
-    let registry = app.state::<crate::process::ProcessRegistryState>();
-    let registry_clone = registry.0.clone();
-    let project_path_clone = project_path.clone();
-    let prompt_clone = prompt.clone();
-    let model_clone = model.clone();
-    // 🔒 CRITICAL FIX: 克隆 tab_id 用于事件发送
-    let tab_id_for_stdout = tab_id.clone();
-    // 🔧 FIX: Clone job_object_holder for passing to register_claude_session
-    #[cfg(windows)]
-    let job_object_holder_clone = job_object_holder.clone();
+    let run_for_stdout = run.clone();
     let stdout_task = tokio::spawn(async move {
-        let mut lines = stdout_reader.lines();
+        let mut lines = BufReader::new(stdout).lines();
         while let Ok(Some(line)) = lines.next_line().await {
-            // Use trace level to avoid flooding logs in debug mode
-            log::trace!("Claude stdout: {}", line);
-
-            // Parse the line to check for init message with session ID
-            if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
-                if msg["type"] == "system" && msg["subtype"] == "init" {
-                    if let Some(claude_session_id) = msg["session_id"].as_str() {
-                        let mut session_id_guard = session_id_holder_clone.lock().unwrap();
-                        if session_id_guard.is_none() {
-                            *session_id_guard = Some(claude_session_id.to_string());
-                            log::info!("Extracted Claude session ID: {}", claude_session_id);
-
-                            // Register with auto-compact manager
-                            if auto_compact_available {
-                                if let Some(auto_compact_state) = app_handle.try_state::<crate::commands::context_manager::AutoCompactState>() {
-                                    if let Err(e) = auto_compact_state.0.register_session(
-                                    claude_session_id.to_string(),
-                                    project_path_clone.clone(),
-                                    model_clone.clone(),
-                                ) {
-                                    log::warn!("Failed to register session with auto-compact manager: {}", e);
-                                }
-                                }
-                            }
-
-                            // Now register with ProcessRegistry using Claude's session ID
-                            // 🔧 FIX: Pass the pre-created Job Object to avoid orphan processes
-                            #[cfg(windows)]
-                            let job_object_for_register = job_object_holder_clone
-                                .lock()
-                                .unwrap()
-                                .take();
-                            #[cfg(not(windows))]
-                            let job_object_for_register: Option<()> = None;
-
-                            match registry_clone.register_claude_session_with_job(
-                                claude_session_id.to_string(),
-                                pid,
-                                project_path_clone.clone(),
-                                prompt_clone.clone(),
-                                model_clone.clone(),
-                                job_object_for_register,
-                            ) {
-                                Ok(run_id) => {
-                                    log::info!("Registered Claude session with run_id: {}", run_id);
-                                    let mut run_id_guard = run_id_holder_clone.lock().unwrap();
-                                    *run_id_guard = Some(run_id);
-
-                                    // ✨ Phase 2: Emit event for real-time session tracking
-                                    let event_payload = serde_json::json!({
-                                        "session_id": claude_session_id,
-                                        "project_path": project_path_clone,
-                                        "model": model_clone,
-                                        "status": "started",
-                                        "pid": pid,
-                                        "run_id": run_id,
-                                    });
-                                    if let Err(e) =
-                                        app_handle.emit("claude-session-state", &event_payload)
-                                    {
-                                        log::warn!(
-                                            "Failed to emit claude-session-state event: {}",
-                                            e
-                                        );
-                                    } else {
-                                        log::info!(
-                                            "Emitted claude-session-started event for session: {}",
-                                            claude_session_id
-                                        );
-                                    }
-
-                                    log::info!(
-                                        "Claude CLI will handle project creation for session: {}",
-                                        claude_session_id
-                                    );
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to register Claude session: {}", e);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // Check for usage information and update context tracking
-                if let Some(usage) = msg.get("usage") {
-                    if let (Some(input_tokens), Some(output_tokens)) = (
-                        usage.get("input_tokens").and_then(|t| t.as_u64()),
-                        usage.get("output_tokens").and_then(|t| t.as_u64()),
-                    ) {
-                        let total_tokens = (input_tokens + output_tokens) as usize;
-
-                        // Extract cache tokens if available
-                        let _cache_creation_tokens = usage
-                            .get("cache_creation_input_tokens")
-                            .and_then(|t| t.as_u64());
-                        let _cache_read_tokens = usage
-                            .get("cache_read_input_tokens")
-                            .and_then(|t| t.as_u64());
-
-                        // Store usage data in database for real-time token statistics
-                        let session_id_for_update =
-                            { session_id_holder_clone.lock().unwrap().as_ref().cloned() };
-
-                        if let Some(session_id_str) = &session_id_for_update {
-                            // Agent database functionality removed - usage tracking disabled
-
-                            // Update auto-compact manager with token count
-                            if auto_compact_available {
-                                if let Some(auto_compact_state) = app_handle.try_state::<crate::commands::context_manager::AutoCompactState>() {
-                                    let auto_compact_state_clone = auto_compact_state.inner().clone();
-                                    let session_id_for_compact = session_id_str.clone();
-
-                                    // Spawn async task to avoid blocking main output loop
-                                    tokio::spawn(async move {
-                                        match auto_compact_state_clone.0.update_session_tokens(&session_id_for_compact, total_tokens).await {
-                                            Ok(compaction_triggered) => {
-                                                if compaction_triggered {
-                                                    log::info!("Auto-compaction triggered for session {}", session_id_for_compact);
-                                                    // The actual compaction will be handled by the background monitoring thread
-                                                }
-                                            }
-                                            Err(e) => {
-                                                log::warn!("Failed to update session tokens for auto-compact: {}", e);
-                                            }
-                                        }
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            // Store live output in registry if we have a run_id
-            if let Some(run_id) = *run_id_holder_clone.lock().unwrap() {
-                let _ = registry_clone.append_live_output(run_id, &line);
-            }
-
-            // Emit the line to the frontend with session isolation if we have session ID
-            if let Some(ref session_id) = *session_id_holder_clone.lock().unwrap() {
-                let _ = app_handle.emit(&format!("claude-output:{}", session_id), &line);
-            }
-            // 🔒 CRITICAL FIX: 全局事件包含 tab_id，用于前端过滤新建会话的消息
-            let global_payload = serde_json::json!({
-                "tab_id": tab_id_for_stdout,
-                "payload": &line
-            });
-            let _ = app_handle.emit("claude-output", &global_payload);
+            run_for_stdout.handle_stdout_line(line);
         }
     });
-
-    let app_handle_stderr = app.clone();
-    let session_id_holder_clone2 = session_id_holder.clone();
-    // 🔒 CRITICAL FIX: 克隆 tab_id 用于 stderr 事件
-    let tab_id_for_stderr = tab_id.clone();
+    let run_for_stderr = run.clone();
     let stderr_task = tokio::spawn(async move {
-        let mut lines = stderr_reader.lines();
+        let mut lines = BufReader::new(stderr).lines();
         while let Ok(Some(line)) = lines.next_line().await {
-            log::error!("Claude stderr: {}", line);
-            // Emit error lines to the frontend with session isolation if we have session ID
-            if let Some(ref session_id) = *session_id_holder_clone2.lock().unwrap() {
-                let _ = app_handle_stderr.emit(&format!("claude-error:{}", session_id), &line);
-            }
-            // 🔒 CRITICAL FIX: 全局事件包含 tab_id
-            let global_payload = serde_json::json!({
-                "tab_id": tab_id_for_stderr,
-                "payload": &line
-            });
-            let _ = app_handle_stderr.emit("claude-error", &global_payload);
+            run_for_stderr.handle_stderr_line(line);
         }
     });
 
     // Wait for the process to complete
     // 🔒 CRITICAL FIX: 直接将 child 移动到 wait task 中，而不是从全局 state 取出
     // 这样每个进程独立管理自己的生命周期，支持真正的多会话并发
-    let app_handle_wait = app.clone();
-    let session_id_holder_clone3 = session_id_holder.clone();
-    let run_id_holder_clone2 = run_id_holder.clone();
-    let registry_clone2 = registry.0.clone();
     let last_spawned_pid = claude_state.last_spawned_pid.clone();
-    // 🔒 CRITICAL FIX: 克隆 tab_id 用于 complete 事件
-    let tab_id_for_complete = tab_id;
     tokio::spawn(async move {
         let _ = stdout_task.await;
         let _ = stderr_task.await;
 
-        // 🔒 CRITICAL FIX: 直接等待 child，不再从全局 state 取出
-        // child 已经被移动到这个 async block 中
-        match child.wait().await {
-            Ok(status) => {
-                log::info!("Claude process exited with status: {}", status);
-                // Add a small delay to ensure all messages are processed
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                    // ✨ Phase 2: Emit state change event
-                    let event_payload = serde_json::json!({
-                        "session_id": session_id,
-                        "status": "stopped",
-                        "success": status.success(),
-                    });
-                    let _ = app_handle_wait.emit("claude-session-state", &event_payload);
-
-                    let _ = app_handle_wait
-                        .emit(&format!("claude-complete:{}", session_id), status.success());
-                }
-                // 🔒 CRITICAL FIX: 全局事件包含 tab_id
-                let global_payload = serde_json::json!({
-                    "tab_id": tab_id_for_complete,
-                    "payload": status.success()
-                });
-                let _ = app_handle_wait.emit("claude-complete", &global_payload);
-            }
-            Err(e) => {
-                log::error!("Failed to wait for Claude process: {}", e);
-                // Add a small delay to ensure all messages are processed
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
-                    // ✨ Phase 2: Emit state change event for error case
-                    let event_payload = serde_json::json!({
-                        "session_id": session_id,
-                        "status": "stopped",
-                        "success": false,
-                        "error": e.to_string(),
-                    });
-                    let _ = app_handle_wait.emit("claude-session-state", &event_payload);
-
-                    let _ =
-                        app_handle_wait.emit(&format!("claude-complete:{}", session_id), false);
-                }
-                // 🔒 CRITICAL FIX: 全局事件包含 tab_id
-                let global_payload = serde_json::json!({
-                    "tab_id": tab_id_for_complete,
-                    "payload": false
-                });
-                let _ = app_handle_wait.emit("claude-complete", &global_payload);
-            }
-        }
-
-        // Unregister from ProcessRegistry if we have a run_id
-        if let Some(run_id) = *run_id_holder_clone2.lock().unwrap() {
-            let _ = registry_clone2.unregister_process(run_id);
-        }
+        let exit = child
+            .wait()
+            .await
+            .map(|status| run_output::ProcessExit {
+                success: status.success(),
+                status: status.to_string(),
+            })
+            .map_err(|e| e.to_string());
 
         if pid != 0 {
             let mut last_pid = last_spawned_pid.lock().await;
@@ -1092,7 +1819,210 @@ async fn spawn_claude_process(
                 *last_pid = None;
             }
         }
+
+        run.finish(exit, retry, attachments, session_permit).await;
     });
 
     Ok(())
 }
+
+/// 限流后重新执行同一请求；返回装箱的 Future 以打断 `spawn_claude_process` 的递归类型
+#[allow(clippy::too_many_arguments)]
+pub(super) fn retry_claude_process(
+    app: AppHandle,
+    cmd: Command,
+    prompt: String,
+    model: String,
+    project_path: String,
+    tab_id: Option<String>,
+    app_approval: bool,
+    retry_attempt: u32,
+    attachments: StagedAttachments,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = AppResult<()>> + Send>> {
+    Box::pin(spawn_claude_process(
+        app,
+        cmd,
+        prompt,
+        model,
+        project_path,
+        tab_id,
+        app_approval,
+        retry_attempt,
+        attachments,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_session_resume_reports_how_the_run_was_started() {
+        let resumed = LastSessionResume::Resumed {
+            session_id: "abc".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&resumed).unwrap(),
+            serde_json::json!({ "kind": "resumed", "sessionId": "abc" })
+        );
+        assert_eq!(
+            serde_json::to_value(LastSessionResume::Continued).unwrap(),
+            serde_json::json!({ "kind": "continued" })
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_or_rejected_resume_does_not_fall_back_to_continue() {
+        use crate::process::concurrency::SessionLimiter;
+        use crate::process::SessionLimits;
+
+        let limiter = Arc::new(SessionLimiter::new(SessionLimits {
+            max_concurrent_sessions: 1,
+            queue_when_full: true,
+        }));
+        let _running = limiter.acquire("claude", "/p", None, None).await.unwrap();
+        let queued_resume = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                limiter
+                    .acquire("claude", "/p", Some("abc".to_string()), None)
+                    .await
+            })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(limiter.cancel_queued(None, Some("abc")));
+        let cancelled = queued_resume.await.unwrap().err().unwrap();
+        assert!(!falls_back_to_continue(&cancelled));
+
+        limiter
+            .set_limits(SessionLimits {
+                max_concurrent_sessions: 1,
+                queue_when_full: false,
+            })
+            .unwrap();
+        let rejected = limiter
+            .acquire("claude", "/p", Some("abc".to_string()), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(!falls_back_to_continue(&rejected));
+
+        // 真正的启动失败仍改用 -c 继续
+        let spawn_failed = AppError::from_io(
+            "Failed to spawn Claude",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "missing"),
+        );
+        assert!(falls_back_to_continue(&spawn_failed));
+    }
+
+    #[test]
+    fn user_aliases_take_precedence_over_builtin_mapping() {
+        let aliases = BTreeMap::from([
+            ("sonnet".to_string(), "claude-sonnet-4-5".to_string()),
+            ("fast".to_string(), "sonnet1m".to_string()),
+        ]);
+
+        // 用户别名覆盖同名内置别名
+        assert_eq!(
+            map_model_to_claude_alias("sonnet", &aliases),
+            "claude-sonnet-4-5"
+        );
+        // 用户别名解析后的结果仍会经过内置映射
+        assert_eq!(map_model_to_claude_alias("fast", &aliases), "sonnet[1m]");
+        // 没有用户别名时使用内置映射，未知模型原样透传
+        assert_eq!(
+            map_model_to_claude_alias("sonnet1m", &aliases),
+            "sonnet[1m]"
+        );
+        assert_eq!(map_model_to_claude_alias("opus", &BTreeMap::new()), "opus");
+        assert_eq!(
+            map_model_to_claude_alias("claude-opus-4-1", &BTreeMap::new()),
+            "claude-opus-4-1"
+        );
+    }
+
+    #[test]
+    fn most_recent_session_uses_last_message_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let write_session = |name: &str, timestamps: &[&str]| {
+            let lines: Vec<String> = timestamps
+                .iter()
+                .map(|ts| {
+                    serde_json::json!({
+                        "type": "user",
+                        "timestamp": ts,
+                        "message": { "role": "user", "content": "hi" }
+                    })
+                    .to_string()
+                })
+                .collect();
+            std::fs::write(dir.path().join(name), lines.join("\n")).unwrap();
+        };
+
+        // 字符串比较时 +08:00 的时间更大，实际时间（02:00Z）却更早
+        write_session("older.jsonl", &["2025-01-01T10:00:00+08:00"]);
+        write_session(
+            "newer.jsonl",
+            &["2024-12-31T00:00:00Z", "2025-01-01T03:00:00Z"],
+        );
+        // 子代理会话和没有时间戳的会话不参与比较
+        write_session("agent-1234.jsonl", &["2025-06-01T00:00:00Z"]);
+        std::fs::write(dir.path().join("empty.jsonl"), "").unwrap();
+
+        assert_eq!(most_recent_session_in(dir.path()).as_deref(), Some("newer"));
+        assert_eq!(most_recent_session_in(&dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn request_thinking_budget_is_set_on_the_child_env() {
+        let project = tempfile::tempdir().unwrap();
+        let project_path = project.path().to_string_lossy().into_owned();
+        let settings_path = dirs::home_dir().map(|home| home.join(".claude").join("settings.json"));
+        let read_settings = || {
+            settings_path
+                .as_ref()
+                .and_then(|path| std::fs::read(path).ok())
+        };
+        let settings_before = read_settings();
+
+        let thinking_env = |cmd: &Command| {
+            cmd.as_std()
+                .get_envs()
+                .find(|(key, _)| *key == "MAX_THINKING_TOKENS")
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_string_lossy().into_owned())
+        };
+
+        let cmd = create_system_command(
+            "claude",
+            Vec::new(),
+            &project_path,
+            &project_path,
+            None,
+            Some(8000),
+        )
+        .unwrap();
+        assert_eq!(thinking_env(&cmd).as_deref(), Some("8000"));
+        assert!(!cmd
+            .as_std()
+            .get_args()
+            .any(|arg| arg == "--max-thinking-tokens"));
+
+        // 没有请求值时保留 settings.json env 中的设置（如果有）
+        let cmd = create_system_command(
+            "claude",
+            Vec::new(),
+            &project_path,
+            &project_path,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            thinking_env(&cmd),
+            thinking_env(&create_command_with_env("claude", Some(&project_path)))
+        );
+
+        assert_eq!(read_settings(), settings_before);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -12,13 +13,21 @@ use serde::Serialize;
 use tokio::sync::OnceCell;
 
 use super::super::wsl_utils;
-use super::paths::{get_claude_dir, get_codex_dir};
+use super::dangerous_skip::record_setting_change;
+use super::file_ops::is_skipped_dir;
+use super::paths::{get_claude_dir, get_codex_dir, normalize_path_for_comparison};
 use super::platform;
 use super::{ClaudeMdFile, ClaudeSettings, ClaudeVersionStatus};
+use crate::claude_binary::ClaudeInstallation;
 use crate::commands::permission_config::{
+    check_tool_names, export_config_blob, known_claude_tools, merge_json, migrate_exported_config,
     ClaudeExecutionConfig, ClaudePermissionConfig, PermissionMode, ALL_TOOLS, DEVELOPMENT_TOOLS,
     SAFE_TOOLS,
 };
+use crate::commands::storage::{
+    load_app_setting, read_app_setting, store_app_setting, write_app_setting,
+};
+use crate::error::AppResult;
 
 #[tauri::command]
 pub async fn get_claude_settings() -> Result<ClaudeSettings, String> {
@@ -119,7 +128,7 @@ pub async fn check_claude_version(app: AppHandle) -> Result<ClaudeVersionStatus,
             return Ok(ClaudeVersionStatus {
                 is_installed: false,
                 version: None,
-                output: e,
+                output: e.to_string(),
             });
         }
     };
@@ -361,6 +370,9 @@ pub async fn save_claude_settings(settings: serde_json::Value) -> Result<String,
 }
 
 /// Updates the thinking mode in settings.json by modifying the MAX_THINKING_TOKENS env variable
+///
+/// This is the persistent global default; a `max_thinking_tokens` passed to execute/continue/resume
+/// takes precedence for that request only and does not touch settings.json
 #[tauri::command]
 pub async fn update_thinking_mode(enabled: bool, tokens: Option<u32>) -> Result<String, String> {
     log::info!(
@@ -475,10 +487,7 @@ fn find_claude_md_recursive(
         if path.is_dir() {
             // Skip common directories that shouldn't be searched
             if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                if matches!(
-                    dir_name,
-                    "node_modules" | "target" | ".git" | "dist" | "build" | ".next" | "__pycache__"
-                ) {
+                if is_skipped_dir(dir_name) {
                     continue;
                 }
             }
@@ -531,6 +540,49 @@ pub async fn read_claude_md_file(file_path: String) -> Result<String, String> {
     fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))
 }
 
+/// Concatenates the user-level ~/.claude/CLAUDE.md and the project's CLAUDE.md files in the order Claude applies them (user, project root, then nested), with source headers
+#[tauri::command]
+pub async fn preview_merged_claude_md(project_path: String) -> Result<String, String> {
+    let user_claude_md = get_claude_dir().ok().map(|dir| dir.join("CLAUDE.md"));
+    merge_claude_md_files(user_claude_md, project_path).await
+}
+
+/// 合并 CLAUDE.md：用户级文件最先应用（存在时），其后为项目内的文件
+async fn merge_claude_md_files(
+    user_claude_md: Option<PathBuf>,
+    project_path: String,
+) -> Result<String, String> {
+    let mut sections = Vec::new();
+    if let Some(path) = user_claude_md.filter(|path| path.is_file()) {
+        let content = read_claude_md_file(path.to_string_lossy().to_string()).await?;
+        sections.push(format!(
+            "<!-- Source: ~/.claude/CLAUDE.md -->\n\n{}",
+            content.trim_end()
+        ));
+    }
+
+    let mut files = find_claude_md_files(project_path).await?;
+    // 按目录深度排序：根目录的 CLAUDE.md 最先应用，越深的越靠后
+    files.sort_by_key(|file| {
+        (
+            std::path::Path::new(&file.relative_path)
+                .components()
+                .count(),
+            file.relative_path.clone(),
+        )
+    });
+
+    for file in files {
+        let content = read_claude_md_file(file.absolute_path).await?;
+        sections.push(format!(
+            "<!-- Source: {} -->\n\n{}",
+            file.relative_path.replace('\\', "/"),
+            content.trim_end()
+        ));
+    }
+    Ok(sections.join("\n\n"))
+}
+
 /// Saves a specific CLAUDE.md file by its absolute path
 #[tauri::command]
 pub async fn save_claude_md_file(file_path: String, content: String) -> Result<String, String> {
@@ -597,16 +649,6 @@ pub async fn set_custom_claude_path(app: AppHandle, custom_path: String) -> Resu
         let db_path = app_data_dir.join("agents.db");
         match rusqlite::Connection::open(&db_path) {
             Ok(conn) => {
-                if let Err(e) = conn.execute(
-                    "CREATE TABLE IF NOT EXISTS app_settings (
-                        key TEXT PRIMARY KEY,
-                        value TEXT NOT NULL
-                    )",
-                    [],
-                ) {
-                    return Err(format!("Failed to create settings table: {}", e));
-                }
-
                 if let Err(e) = conn.execute(
                     "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
                     rusqlite::params!["claude_binary_path", path_str],
@@ -632,7 +674,7 @@ pub async fn set_custom_claude_path(app: AppHandle, custom_path: String) -> Resu
 
 /// Get current Claude CLI path (custom or auto-detected)
 #[tauri::command]
-pub async fn get_claude_path(app: AppHandle) -> Result<String, String> {
+pub async fn get_claude_path(app: AppHandle) -> AppResult<String> {
     log::info!("Getting current Claude CLI path");
 
     // Try to get from database first
@@ -662,6 +704,19 @@ pub async fn get_claude_path(app: AppHandle) -> Result<String, String> {
     }
 }
 
+/// Get the selected Claude CLI installation (path, version, source and installation type)
+#[tauri::command]
+pub async fn get_claude_binary_info(app: AppHandle) -> AppResult<ClaudeInstallation> {
+    let installation = crate::claude_binary::find_claude_installation(&app)?;
+    log::info!(
+        "Claude CLI installation: {} ({}, {:?})",
+        installation.path,
+        installation.source,
+        installation.version
+    );
+    Ok(installation)
+}
+
 /// Clear custom Claude CLI path and revert to auto-detection
 #[tauri::command]
 pub async fn clear_custom_claude_path(app: AppHandle) -> Result<(), String> {
@@ -692,6 +747,82 @@ pub async fn clear_custom_claude_path(app: AppHandle) -> Result<(), String> {
     Err("Failed to get app data directory".to_string())
 }
 
+/// 未配置任何默认模型时使用的兜底模型
+pub(crate) const FALLBACK_MODEL: &str = "sonnet";
+
+/// 从 app_settings 读取全局默认模型（未设置时返回 None）
+pub(crate) fn load_default_model(app: &AppHandle) -> Option<String> {
+    read_app_setting(app, "default_model")
+}
+
+/// Set the global default model used when the frontend passes an empty model
+#[tauri::command]
+pub async fn set_default_model(app: AppHandle, model: String) -> Result<(), String> {
+    let model = model.trim().to_string();
+    log::info!("Setting global default model: {:?}", model);
+
+    // 空字符串表示清除全局默认模型
+    write_app_setting(
+        &app,
+        "default_model",
+        Some(model.as_str()).filter(|m| !m.is_empty()),
+    )?;
+    Ok(())
+}
+
+/// Get the global default model (falls back to "sonnet" when unset)
+#[tauri::command]
+pub async fn get_default_model(app: AppHandle) -> Result<String, String> {
+    Ok(load_default_model(&app).unwrap_or_else(|| FALLBACK_MODEL.to_string()))
+}
+
+/// 从 app_settings 读取用户定义的模型别名（别名 -> 模型 ID），未设置或无法读取时为空
+pub(crate) fn load_model_aliases(app: &AppHandle) -> BTreeMap<String, String> {
+    load_app_setting(app, "model_aliases").unwrap_or_default()
+}
+
+/// 去掉首尾空白，拒绝空的别名或模型 ID
+fn normalize_model_aliases(
+    aliases: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    aliases
+        .into_iter()
+        .map(|(alias, model)| {
+            let (alias, model) = (alias.trim().to_string(), model.trim().to_string());
+            if alias.is_empty() || model.is_empty() {
+                return Err(format!(
+                    "Model alias and model id must not be empty: {:?} -> {:?}",
+                    alias, model
+                ));
+            }
+            Ok((alias, model))
+        })
+        .collect()
+}
+
+/// Get the user-defined model aliases (alias -> model id), consulted before the built-in aliases
+#[tauri::command]
+pub async fn get_model_aliases(app: AppHandle) -> Result<BTreeMap<String, String>, String> {
+    Ok(load_model_aliases(&app))
+}
+
+/// Replace the user-defined model aliases; an empty map removes them
+#[tauri::command]
+pub async fn set_model_aliases(
+    app: AppHandle,
+    aliases: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let aliases = normalize_model_aliases(aliases)?;
+    log::info!("Setting {} model alias(es)", aliases.len());
+
+    if aliases.is_empty() {
+        write_app_setting(&app, "model_aliases", None)?;
+    } else {
+        store_app_setting(&app, "model_aliases", &aliases)?;
+    }
+    Ok(aliases)
+}
+
 fn expand_user_path(input: &str) -> Result<PathBuf, String> {
     if input.trim().is_empty() {
         return Err("Path is empty".to_string());
@@ -787,39 +918,265 @@ fn clear_binary_override(tool: &str) -> Result<(), String> {
 
     Ok(())
 }
-/// 获取当前Claude执行配置
+/// 获取当前Claude执行配置；传入 project_path 时优先使用该项目的执行配置覆盖
 #[tauri::command]
-pub async fn get_claude_execution_config(_app: AppHandle) -> Result<ClaudeExecutionConfig, String> {
+pub async fn get_claude_execution_config(
+    _app: AppHandle,
+    project_path: Option<String>,
+) -> Result<ClaudeExecutionConfig, String> {
+    resolve_execution_config(project_path.as_deref()).map(|effective| effective.config)
+}
+
+/// 实际生效的执行配置及其来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveExecutionConfig {
+    /// "project"（项目级覆盖）或 "global"（execution_config.json）
+    pub source: String,
+    pub project_path: Option<String>,
+    pub config: ClaudeExecutionConfig,
+}
+
+/// 解析执行配置：项目级覆盖 → 全局配置
+pub(crate) fn resolve_execution_config(
+    project_path: Option<&str>,
+) -> Result<EffectiveExecutionConfig, String> {
+    if let Some(project_path) = project_path.filter(|path| !path.trim().is_empty()) {
+        let key = normalize_path_for_comparison(project_path);
+        if let Some(config) = load_project_execution_configs()?.remove(&key) {
+            log::debug!(
+                "Using project execution config override for {}",
+                project_path
+            );
+            return Ok(EffectiveExecutionConfig {
+                source: "project".to_string(),
+                project_path: Some(project_path.to_string()),
+                config,
+            });
+        }
+    }
+
+    // 使用通用配置加载工具
+    Ok(EffectiveExecutionConfig {
+        source: "global".to_string(),
+        project_path: project_path.map(str::to_string),
+        config: crate::utils::config_utils::load_json_config(execution_config_path()?)?,
+    })
+}
+
+/// 项目级执行配置覆盖文件（键为规范化后的项目路径）
+fn project_execution_configs_path() -> Result<PathBuf, String> {
     let claude_dir =
         get_claude_dir().map_err(|e| format!("Failed to get Claude directory: {}", e))?;
-    let config_file = claude_dir.join("execution_config.json");
+    Ok(claude_dir.join("project_execution_configs.json"))
+}
 
-    // 使用通用配置加载工具
-    crate::utils::config_utils::load_json_config(&config_file)
+fn load_project_execution_configs() -> Result<HashMap<String, ClaudeExecutionConfig>, String> {
+    crate::utils::config_utils::load_json_config(project_execution_configs_path()?)
 }
 
-/// 更新Claude执行配置
-#[tauri::command]
-pub async fn update_claude_execution_config(
-    _app: AppHandle,
-    config: ClaudeExecutionConfig,
+fn save_project_execution_configs(
+    configs: &HashMap<String, ClaudeExecutionConfig>,
 ) -> Result<(), String> {
+    crate::utils::config_utils::save_json_config(configs, project_execution_configs_path()?)
+}
+
+/// 危险跳过保护触发时关闭项目级覆盖中的危险跳过模式；返回是否有修改
+pub(super) fn disable_project_dangerous_skip(project_path: &str) -> Result<bool, String> {
+    let key = normalize_path_for_comparison(project_path);
+    let mut configs = load_project_execution_configs()?;
+    match configs.get_mut(&key) {
+        Some(config) if config.permissions.enable_dangerous_skip => {
+            config.permissions.enable_dangerous_skip = false;
+            save_project_execution_configs(&configs)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// 执行配置文件路径
+pub(super) fn execution_config_path() -> Result<PathBuf, String> {
     let claude_dir =
         get_claude_dir().map_err(|e| format!("Failed to get Claude directory: {}", e))?;
-    let config_file = claude_dir.join("execution_config.json");
+    Ok(claude_dir.join("execution_config.json"))
+}
+
+/// 保存执行配置；危险跳过模式的开关发生变化时按 `action` 记录审计
+pub(super) fn store_execution_config(
+    config: &ClaudeExecutionConfig,
+    action: &str,
+) -> Result<(), String> {
+    let config_file = execution_config_path()?;
+    let was_enabled =
+        crate::utils::config_utils::load_json_config::<ClaudeExecutionConfig>(&config_file)
+            .map(|previous| previous.permissions.enable_dangerous_skip)
+            .unwrap_or(false);
 
     // 使用通用配置保存工具
-    crate::utils::config_utils::save_json_config(&config, &config_file)?;
+    crate::utils::config_utils::save_json_config(config, &config_file)?;
+    record_setting_change(
+        was_enabled,
+        config.permissions.enable_dangerous_skip,
+        action,
+    );
 
     log::info!("Updated Claude execution config");
     Ok(())
 }
 
+/// 更新Claude执行配置
+#[tauri::command]
+pub async fn update_claude_execution_config(
+    _app: AppHandle,
+    config: ClaudeExecutionConfig,
+) -> Result<(), String> {
+    store_execution_config(&config, "update_claude_execution_config")
+}
+
 /// 重置Claude执行配置为默认值
 #[tauri::command]
-pub async fn reset_claude_execution_config(app: AppHandle) -> Result<(), String> {
+pub async fn reset_claude_execution_config(_app: AppHandle) -> Result<(), String> {
     let config = ClaudeExecutionConfig::default();
-    update_claude_execution_config(app, config).await
+    store_execution_config(&config, "reset_claude_execution_config")
+}
+
+/// 设置项目级执行配置覆盖（权限配置需通过校验），该项目的会话将使用它而不是全局配置
+#[tauri::command]
+pub async fn set_project_execution_config(
+    project_path: String,
+    config: ClaudeExecutionConfig,
+) -> Result<serde_json::Value, String> {
+    if project_path.trim().is_empty() {
+        return Err("Project path must not be empty".to_string());
+    }
+    let validation_result = validate_permission_config(config.permissions.clone()).await?;
+    if let Some(errors) = validation_errors(&validation_result) {
+        return Err(format!("项目权限配置无效: {}", errors));
+    }
+
+    let key = normalize_path_for_comparison(&project_path);
+    let mut configs = load_project_execution_configs()?;
+    let was_enabled = configs
+        .get(&key)
+        .is_some_and(|previous| previous.permissions.enable_dangerous_skip);
+    let enabled = config.permissions.enable_dangerous_skip;
+    configs.insert(key, config);
+    save_project_execution_configs(&configs)?;
+    record_setting_change(
+        was_enabled,
+        enabled,
+        &format!("set_project_execution_config ({})", project_path),
+    );
+
+    log::info!("Set execution config override for project {}", project_path);
+    Ok(validation_result)
+}
+
+/// 清除项目级执行配置覆盖，之后该项目回退到全局配置；返回是否存在覆盖
+#[tauri::command]
+pub async fn clear_project_execution_config(project_path: String) -> Result<bool, String> {
+    let key = normalize_path_for_comparison(&project_path);
+    let mut configs = load_project_execution_configs()?;
+    let Some(removed) = configs.remove(&key) else {
+        return Ok(false);
+    };
+    save_project_execution_configs(&configs)?;
+    record_setting_change(
+        removed.permissions.enable_dangerous_skip,
+        false,
+        &format!("clear_project_execution_config ({})", project_path),
+    );
+
+    log::info!(
+        "Cleared execution config override for project {}",
+        project_path
+    );
+    Ok(true)
+}
+
+/// 获取项目实际生效的执行配置，并标明来自项目级覆盖还是全局配置
+#[tauri::command]
+pub async fn get_effective_execution_config(
+    project_path: Option<String>,
+) -> Result<EffectiveExecutionConfig, String> {
+    resolve_execution_config(project_path.as_deref())
+}
+
+/// 校验结果无效时返回拼接后的错误信息
+fn validation_errors(validation_result: &serde_json::Value) -> Option<String> {
+    if validation_result["valid"] == serde_json::Value::Bool(true) {
+        return None;
+    }
+    let errors: Vec<String> = validation_result["errors"]
+        .as_array()
+        .map(|errors| {
+            errors
+                .iter()
+                .filter_map(|e| e.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(errors.join("; "))
+}
+
+/// 导出执行配置（含权限配置）为带格式版本的可移植 JSON
+#[tauri::command]
+pub async fn export_execution_config(app: AppHandle) -> Result<String, String> {
+    let config = get_claude_execution_config(app, None).await?;
+    export_config_blob(&config)
+}
+
+/// 导入执行配置：校验权限配置后写入，写入前备份当前配置；`merge` 为 true 时合并到当前配置
+#[tauri::command]
+pub async fn import_execution_config(
+    app: AppHandle,
+    blob: String,
+    merge: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let imported = migrate_exported_config(&blob)?;
+    let merged = if merge.unwrap_or(false) {
+        let current = get_claude_execution_config(app, None).await?;
+        let mut base = serde_json::to_value(&current)
+            .map_err(|e| format!("Failed to serialize execution config: {}", e))?;
+        merge_json(&mut base, imported);
+        base
+    } else {
+        imported
+    };
+    let config: ClaudeExecutionConfig =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid execution config: {}", e))?;
+
+    let mut validation_result = validate_permission_config(config.permissions.clone()).await?;
+    if let Some(errors) = validation_errors(&validation_result) {
+        return Err(format!("导入的权限配置无效: {}", errors));
+    }
+
+    let claude_dir =
+        get_claude_dir().map_err(|e| format!("Failed to get Claude directory: {}", e))?;
+    let config_file = execution_config_path()?;
+    let backup_path = if config_file.exists() {
+        let backups_dir = claude_dir.join("backups");
+        fs::create_dir_all(&backups_dir)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+        let backup_path = backups_dir.join(format!(
+            "execution_config.json.{}.bak",
+            chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+        ));
+        fs::copy(&config_file, &backup_path)
+            .map_err(|e| format!("Failed to back up execution config: {}", e))?;
+        Some(backup_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    store_execution_config(&config, "import_execution_config")?;
+    log::info!(
+        "Imported execution config (merge: {:?}, backup: {:?})",
+        merge,
+        backup_path
+    );
+    validation_result["backup_path"] = serde_json::json!(backup_path);
+    Ok(validation_result)
 }
 
 /// 获取当前权限配置
@@ -827,7 +1184,7 @@ pub async fn reset_claude_execution_config(app: AppHandle) -> Result<(), String>
 pub async fn get_claude_permission_config(
     app: AppHandle,
 ) -> Result<ClaudePermissionConfig, String> {
-    let execution_config = get_claude_execution_config(app).await?;
+    let execution_config = get_claude_execution_config(app, None).await?;
     Ok(execution_config.permissions)
 }
 
@@ -837,9 +1194,9 @@ pub async fn update_claude_permission_config(
     app: AppHandle,
     permission_config: ClaudePermissionConfig,
 ) -> Result<(), String> {
-    let mut execution_config = get_claude_execution_config(app.clone()).await?;
+    let mut execution_config = get_claude_execution_config(app, None).await?;
     execution_config.permissions = permission_config;
-    update_claude_execution_config(app, execution_config).await
+    store_execution_config(&execution_config, "update_claude_permission_config")
 }
 
 /// 获取预设权限配置选项
@@ -861,6 +1218,11 @@ pub async fn get_permission_presets() -> Result<serde_json::Value, String> {
             "description": "平衡的权限设置，需要确认编辑",
             "config": ClaudePermissionConfig::interactive_mode()
         },
+        "app_approval": {
+            "name": "应用审批",
+            "description": "每次工具调用都由应用弹窗确认，超时自动拒绝",
+            "config": ClaudePermissionConfig::app_approval_mode()
+        },
         "legacy": {
             "name": "向后兼容",
             "description": "保持原有的权限跳过行为",
@@ -877,7 +1239,10 @@ pub async fn get_available_tools() -> Result<serde_json::Value, String> {
     let tools = serde_json::json!({
         "development_tools": DEVELOPMENT_TOOLS,
         "safe_tools": SAFE_TOOLS,
-        "all_tools": ALL_TOOLS
+        "all_tools": ALL_TOOLS,
+        "known_tools": known_claude_tools()
+            .map(|(name, since)| serde_json::json!({ "name": name, "since": since }))
+            .collect::<Vec<_>>()
     });
 
     Ok(tools)
@@ -924,6 +1289,16 @@ pub async fn validate_permission_config(
             ));
     }
 
+    // 危险跳过模式会让应用审批失效
+    if config.permission_mode == PermissionMode::AppApproval && config.enable_dangerous_skip {
+        validation_result["warnings"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!(
+                "已启用危险权限跳过模式，应用审批不会生效"
+            ));
+    }
+
     // 检查读写权限组合
     if config.permission_mode == PermissionMode::ReadOnly
         && (config.allowed_tools.contains(&"Write".to_string())
@@ -938,6 +1313,31 @@ pub async fn validate_permission_config(
     Ok(validation_result)
 }
 
+/// 在 validate_permission_config 的基础上，按已安装的 Claude CLI 版本检查工具名
+#[tauri::command]
+pub async fn validate_permission_config_for_version(
+    app: AppHandle,
+    config: ClaudePermissionConfig,
+) -> Result<serde_json::Value, String> {
+    let tools: Vec<String> = config
+        .allowed_tools
+        .iter()
+        .chain(config.disallowed_tools.iter())
+        .cloned()
+        .collect();
+    let mut validation_result = validate_permission_config(config).await?;
+
+    let version = check_claude_version(app).await?.version;
+    let check = check_tool_names(&tools, version.as_deref());
+    if let Some(warnings) = validation_result["warnings"].as_array_mut() {
+        warnings.extend(check.warnings.into_iter().map(serde_json::Value::String));
+    }
+    validation_result["unrecognized_tools"] = serde_json::json!(check.unrecognized);
+    validation_result["claude_version"] = serde_json::json!(version);
+
+    Ok(validation_result)
+}
+
 /// Reads the AGENTS.md system prompt file from Codex directory
 #[tauri::command]
 pub async fn get_codex_system_prompt() -> Result<String, String> {
@@ -1099,6 +1499,12 @@ pub async fn set_claude_wsl_mode_config(
         }
     };
 
+    // WSL 模式未指定发行版时自动选择默认发行版并持久化
+    let wsl_distro = match (&claude_mode, wsl_distro.filter(|d| !d.trim().is_empty())) {
+        (wsl_utils::ClaudeMode::Wsl, None) => Some(wsl_utils::auto_select_wsl_distro("Claude")?),
+        (_, distro) => distro,
+    };
+
     let config = wsl_utils::ClaudeWslConfig {
         mode: claude_mode,
         wsl_distro,
@@ -1117,3 +1523,38 @@ pub async fn set_claude_wsl_mode_config(
             .to_string(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn merged_claude_md_puts_user_file_first() {
+        let home = tempfile::tempdir().unwrap();
+        let user_md = home.path().join("CLAUDE.md");
+        fs::write(&user_md, "user rules\n").unwrap();
+
+        let project = tempfile::tempdir().unwrap();
+        fs::create_dir_all(project.path().join("sub")).unwrap();
+        fs::write(project.path().join("sub").join("CLAUDE.md"), "nested").unwrap();
+        fs::write(project.path().join("CLAUDE.md"), "root").unwrap();
+
+        let project_path = project.path().to_string_lossy().to_string();
+        let merged = merge_claude_md_files(Some(user_md), project_path.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            merged,
+            "<!-- Source: ~/.claude/CLAUDE.md -->\n\nuser rules\n\n\
+             <!-- Source: CLAUDE.md -->\n\nroot\n\n\
+             <!-- Source: sub/CLAUDE.md -->\n\nnested"
+        );
+
+        // 用户级文件不存在时只包含项目文件
+        let missing = home.path().join("missing").join("CLAUDE.md");
+        let merged = merge_claude_md_files(Some(missing), project_path)
+            .await
+            .unwrap();
+        assert!(merged.starts_with("<!-- Source: CLAUDE.md -->"));
+    }
+}
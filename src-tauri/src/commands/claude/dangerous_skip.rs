@@ -0,0 +1,235 @@
+//! 危险跳过模式审计
+//!
+//! `enable_dangerous_skip` 会让 Claude CLI 跳过所有权限检查。这里记录它何时、由哪个操作启用，
+//! 以及启用后已经启动了多少个会话；运行器每次以该模式启动会话时都会醒目地记录日志，
+//! 并在超过执行配置中的 `dangerous_skip_safeguard` 限制时自动关闭它。
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::config::{
+    disable_project_dangerous_skip, execution_config_path, store_execution_config,
+};
+use super::paths::get_claude_dir;
+use super::{DangerousSkipAudit, DangerousSkipEvent};
+use crate::commands::permission_config::{ClaudeExecutionConfig, DangerousSkipSafeguard};
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// 审计历史最多保留的变更条数
+const MAX_HISTORY: usize = 50;
+
+fn audit_path() -> Result<PathBuf, String> {
+    Ok(get_claude_dir()
+        .map_err(|e| format!("Failed to get Claude directory: {}", e))?
+        .join("dangerous_skip_audit.json"))
+}
+
+fn load_audit() -> Result<DangerousSkipAudit, String> {
+    load_json_config(audit_path()?)
+}
+
+fn save_audit(audit: &DangerousSkipAudit) -> Result<(), String> {
+    save_json_config(audit, audit_path()?)
+}
+
+fn push_event(audit: &mut DangerousSkipAudit, change: &str, action: &str, now: DateTime<Utc>) {
+    audit.history.push(DangerousSkipEvent {
+        timestamp: now.to_rfc3339(),
+        change: change.to_string(),
+        action: action.to_string(),
+    });
+    if audit.history.len() > MAX_HISTORY {
+        let excess = audit.history.len() - MAX_HISTORY;
+        audit.history.drain(..excess);
+    }
+}
+
+fn mark_enabled(audit: &mut DangerousSkipAudit, action: &str, now: DateTime<Utc>) {
+    audit.enabled = true;
+    audit.enabled_at = Some(now.to_rfc3339());
+    audit.enabled_by = Some(action.to_string());
+    audit.sessions_launched = 0;
+    push_event(audit, "enabled", action, now);
+}
+
+/// 执行配置保存时调用：开关发生变化则记录一条审计
+pub(super) fn record_setting_change(was_enabled: bool, enabled: bool, action: &str) {
+    if was_enabled == enabled {
+        return;
+    }
+    let result = load_audit().and_then(|mut audit| {
+        let now = Utc::now();
+        if enabled {
+            mark_enabled(&mut audit, action, now);
+        } else {
+            audit.enabled = false;
+            push_event(&mut audit, "disabled", action, now);
+        }
+        save_audit(&audit)
+    });
+    match result {
+        Ok(()) => log::info!(
+            "Dangerous-skip {} by {}",
+            if enabled { "enabled" } else { "disabled" },
+            action
+        ),
+        Err(e) => log::warn!("Failed to record dangerous-skip audit: {}", e),
+    }
+}
+
+/// 超过保护限制时返回原因
+fn safeguard_exceeded(
+    audit: &DangerousSkipAudit,
+    safeguard: &DangerousSkipSafeguard,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    if let Some(max_sessions) = safeguard.max_sessions {
+        if audit.sessions_launched >= max_sessions {
+            return Some(format!("limit of {} sessions reached", max_sessions));
+        }
+    }
+    if let Some(max_hours) = safeguard.max_hours {
+        let enabled_at = audit
+            .enabled_at
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())?;
+        if now - enabled_at.with_timezone(&Utc) >= Duration::hours(i64::from(max_hours)) {
+            return Some(format!("enabled for more than {} hours", max_hours));
+        }
+    }
+    None
+}
+
+/// 读取审计记录并判断以危险跳过模式启动是否会超过保护限制；只在内存中补全启用记录，不写入
+fn evaluate_launch(
+    config: &ClaudeExecutionConfig,
+    now: DateTime<Utc>,
+) -> (DangerousSkipAudit, Option<String>) {
+    let mut audit = load_audit().unwrap_or_else(|e| {
+        log::warn!("Failed to load dangerous-skip audit: {}", e);
+        DangerousSkipAudit::default()
+    });
+    // 审计功能出现之前（或默认配置）就已启用的情况，从第一次使用开始计算
+    if !audit.enabled || audit.enabled_at.is_none() {
+        mark_enabled(&mut audit, "untracked (enabled before auditing)", now);
+    }
+    let exceeded = safeguard_exceeded(&audit, &config.dangerous_skip_safeguard, now);
+    (audit, exceeded)
+}
+
+/// 预览启动命令时调用：与 `check_dangerous_skip_launch` 判断相同，超过保护限制时同样去掉
+/// 危险跳过，但不记录使用、不持久化关闭
+pub(super) fn preview_dangerous_skip_launch(config: &mut ClaudeExecutionConfig) {
+    if !config.permissions.enable_dangerous_skip {
+        return;
+    }
+    if evaluate_launch(config, Utc::now()).1.is_some() {
+        config.permissions.enable_dangerous_skip = false;
+    }
+}
+
+/// 运行器在启动会话前调用：记录一次使用并醒目地输出日志；超过保护限制时关闭危险跳过模式
+pub(super) fn check_dangerous_skip_launch(
+    config: &mut ClaudeExecutionConfig,
+    launch: &str,
+    project_path: &str,
+) {
+    if !config.permissions.enable_dangerous_skip {
+        return;
+    }
+    let now = Utc::now();
+    let (mut audit, exceeded) = evaluate_launch(config, now);
+
+    if let Some(reason) = exceeded {
+        if let Err(e) = save_audit(&audit) {
+            log::warn!("Failed to save dangerous-skip audit: {}", e);
+        }
+        config.permissions.enable_dangerous_skip = false;
+        let action = format!("dangerous_skip_safeguard ({})", reason);
+        let stored = execution_config_path()
+            .and_then(load_json_config::<ClaudeExecutionConfig>)
+            .map(|mut stored| {
+                stored.permissions.enable_dangerous_skip = false;
+                stored
+            })
+            .and_then(|stored| store_execution_config(&stored, &action));
+        if let Err(e) = stored {
+            log::error!("Failed to persist dangerous-skip auto-disable: {}", e);
+        }
+        // 该项目的执行配置覆盖同样需要关闭，否则下次启动仍会启用
+        match disable_project_dangerous_skip(project_path) {
+            Ok(true) => record_setting_change(true, false, &action),
+            Ok(false) => {}
+            Err(e) => log::error!(
+                "Failed to disable dangerous-skip in project override: {}",
+                e
+            ),
+        }
+        log::warn!(
+            "Dangerous-skip automatically disabled ({}); launching {} session in {} with normal permission checks",
+            reason,
+            launch,
+            project_path
+        );
+        return;
+    }
+
+    audit.sessions_launched += 1;
+    audit.last_used_at = Some(now.to_rfc3339());
+    if let Err(e) = save_audit(&audit) {
+        log::warn!("Failed to save dangerous-skip audit: {}", e);
+    }
+    log::warn!(
+        "⚠️ DANGEROUS-SKIP ACTIVE: launching {} session in {} with --dangerously-skip-permissions \
+         (session #{} since enabled by {} at {})",
+        launch,
+        project_path,
+        audit.sessions_launched,
+        audit.enabled_by.as_deref().unwrap_or("unknown"),
+        audit.enabled_at.as_deref().unwrap_or("unknown")
+    );
+}
+
+/// Returns when dangerous-skip was enabled, by which action and how many sessions have used it since
+#[tauri::command]
+pub async fn get_dangerous_skip_audit() -> Result<DangerousSkipAudit, String> {
+    let mut audit = load_audit()?;
+    let config: ClaudeExecutionConfig = load_json_config(execution_config_path()?)?;
+    audit.enabled = config.permissions.enable_dangerous_skip;
+    Ok(audit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safeguard_trips_on_session_count_or_age() {
+        let now = Utc::now();
+        let mut audit = DangerousSkipAudit::default();
+        mark_enabled(&mut audit, "test", now - Duration::hours(3));
+        audit.sessions_launched = 4;
+
+        let unlimited = DangerousSkipSafeguard::default();
+        assert_eq!(safeguard_exceeded(&audit, &unlimited, now), None);
+
+        let sessions = DangerousSkipSafeguard {
+            max_sessions: Some(5),
+            max_hours: None,
+        };
+        assert_eq!(safeguard_exceeded(&audit, &sessions, now), None);
+        audit.sessions_launched = 5;
+        assert!(safeguard_exceeded(&audit, &sessions, now).is_some());
+
+        let hours = DangerousSkipSafeguard {
+            max_sessions: None,
+            max_hours: Some(2),
+        };
+        assert!(safeguard_exceeded(&audit, &hours, now).is_some());
+        assert_eq!(
+            safeguard_exceeded(&audit, &hours, now - Duration::hours(2)),
+            None
+        );
+    }
+}
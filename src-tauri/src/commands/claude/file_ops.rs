@@ -7,6 +7,22 @@ use std::path::PathBuf;
 
 use super::models::FileEntry;
 
+/// Directories that are never searched (dependencies, build output, VCS metadata)
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    ".git",
+    "dist",
+    "build",
+    ".next",
+    "__pycache__",
+];
+
+/// Whether a directory with this name should be skipped when walking a project
+pub(super) fn is_skipped_dir(name: &str) -> bool {
+    SKIPPED_DIRS.contains(&name)
+}
+
 /// Lists files and directories in a given path
 ///
 /// Returns a sorted list of directory entries, with directories appearing first,
@@ -215,10 +231,7 @@ fn search_files_recursive(
         if entry_path.is_dir() {
             // Skip common directories that shouldn't be searched
             if let Some(dir_name) = entry_path.file_name().and_then(|n| n.to_str()) {
-                if matches!(
-                    dir_name,
-                    "node_modules" | "target" | ".git" | "dist" | "build" | ".next" | "__pycache__"
-                ) {
+                if is_skipped_dir(dir_name) {
                     continue;
                 }
             }
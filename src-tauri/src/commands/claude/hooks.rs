@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use super::paths::get_claude_dir;
 use super::platform;
+use crate::commands::enhanced_hooks::HookCondition;
 
 #[tauri::command]
 pub async fn get_hooks_config(
@@ -118,11 +119,23 @@ pub async fn update_hooks_config(
     Ok("Hooks configuration updated successfully".to_string())
 }
 
-/// Validates a hook command by dry-running it
+/// Validates a hook command by dry-running it, along with its optional condition
 #[tauri::command]
-pub async fn validate_hook_command(command: String) -> Result<serde_json::Value, String> {
+pub async fn validate_hook_command(
+    command: String,
+    condition: Option<HookCondition>,
+) -> Result<serde_json::Value, String> {
     log::info!("Validating hook command syntax");
 
+    if let Some(condition) = condition {
+        if let Err(e) = condition.validate() {
+            return Ok(serde_json::json!({
+                "valid": false,
+                "message": format!("Condition error: {}", e)
+            }));
+        }
+    }
+
     // Validate syntax without executing
     let mut cmd = std::process::Command::new("bash");
     cmd.arg("-n") // Syntax check only
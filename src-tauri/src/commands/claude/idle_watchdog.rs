@@ -0,0 +1,121 @@
+//! 空闲会话看门狗
+//!
+//! 会话偶尔会卡住（既不输出也不退出），一直占用并发名额。看门狗定期检查注册表中每个运行中
+//! Claude 会话最后一次输出的时间：超过警告阈值时发送 `claude-idle-warning`，超过取消阈值时
+//! 按用户取消的流程结束会话。两个阈值默认都为 0（关闭）。
+
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::storage::{load_app_setting, store_app_setting};
+use crate::error::{AppError, AppResult};
+use crate::process::{IdleAction, IdleTimeouts, ProcessRegistryState, ProcessType};
+
+use super::cli_runner::cancel_claude_execution;
+
+/// app_settings 中保存空闲阈值（JSON）的键
+const IDLE_TIMEOUTS_SETTING: &str = "idle_timeouts";
+/// 看门狗检查间隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 读取持久化的空闲阈值
+pub fn load_idle_timeouts(app: &AppHandle) -> Option<IdleTimeouts> {
+    load_app_setting(app, IDLE_TIMEOUTS_SETTING)
+}
+
+/// 启动后台看门狗；阈值关闭时每轮检查直接返回
+pub fn spawn_idle_watchdog(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let registry = app.state::<ProcessRegistryState>().0.clone();
+            let idle_sessions = match registry.collect_idle_sessions(Utc::now()) {
+                Ok(sessions) => sessions,
+                Err(e) => {
+                    log::warn!("Idle watchdog failed to inspect sessions: {}", e);
+                    continue;
+                }
+            };
+
+            for idle in idle_sessions {
+                let ProcessType::ClaudeSession { session_id } = &idle.info.process_type else {
+                    continue;
+                };
+                let payload = serde_json::json!({
+                    "session_id": session_id,
+                    "run_id": idle.info.run_id,
+                    "project_path": idle.info.project_path,
+                    "idle_secs": idle.idle_secs,
+                    "cancelling": idle.action == IdleAction::Cancel,
+                });
+                match idle.action {
+                    IdleAction::Warn => {
+                        log::warn!(
+                            "Session {} has produced no output for {}s",
+                            session_id,
+                            idle.idle_secs
+                        );
+                        let _ = app.emit(&format!("claude-idle-warning:{}", session_id), &payload);
+                        let _ = app.emit("claude-idle-warning", &payload);
+                    }
+                    IdleAction::Cancel => {
+                        log::warn!(
+                            "Cancelling session {} after {}s without output",
+                            session_id,
+                            idle.idle_secs
+                        );
+                        let _ = app.emit("claude-idle-warning", &payload);
+                        if let Err(e) =
+                            cancel_claude_execution(app.clone(), Some(session_id.clone())).await
+                        {
+                            log::error!("Failed to cancel idle session {}: {}", session_id, e);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Get the idle warning / auto-cancel thresholds in seconds (0 = off)
+#[tauri::command]
+pub async fn get_idle_timeouts(
+    registry: tauri::State<'_, ProcessRegistryState>,
+) -> AppResult<IdleTimeouts> {
+    Ok(registry.0.idle_timeouts())
+}
+
+/// Set the idle warning / auto-cancel thresholds in seconds (0 = off) and persist them
+#[tauri::command]
+pub async fn set_idle_timeouts(
+    app: AppHandle,
+    registry: tauri::State<'_, ProcessRegistryState>,
+    warn_after_secs: u64,
+    cancel_after_secs: u64,
+) -> AppResult<IdleTimeouts> {
+    if warn_after_secs > 0 && cancel_after_secs > 0 && cancel_after_secs <= warn_after_secs {
+        return Err(AppError::invalid_config(
+            "cancel_after_secs must be greater than warn_after_secs",
+        ));
+    }
+
+    let timeouts = IdleTimeouts {
+        warn_after_secs,
+        cancel_after_secs,
+    };
+    registry
+        .0
+        .set_idle_timeouts(timeouts)
+        .map_err(AppError::External)?;
+    store_app_setting(&app, IDLE_TIMEOUTS_SETTING, &timeouts)?;
+
+    log::info!(
+        "Idle timeouts set to warn after {}s, cancel after {}s",
+        warn_after_secs,
+        cancel_after_secs
+    );
+    Ok(timeouts)
+}
@@ -1,41 +1,158 @@
+mod ansi;
+mod attachments;
+mod claude_md_templates;
 mod cli_runner;
 mod config;
+mod dangerous_skip;
 mod file_ops;
 mod hooks;
+mod idle_watchdog;
 mod models;
+mod output_filter;
+mod output_translation;
 mod paths;
+mod permission_prompt;
+mod plan_capture;
 mod platform;
+mod project_env;
 mod project_store;
+mod project_type;
+mod prompt_prep;
+mod prompt_presets;
+mod pty_runner;
+mod rate_limit;
+mod resumable_sessions;
+mod run_output;
 mod session_history;
+mod session_search;
+mod session_watch;
+mod slash_commands;
 
+pub use self::claude_md_templates::{list_claude_md_templates, scaffold_claude_md};
 pub use models::*;
 pub use paths::*;
 // Export platform utilities for process window hiding
+pub(crate) use self::cli_runner::{
+    build_claude_dry_run_command, create_roundtrip_command, inherited_env,
+};
 pub use self::cli_runner::{
-    cancel_claude_execution, continue_claude_code, execute_claude_code, get_claude_session_output,
-    list_running_claude_sessions, resume_claude_code, ClaudeProcessState,
+    cancel_all_running_sessions, cancel_claude_execution, cancel_project_sessions,
+    continue_claude_code, execute_claude_code, get_claude_session_output, get_live_output_limits,
+    list_running_claude_sessions, list_running_sessions_by_project, load_live_output_limits,
+    reap_orphaned_processes, resume_claude_code, resume_last_claude, set_live_output_limits,
+    subscribe_session_output, unsubscribe_session_output, unsubscribe_window_output,
+    ClaudeProcessState,
 };
+pub(crate) use self::config::FALLBACK_MODEL;
 pub use self::config::{
     check_claude_version, clear_custom_claude_path, find_claude_md_files, get_available_tools,
-    get_claude_execution_config, get_claude_path, get_claude_permission_config,
-    get_claude_settings, get_codex_system_prompt, get_permission_presets, get_system_prompt,
+    get_claude_binary_info, get_claude_execution_config, get_claude_path,
+    get_claude_permission_config, get_claude_settings, get_codex_system_prompt,
+    get_permission_presets, get_system_prompt,
     // Claude WSL mode configuration
     get_claude_wsl_mode_config, set_claude_wsl_mode_config,
-    open_new_session, read_claude_md_file, reset_claude_execution_config, save_claude_md_file,
+    open_new_session, preview_merged_claude_md, read_claude_md_file, reset_claude_execution_config,
+    save_claude_md_file,
     save_claude_settings, save_codex_system_prompt, save_system_prompt, set_custom_claude_path,
     update_claude_execution_config, update_claude_permission_config, update_thinking_mode,
-    validate_permission_config,
+    export_execution_config, import_execution_config,
+    validate_permission_config, validate_permission_config_for_version,
+};
+pub use self::config::{
+    get_default_model, get_model_aliases, set_default_model, set_model_aliases,
+};
+pub use self::config::{
+    clear_project_execution_config, get_effective_execution_config, set_project_execution_config,
 };
+pub use self::dangerous_skip::get_dangerous_skip_audit;
 pub use self::hooks::{get_hooks_config, update_hooks_config, validate_hook_command};
+pub use self::idle_watchdog::{
+    get_idle_timeouts, load_idle_timeouts, set_idle_timeouts, spawn_idle_watchdog,
+};
+pub use self::output_translation::{get_output_translation, set_output_translation};
+pub use self::permission_prompt::respond_to_permission_request;
+pub use self::plan_capture::get_last_plan;
+pub use self::project_env::{get_effective_env, get_project_env_status, set_project_env_trust};
+pub(crate) use self::project_env::load_settings_env;
 use self::project_store::ProjectStore;
+pub use self::project_type::detect_project_type;
+pub(crate) use self::prompt_prep::estimate_tokens;
+pub use self::prompt_prep::prepare_prompt;
+pub use self::prompt_presets::{
+    apply_system_prompt_preset, delete_system_prompt_preset, list_system_prompt_presets,
+    save_system_prompt_preset,
+};
+pub use self::pty_runner::{resize_claude_pty, write_claude_pty};
+pub use self::resumable_sessions::list_resumable_sessions;
+pub use self::session_search::{
+    cancel_session_search, search_all_sessions, search_sessions_content,
+};
+pub use self::session_watch::{unwatch_session, unwatch_window_sessions, watch_session};
+pub use self::slash_commands::list_known_slash_commands;
 pub use file_ops::{list_directory_contents, search_files};
-pub use platform::{apply_no_window_async, kill_process_tree};
+pub(crate) use platform::is_secret_env_key;
+pub use platform::{apply_no_window_async, kill_process_tree, LaunchCommand};
 // Agent functionality removed
 
+/// Lists projects; with `workspace_only`, only members of the active workspace (all projects if none is active)
 #[tauri::command]
-pub async fn list_projects() -> Result<Vec<Project>, String> {
+pub async fn list_projects(workspace_only: Option<bool>) -> Result<Vec<Project>, String> {
     let store = ProjectStore::new()?;
-    store.list_projects()
+    let projects = store.list_projects()?;
+    if !workspace_only.unwrap_or(false) {
+        return Ok(projects);
+    }
+    Ok(match store.active_workspace()? {
+        Some(workspace) => project_store::filter_to_workspace(projects, &workspace),
+        None => projects,
+    })
+}
+
+/// Creates a workspace grouping several project paths
+#[tauri::command]
+pub async fn create_workspace(
+    name: String,
+    project_paths: Vec<String>,
+) -> Result<Workspace, String> {
+    let store = ProjectStore::new()?;
+    store.create_workspace(&name, &project_paths)
+}
+
+/// Lists all workspaces
+#[tauri::command]
+pub async fn list_workspaces() -> Result<Vec<Workspace>, String> {
+    let store = ProjectStore::new()?;
+    store.list_workspaces()
+}
+
+/// Deletes a workspace; member projects are not touched
+#[tauri::command]
+pub async fn delete_workspace(workspace_id: String) -> Result<bool, String> {
+    let store = ProjectStore::new()?;
+    store.delete_workspace(&workspace_id)
+}
+
+/// Sets the active workspace used by list_projects; pass null to clear it
+#[tauri::command]
+pub async fn set_active_workspace(workspace_id: Option<String>) -> Result<(), String> {
+    let store = ProjectStore::new()?;
+    store.set_active_workspace(workspace_id.as_deref())
+}
+
+/// Gets the sessions of all member projects of a workspace, most recent first
+#[tauri::command]
+pub async fn get_workspace_sessions(workspace_id: String) -> Result<Vec<Session>, String> {
+    let store = ProjectStore::new()?;
+    store.get_workspace_sessions(&workspace_id)
+}
+
+/// Sums the JSONL session sizes per project, largest first (hidden projects on request)
+#[tauri::command]
+pub async fn get_project_disk_usage(
+    include_hidden: Option<bool>,
+) -> Result<Vec<ProjectDiskUsage>, String> {
+    let store = ProjectStore::new()?;
+    store.project_disk_usage(include_hidden.unwrap_or(false))
 }
 
 /// Gets sessions for a specific project
@@ -50,6 +167,7 @@ pub async fn get_project_sessions(project_id: String) -> Result<Vec<Session>, St
 pub async fn delete_session(session_id: String, project_id: String) -> Result<String, String> {
     let store = ProjectStore::new()?;
     let session_deleted = store.delete_session(&project_id, &session_id)?;
+    output_translation::clear_session(&session_id);
 
     if session_deleted {
         Ok(format!("Successfully deleted session: {}", session_id))
@@ -69,6 +187,9 @@ pub async fn delete_sessions_batch(
 ) -> Result<String, String> {
     let store = ProjectStore::new()?;
     let outcome = store.delete_sessions_batch(&project_id, &session_ids);
+    for session_id in &session_ids {
+        output_translation::clear_session(session_id);
+    }
 
     if outcome.failed_count > 0 {
         Err(format!(
@@ -85,6 +206,139 @@ pub async fn delete_sessions_batch(
     }
 }
 
+/// Previews (or, with `confirm`, deletes) sessions matching the cleanup criteria;
+/// pinned, favorite-tagged and running sessions are never deleted
+#[tauri::command]
+pub async fn cleanup_sessions(
+    registry: tauri::State<'_, crate::process::ProcessRegistryState>,
+    criteria: SessionCleanupCriteria,
+    confirm: Option<bool>,
+) -> Result<SessionCleanupReport, String> {
+    let store = ProjectStore::new()?;
+    let running: std::collections::HashSet<String> = registry
+        .0
+        .get_running_claude_sessions()?
+        .into_iter()
+        .filter_map(|info| match info.process_type {
+            crate::process::ProcessType::ClaudeSession { session_id } => Some(session_id),
+            _ => None,
+        })
+        .collect();
+    let sessions = store.plan_session_cleanup(&criteria, &running)?;
+
+    if !confirm.unwrap_or(false) {
+        return Ok(SessionCleanupReport {
+            dry_run: true,
+            total_bytes: sessions.iter().map(|s| s.bytes).sum(),
+            sessions,
+            deleted_count: 0,
+            failed_count: 0,
+            errors: Vec::new(),
+        });
+    }
+
+    let mut by_project: std::collections::HashMap<&str, Vec<String>> =
+        std::collections::HashMap::new();
+    for session in &sessions {
+        by_project
+            .entry(session.project_id.as_str())
+            .or_default()
+            .push(session.session_id.clone());
+    }
+    let (mut deleted_count, mut failed_count, mut errors) = (0, 0, Vec::new());
+    for (project_id, session_ids) in by_project {
+        let outcome = store.delete_sessions_batch(project_id, &session_ids);
+        deleted_count += outcome.deleted_count;
+        failed_count += outcome.failed_count;
+        errors.extend(outcome.errors);
+    }
+
+    // 只统计确实被删除的会话
+    let total_bytes = sessions
+        .iter()
+        .filter(|s| !store.session_file_exists(&s.project_id, &s.session_id))
+        .map(|s| s.bytes)
+        .sum();
+    log::info!(
+        "Session cleanup deleted {} sessions ({} bytes), {} failed",
+        deleted_count,
+        total_bytes,
+        failed_count
+    );
+    Ok(SessionCleanupReport {
+        dry_run: false,
+        sessions,
+        total_bytes,
+        deleted_count,
+        failed_count,
+        errors,
+    })
+}
+
+/// Moves a session (and its related files) from one project to another
+#[tauri::command]
+pub async fn move_session(
+    session_id: String,
+    from_project_id: String,
+    to_project_id: String,
+) -> Result<String, String> {
+    let store = ProjectStore::new()?;
+    store.move_session(&session_id, &from_project_id, &to_project_id)?;
+
+    let result_msg = format!(
+        "Session {} moved from project {} to {}",
+        session_id, from_project_id, to_project_id
+    );
+    log::info!("{}", result_msg);
+    Ok(result_msg)
+}
+
+/// Replaces the tags of a session; returns the normalized tag list
+#[tauri::command]
+pub async fn set_session_tags(
+    session_id: String,
+    project_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let store = ProjectStore::new()?;
+    store.set_session_tags(&project_id, &session_id, &tags)
+}
+
+/// Lists sessions across all projects carrying a tag (case-insensitive)
+#[tauri::command]
+pub async fn list_sessions_by_tag(tag: String) -> Result<Vec<Session>, String> {
+    let store = ProjectStore::new()?;
+    store.list_sessions_by_tag(&tag)
+}
+
+/// Pins a session so the frontend can sort it to the top
+#[tauri::command]
+pub async fn pin_session(session_id: String, project_id: String) -> Result<bool, String> {
+    let store = ProjectStore::new()?;
+    store.pin_session(&project_id, &session_id)
+}
+
+/// Unpins a session; a no-op if it isn't pinned or no longer exists
+#[tauri::command]
+pub async fn unpin_session(session_id: String) -> Result<bool, String> {
+    let store = ProjectStore::new()?;
+    store.unpin_session(&session_id)
+}
+
+/// Pins a project so the frontend can sort it to the top
+#[tauri::command]
+pub async fn pin_project(project_id: String) -> Result<bool, String> {
+    let store = ProjectStore::new()?;
+    store.pin_project(&project_id)
+}
+
+/// Unpins a project; a no-op if it isn't pinned or no longer exists
+#[tauri::command]
+pub async fn unpin_project(project_id: String) -> Result<bool, String> {
+    let store = ProjectStore::new()?;
+    store.unpin_project(&project_id)
+}
+
 /// Removes a project from the project list (without deleting files)
 #[tauri::command]
 pub async fn delete_project(project_id: String) -> Result<String, String> {
@@ -144,6 +398,70 @@ pub async fn list_hidden_projects() -> Result<Vec<String>, String> {
     store.list_hidden_projects()
 }
 
+/// Adopts an existing directory as a project before any session exists
+#[tauri::command]
+pub async fn import_project(path: String) -> Result<String, String> {
+    let store = ProjectStore::new()?;
+    store.import_project(&path)
+}
+
+/// Gets the per-project model override for an engine (defaults to claude)
+#[tauri::command]
+pub async fn get_project_model(
+    project_path: String,
+    engine: Option<String>,
+) -> Result<Option<String>, String> {
+    let store = ProjectStore::new()?;
+    let engine = engine.unwrap_or_else(|| "claude".to_string());
+    Ok(store.get_project_model(&project_path, &engine))
+}
+
+/// Sets the per-project model override; an empty or missing model clears it
+#[tauri::command]
+pub async fn set_project_model(
+    project_path: String,
+    model: Option<String>,
+    engine: Option<String>,
+) -> Result<(), String> {
+    let store = ProjectStore::new()?;
+    let engine = engine.unwrap_or_else(|| "claude".to_string());
+    store.set_project_model(&project_path, &engine, model.as_deref())?;
+    log::info!(
+        "Project model override for {} ({}) set to {:?}",
+        project_path,
+        engine,
+        model
+    );
+    Ok(())
+}
+
+/// Resolves the model override stored for a project, used by all engines
+pub(crate) fn project_model_override(project_path: &str, engine: &str) -> Option<String> {
+    ProjectStore::new()
+        .ok()
+        .and_then(|store| store.get_project_model(project_path, engine))
+}
+
+/// Records the working directory a session runs in when it differs from the project path
+pub(crate) fn record_session_working_dir(session_id: &str, project_path: &str, working_dir: &str) {
+    let result = ProjectStore::new()
+        .and_then(|store| store.record_session_working_dir(session_id, project_path, working_dir));
+    if let Err(e) = result {
+        log::warn!(
+            "Failed to record working dir for session {}: {}",
+            session_id,
+            e
+        );
+    }
+}
+
+/// Resolves the working directory recorded for a session of this project
+pub(crate) fn session_working_dir(session_id: &str, project_path: &str) -> Option<String> {
+    ProjectStore::new()
+        .ok()
+        .and_then(|store| store.session_working_dir(session_id, project_path))
+}
+
 /// Reads the Claude settings file
 
 /// Loads the JSONL history for a specific session
@@ -154,3 +472,45 @@ pub async fn load_session_history(
 ) -> Result<Vec<serde_json::Value>, String> {
     session_history::load_session_history(&session_id, &project_id)
 }
+
+/// Loads the history of a session as typed entries (messages, tool calls, tool results)
+#[tauri::command]
+pub async fn load_session_history_structured(
+    session_id: String,
+    project_id: String,
+) -> Result<Vec<SessionEntry>, String> {
+    session_history::load_session_history_structured(&session_id, &project_id)
+}
+
+/// Lists the files read, written or edited in a session, with a count per operation
+#[tauri::command]
+pub async fn get_session_file_activity(
+    session_id: String,
+    project_id: String,
+) -> Result<Vec<FileActivity>, String> {
+    session_history::session_file_activity(&session_id, &project_id)
+}
+
+/// Imports an external Claude transcript JSONL into a project under a new session id
+#[tauri::command]
+pub async fn import_session_jsonl(project_id: String, file_path: String) -> Result<String, String> {
+    session_history::import_session_jsonl(&project_id, &file_path)
+}
+
+/// Reports malformed lines (with line numbers) in a session JSONL file
+#[tauri::command]
+pub async fn validate_session_file(
+    session_id: String,
+    project_id: String,
+) -> Result<SessionFileReport, String> {
+    session_history::validate_session_file(&session_id, &project_id)
+}
+
+/// Backs up a session JSONL file, then drops its malformed and incomplete trailing lines
+#[tauri::command]
+pub async fn repair_session_file(
+    session_id: String,
+    project_id: String,
+) -> Result<SessionRepairResult, String> {
+    session_history::repair_session_file(&session_id, &project_id)
+}
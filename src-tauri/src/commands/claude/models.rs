@@ -12,6 +12,9 @@ pub struct Project {
     pub sessions: Vec<String>,
     /// Unix timestamp of the latest activity (session modification or project creation)
     pub created_at: u64,
+    /// Whether the user pinned this project
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 /// Represents a session with its metadata
@@ -35,6 +38,19 @@ pub struct Session {
     pub last_message_timestamp: Option<String>,
     /// The model used in this session (if available)
     pub model: Option<String>,
+    /// User-defined tags (stored in the project's session_tags.json sidecar)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the user pinned this session
+    #[serde(default)]
+    pub pinned: bool,
+    /// Number of user/assistant messages in the session
+    #[serde(default)]
+    pub message_count: usize,
+    /// Timestamp of the first message (if available) - ISO string
+    pub first_activity: Option<String>,
+    /// Timestamp of the last message, falling back to the file mtime - ISO string
+    pub last_activity: Option<String>,
 }
 
 /// Represents a message entry in the JSONL file
@@ -107,3 +123,272 @@ pub struct FileEntry {
     /// File extension (if applicable)
     pub extension: Option<String>,
 }
+
+/// A session that can be resumed right now, either running or idle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumableSession {
+    /// The session ID (UUID)
+    pub session_id: String,
+    /// The project ID this session belongs to
+    pub project_id: String,
+    /// The project path
+    pub project_path: String,
+    /// "running" when a Claude process is attached, otherwise "idle"
+    pub status: String,
+    /// PID of the running process
+    pub pid: Option<u32>,
+    /// ProcessRegistry run ID of the running process
+    pub run_id: Option<i64>,
+    /// ISO timestamp of the last activity (process start for sessions not yet on disk)
+    pub last_activity: Option<String>,
+    /// First user message content (if available)
+    pub first_message: Option<String>,
+    /// The model used in this session (if available)
+    pub model: Option<String>,
+    /// Number of user/assistant messages in the session
+    pub message_count: usize,
+}
+
+/// Disk space used by the session files of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDiskUsage {
+    /// The project ID (encoded directory name)
+    pub project_id: String,
+    /// The original project path
+    pub project_path: String,
+    /// Total size of the project's JSONL session files in bytes
+    pub total_bytes: u64,
+    /// Number of JSONL session files
+    pub session_count: usize,
+    /// ID of the largest session (if any)
+    pub largest_session_id: Option<String>,
+    /// Size of the largest session in bytes
+    pub largest_session_bytes: u64,
+    /// Whether the project is hidden from the project list
+    pub hidden: bool,
+}
+
+/// Which sessions `cleanup_sessions` should remove
+///
+/// `older_than_days` / `larger_than_bytes` select sessions matching either one;
+/// `keep_latest_n_per_project` then always keeps the newest N sessions of each project.
+/// Used alone, it selects everything beyond the newest N.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionCleanupCriteria {
+    pub older_than_days: Option<u64>,
+    pub larger_than_bytes: Option<u64>,
+    pub keep_latest_n_per_project: Option<usize>,
+}
+
+/// A session selected for cleanup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCleanupCandidate {
+    pub project_id: String,
+    pub session_id: String,
+    /// Size of the session JSONL file and its subagent files in bytes
+    pub bytes: u64,
+    /// Last modification time of the session file - ISO string
+    pub last_modified: Option<String>,
+}
+
+/// Result of `cleanup_sessions` (a preview unless confirmed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCleanupReport {
+    pub dry_run: bool,
+    pub sessions: Vec<SessionCleanupCandidate>,
+    /// Bytes that would be reclaimed (dry run) or were reclaimed
+    pub total_bytes: u64,
+    pub deleted_count: usize,
+    pub failed_count: usize,
+    pub errors: Vec<String>,
+}
+
+/// A line of a session JSONL file that could not be parsed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MalformedLine {
+    /// 1-based line number
+    pub line_number: usize,
+    pub error: String,
+    /// The first characters of the line
+    pub preview: String,
+}
+
+/// Integrity report of a session JSONL file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionFileReport {
+    pub session_id: String,
+    pub project_id: String,
+    /// Number of non-empty lines
+    pub total_lines: usize,
+    pub malformed_lines: Vec<MalformedLine>,
+    /// Whether the last line is incomplete (no trailing newline and not valid JSON)
+    pub trailing_partial_line: bool,
+}
+
+/// Result of `repair_session_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRepairResult {
+    /// Copy of the original file, taken before repairing
+    pub backup_path: Option<String>,
+    /// Line numbers (1-based, in the original file) that were dropped
+    pub removed_lines: Vec<usize>,
+    /// Integrity report after the repair
+    pub report: SessionFileReport,
+}
+
+/// Fields shared by every structured session entry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntryMeta {
+    pub uuid: Option<String>,
+    pub timestamp: Option<String>,
+    /// Set for messages of a subagent (Task) run
+    pub parent_tool_use_id: Option<String>,
+}
+
+/// A typed session history entry; one JSONL line may yield several entries
+/// (e.g. an assistant reply followed by its tool calls)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEntry {
+    UserMessage {
+        #[serde(flatten)]
+        meta: EntryMeta,
+        text: String,
+    },
+    AssistantMessage {
+        #[serde(flatten)]
+        meta: EntryMeta,
+        text: String,
+        model: Option<String>,
+    },
+    ToolUse {
+        #[serde(flatten)]
+        meta: EntryMeta,
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        #[serde(flatten)]
+        meta: EntryMeta,
+        tool_use_id: String,
+        content: Value,
+        is_error: bool,
+    },
+    System {
+        #[serde(flatten)]
+        meta: EntryMeta,
+        subtype: Option<String>,
+        content: Option<String>,
+    },
+    /// Lines of an unknown or unexpected shape, passed through unchanged
+    Raw { value: Value },
+}
+
+/// A file touched by Read / Write / Edit tool calls in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileActivity {
+    /// Absolute path (relative paths are resolved against the project root)
+    pub path: String,
+    /// "read", "write" or "edit"
+    pub operation: String,
+    /// Number of tool calls with this path and operation
+    pub count: usize,
+    pub outside_project: bool,
+}
+
+/// A saved CLAUDE.md variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPromptPreset {
+    pub name: String,
+    pub content: String,
+    /// Unix timestamp (seconds) of the last save
+    pub updated_at: u64,
+    /// Whether CLAUDE.md currently has exactly this content
+    pub active: bool,
+}
+
+/// A named group of projects that are worked on together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    /// Absolute paths of the member projects
+    pub project_paths: Vec<String>,
+    /// Unix timestamp (seconds) of creation
+    pub created_at: u64,
+    /// Whether list_projects is scoped to this workspace when requested
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// A language or framework detected from marker files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedTechnology {
+    pub name: String,
+    /// 0.0–1.0; markers in the project root score highest
+    pub confidence: f32,
+    /// Relative paths of the marker files that matched
+    pub evidence: Vec<String>,
+}
+
+/// Languages and frameworks detected in a project directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectType {
+    /// Sorted by confidence, highest first
+    pub languages: Vec<DetectedTechnology>,
+    /// Sorted by confidence, highest first
+    pub frameworks: Vec<DetectedTechnology>,
+    pub primary_language: Option<String>,
+}
+
+/// A CLAUDE.md template used to scaffold project instructions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeMdTemplate {
+    pub name: String,
+    pub content: String,
+    /// Built into the app rather than read from the templates directory
+    pub builtin: bool,
+}
+
+/// A change to the dangerous-skip permission setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DangerousSkipEvent {
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// "enabled" or "disabled"
+    pub change: String,
+    /// The command or safeguard that made the change
+    pub action: String,
+}
+
+/// Audit trail for `--dangerously-skip-permissions`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DangerousSkipAudit {
+    pub enabled: bool,
+    /// RFC 3339 timestamp of the most recent enablement
+    pub enabled_at: Option<String>,
+    pub enabled_by: Option<String>,
+    /// Sessions launched with dangerous-skip since it was last enabled
+    pub sessions_launched: u32,
+    pub last_used_at: Option<String>,
+    /// Most recent changes, oldest first
+    pub history: Vec<DangerousSkipEvent>,
+}
+
+/// A file or pasted image attached to a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Attachment {
+    /// An existing file; relative paths are resolved against the project path
+    File { path: String },
+    /// Base64 image data, optionally as a `data:image/...;base64,` URL
+    Image {
+        data: String,
+        #[serde(default)]
+        media_type: Option<String>,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
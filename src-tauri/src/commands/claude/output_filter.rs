@@ -0,0 +1,102 @@
+//! 非 JSON 输出行的分类与过滤
+//!
+//! Claude CLI 偶尔会往 stdout 打印纯文本的升级提示、横幅等内容。运行器把每一行标记为
+//! `json` / `text` 后写入 `agent-output` 事件的 `kind` 字段；匹配噪声规则
+//! （执行配置的 `output_noise_patterns`）的文本行只写日志，不进入会话输出。
+
+use regex::Regex;
+use serde::Serialize;
+
+/// 输出行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum OutputLineKind {
+    Json,
+    Text,
+}
+
+impl OutputLineKind {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// 按行首字符和能否解析为 JSON 判断类型
+pub(super) fn classify_line(line: &str) -> OutputLineKind {
+    let trimmed = line.trim_start();
+    let looks_like_json = trimmed.starts_with('{') || trimmed.starts_with('[');
+    if looks_like_json && serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        OutputLineKind::Json
+    } else {
+        OutputLineKind::Text
+    }
+}
+
+/// 编译后的噪声规则
+pub(super) struct OutputNoiseFilter {
+    patterns: Vec<Regex>,
+}
+
+impl OutputNoiseFilter {
+    /// 编译配置中的规则，非法的正则记录警告后跳过
+    pub(super) fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!("Ignoring invalid output noise pattern '{}': {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// 只有文本行才可能是噪声
+    pub(super) fn is_noise(&self, kind: OutputLineKind, line: &str) -> bool {
+        kind == OutputLineKind::Text && self.patterns.iter().any(|regex| regex.is_match(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::permission_config::ClaudeExecutionConfig;
+
+    fn default_filter() -> OutputNoiseFilter {
+        OutputNoiseFilter::new(&ClaudeExecutionConfig::default().output_noise_patterns)
+    }
+
+    #[test]
+    fn classifies_json_and_text_lines() {
+        assert_eq!(
+            classify_line(r#"{"type":"system","subtype":"init"}"#),
+            OutputLineKind::Json
+        );
+        assert_eq!(classify_line("{ not json"), OutputLineKind::Text);
+        assert_eq!(classify_line("Warning: something"), OutputLineKind::Text);
+    }
+
+    #[test]
+    fn filters_known_banners_only() {
+        let filter = default_filter();
+        let banner = "Update available! Run: claude update";
+        assert!(filter.is_noise(classify_line(banner), banner));
+        let node = "(node:1234) ExperimentalWarning: Fetch API";
+        assert!(filter.is_noise(classify_line(node), node));
+
+        let json = r#"{"type":"assistant","text":"update available"}"#;
+        assert!(!filter.is_noise(classify_line(json), json));
+        assert!(!filter.is_noise(OutputLineKind::Text, "Compiling project"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let filter = OutputNoiseFilter::new(&["(".to_string(), "banner".to_string()]);
+        assert!(filter.is_noise(OutputLineKind::Text, "a banner line"));
+    }
+}
@@ -0,0 +1,235 @@
+//! 会话输出自动翻译
+//!
+//! 按会话开启后，运行器把完整的 assistant 消息（不含流式分片）交给翻译服务，
+//! 代码块原样保留，译文通过 `claude-output-translated:{session_id}` 事件与原文一并发送。
+//! 翻译服务未启用、失败或被限流时发送原文，并标记 `translated: false`。
+//! 开关一直保留到显式关闭或会话被删除。新会话在 CLI 分配 ID 之前按 tab_id 开启，
+//! 收到 init 消息后转到会话 ID 下。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::translator::translate_preserving_code;
+
+/// 开启了自动翻译的会话与标签页（目标语言为 None 表示自动判断）
+#[derive(Default)]
+struct TranslationSettings {
+    /// 会话 ID -> 目标语言
+    sessions: HashMap<String, Option<String>>,
+    /// 尚未拿到会话 ID 的新会话：tab_id -> 目标语言
+    tabs: HashMap<String, Option<String>>,
+}
+
+impl TranslationSettings {
+    /// 选择开关所在的表：有会话 ID 时按会话，否则按标签页
+    fn entries(
+        &mut self,
+        session_id: Option<String>,
+        tab_id: Option<String>,
+    ) -> Result<(&mut HashMap<String, Option<String>>, String), String> {
+        match (session_id, tab_id) {
+            (Some(session_id), _) => Ok((&mut self.sessions, session_id)),
+            (None, Some(tab_id)) => Ok((&mut self.tabs, tab_id)),
+            (None, None) => Err("Either a session id or a tab id is required".to_string()),
+        }
+    }
+}
+
+static TRANSLATION_SETTINGS: Lazy<Mutex<TranslationSettings>> =
+    Lazy::new(|| Mutex::new(TranslationSettings::default()));
+
+/// `claude-output-translated:{session_id}` 事件的负载
+#[derive(Debug, Clone, Serialize)]
+struct TranslatedOutputEvent {
+    session_id: String,
+    /// 对应 assistant 消息的 message.id，用于前端与原消息对应
+    message_id: Option<String>,
+    original: String,
+    text: String,
+    /// false 表示翻译不可用，`text` 为原文
+    translated: bool,
+}
+
+/// 拼接 assistant 消息中的文本块；流式分片（stream_event）和没有文本的消息返回 None
+fn assistant_text(msg: &Value) -> Option<String> {
+    if msg["type"] != "assistant" {
+        return None;
+    }
+    let texts: Vec<&str> = msg["message"]["content"]
+        .as_array()?
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    if texts.is_empty() {
+        return None;
+    }
+    Some(texts.join("\n\n"))
+}
+
+/// 会话开启了自动翻译时，在后台翻译完整的 assistant 消息并发送译文事件
+pub(super) fn translate_assistant_message(app: &AppHandle, session_id: &str, msg: &Value) {
+    let target_lang = match TRANSLATION_SETTINGS
+        .lock()
+        .unwrap()
+        .sessions
+        .get(session_id)
+    {
+        Some(target_lang) => target_lang.clone(),
+        None => return,
+    };
+    let Some(original) = assistant_text(msg) else {
+        return;
+    };
+
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    let message_id = msg["message"]["id"].as_str().map(str::to_string);
+    tokio::spawn(async move {
+        let text = match translate_preserving_code(&original, target_lang.as_deref()).await {
+            Ok(text) => text,
+            Err(e) => {
+                log::warn!(
+                    "Failed to translate output of session {}: {}",
+                    session_id,
+                    e
+                );
+                original.clone()
+            }
+        };
+        let event = TranslatedOutputEvent {
+            translated: text != original,
+            session_id: session_id.clone(),
+            message_id,
+            original,
+            text,
+        };
+        let _ = app.emit(&format!("claude-output-translated:{}", session_id), &event);
+    });
+}
+
+/// 新会话收到 init 消息后，把按标签页开启的翻译转到会话 ID 下
+pub(super) fn adopt_tab(tab_id: &str, session_id: &str) {
+    let mut settings = TRANSLATION_SETTINGS.lock().unwrap();
+    if let Some(target_lang) = settings.tabs.remove(tab_id) {
+        settings
+            .sessions
+            .insert(session_id.to_string(), target_lang);
+    }
+}
+
+/// 会话被删除后移除其自动翻译设置
+pub(super) fn clear_session(session_id: &str) {
+    TRANSLATION_SETTINGS
+        .lock()
+        .unwrap()
+        .sessions
+        .remove(session_id);
+}
+
+/// Enables or disables translating completed assistant messages of a session.
+/// A new session without an id yet is addressed by its tab id; the setting moves to
+/// the session id once the CLI reports it and lasts until disabled or the session is deleted.
+#[tauri::command]
+pub async fn set_output_translation(
+    session_id: Option<String>,
+    tab_id: Option<String>,
+    enabled: bool,
+    target_lang: Option<String>,
+) -> Result<(), String> {
+    let mut settings = TRANSLATION_SETTINGS.lock().unwrap();
+    let (entries, key) = settings.entries(session_id, tab_id)?;
+    if enabled {
+        entries.insert(key, target_lang);
+    } else {
+        entries.remove(&key);
+    }
+    Ok(())
+}
+
+/// Returns whether output translation is enabled for a session (or a new session's tab)
+#[tauri::command]
+pub async fn get_output_translation(
+    session_id: Option<String>,
+    tab_id: Option<String>,
+) -> Result<bool, String> {
+    let mut settings = TRANSLATION_SETTINGS.lock().unwrap();
+    let (entries, key) = settings.entries(session_id, tab_id)?;
+    Ok(entries.contains_key(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_complete_assistant_text_is_translated() {
+        let msg = serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "id": "msg_1",
+                "content": [
+                    { "type": "thinking", "thinking": "..." },
+                    { "type": "text", "text": "First" },
+                    { "type": "tool_use", "name": "Read", "input": {} },
+                    { "type": "text", "text": "Second" }
+                ]
+            }
+        });
+        assert_eq!(assistant_text(&msg).as_deref(), Some("First\n\nSecond"));
+
+        let partial = serde_json::json!({
+            "type": "stream_event",
+            "event": { "delta": { "type": "text_delta", "text": "Fir" } }
+        });
+        assert_eq!(assistant_text(&partial), None);
+
+        let tool_only = serde_json::json!({
+            "type": "assistant",
+            "message": { "content": [{ "type": "tool_use", "name": "Read" }] }
+        });
+        assert_eq!(assistant_text(&tool_only), None);
+    }
+
+    #[tokio::test]
+    async fn tab_setting_moves_to_the_session_and_survives_until_deleted() {
+        set_output_translation(
+            None,
+            Some("tab-1".to_string()),
+            true,
+            Some("zh".to_string()),
+        )
+        .await
+        .unwrap();
+        assert!(get_output_translation(None, Some("tab-1".to_string()))
+            .await
+            .unwrap());
+
+        adopt_tab("tab-1", "new-session");
+        assert!(!get_output_translation(None, Some("tab-1".to_string()))
+            .await
+            .unwrap());
+        assert_eq!(
+            TRANSLATION_SETTINGS
+                .lock()
+                .unwrap()
+                .sessions
+                .get("new-session"),
+            Some(&Some("zh".to_string()))
+        );
+
+        clear_session("new-session");
+        assert!(
+            !get_output_translation(Some("new-session".to_string()), None)
+                .await
+                .unwrap()
+        );
+        assert!(get_output_translation(None, None).await.is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
+use std::collections::BTreeSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Gets the path to the ~/.claude directory
 pub fn get_claude_dir() -> Result<PathBuf> {
@@ -67,10 +68,45 @@ pub fn decode_project_path(encoded: &str) -> String {
     }
 }
 
+/// 校验作为单个路径组件使用的 id / 名称（项目 id、会话 id 等），拒绝路径分隔符和 `..`，
+/// 防止拼接到 ~/.claude 下的路径后跳出目标目录
+pub fn validate_path_component(value: &str, what: &str) -> Result<(), String> {
+    if value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\']) {
+        return Err(format!("Invalid {}: {:?}", what, value));
+    }
+    Ok(())
+}
+
+/// 校验用作文件名（不含扩展名）的名称，如预设名、模板名：去掉首尾空白后只允许字母、数字、
+/// 空格、`-` 和 `_`，返回去掉空白后的名称
+pub fn validate_file_name<'a>(name: &'a str, what: &str) -> Result<&'a str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("The {} must not be empty", what));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+    {
+        return Err(format!(
+            "Invalid {} '{}': use letters, digits, spaces, '-' or '_'",
+            what, name
+        ));
+    }
+    Ok(name)
+}
+
 /// Normalize a path for comparison to detect duplicates
 /// This handles case sensitivity, path separators, trailing slashes, and platform-specific paths
+///
+/// 只在默认大小写不敏感的文件系统（Windows / macOS）上忽略大小写；Linux 上 /tmp/Project 与
+/// /tmp/project 是两个不同的目录
 pub fn normalize_path_for_comparison(path: &str) -> String {
-    let mut normalized = path.to_lowercase();
+    let mut normalized = if cfg!(any(target_os = "windows", target_os = "macos")) {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    };
 
     // ⚡ 修复：先处理双反斜杠（JSON 转义格式）
     // CC CLI 可能保存为 "C:\\Users\\..." 格式
@@ -130,3 +166,117 @@ pub fn normalize_path_for_comparison(path: &str) -> String {
 
     normalized
 }
+
+/// 一次性 CLI 调用（会话摘要、往返测试等）使用的临时工作目录
+///
+/// `claude -p` 会把每次调用记录为工作目录对应项目下的会话。丢弃时删除该目录，以及 Claude
+/// 在 ~/.claude/projects 下为它创建的项目目录，避免留下无关的会话
+pub struct ScratchProjectDir {
+    path: PathBuf,
+}
+
+impl ScratchProjectDir {
+    pub fn new() -> Result<Self, String> {
+        let path = std::env::temp_dir().join(format!("anycode-oneshot-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create scratch directory: {}", e))?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Claude 可能为该目录使用的项目目录名：路径可能经过符号链接解析（如 macOS 的
+    /// /var -> /private/var），较新的 CLI 还会把所有非字母数字字符替换为 `-`
+    fn project_dir_names(&self) -> BTreeSet<String> {
+        let mut paths = vec![self.path.to_string_lossy().into_owned()];
+        #[cfg(not(target_os = "windows"))]
+        if let Ok(canonical) = fs::canonicalize(&self.path) {
+            paths.push(canonical.to_string_lossy().into_owned());
+        }
+        paths
+            .iter()
+            .flat_map(|path| {
+                [
+                    encode_project_path(path),
+                    path.chars()
+                        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                        .collect(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// 删除 `projects_dir` 下指定名称的项目目录（不存在的跳过）
+fn remove_project_dirs(projects_dir: &Path, names: &BTreeSet<String>) {
+    for name in names {
+        let dir = projects_dir.join(name);
+        if dir.is_dir() {
+            if let Err(e) = fs::remove_dir_all(&dir) {
+                log::warn!("Failed to remove scratch session {}: {}", dir.display(), e);
+            }
+        }
+    }
+}
+
+impl Drop for ScratchProjectDir {
+    fn drop(&mut self) {
+        if let Some(home) = dirs::home_dir() {
+            remove_project_dirs(
+                &home.join(".claude").join("projects"),
+                &self.project_dir_names(),
+            );
+        }
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            log::warn!(
+                "Failed to remove scratch directory {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_that_are_not_plain_file_names() {
+        assert_eq!(
+            validate_file_name("  terse reviewer ", "preset name"),
+            Ok("terse reviewer")
+        );
+        assert_eq!(
+            validate_file_name("v2_verbose-mode", "preset name"),
+            Ok("v2_verbose-mode")
+        );
+        assert!(validate_file_name("", "preset name").is_err());
+        assert!(validate_file_name("../CLAUDE", "preset name").is_err());
+        assert!(validate_file_name("a/b", "template name").is_err());
+    }
+
+    #[test]
+    fn scratch_project_sessions_are_removed() {
+        let scratch = ScratchProjectDir::new().unwrap();
+        let names = scratch.project_dir_names();
+        assert!(names.contains(&encode_project_path(&scratch.path().to_string_lossy())));
+
+        let projects = tempfile::tempdir().unwrap();
+        let session_dir = projects.path().join(names.iter().next().unwrap());
+        fs::create_dir_all(&session_dir).unwrap();
+        fs::write(session_dir.join("session.jsonl"), "{}\n").unwrap();
+        let other_project = projects.path().join("-home-me-project");
+        fs::create_dir_all(&other_project).unwrap();
+
+        remove_project_dirs(projects.path(), &names);
+        assert!(!session_dir.exists());
+        assert!(other_project.exists());
+
+        let path = scratch.path().to_path_buf();
+        drop(scratch);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,197 @@
+//! 应用审批模式下的工具权限请求
+//!
+//! CLI 以 `--permission-prompt-tool stdio` 启动后，每次工具调用前会在 stdout 输出
+//! `control_request`（subtype 为 `can_use_tool`），并等待 stdin 上的 `control_response`。
+//! 这里把请求转成 `claude-permission-request` 事件，等待前端调用
+//! `respond_to_permission_request`；超时未响应时默认拒绝。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::oneshot;
+
+use crate::error::AppResult;
+
+/// 前端未响应时自动拒绝的等待时间
+pub const PERMISSION_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Event asking the frontend to approve a tool call
+const PERMISSION_REQUEST_EVENT: &str = "claude-permission-request";
+/// Event reporting how a permission request was resolved (approved / denied / timed out)
+const PERMISSION_RESOLVED_EVENT: &str = "claude-permission-resolved";
+
+/// Shared stdin of a Claude process running in app-approval mode; None once closed
+pub type SharedStdin = Arc<tokio::sync::Mutex<Option<ChildStdin>>>;
+
+static PENDING_PERMISSION_REQUESTS: Lazy<Mutex<HashMap<String, oneshot::Sender<bool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns true if the stdout line is a tool permission request from the CLI
+pub fn is_permission_request(msg: &Value) -> bool {
+    msg["type"] == "control_request" && msg["request"]["subtype"] == "can_use_tool"
+}
+
+/// Emit a permission request to the frontend and answer the CLI once it is resolved
+///
+/// Runs in its own task so the stdout loop keeps reading while the user decides.
+pub fn handle_permission_request(
+    app: AppHandle,
+    stdin: SharedStdin,
+    msg: Value,
+    session_id: Option<String>,
+    tab_id: Option<String>,
+) {
+    let Some(cli_request_id) = msg["request_id"].as_str().map(str::to_string) else {
+        log::warn!("Ignoring permission request without request_id");
+        return;
+    };
+    let tool_name = msg["request"]["tool_name"].clone();
+    let input = msg["request"]["input"].clone();
+
+    // 使用应用自己的 id，避免不同会话的 CLI request_id 冲突
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (sender, receiver) = oneshot::channel();
+    PENDING_PERMISSION_REQUESTS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), sender);
+
+    let _ = app.emit(
+        PERMISSION_REQUEST_EVENT,
+        &serde_json::json!({
+            "request_id": request_id,
+            "session_id": session_id,
+            "tab_id": tab_id,
+            "tool_name": tool_name,
+            "input": input,
+            "timeout_secs": PERMISSION_REQUEST_TIMEOUT.as_secs(),
+        }),
+    );
+    log::info!(
+        "Waiting for approval of {} (request {})",
+        tool_name,
+        request_id
+    );
+
+    tokio::spawn(async move {
+        let (allow, reason) = match tokio::time::timeout(PERMISSION_REQUEST_TIMEOUT, receiver).await
+        {
+            Ok(Ok(allow)) => (allow, if allow { "approved" } else { "denied" }),
+            // 发送端被丢弃（不应发生）按拒绝处理
+            Ok(Err(_)) => (false, "denied"),
+            Err(_) => {
+                PENDING_PERMISSION_REQUESTS
+                    .lock()
+                    .unwrap()
+                    .remove(&request_id);
+                log::warn!("Permission request {} timed out, denying", request_id);
+                (false, "timeout")
+            }
+        };
+
+        let response = build_permission_response(&cli_request_id, allow, &input, reason);
+        if let Some(stdin) = stdin.lock().await.as_mut() {
+            let line = format!("{}\n", response);
+            if let Err(e) = stdin.write_all(line.as_bytes()).await {
+                log::warn!("Failed to send permission response to Claude: {}", e);
+            }
+        }
+
+        let _ = app.emit(
+            PERMISSION_RESOLVED_EVENT,
+            &serde_json::json!({
+                "request_id": request_id,
+                "session_id": session_id,
+                "allow": allow,
+                "reason": reason,
+            }),
+        );
+    });
+}
+
+/// Build the control_response line answering a can_use_tool request
+fn build_permission_response(
+    cli_request_id: &str,
+    allow: bool,
+    input: &Value,
+    reason: &str,
+) -> Value {
+    let decision = if allow {
+        serde_json::json!({ "behavior": "allow", "updatedInput": input })
+    } else {
+        let message = if reason == "timeout" {
+            "Permission request timed out and was denied by the app"
+        } else {
+            "Permission denied by the user"
+        };
+        serde_json::json!({ "behavior": "deny", "message": message })
+    };
+
+    serde_json::json!({
+        "type": "control_response",
+        "response": {
+            "subtype": "success",
+            "request_id": cli_request_id,
+            "response": decision,
+        }
+    })
+}
+
+/// Approve or deny a pending tool permission request
+/// Returns false if the request is unknown or has already timed out
+#[tauri::command]
+pub async fn respond_to_permission_request(request_id: String, allow: bool) -> AppResult<bool> {
+    let sender = PENDING_PERMISSION_REQUESTS
+        .lock()
+        .unwrap()
+        .remove(&request_id);
+    Ok(match sender {
+        Some(sender) => sender.send(allow).is_ok(),
+        None => {
+            log::warn!("Permission request {} is no longer pending", request_id);
+            false
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_allow_and_deny_control_responses() {
+        let input = serde_json::json!({ "command": "ls" });
+
+        let allow = build_permission_response("req-1", true, &input, "approved");
+        assert_eq!(allow["type"], "control_response");
+        assert_eq!(allow["response"]["request_id"], "req-1");
+        assert_eq!(allow["response"]["response"]["behavior"], "allow");
+        assert_eq!(allow["response"]["response"]["updatedInput"], input);
+
+        let deny = build_permission_response("req-2", false, &input, "timeout");
+        assert_eq!(deny["response"]["response"]["behavior"], "deny");
+        assert!(deny["response"]["response"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("timed out"));
+    }
+
+    #[test]
+    fn detects_can_use_tool_requests() {
+        let request = serde_json::json!({
+            "type": "control_request",
+            "request_id": "abc",
+            "request": { "subtype": "can_use_tool", "tool_name": "Bash", "input": {} }
+        });
+        assert!(is_permission_request(&request));
+
+        let other = serde_json::json!({ "type": "assistant", "message": {} });
+        assert!(!is_permission_request(&other));
+    }
+}
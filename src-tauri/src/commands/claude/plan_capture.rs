@@ -0,0 +1,216 @@
+//! Plan 模式结果捕获
+//!
+//! Plan 模式下 Claude 只给出计划而不执行。运行器从 stream-json 输出中识别计划
+//! （优先取 ExitPlanMode 工具调用的 `plan` 参数，没有调用时退回到最终 result 文本），
+//! 发送 `claude-plan-ready` 事件并按会话保存，供 `get_last_plan` 查询。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+/// 每个会话最近一次捕获的计划
+static LAST_PLANS: Lazy<Mutex<HashMap<String, CapturedPlan>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanSource {
+    /// ExitPlanMode 工具调用的参数
+    ExitPlanMode,
+    /// 没有调用 ExitPlanMode 时的最终 result 文本
+    ResultMessage,
+}
+
+/// 从 Plan 模式输出中捕获的计划
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedPlan {
+    pub session_id: Option<String>,
+    /// 计划原文（Markdown）
+    pub plan: String,
+    /// 从列表项中提取的步骤
+    pub steps: Vec<String>,
+    pub source: PlanSource,
+    pub captured_at: String,
+}
+
+/// `claude-plan-ready` 事件的负载；会话结束仍没有计划时 `plan` 为 null
+#[derive(Debug, Clone, Serialize)]
+struct PlanReadyEvent<'a> {
+    session_id: Option<&'a str>,
+    tab_id: Option<&'a str>,
+    plan: Option<&'a CapturedPlan>,
+}
+
+/// 启动参数是否为 Plan 模式（`--permission-mode plan`）
+pub(super) fn is_plan_mode(args: &[String]) -> bool {
+    args.windows(2)
+        .any(|pair| pair[0] == "--permission-mode" && pair[1] == "plan")
+}
+
+/// 从 assistant 消息中提取 ExitPlanMode 工具调用的计划
+pub(super) fn detect_exit_plan_mode(msg: &Value) -> Option<String> {
+    if msg["type"] != "assistant" {
+        return None;
+    }
+    msg["message"]["content"]
+        .as_array()?
+        .iter()
+        .find_map(|block| {
+            if block["type"] == "tool_use" && block["name"] == "ExitPlanMode" {
+                block["input"]["plan"]
+                    .as_str()
+                    .filter(|plan| !plan.trim().is_empty())
+                    .map(str::to_string)
+            } else {
+                None
+            }
+        })
+}
+
+/// 成功结束时 result 消息中的最终文本
+pub(super) fn detect_result_text(msg: &Value) -> Option<String> {
+    if msg["type"] != "result" || msg["is_error"] == true {
+        return None;
+    }
+    msg["result"]
+        .as_str()
+        .filter(|text| !text.trim().is_empty())
+        .map(str::to_string)
+}
+
+/// 提取 Markdown 列表项（`-` / `*` / `+` / `1.` / `1)`）作为步骤
+fn parse_steps(plan: &str) -> Vec<String> {
+    plan.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let item = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .or_else(|| line.strip_prefix("+ "))
+                .or_else(|| {
+                    let digits =
+                        line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                    if digits == 0 {
+                        return None;
+                    }
+                    line[digits..]
+                        .strip_prefix(". ")
+                        .or_else(|| line[digits..].strip_prefix(") "))
+                })?;
+            let item = item
+                .strip_prefix("[ ] ")
+                .or_else(|| item.strip_prefix("[x] "))
+                .unwrap_or(item)
+                .trim();
+            (!item.is_empty()).then(|| item.to_string())
+        })
+        .collect()
+}
+
+fn emit_plan_ready(app: &AppHandle, event: &PlanReadyEvent) {
+    if let Some(session_id) = event.session_id {
+        let _ = app.emit(&format!("claude-plan-ready:{}", session_id), event);
+    }
+    let _ = app.emit("claude-plan-ready", event);
+}
+
+/// 保存捕获到的计划并发送 `claude-plan-ready` 事件
+pub(super) fn capture_plan(
+    app: &AppHandle,
+    session_id: Option<String>,
+    tab_id: Option<&str>,
+    plan: String,
+    source: PlanSource,
+) {
+    let captured = CapturedPlan {
+        session_id,
+        steps: parse_steps(&plan),
+        plan,
+        source,
+        captured_at: chrono::Utc::now().to_rfc3339(),
+    };
+    log::info!(
+        "Captured plan for session {:?} from {:?} ({} steps)",
+        captured.session_id,
+        source,
+        captured.steps.len()
+    );
+    if let Some(session_id) = &captured.session_id {
+        if let Ok(mut plans) = LAST_PLANS.lock() {
+            plans.insert(session_id.clone(), captured.clone());
+        }
+    }
+    emit_plan_ready(
+        app,
+        &PlanReadyEvent {
+            session_id: captured.session_id.as_deref(),
+            tab_id,
+            plan: Some(&captured),
+        },
+    );
+}
+
+/// Plan 模式会话结束却没有产生计划：清除旧计划并发送 `plan` 为 null 的事件
+pub(super) fn report_missing_plan(app: &AppHandle, session_id: Option<&str>, tab_id: Option<&str>) {
+    log::warn!("Plan mode session {:?} finished without a plan", session_id);
+    if let Some(session_id) = session_id {
+        if let Ok(mut plans) = LAST_PLANS.lock() {
+            plans.remove(session_id);
+        }
+    }
+    emit_plan_ready(
+        app,
+        &PlanReadyEvent {
+            session_id,
+            tab_id,
+            plan: None,
+        },
+    );
+}
+
+/// Returns the plan captured from the latest plan-mode run of a session, or null if it produced none
+#[tauri::command]
+pub async fn get_last_plan(session_id: String) -> Result<Option<CapturedPlan>, String> {
+    let plans = LAST_PLANS
+        .lock()
+        .map_err(|e| format!("Plan store lock poisoned: {}", e))?;
+    Ok(plans.get(&session_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_exit_plan_mode_and_parses_steps() {
+        let msg = json!({
+            "type": "assistant",
+            "message": {"content": [
+                {"type": "text", "text": "Here is the plan"},
+                {"type": "tool_use", "name": "ExitPlanMode", "input": {
+                    "plan": "## Plan\n1. Add the parser\n2) Wire it up\n- [ ] Write tests\nDone."
+                }}
+            ]}
+        });
+        let plan = detect_exit_plan_mode(&msg).unwrap();
+        assert_eq!(
+            parse_steps(&plan),
+            vec!["Add the parser", "Wire it up", "Write tests"]
+        );
+        assert_eq!(detect_exit_plan_mode(&json!({"type": "result"})), None);
+
+        assert!(is_plan_mode(&[
+            "--permission-mode".to_string(),
+            "plan".to_string()
+        ]));
+        assert_eq!(
+            detect_result_text(&json!({"type": "result", "is_error": false, "result": "ok"})),
+            Some("ok".to_string())
+        );
+    }
+}
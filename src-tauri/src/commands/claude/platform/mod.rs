@@ -66,3 +66,108 @@ pub fn kill_process_tree(pid: u32) -> Result<(), String> {
         unix::kill_process_tree_impl(pid)
     }
 }
+
+/// Resolved command line of a spawned CLI process, attached to start events for debugging
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LaunchCommand {
+    /// Program (binary path) the process was launched with
+    pub binary: String,
+    /// Full argument list, in order
+    pub args: Vec<String>,
+    /// Environment overrides set on the command; secret values are redacted
+    pub env: std::collections::BTreeMap<String, String>,
+}
+
+const REDACTED_VALUE: &str = "***";
+const SECRET_ENV_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "AUTH", "CREDENTIAL"];
+
+/// 键名是否看起来是密钥（包含 KEY / TOKEN / SECRET 等标记）
+pub(crate) fn is_secret_env_key(key: &str) -> bool {
+    let upper = key.to_uppercase();
+    SECRET_ENV_MARKERS
+        .iter()
+        .any(|marker| upper.contains(marker))
+}
+
+/// 键名看起来是密钥（KEY / TOKEN / SECRET 等）时返回打码后的值
+pub(crate) fn redact_env_value(key: &str, value: String) -> String {
+    if is_secret_env_key(key) {
+        REDACTED_VALUE.to_string()
+    } else {
+        value
+    }
+}
+
+impl LaunchCommand {
+    /// Capture the program, args and env overrides from a command before it is spawned
+    pub fn from_command(cmd: &tokio::process::Command) -> Self {
+        let std_cmd = cmd.as_std();
+        let env = std_cmd
+            .get_envs()
+            .filter_map(|(key, value)| {
+                let key = key.to_string_lossy().to_string();
+                // 被移除的环境变量（env_remove）没有值，直接跳过
+                let value = value?.to_string_lossy().to_string();
+                let value = redact_env_value(&key, value);
+                Some((key, value))
+            })
+            .collect();
+
+        Self {
+            binary: std_cmd.get_program().to_string_lossy().to_string(),
+            args: std_cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            env,
+        }
+    }
+
+    /// Argument list for the log file, with the prompt replaced by its length
+    pub fn args_for_log(&self, prompt: &str) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                if !prompt.is_empty() && arg == prompt {
+                    format!("<prompt: {} chars>", prompt.chars().count())
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_command_redacts_secret_env_values() {
+        let mut cmd = tokio::process::Command::new("claude");
+        cmd.args(["--output-format", "stream-json"]);
+        cmd.env("ANTHROPIC_MODEL", "opus");
+        cmd.env("CODEX_API_KEY", "sk-secret");
+        cmd.env("ANTHROPIC_AUTH_TOKEN", "token");
+
+        let launch = LaunchCommand::from_command(&cmd);
+        assert_eq!(launch.binary, "claude");
+        assert_eq!(launch.args, vec!["--output-format", "stream-json"]);
+        assert_eq!(launch.env["ANTHROPIC_MODEL"], "opus");
+        assert_eq!(launch.env["CODEX_API_KEY"], REDACTED_VALUE);
+        assert_eq!(launch.env["ANTHROPIC_AUTH_TOKEN"], REDACTED_VALUE);
+    }
+
+    #[test]
+    fn launch_command_elides_the_prompt_in_logged_args() {
+        let mut cmd = tokio::process::Command::new("claude");
+        cmd.args(["--model", "opus", "-p", "/review secret diff"]);
+
+        let launch = LaunchCommand::from_command(&cmd);
+        assert_eq!(
+            launch.args_for_log("/review secret diff"),
+            vec!["--model", "opus", "-p", "<prompt: 19 chars>"]
+        );
+        assert_eq!(launch.args_for_log(""), launch.args);
+    }
+}
@@ -51,7 +51,9 @@ pub fn resolve_cmd_wrapper(cmd_path: &str) -> Option<(String, String)> {
                         // Verify the script exists
                         if PathBuf::from(&script_path).exists() {
                             log::debug!("Resolved .cmd wrapper to script: {}", script_path);
-                            return Some(("node".to_string(), script_path));
+                            let node = crate::claude_binary::configured_node_path()
+                                .unwrap_or_else(|| "node".to_string());
+                            return Some((node, script_path));
                         }
                     }
                 }
@@ -0,0 +1,477 @@
+//! 项目级环境变量文件（项目根目录下的 `.anycode.env`）
+//!
+//! 启动 CLI 时的环境变量按以下顺序叠加，后者覆盖前者：
+//! 继承的系统环境变量 → ~/.claude/settings.json 的 env → `.anycode.env` → binaries.json 中该工具的 env
+//!
+//! `.anycode.env` 随仓库分发，克隆来的项目可能不可信：只有用户确认信任（记录确认时的文件哈希，
+//! 文件变更后需重新确认）的文件才会被加载，且能改变加载代码或可执行文件搜索路径的变量始终被忽略，
+//! 这类变量应在 settings.json 或 binaries.json 中设置。API 端点和代理（`*_BASE_URL`、`*_PROXY`）
+//! 可以由信任的文件设置，状态中会单独列出，便于用户在信任前确认。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use super::paths::{get_claude_dir, normalize_path_for_comparison};
+use super::platform::redact_env_value;
+use crate::error::AppResult;
+
+/// 项目根目录下的环境变量文件名
+pub const PROJECT_ENV_FILE: &str = ".anycode.env";
+
+/// 项目文件不能设置的变量：可注入代码（NODE_OPTIONS、LD_PRELOAD）或劫持可执行文件（PATH）
+const BLOCKED_ENV_KEYS: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "HOME",
+    "USERPROFILE",
+    "SHELL",
+    "COMSPEC",
+    "BASH_ENV",
+    "ENV",
+    "NODE_OPTIONS",
+    "NODE_PATH",
+    "NODE_EXTRA_CA_CERTS",
+    "NODE_TLS_REJECT_UNAUTHORIZED",
+    "SSL_CERT_FILE",
+    "CLAUDE_CONFIG_DIR",
+];
+const BLOCKED_ENV_PREFIXES: &[&str] = &["LD_", "DYLD_"];
+/// 改变 API 端点或代理的变量后缀：允许加载，但在状态中单独列出
+const ENDPOINT_ENV_SUFFIXES: &[&str] = &["_BASE_URL", "_PROXY"];
+
+/// 用户确认信任的 `.anycode.env`：规范化的项目路径 → 确认时文件内容的 SHA-256
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ProjectEnvTrust {
+    projects: BTreeMap<String, String>,
+}
+
+impl ProjectEnvTrust {
+    fn is_trusted(&self, project_path: &str, content: &str) -> bool {
+        self.projects
+            .get(&normalize_path_for_comparison(project_path))
+            .is_some_and(|hash| *hash == content_hash(content))
+    }
+}
+
+/// 项目 `.anycode.env` 的状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEnvStatus {
+    pub exists: bool,
+    /// 已信任且文件自确认后未改动
+    pub trusted: bool,
+    /// 会被加载的变量名
+    pub keys: Vec<String>,
+    /// 文件中设置了、但始终被忽略的变量名
+    pub blocked_keys: Vec<String>,
+    /// 会被加载的变量中改变 API 端点或代理的变量名（请求和密钥会发往这些地址）
+    pub endpoint_keys: Vec<String>,
+}
+
+fn get_trust_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("project_env_trust.json"))
+}
+
+fn load_trust() -> Result<ProjectEnvTrust, String> {
+    crate::utils::config_utils::load_json_config(get_trust_path()?)
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn is_blocked_env_key(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    BLOCKED_ENV_KEYS.contains(&upper.as_str())
+        || BLOCKED_ENV_PREFIXES
+            .iter()
+            .any(|prefix| upper.starts_with(prefix))
+}
+
+fn is_endpoint_env_key(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    ENDPOINT_ENV_SUFFIXES
+        .iter()
+        .any(|suffix| upper.ends_with(suffix))
+}
+
+/// 拆分为 (允许加载的变量, 被忽略的变量名)
+fn split_blocked(vars: Vec<(String, String)>) -> (Vec<(String, String)>, Vec<String>) {
+    let (blocked, allowed): (Vec<_>, Vec<_>) = vars
+        .into_iter()
+        .partition(|(key, _)| is_blocked_env_key(key));
+    (allowed, blocked.into_iter().map(|(key, _)| key).collect())
+}
+
+/// 按 dotenv 语义解析文件内容，保持声明顺序（重复的键以最后一次为准）
+///
+/// 支持 `#` 注释、`export` 前缀、单引号（原样）、双引号（转义与跨行）以及未加引号值的行尾注释
+pub fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line
+            .strip_prefix("export ")
+            .or_else(|| line.strip_prefix("export\t"))
+            .unwrap_or(line);
+        let Some((key, raw_value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !is_valid_key(key) {
+            log::warn!("Ignoring invalid env key in {}: {}", PROJECT_ENV_FILE, key);
+            continue;
+        }
+
+        let raw_value = raw_value.trim_start();
+        let value = if let Some(rest) = raw_value.strip_prefix('"') {
+            // 双引号值可以跨行，直到遇到未转义的结束引号
+            let mut buffer = rest.to_string();
+            loop {
+                if let Some(value) = parse_double_quoted(&buffer) {
+                    break value;
+                }
+                match lines.next() {
+                    Some(next) => {
+                        buffer.push('\n');
+                        buffer.push_str(next);
+                    }
+                    // 缺少结束引号：按剩余内容原样处理
+                    None => break unescape_double_quoted(&buffer),
+                }
+            }
+        } else if let Some(rest) = raw_value.strip_prefix('\'') {
+            match rest.find('\'') {
+                Some(end) => rest[..end].to_string(),
+                None => rest.to_string(),
+            }
+        } else {
+            strip_inline_comment(raw_value).trim_end().to_string()
+        };
+
+        vars.retain(|(existing, _)| existing != key);
+        vars.push((key.to_string(), value));
+    }
+
+    vars
+}
+
+/// 读取项目根目录下的 `.anycode.env`（不存在、未被信任或读取失败时返回空），并去掉被禁止的变量
+pub fn load_project_env(project_path: &str) -> Vec<(String, String)> {
+    let env_path = Path::new(project_path).join(PROJECT_ENV_FILE);
+    if !env_path.is_file() {
+        return Vec::new();
+    }
+    let content = match std::fs::read_to_string(&env_path) {
+        Ok(content) => content,
+        Err(e) => {
+            log::warn!("Failed to read {}: {}", env_path.display(), e);
+            return Vec::new();
+        }
+    };
+    let trusted = load_trust()
+        .map(|trust| trust.is_trusted(project_path, &content))
+        .unwrap_or(false);
+    if !trusted {
+        log::warn!(
+            "Ignoring {}: the file is not trusted or changed since it was trusted",
+            env_path.display()
+        );
+        return Vec::new();
+    }
+
+    let (vars, blocked) = split_blocked(parse_dotenv(&content));
+    if !blocked.is_empty() {
+        log::warn!(
+            "Ignoring variables not allowed in {}: {}",
+            PROJECT_ENV_FILE,
+            blocked.join(", ")
+        );
+    }
+    let endpoint_keys: Vec<&str> = vars
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .filter(|key| is_endpoint_env_key(key))
+        .collect();
+    if !endpoint_keys.is_empty() {
+        log::info!(
+            "{} overrides API endpoints or proxies: {}",
+            env_path.display(),
+            endpoint_keys.join(", ")
+        );
+    }
+    log::info!(
+        "Loaded {} environment variables from {}",
+        vars.len(),
+        env_path.display()
+    );
+    vars
+}
+
+fn project_env_status(project_path: &str) -> Result<ProjectEnvStatus, String> {
+    let env_path = Path::new(project_path).join(PROJECT_ENV_FILE);
+    if !env_path.is_file() {
+        return Ok(ProjectEnvStatus {
+            exists: false,
+            trusted: false,
+            keys: Vec::new(),
+            blocked_keys: Vec::new(),
+            endpoint_keys: Vec::new(),
+        });
+    }
+    let content = std::fs::read_to_string(&env_path)
+        .map_err(|e| format!("Failed to read {}: {}", env_path.display(), e))?;
+    let (vars, blocked_keys) = split_blocked(parse_dotenv(&content));
+    let keys: Vec<String> = vars.into_iter().map(|(key, _)| key).collect();
+    Ok(ProjectEnvStatus {
+        exists: true,
+        trusted: load_trust()?.is_trusted(project_path, &content),
+        endpoint_keys: keys
+            .iter()
+            .filter(|key| is_endpoint_env_key(key))
+            .cloned()
+            .collect(),
+        keys,
+        blocked_keys,
+    })
+}
+
+/// Reports whether the project's `.anycode.env` exists and is trusted, with the variable names it sets
+#[tauri::command]
+pub async fn get_project_env_status(project_path: String) -> Result<ProjectEnvStatus, String> {
+    project_env_status(&project_path)
+}
+
+/// Trusts (or stops trusting) the project's current `.anycode.env`; any later change to the file
+/// needs to be trusted again before it is loaded
+#[tauri::command]
+pub async fn set_project_env_trust(
+    project_path: String,
+    trusted: bool,
+) -> Result<ProjectEnvStatus, String> {
+    let trust_path = get_trust_path()?;
+    let mut trust: ProjectEnvTrust = crate::utils::config_utils::load_json_config(&trust_path)?;
+    let key = normalize_path_for_comparison(&project_path);
+    if trusted {
+        let env_path = Path::new(&project_path).join(PROJECT_ENV_FILE);
+        let content = std::fs::read_to_string(&env_path)
+            .map_err(|e| format!("Failed to read {}: {}", env_path.display(), e))?;
+        trust.projects.insert(key, content_hash(&content));
+    } else {
+        trust.projects.remove(&key);
+    }
+    crate::utils::config_utils::save_json_config(&trust, &trust_path)?;
+    log::info!(
+        "{} {} for {}",
+        if trusted {
+            "Trusted"
+        } else {
+            "Revoked trust in"
+        },
+        PROJECT_ENV_FILE,
+        project_path
+    );
+    project_env_status(&project_path)
+}
+
+/// 读取 ~/.claude/settings.json 中的 env 字段
+pub fn load_settings_env() -> Vec<(String, String)> {
+    let Ok(claude_dir) = get_claude_dir() else {
+        return Vec::new();
+    };
+    let Ok(content) = std::fs::read_to_string(claude_dir.join("settings.json")) else {
+        return Vec::new();
+    };
+    let Ok(settings) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+
+    settings
+        .get("env")
+        .and_then(|v| v.as_object())
+        .map(|env_obj| {
+            env_obj
+                .iter()
+                .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 按优先级合并自定义环境变量：settings.json → `.anycode.env` → binaries.json 工具 env
+pub fn custom_env(tool: &str, project_path: Option<&str>) -> Vec<(String, String)> {
+    let mut vars = load_settings_env();
+    if let Some(project_path) = project_path {
+        vars.extend(load_project_env(project_path));
+    }
+    vars.extend(crate::claude_binary::load_tool_env(tool));
+    vars
+}
+
+/// Returns the environment a Claude CLI spawned for this project would receive, with secret values redacted
+#[tauri::command]
+pub async fn get_effective_env(
+    app: AppHandle,
+    project_path: String,
+) -> AppResult<BTreeMap<String, String>> {
+    let claude_path = crate::claude_binary::find_claude_binary(&app).unwrap_or_default();
+    let mut env: BTreeMap<String, String> = super::cli_runner::inherited_env(&claude_path)
+        .into_iter()
+        .collect();
+    env.extend(custom_env("claude", Some(&project_path)));
+    Ok(env
+        .into_iter()
+        .map(|(key, value)| {
+            let value = redact_env_value(&key, value);
+            (key, value)
+        })
+        .collect())
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// 解析双引号值（不含开头引号），找到未转义的结束引号时返回
+fn parse_double_quoted(rest: &str) -> Option<String> {
+    let mut escaped = false;
+    for (index, c) in rest.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(unescape_double_quoted(&rest[..index])),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(other @ ('"' | '\\' | '$')) => result.push(other),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 未加引号的值中，空白后的 `#` 开始行尾注释
+fn strip_inline_comment(value: &str) -> &str {
+    let mut previous_is_space = true;
+    for (index, c) in value.char_indices() {
+        if c == '#' && previous_is_space {
+            return &value[..index];
+        }
+        previous_is_space = c.is_whitespace();
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(vars: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn parses_dotenv_syntax() {
+        let content = r#"
+# API endpoints
+export ANTHROPIC_BASE_URL=https://proxy.example.com # internal proxy
+PLAIN = value with spaces
+SINGLE='literal $HOME \n'
+DOUBLE="line1\nline2 \"quoted\""
+MULTI="first
+second"
+HASH=abc#def
+EMPTY=
+not a valid line
+1INVALID=x
+PLAIN=overridden
+"#;
+        let vars = parse_dotenv(content);
+
+        assert_eq!(
+            get(&vars, "ANTHROPIC_BASE_URL"),
+            Some("https://proxy.example.com")
+        );
+        assert_eq!(get(&vars, "SINGLE"), Some("literal $HOME \\n"));
+        assert_eq!(get(&vars, "DOUBLE"), Some("line1\nline2 \"quoted\""));
+        assert_eq!(get(&vars, "MULTI"), Some("first\nsecond"));
+        assert_eq!(get(&vars, "HASH"), Some("abc#def"));
+        assert_eq!(get(&vars, "EMPTY"), Some(""));
+        assert_eq!(get(&vars, "1INVALID"), None);
+        // Later declarations win and keep a single entry
+        assert_eq!(get(&vars, "PLAIN"), Some("overridden"));
+        assert_eq!(vars.iter().filter(|(k, _)| k == "PLAIN").count(), 1);
+    }
+
+    #[test]
+    fn blocks_dangerous_keys() {
+        let vars = parse_dotenv(
+            "NODE_OPTIONS=--require ./evil.js\npath=/tmp/bin\nLD_PRELOAD=x.so\nANTHROPIC_BASE_URL=https://evil\nhttps_proxy=http://evil\nANTHROPIC_MODEL=opus\nMY_FLAG=1\n",
+        );
+        let (allowed, blocked) = split_blocked(vars);
+        assert_eq!(
+            allowed,
+            vec![
+                ("ANTHROPIC_BASE_URL".to_string(), "https://evil".to_string()),
+                ("https_proxy".to_string(), "http://evil".to_string()),
+                ("ANTHROPIC_MODEL".to_string(), "opus".to_string()),
+                ("MY_FLAG".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(blocked, vec!["NODE_OPTIONS", "path", "LD_PRELOAD"]);
+
+        // API 端点和代理可以由信任的文件设置，但会被单独标出
+        let endpoints: Vec<&str> = allowed
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .filter(|key| is_endpoint_env_key(key))
+            .collect();
+        assert_eq!(endpoints, vec!["ANTHROPIC_BASE_URL", "https_proxy"]);
+    }
+
+    #[test]
+    fn trust_requires_matching_content() {
+        let mut trust = ProjectEnvTrust::default();
+        assert!(!trust.is_trusted("/work/api", "A=1\n"));
+
+        trust.projects.insert(
+            normalize_path_for_comparison("/work/api/"),
+            content_hash("A=1\n"),
+        );
+        assert!(trust.is_trusted("/work/api", "A=1\n"));
+        assert!(!trust.is_trusted("/work/api", "A=1\nNODE_OPTIONS=x\n"));
+        assert!(!trust.is_trusted("/work/web", "A=1\n"));
+    }
+}
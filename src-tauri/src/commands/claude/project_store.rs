@@ -4,18 +4,58 @@ use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::models::{Project, Session};
-use super::paths::{decode_project_path, get_claude_dir, normalize_path_for_comparison};
-use super::session_history::{
-    extract_first_user_message, extract_last_message_timestamp, extract_session_model,
+use super::models::{
+    Project, ProjectDiskUsage, Session, SessionCleanupCandidate, SessionCleanupCriteria, Workspace,
 };
+use super::paths::{
+    decode_project_path, encode_project_path, get_claude_dir, normalize_path_for_comparison,
+    validate_path_component,
+};
+use super::session_history::session_stats;
 
 pub struct ProjectStore {
     claude_dir: PathBuf,
 }
 
+/// 置顶的项目与会话（pinned_items.json）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PinnedItems {
+    #[serde(default)]
+    projects: Vec<String>,
+    #[serde(default)]
+    sessions: Vec<String>,
+}
+
+/// 会话实际运行目录与所属项目（session_working_dirs.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionWorkingDir {
+    project_path: String,
+    working_dir: String,
+}
+
+/// 工作区列表（workspaces.json）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspacesFile {
+    #[serde(default)]
+    workspaces: Vec<Workspace>,
+}
+
+/// 带有该标签（不区分大小写）的会话不会被批量清理
+const PROTECTED_SESSION_TAG: &str = "favorite";
+
+/// 清理时参与筛选的会话文件信息
+#[derive(Debug, Clone)]
+struct SessionFileInfo {
+    session_id: String,
+    bytes: u64,
+    modified: SystemTime,
+    protected: bool,
+}
+
 pub struct BatchDeleteOutcome {
     pub deleted_count: usize,
     pub failed_count: usize,
@@ -34,6 +74,8 @@ impl ProjectStore {
         let mut all_projects = Vec::new();
         let projects_dir = self.projects_dir();
         let mut hidden_projects = self.load_hidden_projects()?;
+        let imported_paths = self.load_imported_project_paths()?;
+        let pinned = self.load_pinned_items()?;
 
         if projects_dir.exists() {
             let entries = fs::read_dir(&projects_dir)
@@ -89,17 +131,7 @@ impl ProjectStore {
                         .unwrap_or_default()
                         .as_secs();
 
-                    let project_path = match get_project_path_from_sessions(&path) {
-                        Ok(path) => path,
-                        Err(e) => {
-                            log::warn!(
-                                "Failed to get project path from sessions for {}: {}, falling back to decode",
-                                dir_name,
-                                e
-                            );
-                            decode_project_path(dir_name)
-                        }
-                    };
+                    let project_path = resolve_project_path(&path, dir_name, &imported_paths);
 
                     let mut sessions = Vec::new();
                     let mut latest_activity = created_at;
@@ -114,9 +146,7 @@ impl ProjectStore {
                                 if let Some(session_id) =
                                     session_path.file_stem().and_then(|s| s.to_str())
                                 {
-                                    let (first_message, _) =
-                                        extract_first_user_message(&session_path);
-                                    if first_message.is_some() {
+                                    if session_stats(&session_path).first_message.is_some() {
                                         sessions.push(session_id.to_string());
 
                                         if let Ok(session_metadata) = fs::metadata(&session_path) {
@@ -142,6 +172,7 @@ impl ProjectStore {
                         path: project_path,
                         sessions,
                         created_at: latest_activity,
+                        pinned: pinned.projects.iter().any(|id| id == dir_name),
                     });
                 }
             }
@@ -152,11 +183,80 @@ impl ProjectStore {
         self.deduplicate_projects(all_projects, hidden_projects.len())
     }
 
+    /// 统计每个项目目录中 JSONL 会话文件占用的空间，按大小降序
+    pub fn project_disk_usage(
+        &self,
+        include_hidden: bool,
+    ) -> Result<Vec<ProjectDiskUsage>, String> {
+        let projects_dir = self.projects_dir();
+        if !projects_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let hidden_projects = self.load_hidden_projects()?;
+        let imported_paths = self.load_imported_project_paths()?;
+
+        let entries = fs::read_dir(&projects_dir)
+            .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+        let mut usage = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let hidden = hidden_projects.iter().any(|id| id == dir_name);
+            if hidden && !include_hidden {
+                continue;
+            }
+
+            let mut project = ProjectDiskUsage {
+                project_id: dir_name.to_string(),
+                project_path: resolve_project_path(&path, dir_name, &imported_paths),
+                total_bytes: 0,
+                session_count: 0,
+                largest_session_id: None,
+                largest_session_bytes: 0,
+                hidden,
+            };
+            for session_entry in fs::read_dir(&path).into_iter().flatten().flatten() {
+                let session_path = session_entry.path();
+                if session_path.extension().and_then(|s| s.to_str()) != Some("jsonl") {
+                    continue;
+                }
+                let Ok(metadata) = session_entry.metadata() else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                let size = metadata.len();
+                project.total_bytes += size;
+                let Some(session_id) = session_path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                // 子代理记录（agent-*.jsonl）计入占用空间，但不算作独立会话
+                if session_id.starts_with("agent-") {
+                    continue;
+                }
+                project.session_count += 1;
+                if project.largest_session_id.is_none() || size > project.largest_session_bytes {
+                    project.largest_session_bytes = size;
+                    project.largest_session_id = Some(session_id.to_string());
+                }
+            }
+            usage.push(project);
+        }
+
+        usage.sort_by_key(|u| std::cmp::Reverse(u.total_bytes));
+        Ok(usage)
+    }
+
     pub fn get_project_sessions(&self, project_id: &str) -> Result<Vec<Session>, String> {
         log::info!("Getting sessions for project: {}", project_id);
 
         let project_dir = self.projects_dir().join(project_id);
-        let todos_dir = self.todos_dir();
 
         if !project_dir.exists() {
             return Err(format!("Project directory not found: {}", project_id));
@@ -164,16 +264,21 @@ impl ProjectStore {
 
         let project_path = match get_project_path_from_sessions(&project_dir) {
             Ok(path) => path,
-            Err(e) => {
-                log::warn!(
-                    "Failed to get project path from sessions for {}: {}, falling back to decode",
-                    project_id,
-                    e
-                );
-                decode_project_path(project_id)
-            }
+            Err(e) => match self.load_imported_project_paths()?.remove(project_id) {
+                Some(imported_path) => imported_path,
+                None => {
+                    log::warn!(
+                        "Failed to get project path from sessions for {}: {}, falling back to decode",
+                        project_id,
+                        e
+                    );
+                    decode_project_path(project_id)
+                }
+            },
         };
 
+        let mut session_tags = self.load_session_tags(project_id)?;
+        let pinned = self.load_pinned_items()?;
         let mut sessions = Vec::new();
         let entries = fs::read_dir(&project_dir)
             .map_err(|e| format!("Failed to read project directory: {}", e))?;
@@ -188,75 +293,133 @@ impl ProjectStore {
                     if session_id.starts_with("agent-") {
                         continue;
                     }
-                    let metadata = fs::metadata(&path)
-                        .map_err(|e| format!("Failed to read file metadata: {}", e))?;
-
-                    let created_at = metadata
-                        .created()
-                        .or_else(|_| metadata.modified())
-                        .unwrap_or(SystemTime::UNIX_EPOCH)
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs();
-
-                    let (first_message_raw, message_timestamp) = extract_first_user_message(&path);
-                    let last_message_timestamp = extract_last_message_timestamp(&path);
-                    let model = extract_session_model(&path);
-
-                    // ✅ Fallback: 如果 first_message 为空，使用默认文本以确保会话能显示
-                    // 这样即使所有用户消息都被过滤掉，会话仍然可见
-                    let first_message = first_message_raw.or_else(|| {
-                        // 检查会话是否真的有内容：
-                        // 1. 有 last_message_timestamp，说明有消息
-                        // 2. 文件大小 > 100 字节（排除几乎空的会话文件）
-                        let has_content = last_message_timestamp.is_some()
-                            && path.metadata()
-                                .ok()
-                                .map(|m| m.len() > 100)
-                                .unwrap_or(false);
-
-                        if has_content {
-                            // 只显示 session_id 的前8位，避免 UI 过长
-                            let short_id = if session_id.len() >= 8 {
-                                &session_id[..8]
-                            } else {
-                                session_id
-                            };
-                            Some(format!("Resumed Session ({}...)", short_id))
-                        } else {
-                            // 真正的空会话
-                            None
-                        }
-                    });
-
-                    let todo_path = todos_dir.join(format!("{}.json", session_id));
-                    let todo_data = if todo_path.exists() {
-                        fs::read_to_string(&todo_path)
-                            .ok()
-                            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
-                    } else {
-                        None
-                    };
-
-                    sessions.push(Session {
-                        id: session_id.to_string(),
-                        project_id: project_id.to_string(),
-                        project_path: project_path.clone(),
-                        todo_data,
-                        created_at,
-                        first_message,
-                        message_timestamp,
-                        last_message_timestamp,
-                        model,
-                    });
+                    sessions.push(self.read_session(
+                        &path,
+                        session_id,
+                        project_id,
+                        &project_path,
+                        session_tags.remove(session_id).unwrap_or_default(),
+                        &pinned,
+                    )?);
                 }
             }
         }
 
-        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        // 指定了工作目录的会话由 CLI 存放在工作目录对应的目录下，按记录的所属项目归入本项目；
+        // project_id 保持为会话文件实际所在的目录，加载历史、删除等操作仍能找到文件
+        for (session_id, working_dir) in self.working_dir_sessions(&project_path) {
+            let session_project_id = encode_project_path(&working_dir);
+            let path = self
+                .projects_dir()
+                .join(&session_project_id)
+                .join(format!("{}.jsonl", session_id));
+            if session_project_id == project_id
+                || !path.is_file()
+                || sessions.iter().any(|session| session.id == session_id)
+            {
+                continue;
+            }
+            let tags = self
+                .load_session_tags(&session_project_id)?
+                .remove(&session_id)
+                .unwrap_or_default();
+            sessions.push(self.read_session(
+                &path,
+                &session_id,
+                &session_project_id,
+                &project_path,
+                tags,
+                &pinned,
+            )?);
+        }
+
+        // 默认按最后活动时间倒序
+        sessions.sort_by_key(|session| std::cmp::Reverse(activity_sort_key(session)));
         Ok(sessions)
     }
 
+    /// 读取单个会话文件的摘要信息
+    fn read_session(
+        &self,
+        path: &Path,
+        session_id: &str,
+        project_id: &str,
+        project_path: &str,
+        tags: Vec<String>,
+        pinned: &PinnedItems,
+    ) -> Result<Session, String> {
+        let metadata =
+            fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+
+        let created_at = metadata
+            .created()
+            .or_else(|_| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let stats = session_stats(path);
+        let first_message_raw = stats.first_message.clone();
+        let message_timestamp = stats.first_message_timestamp.clone();
+        let last_message_timestamp = stats.last_activity.clone();
+        let model = stats.model.clone();
+
+        // ✅ Fallback: 如果 first_message 为空，使用默认文本以确保会话能显示
+        // 这样即使所有用户消息都被过滤掉，会话仍然可见
+        let first_message = first_message_raw.or_else(|| {
+            // 检查会话是否真的有内容：
+            // 1. 有 last_message_timestamp，说明有消息
+            // 2. 文件大小 > 100 字节（排除几乎空的会话文件）
+            let has_content = last_message_timestamp.is_some()
+                && path.metadata().ok().map(|m| m.len() > 100).unwrap_or(false);
+
+            if has_content {
+                // 只显示 session_id 的前8位，避免 UI 过长
+                let short_id = if session_id.len() >= 8 {
+                    &session_id[..8]
+                } else {
+                    session_id
+                };
+                Some(format!("Resumed Session ({}...)", short_id))
+            } else {
+                // 真正的空会话
+                None
+            }
+        });
+
+        let todo_path = self.todos_dir().join(format!("{}.json", session_id));
+        let todo_data = if todo_path.exists() {
+            fs::read_to_string(&todo_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        } else {
+            None
+        };
+
+        Ok(Session {
+            id: session_id.to_string(),
+            project_id: project_id.to_string(),
+            project_path: project_path.to_string(),
+            todo_data,
+            created_at,
+            first_message,
+            message_timestamp,
+            last_message_timestamp,
+            model,
+            tags,
+            pinned: pinned.sessions.iter().any(|id| id == session_id),
+            message_count: stats.message_count,
+            first_activity: stats.first_activity,
+            last_activity: stats.last_activity.or_else(|| {
+                metadata
+                    .modified()
+                    .ok()
+                    .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339())
+            }),
+        })
+    }
+
     pub fn delete_session(&self, project_id: &str, session_id: &str) -> Result<bool, String> {
         log::info!(
             "Deleting session {} from project {}",
@@ -266,10 +429,8 @@ impl ProjectStore {
 
         let mut session_deleted = false;
 
-        let session_file = self
-            .projects_dir()
-            .join(project_id)
-            .join(format!("{}.jsonl", session_id));
+        let project_dir = self.projects_dir().join(project_id);
+        let session_file = project_dir.join(format!("{}.jsonl", session_id));
 
         if session_file.exists() {
             fs::remove_file(&session_file)
@@ -280,6 +441,15 @@ impl ProjectStore {
             log::warn!("Session file not found: {:?}", session_file);
         }
 
+        // 子代理记录随父会话一起删除
+        for subagent_file in subagent_files(&project_dir, session_id) {
+            if let Err(e) = fs::remove_file(&subagent_file) {
+                log::warn!("Failed to delete subagent file {:?}: {}", subagent_file, e);
+            } else {
+                log::info!("Deleted subagent file: {:?}", subagent_file);
+            }
+        }
+
         let todo_file = self
             .claude_dir
             .join("todos")
@@ -309,6 +479,13 @@ impl ProjectStore {
             }
         }
 
+        if let Err(e) = self.remove_session_tags(project_id, session_id) {
+            log::warn!("Failed to clean up tags for {}: {}", session_id, e);
+        }
+        if let Err(e) = self.unpin_session(session_id) {
+            log::warn!("Failed to unpin deleted session {}: {}", session_id, e);
+        }
+
         Ok(session_deleted)
     }
 
@@ -345,6 +522,386 @@ impl ProjectStore {
         }
     }
 
+    /// 按条件挑选要清理的会话；置顶、带 favorite 标签以及 `skip_session_ids` 中的会话不会被选中
+    pub fn plan_session_cleanup(
+        &self,
+        criteria: &SessionCleanupCriteria,
+        skip_session_ids: &HashSet<String>,
+    ) -> Result<Vec<SessionCleanupCandidate>, String> {
+        if criteria.older_than_days.is_none()
+            && criteria.larger_than_bytes.is_none()
+            && criteria.keep_latest_n_per_project.is_none()
+        {
+            return Err("At least one cleanup criterion is required".to_string());
+        }
+
+        let projects_dir = self.projects_dir();
+        if !projects_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let pinned = self.load_pinned_items()?;
+        let now = SystemTime::now();
+
+        let entries = fs::read_dir(&projects_dir)
+            .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+        let mut candidates = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(project_id) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let session_tags = self.load_session_tags(project_id)?;
+
+            let mut files = Vec::new();
+            let mut subagent_bytes: HashMap<String, u64> = HashMap::new();
+            for session_entry in fs::read_dir(&path).into_iter().flatten().flatten() {
+                let session_path = session_entry.path();
+                if session_path.extension().and_then(|s| s.to_str()) != Some("jsonl") {
+                    continue;
+                }
+                let (Some(session_id), Ok(metadata)) = (
+                    session_path.file_stem().and_then(|s| s.to_str()),
+                    session_entry.metadata(),
+                ) else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                // 子代理记录不是独立会话：大小计入所属会话，随所属会话一起清理
+                if session_id.starts_with("agent-") {
+                    if let Some(parent_id) = jsonl_session_id(&session_path) {
+                        *subagent_bytes.entry(parent_id).or_default() += metadata.len();
+                    }
+                    continue;
+                }
+                let favorite = session_tags.get(session_id).is_some_and(|tags| {
+                    tags.iter()
+                        .any(|tag| tag.eq_ignore_ascii_case(PROTECTED_SESSION_TAG))
+                });
+                files.push(SessionFileInfo {
+                    session_id: session_id.to_string(),
+                    bytes: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    protected: favorite
+                        || pinned.sessions.iter().any(|id| id == session_id)
+                        || skip_session_ids.contains(session_id),
+                });
+            }
+
+            for file in &mut files {
+                file.bytes += subagent_bytes.remove(&file.session_id).unwrap_or(0);
+            }
+
+            candidates.extend(
+                select_sessions_for_cleanup(files, criteria, now)
+                    .into_iter()
+                    .map(|file| SessionCleanupCandidate {
+                        project_id: project_id.to_string(),
+                        session_id: file.session_id,
+                        bytes: file.bytes,
+                        last_modified: Some(DateTime::<Utc>::from(file.modified).to_rfc3339()),
+                    }),
+            );
+        }
+
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+        Ok(candidates)
+    }
+
+    /// 会话 JSONL 文件是否存在
+    pub fn session_file_exists(&self, project_id: &str, session_id: &str) -> bool {
+        self.projects_dir()
+            .join(project_id)
+            .join(format!("{}.jsonl", session_id))
+            .exists()
+    }
+
+    /// 将会话从一个项目移动到另一个项目（JSONL、子代理文件、会话目录与 git 记录）
+    ///
+    /// 先写入目标并校验，成功后才删除源文件；任何一步失败都会回滚已移动的文件
+    pub fn move_session(
+        &self,
+        session_id: &str,
+        from_project_id: &str,
+        to_project_id: &str,
+    ) -> Result<(), String> {
+        log::info!(
+            "Moving session {} from project {} to {}",
+            session_id,
+            from_project_id,
+            to_project_id
+        );
+
+        validate_path_component(session_id, "session id")?;
+        validate_path_component(from_project_id, "project id")?;
+        validate_path_component(to_project_id, "project id")?;
+        if from_project_id == to_project_id {
+            return Err("Source and target project are the same".to_string());
+        }
+
+        let from_dir = self.projects_dir().join(from_project_id);
+        let to_dir = self.projects_dir().join(to_project_id);
+        let session_file_name = format!("{}.jsonl", session_id);
+        let source_file = from_dir.join(&session_file_name);
+        let target_file = to_dir.join(&session_file_name);
+
+        if !source_file.is_file() {
+            return Err(format!(
+                "Session {} not found in project {}",
+                session_id, from_project_id
+            ));
+        }
+        if target_file.exists() {
+            return Err(format!(
+                "Session {} already exists in project {}",
+                session_id, to_project_id
+            ));
+        }
+
+        // 附属文件：子代理 JSONL、会话目录、git 记录（相对项目目录）
+        let mut related = Vec::new();
+        if let Ok(entries) = fs::read_dir(&from_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                    if file_name.starts_with("agent-")
+                        && file_name.ends_with(".jsonl")
+                        && jsonl_belongs_to_session(&path, session_id)
+                    {
+                        related.push(PathBuf::from(file_name));
+                    }
+                }
+            }
+        }
+        if from_dir.join(session_id).is_dir() {
+            related.push(PathBuf::from(session_id));
+        }
+        let git_records = Path::new("sessions").join(format!("{}.git-records.json", session_id));
+        if from_dir.join(&git_records).is_file() {
+            related.push(git_records);
+        }
+
+        if let Some(conflict) = related.iter().find(|rel| to_dir.join(rel).exists()) {
+            return Err(format!(
+                "Target project {} already contains {:?}",
+                to_project_id, conflict
+            ));
+        }
+
+        // 会话记录里的 cwd 指向旧项目，目标路径可靠时一并更新
+        let from_path = get_project_path_from_sessions(&from_dir).ok();
+        let to_path = match get_project_path_from_sessions(&to_dir) {
+            Ok(path) => Some(path),
+            Err(_) => self.load_imported_project_paths()?.remove(to_project_id),
+        };
+
+        let original = fs::read_to_string(&source_file)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        let content = match (&from_path, &to_path) {
+            (Some(from_path), Some(to_path)) => rewrite_session_cwd(&original, from_path, to_path),
+            _ => original.clone(),
+        };
+
+        fs::create_dir_all(&to_dir)
+            .map_err(|e| format!("Failed to create target project directory: {}", e))?;
+
+        let tmp_file = to_dir.join(format!(".{}.tmp", session_file_name));
+        fs::write(&tmp_file, &content)
+            .and_then(|_| fs::rename(&tmp_file, &target_file))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_file);
+                format!("Failed to write session file to target project: {}", e)
+            })?;
+
+        let verified = fs::read_to_string(&target_file)
+            .map(|written| {
+                written == content && written.lines().count() == original.lines().count()
+            })
+            .unwrap_or(false);
+        if !verified {
+            let _ = fs::remove_file(&target_file);
+            return Err(format!(
+                "Verification of moved session {} failed; source left untouched",
+                session_id
+            ));
+        }
+
+        let mut moved: Vec<&PathBuf> = Vec::new();
+        for rel in &related {
+            let target = to_dir.join(rel);
+            let result = match target.parent() {
+                Some(parent) => fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| fs::rename(from_dir.join(rel), &target));
+
+            if let Err(e) = result {
+                for done in moved.iter().rev() {
+                    let _ = fs::rename(to_dir.join(done), from_dir.join(done));
+                }
+                let _ = fs::remove_file(&target_file);
+                return Err(format!("Failed to move {:?}: {}", rel, e));
+            }
+            moved.push(rel);
+        }
+
+        if let Err(e) = fs::remove_file(&source_file) {
+            for done in moved.iter().rev() {
+                let _ = fs::rename(to_dir.join(done), from_dir.join(done));
+            }
+            let _ = fs::remove_file(&target_file);
+            return Err(format!("Failed to remove original session file: {}", e));
+        }
+
+        if let Err(e) = self.transfer_session_tags(session_id, from_project_id, to_project_id) {
+            log::warn!("Failed to move tags for session {}: {}", session_id, e);
+        }
+
+        log::info!(
+            "Moved session {} ({} related item(s)) to project {}",
+            session_id,
+            related.len(),
+            to_project_id
+        );
+        Ok(())
+    }
+
+    /// 设置会话标签（覆盖原有标签；忽略空白，按大小写不敏感去重），返回规范化后的标签
+    pub fn set_session_tags(
+        &self,
+        project_id: &str,
+        session_id: &str,
+        tags: &[String],
+    ) -> Result<Vec<String>, String> {
+        validate_path_component(project_id, "project id")?;
+        validate_path_component(session_id, "session id")?;
+        let project_dir = self.projects_dir().join(project_id);
+        if !project_dir.join(format!("{}.jsonl", session_id)).is_file() {
+            return Err(format!(
+                "Session {} not found in project {}",
+                session_id, project_id
+            ));
+        }
+
+        let normalized = normalize_tags(tags);
+        let mut session_tags = self.load_session_tags(project_id)?;
+        if normalized.is_empty() {
+            session_tags.remove(session_id);
+        } else {
+            session_tags.insert(session_id.to_string(), normalized.clone());
+        }
+        self.save_session_tags(project_id, &session_tags)?;
+        Ok(normalized)
+    }
+
+    /// 跨项目列出带有指定标签（大小写不敏感）的会话
+    pub fn list_sessions_by_tag(&self, tag: &str) -> Result<Vec<Session>, String> {
+        let wanted = tag.trim().to_lowercase();
+        if wanted.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let projects_dir = self.projects_dir();
+        let Ok(entries) = fs::read_dir(&projects_dir) else {
+            return Ok(Vec::new());
+        };
+
+        let mut matched = Vec::new();
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let Some(project_id) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+
+            let has_match = self
+                .load_session_tags(&project_id)?
+                .values()
+                .any(|tags| tags.iter().any(|t| t.to_lowercase() == wanted));
+            if !has_match {
+                continue;
+            }
+
+            match self.get_project_sessions(&project_id) {
+                Ok(sessions) => matched.extend(
+                    sessions
+                        .into_iter()
+                        .filter(|s| s.tags.iter().any(|t| t.to_lowercase() == wanted)),
+                ),
+                Err(e) => log::warn!("Failed to load sessions for {}: {}", project_id, e),
+            }
+        }
+
+        matched.sort_by_key(|session| std::cmp::Reverse(activity_sort_key(session)));
+        Ok(matched)
+    }
+
+    /// 置顶项目；返回 false 表示已经置顶
+    pub fn pin_project(&self, project_id: &str) -> Result<bool, String> {
+        if !self.projects_dir().join(project_id).is_dir() {
+            return Err(format!("Project directory not found: {}", project_id));
+        }
+
+        let mut pinned = self.load_pinned_items()?;
+        if pinned.projects.iter().any(|id| id == project_id) {
+            return Ok(false);
+        }
+        pinned.projects.push(project_id.to_string());
+        self.save_pinned_items(&pinned)?;
+        Ok(true)
+    }
+
+    /// 取消置顶项目；项目未置顶或已删除时静默返回 false
+    pub fn unpin_project(&self, project_id: &str) -> Result<bool, String> {
+        let mut pinned = self.load_pinned_items()?;
+        let before = pinned.projects.len();
+        pinned.projects.retain(|id| id != project_id);
+        if pinned.projects.len() == before {
+            return Ok(false);
+        }
+        self.save_pinned_items(&pinned)?;
+        Ok(true)
+    }
+
+    /// 置顶会话；返回 false 表示已经置顶
+    pub fn pin_session(&self, project_id: &str, session_id: &str) -> Result<bool, String> {
+        let session_file = self
+            .projects_dir()
+            .join(project_id)
+            .join(format!("{}.jsonl", session_id));
+        if !session_file.is_file() {
+            return Err(format!(
+                "Session {} not found in project {}",
+                session_id, project_id
+            ));
+        }
+
+        let mut pinned = self.load_pinned_items()?;
+        if pinned.sessions.iter().any(|id| id == session_id) {
+            return Ok(false);
+        }
+        pinned.sessions.push(session_id.to_string());
+        self.save_pinned_items(&pinned)?;
+        Ok(true)
+    }
+
+    /// 取消置顶会话；会话未置顶或已删除时静默返回 false
+    pub fn unpin_session(&self, session_id: &str) -> Result<bool, String> {
+        let mut pinned = self.load_pinned_items()?;
+        let before = pinned.sessions.len();
+        pinned.sessions.retain(|id| id != session_id);
+        if pinned.sessions.len() == before {
+            return Ok(false);
+        }
+        self.save_pinned_items(&pinned)?;
+        Ok(true)
+    }
+
     pub fn hide_project(&self, project_id: &str) -> Result<bool, String> {
         let mut hidden_projects = self.load_hidden_projects()?;
         if hidden_projects.contains(&project_id.to_string()) {
@@ -371,6 +928,271 @@ impl ProjectStore {
         }
     }
 
+    /// 导入一个已有目录为项目：创建编码后的项目目录并记录真实路径
+    ///
+    /// 即使还没有任何会话，项目也会立即出现在 list_projects 中；若项目被隐藏则取消隐藏
+    pub fn import_project(&self, project_path: &str) -> Result<String, String> {
+        let trimmed = project_path.trim();
+        // 去掉末尾分隔符，但保留根目录本身
+        let real_path = match trimmed.trim_end_matches(['/', '\\']) {
+            "" => trimmed,
+            stripped => stripped,
+        }
+        .to_string();
+
+        let path = Path::new(&real_path);
+        if !path.is_absolute() {
+            return Err(format!("Project path must be absolute: {}", real_path));
+        }
+        if !path.is_dir() {
+            return Err(format!("Project directory does not exist: {}", real_path));
+        }
+
+        let project_id = encode_project_path(&real_path);
+        let project_dir = self.projects_dir().join(&project_id);
+        fs::create_dir_all(&project_dir)
+            .map_err(|e| format!("Failed to create project directory: {}", e))?;
+
+        let mut imported_paths = self.load_imported_project_paths()?;
+        if imported_paths.get(&project_id) != Some(&real_path) {
+            imported_paths.insert(project_id.clone(), real_path.clone());
+            self.save_imported_project_paths(&imported_paths)?;
+        }
+
+        self.remove_from_hidden_projects(&[&project_id])?;
+
+        log::info!("Imported project {} as {}", real_path, project_id);
+        Ok(project_id)
+    }
+
+    /// 创建工作区；成员路径必须是绝对路径，按规范化后的路径去重
+    pub fn create_workspace(
+        &self,
+        name: &str,
+        project_paths: &[String],
+    ) -> Result<Workspace, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Workspace name must not be empty".to_string());
+        }
+
+        let mut members: Vec<String> = Vec::new();
+        for path in project_paths {
+            let path = path.trim();
+            if !Path::new(path).is_absolute() {
+                return Err(format!("Project path must be absolute: {}", path));
+            }
+            let normalized = normalize_path_for_comparison(path);
+            if !members
+                .iter()
+                .any(|member| normalize_path_for_comparison(member) == normalized)
+            {
+                members.push(path.to_string());
+            }
+        }
+        if members.is_empty() {
+            return Err("A workspace needs at least one project".to_string());
+        }
+
+        let mut file = self.load_workspaces()?;
+        if file.workspaces.iter().any(|w| w.name == name) {
+            return Err(format!("Workspace '{}' already exists", name));
+        }
+        let workspace = Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            project_paths: members,
+            created_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            active: false,
+        };
+        file.workspaces.push(workspace.clone());
+        self.save_workspaces(&file)?;
+        Ok(workspace)
+    }
+
+    pub fn list_workspaces(&self) -> Result<Vec<Workspace>, String> {
+        Ok(self.load_workspaces()?.workspaces)
+    }
+
+    /// 删除工作区（不影响成员项目）；返回 false 表示工作区不存在
+    pub fn delete_workspace(&self, workspace_id: &str) -> Result<bool, String> {
+        let mut file = self.load_workspaces()?;
+        let before = file.workspaces.len();
+        file.workspaces.retain(|w| w.id != workspace_id);
+        if file.workspaces.len() == before {
+            return Ok(false);
+        }
+        self.save_workspaces(&file)?;
+        Ok(true)
+    }
+
+    /// 设置当前工作区；传入 None 取消激活
+    pub fn set_active_workspace(&self, workspace_id: Option<&str>) -> Result<(), String> {
+        let mut file = self.load_workspaces()?;
+        if let Some(id) = workspace_id {
+            if !file.workspaces.iter().any(|w| w.id == id) {
+                return Err(format!("Workspace not found: {}", id));
+            }
+        }
+        for workspace in &mut file.workspaces {
+            workspace.active = Some(workspace.id.as_str()) == workspace_id;
+        }
+        self.save_workspaces(&file)
+    }
+
+    pub fn active_workspace(&self) -> Result<Option<Workspace>, String> {
+        Ok(self
+            .load_workspaces()?
+            .workspaces
+            .into_iter()
+            .find(|w| w.active))
+    }
+
+    /// 汇总工作区内所有成员项目的会话，按最近活动排序
+    pub fn get_workspace_sessions(&self, workspace_id: &str) -> Result<Vec<Session>, String> {
+        let workspace = self
+            .load_workspaces()?
+            .workspaces
+            .into_iter()
+            .find(|w| w.id == workspace_id)
+            .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
+
+        let mut sessions = Vec::new();
+        for project in filter_to_workspace(self.list_projects()?, &workspace) {
+            match self.get_project_sessions(&project.id) {
+                Ok(project_sessions) => sessions.extend(project_sessions),
+                Err(e) => log::warn!("Failed to load sessions for {}: {}", project.id, e),
+            }
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(activity_sort_key(session)));
+        Ok(sessions)
+    }
+
+    /// 从所有工作区中移除指定项目路径
+    fn prune_workspace_path(&self, project_path: &str) -> Result<(), String> {
+        let normalized = normalize_path_for_comparison(project_path);
+        let mut file = self.load_workspaces()?;
+        let mut changed = false;
+        for workspace in &mut file.workspaces {
+            let before = workspace.project_paths.len();
+            workspace
+                .project_paths
+                .retain(|path| normalize_path_for_comparison(path) != normalized);
+            changed |= workspace.project_paths.len() != before;
+        }
+        if changed {
+            log::info!("Removed {} from workspaces", project_path);
+            self.save_workspaces(&file)?;
+        }
+        Ok(())
+    }
+
+    /// 记录会话的工作目录（与项目路径不同时），恢复会话时沿用
+    pub fn record_session_working_dir(
+        &self,
+        session_id: &str,
+        project_path: &str,
+        working_dir: &str,
+    ) -> Result<(), String> {
+        let mut working_dirs = self.load_session_working_dirs()?;
+        working_dirs.insert(
+            session_id.to_string(),
+            SessionWorkingDir {
+                project_path: project_path.to_string(),
+                working_dir: working_dir.to_string(),
+            },
+        );
+        self.save_session_working_dirs(&working_dirs)
+    }
+
+    /// 读取会话记录的工作目录（仅当其所属项目与 project_path 一致时）
+    pub fn session_working_dir(&self, session_id: &str, project_path: &str) -> Option<String> {
+        let entry = self.load_session_working_dirs().ok()?.remove(session_id)?;
+        (normalize_path_for_comparison(&entry.project_path)
+            == normalize_path_for_comparison(project_path))
+        .then_some(entry.working_dir)
+    }
+
+    /// 记录了工作目录且属于 project_path 的会话：(会话 ID, 工作目录)
+    pub fn working_dir_sessions(&self, project_path: &str) -> Vec<(String, String)> {
+        let normalized = normalize_path_for_comparison(project_path);
+        let mut sessions: Vec<(String, String)> = self
+            .load_session_working_dirs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, entry)| normalize_path_for_comparison(&entry.project_path) == normalized)
+            .map(|(session_id, entry)| (session_id, entry.working_dir))
+            .collect();
+        sessions.sort();
+        sessions
+    }
+
+    /// 项目目录名（不加载会话）；`include_hidden` 为 false 时跳过已隐藏的项目
+    pub fn project_ids(&self, include_hidden: bool) -> Result<Vec<String>, String> {
+        let projects_dir = self.projects_dir();
+        if !projects_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let hidden_projects = if include_hidden {
+            Vec::new()
+        } else {
+            self.load_hidden_projects()?
+        };
+        let entries = fs::read_dir(&projects_dir)
+            .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+        Ok(entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|id| !hidden_projects.contains(id))
+            .collect())
+    }
+
+    /// 读取项目级模型覆盖（按引擎区分：claude / codex / gemini）
+    pub fn get_project_model(&self, project_path: &str, engine: &str) -> Option<String> {
+        let key = normalize_path_for_comparison(project_path);
+        self.load_project_models()
+            .ok()?
+            .get(&key)
+            .and_then(|models| models.get(engine))
+            .filter(|model| !model.trim().is_empty())
+            .cloned()
+    }
+
+    /// 设置或清除（model 为空时）项目级模型覆盖
+    pub fn set_project_model(
+        &self,
+        project_path: &str,
+        engine: &str,
+        model: Option<&str>,
+    ) -> Result<(), String> {
+        let key = normalize_path_for_comparison(project_path);
+        let mut project_models = self.load_project_models()?;
+
+        match model.map(str::trim).filter(|m| !m.is_empty()) {
+            Some(model) => {
+                project_models
+                    .entry(key)
+                    .or_default()
+                    .insert(engine.to_string(), model.to_string());
+            }
+            None => {
+                if let Some(models) = project_models.get_mut(&key) {
+                    models.remove(engine);
+                    if models.is_empty() {
+                        project_models.remove(&key);
+                    }
+                }
+            }
+        }
+
+        self.save_project_models(&project_models)
+    }
+
     pub fn delete_project_permanently(&self, project_id: &str) -> Result<String, String> {
         log::info!("Permanently deleting project: {}", project_id);
 
@@ -421,10 +1243,29 @@ impl ProjectStore {
             }
         })?;
 
+        let project_path = get_project_path_from_sessions(&dir_to_delete)
+            .ok()
+            .or_else(|| {
+                self.load_imported_project_paths()
+                    .ok()?
+                    .remove(&actual_project_id)
+            })
+            .unwrap_or_else(|| decode_project_path(&actual_project_id));
+
         fs::remove_dir_all(&dir_to_delete)
             .map_err(|e| format!("Failed to delete project directory: {}", e))?;
 
         self.remove_from_hidden_projects(&[project_id, &actual_project_id])?;
+        self.unpin_project(project_id)?;
+        self.unpin_project(&actual_project_id)?;
+        self.prune_workspace_path(&project_path)?;
+
+        let mut imported_paths = self.load_imported_project_paths()?;
+        let imported_before = imported_paths.len();
+        imported_paths.retain(|id, _| id != project_id && *id != actual_project_id);
+        if imported_paths.len() != imported_before {
+            self.save_imported_project_paths(&imported_paths)?;
+        }
 
         Ok(actual_project_id)
     }
@@ -497,7 +1338,7 @@ impl ProjectStore {
         Ok(validated_hidden_projects)
     }
 
-    fn projects_dir(&self) -> PathBuf {
+    pub(super) fn projects_dir(&self) -> PathBuf {
         self.claude_dir.join("projects")
     }
 
@@ -529,6 +1370,143 @@ impl ProjectStore {
         self.claude_dir.join("hidden_projects.json")
     }
 
+    fn load_project_models(&self) -> Result<HashMap<String, HashMap<String, String>>, String> {
+        load_json_file(&self.project_models_file(), "project models")
+    }
+
+    fn save_project_models(
+        &self,
+        project_models: &HashMap<String, HashMap<String, String>>,
+    ) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(project_models)
+            .map_err(|e| format!("Failed to serialize project models: {}", e))?;
+        fs::write(self.project_models_file(), content)
+            .map_err(|e| format!("Failed to write project models file: {}", e))
+    }
+
+    fn project_models_file(&self) -> PathBuf {
+        self.claude_dir.join("project_models.json")
+    }
+
+    fn load_session_tags(&self, project_id: &str) -> Result<HashMap<String, Vec<String>>, String> {
+        load_json_file(&self.session_tags_file(project_id), "session tags")
+    }
+
+    fn save_session_tags(
+        &self,
+        project_id: &str,
+        session_tags: &HashMap<String, Vec<String>>,
+    ) -> Result<(), String> {
+        let session_tags_file = self.session_tags_file(project_id);
+        if session_tags.is_empty() {
+            if session_tags_file.exists() {
+                fs::remove_file(&session_tags_file)
+                    .map_err(|e| format!("Failed to remove session tags file: {}", e))?;
+            }
+            return Ok(());
+        }
+
+        let content = serde_json::to_string_pretty(session_tags)
+            .map_err(|e| format!("Failed to serialize session tags: {}", e))?;
+        fs::write(&session_tags_file, content)
+            .map_err(|e| format!("Failed to write session tags file: {}", e))
+    }
+
+    fn remove_session_tags(&self, project_id: &str, session_id: &str) -> Result<(), String> {
+        let mut session_tags = self.load_session_tags(project_id)?;
+        if session_tags.remove(session_id).is_some() {
+            self.save_session_tags(project_id, &session_tags)?;
+        }
+        Ok(())
+    }
+
+    fn transfer_session_tags(
+        &self,
+        session_id: &str,
+        from_project_id: &str,
+        to_project_id: &str,
+    ) -> Result<(), String> {
+        let mut from_tags = self.load_session_tags(from_project_id)?;
+        let Some(tags) = from_tags.remove(session_id) else {
+            return Ok(());
+        };
+
+        let mut to_tags = self.load_session_tags(to_project_id)?;
+        to_tags.insert(session_id.to_string(), tags);
+        self.save_session_tags(to_project_id, &to_tags)?;
+        self.save_session_tags(from_project_id, &from_tags)
+    }
+
+    /// 标签存放在项目目录下的 sidecar 文件中，不修改会话 JSONL
+    fn session_tags_file(&self, project_id: &str) -> PathBuf {
+        self.projects_dir()
+            .join(project_id)
+            .join("session_tags.json")
+    }
+
+    fn load_session_working_dirs(&self) -> Result<HashMap<String, SessionWorkingDir>, String> {
+        load_json_file(&self.session_working_dirs_file(), "session working dirs")
+    }
+
+    fn save_session_working_dirs(
+        &self,
+        working_dirs: &HashMap<String, SessionWorkingDir>,
+    ) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(working_dirs)
+            .map_err(|e| format!("Failed to serialize session working dirs: {}", e))?;
+        fs::write(self.session_working_dirs_file(), content)
+            .map_err(|e| format!("Failed to write session working dirs file: {}", e))
+    }
+
+    fn session_working_dirs_file(&self) -> PathBuf {
+        self.claude_dir.join("session_working_dirs.json")
+    }
+
+    fn load_pinned_items(&self) -> Result<PinnedItems, String> {
+        load_json_file(&self.pinned_items_file(), "pinned items")
+    }
+
+    fn save_pinned_items(&self, pinned: &PinnedItems) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(pinned)
+            .map_err(|e| format!("Failed to serialize pinned items: {}", e))?;
+        fs::write(self.pinned_items_file(), content)
+            .map_err(|e| format!("Failed to write pinned items file: {}", e))
+    }
+
+    fn pinned_items_file(&self) -> PathBuf {
+        self.claude_dir.join("pinned_items.json")
+    }
+
+    fn load_workspaces(&self) -> Result<WorkspacesFile, String> {
+        load_json_file(&self.workspaces_file(), "workspaces")
+    }
+
+    fn save_workspaces(&self, file: &WorkspacesFile) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(file)
+            .map_err(|e| format!("Failed to serialize workspaces: {}", e))?;
+        fs::write(self.workspaces_file(), content)
+            .map_err(|e| format!("Failed to write workspaces file: {}", e))
+    }
+
+    fn workspaces_file(&self) -> PathBuf {
+        self.claude_dir.join("workspaces.json")
+    }
+
+    fn load_imported_project_paths(&self) -> Result<HashMap<String, String>, String> {
+        load_json_file(&self.imported_project_paths_file(), "imported projects")
+    }
+
+    fn save_imported_project_paths(&self, paths: &HashMap<String, String>) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(paths)
+            .map_err(|e| format!("Failed to serialize imported projects: {}", e))?;
+        fs::write(self.imported_project_paths_file(), content)
+            .map_err(|e| format!("Failed to write imported projects file: {}", e))
+    }
+
+    fn imported_project_paths_file(&self) -> PathBuf {
+        self.claude_dir.join("imported_projects.json")
+    }
+
     fn deduplicate_projects(
         &self,
         all_projects: Vec<Project>,
@@ -556,6 +1534,8 @@ impl ProjectStore {
                         }
                     }
 
+                    existing_project.pinned |= project.pinned;
+
                     if project.created_at > existing_project.created_at {
                         existing_project.created_at = project.created_at;
                     }
@@ -625,6 +1605,39 @@ impl ProjectStore {
     }
 }
 
+/// 读取 JSON 数据文件，不存在时返回默认值
+///
+/// 损坏的文件改名备份后报错，之后从默认值重新开始，不会被下一次保存覆盖
+fn load_json_file<T: serde::de::DeserializeOwned + Default>(
+    path: &Path,
+    what: &str,
+) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {} file: {}", what, e))?;
+    serde_json::from_str(&content).map_err(|e| {
+        let backup = path.with_extension(format!(
+            "json.corrupt-{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        match fs::rename(path, &backup) {
+            Ok(()) => format!(
+                "The {} file is corrupt ({}); it was moved to {}",
+                what,
+                e,
+                backup.display()
+            ),
+            Err(rename_error) => format!(
+                "The {} file is corrupt ({}) and could not be backed up: {}",
+                what, e, rename_error
+            ),
+        }
+    })
+}
+
 fn get_project_path_from_sessions(project_dir: &Path) -> Result<String, String> {
     let entries = fs::read_dir(project_dir)
         .map_err(|e| format!("Failed to read project directory: {}", e))?;
@@ -678,6 +1691,163 @@ fn get_project_path_from_sessions(project_dir: &Path) -> Result<String, String>
     Err("Could not determine project path from session files".to_string())
 }
 
+/// 项目真实路径：优先从会话记录读取，其次使用导入时记录的路径，最后回退到目录名解码
+fn resolve_project_path(
+    project_dir: &Path,
+    dir_name: &str,
+    imported_paths: &HashMap<String, String>,
+) -> String {
+    match get_project_path_from_sessions(project_dir) {
+        Ok(path) => path,
+        Err(e) => match imported_paths.get(dir_name) {
+            // 通过 import_project 导入、尚无会话的项目使用记录的真实路径
+            Some(imported_path) => imported_path.clone(),
+            None => {
+                log::warn!(
+                    "Failed to get project path from sessions for {}: {}, falling back to decode",
+                    dir_name,
+                    e
+                );
+                decode_project_path(dir_name)
+            }
+        },
+    }
+}
+
+/// 在一个项目的会话文件中挑选要清理的会话（见 `SessionCleanupCriteria` 的说明）
+fn select_sessions_for_cleanup(
+    mut files: Vec<SessionFileInfo>,
+    criteria: &SessionCleanupCriteria,
+    now: SystemTime,
+) -> Vec<SessionFileInfo> {
+    // 最新的在前，便于按序号保留最近 N 个
+    files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+    let cutoff = criteria.older_than_days.and_then(|days| {
+        now.checked_sub(std::time::Duration::from_secs(
+            days.saturating_mul(24 * 60 * 60),
+        ))
+    });
+    let has_filters = criteria.older_than_days.is_some() || criteria.larger_than_bytes.is_some();
+
+    files
+        .into_iter()
+        .enumerate()
+        .filter(|(index, file)| {
+            if file.protected {
+                return false;
+            }
+            let beyond_kept = criteria
+                .keep_latest_n_per_project
+                .map(|keep| *index >= keep);
+            if !has_filters {
+                return beyond_kept.unwrap_or(false);
+            }
+            let too_old = cutoff.is_some_and(|cutoff| file.modified < cutoff);
+            let too_large = criteria
+                .larger_than_bytes
+                .is_some_and(|limit| file.bytes > limit);
+            (too_old || too_large) && beyond_kept.unwrap_or(true)
+        })
+        .map(|(_, file)| file)
+        .collect()
+}
+
+/// 只保留路径属于工作区成员的项目
+pub(super) fn filter_to_workspace(projects: Vec<Project>, workspace: &Workspace) -> Vec<Project> {
+    let members: HashSet<String> = workspace
+        .project_paths
+        .iter()
+        .map(|path| normalize_path_for_comparison(path))
+        .collect();
+    projects
+        .into_iter()
+        .filter(|project| members.contains(&normalize_path_for_comparison(&project.path)))
+        .collect()
+}
+
+/// 会话的最后活动时间（秒），无法解析时退回文件创建时间
+fn activity_sort_key(session: &Session) -> i64 {
+    session
+        .last_activity
+        .as_deref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.timestamp())
+        .unwrap_or(session.created_at as i64)
+}
+
+/// 去除空白标签，并按大小写不敏感去重（保留首次出现的写法）
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 子代理 JSONL 的 sessionId 是否指向指定会话
+fn jsonl_belongs_to_session(path: &Path, session_id: &str) -> bool {
+    jsonl_session_id(path).is_some_and(|id| id == session_id)
+}
+
+/// JSONL 前几行中记录的 sessionId（子代理文件据此找到所属会话）
+fn jsonl_session_id(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    BufReader::new(file)
+        .lines()
+        .take(10)
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+        .find_map(|json| json.get("sessionId")?.as_str().map(str::to_string))
+}
+
+/// 项目目录中属于该会话的子代理文件（agent-*.jsonl）
+fn subagent_files(project_dir: &Path, session_id: &str) -> Vec<PathBuf> {
+    fs::read_dir(project_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.starts_with("agent-") && name.ends_with(".jsonl"))
+                && jsonl_belongs_to_session(path, session_id)
+        })
+        .collect()
+}
+
+/// 把会话记录中指向 from_path 的 cwd 改为 to_path，其它行原样保留
+fn rewrite_session_cwd(content: &str, from_path: &str, to_path: &str) -> String {
+    let from_normalized = normalize_path_for_comparison(from_path);
+    let mut rewritten = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+
+        let replaced = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|mut json| {
+                let cwd = json.get("cwd")?.as_str()?;
+                if normalize_path_for_comparison(cwd) != from_normalized {
+                    return None;
+                }
+                json["cwd"] = Value::String(to_path.to_string());
+                serde_json::to_string(&json).ok()
+            });
+
+        match replaced {
+            Some(new_body) => {
+                rewritten.push_str(&new_body);
+                rewritten.push_str(ending);
+            }
+            None => rewritten.push_str(line),
+        }
+    }
+    rewritten
+}
+
 /// Normalize macOS paths without using canonicalize()
 /// This avoids issues with:
 /// 1. Symlink resolution (e.g., /tmp -> /private/tmp)
@@ -704,3 +1874,361 @@ fn normalize_macos_path(path: &str) -> String {
 
     normalized
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn file(
+        id: &str,
+        days_old: u64,
+        bytes: u64,
+        protected: bool,
+        now: SystemTime,
+    ) -> SessionFileInfo {
+        SessionFileInfo {
+            session_id: id.to_string(),
+            bytes,
+            modified: now - Duration::from_secs(days_old * 24 * 60 * 60),
+            protected,
+        }
+    }
+
+    fn selected(
+        files: &[SessionFileInfo],
+        criteria: SessionCleanupCriteria,
+        now: SystemTime,
+    ) -> Vec<String> {
+        let mut ids: Vec<String> = select_sessions_for_cleanup(files.to_vec(), &criteria, now)
+            .into_iter()
+            .map(|file| file.session_id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn cleanup_selection_respects_criteria_and_protection() {
+        let now = SystemTime::now();
+        let files = vec![
+            file("new", 1, 100, false, now),
+            file("big", 5, 10_000, false, now),
+            file("old", 40, 100, false, now),
+            file("old-pinned", 60, 100, true, now),
+            file("oldest", 90, 100, false, now),
+        ];
+
+        let older_than_30 = SessionCleanupCriteria {
+            older_than_days: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(selected(&files, older_than_30, now), vec!["old", "oldest"]);
+
+        let old_or_big = SessionCleanupCriteria {
+            older_than_days: Some(30),
+            larger_than_bytes: Some(1_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            selected(&files, old_or_big, now),
+            vec!["big", "old", "oldest"]
+        );
+
+        // Keeping the newest 3 protects "old" (3rd newest) from the age filter
+        let old_but_keep_three = SessionCleanupCriteria {
+            older_than_days: Some(30),
+            keep_latest_n_per_project: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(selected(&files, old_but_keep_three, now), vec!["oldest"]);
+
+        let keep_two = SessionCleanupCriteria {
+            keep_latest_n_per_project: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(selected(&files, keep_two, now), vec!["old", "oldest"]);
+
+        // 超出 SystemTime 可表示范围的天数不会 panic，也不会选中任何会话
+        let far_past = SessionCleanupCriteria {
+            older_than_days: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert!(selected(&files, far_past, now).is_empty());
+    }
+
+    #[test]
+    fn cleanup_treats_subagent_files_as_part_of_their_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let project_dir = store.projects_dir().join("-work-api");
+        fs::create_dir_all(&project_dir).unwrap();
+        let now = SystemTime::now();
+        for (name, content, days_old) in [
+            ("s1.jsonl", "{\"sessionId\":\"s1\"}\n", 3),
+            ("s2.jsonl", "{\"sessionId\":\"s2\"}\n", 2),
+            (
+                "agent-a1.jsonl",
+                "{\"sessionId\":\"s1\",\"agentId\":\"a1\"}\n",
+                1,
+            ),
+        ] {
+            let path = project_dir.join(name);
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(days_old * 24 * 60 * 60))
+                .unwrap();
+        }
+
+        // 子代理文件不占用“保留最新 N 个”的名额，大小计入所属会话
+        let criteria = SessionCleanupCriteria {
+            keep_latest_n_per_project: Some(1),
+            ..Default::default()
+        };
+        let candidates = store
+            .plan_session_cleanup(&criteria, &HashSet::new())
+            .unwrap();
+        let planned: Vec<(&str, u64)> = candidates
+            .iter()
+            .map(|c| (c.session_id.as_str(), c.bytes))
+            .collect();
+        let bytes = |name: &str| fs::metadata(project_dir.join(name)).unwrap().len();
+        assert_eq!(
+            planned,
+            vec![("s1", bytes("s1.jsonl") + bytes("agent-a1.jsonl"))]
+        );
+
+        assert!(store.delete_session("-work-api", "s1").unwrap());
+        assert!(!project_dir.join("agent-a1.jsonl").exists());
+        assert!(project_dir.join("s2.jsonl").exists());
+    }
+
+    #[test]
+    fn disk_usage_counts_subagent_bytes_but_not_subagent_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let project_dir = store.projects_dir().join("-work-api");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("s1.jsonl"), "{}\n").unwrap();
+        fs::write(
+            project_dir.join("agent-a1.jsonl"),
+            "{\"sessionId\":\"s1\"}\n",
+        )
+        .unwrap();
+
+        let usage = store.project_disk_usage(false).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].total_bytes, 3 + 19);
+        assert_eq!(usage[0].session_count, 1);
+        assert_eq!(usage[0].largest_session_id.as_deref(), Some("s1"));
+    }
+
+    #[test]
+    fn move_session_rejects_traversal_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let project_dir = store.projects_dir().join("-work-api");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("s1.jsonl"), "{}\n").unwrap();
+
+        assert!(store.move_session("s1", "-work-api", "..").is_err());
+        assert!(store.move_session("s1", "../api", "-work-web").is_err());
+        assert!(store
+            .move_session("../s1", "-work-api", "-work-web")
+            .is_err());
+        assert!(project_dir.join("s1.jsonl").exists());
+    }
+
+    #[test]
+    fn session_tags_are_normalized_and_ids_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let project_dir = store.projects_dir().join("-work-api");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("s1.jsonl"), "{}\n").unwrap();
+
+        let tags = store
+            .set_session_tags(
+                "-work-api",
+                "s1",
+                &[
+                    " bug ".to_string(),
+                    "Bug".to_string(),
+                    "".to_string(),
+                    "ui".to_string(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(tags, vec!["bug", "ui"]);
+        assert_eq!(
+            store.load_session_tags("-work-api").unwrap().get("s1"),
+            Some(&tags)
+        );
+
+        // 清空标签后删除 sidecar 文件
+        store.set_session_tags("-work-api", "s1", &[]).unwrap();
+        assert!(!store.session_tags_file("-work-api").exists());
+
+        assert!(store.set_session_tags("-work-api", "missing", &[]).is_err());
+        for (project_id, session_id) in [("..", "s1"), ("-work-api", "../s1"), ("a/b", "s1")] {
+            let err = store
+                .set_session_tags(project_id, session_id, &["x".to_string()])
+                .unwrap_err();
+            assert!(err.starts_with("Invalid"), "{}", err);
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn working_dir_sessions_are_listed_under_their_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let project_id = encode_project_path("/work/api");
+        let sub_id = encode_project_path("/work/api/server");
+        for (id, session, cwd) in [
+            (&project_id, "s1", "/work/api"),
+            (&sub_id, "s2", "/work/api/server"),
+            (&sub_id, "s3", "/work/api/server"),
+        ] {
+            let project_dir = store.projects_dir().join(id);
+            fs::create_dir_all(&project_dir).unwrap();
+            fs::write(
+                project_dir.join(format!("{}.jsonl", session)),
+                format!(
+                    "{{\"type\":\"user\",\"cwd\":\"{}\",\"message\":{{\"role\":\"user\",\"content\":\"hi\"}}}}\n",
+                    cwd
+                ),
+            )
+            .unwrap();
+        }
+        // s3 是直接在子目录中启动的会话，没有记录所属项目
+        store
+            .record_session_working_dir("s2", "/work/api", "/work/api/server")
+            .unwrap();
+
+        assert_eq!(
+            store.working_dir_sessions("/work/api/"),
+            vec![("s2".to_string(), "/work/api/server".to_string())]
+        );
+        let mut sessions = store.get_project_sessions(&project_id).unwrap();
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        let listed: Vec<(&str, &str, &str)> = sessions
+            .iter()
+            .map(|s| {
+                (
+                    s.id.as_str(),
+                    s.project_id.as_str(),
+                    s.project_path.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("s1", project_id.as_str(), "/work/api"),
+                ("s2", sub_id.as_str(), "/work/api"),
+            ]
+        );
+        assert_eq!(
+            store.session_working_dir("s2", "/work/api").as_deref(),
+            Some("/work/api/server")
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn workspaces_dedupe_members_and_prune_deleted_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+
+        let workspace = store
+            .create_workspace(
+                " Full stack ",
+                &[
+                    "/work/api".to_string(),
+                    "/work/api/".to_string(),
+                    "/work/web".to_string(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(workspace.name, "Full stack");
+        assert_eq!(workspace.project_paths, vec!["/work/api", "/work/web"]);
+        assert!(store
+            .create_workspace("Relative", &["work/api".to_string()])
+            .is_err());
+
+        store.set_active_workspace(Some(&workspace.id)).unwrap();
+        assert_eq!(
+            store.active_workspace().unwrap().map(|w| w.id),
+            Some(workspace.id.clone())
+        );
+
+        store.prune_workspace_path("/work/api").unwrap();
+        assert_eq!(
+            store.list_workspaces().unwrap()[0].project_paths,
+            vec!["/work/web"]
+        );
+    }
+
+    #[test]
+    fn corrupt_workspaces_file_is_backed_up_instead_of_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        fs::write(store.workspaces_file(), "{ not json").unwrap();
+
+        let error = store.list_workspaces().unwrap_err();
+        assert!(error.contains("corrupt"));
+        assert!(!store.workspaces_file().exists());
+        let backups: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("workspaces.json.corrupt-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&backups[0])).unwrap(),
+            "{ not json"
+        );
+
+        // 备份之后从空列表开始，可以继续创建工作区
+        assert!(store.list_workspaces().unwrap().is_empty());
+        store
+            .create_workspace("Api", &["/work/api".to_string()])
+            .unwrap();
+        assert_eq!(store.list_workspaces().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn corrupt_session_tags_file_is_backed_up_instead_of_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProjectStore {
+            claude_dir: dir.path().to_path_buf(),
+        };
+        let tags_file = store.session_tags_file("-work-api");
+        fs::create_dir_all(tags_file.parent().unwrap()).unwrap();
+        fs::write(&tags_file, "[1, 2").unwrap();
+
+        let error = store.load_session_tags("-work-api").unwrap_err();
+        assert!(error.contains("session tags file is corrupt"));
+        assert!(!tags_file.exists());
+        assert!(store.load_session_tags("-work-api").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! 项目类型识别
+//!
+//! 通过项目目录前两层中的标记文件（Cargo.toml、package.json、pyproject.toml、go.mod 等）
+//! 推断项目使用的语言和框架，用于给出模型、权限预设和 CLAUDE.md 模板等默认建议。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::file_ops::is_skipped_dir;
+use super::models::{DetectedTechnology, ProjectType};
+
+/// 根目录之下最多再遍历的层数
+const MAX_DEPTH: usize = 2;
+
+/// 标记文件名 -> 语言
+const LANGUAGE_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust"),
+    ("package.json", "JavaScript"),
+    ("tsconfig.json", "TypeScript"),
+    ("pyproject.toml", "Python"),
+    ("requirements.txt", "Python"),
+    ("setup.py", "Python"),
+    ("Pipfile", "Python"),
+    ("go.mod", "Go"),
+    ("pom.xml", "Java"),
+    ("build.gradle", "Java"),
+    ("build.gradle.kts", "Kotlin"),
+    ("Gemfile", "Ruby"),
+    ("composer.json", "PHP"),
+    ("CMakeLists.txt", "C/C++"),
+    ("Package.swift", "Swift"),
+    ("pubspec.yaml", "Dart"),
+    ("mix.exs", "Elixir"),
+];
+
+/// 依赖清单文件名 -> [(依赖中出现的关键字, 框架)]
+const FRAMEWORK_MARKERS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "package.json",
+        &[
+            ("\"react\"", "React"),
+            ("\"next\"", "Next.js"),
+            ("\"vue\"", "Vue"),
+            ("\"nuxt\"", "Nuxt"),
+            ("\"svelte\"", "Svelte"),
+            ("\"@angular/core\"", "Angular"),
+            ("\"express\"", "Express"),
+            ("\"electron\"", "Electron"),
+            ("\"@tauri-apps/api\"", "Tauri"),
+        ],
+    ),
+    (
+        "Cargo.toml",
+        &[
+            ("tauri", "Tauri"),
+            ("axum", "Axum"),
+            ("actix-web", "Actix Web"),
+            ("rocket", "Rocket"),
+        ],
+    ),
+    (
+        "pyproject.toml",
+        &[
+            ("django", "Django"),
+            ("flask", "Flask"),
+            ("fastapi", "FastAPI"),
+        ],
+    ),
+    (
+        "requirements.txt",
+        &[
+            ("django", "Django"),
+            ("flask", "Flask"),
+            ("fastapi", "FastAPI"),
+        ],
+    ),
+    (
+        "go.mod",
+        &[("gin-gonic/gin", "Gin"), ("labstack/echo", "Echo")],
+    ),
+    ("pom.xml", &[("spring-boot", "Spring Boot")]),
+    (
+        "build.gradle",
+        &[("org.springframework.boot", "Spring Boot")],
+    ),
+    ("Gemfile", &[("'rails'", "Rails"), ("\"rails\"", "Rails")]),
+    ("composer.json", &[("laravel/framework", "Laravel")]),
+    ("pubspec.yaml", &[("flutter:", "Flutter")]),
+];
+
+/// 标记文件所在深度对应的置信度
+fn depth_confidence(depth: usize) -> f32 {
+    match depth {
+        0 => 1.0,
+        1 => 0.7,
+        _ => 0.5,
+    }
+}
+
+fn record(
+    found: &mut BTreeMap<String, DetectedTechnology>,
+    name: &str,
+    confidence: f32,
+    evidence: &str,
+) {
+    let entry = found
+        .entry(name.to_string())
+        .or_insert_with(|| DetectedTechnology {
+            name: name.to_string(),
+            confidence,
+            evidence: Vec::new(),
+        });
+    entry.confidence = entry.confidence.max(confidence);
+    if !entry.evidence.iter().any(|e| e == evidence) {
+        entry.evidence.push(evidence.to_string());
+    }
+}
+
+fn scan_dir(
+    dir: &Path,
+    root: &Path,
+    depth: usize,
+    languages: &mut BTreeMap<String, DetectedTechnology>,
+    frameworks: &mut BTreeMap<String, DetectedTechnology>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+
+        if path.is_dir() {
+            if depth < MAX_DEPTH && !name.starts_with('.') && !is_skipped_dir(name) {
+                scan_dir(&path, root, depth + 1, languages, frameworks);
+            }
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let confidence = depth_confidence(depth);
+
+        for (marker, language) in LANGUAGE_MARKERS {
+            if name == *marker {
+                record(languages, language, confidence, &relative);
+            }
+        }
+        if name.ends_with(".csproj") || name.ends_with(".sln") {
+            record(languages, "C#", confidence, &relative);
+        }
+
+        if let Some((_, keywords)) = FRAMEWORK_MARKERS.iter().find(|(file, _)| name == *file) {
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let content = content.to_lowercase();
+            for (keyword, framework) in keywords.iter() {
+                if content.contains(&keyword.to_lowercase()) {
+                    record(frameworks, framework, confidence, &relative);
+                }
+            }
+            // 声明了 typescript 依赖的 package.json 也算 TypeScript 项目
+            if name == "package.json" && content.contains("\"typescript\"") {
+                record(languages, "TypeScript", confidence, &relative);
+            }
+        }
+    }
+}
+
+/// 按置信度降序排列，置信度相同时证据多的在前
+fn ranked(found: BTreeMap<String, DetectedTechnology>) -> Vec<DetectedTechnology> {
+    let mut ranked: Vec<DetectedTechnology> = found.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| b.evidence.len().cmp(&a.evidence.len()))
+    });
+    ranked
+}
+
+pub(super) fn detect_in_dir(root: &Path) -> ProjectType {
+    let mut languages = BTreeMap::new();
+    let mut frameworks = BTreeMap::new();
+    scan_dir(root, root, 0, &mut languages, &mut frameworks);
+
+    let languages = ranked(languages);
+    ProjectType {
+        primary_language: languages.first().map(|l| l.name.clone()),
+        languages,
+        frameworks: ranked(frameworks),
+    }
+}
+
+/// Detects the languages and frameworks of a project from marker files in its top levels
+#[tauri::command]
+pub async fn detect_project_type(project_path: String) -> Result<ProjectType, String> {
+    let root = Path::new(&project_path);
+    if !root.is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+    Ok(detect_in_dir(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_languages_and_frameworks_by_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "[dependencies]\ntauri = \"2\"\n").unwrap();
+        fs::create_dir_all(root.join("web/src")).unwrap();
+        fs::write(
+            root.join("web/package.json"),
+            r#"{"dependencies": {"react": "18"}, "devDependencies": {"typescript": "5"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/go.mod"), "module pkg").unwrap();
+
+        let detected = detect_in_dir(root);
+        assert_eq!(detected.primary_language.as_deref(), Some("Rust"));
+        let names: Vec<&str> = detected.languages.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["Rust", "JavaScript", "TypeScript"]);
+        assert_eq!(detected.languages[1].confidence, 0.7);
+        assert_eq!(detected.languages[1].evidence, vec!["web/package.json"]);
+
+        let frameworks: Vec<&str> = detected
+            .frameworks
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(frameworks, vec!["Tauri", "React"]);
+    }
+}
@@ -0,0 +1,182 @@
+//! 发送前的 prompt 校验与规范化
+//!
+//! 通过 stdin 传给 CLI 的 prompt 若夹带终端控制字符（例如从终端粘贴的 ANSI 颜色码）会导致
+//! 异常行为；过长的 prompt 则会消耗大量 token。`prepare_prompt` 清理控制字符并给出体积估算，
+//! 让前端在发送前有机会提醒用户。
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::config::get_claude_execution_config;
+use super::slash_commands::{is_slash_command, known_slash_command_names};
+use crate::commands::permission_config::ClaudeExecutionConfig;
+
+/// Result of validating and normalizing a prompt
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PreparedPrompt {
+    /// The normalized prompt to send
+    pub prompt: String,
+    /// UTF-8 byte length of the normalized prompt
+    pub byte_length: usize,
+    /// Rough token estimate (~4 ASCII chars per token, 1 token per non-ASCII char)
+    pub estimated_tokens: usize,
+    /// Number of control characters / escape sequences removed
+    pub removed_control_chars: usize,
+    /// Whether the prompt is sent as a slash command (`-p` flag)
+    pub is_slash_command: bool,
+    /// Token threshold from the execution config
+    pub warning_threshold: usize,
+    /// Whether estimated_tokens exceeds the threshold
+    pub exceeds_threshold: bool,
+    /// Human readable warnings for the UI
+    pub warnings: Vec<String>,
+}
+
+/// Strip terminal control characters, keeping newlines and tabs
+/// ANSI escape sequences (ESC [ ... final byte) are removed as a whole; CRLF / CR become LF
+fn strip_control_chars(prompt: &str) -> (String, usize) {
+    let mut cleaned = String::with_capacity(prompt.len());
+    let mut removed = 0;
+    let mut chars = prompt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '\t' => cleaned.push(c),
+            '\r' => {
+                if chars.peek() != Some(&'\n') {
+                    cleaned.push('\n');
+                }
+            }
+            '\u{1b}' => {
+                removed += 1;
+                // CSI 序列：ESC [ 参数... 结束字节(0x40-0x7E)
+                if chars.peek() == Some(&'[') {
+                    chars.next();
+                    for next in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+            }
+            c if c.is_control() => removed += 1,
+            c => cleaned.push(c),
+        }
+    }
+
+    (cleaned, removed)
+}
+
+/// 按字符粗略估算 token 数：ASCII 约 4 字符 1 token，其它字符按 1 字符 1 token
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text.chars().fold((0usize, 0usize), |(ascii, other), c| {
+        if c.is_ascii() {
+            (ascii + 1, other)
+        } else {
+            (ascii, other + 1)
+        }
+    });
+    ascii.div_ceil(4) + other
+}
+
+/// Normalize a prompt and compute its size against the warning threshold
+pub fn prepare_prompt_text(
+    prompt: &str,
+    warning_threshold: usize,
+    known_commands: &[String],
+) -> Result<PreparedPrompt, String> {
+    let (cleaned, removed_control_chars) = strip_control_chars(prompt);
+    if cleaned.trim().is_empty() {
+        return Err("Prompt is empty".to_string());
+    }
+
+    let byte_length = cleaned.len();
+    let estimated_tokens = estimate_tokens(&cleaned);
+    let exceeds_threshold = warning_threshold > 0 && estimated_tokens > warning_threshold;
+
+    let mut warnings = Vec::new();
+    if removed_control_chars > 0 {
+        warnings.push(format!(
+            "Removed {} control character(s) from the prompt",
+            removed_control_chars
+        ));
+    }
+    if exceeds_threshold {
+        warnings.push(format!(
+            "Prompt is about {} tokens, above the warning threshold of {}",
+            estimated_tokens, warning_threshold
+        ));
+    }
+
+    Ok(PreparedPrompt {
+        is_slash_command: is_slash_command(&cleaned, known_commands),
+        prompt: cleaned,
+        byte_length,
+        estimated_tokens,
+        removed_control_chars,
+        warning_threshold,
+        exceeds_threshold,
+        warnings,
+    })
+}
+
+/// Sanitize a prompt in the runner when enabled in the execution config
+/// Falls back to the original prompt if it can't be prepared
+pub(super) fn maybe_prepare_prompt(config: &ClaudeExecutionConfig, prompt: String) -> String {
+    if !config.sanitize_prompt {
+        return prompt;
+    }
+    // 这里只用于清理和日志，是否按命令传递由运行器另行判断
+    match prepare_prompt_text(&prompt, config.prompt_warning_tokens, &[]) {
+        Ok(prepared) => {
+            for warning in &prepared.warnings {
+                log::warn!("{}", warning);
+            }
+            prepared.prompt
+        }
+        Err(_) => prompt,
+    }
+}
+
+/// Validate and normalize a prompt before sending it
+#[tauri::command]
+pub async fn prepare_prompt(
+    app: AppHandle,
+    prompt: String,
+    project_path: Option<String>,
+) -> Result<PreparedPrompt, String> {
+    let config = get_claude_execution_config(app, project_path.clone())
+        .await
+        .unwrap_or_else(|_| ClaudeExecutionConfig::default());
+    let known_commands = known_slash_command_names(project_path, &config).await;
+    prepare_prompt_text(&prompt, config.prompt_warning_tokens, &known_commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_ansi_sequences_and_control_chars() {
+        let prepared =
+            prepare_prompt_text("\u{1b}[31mred\u{1b}[0m text\u{7}\r\nnext\tline", 0, &[]).unwrap();
+        assert_eq!(prepared.prompt, "red text\nnext\tline");
+        assert_eq!(prepared.removed_control_chars, 3);
+        assert_eq!(prepared.byte_length, prepared.prompt.len());
+        assert!(!prepared.warnings.is_empty());
+    }
+
+    #[test]
+    fn reports_threshold_and_slash_commands() {
+        let prepared = prepare_prompt_text(&"a".repeat(400), 50, &[]).unwrap();
+        assert_eq!(prepared.estimated_tokens, 100);
+        assert!(prepared.exceeds_threshold);
+        assert!(!prepared.is_slash_command);
+
+        let prepared = prepare_prompt_text("/compact", 50, &[]).unwrap();
+        assert!(prepared.is_slash_command);
+        assert!(!prepared.exceeds_threshold);
+
+        assert!(prepare_prompt_text("\u{1b}[0m \r\n", 50, &[]).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! 系统提示词（CLAUDE.md）预设
+//!
+//! 预设以 `<name>.md` 保存在 `~/.claude/system-prompt-presets/` 下。应用预设会把内容写入
+//! CLAUDE.md，写入前把当前的 CLAUDE.md 备份到 `backups/`，便于切换回去。
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use super::models::SystemPromptPreset;
+use super::paths::{get_claude_dir, validate_file_name};
+
+const PRESETS_DIR: &str = "system-prompt-presets";
+const BACKUPS_DIR: &str = "backups";
+
+fn presets_dir() -> Result<PathBuf, String> {
+    Ok(get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join(PRESETS_DIR))
+}
+
+fn preset_path(name: &str) -> Result<PathBuf, String> {
+    let name = validate_file_name(name, "preset name")?;
+    Ok(presets_dir()?.join(format!("{}.md", name)))
+}
+
+/// Lists the saved system prompt presets
+#[tauri::command]
+pub async fn list_system_prompt_presets() -> Result<Vec<SystemPromptPreset>, String> {
+    let dir = presets_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let current = fs::read_to_string(
+        get_claude_dir()
+            .map_err(|e| e.to_string())?
+            .join("CLAUDE.md"),
+    )
+    .ok();
+
+    let mut presets = Vec::new();
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read presets directory: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("Skipping unreadable preset {:?}: {}", path, e);
+                continue;
+            }
+        };
+        let updated_at = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        presets.push(SystemPromptPreset {
+            name: name.to_string(),
+            active: current.as_deref() == Some(content.as_str()),
+            content,
+            updated_at,
+        });
+    }
+
+    presets.sort_by_key(|p| p.name.to_lowercase());
+    Ok(presets)
+}
+
+/// Saves (creates or overwrites) a system prompt preset
+#[tauri::command]
+pub async fn save_system_prompt_preset(name: String, content: String) -> Result<(), String> {
+    let path = preset_path(&name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create presets directory: {}", e))?;
+    }
+    fs::write(&path, content).map_err(|e| format!("Failed to save preset: {}", e))?;
+    log::info!("Saved system prompt preset '{}'", name.trim());
+    Ok(())
+}
+
+/// Writes a preset into CLAUDE.md after backing up the current file; returns the backup path
+#[tauri::command]
+pub async fn apply_system_prompt_preset(name: String) -> Result<Option<String>, String> {
+    let path = preset_path(&name)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Preset '{}' not found: {}", name.trim(), e))?;
+
+    let claude_md_path = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("CLAUDE.md");
+    let backup_path = if claude_md_path.exists() {
+        let backups_dir = presets_dir()?.join(BACKUPS_DIR);
+        fs::create_dir_all(&backups_dir)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+        let backup_path = backups_dir.join(format!(
+            "CLAUDE.md.{}.bak",
+            chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+        ));
+        fs::copy(&claude_md_path, &backup_path)
+            .map_err(|e| format!("Failed to back up CLAUDE.md: {}", e))?;
+        Some(backup_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    fs::write(&claude_md_path, content).map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+    log::info!(
+        "Applied system prompt preset '{}' (backup: {:?})",
+        name.trim(),
+        backup_path
+    );
+    Ok(backup_path)
+}
+
+/// Deletes a system prompt preset; returns false if it did not exist
+#[tauri::command]
+pub async fn delete_system_prompt_preset(name: String) -> Result<bool, String> {
+    let path = preset_path(&name)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete preset: {}", e))?;
+    log::info!("Deleted system prompt preset '{}'", name.trim());
+    Ok(true)
+}
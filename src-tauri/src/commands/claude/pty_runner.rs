@@ -0,0 +1,526 @@
+//! 基于伪终端（pty）的 Claude 执行模式
+//!
+//! 默认的管道模式下 CLI 检测不到终端，交互式提示（登录、确认）无法完成，进度显示也会退化。
+//! 执行配置开启 `use_pty` 且编译时启用 `pty` feature 时，运行器改为在 portable-pty 创建的伪终端中
+//! 启动 Claude：prompt 写入终端输入（以 EOF 结束），回显的 prompt 不转发；规范模式下终端会截断
+//! 过长的行，含有长行的 prompt 改为作为 `-p` 参数传入，参数也放不下时整个运行改用管道。终端输出按行拆分后交给与
+//! 管道模式相同的 `run_output::ClaudeRun` 处理，限流重试、登录检测、计划捕获、输出翻译与空闲检测都保持一致。
+//! 前端可以用 `write_claude_pty` 回应交互提示、用 `resize_claude_pty` 同步终端尺寸。
+//! 进程退出后关闭伪终端，收尾流程同样与管道模式共用。
+//! 应用内审批需要通过 stdin 交换 stream-json 消息，该模式下始终使用管道。
+
+#[cfg(feature = "pty")]
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "pty")]
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(feature = "pty")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "pty")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "pty")]
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+#[cfg(feature = "pty")]
+use tauri::AppHandle;
+#[cfg(feature = "pty")]
+use tokio::process::Command;
+
+#[cfg(feature = "pty")]
+use crate::commands::permission_config::ClaudeExecutionConfig;
+#[cfg(feature = "pty")]
+use crate::error::{AppError, AppResult};
+
+#[cfg(feature = "pty")]
+use super::attachments::StagedAttachments;
+#[cfg(feature = "pty")]
+use super::platform;
+#[cfg(feature = "pty")]
+use super::run_output::{ClaudeRun, ProcessExit, RateLimitRetry};
+
+/// 前端同步尺寸之前使用的初始终端大小
+#[cfg(feature = "pty")]
+const DEFAULT_ROWS: u16 = 40;
+#[cfg(feature = "pty")]
+const DEFAULT_COLS: u16 = 120;
+
+/// 规范模式下终端输入一行的安全长度：行规程会截断超过 MAX_CANON 的行
+/// （macOS 为 1024 字节，Linux 为 4096），留出换行的余量
+#[cfg(feature = "pty")]
+const MAX_TERMINAL_LINE: usize = 1000;
+
+/// 作为单个命令行参数传入的 prompt 上限（Linux 的 MAX_ARG_STRLEN 为 128 KiB，Windows 整个命令行约 32K 字符）
+#[cfg(all(feature = "pty", not(windows)))]
+const MAX_PROMPT_ARG: usize = 128 * 1024 - 1;
+#[cfg(all(feature = "pty", windows))]
+const MAX_PROMPT_ARG: usize = 30_000;
+
+#[cfg(not(feature = "pty"))]
+const PTY_DISABLED: &str = "This build does not include pty execution (enable the `pty` feature)";
+
+/// 运行中的伪终端会话；移除后关闭主端，读取线程随之结束
+#[cfg(feature = "pty")]
+struct PtySession {
+    session_id: Option<String>,
+    tab_id: Option<String>,
+    master: Box<dyn MasterPty + Send>,
+    writer: PtyWriter,
+}
+
+/// 写入 prompt 的线程与 `write_claude_pty` 共用
+#[cfg(feature = "pty")]
+type PtyWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// 按启动时生成的 key 保存；拿到 Claude 会话 ID 后写入 `session_id`
+#[cfg(feature = "pty")]
+static PTY_SESSIONS: Lazy<Mutex<HashMap<String, PtySession>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[cfg(feature = "pty")]
+fn pty_size(rows: u16, cols: u16) -> PtySize {
+    PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// 按会话 ID（优先）或标签页 ID 查找伪终端会话
+#[cfg(feature = "pty")]
+fn with_pty_session<T>(
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    f: impl FnOnce(&mut PtySession) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut sessions = PTY_SESSIONS.lock().unwrap();
+    let session = sessions
+        .values_mut()
+        .find(|session| match (session_id, tab_id) {
+            (Some(id), _) => session.session_id.as_deref() == Some(id),
+            (None, Some(tab)) => session.tab_id.as_deref() == Some(tab),
+            (None, None) => false,
+        })
+        .ok_or("No pty session found")?;
+    f(session)
+}
+
+/// prompt 传给 pty 中 CLI 的方式
+#[cfg(feature = "pty")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptDelivery {
+    /// 斜杠命令已经以 `-p <prompt>` 在参数中
+    InArgs,
+    /// 写入终端输入
+    TerminalInput,
+    /// 含有超过终端行长度的行，以 `-p <prompt>` 追加到参数
+    Argument,
+}
+
+#[cfg(feature = "pty")]
+impl PromptDelivery {
+    fn choose(prompt: &str, prompt_in_args: bool) -> Option<Self> {
+        if prompt_in_args {
+            Some(Self::InArgs)
+        } else if prompt.lines().all(|line| line.len() < MAX_TERMINAL_LINE) {
+            Some(Self::TerminalInput)
+        } else if prompt.len() <= MAX_PROMPT_ARG {
+            Some(Self::Argument)
+        } else {
+            None
+        }
+    }
+}
+
+/// prompt 能否在 pty 模式下完整传给 CLI；不能时运行器改用管道模式
+#[cfg(feature = "pty")]
+pub(super) fn prompt_fits_pty(prompt: &str, prompt_in_args: bool) -> bool {
+    PromptDelivery::choose(prompt, prompt_in_args).is_some()
+}
+
+/// 由管道模式的命令生成 pty 命令；prompt 写入终端输入时只追加 `-p`
+#[cfg(feature = "pty")]
+fn command_builder(cmd: &Command, delivery: PromptDelivery, prompt: &str) -> CommandBuilder {
+    let source = cmd.as_std();
+    let mut builder = CommandBuilder::new(source.get_program());
+    builder.args(source.get_args());
+    match delivery {
+        PromptDelivery::InArgs => {}
+        PromptDelivery::TerminalInput => builder.arg("-p"),
+        PromptDelivery::Argument => builder.args(["-p", prompt]),
+    }
+    let mut has_term = false;
+    for (key, value) in source.get_envs() {
+        has_term |= key == "TERM";
+        match value {
+            Some(value) => builder.env(key, value),
+            None => builder.env_remove(key),
+        }
+    }
+    // 从桌面启动时没有 TERM，CLI 会按哑终端输出
+    if !has_term {
+        builder.env("TERM", "xterm-256color");
+    }
+    if let Some(dir) = source.get_current_dir() {
+        builder.cwd(dir);
+    }
+    builder
+}
+
+/// 终端输入中的 prompt：以换行结束整行，再以 EOF（Ctrl-D）结束输入
+#[cfg(feature = "pty")]
+fn prompt_input(prompt: &str) -> Vec<u8> {
+    let mut input = prompt.as_bytes().to_vec();
+    if !prompt.ends_with('\n') {
+        input.push(b'\n');
+    }
+    input.push(0x04);
+    input
+}
+
+/// 终端会回显写入的 prompt：CLI 输出之前与 prompt 逐行相同的行视为回显丢弃
+#[cfg(feature = "pty")]
+struct EchoFilter {
+    pending: VecDeque<String>,
+}
+
+#[cfg(feature = "pty")]
+impl EchoFilter {
+    fn new(input: Option<&str>) -> Self {
+        Self {
+            pending: input
+                .map(|input| input.lines().map(str::to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 第一行不匹配的输出之后不再过滤
+    fn is_echo(&mut self, line: &str) -> bool {
+        if self
+            .pending
+            .front()
+            .is_some_and(|expected| expected == line)
+        {
+            self.pending.pop_front();
+            return true;
+        }
+        self.pending.clear();
+        false
+    }
+}
+
+/// 从终端字节流中切出完整的行，去掉 `\r\n` 中的 `\r`；非 UTF-8 内容按有损方式转换
+#[cfg(feature = "pty")]
+fn read_lines(reader: impl Read, mut on_line: impl FnMut(String)) {
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer);
+                on_line(line.trim_end_matches(['\n', '\r']).to_string());
+            }
+            // 子进程退出后部分平台读取主端会返回 EIO，视为结束
+            Err(e) => {
+                log::debug!("pty reader finished: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// 在伪终端中启动 Claude；输出逐行交给与管道模式相同的处理（`run_output::ClaudeRun`）
+#[cfg(feature = "pty")]
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn spawn_claude_pty(
+    app: AppHandle,
+    cmd: Command,
+    prompt: String,
+    prompt_in_args: bool,
+    model: String,
+    project_path: String,
+    tab_id: Option<String>,
+    attachments: StagedAttachments,
+    execution_config: &ClaudeExecutionConfig,
+    retry: RateLimitRetry,
+) -> AppResult<()> {
+    let delivery = PromptDelivery::choose(&prompt, prompt_in_args)
+        .ok_or_else(|| AppError::invalid_config("Prompt is too long for pty mode"))?;
+    let launch = platform::LaunchCommand::from_command(&cmd);
+    log::info!(
+        "Claude pty launch command: {} {:?}",
+        launch.binary,
+        launch.args_for_log(&prompt)
+    );
+    let working_dir = cmd
+        .as_std()
+        .get_current_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .filter(|dir| *dir != project_path);
+    let resume_session_id = launch
+        .args
+        .iter()
+        .position(|arg| arg == "--resume")
+        .and_then(|index| launch.args.get(index + 1).cloned());
+    let session_permit = crate::commands::session_limits::acquire_session_permit(
+        &app,
+        "claude",
+        &project_path,
+        resume_session_id,
+        tab_id.clone(),
+    )
+    .await?;
+
+    let pair = native_pty_system()
+        .openpty(pty_size(DEFAULT_ROWS, DEFAULT_COLS))
+        .map_err(|e| AppError::external(format!("Failed to open pty: {}", e)))?;
+    let mut child = pair
+        .slave
+        .spawn_command(command_builder(&cmd, delivery, &prompt))
+        .map_err(|e| AppError::external(format!("Failed to spawn Claude in pty: {}", e)))?;
+    // 关闭从端，子进程退出后主端才能读到结束
+    drop(pair.slave);
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| AppError::external(format!("Failed to read pty: {}", e)))?;
+    let writer: PtyWriter =
+        Arc::new(Mutex::new(pair.master.take_writer().map_err(|e| {
+            AppError::external(format!("Failed to write pty: {}", e))
+        })?));
+    let pid = child.process_id().unwrap_or(0);
+    log::info!("Spawned Claude in pty with PID: {}", pid);
+
+    // prompt 尽量写入终端输入而不是命令行参数，不出现在进程列表中；
+    // 子进程开始读取前写入可能阻塞，因此放到单独的线程
+    if delivery == PromptDelivery::TerminalInput {
+        let input = prompt_input(&prompt);
+        let writer = writer.clone();
+        std::thread::spawn(move || {
+            let mut writer = writer.lock().unwrap();
+            if let Err(e) = writer.write_all(&input).and_then(|_| writer.flush()) {
+                log::error!("Failed to write prompt to pty: {}", e);
+            }
+        });
+    }
+
+    let pty_key = uuid::Uuid::new_v4().to_string();
+    PTY_SESSIONS.lock().unwrap().insert(
+        pty_key.clone(),
+        PtySession {
+            session_id: None,
+            tab_id: tab_id.clone(),
+            master: pair.master,
+            writer,
+        },
+    );
+
+    let run = Arc::new(
+        ClaudeRun::new(
+            &app,
+            pid,
+            launch,
+            execution_config,
+            project_path,
+            prompt.clone(),
+            model,
+            tab_id,
+            working_dir,
+        )
+        .in_pty(),
+    );
+
+    // 读取线程只负责切行，处理放在异步任务中，与管道模式一样运行在 tokio 运行时里
+    let (line_tx, mut line_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut echo_filter =
+        EchoFilter::new((delivery == PromptDelivery::TerminalInput).then_some(prompt.as_str()));
+    let reader_thread = std::thread::spawn(move || {
+        read_lines(reader, |line| {
+            if echo_filter.is_echo(&line) {
+                log::trace!("Claude pty echo: {}", line);
+                return;
+            }
+            let _ = line_tx.send(line);
+        });
+    });
+    let run_for_output = run.clone();
+    let key_for_output = pty_key.clone();
+    let output_task = tokio::spawn(async move {
+        let mut session_recorded = false;
+        while let Some(line) = line_rx.recv().await {
+            run_for_output.handle_stdout_line(line);
+            if session_recorded {
+                continue;
+            }
+            // 拿到会话 ID 后 write / resize 命令可以按会话 ID 找到终端
+            if let Some(session_id) = run_for_output.session_id() {
+                if let Some(session) = PTY_SESSIONS.lock().unwrap().get_mut(&key_for_output) {
+                    session.session_id = Some(session_id);
+                }
+                session_recorded = true;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let wait_result = tokio::task::spawn_blocking(move || {
+            let status = child.wait();
+            // 关闭主端：Windows ConPTY 在主端关闭前不会结束读取
+            PTY_SESSIONS.lock().unwrap().remove(&pty_key);
+            let _ = reader_thread.join();
+            status
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|status| status.map_err(|e| e.to_string()));
+        let _ = output_task.await;
+
+        let exit = wait_result.map(|status| ProcessExit {
+            success: status.success(),
+            status: format!("code {}", status.exit_code()),
+        });
+        run.finish(exit, retry, attachments, session_permit).await;
+    });
+
+    Ok(())
+}
+
+#[cfg(feature = "pty")]
+fn resize_session(
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    rows: u16,
+    cols: u16,
+) -> Result<(), String> {
+    with_pty_session(session_id, tab_id, |session| {
+        session
+            .master
+            .resize(pty_size(rows, cols))
+            .map_err(|e| format!("Failed to resize pty: {}", e))
+    })
+}
+
+#[cfg(not(feature = "pty"))]
+fn resize_session(
+    _session_id: Option<&str>,
+    _tab_id: Option<&str>,
+    _rows: u16,
+    _cols: u16,
+) -> Result<(), String> {
+    Err(PTY_DISABLED.to_string())
+}
+
+#[cfg(feature = "pty")]
+fn write_session(session_id: Option<&str>, tab_id: Option<&str>, data: &str) -> Result<(), String> {
+    // 写入可能阻塞，不持有全局会话表的锁
+    let writer = with_pty_session(session_id, tab_id, |session| Ok(session.writer.clone()))?;
+    let mut writer = writer.lock().unwrap();
+    writer
+        .write_all(data.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write to pty: {}", e))
+}
+
+#[cfg(not(feature = "pty"))]
+fn write_session(
+    _session_id: Option<&str>,
+    _tab_id: Option<&str>,
+    _data: &str,
+) -> Result<(), String> {
+    Err(PTY_DISABLED.to_string())
+}
+
+/// Resizes the terminal of a Claude session running in pty mode, found by session ID or tab ID
+#[tauri::command]
+pub async fn resize_claude_pty(
+    session_id: Option<String>,
+    tab_id: Option<String>,
+    rows: u16,
+    cols: u16,
+) -> Result<(), String> {
+    resize_session(session_id.as_deref(), tab_id.as_deref(), rows, cols)
+}
+
+/// Sends raw input (e.g. an answer to an interactive prompt) to a Claude session running in pty mode
+#[tauri::command]
+pub async fn write_claude_pty(
+    session_id: Option<String>,
+    tab_id: Option<String>,
+    data: String,
+) -> Result<(), String> {
+    write_session(session_id.as_deref(), tab_id.as_deref(), &data)
+}
+
+#[cfg(all(test, feature = "pty"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_terminal_output_into_lines() {
+        let mut lines = Vec::new();
+        read_lines(
+            &b"{\"type\":\"system\"}\r\nplain text\r\npartial"[..],
+            |line| lines.push(line),
+        );
+        assert_eq!(lines, ["{\"type\":\"system\"}", "plain text", "partial"]);
+    }
+
+    #[test]
+    fn prompt_is_written_to_terminal_input() {
+        let mut cmd = Command::new("claude");
+        cmd.args(["--output-format", "stream-json"]);
+        let builder = command_builder(&cmd, PromptDelivery::TerminalInput, "hello");
+        assert_eq!(
+            *builder.get_argv(),
+            ["claude", "--output-format", "stream-json", "-p"]
+        );
+        assert_eq!(builder.get_env("TERM").unwrap(), "xterm-256color");
+        assert_eq!(prompt_input("hello"), b"hello\n\x04");
+        assert_eq!(prompt_input("a\nb\n"), b"a\nb\n\x04");
+
+        let builder = command_builder(&cmd, PromptDelivery::InArgs, "/help");
+        assert_eq!(builder.get_argv().len(), 3);
+    }
+
+    #[test]
+    fn long_single_line_prompt_is_passed_as_argument() {
+        let long_line = "x".repeat(5000);
+        let prompt = format!("short line\n{}", long_line);
+        assert_eq!(
+            PromptDelivery::choose(&prompt, false),
+            Some(PromptDelivery::Argument)
+        );
+
+        let mut cmd = Command::new("claude");
+        cmd.args(["--output-format", "stream-json"]);
+        let builder = command_builder(&cmd, PromptDelivery::Argument, &prompt);
+        let argv = builder.get_argv();
+        assert_eq!(argv[argv.len() - 2], "-p");
+        assert_eq!(argv[argv.len() - 1], prompt.as_str());
+
+        assert_eq!(
+            PromptDelivery::choose("fits\nin lines", false),
+            Some(PromptDelivery::TerminalInput)
+        );
+        assert_eq!(
+            PromptDelivery::choose(&long_line, true),
+            Some(PromptDelivery::InArgs)
+        );
+        assert!(!prompt_fits_pty(&"y".repeat(MAX_PROMPT_ARG + 1), false));
+    }
+
+    #[test]
+    fn drops_echoed_prompt_lines_only() {
+        let mut filter = EchoFilter::new(Some("fix the bug\nin main.rs"));
+        assert!(filter.is_echo("fix the bug"));
+        assert!(filter.is_echo("in main.rs"));
+        assert!(!filter.is_echo("in main.rs"));
+
+        let mut filter = EchoFilter::new(Some("hello"));
+        assert!(!filter.is_echo("{\"type\":\"system\"}"));
+        assert!(!filter.is_echo("hello"));
+
+        assert!(!EchoFilter::new(None).is_echo("hello"));
+    }
+}
@@ -0,0 +1,204 @@
+//! 限流 / 过载错误识别
+//!
+//! Claude CLI 遇到 429 / 529 时只会在输出流中给出原始错误文本，这里从 stream-json 的错误消息
+//! 和 stderr 中识别出限流信息以及建议的重试间隔，供运行器发送 `claude-rate-limited` 事件和自动重试。
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::commands::permission_config::RateLimitRetryConfig;
+
+static STATUS_CODE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(429|529)\b").expect("valid status regex"));
+/// `retry-after: 30` / `"retry_after": 30` / `Retry-After=30`
+static RETRY_AFTER_HEADER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)retry[-_ ]after"?\s*[:=]?\s*"?(\d+)"#).expect("valid retry-after regex")
+});
+/// `try again in 30 seconds` / `retrying in 2m`
+static RETRY_IN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:try again|retry(?:ing)?) in (\d+)\s*(m|min|minutes?|s|sec|seconds?)?\b")
+        .expect("valid retry-in regex")
+});
+
+/// 正在等待自动重试的会话；取消执行时从这里移除即可阻止重试
+static PENDING_RETRIES: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum RateLimitKind {
+    RateLimit,
+    Overloaded,
+}
+
+/// 从输出中识别出的限流信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(super) struct RateLimitInfo {
+    pub kind: RateLimitKind,
+    pub status: Option<u16>,
+    /// 服务端建议的重试间隔（秒）
+    pub retry_after_secs: Option<u64>,
+    pub message: String,
+}
+
+/// `claude-rate-limited` 事件内容，每次失败的尝试发送一次
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct RateLimitEvent {
+    pub session_id: Option<String>,
+    pub tab_id: Option<String>,
+    #[serde(flatten)]
+    pub info: RateLimitInfo,
+    /// 本次是第几次尝试（从 1 开始）
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub will_retry: bool,
+    /// 自动重试前等待的秒数
+    pub retry_in_secs: Option<u64>,
+}
+
+/// 检查 stream-json 中的错误消息（普通助手输出不检查，避免误判对话内容）
+pub(super) fn detect_in_message(msg: &Value) -> Option<RateLimitInfo> {
+    let text = match msg["type"].as_str() {
+        Some("result") if msg["is_error"].as_bool() == Some(true) => {
+            msg["result"].as_str().map(str::to_string)
+        }
+        Some("error") => Some(match &msg["error"] {
+            Value::String(text) => text.clone(),
+            Value::Null => msg.to_string(),
+            error => error["message"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string()),
+        }),
+        Some("assistant") if msg["isApiErrorMessage"].as_bool() == Some(true) => msg["message"]
+            ["content"][0]["text"]
+            .as_str()
+            .map(str::to_string),
+        _ => None,
+    }?;
+    detect_in_text(&text)
+}
+
+/// 检查一行错误文本（stderr 或错误消息内容）
+pub(super) fn detect_in_text(text: &str) -> Option<RateLimitInfo> {
+    let lower = text.to_lowercase();
+    let status = STATUS_CODE
+        .captures(text)
+        .and_then(|caps| caps[1].parse::<u16>().ok());
+
+    // 单独的数字不足以判断（例如 "Compiled 429 files"），需要同时出现错误字样
+    let is_error = lower.contains("error");
+    let kind = if lower.contains("overloaded") || (status == Some(529) && is_error) {
+        RateLimitKind::Overloaded
+    } else if (status == Some(429) && is_error)
+        || lower.contains("rate_limit")
+        || lower.contains("rate limit")
+        || lower.contains("too many requests")
+    {
+        RateLimitKind::RateLimit
+    } else {
+        return None;
+    };
+
+    Some(RateLimitInfo {
+        kind,
+        status,
+        retry_after_secs: parse_retry_after(text),
+        message: text.trim().chars().take(500).collect(),
+    })
+}
+
+fn parse_retry_after(text: &str) -> Option<u64> {
+    if let Some(caps) = RETRY_AFTER_HEADER.captures(text) {
+        return caps[1].parse().ok();
+    }
+    let caps = RETRY_IN.captures(text)?;
+    let value: u64 = caps[1].parse().ok()?;
+    let is_minutes = caps
+        .get(2)
+        .is_some_and(|unit| unit.as_str().to_lowercase().starts_with('m'));
+    Some(if is_minutes { value * 60 } else { value })
+}
+
+pub(super) fn mark_retry_pending(session_id: &str) {
+    if let Ok(mut pending) = PENDING_RETRIES.lock() {
+        pending.insert(session_id.to_string());
+    }
+}
+
+/// 等待结束后调用：返回 false 表示期间已被取消
+pub(super) fn take_pending_retry(session_id: &str) -> bool {
+    PENDING_RETRIES
+        .lock()
+        .map(|mut pending| pending.remove(session_id))
+        .unwrap_or(true)
+}
+
+/// 取消等待中的自动重试，返回是否存在这样的重试
+pub(super) fn cancel_pending_retry(session_id: &str) -> bool {
+    PENDING_RETRIES
+        .lock()
+        .map(|mut pending| pending.remove(session_id))
+        .unwrap_or(false)
+}
+
+/// 第 `attempt` 次重试前的等待秒数：优先使用服务端建议值，否则指数退避，均不超过上限
+pub(super) fn backoff_delay_secs(
+    attempt: u32,
+    retry_after_secs: Option<u64>,
+    config: &RateLimitRetryConfig,
+) -> u64 {
+    let delay = retry_after_secs.unwrap_or_else(|| {
+        let exponent = attempt.saturating_sub(1).min(16);
+        config.base_delay_secs.saturating_mul(1u64 << exponent)
+    });
+    delay.clamp(1, config.max_delay_secs.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_rate_limits_and_computes_backoff() {
+        let result = json!({
+            "type": "result",
+            "is_error": true,
+            "result": "API Error: 429 {\"type\":\"error\",\"error\":{\"type\":\"rate_limit_error\"}} retry-after: 42",
+        });
+        let info = detect_in_message(&result).unwrap();
+        assert_eq!(info.kind, RateLimitKind::RateLimit);
+        assert_eq!(info.status, Some(429));
+        assert_eq!(info.retry_after_secs, Some(42));
+
+        let info = detect_in_text("API Error: Overloaded, please try again in 2 minutes").unwrap();
+        assert_eq!(info.kind, RateLimitKind::Overloaded);
+        assert_eq!(info.retry_after_secs, Some(120));
+
+        // Regular conversation output is never inspected
+        let assistant = json!({
+            "type": "assistant",
+            "message": { "content": [{ "type": "text", "text": "the server was overloaded (429)" }] },
+        });
+        assert!(detect_in_message(&assistant).is_none());
+        assert!(detect_in_text("Compiled 429 files").is_none());
+        assert!(detect_in_text("All tests passed").is_none());
+
+        let config = RateLimitRetryConfig {
+            enabled: true,
+            max_retries: 3,
+            base_delay_secs: 10,
+            max_delay_secs: 60,
+        };
+        assert_eq!(backoff_delay_secs(1, None, &config), 10);
+        assert_eq!(backoff_delay_secs(3, None, &config), 40);
+        assert_eq!(backoff_delay_secs(4, None, &config), 60);
+        assert_eq!(backoff_delay_secs(1, Some(5), &config), 5);
+        assert_eq!(backoff_delay_secs(1, Some(600), &config), 60);
+    }
+}
@@ -0,0 +1,160 @@
+//! 可恢复的会话列表
+//!
+//! 合并项目目录中已保存的会话与进程注册表中正在运行的会话，供恢复会话时选择。
+
+use std::collections::HashSet;
+
+use crate::process::{ProcessInfo, ProcessRegistryState, ProcessType};
+
+use super::models::{ResumableSession, Session};
+use super::paths::encode_project_path;
+use super::project_store::ProjectStore;
+
+/// Lists the sessions of a project that can be resumed, marking the ones with a running process
+#[tauri::command]
+pub async fn list_resumable_sessions(
+    registry: tauri::State<'_, ProcessRegistryState>,
+    project_id: String,
+) -> Result<Vec<ResumableSession>, String> {
+    let store = ProjectStore::new()?;
+    // 新建会话在 CLI 写入 JSONL 之前项目目录可能还不存在，此时只返回运行中的会话
+    let sessions = if store.projects_dir().join(&project_id).exists() {
+        store.get_project_sessions(&project_id)?
+    } else {
+        Vec::new()
+    };
+    let running = registry.0.get_running_claude_sessions()?;
+    Ok(merge_resumable_sessions(&project_id, sessions, running))
+}
+
+/// 合并磁盘上的会话与注册表中运行的会话（按 session_id 去重），运行中的排在前面，其余按最近活动排序
+fn merge_resumable_sessions(
+    project_id: &str,
+    sessions: Vec<Session>,
+    running: Vec<ProcessInfo>,
+) -> Vec<ResumableSession> {
+    let stored_ids: HashSet<String> = sessions.iter().map(|s| s.id.clone()).collect();
+    let running: Vec<(String, ProcessInfo)> = running
+        .into_iter()
+        .filter_map(|info| match &info.process_type {
+            ProcessType::ClaudeSession { session_id } => Some((session_id.clone(), info)),
+            _ => None,
+        })
+        .filter(|(session_id, info)| {
+            stored_ids.contains(session_id) || encode_project_path(&info.project_path) == project_id
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut merged: Vec<ResumableSession> = Vec::new();
+    for session in sessions {
+        if !seen.insert(session.id.clone()) {
+            continue;
+        }
+        let process = running.iter().find(|(id, _)| *id == session.id);
+        merged.push(ResumableSession {
+            status: if process.is_some() { "running" } else { "idle" }.to_string(),
+            pid: process.map(|(_, info)| info.pid),
+            run_id: process.map(|(_, info)| info.run_id),
+            last_activity: session
+                .last_activity
+                .or_else(|| process.map(|(_, info)| info.started_at.to_rfc3339())),
+            session_id: session.id,
+            project_id: session.project_id,
+            project_path: session.project_path,
+            first_message: session.first_message,
+            model: session.model,
+            message_count: session.message_count,
+        });
+    }
+
+    // 还没有写入磁盘的运行中会话
+    for (session_id, info) in running {
+        if !seen.insert(session_id.clone()) {
+            continue;
+        }
+        merged.push(ResumableSession {
+            session_id,
+            project_id: project_id.to_string(),
+            project_path: info.project_path,
+            status: "running".to_string(),
+            pid: Some(info.pid),
+            run_id: Some(info.run_id),
+            last_activity: Some(info.started_at.to_rfc3339()),
+            first_message: Some(info.task).filter(|task| !task.is_empty()),
+            model: Some(info.model).filter(|model| !model.is_empty()),
+            message_count: 0,
+        });
+    }
+
+    merged.sort_by(|a, b| {
+        (b.status == "running")
+            .cmp(&(a.status == "running"))
+            .then_with(|| b.last_activity.cmp(&a.last_activity))
+    });
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn session(id: &str, last_activity: &str) -> Session {
+        Session {
+            id: id.to_string(),
+            project_id: "-work-app".to_string(),
+            project_path: "/work/app".to_string(),
+            todo_data: None,
+            created_at: 0,
+            first_message: Some(format!("message {}", id)),
+            message_timestamp: None,
+            last_message_timestamp: None,
+            model: None,
+            tags: Vec::new(),
+            pinned: false,
+            message_count: 2,
+            first_activity: None,
+            last_activity: Some(last_activity.to_string()),
+        }
+    }
+
+    fn process(session_id: &str, project_path: &str, pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            run_id: pid as i64,
+            process_type: ProcessType::ClaudeSession {
+                session_id: session_id.to_string(),
+            },
+            pid,
+            started_at: Utc::now(),
+            project_path: project_path.to_string(),
+            task: "fix the build".to_string(),
+            model: "sonnet".to_string(),
+        }
+    }
+
+    #[test]
+    fn merges_running_and_stored_sessions_without_duplicates() {
+        let sessions = vec![
+            session("old", "2025-01-01T00:00:00Z"),
+            session("recent", "2025-03-01T00:00:00Z"),
+            session("active", "2025-02-01T00:00:00Z"),
+        ];
+        let running = vec![
+            process("active", "/work/app", 100),
+            process("fresh", "/work/app", 200),
+            process("elsewhere", "/work/other", 300),
+        ];
+
+        let merged = merge_resumable_sessions("-work-app", sessions, running);
+        let ids: Vec<&str> = merged.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["fresh", "active", "recent", "old"]);
+
+        assert_eq!(merged[0].status, "running");
+        assert_eq!(merged[0].first_message.as_deref(), Some("fix the build"));
+        assert_eq!(merged[1].pid, Some(100));
+        assert_eq!(merged[1].message_count, 2);
+        assert_eq!(merged[2].status, "idle");
+        assert_eq!(merged[2].pid, None);
+    }
+}
@@ -0,0 +1,799 @@
+//! Claude 运行的输出处理与收尾
+//!
+//! 管道模式（`cli_runner`）与伪终端模式（`pty_runner`）共用同一套逐行处理：会话 ID 登记、
+//! 应用内审批、限流识别、登录检测、计划捕获、输出翻译、上下文统计以及输出事件转发。
+//! 进程退出后的通知、完成事件、注册表清理与限流自动重试同样由这里完成，两种模式的行为保持一致。
+
+use std::ffi::{OsStr, OsString};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::commands::agent::{self, AgentTool};
+use crate::commands::auth_detection::{self, AuthRequiredDetector};
+use crate::commands::notifications::{notify_session_event, SessionNotification};
+use crate::commands::permission_config::{ClaudeExecutionConfig, RateLimitRetryConfig};
+use crate::process::concurrency::SessionPermit;
+#[cfg(windows)]
+use crate::process::JobObject;
+use crate::process::{ProcessRegistry, ProcessRegistryState};
+
+use super::ansi;
+use super::attachments::StagedAttachments;
+use super::output_filter::{self, OutputNoiseFilter};
+use super::output_translation;
+use super::permission_prompt;
+use super::plan_capture;
+use super::platform::{self, LaunchCommand};
+use super::rate_limit;
+
+/// 进程的退出结果
+pub(super) struct ProcessExit {
+    pub success: bool,
+    /// 退出状态的文字描述，用于日志与通知
+    pub status: String,
+}
+
+/// 限流自动重试的状态
+pub(super) struct RateLimitRetry {
+    /// 已经重试的次数，首次执行为 0
+    pub attempt: u32,
+    pub config: RateLimitRetryConfig,
+    /// 还允许重试时保存的原始命令（追加 -p 参数之前复制）
+    pub command: Option<Command>,
+    pub app_approval: bool,
+}
+
+impl RateLimitRetry {
+    pub(super) fn new(
+        config: RateLimitRetryConfig,
+        attempt: u32,
+        cmd: &Command,
+        app_approval: bool,
+    ) -> Self {
+        Self {
+            attempt,
+            config,
+            command: (config.enabled && attempt < config.max_retries).then(|| rebuild_command(cmd)),
+            app_approval,
+        }
+    }
+}
+
+/// 一次 Claude 运行的上下文，由读取输出的任务与等待退出的任务共享
+pub(super) struct ClaudeRun {
+    app: AppHandle,
+    registry: Arc<ProcessRegistry>,
+    pid: u32,
+    project_path: String,
+    prompt: String,
+    model: String,
+    tab_id: Option<String>,
+    /// 与项目路径不同的工作目录，拿到会话 ID 后记录下来供恢复会话使用
+    working_dir: Option<String>,
+    /// 最终的启动命令，随 started 事件一起发送
+    launch: LaunchCommand,
+    pty: bool,
+    noise_filter: OutputNoiseFilter,
+    strip_ansi: bool,
+    /// 应用内审批模式下与 CLI 交换 stream-json 消息的 stdin
+    approval_stdin: Option<permission_prompt::SharedStdin>,
+    /// Plan 模式下从输出中捕获计划，供前端展示“批准计划”
+    plan_mode: bool,
+    plan_captured: AtomicBool,
+    /// stdout 与 stderr 共用一个检测器，登录提示只上报一次
+    auth_detector: Mutex<AuthRequiredDetector>,
+    /// 输出中识别到的限流 / 过载错误
+    rate_limit: Mutex<Option<rate_limit::RateLimitInfo>>,
+    session_id: Mutex<Option<String>>,
+    run_id: Mutex<Option<i64>>,
+    #[cfg(windows)]
+    job_object: Mutex<Option<Arc<JobObject>>>,
+}
+
+impl ClaudeRun {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new(
+        app: &AppHandle,
+        pid: u32,
+        launch: LaunchCommand,
+        execution_config: &ClaudeExecutionConfig,
+        project_path: String,
+        prompt: String,
+        model: String,
+        tab_id: Option<String>,
+        working_dir: Option<String>,
+    ) -> Self {
+        Self {
+            app: app.clone(),
+            registry: app.state::<ProcessRegistryState>().0.clone(),
+            pid,
+            project_path,
+            prompt,
+            model,
+            tab_id,
+            working_dir,
+            plan_mode: plan_capture::is_plan_mode(&launch.args),
+            launch,
+            pty: false,
+            noise_filter: OutputNoiseFilter::new(&execution_config.output_noise_patterns),
+            strip_ansi: execution_config.strip_ansi,
+            approval_stdin: None,
+            plan_captured: AtomicBool::new(false),
+            auth_detector: Mutex::new(AuthRequiredDetector::new(AgentTool::Claude)),
+            rate_limit: Mutex::new(None),
+            session_id: Mutex::new(None),
+            run_id: Mutex::new(None),
+            #[cfg(windows)]
+            job_object: Mutex::new(None),
+        }
+    }
+
+    /// 标记为伪终端中的运行（started 事件带 `pty: true`）
+    #[cfg(feature = "pty")]
+    pub(super) fn in_pty(mut self) -> Self {
+        self.pty = true;
+        self
+    }
+
+    pub(super) fn with_approval_stdin(
+        mut self,
+        stdin: Option<permission_prompt::SharedStdin>,
+    ) -> Self {
+        self.approval_stdin = stdin;
+        self
+    }
+
+    /// 启动后立即创建的 Job Object，登记会话时交给 ProcessRegistry
+    #[cfg(windows)]
+    pub(super) fn with_job_object(self, job_object: Option<Arc<JobObject>>) -> Self {
+        *self.job_object.lock().unwrap() = job_object;
+        self
+    }
+
+    /// 从 init 消息中拿到的 Claude 会话 ID
+    pub(super) fn session_id(&self) -> Option<String> {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    /// 处理一行 stdout：识别消息后转发给前端，噪声与权限请求不转发
+    pub(super) fn handle_stdout_line(&self, line: String) {
+        // Use trace level to avoid flooding logs in debug mode
+        log::trace!("Claude stdout: {}", line);
+        // 去掉 ANSI 颜色 / 光标序列，只有控制序列的行直接丢弃
+        let Some(line) = ansi::clean_line(line, self.strip_ansi) else {
+            return;
+        };
+
+        // 升级提示等纯文本噪声只写日志，不进入会话输出
+        let line_kind = output_filter::classify_line(&line);
+        if self.noise_filter.is_noise(line_kind, &line) {
+            log::info!("Filtered Claude stdout banner: {}", line);
+            return;
+        }
+        self.check_auth_required(&line);
+
+        if let Ok(msg) = serde_json::from_str::<Value>(&line) {
+            if !self.handle_message(msg) {
+                return;
+            }
+        }
+
+        // Store live output in registry if we have a run_id
+        if let Some(run_id) = *self.run_id.lock().unwrap() {
+            let _ = self.registry.append_live_output(run_id, &line);
+        }
+
+        // Emit the line to the frontend with session isolation if we have session ID
+        let current_session_id = self.session_id();
+        if let Some(ref session_id) = current_session_id {
+            let _ = self
+                .app
+                .emit(&format!("claude-output:{}", session_id), &line);
+        }
+        // 🔒 CRITICAL FIX: 全局事件包含 tab_id，用于前端过滤新建会话的消息
+        let global_payload = serde_json::json!({
+            "tab_id": self.tab_id,
+            "payload": &line
+        });
+        let _ = self.app.emit("claude-output", &global_payload);
+        // 统一事件同时携带行类型，前端据此决定按 JSON 解析还是按纯文本展示
+        agent::emit_agent_output(
+            &self.app,
+            AgentTool::Claude,
+            current_session_id.as_deref(),
+            self.tab_id.as_deref(),
+            Some(line_kind.as_str()),
+            &line,
+        );
+    }
+
+    /// 处理一行 stderr：识别限流与登录提示后作为错误输出转发
+    pub(super) fn handle_stderr_line(&self, line: String) {
+        log::error!("Claude stderr: {}", line);
+        let Some(line) = ansi::clean_line(line, self.strip_ansi) else {
+            return;
+        };
+        if let Some(info) = rate_limit::detect_in_text(&line) {
+            self.record_rate_limit(info);
+        }
+        self.check_auth_required(&line);
+
+        // Emit error lines to the frontend with session isolation if we have session ID
+        let current_session_id = self.session_id();
+        if let Some(ref session_id) = current_session_id {
+            let _ = self
+                .app
+                .emit(&format!("claude-error:{}", session_id), &line);
+        }
+        // 🔒 CRITICAL FIX: 全局事件包含 tab_id
+        let global_payload = serde_json::json!({
+            "tab_id": self.tab_id,
+            "payload": &line
+        });
+        let _ = self.app.emit("claude-error", &global_payload);
+        agent::emit_agent_error(
+            &self.app,
+            AgentTool::Claude,
+            current_session_id.as_deref(),
+            self.tab_id.as_deref(),
+            &line,
+        );
+    }
+
+    fn check_auth_required(&self, line: &str) {
+        if self.auth_detector.lock().unwrap().check(line) {
+            let session_id = self.session_id();
+            auth_detection::handle_auth_required(
+                &self.app,
+                AgentTool::Claude,
+                session_id.as_deref(),
+                self.tab_id.as_deref(),
+                self.pid,
+                line,
+            );
+        }
+    }
+
+    /// 处理一条 stream-json 消息；返回 false 表示该行不作为普通输出转发
+    fn handle_message(&self, msg: Value) -> bool {
+        if let Some(stdin) = &self.approval_stdin {
+            // 工具权限请求交给应用审批，不作为普通输出转发
+            if permission_prompt::is_permission_request(&msg) {
+                let tool_name = msg["request"]["tool_name"].as_str().unwrap_or("A tool");
+                notify_session_event(
+                    &self.app,
+                    SessionNotification::PermissionRequest,
+                    &self.project_path,
+                    self.tab_id.as_deref(),
+                    &format!("{} is waiting for approval", tool_name),
+                );
+                permission_prompt::handle_permission_request(
+                    self.app.clone(),
+                    stdin.clone(),
+                    msg,
+                    self.session_id(),
+                    self.tab_id.clone(),
+                );
+                return false;
+            }
+            // 本轮结束后关闭 stdin，CLI 才会退出
+            if msg["type"] == "result" {
+                let stdin = stdin.clone();
+                tokio::spawn(async move {
+                    if let Some(mut stdin) = stdin.lock().await.take() {
+                        let _ = stdin.shutdown().await;
+                    }
+                });
+            }
+        }
+
+        if let Some(info) = rate_limit::detect_in_message(&msg) {
+            self.record_rate_limit(info);
+        }
+
+        if self.plan_mode {
+            self.capture_plan(&msg);
+        }
+
+        // 完整的 assistant 消息按会话设置翻译（不处理流式分片）
+        if msg["type"] == "assistant" {
+            if let Some(session_id) = self.session_id() {
+                output_translation::translate_assistant_message(&self.app, &session_id, &msg);
+            }
+        }
+
+        if msg["type"] == "system" && msg["subtype"] == "init" {
+            if let Some(claude_session_id) = msg["session_id"].as_str() {
+                self.register_session(claude_session_id);
+            }
+        }
+
+        // Check for usage information and update context tracking
+        if let Some(usage) = msg.get("usage") {
+            if let (Some(input_tokens), Some(output_tokens)) = (
+                usage.get("input_tokens").and_then(|t| t.as_u64()),
+                usage.get("output_tokens").and_then(|t| t.as_u64()),
+            ) {
+                self.update_context_tokens((input_tokens + output_tokens) as usize);
+            }
+        }
+        true
+    }
+
+    fn capture_plan(&self, msg: &Value) {
+        // ExitPlanMode 总是以最新一次为准；没有调用时退回到最终 result 文本
+        let plan = plan_capture::detect_exit_plan_mode(msg)
+            .map(|plan| (plan, plan_capture::PlanSource::ExitPlanMode))
+            .or_else(|| {
+                if self.plan_captured.load(Ordering::Relaxed) {
+                    return None;
+                }
+                plan_capture::detect_result_text(msg)
+                    .map(|text| (text, plan_capture::PlanSource::ResultMessage))
+            });
+        if let Some((plan, source)) = plan {
+            self.plan_captured.store(true, Ordering::Relaxed);
+            plan_capture::capture_plan(
+                &self.app,
+                self.session_id(),
+                self.tab_id.as_deref(),
+                plan,
+                source,
+            );
+        }
+    }
+
+    /// 收到 init 消息后记录会话 ID，并登记到 auto-compact 管理器与 ProcessRegistry
+    fn register_session(&self, claude_session_id: &str) {
+        {
+            let mut session_id_guard = self.session_id.lock().unwrap();
+            if session_id_guard.is_some() {
+                return;
+            }
+            *session_id_guard = Some(claude_session_id.to_string());
+        }
+        log::info!("Extracted Claude session ID: {}", claude_session_id);
+
+        if let Some(tab_id) = &self.tab_id {
+            output_translation::adopt_tab(tab_id, claude_session_id);
+        }
+
+        if let Some(working_dir) = &self.working_dir {
+            super::record_session_working_dir(claude_session_id, &self.project_path, working_dir);
+        }
+
+        // Register with auto-compact manager
+        if let Some(auto_compact_state) = self
+            .app
+            .try_state::<crate::commands::context_manager::AutoCompactState>()
+        {
+            if let Err(e) = auto_compact_state.0.register_session(
+                claude_session_id.to_string(),
+                self.project_path.clone(),
+                self.model.clone(),
+            ) {
+                log::warn!(
+                    "Failed to register session with auto-compact manager: {}",
+                    e
+                );
+            }
+        }
+
+        // Now register with ProcessRegistry using Claude's session ID
+        // 🔧 FIX: Pass the pre-created Job Object to avoid orphan processes
+        #[cfg(windows)]
+        let job_object_for_register = self.job_object.lock().unwrap().take();
+        #[cfg(not(windows))]
+        let job_object_for_register: Option<()> = None;
+
+        match self.registry.register_claude_session_with_job(
+            claude_session_id.to_string(),
+            self.pid,
+            self.project_path.clone(),
+            self.prompt.clone(),
+            self.model.clone(),
+            job_object_for_register,
+        ) {
+            Ok(run_id) => {
+                log::info!("Registered Claude session with run_id: {}", run_id);
+                *self.run_id.lock().unwrap() = Some(run_id);
+
+                // ✨ Phase 2: Emit event for real-time session tracking
+                let mut event_payload = serde_json::json!({
+                    "session_id": claude_session_id,
+                    "project_path": self.project_path,
+                    "model": self.model,
+                    "status": "started",
+                    "pid": self.pid,
+                    "run_id": run_id,
+                    "binary": self.launch.binary,
+                    "args": self.launch.args,
+                    "env": self.launch.env,
+                });
+                if self.pty {
+                    event_payload["pty"] = Value::Bool(true);
+                }
+                agent::emit_agent_session_state(
+                    &self.app,
+                    AgentTool::Claude,
+                    Some(claude_session_id),
+                    &event_payload,
+                );
+                if let Err(e) = self.app.emit("claude-session-state", &event_payload) {
+                    log::warn!("Failed to emit claude-session-state event: {}", e);
+                } else {
+                    log::info!(
+                        "Emitted claude-session-started event for session: {}",
+                        claude_session_id
+                    );
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to register Claude session: {}", e);
+            }
+        }
+    }
+
+    /// 把最新的 token 用量交给 auto-compact 管理器
+    fn update_context_tokens(&self, total_tokens: usize) {
+        let Some(session_id) = self.session_id() else {
+            return;
+        };
+        let Some(auto_compact_state) = self
+            .app
+            .try_state::<crate::commands::context_manager::AutoCompactState>()
+        else {
+            return;
+        };
+        let auto_compact_state = auto_compact_state.inner().clone();
+        // Spawn async task to avoid blocking main output loop
+        tokio::spawn(async move {
+            match auto_compact_state
+                .0
+                .update_session_tokens(&session_id, total_tokens)
+                .await
+            {
+                Ok(true) => {
+                    // The actual compaction will be handled by the background monitoring thread
+                    log::info!("Auto-compaction triggered for session {}", session_id);
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    log::warn!("Failed to update session tokens for auto-compact: {}", e);
+                }
+            }
+        });
+    }
+
+    /// 记录识别到的限流信息，优先保留带有重试间隔提示的那条
+    fn record_rate_limit(&self, info: rate_limit::RateLimitInfo) {
+        let mut current = self.rate_limit.lock().unwrap();
+        if current
+            .as_ref()
+            .and_then(|existing| existing.retry_after_secs)
+            .is_none()
+        {
+            *current = Some(info);
+        }
+    }
+
+    /// 进程退出、输出读取完毕后调用：发送通知与完成事件、注销进程，需要时退避后自动重试。
+    /// 重试期间不发送 complete 事件，并发名额在等待期间释放
+    pub(super) async fn finish(
+        self: Arc<Self>,
+        exit: Result<ProcessExit, String>,
+        retry: RateLimitRetry,
+        attachments: StagedAttachments,
+        session_permit: SessionPermit,
+    ) {
+        let session_id = self.session_id();
+        let retry_delay = self.report_rate_limit(session_id.as_deref(), &retry);
+        if let (Some(_), Some(session_id)) = (retry_delay, &session_id) {
+            rate_limit::mark_retry_pending(session_id);
+        }
+
+        if retry_delay.is_none() {
+            if self.plan_mode && !self.plan_captured.load(Ordering::Relaxed) {
+                plan_capture::report_missing_plan(
+                    &self.app,
+                    session_id.as_deref(),
+                    self.tab_id.as_deref(),
+                );
+            }
+            let (event, detail) = match &exit {
+                Ok(exit) if exit.success => (
+                    SessionNotification::Complete,
+                    "Claude finished the task".to_string(),
+                ),
+                Ok(exit) => (
+                    SessionNotification::Error,
+                    format!("Claude exited with {}", exit.status),
+                ),
+                Err(e) => (SessionNotification::Error, e.clone()),
+            };
+            notify_session_event(
+                &self.app,
+                event,
+                &self.project_path,
+                self.tab_id.as_deref(),
+                &detail,
+            );
+        }
+
+        let success = match &exit {
+            Ok(exit) => {
+                log::info!("Claude process exited with status: {}", exit.status);
+                exit.success
+            }
+            Err(e) => {
+                log::error!("Failed to wait for Claude process: {}", e);
+                false
+            }
+        };
+        // Add a small delay to ensure all messages are processed
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        if let Some(ref session_id) = session_id {
+            // ✨ Phase 2: Emit state change event
+            let mut event_payload = serde_json::json!({
+                "session_id": session_id,
+                "status": "stopped",
+                "success": success,
+            });
+            if let Err(e) = &exit {
+                event_payload["error"] = Value::String(e.clone());
+            }
+            let _ = self.app.emit("claude-session-state", &event_payload);
+            agent::emit_agent_session_state(
+                &self.app,
+                AgentTool::Claude,
+                Some(session_id),
+                &event_payload,
+            );
+        }
+        if retry_delay.is_none() {
+            self.emit_complete(success);
+        }
+
+        // Unregister from ProcessRegistry if we have a run_id
+        if let Some(run_id) = *self.run_id.lock().unwrap() {
+            let _ = self.registry.unregister_process(run_id);
+        }
+
+        let (Some(delay), Some(cmd)) = (retry_delay, retry.command) else {
+            return;
+        };
+        // 已经拿到会话 ID 时接续该会话，而不是原样重跑另起一个前端不知道的新会话
+        let cmd = match &session_id {
+            Some(session_id) => resume_command(&cmd, session_id),
+            None => cmd,
+        };
+        // 等待期间不占用并发名额
+        drop(session_permit);
+        tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
+
+        // 等待期间被取消时，取消命令已经发送过 cancelled / complete 事件
+        let cancelled = session_id
+            .as_deref()
+            .is_some_and(|session_id| !rate_limit::take_pending_retry(session_id));
+        if cancelled {
+            log::info!("Rate limit retry cancelled for session {:?}", session_id);
+            return;
+        }
+
+        log::info!(
+            "Retrying Claude request after rate limit (attempt {})",
+            retry.attempt + 2
+        );
+        let result = super::cli_runner::retry_claude_process(
+            self.app.clone(),
+            cmd,
+            self.prompt.clone(),
+            self.model.clone(),
+            self.project_path.clone(),
+            self.tab_id.clone(),
+            retry.app_approval,
+            retry.attempt + 1,
+            attachments,
+        )
+        .await;
+        if let Err(e) = result {
+            log::error!("Failed to retry Claude request: {}", e);
+            let error_payload = serde_json::json!({
+                "tab_id": self.tab_id,
+                "payload": e.to_string()
+            });
+            let _ = self.app.emit("claude-error", &error_payload);
+            agent::emit_agent_error(
+                &self.app,
+                AgentTool::Claude,
+                session_id.as_deref(),
+                self.tab_id.as_deref(),
+                &e.to_string(),
+            );
+            self.emit_complete(false);
+        }
+    }
+
+    /// 限流 / 过载：通知前端，按配置返回自动重试前的等待秒数
+    fn report_rate_limit(&self, session_id: Option<&str>, retry: &RateLimitRetry) -> Option<u64> {
+        let info = self.rate_limit.lock().unwrap().take()?;
+        let attempt = retry.attempt + 1;
+        let retry_in_secs = retry
+            .command
+            .as_ref()
+            .map(|_| rate_limit::backoff_delay_secs(attempt, info.retry_after_secs, &retry.config));
+        log::warn!(
+            "Claude request hit {:?} (attempt {}), retry in {:?}s",
+            info.kind,
+            attempt,
+            retry_in_secs
+        );
+        let event = rate_limit::RateLimitEvent {
+            session_id: session_id.map(str::to_string),
+            tab_id: self.tab_id.clone(),
+            info,
+            attempt,
+            max_retries: retry.config.max_retries,
+            will_retry: retry_in_secs.is_some(),
+            retry_in_secs,
+        };
+        if let Some(session_id) = session_id {
+            let _ = self
+                .app
+                .emit(&format!("claude-rate-limited:{}", session_id), &event);
+        }
+        let _ = self.app.emit("claude-rate-limited", &event);
+        let detail = match retry_in_secs {
+            Some(secs) => format!("Retrying in {}s", secs),
+            None => event.info.message.clone(),
+        };
+        notify_session_event(
+            &self.app,
+            SessionNotification::RateLimited,
+            &self.project_path,
+            self.tab_id.as_deref(),
+            &detail,
+        );
+        retry_in_secs
+    }
+
+    fn emit_complete(&self, success: bool) {
+        emit_claude_complete(
+            &self.app,
+            self.session_id().as_deref(),
+            self.tab_id.as_deref(),
+            success,
+        );
+    }
+}
+
+/// 发送单个会话的结束事件：`claude-complete:{session_id}` 与统一的 `agent-complete`
+pub(super) fn emit_session_complete(
+    app: &AppHandle,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    success: bool,
+) {
+    if let Some(session_id) = session_id {
+        let _ = app.emit(&format!("claude-complete:{}", session_id), success);
+    }
+    agent::emit_agent_complete(app, AgentTool::Claude, session_id, tab_id, success);
+}
+
+/// 在会话级结束事件之外，再发送带 tab_id 的全局 `claude-complete`；
+/// 进程正常退出与取消都走这里，保证 `agent-complete` 不会漏发
+pub(super) fn emit_claude_complete(
+    app: &AppHandle,
+    session_id: Option<&str>,
+    tab_id: Option<&str>,
+    success: bool,
+) {
+    emit_session_complete(app, session_id, tab_id, success);
+    // 🔒 CRITICAL FIX: 全局事件包含 tab_id
+    let global_payload = serde_json::json!({
+        "tab_id": tab_id,
+        "payload": success
+    });
+    let _ = app.emit("claude-complete", &global_payload);
+}
+
+/// 复制一个尚未启动的命令（程序、参数、环境变量、工作目录），用于原样重新执行
+fn rebuild_command(cmd: &Command) -> Command {
+    rebuild_command_with_args(cmd, cmd.as_std().get_args())
+}
+
+/// 重试命令改为 `--resume <session_id>`，去掉原有的 `-c` / `--resume <id>`
+fn resume_command(cmd: &Command, session_id: &str) -> Command {
+    let args = resume_args(cmd.as_std().get_args(), session_id);
+    rebuild_command_with_args(cmd, args)
+}
+
+fn resume_args<'a>(mut args: impl Iterator<Item = &'a OsStr>, session_id: &str) -> Vec<OsString> {
+    let mut resumed = vec![OsString::from("--resume"), OsString::from(session_id)];
+    while let Some(arg) = args.next() {
+        if arg == "-c" || arg == "--continue" {
+            continue;
+        }
+        if arg == "--resume" || arg == "-r" {
+            args.next();
+            continue;
+        }
+        resumed.push(arg.to_os_string());
+    }
+    resumed
+}
+
+fn rebuild_command_with_args<I, S>(cmd: &Command, args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let source = cmd.as_std();
+    let mut rebuilt = Command::new(source.get_program());
+    rebuilt.args(args);
+    for (key, value) in source.get_envs() {
+        match value {
+            Some(value) => rebuilt.env(key, value),
+            None => rebuilt.env_remove(key),
+        };
+    }
+    if let Some(dir) = source.get_current_dir() {
+        rebuilt.current_dir(dir);
+    }
+    rebuilt.stdin(Stdio::piped());
+    rebuilt.stdout(Stdio::piped());
+    rebuilt.stderr(Stdio::piped());
+    platform::apply_no_window_async(&mut rebuilt);
+    #[cfg(unix)]
+    rebuilt.process_group(0);
+    rebuilt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args<'a>(list: &[&'a str]) -> Vec<&'a OsStr> {
+        list.iter().copied().map(OsStr::new).collect()
+    }
+
+    #[test]
+    fn retry_resumes_the_session_seen_by_the_first_attempt() {
+        let continued = args(&["-c", "--model", "sonnet", "--output-format", "stream-json"]);
+        assert_eq!(
+            resume_args(continued.into_iter(), "abc"),
+            [
+                "--resume",
+                "abc",
+                "--model",
+                "sonnet",
+                "--output-format",
+                "stream-json"
+            ]
+        );
+
+        let resumed = args(&["--resume", "old", "--model", "sonnet"]);
+        assert_eq!(
+            resume_args(resumed.into_iter(), "abc"),
+            ["--resume", "abc", "--model", "sonnet"]
+        );
+
+        let mut cmd = Command::new("claude");
+        cmd.args(["--model", "opus"]).current_dir("/tmp");
+        let retried = resume_command(&cmd, "abc");
+        assert_eq!(
+            retried.as_std().get_args().collect::<Vec<_>>(),
+            ["--resume", "abc", "--model", "opus"]
+        );
+        assert_eq!(
+            retried.as_std().get_current_dir(),
+            Some(std::path::Path::new("/tmp"))
+        );
+    }
+}
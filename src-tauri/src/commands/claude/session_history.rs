@@ -1,149 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
 use std::time::SystemTime;
 
 use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
 use serde_json::Value;
 
-use super::models::JsonlEntry;
-use super::paths::get_claude_dir;
+use super::models::{
+    EntryMeta, FileActivity, JsonlEntry, MalformedLine, SessionEntry, SessionFileReport,
+    SessionRepairResult,
+};
+use super::paths::{
+    decode_project_path, get_claude_dir, normalize_path_for_comparison, validate_path_component,
+};
+
+/// 用户真正输入的文本；工具结果、本地命令输出和自动发送的 Warmup 消息返回 None
+fn user_prompt_text(entry: &JsonlEntry) -> Option<String> {
+    let message = entry.message.as_ref()?;
+    if message.role.as_deref() != Some("user") {
+        return None;
+    }
 
-/// Extracts the first valid user message from a JSONL file
-pub fn extract_first_user_message<P: AsRef<Path>>(
-    jsonl_path: P,
-) -> (Option<String>, Option<String>) {
-    let file = match fs::File::open(jsonl_path) {
-        Ok(file) => file,
-        Err(_) => return (None, None),
-    };
+    // 提取文本内容（支持字符串和数组两种格式）
+    let content_value = message.content.as_ref()?;
+    let mut extracted_text = String::new();
+    let mut has_text_content = false;
+
+    if let Some(text) = content_value.as_str() {
+        // 字符串格式
+        extracted_text = text.to_string();
+        has_text_content = !text.trim().is_empty();
+    } else if let Some(arr) = content_value.as_array() {
+        // 数组格式（可能包含 text 和 tool_result）
+        for item in arr {
+            if item.get("type").and_then(|t| t.as_str()) == Some("text") {
+                if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
+                    extracted_text.push_str(text);
+                    has_text_content = true;
+                }
+            }
+        }
+    }
 
-    let reader = BufReader::new(file);
+    // 必须有文本内容
+    if !has_text_content {
+        return None;
+    }
 
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            if let Ok(entry) = serde_json::from_str::<JsonlEntry>(&line) {
-                if let Some(message) = entry.message {
-                    if message.role.as_deref() == Some("user") {
-                        if let Some(content_value) = message.content {
-                            // 提取文本内容（支持字符串和数组两种格式）
-                            let mut extracted_text = String::new();
-                            let mut has_text_content = false;
-
-                            if let Some(text) = content_value.as_str() {
-                                // 字符串格式
-                                extracted_text = text.to_string();
-                                has_text_content = !text.trim().is_empty();
-                            } else if let Some(arr) = content_value.as_array() {
-                                // 数组格式（可能包含 text 和 tool_result）
-                                for item in arr {
-                                    if let Some(item_type) =
-                                        item.get("type").and_then(|t| t.as_str())
-                                    {
-                                        if item_type == "text" {
-                                            if let Some(text) =
-                                                item.get("text").and_then(|t| t.as_str())
-                                            {
-                                                extracted_text.push_str(text);
-                                                has_text_content = true;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+    // Skip if it contains the caveat message
+    if extracted_text.contains(
+        "Caveat: The messages below were generated by the user while running local commands",
+    ) {
+        return None;
+    }
 
-                            // 必须有文本内容
-                            if !has_text_content {
-                                continue;
-                            }
+    // Skip if it starts with command tags
+    if extracted_text.starts_with("<command-name>")
+        || extracted_text.starts_with("<local-command-stdout>")
+    {
+        return None;
+    }
 
-                            // Skip if it contains the caveat message
-                            if extracted_text.contains("Caveat: The messages below were generated by the user while running local commands") {
-                                continue;
-                            }
+    // Skip Warmup messages (auto-sent on session start)
+    if extracted_text.contains("Warmup") {
+        return None;
+    }
 
-                            // Skip if it starts with command tags
-                            if extracted_text.starts_with("<command-name>")
-                                || extracted_text.starts_with("<local-command-stdout>")
-                            {
-                                continue;
-                            }
+    Some(extracted_text)
+}
 
-                            // Skip Warmup messages (auto-sent on session start)
-                            if extracted_text.contains("Warmup") {
-                                continue;
-                            }
+/// Derived per-session statistics used for listing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Number of entries carrying a message (user or assistant)
+    pub message_count: usize,
+    /// Timestamp of the first message - ISO string
+    pub first_activity: Option<String>,
+    /// Timestamp of the last message - ISO string
+    pub last_activity: Option<String>,
+    /// First message typed by the user (tool results, local command output and Warmup skipped)
+    pub first_message: Option<String>,
+    /// Timestamp of the first valid user message - ISO string
+    pub first_message_timestamp: Option<String>,
+    /// Last model recorded in system init or assistant entries
+    pub model: Option<String>,
+}
 
-                            // Found a valid user message
-                            return (Some(extracted_text), entry.timestamp);
-                        }
-                    }
-                }
-            }
+/// Upper bound on cached session stats; the least recently used entry is evicted beyond it
+const SESSION_STATS_CACHE_CAPACITY: usize = 4096;
+
+struct CachedStats {
+    modified: SystemTime,
+    len: u64,
+    last_used: u64,
+    stats: SessionStats,
+}
+
+/// Cached stats keyed by JSONL path, invalidated when the file's mtime or size changes
+#[derive(Default)]
+struct SessionStatsCache {
+    tick: u64,
+    entries: HashMap<PathBuf, CachedStats>,
+}
+
+impl SessionStatsCache {
+    fn get(&mut self, path: &Path, modified: SystemTime, len: u64) -> Option<SessionStats> {
+        self.tick += 1;
+        let tick = self.tick;
+        let cached = self.entries.get_mut(path)?;
+        if cached.modified != modified || cached.len != len {
+            return None;
         }
+        cached.last_used = tick;
+        Some(cached.stats.clone())
     }
 
-    (None, None)
+    fn insert(&mut self, path: PathBuf, modified: SystemTime, len: u64, stats: SessionStats) {
+        self.tick += 1;
+        if self.entries.len() >= SESSION_STATS_CACHE_CAPACITY && !self.entries.contains_key(&path) {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(path, _)| path.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            path,
+            CachedStats {
+                modified,
+                len,
+                last_used: self.tick,
+                stats,
+            },
+        );
+    }
 }
 
-/// Extracts the timestamp of the last message (user or assistant) from a JSONL file
-pub fn extract_last_message_timestamp<P: AsRef<Path>>(jsonl_path: P) -> Option<String> {
-    let file = match fs::File::open(jsonl_path) {
-        Ok(file) => file,
-        Err(_) => return None,
+static SESSION_STATS_CACHE: Lazy<Mutex<SessionStatsCache>> =
+    Lazy::new(|| Mutex::new(SessionStatsCache::default()));
+
+/// Returns everything the session listing needs from a JSONL file in a single scan
+/// Unchanged files are served from the cache instead of being re-scanned
+pub fn session_stats<P: AsRef<Path>>(jsonl_path: P) -> SessionStats {
+    let path = jsonl_path.as_ref();
+    let Ok(metadata) = fs::metadata(path) else {
+        return SessionStats::default();
     };
+    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let len = metadata.len();
 
-    let reader = BufReader::new(file);
-    let mut last_timestamp: Option<String> = None;
-
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            if let Ok(entry) = serde_json::from_str::<JsonlEntry>(&line) {
-                // Check if this entry has a message (user or assistant)
-                if entry.message.is_some() {
-                    // Update last_timestamp if this entry has a timestamp
-                    if let Some(timestamp) = entry.timestamp {
-                        last_timestamp = Some(timestamp);
-                    }
-                }
-            }
+    if let Ok(mut cache) = SESSION_STATS_CACHE.lock() {
+        if let Some(stats) = cache.get(path, modified, len) {
+            return stats;
         }
     }
 
-    last_timestamp
+    let stats = scan_session_stats(path);
+    if let Ok(mut cache) = SESSION_STATS_CACHE.lock() {
+        cache.insert(path.to_path_buf(), modified, len, stats.clone());
+    }
+    stats
 }
 
-/// Extracts the model used in the session from a JSONL file
-/// Looks for model information in system init messages or assistant messages
-pub fn extract_session_model<P: AsRef<Path>>(jsonl_path: P) -> Option<String> {
+fn scan_session_stats(jsonl_path: &Path) -> SessionStats {
     let file = match fs::File::open(jsonl_path) {
         Ok(file) => file,
-        Err(_) => return None,
+        Err(_) => return SessionStats::default(),
     };
 
     let reader = BufReader::new(file);
-    let mut last_model: Option<String> = None;
-
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            // Try to parse as a generic JSON value first
-            if let Ok(entry) = serde_json::from_str::<Value>(&line) {
-                // Check for model in different locations:
-                // 1. System init message: { "type": "system", "model": "..." }
-                // 2. Assistant message: { "type": "assistant", "message": { "model": "..." } }
-
-                if let Some(model_str) = entry.get("model").and_then(|m| m.as_str()) {
-                    last_model = Some(model_str.to_string());
-                } else if let Some(message) = entry.get("message") {
-                    if let Some(model_str) = message.get("model").and_then(|m| m.as_str()) {
-                        last_model = Some(model_str.to_string());
-                    }
+    let mut stats = SessionStats::default();
+
+    for line in reader.lines().map_while(Result::ok) {
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let Some(model) = entry_model(&value) {
+            stats.model = Some(model.to_string());
+        }
+        let Ok(entry) = serde_json::from_value::<JsonlEntry>(value) else {
+            continue;
+        };
+
+        // Only entries with a message (user or assistant) count as activity
+        if entry.message.is_some() {
+            stats.message_count += 1;
+            if let Some(timestamp) = entry.timestamp.as_ref() {
+                if stats.first_activity.is_none() {
+                    stats.first_activity = Some(timestamp.clone());
                 }
+                stats.last_activity = Some(timestamp.clone());
             }
         }
+        if stats.first_message.is_none() {
+            if let Some(text) = user_prompt_text(&entry) {
+                stats.first_message = Some(text);
+                stats.first_message_timestamp = entry.timestamp;
+            }
+        }
+    }
+
+    stats
+}
+
+/// Model recorded on a JSONL entry:
+/// 1. System init message: { "type": "system", "model": "..." }
+/// 2. Assistant message: { "type": "assistant", "message": { "model": "..." } }
+fn entry_model(entry: &Value) -> Option<&str> {
+    entry
+        .get("model")
+        .and_then(|m| m.as_str())
+        .or_else(|| entry.get("message")?.get("model")?.as_str())
+}
+
+/// 逐行检查 JSONL 内容，返回非空行数、无法解析的行，以及末行是否为写入中断留下的不完整行
+fn check_jsonl(content: &[u8]) -> (usize, Vec<MalformedLine>, bool) {
+    let mut total_lines = 0;
+    let mut malformed = Vec::new();
+    let mut last_line_malformed = false;
+
+    for (index, raw) in content.split(|b| *b == b'\n').enumerate() {
+        let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        if raw.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        total_lines += 1;
+        let text = String::from_utf8_lossy(raw);
+        let error = match std::str::from_utf8(raw) {
+            Ok(line) => serde_json::from_str::<Value>(line)
+                .err()
+                .map(|e| e.to_string()),
+            Err(e) => Some(format!("invalid UTF-8: {}", e)),
+        };
+        last_line_malformed = error.is_some();
+        if let Some(error) = error {
+            malformed.push(MalformedLine {
+                line_number: index + 1,
+                error,
+                preview: text.chars().take(120).collect(),
+            });
+        }
     }
 
-    last_model
+    let trailing_partial = last_line_malformed && !content.ends_with(b"\n");
+    (total_lines, malformed, trailing_partial)
+}
+
+fn session_file_path(session_id: &str, project_id: &str) -> Result<PathBuf, String> {
+    validate_path_component(project_id, "project id")?;
+    validate_path_component(session_id, "session id")?;
+    let claude_dir = get_claude_dir().map_err(|e| e.to_string())?;
+    let session_path = claude_dir
+        .join("projects")
+        .join(project_id)
+        .join(format!("{}.jsonl", session_id));
+    if !session_path.exists() {
+        return Err(format!("Session file not found: {}", session_id));
+    }
+    Ok(session_path)
+}
+
+fn build_report(session_id: &str, project_id: &str, content: &[u8]) -> SessionFileReport {
+    let (total_lines, malformed_lines, trailing_partial_line) = check_jsonl(content);
+    SessionFileReport {
+        session_id: session_id.to_string(),
+        project_id: project_id.to_string(),
+        total_lines,
+        malformed_lines,
+        trailing_partial_line,
+    }
+}
+
+/// 报告会话 JSONL 中无法解析的行
+pub fn validate_session_file(
+    session_id: &str,
+    project_id: &str,
+) -> Result<SessionFileReport, String> {
+    let session_path = session_file_path(session_id, project_id)?;
+    let content =
+        fs::read(&session_path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    Ok(build_report(session_id, project_id, &content))
+}
+
+/// 备份原文件后删除无法解析的行（包括末尾不完整的行）
+pub fn repair_session_file(
+    session_id: &str,
+    project_id: &str,
+) -> Result<SessionRepairResult, String> {
+    let session_path = session_file_path(session_id, project_id)?;
+    let (backup_path, removed_lines) = repair_jsonl_file(&session_path)?;
+    let content =
+        fs::read(&session_path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    Ok(SessionRepairResult {
+        backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
+        removed_lines,
+        report: build_report(session_id, project_id, &content),
+    })
+}
+
+/// 文件完好时不做任何修改；否则先复制为 `<name>.jsonl.<时间戳>.bak`，再写入只保留有效行的新内容
+fn repair_jsonl_file(path: &Path) -> Result<(Option<PathBuf>, Vec<usize>), String> {
+    let content = fs::read(path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    let (_, malformed, _) = check_jsonl(&content);
+    if malformed.is_empty() {
+        return Ok((None, Vec::new()));
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| "Invalid session file name".to_string())?;
+    let backup_path = path.with_file_name(format!(
+        "{}.{}.bak",
+        file_name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::copy(path, &backup_path).map_err(|e| format!("Failed to back up session file: {}", e))?;
+
+    let removed: Vec<usize> = malformed.iter().map(|line| line.line_number).collect();
+    let mut repaired = Vec::with_capacity(content.len());
+    for (index, raw) in content.split(|b| *b == b'\n').enumerate() {
+        if removed.contains(&(index + 1)) || raw.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        repaired.extend_from_slice(raw);
+        repaired.push(b'\n');
+    }
+
+    // 先写临时文件再替换，避免修复过程中再次中断导致文件损坏
+    let temp_path = path.with_file_name(format!("{}.repairing", file_name));
+    fs::write(&temp_path, &repaired)
+        .map_err(|e| format!("Failed to write repaired session file: {}", e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to replace session file: {}", e))?;
+
+    log::info!(
+        "Repaired session file {:?}: dropped lines {:?}, backup at {:?}",
+        path,
+        removed,
+        backup_path
+    );
+    Ok((Some(backup_path), removed))
 }
 
 /// Loads the JSONL history for a specific session
@@ -175,43 +377,59 @@ pub fn load_session_history(session_id: &str, project_id: &str) -> Result<Vec<Va
 
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
+    let mut skipped_lines = Vec::new();
 
     // Step 1: Load main session messages and build agentId -> tool_use_id mapping
     let mut agent_to_tool_use_id: std::collections::HashMap<String, String> =
         std::collections::HashMap::new();
 
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            if let Ok(json) = serde_json::from_str::<Value>(&line) {
-                // Check for tool_result with agentId to build mapping
-                if let Some(content) = json
-                    .get("message")
-                    .and_then(|m| m.get("content"))
-                    .and_then(|c| c.as_array())
-                {
-                    for item in content {
-                        if item.get("type").and_then(|t| t.as_str()) == Some("tool_result") {
-                            // Get tool_use_id and agentId from toolUseResult
-                            if let (Some(tool_use_id), Some(agent_id)) = (
-                                item.get("tool_use_id").and_then(|t| t.as_str()),
-                                json.get("toolUseResult")
-                                    .and_then(|r| r.get("agentId"))
-                                    .and_then(|a| a.as_str()),
-                            ) {
-                                log::debug!(
-                                    "Found agentId mapping: {} -> {}",
-                                    agent_id,
-                                    tool_use_id
-                                );
-                                agent_to_tool_use_id
-                                    .insert(agent_id.to_string(), tool_use_id.to_string());
-                            }
-                        }
+    for (index, line) in reader.split(b'\n').enumerate() {
+        let Ok(raw) = line else {
+            break;
+        };
+        if raw.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        // 写入中断可能留下不完整的行（甚至截断的 UTF-8），跳过并记录，不影响其余消息
+        let Some(json) = std::str::from_utf8(&raw)
+            .ok()
+            .and_then(|line| serde_json::from_str::<Value>(line).ok())
+        else {
+            skipped_lines.push(index + 1);
+            continue;
+        };
+
+        // Check for tool_result with agentId to build mapping
+        if let Some(content) = json
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+        {
+            for item in content {
+                if item.get("type").and_then(|t| t.as_str()) == Some("tool_result") {
+                    // Get tool_use_id and agentId from toolUseResult
+                    if let (Some(tool_use_id), Some(agent_id)) = (
+                        item.get("tool_use_id").and_then(|t| t.as_str()),
+                        json.get("toolUseResult")
+                            .and_then(|r| r.get("agentId"))
+                            .and_then(|a| a.as_str()),
+                    ) {
+                        log::debug!("Found agentId mapping: {} -> {}", agent_id, tool_use_id);
+                        agent_to_tool_use_id.insert(agent_id.to_string(), tool_use_id.to_string());
                     }
                 }
-                messages.push(json);
             }
         }
+        messages.push(json);
+    }
+
+    if !skipped_lines.is_empty() {
+        log::warn!(
+            "Skipped {} malformed line(s) in session {} (lines {:?}); use repair_session_file to fix",
+            skipped_lines.len(),
+            session_id,
+            skipped_lines
+        );
     }
 
     log::info!(
@@ -311,3 +529,495 @@ pub fn load_session_history(session_id: &str, project_id: &str) -> Result<Vec<Va
     );
     Ok(messages)
 }
+
+/// 根据各行的 type 判断记录格式："claude"、"codex"，无法识别时返回 None
+fn detect_transcript_format(lines: &[Value]) -> Option<&'static str> {
+    let is_codex = lines.iter().any(|line| {
+        matches!(
+            line["type"].as_str(),
+            Some("session_meta" | "response_item" | "event_msg" | "turn_context")
+        )
+    });
+    if is_codex {
+        return Some("codex");
+    }
+    let has_messages = lines.iter().any(|line| {
+        matches!(line["type"].as_str(), Some("user" | "assistant")) && line.get("message").is_some()
+    });
+    has_messages.then_some("claude")
+}
+
+/// 导入外部的 Claude 会话 JSONL：校验后以新的会话 ID 复制到项目目录，返回新 ID
+pub fn import_session_jsonl(project_id: &str, file_path: &str) -> Result<String, String> {
+    validate_path_component(project_id, "project id")?;
+    let content =
+        fs::read(file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let (total_lines, malformed, _) = check_jsonl(&content);
+    if let Some(first) = malformed.first() {
+        return Err(format!(
+            "{} of {} lines are not valid JSON (first at line {}: {})",
+            malformed.len(),
+            total_lines,
+            first.line_number,
+            first.error
+        ));
+    }
+
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<Value> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    match detect_transcript_format(&lines) {
+        Some("claude") => {}
+        Some(format) => {
+            return Err(format!(
+                "This looks like a {} transcript; import it into {} and use convert_session to convert it to a Claude session first",
+                format, format
+            ))
+        }
+        None => return Err("File does not look like a Claude session transcript".to_string()),
+    }
+
+    let new_session_id = uuid::Uuid::new_v4().to_string();
+    let mut imported = String::new();
+    for mut line in lines {
+        if line.get("sessionId").is_some() {
+            line["sessionId"] = Value::String(new_session_id.clone());
+        }
+        imported.push_str(&line.to_string());
+        imported.push('\n');
+    }
+
+    let project_dir = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("projects")
+        .join(project_id);
+    fs::create_dir_all(&project_dir)
+        .map_err(|e| format!("Failed to create project directory: {}", e))?;
+    fs::write(
+        project_dir.join(format!("{}.jsonl", new_session_id)),
+        imported,
+    )
+    .map_err(|e| format!("Failed to write imported session: {}", e))?;
+
+    log::info!(
+        "Imported {} ({} lines) into project {} as session {}",
+        file_path,
+        total_lines,
+        project_id,
+        new_session_id
+    );
+    Ok(new_session_id)
+}
+
+/// 加载会话历史并转换为结构化条目
+pub fn load_session_history_structured(
+    session_id: &str,
+    project_id: &str,
+) -> Result<Vec<SessionEntry>, String> {
+    let messages = load_session_history(session_id, project_id)?;
+    Ok(messages.into_iter().flat_map(parse_session_entry).collect())
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// 把一行 JSONL 拆分为结构化条目；无法识别的形状原样放入 `Raw`
+pub(super) fn parse_session_entry(msg: Value) -> Vec<SessionEntry> {
+    let meta = EntryMeta {
+        uuid: str_field(&msg, "uuid"),
+        timestamp: str_field(&msg, "timestamp"),
+        parent_tool_use_id: str_field(&msg, "parent_tool_use_id"),
+    };
+    let content = &msg["message"]["content"];
+
+    let entries = match msg["type"].as_str() {
+        Some("user") => match content {
+            Value::String(text) => vec![SessionEntry::UserMessage {
+                meta,
+                text: text.clone(),
+            }],
+            Value::Array(blocks) => {
+                let mut text = String::new();
+                let mut results = Vec::new();
+                for block in blocks {
+                    match block["type"].as_str() {
+                        Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+                        Some("tool_result") => results.push(SessionEntry::ToolResult {
+                            meta: meta.clone(),
+                            tool_use_id: str_field(block, "tool_use_id").unwrap_or_default(),
+                            content: block.get("content").cloned().unwrap_or(Value::Null),
+                            is_error: block["is_error"].as_bool().unwrap_or(false),
+                        }),
+                        _ => {}
+                    }
+                }
+                if !text.is_empty() {
+                    results.insert(0, SessionEntry::UserMessage { meta, text });
+                }
+                results
+            }
+            _ => Vec::new(),
+        },
+        Some("assistant") => match content {
+            Value::String(text) => vec![SessionEntry::AssistantMessage {
+                meta,
+                text: text.clone(),
+                model: str_field(&msg["message"], "model"),
+            }],
+            Value::Array(blocks) => {
+                let mut text = String::new();
+                let mut tool_uses = Vec::new();
+                for block in blocks {
+                    match block["type"].as_str() {
+                        Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+                        Some("tool_use") => tool_uses.push(SessionEntry::ToolUse {
+                            meta: meta.clone(),
+                            id: str_field(block, "id").unwrap_or_default(),
+                            name: str_field(block, "name").unwrap_or_default(),
+                            input: block.get("input").cloned().unwrap_or(Value::Null),
+                        }),
+                        _ => {}
+                    }
+                }
+                if !text.is_empty() {
+                    tool_uses.insert(
+                        0,
+                        SessionEntry::AssistantMessage {
+                            meta,
+                            text,
+                            model: str_field(&msg["message"], "model"),
+                        },
+                    );
+                }
+                tool_uses
+            }
+            _ => Vec::new(),
+        },
+        Some("system") => vec![SessionEntry::System {
+            meta,
+            subtype: str_field(&msg, "subtype"),
+            content: str_field(&msg, "content"),
+        }],
+        _ => Vec::new(),
+    };
+
+    if entries.is_empty() {
+        vec![SessionEntry::Raw { value: msg }]
+    } else {
+        entries
+    }
+}
+
+/// 会话中文件工具（Read / Write / Edit / MultiEdit）涉及的文件，按路径和操作去重计数
+pub fn session_file_activity(
+    session_id: &str,
+    project_id: &str,
+) -> Result<Vec<FileActivity>, String> {
+    let messages = load_session_history(session_id, project_id)?;
+    let project_root = messages
+        .iter()
+        .find_map(|msg| str_field(msg, "cwd"))
+        .unwrap_or_else(|| decode_project_path(project_id));
+    let entries = messages.into_iter().flat_map(parse_session_entry);
+    Ok(collect_file_activity(entries, &project_root))
+}
+
+fn collect_file_activity(
+    entries: impl IntoIterator<Item = SessionEntry>,
+    project_root: &str,
+) -> Vec<FileActivity> {
+    let root = normalize_path_for_comparison(project_root);
+    // 以规范化后的路径去重，保留第一次出现时的写法
+    let mut activity: BTreeMap<(String, &'static str), FileActivity> = BTreeMap::new();
+
+    for entry in entries {
+        let SessionEntry::ToolUse { name, input, .. } = entry else {
+            continue;
+        };
+        let (operation, key) = match name.as_str() {
+            "Read" => ("read", "file_path"),
+            "Write" => ("write", "file_path"),
+            "Edit" | "MultiEdit" => ("edit", "file_path"),
+            "NotebookEdit" => ("edit", "notebook_path"),
+            _ => continue,
+        };
+        let Some(raw_path) = input.get(key).and_then(Value::as_str) else {
+            continue;
+        };
+
+        let path = resolve_against_root(raw_path, project_root);
+        let normalized = normalize_path_for_comparison(&path);
+        let outside_project = normalized != root
+            && !normalized.starts_with(&format!("{}/", root.trim_end_matches('/')));
+        activity
+            .entry((normalized, operation))
+            .or_insert_with(|| FileActivity {
+                path,
+                operation: operation.to_string(),
+                count: 0,
+                outside_project,
+            })
+            .count += 1;
+    }
+
+    activity.into_values().collect()
+}
+
+/// 相对路径拼接到项目根目录下，并按字面消除 `.` / `..`（文件可能已不存在，不能用 canonicalize）
+fn resolve_against_root(path: &str, project_root: &str) -> String {
+    let joined = Path::new(project_root).join(path);
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn session_file_path_rejects_traversal_ids() {
+        assert!(session_file_path("s1", "..").is_err());
+        assert!(session_file_path("../../.ssh/id_rsa", "-work-api").is_err());
+        assert!(repair_session_file("s1", "../..").is_err());
+    }
+
+    #[test]
+    fn import_rejects_traversal_project_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("session.jsonl");
+        fs::write(
+            &source,
+            r#"{"type":"user","sessionId":"s","message":{"role":"user","content":"hi"}}"#,
+        )
+        .unwrap();
+        let error = import_session_jsonl("../outside", &source.to_string_lossy()).unwrap_err();
+        assert!(error.contains("project id"));
+    }
+
+    #[test]
+    fn session_stats_counts_messages_and_refreshes_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            writeln!(file, r#"{{"type":"summary","summary":"x"}}"#).unwrap();
+            writeln!(
+                file,
+                r#"{{"type":"user","timestamp":"2024-01-01T10:00:00Z","message":{{"role":"user","content":"hi"}}}}"#
+            )
+            .unwrap();
+            writeln!(
+                file,
+                r#"{{"type":"assistant","timestamp":"2024-01-01T10:05:00Z","message":{{"role":"assistant","content":"hello"}}}}"#
+            )
+            .unwrap();
+        }
+
+        let stats = session_stats(&path);
+        assert_eq!(stats.message_count, 2);
+        assert_eq!(
+            stats.first_activity.as_deref(),
+            Some("2024-01-01T10:00:00Z")
+        );
+        assert_eq!(stats.last_activity.as_deref(), Some("2024-01-01T10:05:00Z"));
+
+        // Appending changes the file size, so the cached entry is not reused
+        {
+            let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+            writeln!(
+                file,
+                r#"{{"type":"user","timestamp":"2024-01-01T11:00:00Z","message":{{"role":"user","content":"again"}}}}"#
+            )
+            .unwrap();
+        }
+        let stats = session_stats(&path);
+        assert_eq!(stats.message_count, 3);
+        assert_eq!(stats.last_activity.as_deref(), Some("2024-01-01T11:00:00Z"));
+        assert_eq!(stats.first_message.as_deref(), Some("hi"));
+        assert_eq!(
+            stats.first_message_timestamp.as_deref(),
+            Some("2024-01-01T10:00:00Z")
+        );
+    }
+
+    #[test]
+    fn session_stats_cache_evicts_least_recently_used() {
+        let mut cache = SessionStatsCache::default();
+        let modified = SystemTime::UNIX_EPOCH;
+        for i in 0..SESSION_STATS_CACHE_CAPACITY {
+            cache.insert(
+                PathBuf::from(format!("{}.jsonl", i)),
+                modified,
+                0,
+                SessionStats::default(),
+            );
+        }
+        // 访问第一条后，最久未使用的变为第二条
+        assert!(cache.get(Path::new("0.jsonl"), modified, 0).is_some());
+        cache.insert(
+            PathBuf::from("new.jsonl"),
+            modified,
+            0,
+            SessionStats::default(),
+        );
+
+        assert_eq!(cache.entries.len(), SESSION_STATS_CACHE_CAPACITY);
+        assert!(cache.entries.contains_key(Path::new("0.jsonl")));
+        assert!(!cache.entries.contains_key(Path::new("1.jsonl")));
+        // mtime 或大小变化时不复用缓存
+        assert!(cache.get(Path::new("new.jsonl"), modified, 1).is_none());
+    }
+
+    #[test]
+    fn repair_drops_malformed_and_trailing_partial_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        fs::write(
+            &path,
+            "{\"type\":\"user\"}\nnot json\n{\"type\":\"assistant\"}\n{\"type\":\"assi",
+        )
+        .unwrap();
+
+        let (total, malformed, trailing_partial) = check_jsonl(&fs::read(&path).unwrap());
+        assert_eq!(total, 4);
+        let lines: Vec<usize> = malformed.iter().map(|l| l.line_number).collect();
+        assert_eq!(lines, vec![2, 4]);
+        assert!(trailing_partial);
+
+        let (backup, removed) = repair_jsonl_file(&path).unwrap();
+        assert_eq!(removed, vec![2, 4]);
+        assert!(backup.unwrap().exists());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\"type\":\"user\"}\n{\"type\":\"assistant\"}\n"
+        );
+
+        // A valid file is left untouched and not backed up again
+        let (backup, removed) = repair_jsonl_file(&path).unwrap();
+        assert!(backup.is_none());
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn parses_tool_calls_into_structured_entries() {
+        let assistant = serde_json::json!({
+            "type": "assistant",
+            "uuid": "a1",
+            "message": {
+                "model": "claude-sonnet",
+                "content": [
+                    { "type": "text", "text": "Reading it" },
+                    { "type": "tool_use", "id": "t1", "name": "Read", "input": { "file_path": "a.rs" } }
+                ]
+            }
+        });
+        let entries = parse_session_entry(assistant);
+        assert_eq!(entries.len(), 2);
+        let SessionEntry::AssistantMessage { text, model, .. } = &entries[0] else {
+            panic!("expected assistant message, got {:?}", entries[0]);
+        };
+        assert_eq!(text, "Reading it");
+        assert_eq!(model.as_deref(), Some("claude-sonnet"));
+        let SessionEntry::ToolUse { name, meta, .. } = &entries[1] else {
+            panic!("expected tool use, got {:?}", entries[1]);
+        };
+        assert_eq!(name, "Read");
+        assert_eq!(meta.uuid.as_deref(), Some("a1"));
+
+        let result = serde_json::json!({
+            "type": "user",
+            "message": { "content": [{ "type": "tool_result", "tool_use_id": "t1", "content": "no such file", "is_error": true }] }
+        });
+        let entries = parse_session_entry(result);
+        let [SessionEntry::ToolResult {
+            tool_use_id,
+            is_error,
+            ..
+        }] = &entries[..]
+        else {
+            panic!("expected a single tool result, got {:?}", entries);
+        };
+        assert_eq!(tool_use_id, "t1");
+        assert!(*is_error);
+
+        let unknown = serde_json::json!({ "type": "file-history-snapshot" });
+        assert!(matches!(
+            &parse_session_entry(unknown)[..],
+            [SessionEntry::Raw { .. }]
+        ));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn collects_file_activity_relative_to_project_root() {
+        let tool_use = |name: &str, path: &str| SessionEntry::ToolUse {
+            meta: EntryMeta::default(),
+            id: String::new(),
+            name: name.to_string(),
+            input: serde_json::json!({ "file_path": path }),
+        };
+        let entries = vec![
+            tool_use("Read", "/work/app/src/main.rs"),
+            tool_use("Read", "src/main.rs"),
+            tool_use("Edit", "./src/../src/main.rs"),
+            tool_use("MultiEdit", "/work/app/src/main.rs"),
+            tool_use("Write", "../other/notes.md"),
+            tool_use("Bash", "/work/app/ignored"),
+        ];
+
+        let activity = collect_file_activity(entries, "/work/app");
+        let summary: Vec<(&str, &str, usize, bool)> = activity
+            .iter()
+            .map(|a| {
+                (
+                    a.path.as_str(),
+                    a.operation.as_str(),
+                    a.count,
+                    a.outside_project,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/work/app/src/main.rs", "edit", 2, false),
+                ("/work/app/src/main.rs", "read", 2, false),
+                ("/work/other/notes.md", "write", 1, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_transcript_format_for_import() {
+        let claude = vec![
+            serde_json::json!({ "type": "summary", "summary": "x" }),
+            serde_json::json!({ "type": "user", "message": { "content": "hi" } }),
+        ];
+        assert_eq!(detect_transcript_format(&claude), Some("claude"));
+
+        let codex = vec![
+            serde_json::json!({ "type": "session_meta", "payload": {} }),
+            serde_json::json!({ "type": "response_item", "payload": {} }),
+        ];
+        assert_eq!(detect_transcript_format(&codex), Some("codex"));
+
+        assert_eq!(
+            detect_transcript_format(&[serde_json::json!({ "a": 1 })]),
+            None
+        );
+    }
+}
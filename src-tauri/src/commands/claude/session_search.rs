@@ -0,0 +1,580 @@
+//! 会话内容搜索
+//!
+//! 在项目的会话 JSONL 中查找文本（不区分大小写），匹配用户消息、助手回复以及工具调用的
+//! 输入和结果。扫描在阻塞线程中进行，并限制返回条数和读取的字节数，避免大项目拖慢界面。
+//!
+//! 跨项目搜索（`search_all_sessions`）并发扫描各项目，通过 `session-search-result` 事件逐条
+//! 推送结果，结束时发送 `session-search-done`；所有项目共享同一份结果 / 字节额度。
+//! search id 由调用方提供（未提供时生成），可以在结果到达前订阅事件，也可以随时取消。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, Semaphore};
+
+use super::models::SessionEntry;
+use super::paths::get_claude_dir;
+use super::project_store::ProjectStore;
+use super::session_history::parse_session_entry;
+
+/// 单次搜索最多返回的匹配数
+const MAX_RESULTS: usize = 200;
+/// 单个会话最多返回的匹配数，避免一个会话占满结果
+const MAX_MATCHES_PER_SESSION: usize = 20;
+/// 单次搜索最多读取的字节数
+const MAX_SCANNED_BYTES: u64 = 256 * 1024 * 1024;
+/// 片段中匹配位置前后保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 80;
+/// 跨项目搜索的默认 / 最大结果数
+const DEFAULT_GLOBAL_RESULTS: usize = 200;
+const MAX_GLOBAL_RESULTS: usize = 1000;
+/// 跨项目搜索同时扫描的项目数
+const MAX_CONCURRENT_PROJECT_SCANS: usize = 4;
+
+/// 进行中的跨项目搜索（search id -> 停止标记）
+static ACTIVE_SEARCHES: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A line of a session that contains the search query
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMatch {
+    pub project_id: String,
+    pub session_id: String,
+    /// Conversation turn the match belongs to (number of user prompts before it, starting at 0)
+    pub turn_index: usize,
+    /// "user", "assistant", "tool_use" or "tool_result"
+    pub role: String,
+    pub snippet: String,
+    pub timestamp: Option<String>,
+}
+
+/// 搜索的剩余额度（匹配数和字节数），多个会话文件以及并发扫描的项目共享；
+/// 设置了停止标记时随时可以中止
+struct SearchBudget {
+    matches_left: AtomicUsize,
+    bytes_left: AtomicU64,
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchBudget {
+    fn new(max_matches: usize) -> Self {
+        Self {
+            matches_left: AtomicUsize::new(max_matches),
+            bytes_left: AtomicU64::new(MAX_SCANNED_BYTES),
+            stop: None,
+        }
+    }
+
+    fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    fn exhausted(&self) -> bool {
+        self.matches_left.load(Ordering::Relaxed) == 0
+            || self.bytes_left.load(Ordering::Relaxed) == 0
+            || self
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    fn consume_bytes(&self, bytes: u64) {
+        let _ = self
+            .bytes_left
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left.saturating_sub(bytes))
+            });
+    }
+
+    /// 占用一个结果名额；额度已被其他扫描用完时返回 false
+    fn take_match(&self) -> bool {
+        self.matches_left
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+/// 小写化后的查询；不含引号、反斜杠和控制字符时可以先在原始 JSON 行上快速筛选
+struct SearchQuery {
+    lower: String,
+    raw_prefilter: bool,
+}
+
+impl SearchQuery {
+    fn new(query: &str) -> Result<Self, String> {
+        let lower = query.trim().to_lowercase();
+        if lower.is_empty() {
+            return Err("Search query must not be empty".to_string());
+        }
+        let raw_prefilter = !lower
+            .chars()
+            .any(|c| c == '"' || c == '\\' || c.is_control());
+        Ok(Self {
+            lower,
+            raw_prefilter,
+        })
+    }
+}
+
+/// 收集 JSON 中所有字符串叶子（工具输入 / 结果）
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+/// 条目中可搜索的文本及其角色
+fn searchable_texts(entry: &SessionEntry) -> Vec<(&'static str, String)> {
+    match entry {
+        SessionEntry::UserMessage { text, .. } => vec![("user", text.clone())],
+        SessionEntry::AssistantMessage { text, .. } => vec![("assistant", text.clone())],
+        SessionEntry::ToolUse { input, .. } => {
+            let mut texts = Vec::new();
+            collect_strings(input, &mut texts);
+            texts.into_iter().map(|text| ("tool_use", text)).collect()
+        }
+        SessionEntry::ToolResult { content, .. } => {
+            let mut texts = Vec::new();
+            collect_strings(content, &mut texts);
+            texts
+                .into_iter()
+                .map(|text| ("tool_result", text))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// 截取匹配位置前后的文本，空白折叠为单个空格
+fn make_snippet(text: &str, lower_text: &str, match_pos: usize, query_chars: usize) -> String {
+    let start_char = lower_text[..match_pos].chars().count();
+    let chars: Vec<char> = text.chars().collect();
+    let start = start_char
+        .saturating_sub(SNIPPET_CONTEXT_CHARS)
+        .min(chars.len());
+    let end = (start_char + query_chars + SNIPPET_CONTEXT_CHARS).min(chars.len());
+
+    let body: String = chars[start..end].iter().collect();
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        body,
+        if end < chars.len() { "…" } else { "" }
+    )
+}
+
+/// 在单个会话文件中搜索，消耗共享额度
+fn search_session_file(
+    path: &Path,
+    project_id: &str,
+    session_id: &str,
+    query: &SearchQuery,
+    budget: &SearchBudget,
+) -> std::io::Result<Vec<SessionMatch>> {
+    let reader = BufReader::new(fs::File::open(path)?);
+    let query_chars = query.lower.chars().count();
+    let mut matches = Vec::new();
+    let mut turn_index = 0usize;
+    let mut seen_prompt = false;
+
+    for line in reader.lines() {
+        if budget.exhausted() || matches.len() >= MAX_MATCHES_PER_SESSION {
+            break;
+        }
+        let line = line?;
+        budget.consume_bytes(line.len() as u64 + 1);
+
+        // 轮次按用户 prompt 计数，prefilter 之前就要统计
+        let is_prompt = line.contains("\"type\":\"user\"") && !line.contains("\"tool_result\"");
+        if is_prompt {
+            if seen_prompt {
+                turn_index += 1;
+            }
+            seen_prompt = true;
+        }
+        if query.raw_prefilter && !line.to_lowercase().contains(&query.lower) {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let timestamp = value["timestamp"].as_str().map(str::to_string);
+
+        for entry in parse_session_entry(value) {
+            for (role, text) in searchable_texts(&entry) {
+                let lower_text = text.to_lowercase();
+                let Some(pos) = lower_text.find(&query.lower) else {
+                    continue;
+                };
+                if !budget.take_match() {
+                    return Ok(matches);
+                }
+                matches.push(SessionMatch {
+                    project_id: project_id.to_string(),
+                    session_id: session_id.to_string(),
+                    turn_index,
+                    role: role.to_string(),
+                    snippet: make_snippet(&text, &lower_text, pos, query_chars),
+                    timestamp: timestamp.clone(),
+                });
+                if budget.exhausted() || matches.len() >= MAX_MATCHES_PER_SESSION {
+                    return Ok(matches);
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// 项目下的会话文件（最近修改的在前）
+fn project_session_files(project_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(project_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("jsonl"))
+        .filter_map(|path| {
+            let session_id = path.file_stem()?.to_str()?.to_string();
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            Some((modified, session_id, path))
+        })
+        .collect();
+    files.sort_by_key(|f| std::cmp::Reverse(f.0));
+    files
+        .into_iter()
+        .map(|(_, session_id, path)| (session_id, path))
+        .collect()
+}
+
+/// 搜索一个项目的所有会话
+fn search_project(project_id: &str, query: &str) -> Result<Vec<SessionMatch>, String> {
+    let query = SearchQuery::new(query)?;
+    let project_dir = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("projects")
+        .join(project_id);
+    if !project_dir.is_dir() {
+        return Err(format!("Project not found: {}", project_id));
+    }
+
+    let budget = SearchBudget::new(MAX_RESULTS);
+    let mut results = Vec::new();
+    for (session_id, path) in project_session_files(&project_dir) {
+        if budget.exhausted() {
+            log::info!(
+                "Session search in {} stopped at the result/byte limit",
+                project_id
+            );
+            break;
+        }
+        match search_session_file(&path, project_id, &session_id, &query, &budget) {
+            Ok(matches) => results.extend(matches),
+            Err(e) => log::warn!("Failed to search session {}: {}", session_id, e),
+        }
+    }
+    Ok(results)
+}
+
+/// Searches the sessions of a project for text (case-insensitive); results and scanned bytes are capped
+#[tauri::command]
+pub async fn search_sessions_content(
+    project_id: String,
+    query: String,
+) -> Result<Vec<SessionMatch>, String> {
+    tokio::task::spawn_blocking(move || search_project(&project_id, &query))
+        .await
+        .map_err(|e| format!("Session search task failed: {}", e))?
+}
+
+/// `session-search-result` 事件负载
+#[derive(Debug, Clone, Serialize)]
+struct SearchResultEvent<'a> {
+    search_id: &'a str,
+    #[serde(flatten)]
+    result: &'a SessionMatch,
+}
+
+/// `session-search-done` 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSearchDone {
+    pub search_id: String,
+    pub total_results: usize,
+    /// The result limit was reached; more matches may exist
+    pub limit_reached: bool,
+    pub cancelled: bool,
+}
+
+/// 在阻塞线程中扫描一个项目，每个会话文件的匹配通过通道发出；接收端关闭后停止
+fn scan_project_into(
+    projects_dir: &Path,
+    project_id: &str,
+    query: &SearchQuery,
+    budget: &SearchBudget,
+    tx: &mpsc::Sender<SessionMatch>,
+) {
+    for (session_id, path) in project_session_files(&projects_dir.join(project_id)) {
+        if budget.exhausted() {
+            return;
+        }
+        match search_session_file(&path, project_id, &session_id, query, budget) {
+            Ok(matches) => {
+                for session_match in matches {
+                    if tx.blocking_send(session_match).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => log::warn!("Failed to search session {}: {}", session_id, e),
+        }
+    }
+}
+
+/// 并发扫描所有项目并推送结果，直到扫描完毕、达到上限或被取消
+async fn run_global_search(
+    app: AppHandle,
+    search_id: String,
+    projects_dir: PathBuf,
+    project_ids: Vec<String>,
+    query: Arc<SearchQuery>,
+    limit: usize,
+    stop: Arc<AtomicBool>,
+) {
+    let (tx, mut rx) = mpsc::channel::<SessionMatch>(64);
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PROJECT_SCANS));
+    // 所有项目共用一份额度，结果数和读取的字节数都按整次搜索计算
+    let budget = Arc::new(SearchBudget::new(limit).with_stop(stop.clone()));
+    for project_id in project_ids {
+        let (tx, query, budget, semaphore) =
+            (tx.clone(), query.clone(), budget.clone(), semaphore.clone());
+        let projects_dir = projects_dir.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = semaphore.acquire_owned().await else {
+                return;
+            };
+            if budget.exhausted() {
+                return;
+            }
+            let scan = tokio::task::spawn_blocking(move || {
+                scan_project_into(&projects_dir, &project_id, &query, &budget, &tx);
+            });
+            if let Err(e) = scan.await {
+                log::warn!("Session search task failed: {}", e);
+            }
+        });
+    }
+    drop(tx);
+
+    let mut total_results = 0usize;
+    let mut limit_reached = false;
+    while let Some(session_match) = rx.recv().await {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let _ = app.emit(
+            "session-search-result",
+            &SearchResultEvent {
+                search_id: &search_id,
+                result: &session_match,
+            },
+        );
+        total_results += 1;
+        if total_results >= limit {
+            limit_reached = true;
+            break;
+        }
+    }
+    // 达到上限或被取消：通知仍在扫描的项目停止，关闭通道
+    let cancelled = !limit_reached && stop.load(Ordering::Relaxed);
+    stop.store(true, Ordering::Relaxed);
+    drop(rx);
+
+    if let Ok(mut searches) = ACTIVE_SEARCHES.lock() {
+        searches.remove(&search_id);
+    }
+    log::info!(
+        "Session search {} finished: {} result(s), limit_reached={}, cancelled={}",
+        search_id,
+        total_results,
+        limit_reached,
+        cancelled
+    );
+    let _ = app.emit(
+        "session-search-done",
+        &SessionSearchDone {
+            search_id,
+            total_results,
+            limit_reached,
+            cancelled,
+        },
+    );
+}
+
+/// Searches session contents across all projects; results stream as `session-search-result` events
+/// followed by `session-search-done`. `search_id` lets the caller subscribe to those events before
+/// starting; one is generated when omitted. Returns the search id used for cancellation.
+#[tauri::command]
+pub async fn search_all_sessions(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+    include_hidden: Option<bool>,
+    search_id: Option<String>,
+) -> Result<String, String> {
+    let query = Arc::new(SearchQuery::new(&query)?);
+    let limit = limit
+        .unwrap_or(DEFAULT_GLOBAL_RESULTS)
+        .clamp(1, MAX_GLOBAL_RESULTS);
+    let project_ids = ProjectStore::new()?.project_ids(include_hidden.unwrap_or(false))?;
+    let projects_dir = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("projects");
+
+    let search_id = search_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut searches = ACTIVE_SEARCHES
+            .lock()
+            .map_err(|e| format!("Session search lock poisoned: {}", e))?;
+        if searches.contains_key(&search_id) {
+            return Err(format!("Session search {} is already running", search_id));
+        }
+        searches.insert(search_id.clone(), stop.clone());
+    }
+    log::info!(
+        "Starting session search {} across {} project(s)",
+        search_id,
+        project_ids.len()
+    );
+
+    tokio::spawn(run_global_search(
+        app,
+        search_id.clone(),
+        projects_dir,
+        project_ids,
+        query,
+        limit,
+        stop,
+    ));
+    Ok(search_id)
+}
+
+/// Cancels a running cross-project session search; returns whether it was still running
+#[tauri::command]
+pub async fn cancel_session_search(search_id: String) -> Result<bool, String> {
+    let searches = ACTIVE_SEARCHES
+        .lock()
+        .map_err(|e| format!("Session search lock poisoned: {}", e))?;
+    Ok(searches
+        .get(&search_id)
+        .map(|stop| stop.store(true, Ordering::Relaxed))
+        .is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_matches_with_turn_index_and_snippet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+        let lines = [
+            r#"{"type":"user","message":{"role":"user","content":"Add a cache"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Done."}]}}"#,
+            r#"{"type":"user","message":{"role":"user","content":"Now handle failures"}}"#,
+            r#"{"type":"assistant","timestamp":"t2","message":{"content":[{"type":"text","text":"I added Retry Logic with backoff."},{"type":"tool_use","id":"1","name":"Edit","input":{"new_string":"fn retry_logic() {}"}}]}}"#,
+        ];
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let query = SearchQuery::new("retry logic").unwrap();
+        let budget = SearchBudget::new(10);
+        let matches = search_session_file(&path, "p", "s1", &query, &budget).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].turn_index, 1);
+        assert_eq!(matches[0].role, "assistant");
+        assert_eq!(matches[0].snippet, "I added Retry Logic with backoff.");
+        assert_eq!(matches[0].timestamp.as_deref(), Some("t2"));
+
+        let query = SearchQuery::new("RETRY_LOGIC").unwrap();
+        let matches = search_session_file(&path, "p", "s1", &query, &budget).unwrap();
+        assert_eq!(matches[0].role, "tool_use");
+
+        assert!(SearchQuery::new("  ").is_err());
+    }
+
+    #[test]
+    fn scan_streams_matches_and_stops_when_flagged() {
+        let projects = tempfile::tempdir().unwrap();
+        let project_dir = projects.path().join("-tmp-project");
+        fs::create_dir(&project_dir).unwrap();
+        for session in ["a", "b"] {
+            fs::write(
+                project_dir.join(format!("{}.jsonl", session)),
+                r#"{"type":"user","message":{"role":"user","content":"find the needle"}}"#,
+            )
+            .unwrap();
+        }
+        let query = SearchQuery::new("Needle").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let budget = SearchBudget::new(10);
+        scan_project_into(projects.path(), "-tmp-project", &query, &budget, &tx);
+        drop(tx);
+        let mut sessions = Vec::new();
+        while let Ok(session_match) = rx.try_recv() {
+            sessions.push(session_match.session_id);
+        }
+        sessions.sort();
+        assert_eq!(sessions, vec!["a", "b"]);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let stop = Arc::new(AtomicBool::new(true));
+        let budget = SearchBudget::new(10).with_stop(stop);
+        scan_project_into(projects.path(), "-tmp-project", &query, &budget, &tx);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn projects_share_one_result_budget() {
+        let projects = tempfile::tempdir().unwrap();
+        for project in ["-tmp-a", "-tmp-b"] {
+            let project_dir = projects.path().join(project);
+            fs::create_dir(&project_dir).unwrap();
+            fs::write(
+                project_dir.join("s.jsonl"),
+                r#"{"type":"user","message":{"role":"user","content":"find the needle"}}"#,
+            )
+            .unwrap();
+        }
+        let query = SearchQuery::new("needle").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let budget = SearchBudget::new(1);
+        for project in ["-tmp-a", "-tmp-b"] {
+            scan_project_into(projects.path(), project, &query, &budget, &tx);
+        }
+        drop(tx);
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+        assert!(budget.exhausted());
+    }
+}
@@ -0,0 +1,128 @@
+//! 会话 JSONL 文件监听
+//!
+//! 前端不再轮询 `load_session_history`：`watch_session` 监听会话文件，文件变长时向调用窗口
+//! 推送 `session-file-changed:{session_id}`（载荷为新的字节长度），配合增量读取使用。
+//! 监听器与窗口绑定，窗口销毁时统一释放，避免文件描述符泄漏。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use tauri::{Emitter, Manager};
+
+use super::paths::{get_claude_dir, validate_path_component};
+use crate::error::{AppError, AppResult};
+
+/// An active session file watcher bound to a window
+struct SessionWatch {
+    window_label: String,
+    // 仅用于保持监听存活，drop 时停止监听
+    _watcher: RecommendedWatcher,
+}
+
+static SESSION_WATCHES: Lazy<Mutex<HashMap<String, SessionWatch>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn session_file_path(project_id: &str, session_id: &str) -> AppResult<PathBuf> {
+    validate_path_component(project_id, "project id")?;
+    validate_path_component(session_id, "session id")?;
+    let claude_dir = get_claude_dir().map_err(|e| AppError::not_found(e.to_string()))?;
+    Ok(claude_dir
+        .join("projects")
+        .join(project_id)
+        .join(format!("{}.jsonl", session_id)))
+}
+
+/// Watch a session JSONL and emit `session-file-changed:{session_id}` to the calling
+/// window with the new byte length whenever the file grows. Returns the watch id.
+#[tauri::command]
+pub async fn watch_session(
+    window: tauri::WebviewWindow,
+    session_id: String,
+    project_id: String,
+) -> AppResult<String> {
+    let session_path = session_file_path(&project_id, &session_id)?;
+    let project_dir = session_path
+        .parent()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::invalid_config("Invalid session path"))?;
+    if !session_path.is_file() {
+        return Err(AppError::not_found(format!(
+            "Session file not found: {}",
+            session_id
+        )));
+    }
+
+    let watch_id = uuid::Uuid::new_v4().to_string();
+    let window_label = window.label().to_string();
+    let app = window.app_handle().clone();
+    let event_name = format!("session-file-changed:{}", session_id);
+    let mut last_len = std::fs::metadata(&session_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let task_window_label = window_label.clone();
+    let task_session_path = session_path.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if !event.paths.iter().any(|p| p == &task_session_path) {
+            return;
+        }
+        let Ok(len) = std::fs::metadata(&task_session_path).map(|m| m.len()) else {
+            return;
+        };
+        // 文件被截断或重写时只重置基准，不发事件
+        if len > last_len {
+            let _ = app.emit_to(task_window_label.as_str(), &event_name, len);
+        }
+        last_len = len;
+    })
+    .map_err(|e| AppError::external(format!("Failed to create file watcher: {}", e)))?;
+
+    // 监听所在目录而不是文件本身：文件被原子替换后仍能收到事件
+    watcher
+        .watch(&project_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| AppError::external(format!("Failed to watch session file: {}", e)))?;
+
+    SESSION_WATCHES.lock().unwrap().insert(
+        watch_id.clone(),
+        SessionWatch {
+            window_label: window_label.clone(),
+            _watcher: watcher,
+        },
+    );
+
+    log::info!(
+        "Window {} watching session file {:?} ({})",
+        window_label,
+        session_path,
+        watch_id
+    );
+    Ok(watch_id)
+}
+
+/// Stop watching a session file
+#[tauri::command]
+pub async fn unwatch_session(watch_id: String) -> AppResult<bool> {
+    let removed = SESSION_WATCHES.lock().unwrap().remove(&watch_id);
+    Ok(removed.is_some())
+}
+
+/// Drop every session watcher owned by a window (called when the window is destroyed)
+pub fn unwatch_window_sessions(window_label: &str) {
+    let mut watches = SESSION_WATCHES.lock().unwrap();
+    let before = watches.len();
+    watches.retain(|_, watch| watch.window_label != window_label);
+    let removed = before - watches.len();
+    if removed > 0 {
+        log::info!(
+            "Dropped {} session watcher(s) for closed window {}",
+            removed,
+            window_label
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! 斜杠命令识别
+//!
+//! Claude CLI 只在 `-p` 参数中解析斜杠命令，stdin 管道不会触发，所以运行器需要判断 prompt
+//! 是否应作为命令传递。短的单行 `/xxx` 一律视为命令；多行或较长的 prompt 只有在首个
+//! `/word` 属于已知命令（内置、用户 / 项目自定义、执行配置中声明）时才按命令处理，
+//! 且超过 `MAX_SLASH_COMMAND_BYTES` 时仍退回 stdin，避免超出命令行长度限制。
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::config::get_claude_execution_config;
+use crate::commands::permission_config::ClaudeExecutionConfig;
+
+/// 单行短命令的长度上限（保持原有行为）
+const SIMPLE_SLASH_COMMAND_MAX_LEN: usize = 256;
+
+/// 带参数的已知命令通过命令行传递的字节上限（Windows cmd 约 8KB）
+const MAX_SLASH_COMMAND_BYTES: usize = 6 * 1024;
+
+/// Claude CLI 内置的斜杠命令
+const BUILTIN_SLASH_COMMANDS: &[&str] = &[
+    "add-dir",
+    "agents",
+    "bug",
+    "clear",
+    "compact",
+    "config",
+    "context",
+    "cost",
+    "doctor",
+    "export",
+    "help",
+    "hooks",
+    "init",
+    "login",
+    "logout",
+    "mcp",
+    "memory",
+    "model",
+    "output-style",
+    "permissions",
+    "plugin",
+    "pr-comments",
+    "release-notes",
+    "resume",
+    "review",
+    "rewind",
+    "security-review",
+    "status",
+    "statusline",
+    "todos",
+    "usage",
+];
+
+/// A slash command the runner routes through `-p`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct KnownSlashCommand {
+    /// Command name without the leading '/'
+    pub name: String,
+    /// "builtin" / "user" / "project" / "config"
+    pub source: String,
+}
+
+/// The command name of a prompt's leading `/word` token, if it has one
+fn leading_command_name(prompt: &str) -> Option<&str> {
+    let token = prompt.split_whitespace().next()?;
+    let name = token.strip_prefix('/')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'));
+    valid.then_some(name)
+}
+
+/// Decide whether a prompt should be passed as a slash command
+pub(super) fn is_slash_command(prompt: &str, known_commands: &[String]) -> bool {
+    let trimmed = prompt.trim();
+    if !trimmed.starts_with('/') {
+        return false;
+    }
+    if !trimmed.contains('\n') && trimmed.len() < SIMPLE_SLASH_COMMAND_MAX_LEN {
+        return true;
+    }
+    if trimmed.len() > MAX_SLASH_COMMAND_BYTES {
+        return false;
+    }
+
+    leading_command_name(trimmed).is_some_and(|name| {
+        known_commands
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Collect built-in, custom (user / project) and configured slash commands
+pub(super) async fn known_slash_commands(
+    project_path: Option<String>,
+    config: &ClaudeExecutionConfig,
+) -> Vec<KnownSlashCommand> {
+    let mut commands: Vec<KnownSlashCommand> = BUILTIN_SLASH_COMMANDS
+        .iter()
+        .map(|name| KnownSlashCommand {
+            name: name.to_string(),
+            source: "builtin".to_string(),
+        })
+        .collect();
+
+    match crate::commands::extensions::list_custom_slash_commands(project_path).await {
+        Ok(custom) => commands.extend(custom.into_iter().map(|command| KnownSlashCommand {
+            name: command.name,
+            source: command.scope,
+        })),
+        Err(e) => log::warn!("Failed to list custom slash commands: {}", e),
+    }
+
+    commands.extend(
+        config
+            .known_slash_commands
+            .iter()
+            .map(|name| KnownSlashCommand {
+                name: name.trim().trim_start_matches('/').to_string(),
+                source: "config".to_string(),
+            }),
+    );
+
+    let mut seen = std::collections::HashSet::new();
+    commands.retain(|command| !command.name.is_empty() && seen.insert(command.name.to_lowercase()));
+    commands
+}
+
+/// Names of the known slash commands, used for routing decisions
+pub(super) async fn known_slash_command_names(
+    project_path: Option<String>,
+    config: &ClaudeExecutionConfig,
+) -> Vec<String> {
+    known_slash_commands(project_path, config)
+        .await
+        .into_iter()
+        .map(|command| command.name)
+        .collect()
+}
+
+/// List the slash commands the runner recognizes for a project
+#[tauri::command]
+pub async fn list_known_slash_commands(
+    app: AppHandle,
+    project_path: Option<String>,
+) -> Result<Vec<KnownSlashCommand>, String> {
+    let config = get_claude_execution_config(app, project_path.clone())
+        .await
+        .unwrap_or_else(|_| ClaudeExecutionConfig::default());
+    Ok(known_slash_commands(project_path, &config).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_single_line_commands_are_always_detected() {
+        assert!(is_slash_command("/help", &[]));
+        assert!(is_slash_command("  /compact keep the api notes ", &[]));
+        assert!(!is_slash_command("please run /help", &[]));
+    }
+
+    #[test]
+    fn multi_line_commands_require_a_known_name() {
+        let known = vec!["deploy".to_string(), "frontend:lint".to_string()];
+        let prompt = "/deploy staging\nwith the following notes\n- a\n- b";
+        assert!(is_slash_command(prompt, &known));
+        assert!(is_slash_command("/Frontend:lint\nsrc/", &known));
+        assert!(!is_slash_command("/unknown\nline two", &known));
+        assert!(!is_slash_command("/usr/bin/env is broken\nhelp", &known));
+    }
+
+    #[test]
+    fn huge_prompts_fall_back_to_stdin() {
+        let known = vec!["deploy".to_string()];
+        let prompt = format!("/deploy\n{}", "x".repeat(MAX_SLASH_COMMAND_BYTES));
+        assert!(!is_slash_command(&prompt, &known));
+    }
+}
@@ -1,70 +1,283 @@
 use base64::{engine::general_purpose, Engine};
 use serde::{Deserialize, Serialize};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
 use std::fs;
+use std::io::Cursor;
 use tauri::{command, AppHandle};
 
 // ⚡ 新增：文本剪贴板支持
 use arboard::Clipboard;
 
+/// 默认最大边长（像素），超过时按比例缩小
+const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 4096;
+/// 默认最大字节数（20MB）
+const DEFAULT_MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+/// 为满足大小限制而缩小时的最小边长，缩到该尺寸仍超限则报错
+const MIN_DOWNSCALE_DIMENSION: u32 = 64;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SavedImageResult {
-    pub success: bool,
     pub file_path: Option<String>,
-    pub error: Option<String>,
+    /// 最终写入图片的宽度
+    pub width: Option<u32>,
+    /// 最终写入图片的高度
+    pub height: Option<u32>,
+    /// 最终写入图片的格式（png / jpg / gif / webp）
+    pub format: Option<String>,
+    /// 最终写入文件的字节数
+    pub byte_size: Option<u64>,
+}
+
+/// 剪贴板图片保存错误，前端可根据 kind 区分"剪贴板为空"与"写入失败"等情况
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum ClipboardImageError {
+    /// 剪贴板中没有图片数据
+    EmptyClipboard,
+    /// 数据无法解析为图片
+    InvalidImage(String),
+    /// 图片超过限制且不允许（或无法）缩小
+    TooLarge(String),
+    /// 不支持的输出格式
+    UnsupportedFormat(String),
+    /// 图片重新编码失败
+    EncodeFailed(String),
+    /// 写入文件失败
+    WriteFailed(String),
+}
+
+impl std::fmt::Display for ClipboardImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyClipboard => write!(f, "Clipboard does not contain an image"),
+            Self::InvalidImage(msg) => write!(f, "Invalid image data: {}", msg),
+            Self::TooLarge(msg) => write!(f, "Image too large: {}", msg),
+            Self::UnsupportedFormat(format) => write!(
+                f,
+                "Unsupported image format \"{}\"; accepted formats are png, jpg and jpeg",
+                format
+            ),
+            Self::EncodeFailed(msg) => write!(f, "Failed to encode image: {}", msg),
+            Self::WriteFailed(msg) => write!(f, "Failed to write image file: {}", msg),
+        }
+    }
+}
+
+/// 处理后的图片数据
+struct ProcessedImage {
+    bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+    extension: &'static str,
+}
+
+/// 将图片格式映射为文件扩展名
+fn image_format_extension(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::WebP => "webp",
+        _ => "png",
+    }
+}
+
+/// 解析输出格式参数，只支持 PNG 与 JPEG；为空时返回 None（保留原始格式）
+fn parse_output_format(format: Option<&str>) -> Result<Option<ImageFormat>, ClipboardImageError> {
+    let Some(format) = format.map(str::trim).filter(|f| !f.is_empty()) else {
+        return Ok(None);
+    };
+    match format.to_lowercase().as_str() {
+        "png" => Ok(Some(ImageFormat::Png)),
+        "jpg" | "jpeg" => Ok(Some(ImageFormat::Jpeg)),
+        _ => Err(ClipboardImageError::UnsupportedFormat(format.to_string())),
+    }
+}
+
+fn encode_image(img: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ClipboardImageError> {
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), format)
+        .map_err(|e| ClipboardImageError::EncodeFailed(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// 编码图片，超过 `max_bytes` 时逐步缩小尺寸后重新编码
+fn encode_within_limit(
+    mut img: DynamicImage,
+    format: ImageFormat,
+    max_bytes: u64,
+) -> Result<(DynamicImage, Vec<u8>), ClipboardImageError> {
+    loop {
+        let bytes = encode_image(&img, format)?;
+        let len = bytes.len() as u64;
+        if len <= max_bytes {
+            return Ok((img, bytes));
+        }
+
+        // 编码后的大小大致与像素数成正比：按面积比例缩小边长，并多留 10% 余量
+        let scale = ((max_bytes as f64 / len as f64).sqrt() * 0.9).min(0.9);
+        let width = (img.width() as f64 * scale) as u32;
+        let height = (img.height() as f64 * scale) as u32;
+        if width.max(height) < MIN_DOWNSCALE_DIMENSION {
+            return Err(ClipboardImageError::TooLarge(format!(
+                "encoded image is {} bytes at {}x{}, limit is {} bytes",
+                len,
+                img.width(),
+                img.height(),
+                max_bytes
+            )));
+        }
+        log::info!(
+            "Encoded image is {} bytes (limit {}), downscaling to {}x{}",
+            len,
+            max_bytes,
+            width,
+            height
+        );
+        img = img.resize(width.max(1), height.max(1), FilterType::Triangle);
+    }
+}
+
+/// 按限制处理图片：必要时缩小尺寸并重新编码为目标格式
+fn process_clipboard_image(
+    data: Vec<u8>,
+    output_format: Option<ImageFormat>,
+    max_dimension: u32,
+    max_bytes: u64,
+    downscale: bool,
+) -> Result<ProcessedImage, ClipboardImageError> {
+    let source_format =
+        image::guess_format(&data).map_err(|e| ClipboardImageError::InvalidImage(e.to_string()))?;
+
+    // 只读取头部获取尺寸，避免对超大图片做无谓的完整解码
+    let (width, height) = ImageReader::with_format(Cursor::new(&data), source_format)
+        .into_dimensions()
+        .map_err(|e| ClipboardImageError::InvalidImage(e.to_string()))?;
+
+    let too_big_dimension = width > max_dimension || height > max_dimension;
+    let too_big_bytes = data.len() as u64 > max_bytes;
+
+    if (too_big_dimension || too_big_bytes) && !downscale {
+        return Err(ClipboardImageError::TooLarge(format!(
+            "{}x{} ({} bytes) exceeds limit of {}px / {} bytes",
+            width,
+            height,
+            data.len(),
+            max_dimension,
+            max_bytes
+        )));
+    }
+
+    let target_format = output_format.unwrap_or(source_format);
+    let needs_reencode = too_big_dimension
+        || too_big_bytes
+        || (output_format.is_some() && target_format != source_format);
+
+    if !needs_reencode {
+        return Ok(ProcessedImage {
+            bytes: data,
+            width,
+            height,
+            extension: image_format_extension(source_format),
+        });
+    }
+
+    // 只能重新编码为 PNG 或 JPEG
+    let target_format = match target_format {
+        ImageFormat::Jpeg => ImageFormat::Jpeg,
+        _ => ImageFormat::Png,
+    };
+
+    let mut img = image::load_from_memory_with_format(&data, source_format)
+        .map_err(|e| ClipboardImageError::InvalidImage(e.to_string()))?;
+    drop(data);
+
+    if too_big_dimension {
+        img = img.resize(max_dimension, max_dimension, FilterType::Triangle);
+    }
+
+    // JPEG 不支持 alpha 通道
+    if target_format == ImageFormat::Jpeg {
+        img = DynamicImage::ImageRgb8(img.to_rgb8());
+    }
+
+    let (img, bytes) = encode_within_limit(img, target_format, max_bytes)?;
+
+    Ok(ProcessedImage {
+        bytes,
+        width: img.width(),
+        height: img.height(),
+        extension: image_format_extension(target_format),
+    })
 }
 
 /// 保存Base64图片数据到临时文件
+///
+/// - `format`: 输出格式（"png" / "jpg" / "jpeg"），为空时保留原始格式，其他值报错
+/// - `max_dimension` / `max_bytes`: 尺寸与大小限制，超过时按 `downscale` 决定缩小或报错
 #[command]
 pub async fn save_clipboard_image(
     _app: AppHandle,
     base64_data: String,
     format: Option<String>,
-) -> Result<SavedImageResult, String> {
-    println!("Received base64_data length: {}", base64_data.len());
-    println!(
-        "Base64 data preview: {}",
-        &base64_data[..std::cmp::min(100, base64_data.len())]
+    max_dimension: Option<u32>,
+    max_bytes: Option<u64>,
+    downscale: Option<bool>,
+) -> Result<SavedImageResult, ClipboardImageError> {
+    log::info!(
+        "Received clipboard image, base64 length: {}",
+        base64_data.len()
     );
 
     // 解析Data URL格式 (data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAA...)
     let data_url_prefix = "data:image/";
-    let (base64_content, extension) = if base64_data.starts_with(data_url_prefix) {
+    let base64_content = if base64_data.starts_with(data_url_prefix) {
         // 找到逗号位置，分离元数据和Base64数据
-        let comma_pos = base64_data
-            .find(",")
-            .ok_or_else(|| "Invalid data URL format: missing comma separator".to_string())?;
-
-        // 提取MIME类型信息
-        let mime_part = &base64_data[data_url_prefix.len()..comma_pos];
-        let extension = if mime_part.contains("png") {
-            "png"
-        } else if mime_part.contains("jpeg") || mime_part.contains("jpg") {
-            "jpg"
-        } else if mime_part.contains("gif") {
-            "gif"
-        } else if mime_part.contains("webp") {
-            "webp"
-        } else {
-            "png" // 默认为PNG
-        };
-
-        // 提取纯Base64内容
-        let base64_content = &base64_data[comma_pos + 1..];
-        (base64_content, extension)
+        let comma_pos = base64_data.find(",").ok_or_else(|| {
+            ClipboardImageError::InvalidImage(
+                "Invalid data URL format: missing comma separator".to_string(),
+            )
+        })?;
+        &base64_data[comma_pos + 1..]
     } else {
         // 如果没有Data URL前缀，假设是纯Base64数据
-        (base64_data.as_str(), format.as_deref().unwrap_or("png"))
+        base64_data.as_str()
     };
 
-    println!("Detected extension: {}", extension);
-    println!("Base64 content length: {}", base64_content.len());
+    if base64_content.trim().is_empty() {
+        return Err(ClipboardImageError::EmptyClipboard);
+    }
 
     // 解码Base64数据
     let image_data = general_purpose::STANDARD
-        .decode(base64_content)
-        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+        .decode(base64_content.trim())
+        .map_err(|e| {
+            ClipboardImageError::InvalidImage(format!("Failed to decode base64: {}", e))
+        })?;
+
+    if image_data.is_empty() {
+        return Err(ClipboardImageError::EmptyClipboard);
+    }
 
-    println!("Decoded image data size: {} bytes", image_data.len());
+    log::info!("Decoded image data size: {} bytes", image_data.len());
+
+    let output_format = parse_output_format(format.as_deref())?;
+    let max_dimension = max_dimension.unwrap_or(DEFAULT_MAX_IMAGE_DIMENSION);
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_IMAGE_BYTES);
+    let downscale = downscale.unwrap_or(true);
+
+    // 解码/缩放/编码属于 CPU 密集操作，放到阻塞线程池避免卡住界面
+    let processed = tokio::task::spawn_blocking(move || {
+        process_clipboard_image(
+            image_data,
+            output_format,
+            max_dimension,
+            max_bytes,
+            downscale,
+        )
+    })
+    .await
+    .map_err(|e| ClipboardImageError::EncodeFailed(e.to_string()))??;
 
     // 获取用户临时目录，确保使用完整路径
     let temp_dir = std::env::var("TEMP")
@@ -78,31 +291,31 @@ pub async fn save_clipboard_image(
     let images_dir = temp_dir.join("claude_workbench_clipboard_images");
 
     // 创建目录
-    fs::create_dir_all(&images_dir)
-        .map_err(|e| format!("Failed to create images directory: {}", e))?;
+    fs::create_dir_all(&images_dir).map_err(|e| {
+        ClipboardImageError::WriteFailed(format!("Failed to create images directory: {}", e))
+    })?;
 
     // 生成唯一文件名
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f");
-    let filename = format!("clipboard_image_{}.{}", timestamp, extension);
+    let filename = format!("clipboard_image_{}.{}", timestamp, processed.extension);
     let file_path = images_dir.join(&filename);
 
-    println!("Saving image to: {}", file_path.display());
+    log::info!("Saving image to: {}", file_path.display());
 
     // 保存文件
-    fs::write(&file_path, image_data).map_err(|e| format!("Failed to write image file: {}", e))?;
+    fs::write(&file_path, &processed.bytes)
+        .map_err(|e| ClipboardImageError::WriteFailed(e.to_string()))?;
 
     // 验证文件是否成功保存
     if !file_path.exists() {
-        return Ok(SavedImageResult {
-            success: false,
-            file_path: None,
-            error: Some("File was not saved successfully".to_string()),
-        });
+        return Err(ClipboardImageError::WriteFailed(
+            "File was not saved successfully".to_string(),
+        ));
     }
 
-    let file_size = fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
-
-    println!("Image saved successfully! File size: {} bytes", file_size);
+    let file_size = fs::metadata(&file_path)
+        .map(|m| m.len())
+        .unwrap_or(processed.bytes.len() as u64);
 
     // 返回清洁的Windows文件路径，移除UNC前缀
     let mut path_str = file_path.to_string_lossy().to_string();
@@ -112,12 +325,20 @@ pub async fn save_clipboard_image(
         path_str = path_str[4..].to_string();
     }
 
-    println!("Final cleaned path: {}", path_str);
+    log::info!(
+        "Image saved: {} ({}x{}, {} bytes)",
+        path_str,
+        processed.width,
+        processed.height,
+        file_size
+    );
 
     Ok(SavedImageResult {
-        success: true,
         file_path: Some(path_str),
-        error: None,
+        width: Some(processed.width),
+        height: Some(processed.height),
+        format: Some(processed.extension.to_string()),
+        byte_size: Some(file_size),
     })
 }
 
@@ -138,15 +359,192 @@ pub async fn write_to_clipboard(text: String) -> Result<(), String> {
     Ok(())
 }
 
+/// 剪贴板中可用的内容格式
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClipboardFormats {
+    pub text: bool,
+    pub html: bool,
+    pub image: bool,
+    pub files: bool,
+}
+
+/// 带格式信息的剪贴板读取结果
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ClipboardReadResult {
+    /// 仅文本（默认，兼容旧调用）
+    Text(String),
+    /// 文本 + 可用格式列表
+    WithFormats {
+        text: Option<String>,
+        formats: ClipboardFormats,
+    },
+}
+
+/// 将单条 file:// URI 转换为本地路径
+fn file_uri_to_path(uri: &str) -> Option<String> {
+    let rest = uri.strip_prefix("file://")?;
+    // file://localhost/path 与 file:///path 等价
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let decoded = urlencoding::decode(rest).ok()?.into_owned();
+
+    // Windows: file:///C:/foo -> C:/foo
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Some(decoded[1..].to_string());
+    }
+
+    Some(decoded)
+}
+
+/// 从文本形式的剪贴板内容中解析文件路径
+///
+/// 不同平台的文件复制格式不同：
+/// - Linux (GNOME/Nautilus): `x-special/gnome-copied-files`，首行为 copy/cut，后续为 file:// URI
+/// - Linux (KDE/通用): `text/uri-list`，每行一个 file:// URI，# 开头为注释
+/// - macOS: `public.file-url`，为 file:// URI
+/// - Windows: `CF_HDROP`，由 arboard 直接解析为路径
+fn parse_file_list_text(text: &str) -> Vec<String> {
+    let lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mut paths = Vec::new();
+
+    for line in lines {
+        // GNOME 格式的操作行
+        if line == "copy" || line == "cut" || line.starts_with('#') {
+            continue;
+        }
+        match file_uri_to_path(line) {
+            Some(path) => paths.push(path),
+            // 只要有一行不是文件 URI，就认为是普通文本
+            None => return Vec::new(),
+        }
+    }
+
+    paths
+}
+
+/// 读取剪贴板中的文件路径（从系统文件管理器复制的文件）
+fn read_clipboard_file_paths(clipboard: &mut Clipboard) -> Vec<String> {
+    if let Ok(files) = clipboard.get().file_list() {
+        if !files.is_empty() {
+            return files
+                .into_iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect();
+        }
+    }
+
+    clipboard
+        .get_text()
+        .map(|text| parse_file_list_text(&text))
+        .unwrap_or_default()
+}
+
+/// 从剪贴板读取文件路径
+#[command]
+pub async fn read_clipboard_files() -> Result<Vec<String>, String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+
+    let files = read_clipboard_file_paths(&mut clipboard);
+    log::info!("Read {} file path(s) from clipboard", files.len());
+    Ok(files)
+}
+
 /// 从剪贴板读取文本
+///
+/// `include_formats` 为 true 时同时返回可用格式（text/html/image/files），
+/// 此时剪贴板没有文本不会报错，text 为 null
 #[command]
-pub async fn read_from_clipboard() -> Result<String, String> {
+pub async fn read_from_clipboard(
+    include_formats: Option<bool>,
+) -> Result<ClipboardReadResult, String> {
     let mut clipboard =
         Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
 
-    let text = clipboard
-        .get_text()
-        .map_err(|e| format!("Failed to read from clipboard: {}", e))?;
+    if !include_formats.unwrap_or(false) {
+        let text = clipboard
+            .get_text()
+            .map_err(|e| format!("Failed to read from clipboard: {}", e))?;
+        return Ok(ClipboardReadResult::Text(text));
+    }
+
+    let text = clipboard.get_text().ok();
+    let formats = ClipboardFormats {
+        text: text.is_some(),
+        html: clipboard.get().html().is_ok(),
+        image: clipboard.get_image().is_ok(),
+        files: !read_clipboard_file_paths(&mut clipboard).is_empty(),
+    };
+
+    Ok(ClipboardReadResult::WithFormats { text, formats })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成难以压缩的噪声 PNG
+    fn noise_png(width: u32, height: u32) -> Vec<u8> {
+        let mut seed: u32 = 12345;
+        let img = image::RgbImage::from_fn(width, height, |_, _| {
+            let mut channel = || {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            };
+            image::Rgb([channel(), channel(), channel()])
+        });
+        encode_image(&DynamicImage::ImageRgb8(img), ImageFormat::Png).unwrap()
+    }
+
+    #[test]
+    fn oversized_bytes_are_downscaled_to_fit() {
+        let data = noise_png(256, 256);
+        let max_bytes = (data.len() / 4) as u64;
 
-    Ok(text)
+        let processed = process_clipboard_image(data, None, 4096, max_bytes, true).unwrap();
+        assert!(processed.bytes.len() as u64 <= max_bytes);
+        assert!(processed.width < 256 && processed.height < 256);
+        assert_eq!(processed.width, processed.height);
+        assert_eq!(processed.extension, "png");
+    }
+
+    #[test]
+    fn oversized_bytes_are_rejected_without_downscale() {
+        let data = noise_png(128, 128);
+        let max_bytes = (data.len() / 4) as u64;
+
+        let result = process_clipboard_image(data, None, 4096, max_bytes, false);
+        assert!(matches!(result, Err(ClipboardImageError::TooLarge(_))));
+    }
+
+    #[test]
+    fn gives_up_when_even_the_minimum_size_is_too_large() {
+        let data = noise_png(128, 128);
+
+        let result = process_clipboard_image(data, None, 4096, 16, true);
+        assert!(matches!(result, Err(ClipboardImageError::TooLarge(_))));
+    }
+
+    #[test]
+    fn unsupported_output_format_is_rejected() {
+        assert!(matches!(
+            parse_output_format(Some(" JPEG ")),
+            Ok(Some(ImageFormat::Jpeg))
+        ));
+        assert!(matches!(parse_output_format(Some("")), Ok(None)));
+        let err = parse_output_format(Some("bmp")).unwrap_err();
+        assert!(matches!(err, ClipboardImageError::UnsupportedFormat(_)));
+        assert!(err.to_string().contains("png, jpg and jpeg"));
+    }
+
+    #[test]
+    fn small_images_are_kept_as_is() {
+        let data = noise_png(16, 16);
+        let len = data.len();
+
+        let processed = process_clipboard_image(data, None, 4096, 1024 * 1024, true).unwrap();
+        assert_eq!(processed.bytes.len(), len);
+        assert_eq!((processed.width, processed.height), (16, 16));
+    }
 }
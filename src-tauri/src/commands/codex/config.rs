@@ -9,15 +9,18 @@ use rusqlite;
  * - Mode configuration (Native/WSL)
  * - Provider management (presets, switching, CRUD)
  */
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use tokio::process::Command;
-use tokio::sync::OnceCell;
+use tokio::sync::{Mutex, OnceCell};
 
 // Import platform-specific utilities for window hiding
-use crate::claude_binary::detect_binary_for_tool;
+use crate::claude_binary::{
+    detect_binary_for_tool, installation_for_path, ClaudeInstallation, InstallationType,
+};
 use crate::commands::claude::apply_no_window_async;
 // Import WSL utilities
 use super::super::wsl_utils;
@@ -35,8 +38,9 @@ pub struct CodexAvailability {
 }
 
 /// 全局 Codex 可用性结果缓存
-/// 避免重复创建 WSL 进程检测可用性
-static CODEX_AVAILABILITY_CACHE: OnceCell<CodexAvailability> = OnceCell::const_new();
+/// 避免重复创建 WSL 进程检测可用性；登录 / 更新后清空以重新检测
+static CODEX_AVAILABILITY_CACHE: Lazy<Mutex<Option<CodexAvailability>>> =
+    Lazy::new(|| Mutex::new(None));
 
 /// 全局 Codex 模式配置缓存
 /// 避免重复创建 WSL 进程检测模式配置
@@ -301,15 +305,24 @@ pub fn get_codex_sessions_dir() -> Result<PathBuf, String> {
 #[tauri::command]
 pub async fn check_codex_availability() -> Result<CodexAvailability, String> {
     // 使用缓存避免重复检测
-    let result = CODEX_AVAILABILITY_CACHE
-        .get_or_init(|| async {
+    let mut cache = CODEX_AVAILABILITY_CACHE.lock().await;
+    let result = match cache.as_ref() {
+        Some(result) => result.clone(),
+        None => {
             log::info!("[Codex] Checking availability (first time)...");
-            do_check_codex_availability().await
-        })
-        .await;
+            let result = do_check_codex_availability().await;
+            *cache = Some(result.clone());
+            result
+        }
+    };
 
     log::debug!("[Codex] Returning cached availability: {:?}", result);
-    Ok(result.clone())
+    Ok(result)
+}
+
+/// 清空可用性缓存，下次检查时重新检测
+pub(crate) async fn invalidate_codex_availability_cache() {
+    *CODEX_AVAILABILITY_CACHE.lock().await = None;
 }
 
 /// 实际执行 Codex 可用性检测（内部函数）
@@ -507,13 +520,6 @@ pub async fn set_custom_codex_path(app: AppHandle, custom_path: String) -> Resul
             }
         }
         if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-            let _ = conn.execute(
-                "CREATE TABLE IF NOT EXISTS app_settings (
-                    key TEXT PRIMARY KEY,
-                    value TEXT NOT NULL
-                )",
-                [],
-            );
             let _ = conn.execute(
                 "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
                 rusqlite::params!["codex_binary_path", path_str],
@@ -524,7 +530,7 @@ pub async fn set_custom_codex_path(app: AppHandle, custom_path: String) -> Resul
     Ok(())
 }
 
-fn read_custom_codex_path_from_db(app: &AppHandle) -> Option<String> {
+pub(crate) fn read_custom_codex_path_from_db(app: &AppHandle) -> Option<String> {
     if let Ok(app_data_dir) = app.path().app_data_dir() {
         let db_path = app_data_dir.join("agents.db");
         if db_path.exists() {
@@ -542,22 +548,39 @@ fn read_custom_codex_path_from_db(app: &AppHandle) -> Option<String> {
     None
 }
 
-/// Get current Codex path (custom first, then runtime detection)
-#[tauri::command]
-pub async fn get_codex_path(app: AppHandle) -> Result<String, String> {
+/// Resolve the Codex CLI in priority order: binaries.json override, custom path in
+/// app_settings, then runtime detection. Returns (path, source, installation type)
+fn resolve_codex_binary(app: &AppHandle) -> Result<(String, String, InstallationType), String> {
     if let Some(override_path) = get_binary_override("codex") {
-        return Ok(override_path);
+        return Ok((
+            override_path,
+            "binaries.json".to_string(),
+            InstallationType::Custom,
+        ));
     }
-    if let Some(db_path) = read_custom_codex_path_from_db(&app) {
-        return Ok(db_path);
+    if let Some(db_path) = read_custom_codex_path_from_db(app) {
+        return Ok((db_path, "custom".to_string(), InstallationType::Custom));
     }
 
     let (_env, detected) = detect_binary_for_tool("codex", "CODEX_PATH", "codex");
-    if let Some(inst) = detected {
-        return Ok(inst.path);
-    }
+    detected
+        .map(|inst| (inst.path, inst.source, inst.installation_type))
+        .ok_or_else(|| {
+            "Codex CLI not found. Please set CODEX_PATH or install codex CLI".to_string()
+        })
+}
 
-    Err("Codex CLI not found. Please set CODEX_PATH or install codex CLI".to_string())
+/// Get current Codex path (custom first, then runtime detection)
+#[tauri::command]
+pub async fn get_codex_path(app: AppHandle) -> Result<String, String> {
+    resolve_codex_binary(&app).map(|(path, _, _)| path)
+}
+
+/// Get the selected Codex CLI installation (path, version, source and installation type)
+#[tauri::command]
+pub async fn get_codex_binary_info(app: AppHandle) -> Result<ClaudeInstallation, String> {
+    let (path, source, installation_type) = resolve_codex_binary(&app)?;
+    Ok(installation_for_path(&path, &source, installation_type))
 }
 
 /// Clear custom Codex path, restore auto detection
@@ -952,6 +975,12 @@ pub async fn set_codex_mode_config(
         }
     };
 
+    // WSL 模式未指定发行版时自动选择默认发行版并持久化
+    let wsl_distro = match (&codex_mode, wsl_distro.filter(|d| !d.trim().is_empty())) {
+        (wsl_utils::CodexMode::Wsl, None) => Some(wsl_utils::auto_select_wsl_distro("Codex")?),
+        (_, distro) => distro,
+    };
+
     let config = wsl_utils::CodexConfig {
         mode: codex_mode,
         wsl_distro,
@@ -979,7 +1008,7 @@ fn should_use_wsl_config() -> bool {
 /// Get Codex config directory path (supports both Native and WSL modes)
 /// When WSL mode is enabled, returns the WSL UNC path (e.g., \\wsl$\Ubuntu\home\user\.codex)
 /// Otherwise returns the Windows native path (e.g., C:\Users\xxx\.codex)
-fn get_codex_config_dir() -> Result<PathBuf, String> {
+pub(crate) fn get_codex_config_dir() -> Result<PathBuf, String> {
     // Check if WSL mode is enabled
     if should_use_wsl_config() {
         if let Some(wsl_dir) = wsl_utils::get_wsl_codex_dir() {
@@ -1008,13 +1037,13 @@ fn get_codex_config_path() -> Result<PathBuf, String> {
 /// Get Codex providers.json path (for custom presets)
 /// Note: Providers are stored in native Windows path, not WSL
 /// because they are managed by Workbench, not by Codex CLI
-fn get_codex_providers_path() -> Result<PathBuf, String> {
+pub(crate) fn get_codex_providers_path() -> Result<PathBuf, String> {
     let home_dir = dirs::home_dir().ok_or_else(|| "Cannot get home directory".to_string())?;
     Ok(home_dir.join(".codex").join("providers.json"))
 }
 
 /// Extract API key from auth JSON
-fn extract_api_key_from_auth(auth: &serde_json::Value) -> Option<String> {
+pub(crate) fn extract_api_key_from_auth(auth: &serde_json::Value) -> Option<String> {
     auth.get("OPENAI_API_KEY")
         .or_else(|| auth.get("OPENAI_KEY"))
         .or_else(|| auth.get("API_KEY"))
@@ -1023,7 +1052,7 @@ fn extract_api_key_from_auth(auth: &serde_json::Value) -> Option<String> {
 }
 
 /// Extract base_url from config.toml text
-fn extract_base_url_from_config(config: &str) -> Option<String> {
+pub(crate) fn extract_base_url_from_config(config: &str) -> Option<String> {
     let re = regex::Regex::new(r#"base_url\s*=\s*"([^"]+)""#).ok()?;
     re.captures(config)
         .and_then(|caps| caps.get(1))
@@ -1045,6 +1074,13 @@ fn extract_model_from_config(config: &str) -> Option<String> {
     None
 }
 
+/// Read the default model configured in Codex config.toml (if any)
+pub(super) fn load_configured_codex_model() -> Option<String> {
+    let config_path = get_codex_config_path().ok()?;
+    let config = fs::read_to_string(config_path).ok()?;
+    extract_model_from_config(&config)
+}
+
 // ============================================================================
 // Provider Management Commands
 // ============================================================================
@@ -1113,6 +1149,94 @@ pub async fn get_current_codex_config() -> Result<CurrentCodexConfig, String> {
     })
 }
 
+/// Expand `${VAR}` references in `value` using `lookup`.
+/// Unresolved or malformed references are errors, so a literal `${VAR}` is never written out
+fn expand_env_refs_with(
+    value: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unterminated variable reference in '{}'", value))?;
+        let name = &after[..end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Invalid variable reference '${{{}}}'", name));
+        }
+        let resolved =
+            lookup(name).ok_or_else(|| format!("Environment variable '{}' is not set", name))?;
+        result.push_str(&resolved);
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Expand `${VAR}` references from the process environment, falling back to
+/// the `env` section of ~/.claude/settings.json
+pub(crate) fn expand_env_refs(value: &str) -> Result<String, String> {
+    if !value.contains("${") {
+        return Ok(value.to_string());
+    }
+    let settings_env: std::collections::HashMap<String, String> =
+        crate::commands::claude::load_settings_env()
+            .into_iter()
+            .collect();
+    expand_env_refs_with(value, &|name| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| settings_env.get(name).cloned())
+    })
+}
+
+/// Resolve keychain and `${VAR}` references in auth values and base_url so the
+/// written auth.json / config.toml never contain the placeholders
+fn resolve_codex_env_refs(mut config: CodexProviderConfig) -> Result<CodexProviderConfig, String> {
+    if let Some(auth) = config.auth.as_object_mut() {
+        for (key, value) in auth.iter_mut() {
+            if let Some(text) = value.as_str() {
+                let expanded = crate::commands::keychain::resolve_secret(text)
+                    .and_then(|text| expand_env_refs(&text))
+                    .map_err(|e| format!("auth.{}: {}", key, e))?;
+                *value = serde_json::Value::String(expanded);
+            }
+        }
+    }
+
+    if config.config.contains("${") {
+        let mut table: toml::Table = toml::from_str(&config.config)
+            .map_err(|e| format!("Invalid TOML configuration: {}", e))?;
+        let mut changed = false;
+        let mut expand_base_url = |section: &mut toml::Table| -> Result<(), String> {
+            if let Some(toml::Value::String(url)) = section.get_mut("base_url") {
+                if url.contains("${") {
+                    *url = expand_env_refs(url).map_err(|e| format!("base_url: {}", e))?;
+                    changed = true;
+                }
+            }
+            Ok(())
+        };
+        expand_base_url(&mut table)?;
+        if let Some(toml::Value::Table(providers)) = table.get_mut("model_providers") {
+            for (_, section) in providers.iter_mut() {
+                if let toml::Value::Table(section) = section {
+                    expand_base_url(section)?;
+                }
+            }
+        }
+        if changed {
+            config.config = toml::to_string_pretty(&table)
+                .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        }
+    }
+
+    Ok(config)
+}
+
 /// Switch to a Codex provider configuration
 /// Preserves user's custom settings and OAuth tokens
 /// Supports both Native Windows and WSL modes
@@ -1138,6 +1262,9 @@ pub async fn switch_codex_provider(config: CodexProviderConfig) -> Result<String
             .map_err(|e| format!("Failed to create .codex directory at {:?}: {}", config_dir, e))?;
     }
 
+    // Resolve ${VAR} references before anything is written
+    let config = resolve_codex_env_refs(config)?;
+
     // Validate new TOML if not empty
     let new_config_table: Option<toml::Table> = if !config.config.trim().is_empty() {
         Some(
@@ -1257,10 +1384,134 @@ pub async fn switch_codex_provider(config: CodexProviderConfig) -> Result<String
     ))
 }
 
+/// Check a Codex provider config, returning (errors, warnings).
+/// Errors make the config unusable; warnings are likely mistakes
+fn codex_config_issues(config: &CodexProviderConfig) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let is_official = config.is_official.unwrap_or(false);
+
+    // auth must be a JSON object whose API key fields are strings
+    match config.auth.as_object() {
+        Some(auth) => {
+            for key in ["OPENAI_API_KEY", "OPENAI_KEY", "API_KEY"] {
+                if let Some(value) = auth.get(key) {
+                    if !value.is_string() && !value.is_null() {
+                        errors.push(format!("auth.{} must be a string", key));
+                    }
+                }
+            }
+            let has_api_key =
+                extract_api_key_from_auth(&config.auth).is_some_and(|key| !key.trim().is_empty());
+            if !is_official && !has_api_key {
+                warnings.push("auth has no API key (OPENAI_API_KEY)".to_string());
+            }
+        }
+        None => errors.push("auth must be a JSON object".to_string()),
+    }
+
+    if config.config.trim().is_empty() {
+        if !is_official {
+            warnings.push("config.toml is empty; no base_url or model will be set".to_string());
+        }
+        return (errors, warnings);
+    }
+
+    let table: toml::Table = match toml::from_str(&config.config) {
+        Ok(table) => table,
+        Err(e) => {
+            errors.push(format!("Invalid TOML configuration: {}", e));
+            return (errors, warnings);
+        }
+    };
+
+    match table.get("model") {
+        Some(toml::Value::String(model)) if !model.trim().is_empty() => {}
+        Some(toml::Value::String(_)) | None => {
+            warnings.push("model is not set in config.toml".to_string())
+        }
+        Some(_) => errors.push("model must be a string".to_string()),
+    }
+
+    // base_url lives in the selected [model_providers.<name>] section (or at top level)
+    let providers = table.get("model_providers").and_then(|v| v.as_table());
+    let selected = table.get("model_provider").and_then(|v| v.as_str());
+    if let Some(name) = selected {
+        if !providers.is_some_and(|p| p.contains_key(name)) && name != "openai" {
+            warnings.push(format!(
+                "model_provider '{}' has no [model_providers.{}] section",
+                name, name
+            ));
+        }
+    }
+    let section = match selected {
+        Some(name) => providers.and_then(|p| p.get(name)),
+        None => providers.and_then(|p| p.values().next()),
+    };
+    let base_url = section
+        .and_then(|s| s.get("base_url"))
+        .or_else(|| table.get("base_url"));
+    match base_url {
+        // ${VAR} references are resolved (and checked) when switching
+        Some(toml::Value::String(url)) if url.contains("${") => {}
+        Some(toml::Value::String(url)) => {
+            if let Err(e) = crate::commands::url_utils::validate_http_url(url) {
+                errors.push(format!("base_url: {}", e));
+            }
+        }
+        Some(_) => errors.push("base_url must be a string".to_string()),
+        None if !is_official => warnings.push("base_url is not set in config.toml".to_string()),
+        None => {}
+    }
+
+    (errors, warnings)
+}
+
+/// Move API keys into the OS keychain when keychain storage is enabled
+fn protect_codex_secrets(mut config: CodexProviderConfig) -> CodexProviderConfig {
+    if let Some(auth) = config.auth.as_object_mut() {
+        for key in ["OPENAI_API_KEY", "OPENAI_KEY", "API_KEY"] {
+            if let Some(serde_json::Value::String(value)) = auth.get_mut(key) {
+                *value = crate::commands::keychain::protect_secret("codex", &config.id, key, value);
+            }
+        }
+    }
+    config
+}
+
+/// Reject configs with validation errors before they are saved
+fn ensure_valid_codex_config(config: &CodexProviderConfig) -> Result<(), String> {
+    let (errors, _) = codex_config_issues(config);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid Codex provider config: {}",
+            errors.join("; ")
+        ))
+    }
+}
+
+/// Validate a Codex provider config without saving or switching to it.
+/// Returns issues prefixed with "error:" (config would be rejected) or "warning:"
+#[tauri::command]
+pub async fn validate_codex_provider_config(
+    config: CodexProviderConfig,
+) -> Result<Vec<String>, String> {
+    let (errors, warnings) = codex_config_issues(&config);
+    Ok(errors
+        .into_iter()
+        .map(|e| format!("error: {}", e))
+        .chain(warnings.into_iter().map(|w| format!("warning: {}", w)))
+        .collect())
+}
+
 /// Add a new Codex provider configuration
 #[tauri::command]
 pub async fn add_codex_provider_config(config: CodexProviderConfig) -> Result<String, String> {
     log::info!("[Codex Provider] Adding provider: {}", config.name);
+    ensure_valid_codex_config(&config)?;
+    let config = protect_codex_secrets(config);
 
     let providers_path = get_codex_providers_path()?;
 
@@ -1307,6 +1558,8 @@ pub async fn add_codex_provider_config(config: CodexProviderConfig) -> Result<St
 #[tauri::command]
 pub async fn update_codex_provider_config(config: CodexProviderConfig) -> Result<String, String> {
     log::info!("[Codex Provider] Updating provider: {}", config.name);
+    ensure_valid_codex_config(&config)?;
+    let config = protect_codex_secrets(config);
 
     let providers_path = get_codex_providers_path()?;
 
@@ -1360,18 +1613,18 @@ pub async fn delete_codex_provider_config(id: String) -> Result<String, String>
         .map_err(|e| format!("Failed to parse providers.json: {}", e))?;
 
     // Find and remove the provider
-    let initial_len = providers.len();
-    providers.retain(|p| p.id != id);
-
-    if providers.len() == initial_len {
-        return Err(format!("Provider with ID '{}' not found", id));
-    }
+    let index = providers
+        .iter()
+        .position(|p| p.id == id)
+        .ok_or_else(|| format!("Provider with ID '{}' not found", id))?;
+    let deleted = providers.remove(index);
 
     // Save providers
     let content = serde_json::to_string_pretty(&providers)
         .map_err(|e| format!("Failed to serialize providers: {}", e))?;
     fs::write(&providers_path, content)
         .map_err(|e| format!("Failed to write providers.json: {}", e))?;
+    crate::commands::keychain::delete_provider_secrets(&deleted);
 
     log::info!("[Codex Provider] Successfully deleted provider: {}", id);
     Ok(format!("Successfully deleted Codex provider: {}", id))
@@ -1460,6 +1713,7 @@ pub async fn test_codex_provider_connection(
     let mut request = client.get(&test_url);
 
     if let Some(key) = api_key {
+        let key = crate::commands::keychain::resolve_secret(&key)?;
         request = request.header("Authorization", format!("Bearer {}", key));
     }
 
@@ -1543,3 +1797,80 @@ pub async fn update_codex_reasoning_level(level: String) -> Result<String, Strin
         mode_info
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(auth: serde_json::Value, config: &str) -> CodexProviderConfig {
+        CodexProviderConfig {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            description: None,
+            website_url: None,
+            category: None,
+            auth,
+            config: config.to_string(),
+            is_official: None,
+            is_partner: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn codex_config_issues_flag_invalid_configs() {
+        let valid = provider(
+            serde_json::json!({ "OPENAI_API_KEY": "sk-test" }),
+            "model = \"gpt-5\"\nmodel_provider = \"proxy\"\n\n[model_providers.proxy]\nbase_url = \"https://proxy.example.com/v1\"\n",
+        );
+        assert_eq!(codex_config_issues(&valid), (vec![], vec![]));
+
+        let bad_toml = provider(serde_json::json!({}), "model = ");
+        assert_eq!(codex_config_issues(&bad_toml).0.len(), 1);
+
+        let bad_url = provider(
+            serde_json::json!({ "OPENAI_API_KEY": "sk-test" }),
+            "model = \"gpt-5\"\nbase_url = \"proxy.example.com\"\n",
+        );
+        assert!(codex_config_issues(&bad_url).0[0].starts_with("base_url"));
+
+        let bad_auth = provider(serde_json::json!("sk-test"), "");
+        let (errors, warnings) = codex_config_issues(&bad_auth);
+        assert_eq!(errors, vec!["auth must be a JSON object".to_string()]);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn expand_env_refs_resolves_or_errors() {
+        let lookup = |name: &str| (name == "OPENAI_API_KEY").then(|| "sk-env".to_string());
+
+        assert_eq!(
+            expand_env_refs_with("${OPENAI_API_KEY}", &lookup).unwrap(),
+            "sk-env"
+        );
+        assert_eq!(
+            expand_env_refs_with("Bearer ${OPENAI_API_KEY}!", &lookup).unwrap(),
+            "Bearer sk-env!"
+        );
+        assert_eq!(expand_env_refs_with("plain", &lookup).unwrap(), "plain");
+        assert!(expand_env_refs_with("${MISSING}", &lookup).is_err());
+        assert!(expand_env_refs_with("${OPENAI_API_KEY", &lookup).is_err());
+        assert!(expand_env_refs_with("${BAD NAME}", &lookup).is_err());
+    }
+
+    #[test]
+    fn resolve_codex_env_refs_expands_provider_base_url() {
+        std::env::set_var("ANY_CODE_TEST_PROXY_URL", "https://proxy.example.com/v1");
+        let config = provider(
+            serde_json::json!({}),
+            "model_provider = \"proxy\"\n\n[model_providers.proxy]\nbase_url = \"${ANY_CODE_TEST_PROXY_URL}\"\n",
+        );
+
+        let resolved = resolve_codex_env_refs(config).unwrap();
+        let table: toml::Table = toml::from_str(&resolved.config).unwrap();
+        assert_eq!(
+            table["model_providers"]["proxy"]["base_url"].as_str(),
+            Some("https://proxy.example.com/v1")
+        );
+    }
+}
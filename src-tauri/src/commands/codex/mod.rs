@@ -8,11 +8,13 @@
  * - session.rs: Session lifecycle management (execute, resume, cancel, list, delete)
  * - git_ops.rs: Git operations for rewind functionality (records, truncate, revert)
  * - config.rs: Configuration management (availability, paths, mode, providers)
+ * - session_converter.rs / session_ir.rs: Cross-engine session conversion
  */
 pub mod config;
 pub mod git_ops;
 pub mod session;
 pub mod session_converter;
+pub mod session_ir;
 pub mod usage;
 
 // ============================================================================
@@ -33,7 +35,7 @@ pub use config::{CodexAvailability, CodexModeInfo, CodexProviderConfig, CurrentC
 
 // Session converter types
 #[allow(unused_imports)]
-pub use session_converter::{ConversionResult, ConversionSource};
+pub use session_converter::{ConversionPreview, ConversionResult, ConversionSource};
 
 // ============================================================================
 // Re-export Tauri Commands - Session Management
@@ -58,8 +60,9 @@ pub use git_ops::{
 // ============================================================================
 
 pub use config::{
-    check_codex_availability, clear_custom_codex_path, get_codex_mode_config, get_codex_path,
-    set_codex_mode_config, set_custom_codex_path, validate_codex_path_cmd,
+    check_codex_availability, clear_custom_codex_path, get_codex_binary_info,
+    get_codex_mode_config, get_codex_path, set_codex_mode_config, set_custom_codex_path,
+    validate_codex_path_cmd,
 };
 
 // ============================================================================
@@ -70,14 +73,18 @@ pub use config::{
     add_codex_provider_config, clear_codex_provider_config, delete_codex_provider_config,
     get_codex_provider_presets, get_current_codex_config, reorder_codex_provider_configs,
     switch_codex_provider, test_codex_provider_connection, update_codex_provider_config,
-    update_codex_reasoning_level,
+    update_codex_reasoning_level, validate_codex_provider_config,
 };
 
 // ============================================================================
 // Re-export Tauri Commands - Session Conversion
 // ============================================================================
 
-pub use session_converter::{convert_claude_to_codex, convert_codex_to_claude, convert_session};
+pub use session_converter::{
+    convert_claude_to_codex, convert_claude_to_gemini, convert_codex_to_claude,
+    convert_codex_to_gemini, convert_gemini_to_claude, convert_gemini_to_codex, convert_session,
+    preview_session_conversion,
+};
 
 // ============================================================================
 // Re-export Helper Functions (for internal use by submodules)
@@ -18,12 +18,14 @@ use tokio::sync::Mutex;
 
 // Import platform-specific utilities for window hiding
 use crate::claude_binary::detect_binary_for_tool;
-use crate::commands::claude::apply_no_window_async;
+use crate::commands::agent::{self, AgentTool};
+use crate::commands::auth_detection::{self, AuthRequiredDetector};
+use crate::commands::claude::{apply_no_window_async, LaunchCommand};
 use crate::process::JobObject;
 // Import WSL utilities for Windows + WSL Codex support
 use super::super::wsl_utils;
 // Import config module for sessions directory
-use super::config::get_codex_sessions_dir;
+use super::config::{get_codex_sessions_dir, load_configured_codex_model};
 
 // ============================================================================
 // Type Definitions
@@ -131,6 +133,8 @@ pub struct CodexProcessHandle {
     pub pid: u32,
     /// Windows Job Object (kills all child processes when dropped); no-op on non-Windows.
     pub job_object: Option<JobObject>,
+    /// Project the session was started in
+    pub project_path: String,
 }
 
 /// Global state to track Codex processes
@@ -152,12 +156,31 @@ impl Default for CodexProcessState {
 // Core Execution Methods
 // ============================================================================
 
+/// Resolves the model for a Codex run: explicit model → project override → config.toml
+/// The config.toml model is not passed via --model (the CLI reads it itself),
+/// it is only reported as the effective model.
+pub(crate) fn resolve_codex_model(options: &mut CodexExecutionOptions) -> Option<String> {
+    if options
+        .model
+        .as_deref()
+        .is_some_and(|m| m.trim().is_empty())
+    {
+        options.model = None;
+    }
+    if options.model.is_none() {
+        options.model =
+            crate::commands::claude::project_model_override(&options.project_path, "codex");
+    }
+    options.model.clone().or_else(load_configured_codex_model)
+}
+
 /// Executes a Codex task in non-interactive mode with streaming output
 #[tauri::command]
 pub async fn execute_codex(
-    options: CodexExecutionOptions,
+    mut options: CodexExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
+    let effective_model = resolve_codex_model(&mut options);
     // Avoid logging sensitive fields (prompt/api_key). Log only non-sensitive metadata.
     log::info!(
         "execute_codex called: project_path={}, mode={:?}, model={:?}, json={}, output_schema_present={}, output_file_present={}, skip_git_repo_check={}, session_id_present={}, resume_last={}, api_key_present={}, prompt_len={}",
@@ -179,17 +202,26 @@ pub async fn execute_codex(
 
     // Execute and stream output
     let session_id = format!("codex-{}", uuid::Uuid::new_v4());
-    execute_codex_process(session_id, cmd, prompt, options.project_path.clone(), app_handle).await
+    execute_codex_process(
+        session_id,
+        cmd,
+        prompt,
+        options.project_path.clone(),
+        effective_model,
+        app_handle,
+    )
+    .await
 }
 
 /// Resumes a previous Codex session
 #[tauri::command]
 pub async fn resume_codex(
     session_id: String,
-    options: CodexExecutionOptions,
+    mut options: CodexExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     log::info!("resume_codex called for session: {}", session_id);
+    let effective_model = resolve_codex_model(&mut options);
 
     // Build codex exec resume command (session_id added inside build function)
     let (cmd, prompt) = build_codex_command(&options, true, Some(&session_id))?;
@@ -201,6 +233,7 @@ pub async fn resume_codex(
         cmd,
         prompt,
         options.project_path.clone(),
+        effective_model,
         app_handle,
     )
     .await
@@ -209,17 +242,26 @@ pub async fn resume_codex(
 /// Resumes the last Codex session
 #[tauri::command]
 pub async fn resume_last_codex(
-    options: CodexExecutionOptions,
+    mut options: CodexExecutionOptions,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     log::info!("resume_last_codex called");
+    let effective_model = resolve_codex_model(&mut options);
 
     // Build codex exec resume --last command
     let (cmd, prompt) = build_codex_command(&options, true, Some("--last"))?;
 
     // Execute and stream output
     let session_id = format!("codex-{}", uuid::Uuid::new_v4());
-    execute_codex_process(session_id, cmd, prompt, options.project_path.clone(), app_handle).await
+    execute_codex_process(
+        session_id,
+        cmd,
+        prompt,
+        options.project_path.clone(),
+        effective_model,
+        app_handle,
+    )
+    .await
 }
 
 /// Cancels a running Codex execution
@@ -229,6 +271,15 @@ pub async fn cancel_codex(session_id: Option<String>, app_handle: AppHandle) ->
 
     log::info!("cancel_codex called for session: {:?}", session_id);
 
+    // 仍在排队等待名额的会话直接移出队列
+    if let Some(sid) = &session_id {
+        let limiter = app_handle.state::<crate::process::SessionLimiterState>();
+        if limiter.0.cancel_queued(None, Some(sid)) {
+            log::info!("Removed queued Codex session {} before it started", sid);
+            return Ok(());
+        }
+    }
+
     let state: tauri::State<'_, CodexProcessState> = app_handle.state();
     let mut processes = state.processes.lock().await;
 
@@ -249,6 +300,10 @@ pub async fn cancel_codex(session_id: Option<String>, app_handle: AppHandle) ->
             } else {
                 log::info!("Successfully killed Codex process tree for session: {}", sid);
             }
+
+            // Emit cancellation event
+            let _ = app_handle.emit(&format!("codex-cancelled:{}", sid), true);
+            let _ = app_handle.emit("codex-cancelled", true);
         } else {
             log::warn!("No running process found for session: {}", sid);
         }
@@ -267,7 +322,9 @@ pub async fn cancel_codex(session_id: Option<String>, app_handle: AppHandle) ->
             } else {
                 log::info!("Successfully killed Codex process tree for session: {}", sid);
             }
+            let _ = app_handle.emit(&format!("codex-cancelled:{}", sid), true);
         }
+        let _ = app_handle.emit("codex-cancelled", true);
     }
 
     Ok(())
@@ -596,7 +653,7 @@ pub async fn delete_codex_session(session_id: String) -> Result<String, String>
 /// Builds a Codex command with the given options
 /// Returns (Command, Option<String>) where the String is the prompt to be passed via stdin
 /// Supports both native execution and WSL mode on Windows
-fn build_codex_command(
+pub(crate) fn build_codex_command(
     options: &CodexExecutionOptions,
     is_resume: bool,
     session_id: Option<&str>,
@@ -832,14 +889,26 @@ async fn execute_codex_process(
     session_id: String,
     mut cmd: Command,
     prompt: Option<String>,
-    _project_path: String,
+    project_path: String,
+    model: Option<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // 启动流程一开始就发送 session_init，确保即使启动失败也能让前端拿到 session_id 做隔离与错误反馈
+    let launch = LaunchCommand::from_command(&cmd);
     let init_payload = serde_json::json!({
         "type": "session_init",
-        "session_id": session_id
+        "session_id": session_id,
+        "model": model,
+        "binary": launch.binary,
+        "args": launch.args,
+        "env": launch.env
     });
+    agent::emit_agent_session_state(
+        &app_handle,
+        AgentTool::Codex,
+        Some(&session_id),
+        &init_payload,
+    );
     if let Err(e) = app_handle.emit("codex-session-init", init_payload) {
         log::error!("Failed to emit codex-session-init: {}", e);
     }
@@ -854,6 +923,23 @@ async fn execute_codex_process(
     // This prevents the terminal window from flashing when starting Codex sessions
     apply_no_window_async(&mut cmd);
 
+    // 并发限制：名额在进程退出（或被取消）后的清理任务结束时释放
+    let session_permit = match crate::commands::session_limits::acquire_session_permit(
+        &app_handle,
+        "codex",
+        &project_path,
+        Some(session_id.clone()),
+        None,
+    )
+    .await
+    {
+        Ok(permit) => permit,
+        Err(e) => {
+            emit_codex_error(&app_handle, &session_id, "Codex 会话未能启动", Some(e.message()));
+            return Ok(());
+        }
+    };
+
     // Spawn process
     let mut child = match cmd.spawn() {
         Ok(child) => child,
@@ -879,6 +965,10 @@ async fn execute_codex_process(
         }
     };
     log::info!("[Codex] Spawned process with PID: {}", pid);
+    app_handle
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .journal_process(pid, "codex", Some(session_id.clone()));
 
     // Windows robustness: assign the process to a Job Object so *all* descendants are cleaned up
     // even if Codex/MCP spawns detached node.exe processes.
@@ -965,6 +1055,7 @@ async fn execute_codex_process(
             child,
             pid,
             job_object,
+            project_path: project_path.clone(),
         };
         processes.insert(session_id.clone(), handle);
 
@@ -974,6 +1065,7 @@ async fn execute_codex_process(
 
     // Clone handles for async tasks
     let app_handle_stdout = app_handle.clone();
+    let app_handle_stderr = app_handle.clone();
     let app_handle_complete = app_handle.clone();
     let session_id_stdout = session_id.clone(); // Clone for stdout task
     let session_id_stderr = session_id.clone(); // Clone for stderr task
@@ -985,6 +1077,11 @@ async fn execute_codex_process(
     let stderr_buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
     let stderr_buffer_for_stderr = stderr_buffer.clone();
     let stderr_buffer_for_complete = stderr_buffer.clone();
+    // stdout 与 stderr 共用一个登录提示检测器
+    let auth_detector = Arc::new(std::sync::Mutex::new(AuthRequiredDetector::new(
+        AgentTool::Codex,
+    )));
+    let auth_detector_for_stderr = auth_detector.clone();
 
     // 🔧 FIX: Use channels to track stdout/stderr closure for timeout detection
     let (done_tx, done_rx) = tokio::sync::oneshot::channel();
@@ -1000,6 +1097,16 @@ async fn execute_codex_process(
                 saw_stdout.store(true, Ordering::Relaxed);
                 // Use trace level to avoid flooding logs in debug mode
                 log::trace!("Codex output: {}", line);
+                if auth_detector.lock().unwrap().check(&line) {
+                    auth_detection::handle_auth_required(
+                        &app_handle_stdout,
+                        AgentTool::Codex,
+                        Some(&session_id_stdout),
+                        None,
+                        pid,
+                        &line,
+                    );
+                }
                 // Emit to session-specific channel first (for multi-tab isolation)
                 if let Err(e) =
                     app_handle_stdout.emit(&format!("codex-output:{}", session_id_stdout), &line)
@@ -1010,6 +1117,14 @@ async fn execute_codex_process(
                 if let Err(e) = app_handle_stdout.emit("codex-output", &line) {
                     log::error!("Failed to emit codex-output (global): {}", e);
                 }
+                agent::emit_agent_output(
+                    &app_handle_stdout,
+                    AgentTool::Codex,
+                    Some(&session_id_stdout),
+                    None,
+                    None,
+                    &line,
+                );
 
                 // Detect turn completion to trigger backend cleanup even if stdout never closes.
                 if done_tx.is_some() {
@@ -1049,6 +1164,16 @@ async fn execute_codex_process(
             // Log error messages for debugging
             if !line.trim().is_empty() {
                 log::warn!("Codex stderr: {}", line);
+                if auth_detector_for_stderr.lock().unwrap().check(&line) {
+                    auth_detection::handle_auth_required(
+                        &app_handle_stderr,
+                        AgentTool::Codex,
+                        Some(&session_id_stderr),
+                        None,
+                        pid,
+                        &line,
+                    );
+                }
                 // 仅缓存少量 stderr 以便在“无 stdout 输出”的启动失败场景下进行汇总反馈
                 let mut buf = stderr_buffer_for_stderr.lock().await;
                 if buf.len() < 20 {
@@ -1068,6 +1193,7 @@ async fn execute_codex_process(
     tokio::spawn(async move {
         use crate::commands::claude::kill_process_tree;
 
+        let _session_permit = session_permit;
         let state: tauri::State<'_, CodexProcessState> = app_handle_complete.state();
 
         // Only wait for stdout to close (stderr can continue logging)
@@ -1103,6 +1229,23 @@ async fn execute_codex_process(
         if let Err(e) = app_handle_complete.emit("codex-complete", true) {
             log::error!("Failed to emit codex-complete (global): {}", e);
         }
+        agent::emit_agent_session_state(
+            &app_handle_complete,
+            AgentTool::Codex,
+            Some(&session_id_complete),
+            &serde_json::json!({
+                "session_id": session_id_complete,
+                "status": "stopped",
+                "success": true,
+            }),
+        );
+        agent::emit_agent_complete(
+            &app_handle_complete,
+            AgentTool::Codex,
+            Some(&session_id_complete),
+            None,
+            true,
+        );
 
         // Continue waiting for process exit in background (with timeout protection)
         // This ensures proper cleanup but doesn't block the completion event
@@ -1188,6 +1331,12 @@ async fn execute_codex_process(
                 break;
             }
         }
+
+        app_handle_complete
+            .state::<crate::process::ProcessRegistryState>()
+            .0
+            .journal()
+            .forget(pid_for_cleanup);
     });
 
     Ok(())
@@ -1206,4 +1355,11 @@ fn emit_codex_error(app_handle: &AppHandle, session_id: &str, message: &str, det
 
     let _ = app_handle.emit(&format!("codex-error:{}", session_id), &payload_str);
     let _ = app_handle.emit("codex-error", &payload_str);
+    agent::emit_agent_error(
+        app_handle,
+        AgentTool::Codex,
+        Some(session_id),
+        None,
+        &payload_str,
+    );
 }
@@ -1,11 +1,14 @@
 use once_cell::sync::Lazy;
 /**
- * Claude ↔ Codex Session 转换模块
+ * Claude ↔ Codex ↔ Gemini Session 转换模块
  *
- * 实现 Claude 与 Codex 引擎之间的 Session 双向转换功能。
+ * 实现 Claude、Codex 与 Gemini 引擎之间的 Session 双向转换功能。
  * 支持：
  * - Claude → Codex：将 Claude session 转换为 Codex 可执行的 session
  * - Codex → Claude：将 Codex session 转换为 Claude 可加载的历史记录
+ * - Claude / Codex ↔ Gemini
+ *
+ * 所有组合都经由 session_ir.rs 中的中间表示完成，本模块负责引擎识别与 Tauri 接口
  *
  * 核心特性：
  * - 自动识别引擎类型（UUID vs rollout-前缀）
@@ -17,7 +20,8 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
+
+use super::session_ir;
 
 // ================================
 // 数据结构定义
@@ -27,7 +31,7 @@ use std::io::{BufRead, BufReader, Write};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConversionSource {
-    /// 源引擎类型: "claude" | "codex"
+    /// 源引擎类型: "claude" | "codex" | "gemini"
     pub engine: String,
     /// 源 Session ID
     pub session_id: String,
@@ -55,6 +59,97 @@ pub struct ConversionResult {
     pub target_path: String,
     /// 错误信息 (如果失败)
     pub error: Option<String>,
+    /// 转换预览（仅在未确认的 convert_session 调用中返回，此时不会写入文件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ConversionPreview>,
+}
+
+/// 转换预览 - 在内存中执行转换，不写入任何文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionPreview {
+    /// 源引擎类型
+    pub source_engine: String,
+    /// 目标引擎类型
+    pub target_engine: String,
+    /// 源 session 中的条目数量
+    pub source_count: usize,
+    /// 无损转换的条目数量
+    pub clean_count: usize,
+    /// 有损转换的条目数量（部分内容丢失或降级为文本）
+    pub lossy_count: usize,
+    /// 被丢弃的条目数量
+    pub dropped_count: usize,
+    /// 转换后输出的条目数量
+    pub output_count: usize,
+    /// 有损/丢弃原因汇总
+    pub warnings: Vec<String>,
+    /// 输出样例（前几条）
+    pub sample: Vec<Value>,
+}
+
+/// 预览中展示的输出样例条数
+const PREVIEW_SAMPLE_SIZE: usize = 5;
+
+/// 单条源记录的转换结果分类
+pub(super) enum ConversionOutcome {
+    Clean,
+    Lossy(String),
+    Dropped(String),
+}
+
+/// 转换统计（用于生成预览）
+#[derive(Default)]
+pub(super) struct ConversionStats {
+    pub(super) clean: usize,
+    pub(super) lossy: usize,
+    pub(super) dropped: usize,
+    reasons: HashMap<String, usize>,
+}
+
+impl ConversionStats {
+    pub(super) fn record(&mut self, outcome: ConversionOutcome) {
+        match outcome {
+            ConversionOutcome::Clean => self.clean += 1,
+            ConversionOutcome::Lossy(reason) => {
+                self.lossy += 1;
+                *self.reasons.entry(reason).or_insert(0) += 1;
+            }
+            ConversionOutcome::Dropped(reason) => {
+                self.dropped += 1;
+                *self.reasons.entry(reason).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub(super) fn into_preview<T: Serialize>(
+        self,
+        source_engine: &str,
+        target_engine: &str,
+        output: &[T],
+    ) -> ConversionPreview {
+        let mut reasons: Vec<(String, usize)> = self.reasons.into_iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        ConversionPreview {
+            source_engine: source_engine.to_string(),
+            target_engine: target_engine.to_string(),
+            source_count: self.clean + self.lossy + self.dropped,
+            clean_count: self.clean,
+            lossy_count: self.lossy,
+            dropped_count: self.dropped,
+            output_count: output.len(),
+            warnings: reasons
+                .into_iter()
+                .map(|(reason, count)| format!("{} (x{})", reason, count))
+                .collect(),
+            sample: output
+                .iter()
+                .take(PREVIEW_SAMPLE_SIZE)
+                .filter_map(|item| serde_json::to_value(item).ok())
+                .collect(),
+        }
+    }
 }
 
 // ================================
@@ -145,32 +240,6 @@ pub struct ClaudeMessageContent {
     pub usage: Option<TokenUsage>,
 }
 
-/// Claude 内容块 - 使用标签联合类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
-pub enum ClaudeContentBlock {
-    #[serde(rename = "text")]
-    Text { text: String },
-
-    #[serde(rename = "tool_use")]
-    ToolUse {
-        id: String,
-        name: String,
-        input: Value,
-    },
-
-    #[serde(rename = "tool_result")]
-    ToolResult {
-        tool_use_id: String,
-        content: Value,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        is_error: Option<bool>,
-    },
-
-    #[serde(rename = "thinking")]
-    Thinking { thinking: String },
-}
-
 /// Token 使用统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenUsage {
@@ -295,929 +364,16 @@ pub fn map_claude_to_codex_tool(claude_name: &str) -> String {
         .unwrap_or_else(|| claude_name.to_string())
 }
 
-// ================================
-// Claude → Codex 转换器
-// ================================
-
-/// Claude Session → Codex Session 转换器
-pub struct ClaudeToCodexConverter {
-    source_session_id: String,
-    project_id: String,           // 实际的目录名（如 C--Users-...）
-    project_path: String,         // 原始项目路径
-    new_session_uuid: String,     // 纯 UUID（用于文件内容）
-    new_session_filename: String, // rollout-{uuid}（用于文件名）
-}
-
-impl ClaudeToCodexConverter {
-    pub fn new(source_session_id: String, project_id: String, project_path: String) -> Self {
-        let uuid = uuid::Uuid::new_v4().to_string();
-        let new_session_uuid = uuid.clone();
-
-        // 生成带时间戳的文件名：rollout-2025-12-01T09-26-15-{uuid}
-        let now = chrono::Utc::now();
-        let timestamp = now.format("%Y-%m-%dT%H-%M-%S").to_string();
-        let new_session_filename = format!("rollout-{}-{}", timestamp, uuid);
-
-        Self {
-            source_session_id,
-            project_id,
-            project_path,
-            new_session_uuid,
-            new_session_filename,
-        }
-    }
-
-    /// 解析 content（支持字符串或数组格式）为 ClaudeContentBlock 数组
-    fn parse_content_blocks(&self, content: &Option<Value>) -> Vec<ClaudeContentBlock> {
-        let mut blocks = Vec::new();
-
-        if let Some(content_value) = content {
-            if let Some(text) = content_value.as_str() {
-                // 字符串格式 - 直接转为文本块
-                blocks.push(ClaudeContentBlock::Text {
-                    text: text.to_string(),
-                });
-            } else if let Some(array) = content_value.as_array() {
-                // 数组格式 - 解析每个块
-                for item in array {
-                    if let Some(block_type) = item.get("type").and_then(|t| t.as_str()) {
-                        match block_type {
-                            "text" => {
-                                if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                                    blocks.push(ClaudeContentBlock::Text {
-                                        text: text.to_string(),
-                                    });
-                                }
-                            }
-                            "tool_use" => {
-                                if let (Some(id), Some(name), Some(input)) = (
-                                    item.get("id").and_then(|i| i.as_str()),
-                                    item.get("name").and_then(|n| n.as_str()),
-                                    item.get("input"),
-                                ) {
-                                    blocks.push(ClaudeContentBlock::ToolUse {
-                                        id: id.to_string(),
-                                        name: name.to_string(),
-                                        input: input.clone(),
-                                    });
-                                }
-                            }
-                            "tool_result" => {
-                                if let (Some(tool_use_id), Some(content)) = (
-                                    item.get("tool_use_id").and_then(|t| t.as_str()),
-                                    item.get("content"),
-                                ) {
-                                    blocks.push(ClaudeContentBlock::ToolResult {
-                                        tool_use_id: tool_use_id.to_string(),
-                                        content: content.clone(),
-                                        is_error: item.get("is_error").and_then(|e| e.as_bool()),
-                                    });
-                                }
-                            }
-                            "thinking" => {
-                                if let Some(thinking) =
-                                    item.get("thinking").and_then(|t| t.as_str())
-                                {
-                                    blocks.push(ClaudeContentBlock::Thinking {
-                                        thinking: thinking.to_string(),
-                                    });
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
-
-        blocks
-    }
-
-    pub fn convert(&self) -> Result<ConversionResult, String> {
-        log::info!(
-            "Converting Claude session {} to Codex",
-            self.source_session_id
-        );
-
-        // 1. 读取源 Claude session
-        let claude_messages = self.read_claude_session()?;
-
-        // 2. 验证 session 已完成
-        self.validate_session_completed(&claude_messages)?;
-
-        // 3. 转换消息为 Codex 事件
-        let mut codex_events = Vec::new();
-
-        // 3a. 创建 session_meta 事件 (首行)
-        let first_timestamp = claude_messages
-            .first()
-            .and_then(|m| {
-                m.timestamp
-                    .clone()
-                    .or_else(|| m.sent_at.clone())
-                    .or_else(|| m.received_at.clone())
-            })
-            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-        let model = claude_messages.iter().find_map(|m| m.model.clone());
-        codex_events.push(self.create_session_meta(&first_timestamp, model.as_deref()));
-
-        // 3b. 转换每条消息（拆分多内容块为多个事件）
-        for msg in &claude_messages {
-            codex_events.extend(self.convert_claude_message(msg));
-        }
-
-        // 4. 写入目标文件
-        let target_path = self.write_codex_session(&codex_events)?;
-
-        log::info!(
-            "Successfully converted {} messages to Codex session {}",
-            codex_events.len(),
-            self.new_session_filename
-        );
-
-        Ok(ConversionResult {
-            success: true,
-            new_session_id: self.new_session_filename.clone(), // 返回文件名（带 rollout- 前缀）
-            target_engine: "codex".to_string(),
-            message_count: codex_events.len(),
-            source: ConversionSource {
-                engine: "claude".to_string(),
-                session_id: self.source_session_id.clone(),
-                converted_at: chrono::Utc::now().to_rfc3339(),
-                source_project_path: self.project_path.clone(),
-            },
-            target_path,
-            error: None,
-        })
-    }
-
-    /// 读取 Claude session 文件
-    fn read_claude_session(&self) -> Result<Vec<ClaudeMessage>, String> {
-        let claude_dir = super::super::claude::get_claude_dir()
-            .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
-
-        // 直接使用 project_id（实际的目录名）
-        let session_path = claude_dir
-            .join("projects")
-            .join(&self.project_id)
-            .join(format!("{}.jsonl", self.source_session_id));
-
-        if !session_path.exists() {
-            return Err(format!(
-                "Claude session file not found: {}",
-                session_path.display()
-            ));
-        }
-
-        let file = std::fs::File::open(&session_path)
-            .map_err(|e| format!("Failed to open session file: {}", e))?;
-
-        let reader = BufReader::new(file);
-        let mut messages = Vec::new();
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            match serde_json::from_str::<ClaudeMessage>(&line) {
-                Ok(msg) => messages.push(msg),
-                Err(e) => log::warn!("Failed to parse Claude message: {}", e),
-            }
-        }
-
-        if messages.is_empty() {
-            return Err("Claude session is empty".to_string());
-        }
-
-        log::info!("Read {} messages from Claude session", messages.len());
-        Ok(messages)
-    }
-
-    /// 验证 session 已完成（最后一条消息不应该是 user）
-    fn validate_session_completed(&self, messages: &[ClaudeMessage]) -> Result<(), String> {
-        if messages.is_empty() {
-            return Err("Session is empty".to_string());
-        }
-
-        if let Some(last) = messages.last() {
-            if last.message_type == "user" {
-                return Err("Session appears incomplete (ends with user message)".to_string());
-            }
-        }
-
-        Ok(())
-    }
-
-    /// 创建 session_meta 事件（Codex session 文件的首行）
-    fn create_session_meta(&self, timestamp: &str, model: Option<&str>) -> CodexEvent {
-        CodexEvent {
-            event_type: "session_meta".to_string(),
-            timestamp: Some(timestamp.to_string()),
-            payload: Some(serde_json::json!({
-                "id": self.new_session_uuid, // 使用纯 UUID（不带 rollout- 前缀）
-                "timestamp": timestamp,
-                "cwd": self.project_path,
-                "originator": "session_converter",
-                "cli_version": "converted",
-                "source": "conversion",
-                "model_provider": model.map(|_| "converted").unwrap_or("unknown"),
-                "conversion_source": {
-                    "engine": "claude",
-                    "session_id": self.source_session_id,
-                    "converted_at": chrono::Utc::now().to_rfc3339(),
-                    "source_project_path": self.project_path
-                }
-            })),
-            thread_id: None,
-            usage: None,
-        }
-    }
-
-    /// 转换单条 Claude 消息为多个 Codex 事件
-    fn convert_claude_message(&self, msg: &ClaudeMessage) -> Vec<CodexEvent> {
-        let mut events = Vec::new();
-        let timestamp = msg
-            .timestamp
-            .clone()
-            .or_else(|| msg.received_at.clone())
-            .or_else(|| msg.sent_at.clone())
-            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-
-        match msg.message_type.as_str() {
-            "user" => {
-                if let Some(ref message) = msg.message {
-                    let blocks = self.parse_content_blocks(&message.content);
-                    events.push(self.create_user_response_item(&blocks, &timestamp));
-                }
-            }
-            "assistant" => {
-                if let Some(ref message) = msg.message {
-                    let blocks = self.parse_content_blocks(&message.content);
-                    // 拆分多内容块为多个事件
-                    events.extend(self.convert_assistant_content(&blocks, &timestamp));
-                }
-            }
-            _ => {
-                // 跳过其他类型（system等）
-            }
-        }
-
-        events
-    }
-
-    /// 创建用户消息事件
-    fn create_user_response_item(
-        &self,
-        blocks: &[ClaudeContentBlock],
-        timestamp: &str,
-    ) -> CodexEvent {
-        let content: Vec<Value> = blocks
-            .iter()
-            .filter_map(|b| match b {
-                ClaudeContentBlock::Text { text } => {
-                    // Codex 使用 input_text 类型
-                    Some(serde_json::json!({"type": "input_text", "text": text}))
-                }
-                _ => None,
-            })
-            .collect();
-
-        CodexEvent {
-            event_type: "response_item".to_string(),
-            timestamp: Some(timestamp.to_string()),
-            payload: Some(serde_json::json!({
-                "type": "message",
-                "role": "user",
-                "content": content
-            })),
-            thread_id: None,
-            usage: None,
-        }
-    }
-
-    /// 转换 assistant 内容块为多个 Codex 事件
-    fn convert_assistant_content(
-        &self,
-        blocks: &[ClaudeContentBlock],
-        timestamp: &str,
-    ) -> Vec<CodexEvent> {
-        let mut events = Vec::new();
-
-        for block in blocks {
-            match block {
-                ClaudeContentBlock::Text { text } => {
-                    events.push(CodexEvent {
-                        event_type: "response_item".to_string(),
-                        timestamp: Some(timestamp.to_string()),
-                        payload: Some(serde_json::json!({
-                            "type": "message",
-                            "role": "assistant",
-                            "content": [{ "type": "output_text", "text": text }] // Codex 使用 output_text
-                        })),
-                        thread_id: None,
-                        usage: None,
-                    });
-                }
-                ClaudeContentBlock::ToolUse { id, name, input } => {
-                    // 生成新的 call_id
-                    let new_id = format!("call_{}", uuid::Uuid::new_v4());
-                    let codex_tool_name = map_claude_to_codex_tool(name);
-                    let arguments = serde_json::to_string(input).unwrap_or_default();
-
-                    events.push(CodexEvent {
-                        event_type: "response_item".to_string(),
-                        timestamp: Some(timestamp.to_string()),
-                        payload: Some(serde_json::json!({
-                            "type": "function_call",
-                            "name": codex_tool_name,
-                            "arguments": arguments,
-                            "call_id": new_id,
-                            "timestamp": timestamp,
-                            "original_tool_use_id": id
-                        })),
-                        thread_id: None,
-                        usage: None,
-                    });
-                }
-                ClaudeContentBlock::ToolResult {
-                    tool_use_id,
-                    content,
-                    is_error,
-                } => {
-                    let output_str = match content {
-                        Value::String(s) => s.clone(),
-                        _ => serde_json::to_string(content).unwrap_or_default(),
-                    };
-
-                    events.push(CodexEvent {
-                        event_type: "response_item".to_string(),
-                        timestamp: Some(timestamp.to_string()),
-                        payload: Some(serde_json::json!({
-                            "type": "function_call_output",
-                            "call_id": tool_use_id,
-                            "output": output_str,
-                            "is_error": is_error.unwrap_or(false),
-                            "timestamp": timestamp
-                        })),
-                        thread_id: None,
-                        usage: None,
-                    });
-                }
-                ClaudeContentBlock::Thinking { thinking } => {
-                    events.push(CodexEvent {
-                        event_type: "event_msg".to_string(),
-                        timestamp: Some(timestamp.to_string()),
-                        payload: Some(serde_json::json!({
-                            "item": {
-                                "id": format!("reasoning_{}", uuid::Uuid::new_v4()),
-                                "type": "reasoning",
-                                "text": thinking
-                            },
-                            "phase": "completed"
-                        })),
-                        thread_id: None,
-                        usage: None,
-                    });
-                }
-            }
-        }
-
-        events
-    }
-
-    /// 写入 Codex session 文件
-    fn write_codex_session(&self, events: &[CodexEvent]) -> Result<String, String> {
-        let sessions_dir = super::config::get_codex_sessions_dir()
-            .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
-
-        // 创建日期目录结构 YYYY/MM/DD
-        let now = chrono::Utc::now();
-        let date_dir = sessions_dir
-            .join(now.format("%Y").to_string())
-            .join(now.format("%m").to_string())
-            .join(now.format("%d").to_string());
-
-        std::fs::create_dir_all(&date_dir)
-            .map_err(|e| format!("Failed to create date directory: {}", e))?;
-
-        let file_path = date_dir.join(format!("{}.jsonl", self.new_session_filename));
-
-        let mut file = std::fs::File::create(&file_path)
-            .map_err(|e| format!("Failed to create session file: {}", e))?;
-
-        // 逐行写入 JSONL
-        for event in events {
-            let line = serde_json::to_string(event)
-                .map_err(|e| format!("Failed to serialize event: {}", e))?;
-            writeln!(file, "{}", line).map_err(|e| format!("Failed to write event: {}", e))?;
-        }
-
-        Ok(file_path.to_string_lossy().to_string())
-    }
-}
-
-// ================================
-// Codex → Claude 转换器
-// ================================
-
-/// Codex Session → Claude Session 转换器
-pub struct CodexToClaudeConverter {
-    source_session_id: String,
-    project_id: String,     // 实际的目录名（如 C--Users-...）
-    project_path: String,   // 原始项目路径
-    new_session_id: String, // UUID 格式
-}
-
-impl CodexToClaudeConverter {
-    pub fn new(source_session_id: String, project_id: String, project_path: String) -> Self {
-        let new_session_id = uuid::Uuid::new_v4().to_string();
-        Self {
-            source_session_id,
-            project_id,
-            project_path,
-            new_session_id,
-        }
-    }
-
-    /// 转换 content 为标准数组格式
-    fn simplify_content(&self, content: Vec<ClaudeContentBlock>) -> Option<Value> {
-        if content.is_empty() {
-            return None;
-        }
-
-        // 统一使用数组格式（与原生 Claude 一致）
-        let array: Vec<Value> = content
-            .iter()
-            .filter_map(|block| match block {
-                ClaudeContentBlock::Text { text } => {
-                    Some(serde_json::json!({"type": "text", "text": text}))
-                }
-                ClaudeContentBlock::ToolUse { id, name, input } => Some(serde_json::json!({
-                    "type": "tool_use",
-                    "id": id,
-                    "name": name,
-                    "input": input
-                })),
-                ClaudeContentBlock::ToolResult {
-                    tool_use_id,
-                    content,
-                    is_error,
-                } => Some(serde_json::json!({
-                    "type": "tool_result",
-                    "tool_use_id": tool_use_id,
-                    "content": content,
-                    "is_error": is_error
-                })),
-                ClaudeContentBlock::Thinking { thinking } => {
-                    Some(serde_json::json!({"type": "thinking", "thinking": thinking}))
-                }
-            })
-            .collect();
-
-        Some(Value::Array(array))
-    }
-
-    /// 创建标准 Claude 消息的辅助函数
-    fn create_claude_message(
-        &self,
-        message_type: &str,
-        role: &str,
-        content: Vec<ClaudeContentBlock>,
-        timestamp: &str,
-        model: Option<String>,
-    ) -> ClaudeMessage {
-        // 将 content 数组转换为简化格式
-        let simplified_content = self.simplify_content(content);
-
-        ClaudeMessage {
-            message_type: message_type.to_string(),
-            message: Some(ClaudeMessageContent {
-                role: role.to_string(),
-                content: simplified_content,
-                usage: None,
-            }),
-            timestamp: Some(timestamp.to_string()),
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
-            parent_uuid: None,
-            session_id: Some(self.new_session_id.clone()),
-            cwd: Some(self.project_path.clone()),
-            version: Some("2.0.55".to_string()), // 使用真实版本号，避免被识别为特殊模式
-            git_branch: None,
-            user_type: if role == "user" {
-                Some("external".to_string())
-            } else {
-                None
-            },
-            is_sidechain: Some(false),
-            subtype: None,
-            received_at: if role != "user" {
-                Some(timestamp.to_string())
-            } else {
-                None
-            },
-            sent_at: if role == "user" {
-                Some(timestamp.to_string())
-            } else {
-                None
-            },
-            model,
-            conversion_source: None,
-            extra: HashMap::new(),
-        }
-    }
-
-    pub fn convert(&self) -> Result<ConversionResult, String> {
-        log::info!(
-            "Converting Codex session {} to Claude",
-            self.source_session_id
-        );
-
-        // 1. 读取源 Codex session
-        let codex_events = self.read_codex_session()?;
-
-        // 2. 验证 session 已完成
-        self.validate_session_completed(&codex_events)?;
-
-        // 3. 转换事件为 Claude 消息
-        let mut claude_messages: Vec<ClaudeMessage> = Vec::new();
-
-        // 3a. 添加 file-history-snapshot 作为第一条消息（必需！）
-        let first_timestamp = codex_events
-            .first()
-            .and_then(|e| e.timestamp.clone())
-            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-        let snapshot_uuid = uuid::Uuid::new_v4().to_string();
-
-        claude_messages.push(ClaudeMessage {
-            message_type: "file-history-snapshot".to_string(),
-            message: None,
-            timestamp: Some(first_timestamp.clone()),
-            uuid: Some(snapshot_uuid.clone()),
-            parent_uuid: None,
-            session_id: None,
-            cwd: None,
-            version: None,
-            git_branch: None,
-            user_type: None,
-            is_sidechain: None,
-            subtype: None,
-            received_at: None,
-            sent_at: None,
-            model: None,
-            conversion_source: None,
-            extra: {
-                let mut map = HashMap::new();
-                map.insert(
-                    "messageId".to_string(),
-                    Value::String(snapshot_uuid.clone()),
-                );
-                map.insert(
-                    "snapshot".to_string(),
-                    serde_json::json!({
-                        "messageId": snapshot_uuid,
-                        "trackedFileBackups": {},
-                        "timestamp": first_timestamp
-                    }),
-                );
-                map.insert("isSnapshotUpdate".to_string(), Value::Bool(false));
-                map
-            },
-        });
-
-        // 3b. 转换 Codex 事件
-        for event in &codex_events {
-            if let Some(msg) = self.convert_codex_event(event) {
-                claude_messages.push(msg);
-            }
-        }
-
-        // 4. 写入目标文件
-        let target_path = self.write_claude_session(&claude_messages)?;
-
-        log::info!(
-            "Successfully converted {} events to Claude session {}",
-            claude_messages.len(),
-            self.new_session_id
-        );
-
-        Ok(ConversionResult {
-            success: true,
-            new_session_id: self.new_session_id.clone(),
-            target_engine: "claude".to_string(),
-            message_count: claude_messages.len(),
-            source: ConversionSource {
-                engine: "codex".to_string(),
-                session_id: self.source_session_id.clone(),
-                converted_at: chrono::Utc::now().to_rfc3339(),
-                source_project_path: self.project_path.clone(),
-            },
-            target_path,
-            error: None,
-        })
-    }
-
-    /// 读取 Codex session 文件
-    fn read_codex_session(&self) -> Result<Vec<CodexEvent>, String> {
-        let sessions_dir = super::config::get_codex_sessions_dir()
-            .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
-
-        // 使用 codex/session.rs 中的 find_session_file 函数
-        let session_path =
-            super::session::find_session_file(&sessions_dir, &self.source_session_id).ok_or_else(
-                || format!("Codex session file not found: {}", self.source_session_id),
-            )?;
-
-        let file = std::fs::File::open(&session_path)
-            .map_err(|e| format!("Failed to open session file: {}", e))?;
-
-        let reader = BufReader::new(file);
-        let mut events = Vec::new();
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            match serde_json::from_str::<CodexEvent>(&line) {
-                Ok(event) => events.push(event),
-                Err(e) => log::warn!("Failed to parse Codex event: {}", e),
-            }
-        }
-
-        if events.is_empty() {
-            return Err("Codex session is empty".to_string());
-        }
-
-        log::info!("Read {} events from Codex session", events.len());
-        Ok(events)
-    }
-
-    /// 验证 session 已完成
-    fn validate_session_completed(&self, _events: &[CodexEvent]) -> Result<(), String> {
-        // Codex session 的完成性检查可以更灵活
-        // 暂时只检查是否为空
-        Ok(())
-    }
-
-    /// 转换单个 Codex 事件为 Claude 消息
-    fn convert_codex_event(&self, event: &CodexEvent) -> Option<ClaudeMessage> {
-        let timestamp = event
-            .timestamp
-            .clone()
-            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-
-        match event.event_type.as_str() {
-            "session_meta" => self.convert_session_meta(event, &timestamp),
-            "response_item" => self.convert_response_item(event, &timestamp),
-            "event_msg" => self.convert_event_msg(event, &timestamp),
-            _ => None,
-        }
-    }
-
-    /// 转换 session_meta 事件
-    fn convert_session_meta(&self, event: &CodexEvent, timestamp: &str) -> Option<ClaudeMessage> {
-        let payload = event.payload.as_ref()?;
-        Some(ClaudeMessage {
-            message_type: "system".to_string(),
-            message: None,
-            timestamp: Some(timestamp.to_string()),
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
-            parent_uuid: None,
-            session_id: Some(self.new_session_id.clone()),
-            cwd: Some(self.project_path.clone()),
-            version: Some("2.0.55".to_string()), // 使用真实版本号
-            git_branch: payload
-                .get("git")
-                .and_then(|g| g.get("branch"))
-                .and_then(|b| b.as_str())
-                .map(String::from),
-            user_type: None,
-            is_sidechain: Some(false),
-            subtype: Some("init".to_string()),
-            received_at: Some(timestamp.to_string()),
-            sent_at: None,
-            model: payload
-                .get("model")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            conversion_source: Some(ConversionSource {
-                engine: "codex".to_string(),
-                session_id: self.source_session_id.clone(),
-                converted_at: chrono::Utc::now().to_rfc3339(),
-                source_project_path: self.project_path.clone(),
-            }),
-            extra: HashMap::new(),
-        })
-    }
-
-    /// 转换 response_item 事件
-    fn convert_response_item(&self, event: &CodexEvent, timestamp: &str) -> Option<ClaudeMessage> {
-        let payload = event.payload.as_ref()?;
-        let item_type = payload.get("type")?.as_str()?;
-        let role = payload
-            .get("role")
-            .and_then(|v| v.as_str())
-            .unwrap_or("assistant");
-
-        match item_type {
-            "message" => {
-                let content = payload.get("content")?.as_array()?;
-                let blocks: Vec<ClaudeContentBlock> = content
-                    .iter()
-                    .filter_map(|item| {
-                        let item_type = item.get("type")?.as_str()?;
-                        // Codex 使用 input_text 和 output_text
-                        if item_type == "text"
-                            || item_type == "input_text"
-                            || item_type == "output_text"
-                        {
-                            Some(ClaudeContentBlock::Text {
-                                text: item.get("text")?.as_str()?.to_string(),
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                if blocks.is_empty() {
-                    return None;
-                }
-
-                Some(self.create_claude_message(
-                    if role == "user" { "user" } else { "assistant" },
-                    role,
-                    blocks,
-                    timestamp,
-                    None,
-                ))
-            }
-            "function_call" => {
-                let name = payload.get("name")?.as_str()?;
-                let arguments = payload.get("arguments")?.as_str()?;
-                let call_id = payload.get("call_id")?.as_str()?;
-
-                let claude_tool_name = map_codex_to_claude_tool(name);
-                let input: Value = serde_json::from_str(arguments).unwrap_or(Value::Null);
-
-                Some(self.create_claude_message(
-                    "assistant",
-                    "assistant",
-                    vec![ClaudeContentBlock::ToolUse {
-                        id: call_id.to_string(),
-                        name: claude_tool_name,
-                        input,
-                    }],
-                    timestamp,
-                    None,
-                ))
-            }
-            "function_call_output" => {
-                let call_id = payload.get("call_id")?.as_str()?;
-                let output = payload.get("output").and_then(|v| v.as_str()).unwrap_or("");
-                let is_error = payload
-                    .get("is_error")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-
-                // tool_result 必须在 user 消息中！
-                Some(self.create_claude_message(
-                    "user", // 改为 user！
-                    "user", // 改为 user！
-                    vec![ClaudeContentBlock::ToolResult {
-                        tool_use_id: call_id.to_string(),
-                        content: Value::String(output.to_string()),
-                        is_error: Some(is_error),
-                    }],
-                    timestamp,
-                    None,
-                ))
-            }
-            _ => None,
-        }
-    }
-
-    /// 转换 event_msg 事件
-    fn convert_event_msg(&self, event: &CodexEvent, timestamp: &str) -> Option<ClaudeMessage> {
-        let payload = event.payload.as_ref()?;
-        let item = payload.get("item")?;
-        let item_type = item.get("type")?.as_str()?;
-
-        match item_type {
-            "reasoning" => {
-                let text = item.get("text")?.as_str()?;
-                Some(self.create_claude_message(
-                    "assistant",
-                    "assistant",
-                    vec![ClaudeContentBlock::Thinking {
-                        thinking: text.to_string(),
-                    }],
-                    timestamp,
-                    None,
-                ))
-            }
-            "agent_message" => {
-                let text = item.get("text")?.as_str()?;
-                Some(self.create_claude_message(
-                    "assistant",
-                    "assistant",
-                    vec![ClaudeContentBlock::Text {
-                        text: text.to_string(),
-                    }],
-                    timestamp,
-                    None,
-                ))
-            }
-            "todo_list" | "file_change" | "mcp_tool_call" => {
-                // 转换为 system 消息（使用标准数组格式）
-                Some(ClaudeMessage {
-                    message_type: "system".to_string(),
-                    message: Some(ClaudeMessageContent {
-                        role: "system".to_string(),
-                        content: Some(serde_json::json!([{
-                            "type": "text",
-                            "text": format!("[Codex {}]: {}", item_type, item.to_string())
-                        }])),
-                        usage: None,
-                    }),
-                    timestamp: Some(timestamp.to_string()),
-                    uuid: Some(uuid::Uuid::new_v4().to_string()),
-                    parent_uuid: None,
-                    session_id: Some(self.new_session_id.clone()),
-                    cwd: Some(self.project_path.clone()),
-                    version: Some("2.0.55".to_string()), // 使用真实版本号
-                    git_branch: None,
-                    user_type: None,
-                    is_sidechain: Some(false),
-                    subtype: Some(item_type.to_string()),
-                    received_at: Some(timestamp.to_string()),
-                    sent_at: None,
-                    model: None,
-                    conversion_source: None,
-                    extra: HashMap::new(),
-                })
-            }
-            "command_execution" => {
-                // command_execution 需要拆分为两条消息：
-                // 1. assistant: tool_use
-                // 2. user: tool_result
-                // 但这里只能返回一条，所以只返回 tool_use
-                // tool_result 会在下一个事件中处理
-                // 实际上 Codex 的 command_execution 是已完成的命令，
-                // 应该忽略，因为对应的 function_call 和 function_call_output 已经处理了
-                None
-            }
-            _ => None,
-        }
-    }
-
-    /// 写入 Claude session 文件
-    fn write_claude_session(&self, messages: &[ClaudeMessage]) -> Result<String, String> {
-        let claude_dir = super::super::claude::get_claude_dir()
-            .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
-
-        // 直接使用 project_id（实际的目录名）
-        let project_dir = claude_dir.join("projects").join(&self.project_id);
-
-        std::fs::create_dir_all(&project_dir)
-            .map_err(|e| format!("Failed to create project directory: {}", e))?;
-
-        let file_path = project_dir.join(format!("{}.jsonl", self.new_session_id));
-
-        let mut file = std::fs::File::create(&file_path)
-            .map_err(|e| format!("Failed to create session file: {}", e))?;
-
-        // 建立 parentUuid 消息链
-        let mut prev_uuid: Option<String> = None;
-        let mut linked_messages = messages.to_vec();
-
-        for msg in &mut linked_messages {
-            // 设置 parent_uuid 指向前一条消息
-            msg.parent_uuid = prev_uuid.clone();
-            // 更新 prev_uuid 为当前消息的 uuid
-            prev_uuid = msg.uuid.clone();
-        }
-
-        // 写入文件
-        for msg in &linked_messages {
-            let line = serde_json::to_string(msg)
-                .map_err(|e| format!("Failed to serialize message: {}", e))?;
-            writeln!(file, "{}", line).map_err(|e| format!("Failed to write message: {}", e))?;
-        }
-
-        Ok(file_path.to_string_lossy().to_string())
-    }
-}
-
 // ================================
 // Tauri Commands
 // ================================
 
 /// 根据文件存在性判断 session 的源引擎类型
-fn detect_session_engine(session_id: &str, project_id: &str) -> Result<String, String> {
+fn detect_session_engine(
+    session_id: &str,
+    project_id: &str,
+    project_path: &str,
+) -> Result<String, String> {
     // 1. 检查是否为 Codex session（查找 sessions 目录）
     if let Ok(sessions_dir) = super::config::get_codex_sessions_dir() {
         if super::session::find_session_file(&sessions_dir, session_id).is_some() {
@@ -1236,19 +392,76 @@ fn detect_session_engine(session_id: &str, project_id: &str) -> Result<String, S
         }
     }
 
+    // 3. 检查是否为 Gemini session（查找项目对应的 chats 目录）
+    if crate::commands::gemini::config::read_session_detail(project_path, session_id).is_ok() {
+        return Ok("gemini".to_string());
+    }
+
     Err(format!(
-        "Session {} not found in either Claude or Codex directories",
+        "Session {} not found in Claude, Codex or Gemini directories",
         session_id
     ))
 }
 
+/// 根据源/目标引擎执行预览
+fn preview_conversion(
+    session_id: String,
+    source_engine: &str,
+    target_engine: &str,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionPreview, String> {
+    if source_engine == target_engine {
+        return Err(format!(
+            "Session {} is already a {} session",
+            session_id, target_engine
+        ));
+    }
+
+    session_ir::preview_via_ir(
+        &session_id,
+        source_engine,
+        target_engine,
+        &project_id,
+        &project_path,
+    )
+}
+
+/// 预览 session 转换：在内存中执行转换并报告无损/有损/丢弃的条目数量与输出样例，不写入任何文件
+#[tauri::command]
+pub async fn preview_session_conversion(
+    session_id: String,
+    from: Option<String>,
+    to: String,
+    project_id: String,
+    project_path: String,
+) -> Result<ConversionPreview, String> {
+    let source_engine = match from {
+        Some(engine) => engine,
+        None => detect_session_engine(&session_id, &project_id, &project_path)?,
+    };
+
+    log::info!(
+        "Previewing conversion of session {} from {} to {}",
+        session_id,
+        source_engine,
+        to
+    );
+
+    preview_conversion(session_id, &source_engine, &to, project_id, project_path)
+}
+
 /// 统一转换接口
+///
+/// `confirm` 为 `Some(false)` 时只返回预览（`preview` 字段），不写入文件；
+/// 为 `None` 或 `Some(true)` 时直接执行转换
 #[tauri::command]
 pub async fn convert_session(
     session_id: String,
     target_engine: String,
     project_id: String,
     project_path: String,
+    confirm: Option<bool>,
 ) -> Result<ConversionResult, String> {
     log::info!(
         "Converting session {} to engine: {}, project_id: {}, project_path: {}",
@@ -1259,7 +472,7 @@ pub async fn convert_session(
     );
 
     // 根据文件存在性检测源引擎
-    let source_engine = detect_session_engine(&session_id, &project_id)?;
+    let source_engine = detect_session_engine(&session_id, &project_id, &project_path)?;
 
     if source_engine == target_engine {
         return Err(format!(
@@ -1268,17 +481,39 @@ pub async fn convert_session(
         ));
     }
 
-    match target_engine.as_str() {
-        "codex" => {
-            let converter = ClaudeToCodexConverter::new(session_id, project_id, project_path);
-            converter.convert()
-        }
-        "claude" => {
-            let converter = CodexToClaudeConverter::new(session_id, project_id, project_path);
-            converter.convert()
-        }
-        _ => Err(format!("Unknown target engine: {}", target_engine)),
+    if confirm == Some(false) {
+        let preview = preview_conversion(
+            session_id.clone(),
+            &source_engine,
+            &target_engine,
+            project_id,
+            project_path.clone(),
+        )?;
+
+        return Ok(ConversionResult {
+            success: false,
+            new_session_id: String::new(),
+            target_engine,
+            message_count: preview.output_count,
+            source: ConversionSource {
+                engine: source_engine,
+                session_id,
+                converted_at: chrono::Utc::now().to_rfc3339(),
+                source_project_path: project_path,
+            },
+            target_path: String::new(),
+            error: None,
+            preview: Some(preview),
+        });
     }
+
+    session_ir::convert_via_ir(
+        &session_id,
+        &source_engine,
+        &target_engine,
+        &project_id,
+        &project_path,
+    )
 }
 
 /// 便捷接口：Claude → Codex
@@ -1287,8 +522,16 @@ pub async fn convert_claude_to_codex(
     session_id: String,
     project_id: String,
     project_path: String,
+    confirm: Option<bool>,
 ) -> Result<ConversionResult, String> {
-    convert_session(session_id, "codex".to_string(), project_id, project_path).await
+    convert_session(
+        session_id,
+        "codex".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
 }
 
 /// 便捷接口：Codex → Claude
@@ -1297,6 +540,86 @@ pub async fn convert_codex_to_claude(
     session_id: String,
     project_id: String,
     project_path: String,
+    confirm: Option<bool>,
+) -> Result<ConversionResult, String> {
+    convert_session(
+        session_id,
+        "claude".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
+}
+
+/// 便捷接口：Claude → Gemini
+#[tauri::command]
+pub async fn convert_claude_to_gemini(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+    confirm: Option<bool>,
+) -> Result<ConversionResult, String> {
+    convert_session(
+        session_id,
+        "gemini".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
+}
+
+/// 便捷接口：Gemini → Claude
+#[tauri::command]
+pub async fn convert_gemini_to_claude(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+    confirm: Option<bool>,
+) -> Result<ConversionResult, String> {
+    convert_session(
+        session_id,
+        "claude".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
+}
+
+/// 便捷接口：Codex → Gemini
+#[tauri::command]
+pub async fn convert_codex_to_gemini(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+    confirm: Option<bool>,
+) -> Result<ConversionResult, String> {
+    convert_session(
+        session_id,
+        "gemini".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
+}
+
+/// 便捷接口：Gemini → Codex
+#[tauri::command]
+pub async fn convert_gemini_to_codex(
+    session_id: String,
+    project_id: String,
+    project_path: String,
+    confirm: Option<bool>,
 ) -> Result<ConversionResult, String> {
-    convert_session(session_id, "claude".to_string(), project_id, project_path).await
+    convert_session(
+        session_id,
+        "codex".to_string(),
+        project_id,
+        project_path,
+        confirm,
+    )
+    .await
 }
@@ -0,0 +1,1812 @@
+use once_cell::sync::Lazy;
+/**
+ * 跨引擎 Session 中间表示（IR）
+ *
+ * 所有引擎之间的转换统一经过 IR：源引擎 → IR → 目标引擎。
+ * 新增引擎时只需实现 `SessionAdapter`（读取为 IR、从 IR 渲染、写入文件），
+ * 并在 `adapter_for` 中注册。
+ *
+ * 约定：
+ * - IR 中的工具名称统一使用 Claude 工具名（bash / read / edit ...）
+ * - tool result 总是位于 user 角色的消息中，并通过 call_id 关联到 tool call
+ * - 目标格式没有对应结构的内容以纯文本注释保留，而不是直接丢弃
+ */
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+
+use super::session_converter::{
+    map_claude_to_codex_tool, map_codex_to_claude_tool, ClaudeMessage, ClaudeMessageContent,
+    CodexEvent, ConversionOutcome, ConversionPreview, ConversionResult, ConversionSource,
+    ConversionStats, CLAUDE_TO_CODEX_TOOL_MAP, CODEX_TO_CLAUDE_TOOL_MAP,
+};
+use crate::commands::gemini::config as gemini_config;
+
+// ================================
+// IR 数据结构
+// ================================
+
+/// IR 消息角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrRole {
+    User,
+    Assistant,
+    System,
+}
+
+/// IR 内容块
+#[derive(Debug, Clone)]
+pub enum IrBlock {
+    Text(String),
+    Thinking(String),
+    ToolCall {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        call_id: String,
+        output: String,
+        is_error: bool,
+    },
+    /// 源格式中没有通用对应结构的内容，以纯文本形式保留
+    Annotation(String),
+}
+
+/// IR 消息
+#[derive(Debug, Clone)]
+pub struct IrMessage {
+    pub role: IrRole,
+    pub blocks: Vec<IrBlock>,
+    pub timestamp: String,
+    /// 对应的源条目序号（用于预览统计）
+    pub source_index: usize,
+}
+
+/// IR Session
+#[derive(Debug, Clone)]
+pub struct IrSession {
+    pub engine: &'static str,
+    pub session_id: String,
+    pub project_path: String,
+    pub model: Option<String>,
+    pub messages: Vec<IrMessage>,
+}
+
+impl IrSession {
+    fn first_timestamp(&self) -> String {
+        self.messages
+            .first()
+            .map(|m| m.timestamp.clone())
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339())
+    }
+
+    fn last_timestamp(&self) -> String {
+        self.messages
+            .last()
+            .map(|m| m.timestamp.clone())
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339())
+    }
+
+    fn conversion_source(&self) -> ConversionSource {
+        ConversionSource {
+            engine: self.engine.to_string(),
+            session_id: self.session_id.clone(),
+            converted_at: chrono::Utc::now().to_rfc3339(),
+            source_project_path: self.project_path.clone(),
+        }
+    }
+}
+
+/// 按源条目记录转换结果；同一条目只保留最先出现的有损原因
+#[derive(Default)]
+pub struct IrTracker {
+    outcomes: Vec<ConversionOutcome>,
+}
+
+impl IrTracker {
+    /// 登记一条源条目，返回其序号
+    fn source(&mut self, outcome: ConversionOutcome) -> usize {
+        self.outcomes.push(outcome);
+        self.outcomes.len() - 1
+    }
+
+    /// 将源条目标记为有损（已丢弃或已有损的条目保持不变）
+    fn lossy(&mut self, index: usize, reason: impl Into<String>) {
+        if let Some(slot) = self.outcomes.get_mut(index) {
+            if matches!(slot, ConversionOutcome::Clean) {
+                *slot = ConversionOutcome::Lossy(reason.into());
+            }
+        }
+    }
+
+    fn into_stats(self) -> ConversionStats {
+        let mut stats = ConversionStats::default();
+        for outcome in self.outcomes {
+            stats.record(outcome);
+        }
+        stats
+    }
+}
+
+/// 从 IR 渲染出的目标 session（尚未写入）
+pub struct RenderedSession {
+    /// 返回给前端的新 Session ID
+    pub session_id: String,
+    /// 目标文件名
+    pub file_name: String,
+    /// 目标格式的条目（Claude/Codex 为 JSONL 行，Gemini 为 messages 数组元素）
+    pub entries: Vec<Value>,
+    pub source: ConversionSource,
+    pub start_time: String,
+    pub last_updated: String,
+}
+
+/// 引擎适配器：负责引擎格式与 IR 之间的双向映射
+pub trait SessionAdapter {
+    fn engine(&self) -> &'static str;
+
+    /// 读取源 session 并转换为 IR
+    fn read(&self, session_id: &str, tracker: &mut IrTracker) -> Result<IrSession, String>;
+
+    /// 将 IR 渲染为目标格式（不写入文件）
+    fn render(&self, session: &IrSession, tracker: &mut IrTracker) -> RenderedSession;
+
+    /// 写入目标 session 文件，返回文件路径
+    fn write(&self, rendered: &RenderedSession) -> Result<String, String>;
+}
+
+/// 根据引擎名称获取适配器
+pub fn adapter_for(
+    engine: &str,
+    project_id: &str,
+    project_path: &str,
+) -> Result<Box<dyn SessionAdapter>, String> {
+    match engine {
+        "claude" => Ok(Box::new(ClaudeAdapter {
+            project_id: project_id.to_string(),
+            project_path: project_path.to_string(),
+        })),
+        "codex" => Ok(Box::new(CodexAdapter {
+            project_path: project_path.to_string(),
+        })),
+        "gemini" => Ok(Box::new(GeminiAdapter {
+            project_path: project_path.to_string(),
+        })),
+        _ => Err(format!("Unknown engine: {}", engine)),
+    }
+}
+
+// ================================
+// 工具名称映射表（Gemini）
+// ================================
+
+/// Gemini → Claude 工具名称映射
+pub static GEMINI_TO_CLAUDE_TOOL_MAP: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+    m.insert("run_shell_command", "bash");
+    m.insert("read_file", "read");
+    m.insert("read_many_files", "read");
+    m.insert("write_file", "write");
+    m.insert("replace", "edit");
+    m.insert("glob", "glob");
+    m.insert("search_file_content", "grep");
+    m.insert("list_directory", "ls");
+    m.insert("web_fetch", "webfetch");
+    m.insert("google_web_search", "websearch");
+    m
+});
+
+/// Claude → Gemini 工具名称映射 (反向)
+pub static CLAUDE_TO_GEMINI_TOOL_MAP: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+    m.insert("bash", "run_shell_command");
+    m.insert("read", "read_file");
+    m.insert("write", "write_file");
+    m.insert("edit", "replace");
+    m.insert("multiedit", "replace");
+    m.insert("glob", "glob");
+    m.insert("grep", "search_file_content");
+    m.insert("ls", "list_directory");
+    m.insert("webfetch", "web_fetch");
+    m.insert("websearch", "google_web_search");
+    m
+});
+
+/// 映射 Gemini 工具名到 Claude 工具名
+/// MCP 工具 (mcp__ 前缀) 不进行映射
+pub fn map_gemini_to_claude_tool(gemini_name: &str) -> String {
+    if gemini_name.starts_with("mcp__") {
+        return gemini_name.to_string();
+    }
+    let lower = gemini_name.to_lowercase();
+    GEMINI_TO_CLAUDE_TOOL_MAP
+        .get(lower.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| gemini_name.to_string())
+}
+
+/// 映射 Claude 工具名到 Gemini 工具名
+/// MCP 工具 (mcp__ 前缀) 不进行映射
+pub fn map_claude_to_gemini_tool(claude_name: &str) -> String {
+    if claude_name.starts_with("mcp__") {
+        return claude_name.to_string();
+    }
+    let lower = claude_name.to_lowercase();
+    CLAUDE_TO_GEMINI_TOOL_MAP
+        .get(lower.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| claude_name.to_string())
+}
+
+/// 工具名在目标映射表中是否有对应项
+fn has_tool_mapping(map: &HashMap<&'static str, &'static str>, name: &str) -> bool {
+    name.starts_with("mcp__") || map.contains_key(name.to_lowercase().as_str())
+}
+
+// ================================
+// 通用辅助函数
+// ================================
+
+/// 将 tool result 等任意 JSON 内容展平为文本
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => {
+            let texts: Vec<&str> = items
+                .iter()
+                .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+                .collect();
+            if texts.len() == items.len() {
+                texts.join("\n")
+            } else {
+                value.to_string()
+            }
+        }
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// 注释文本：目标格式无法表达的 tool result
+fn tool_result_annotation(call_id: &str, output: &str, is_error: bool) -> String {
+    format!(
+        "[Tool result for {}{}]:\n{}",
+        call_id,
+        if is_error { " (error)" } else { "" },
+        output
+    )
+}
+
+/// 注释文本：目标格式无法表达的 tool call
+fn tool_call_annotation(name: &str, input: &Value) -> String {
+    format!("[Tool call {}]: {}", name, input)
+}
+
+/// 按 JSONL 逐行写入
+fn write_jsonl(path: &std::path::Path, entries: &[Value]) -> Result<(), String> {
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("Failed to create session file: {}", e))?;
+
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize entry: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write entry: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// ================================
+// Claude 适配器
+// ================================
+
+struct ClaudeAdapter {
+    project_id: String,   // 实际的目录名（如 C--Users-...）
+    project_path: String, // 原始项目路径
+}
+
+impl ClaudeAdapter {
+    /// 解析 Claude content 为 IR 内容块，返回 (内容块, 无法识别的块数量)
+    fn content_to_ir(content: &Option<Value>) -> (Vec<IrBlock>, usize) {
+        let mut blocks = Vec::new();
+        let mut skipped = 0;
+
+        match content {
+            Some(Value::String(text)) => blocks.push(IrBlock::Text(text.clone())),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let block = match item.get("type").and_then(|t| t.as_str()) {
+                        Some("text") => item
+                            .get("text")
+                            .and_then(|t| t.as_str())
+                            .map(|t| IrBlock::Text(t.to_string())),
+                        Some("thinking") => item
+                            .get("thinking")
+                            .and_then(|t| t.as_str())
+                            .map(|t| IrBlock::Thinking(t.to_string())),
+                        Some("tool_use") => match (
+                            item.get("id").and_then(|i| i.as_str()),
+                            item.get("name").and_then(|n| n.as_str()),
+                        ) {
+                            (Some(id), Some(name)) => Some(IrBlock::ToolCall {
+                                id: id.to_string(),
+                                name: name.to_string(),
+                                input: item.get("input").cloned().unwrap_or(Value::Null),
+                            }),
+                            _ => None,
+                        },
+                        Some("tool_result") => item
+                            .get("tool_use_id")
+                            .and_then(|t| t.as_str())
+                            .map(|id| IrBlock::ToolResult {
+                                call_id: id.to_string(),
+                                output: item.get("content").map(value_to_text).unwrap_or_default(),
+                                is_error: item
+                                    .get("is_error")
+                                    .and_then(|e| e.as_bool())
+                                    .unwrap_or(false),
+                            }),
+                        _ => None,
+                    };
+
+                    match block {
+                        Some(block) => blocks.push(block),
+                        None => skipped += 1,
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        (blocks, skipped)
+    }
+
+    /// 创建 Claude 消息条目
+    fn entry(
+        &self,
+        session_id: &str,
+        message_type: &str,
+        content: Option<Value>,
+        timestamp: &str,
+        model: Option<String>,
+    ) -> ClaudeMessage {
+        let is_user = message_type == "user";
+        ClaudeMessage {
+            parent_uuid: None,
+            is_sidechain: Some(false),
+            user_type: if is_user {
+                Some("external".to_string())
+            } else {
+                None
+            },
+            cwd: Some(self.project_path.clone()),
+            session_id: Some(session_id.to_string()),
+            version: Some("2.0.55".to_string()), // 使用真实版本号，避免被识别为特殊模式
+            git_branch: None,
+            message_type: message_type.to_string(),
+            message: content.map(|content| ClaudeMessageContent {
+                role: message_type.to_string(),
+                content: Some(content),
+                usage: None,
+            }),
+            uuid: Some(uuid::Uuid::new_v4().to_string()),
+            timestamp: Some(timestamp.to_string()),
+            subtype: None,
+            received_at: if is_user {
+                None
+            } else {
+                Some(timestamp.to_string())
+            },
+            sent_at: if is_user {
+                Some(timestamp.to_string())
+            } else {
+                None
+            },
+            model,
+            conversion_source: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+impl SessionAdapter for ClaudeAdapter {
+    fn engine(&self) -> &'static str {
+        "claude"
+    }
+
+    fn read(&self, session_id: &str, tracker: &mut IrTracker) -> Result<IrSession, String> {
+        let claude_dir = super::super::claude::get_claude_dir()
+            .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
+
+        let session_path = claude_dir
+            .join("projects")
+            .join(&self.project_id)
+            .join(format!("{}.jsonl", session_id));
+
+        if !session_path.exists() {
+            return Err(format!(
+                "Claude session file not found: {}",
+                session_path.display()
+            ));
+        }
+
+        let file = std::fs::File::open(&session_path)
+            .map_err(|e| format!("Failed to open session file: {}", e))?;
+
+        let mut raw_messages = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<ClaudeMessage>(&line) {
+                Ok(msg) => raw_messages.push(msg),
+                Err(e) => log::warn!("Failed to parse Claude message: {}", e),
+            }
+        }
+
+        match raw_messages.last() {
+            None => return Err("Claude session is empty".to_string()),
+            Some(last) if last.message_type == "user" => {
+                return Err("Session appears incomplete (ends with user message)".to_string());
+            }
+            _ => {}
+        }
+
+        let mut messages = Vec::new();
+        for msg in &raw_messages {
+            let role = match msg.message_type.as_str() {
+                "user" => IrRole::User,
+                "assistant" => IrRole::Assistant,
+                other => {
+                    tracker.source(ConversionOutcome::Dropped(format!(
+                        "'{}' entries are not converted",
+                        other
+                    )));
+                    continue;
+                }
+            };
+
+            let Some(ref message) = msg.message else {
+                tracker.source(ConversionOutcome::Dropped(format!(
+                    "{} entry without message body",
+                    msg.message_type
+                )));
+                continue;
+            };
+
+            let (blocks, skipped) = Self::content_to_ir(&message.content);
+            if blocks.is_empty() {
+                tracker.source(ConversionOutcome::Dropped(format!(
+                    "{} message has no convertible content",
+                    msg.message_type
+                )));
+                continue;
+            }
+
+            let index = tracker.source(ConversionOutcome::Clean);
+            if skipped > 0 {
+                tracker.lossy(
+                    index,
+                    format!(
+                        "{} message contains unsupported content blocks (e.g. images)",
+                        msg.message_type
+                    ),
+                );
+            }
+
+            messages.push(IrMessage {
+                role,
+                blocks,
+                timestamp: msg
+                    .timestamp
+                    .clone()
+                    .or_else(|| msg.sent_at.clone())
+                    .or_else(|| msg.received_at.clone())
+                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+                source_index: index,
+            });
+        }
+
+        log::info!(
+            "Read {} IR messages from Claude session {}",
+            messages.len(),
+            session_id
+        );
+
+        Ok(IrSession {
+            engine: "claude",
+            session_id: session_id.to_string(),
+            project_path: self.project_path.clone(),
+            model: raw_messages.iter().find_map(|m| m.model.clone()),
+            messages,
+        })
+    }
+
+    fn render(&self, session: &IrSession, _tracker: &mut IrTracker) -> RenderedSession {
+        let new_session_id = uuid::Uuid::new_v4().to_string();
+        let source = session.conversion_source();
+        let start_time = session.first_timestamp();
+        let mut claude_messages = Vec::new();
+
+        // file-history-snapshot 必须是第一条消息
+        let snapshot_uuid = uuid::Uuid::new_v4().to_string();
+        let mut snapshot = self.entry(
+            &new_session_id,
+            "file-history-snapshot",
+            None,
+            &start_time,
+            None,
+        );
+        snapshot.uuid = Some(snapshot_uuid.clone());
+        snapshot.session_id = None;
+        snapshot.cwd = None;
+        snapshot.version = None;
+        snapshot.is_sidechain = None;
+        snapshot.received_at = None;
+        snapshot.extra.insert(
+            "messageId".to_string(),
+            Value::String(snapshot_uuid.clone()),
+        );
+        snapshot.extra.insert(
+            "snapshot".to_string(),
+            serde_json::json!({
+                "messageId": snapshot_uuid,
+                "trackedFileBackups": {},
+                "timestamp": start_time
+            }),
+        );
+        snapshot
+            .extra
+            .insert("isSnapshotUpdate".to_string(), Value::Bool(false));
+        claude_messages.push(snapshot);
+
+        // 记录转换来源的 init 消息
+        let mut init = self.entry(
+            &new_session_id,
+            "system",
+            None,
+            &start_time,
+            session.model.clone(),
+        );
+        init.subtype = Some("init".to_string());
+        init.conversion_source = Some(source.clone());
+        claude_messages.push(init);
+
+        for msg in &session.messages {
+            let message_type = match msg.role {
+                IrRole::User => "user",
+                IrRole::Assistant => "assistant",
+                IrRole::System => "system",
+            };
+
+            let content: Vec<Value> = msg
+                .blocks
+                .iter()
+                .map(|block| match block {
+                    IrBlock::Text(text) | IrBlock::Annotation(text) => {
+                        serde_json::json!({"type": "text", "text": text})
+                    }
+                    IrBlock::Thinking(thinking) => {
+                        serde_json::json!({"type": "thinking", "thinking": thinking})
+                    }
+                    IrBlock::ToolCall { id, name, input } => serde_json::json!({
+                        "type": "tool_use",
+                        "id": id,
+                        "name": name,
+                        "input": input
+                    }),
+                    IrBlock::ToolResult {
+                        call_id,
+                        output,
+                        is_error,
+                    } => serde_json::json!({
+                        "type": "tool_result",
+                        "tool_use_id": call_id,
+                        "content": output,
+                        "is_error": is_error
+                    }),
+                })
+                .collect();
+
+            claude_messages.push(self.entry(
+                &new_session_id,
+                message_type,
+                Some(Value::Array(content)),
+                &msg.timestamp,
+                None,
+            ));
+        }
+
+        // 建立 parentUuid 消息链
+        let mut prev_uuid: Option<String> = None;
+        for msg in &mut claude_messages {
+            msg.parent_uuid = prev_uuid.clone();
+            prev_uuid = msg.uuid.clone();
+        }
+
+        RenderedSession {
+            file_name: format!("{}.jsonl", new_session_id),
+            session_id: new_session_id,
+            entries: claude_messages
+                .iter()
+                .filter_map(|m| serde_json::to_value(m).ok())
+                .collect(),
+            source,
+            start_time,
+            last_updated: session.last_timestamp(),
+        }
+    }
+
+    fn write(&self, rendered: &RenderedSession) -> Result<String, String> {
+        let claude_dir = super::super::claude::get_claude_dir()
+            .map_err(|e| format!("Failed to get Claude directory: {}", e))?;
+
+        let project_dir = claude_dir.join("projects").join(&self.project_id);
+        std::fs::create_dir_all(&project_dir)
+            .map_err(|e| format!("Failed to create project directory: {}", e))?;
+
+        let file_path = project_dir.join(&rendered.file_name);
+        write_jsonl(&file_path, &rendered.entries)?;
+
+        Ok(file_path.to_string_lossy().to_string())
+    }
+}
+
+// ================================
+// Codex 适配器
+// ================================
+
+struct CodexAdapter {
+    project_path: String,
+}
+
+impl CodexAdapter {
+    fn event(event_type: &str, timestamp: &str, payload: Value) -> Value {
+        serde_json::to_value(CodexEvent {
+            event_type: event_type.to_string(),
+            timestamp: Some(timestamp.to_string()),
+            payload: Some(payload),
+            thread_id: None,
+            usage: None,
+        })
+        .unwrap_or(Value::Null)
+    }
+
+    fn message(role: &str, text_type: &str, timestamp: &str, texts: &[String]) -> Value {
+        let content: Vec<Value> = texts
+            .iter()
+            .map(|text| serde_json::json!({"type": text_type, "text": text}))
+            .collect();
+        Self::event(
+            "response_item",
+            timestamp,
+            serde_json::json!({"type": "message", "role": role, "content": content}),
+        )
+    }
+
+    /// 将单个 Codex 事件转换为 IR 消息
+    fn event_to_ir(event: &CodexEvent, index: usize, tracker: &mut IrTracker) -> Option<IrMessage> {
+        let payload = event.payload.as_ref()?;
+        let timestamp = event
+            .timestamp
+            .clone()
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+        let message = |role: IrRole, blocks: Vec<IrBlock>| IrMessage {
+            role,
+            blocks,
+            timestamp: timestamp.clone(),
+            source_index: index,
+        };
+
+        match event.event_type.as_str() {
+            "response_item" => match payload.get("type").and_then(|t| t.as_str())? {
+                "message" => {
+                    let items = payload.get("content")?.as_array()?;
+                    let blocks: Vec<IrBlock> = items
+                        .iter()
+                        .filter_map(|item| match item.get("type").and_then(|t| t.as_str()) {
+                            Some("text") | Some("input_text") | Some("output_text") => item
+                                .get("text")
+                                .and_then(|t| t.as_str())
+                                .map(|t| IrBlock::Text(t.to_string())),
+                            _ => None,
+                        })
+                        .collect();
+                    if blocks.len() < items.len() {
+                        tracker.lossy(index, "message contains non-text content (e.g. images)");
+                    }
+                    if blocks.is_empty() {
+                        return None;
+                    }
+
+                    let role = match payload.get("role").and_then(|r| r.as_str()) {
+                        Some("user") => IrRole::User,
+                        Some("assistant") | None => IrRole::Assistant,
+                        Some(_) => IrRole::System,
+                    };
+                    Some(message(role, blocks))
+                }
+                "function_call" | "custom_tool_call" => {
+                    let name = payload.get("name")?.as_str()?;
+                    let call_id = payload.get("call_id")?.as_str()?;
+                    let input = match payload.get("arguments").or_else(|| payload.get("input")) {
+                        Some(Value::String(raw)) => {
+                            serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()))
+                        }
+                        Some(other) => other.clone(),
+                        None => Value::Null,
+                    };
+
+                    if !has_tool_mapping(&CODEX_TO_CLAUDE_TOOL_MAP, name) {
+                        tracker.lossy(
+                            index,
+                            format!("tool '{}' has no common equivalent (name kept as-is)", name),
+                        );
+                    }
+
+                    Some(message(
+                        IrRole::Assistant,
+                        vec![IrBlock::ToolCall {
+                            id: call_id.to_string(),
+                            name: map_codex_to_claude_tool(name),
+                            input,
+                        }],
+                    ))
+                }
+                "function_call_output" | "custom_tool_call_output" => {
+                    let call_id = payload.get("call_id")?.as_str()?;
+                    Some(message(
+                        IrRole::User,
+                        vec![IrBlock::ToolResult {
+                            call_id: call_id.to_string(),
+                            output: payload.get("output").map(value_to_text).unwrap_or_default(),
+                            is_error: payload
+                                .get("is_error")
+                                .and_then(|e| e.as_bool())
+                                .unwrap_or(false),
+                        }],
+                    ))
+                }
+                "reasoning" => {
+                    let text: Vec<&str> = payload
+                        .get("summary")?
+                        .as_array()?
+                        .iter()
+                        .filter_map(|s| s.get("text").and_then(|t| t.as_str()))
+                        .collect();
+                    if text.is_empty() {
+                        return None;
+                    }
+                    Some(message(
+                        IrRole::Assistant,
+                        vec![IrBlock::Thinking(text.join("\n\n"))],
+                    ))
+                }
+                _ => None,
+            },
+            "event_msg" => {
+                let item = payload.get("item")?;
+                let item_type = item.get("type")?.as_str()?;
+                match item_type {
+                    "reasoning" => Some(message(
+                        IrRole::Assistant,
+                        vec![IrBlock::Thinking(item.get("text")?.as_str()?.to_string())],
+                    )),
+                    "agent_message" => Some(message(
+                        IrRole::Assistant,
+                        vec![IrBlock::Text(item.get("text")?.as_str()?.to_string())],
+                    )),
+                    "todo_list" | "file_change" | "mcp_tool_call" => {
+                        tracker.lossy(
+                            index,
+                            format!(
+                                "'{}' items are converted to plain-text annotations",
+                                item_type
+                            ),
+                        );
+                        Some(message(
+                            IrRole::System,
+                            vec![IrBlock::Annotation(format!(
+                                "[Codex {}]: {}",
+                                item_type, item
+                            ))],
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl SessionAdapter for CodexAdapter {
+    fn engine(&self) -> &'static str {
+        "codex"
+    }
+
+    fn read(&self, session_id: &str, tracker: &mut IrTracker) -> Result<IrSession, String> {
+        let sessions_dir = super::config::get_codex_sessions_dir()
+            .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
+
+        let session_path = super::session::find_session_file(&sessions_dir, session_id)
+            .ok_or_else(|| format!("Codex session file not found: {}", session_id))?;
+
+        let file = std::fs::File::open(&session_path)
+            .map_err(|e| format!("Failed to open session file: {}", e))?;
+
+        let mut events = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<CodexEvent>(&line) {
+                Ok(event) => events.push(event),
+                Err(e) => log::warn!("Failed to parse Codex event: {}", e),
+            }
+        }
+
+        if events.is_empty() {
+            return Err("Codex session is empty".to_string());
+        }
+
+        let mut model = None;
+        let mut messages = Vec::new();
+        for event in &events {
+            // session_meta 只提供元数据（模型等），不产生消息
+            if event.event_type == "session_meta" {
+                model = event
+                    .payload
+                    .as_ref()
+                    .and_then(|p| p.get("model"))
+                    .and_then(|m| m.as_str())
+                    .map(String::from);
+                tracker.source(ConversionOutcome::Clean);
+                continue;
+            }
+
+            let index = tracker.source(ConversionOutcome::Clean);
+            match Self::event_to_ir(event, index, tracker) {
+                Some(msg) => messages.push(msg),
+                None => {
+                    let kind = event
+                        .payload
+                        .as_ref()
+                        .and_then(|p| {
+                            p.get("type")
+                                .or_else(|| p.get("item").and_then(|i| i.get("type")))
+                        })
+                        .and_then(|t| t.as_str())
+                        .map(|t| format!("{}/{}", event.event_type, t))
+                        .unwrap_or_else(|| event.event_type.clone());
+                    tracker.outcomes[index] =
+                        ConversionOutcome::Dropped(format!("'{}' events are not converted", kind));
+                }
+            }
+        }
+
+        log::info!(
+            "Read {} IR messages from Codex session {}",
+            messages.len(),
+            session_id
+        );
+
+        Ok(IrSession {
+            engine: "codex",
+            session_id: session_id.to_string(),
+            project_path: self.project_path.clone(),
+            model,
+            messages,
+        })
+    }
+
+    fn render(&self, session: &IrSession, tracker: &mut IrTracker) -> RenderedSession {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now();
+        // 文件名带时间戳：rollout-2025-12-01T09-26-15-{uuid}
+        let new_session_filename = format!("rollout-{}-{}", now.format("%Y-%m-%dT%H-%M-%S"), uuid);
+        let source = session.conversion_source();
+        let start_time = session.first_timestamp();
+
+        let mut events = vec![Self::event(
+            "session_meta",
+            &start_time,
+            serde_json::json!({
+                "id": uuid, // 使用纯 UUID（不带 rollout- 前缀）
+                "timestamp": start_time,
+                "cwd": self.project_path,
+                "originator": "session_converter",
+                "cli_version": "converted",
+                "source": "conversion",
+                "model_provider": session.model.as_ref().map(|_| "converted").unwrap_or("unknown"),
+                "conversion_source": {
+                    "engine": source.engine,
+                    "session_id": source.session_id,
+                    "converted_at": source.converted_at,
+                    "source_project_path": source.source_project_path
+                }
+            }),
+        )];
+
+        for msg in &session.messages {
+            let ts = msg.timestamp.as_str();
+            let (role, text_type) = match msg.role {
+                IrRole::User => ("user", "input_text"),
+                IrRole::Assistant => ("assistant", "output_text"),
+                IrRole::System => {
+                    // Codex 没有 system 消息，降级为 assistant 文本注释
+                    tracker.lossy(
+                        msg.source_index,
+                        "system messages are kept as plain-text annotations",
+                    );
+                    ("assistant", "output_text")
+                }
+            };
+
+            // 连续的文本块合并为一条 message，遇到其他块时先输出，保持原有顺序
+            let mut texts: Vec<String> = Vec::new();
+            for block in &msg.blocks {
+                if !matches!(block, IrBlock::Text(_) | IrBlock::Annotation(_)) && !texts.is_empty()
+                {
+                    events.push(Self::message(role, text_type, ts, &texts));
+                    texts.clear();
+                }
+                match block {
+                    IrBlock::Text(text) | IrBlock::Annotation(text) => texts.push(text.clone()),
+                    IrBlock::Thinking(thinking) => {
+                        events.push(Self::event(
+                            "event_msg",
+                            ts,
+                            serde_json::json!({
+                                "item": {
+                                    "id": format!("reasoning_{}", uuid::Uuid::new_v4()),
+                                    "type": "reasoning",
+                                    "text": thinking
+                                },
+                                "phase": "completed"
+                            }),
+                        ));
+                    }
+                    IrBlock::ToolCall { id, name, input } => {
+                        if !has_tool_mapping(&CLAUDE_TO_CODEX_TOOL_MAP, name) {
+                            tracker.lossy(
+                                msg.source_index,
+                                format!(
+                                    "tool '{}' has no Codex equivalent (name kept as-is)",
+                                    name
+                                ),
+                            );
+                        }
+                        events.push(Self::event(
+                            "response_item",
+                            ts,
+                            serde_json::json!({
+                                "type": "function_call",
+                                "name": map_claude_to_codex_tool(name),
+                                "arguments": serde_json::to_string(input).unwrap_or_default(),
+                                "call_id": id,
+                                "timestamp": ts
+                            }),
+                        ));
+                    }
+                    IrBlock::ToolResult {
+                        call_id,
+                        output,
+                        is_error,
+                    } => {
+                        events.push(Self::event(
+                            "response_item",
+                            ts,
+                            serde_json::json!({
+                                "type": "function_call_output",
+                                "call_id": call_id,
+                                "output": output,
+                                "is_error": is_error,
+                                "timestamp": ts
+                            }),
+                        ));
+                    }
+                }
+            }
+
+            if !texts.is_empty() {
+                events.push(Self::message(role, text_type, ts, &texts));
+            }
+        }
+
+        RenderedSession {
+            file_name: format!("{}.jsonl", new_session_filename),
+            session_id: new_session_filename, // 返回文件名（带 rollout- 前缀）
+            entries: events,
+            source,
+            start_time,
+            last_updated: session.last_timestamp(),
+        }
+    }
+
+    fn write(&self, rendered: &RenderedSession) -> Result<String, String> {
+        let sessions_dir = super::config::get_codex_sessions_dir()
+            .map_err(|e| format!("Failed to get Codex sessions directory: {}", e))?;
+
+        // 创建日期目录结构 YYYY/MM/DD
+        let now = chrono::Utc::now();
+        let date_dir = sessions_dir
+            .join(now.format("%Y").to_string())
+            .join(now.format("%m").to_string())
+            .join(now.format("%d").to_string());
+
+        std::fs::create_dir_all(&date_dir)
+            .map_err(|e| format!("Failed to create date directory: {}", e))?;
+
+        let file_path = date_dir.join(&rendered.file_name);
+        write_jsonl(&file_path, &rendered.entries)?;
+
+        Ok(file_path.to_string_lossy().to_string())
+    }
+}
+
+// ================================
+// Gemini 适配器
+// ================================
+
+struct GeminiAdapter {
+    project_path: String,
+}
+
+impl GeminiAdapter {
+    /// 提取 Gemini 消息 content 中的文本，返回 (文本, 是否包含非文本部分)
+    fn content_text(content: Option<&Value>) -> (String, bool) {
+        match content {
+            Some(Value::String(text)) => (text.clone(), false),
+            Some(Value::Array(parts)) => {
+                let texts: Vec<&str> = parts
+                    .iter()
+                    .filter_map(|p| {
+                        p.as_str()
+                            .or_else(|| p.get("text").and_then(|t| t.as_str()))
+                    })
+                    .collect();
+                (texts.join("\n"), texts.len() < parts.len())
+            }
+            Some(Value::Object(part)) => match part.get("text").and_then(|t| t.as_str()) {
+                Some(text) => (text.to_string(), false),
+                None => (String::new(), true),
+            },
+            _ => (String::new(), false),
+        }
+    }
+
+    /// 提取工具调用结果文本（优先 resultDisplay，其次 functionResponse）
+    fn tool_output(call: &Value) -> Option<String> {
+        if let Some(display) = call.get("resultDisplay").and_then(|d| d.as_str()) {
+            return Some(display.to_string());
+        }
+
+        let result = call.get("result")?;
+        let responses: Vec<String> = result
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p.get("functionResponse").and_then(|f| f.get("response")))
+                    .map(|r| {
+                        r.get("output")
+                            .or_else(|| r.get("error"))
+                            .map(value_to_text)
+                            .unwrap_or_else(|| r.to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if responses.is_empty() {
+            Some(value_to_text(result))
+        } else {
+            Some(responses.join("\n"))
+        }
+    }
+
+    /// 将单条 Gemini 消息转换为 IR 消息（工具结果单独拆分为 user 消息）
+    fn message_to_ir(
+        value: &Value,
+        index: usize,
+        tracker: &mut IrTracker,
+    ) -> Option<Vec<IrMessage>> {
+        let message_type = value.get("type")?.as_str()?;
+        let timestamp = value
+            .get("timestamp")
+            .and_then(|t| t.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+        let (text, has_non_text) = Self::content_text(value.get("content"));
+        if has_non_text {
+            tracker.lossy(index, "message contains non-text content (e.g. images)");
+        }
+        let message = |role: IrRole, blocks: Vec<IrBlock>| IrMessage {
+            role,
+            blocks,
+            timestamp: timestamp.clone(),
+            source_index: index,
+        };
+
+        match message_type {
+            "user" => {
+                if text.is_empty() {
+                    return None;
+                }
+                Some(vec![message(IrRole::User, vec![IrBlock::Text(text)])])
+            }
+            "gemini" => {
+                let mut blocks = Vec::new();
+                let mut results = Vec::new();
+
+                for thought in value
+                    .get("thoughts")
+                    .and_then(|t| t.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let subject = thought
+                        .get("subject")
+                        .and_then(|s| s.as_str())
+                        .unwrap_or("");
+                    let description = thought
+                        .get("description")
+                        .and_then(|d| d.as_str())
+                        .unwrap_or("");
+                    let thinking = match (subject.is_empty(), description.is_empty()) {
+                        (true, true) => continue,
+                        (false, false) => format!("**{}**\n{}", subject, description),
+                        (true, false) => description.to_string(),
+                        (false, true) => subject.to_string(),
+                    };
+                    blocks.push(IrBlock::Thinking(thinking));
+                }
+
+                if !text.is_empty() {
+                    blocks.push(IrBlock::Text(text));
+                }
+
+                for call in value
+                    .get("toolCalls")
+                    .and_then(|t| t.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let Some(name) = call.get("name").and_then(|n| n.as_str()) else {
+                        continue;
+                    };
+                    let id = call
+                        .get("id")
+                        .and_then(|i| i.as_str())
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4()));
+
+                    if !has_tool_mapping(&GEMINI_TO_CLAUDE_TOOL_MAP, name) {
+                        tracker.lossy(
+                            index,
+                            format!("tool '{}' has no common equivalent (name kept as-is)", name),
+                        );
+                    }
+
+                    blocks.push(IrBlock::ToolCall {
+                        id: id.clone(),
+                        name: map_gemini_to_claude_tool(name),
+                        input: call.get("args").cloned().unwrap_or(Value::Null),
+                    });
+
+                    if let Some(output) = Self::tool_output(call) {
+                        results.push(IrBlock::ToolResult {
+                            call_id: id,
+                            output,
+                            is_error: matches!(
+                                call.get("status").and_then(|s| s.as_str()),
+                                Some("error") | Some("cancelled")
+                            ),
+                        });
+                    }
+                }
+
+                if blocks.is_empty() {
+                    return None;
+                }
+
+                let mut messages = vec![message(IrRole::Assistant, blocks)];
+                if !results.is_empty() {
+                    messages.push(message(IrRole::User, results));
+                }
+                Some(messages)
+            }
+            "info" | "error" | "warning" => {
+                if text.is_empty() {
+                    return None;
+                }
+                Some(vec![message(
+                    IrRole::System,
+                    vec![IrBlock::Annotation(format!(
+                        "[Gemini {}]: {}",
+                        message_type, text
+                    ))],
+                )])
+            }
+            _ => None,
+        }
+    }
+}
+
+impl SessionAdapter for GeminiAdapter {
+    fn engine(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn read(&self, session_id: &str, tracker: &mut IrTracker) -> Result<IrSession, String> {
+        let detail = gemini_config::read_session_detail(&self.project_path, session_id)?;
+
+        if detail.messages.is_empty() {
+            return Err("Gemini session is empty".to_string());
+        }
+
+        let model = detail
+            .messages
+            .iter()
+            .find_map(|m| m.get("model").and_then(|v| v.as_str()).map(String::from));
+
+        let mut messages = Vec::new();
+        for value in &detail.messages {
+            let index = tracker.source(ConversionOutcome::Clean);
+            match Self::message_to_ir(value, index, tracker) {
+                Some(converted) => messages.extend(converted),
+                None => {
+                    let kind = value
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or("unknown");
+                    tracker.outcomes[index] = ConversionOutcome::Dropped(format!(
+                        "'{}' messages without convertible content are not converted",
+                        kind
+                    ));
+                }
+            }
+        }
+
+        log::info!(
+            "Read {} IR messages from Gemini session {}",
+            messages.len(),
+            session_id
+        );
+
+        Ok(IrSession {
+            engine: "gemini",
+            session_id: detail.session_id,
+            project_path: self.project_path.clone(),
+            model,
+            messages,
+        })
+    }
+
+    fn render(&self, session: &IrSession, tracker: &mut IrTracker) -> RenderedSession {
+        let new_session_id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now();
+        let mut out: Vec<Value> = Vec::new();
+        // call_id → (消息下标, toolCalls 下标)，用于将 tool result 挂接到对应调用
+        let mut call_slots: HashMap<String, (usize, usize)> = HashMap::new();
+        // Gemini 每轮模型响应只有一条消息：合并连续的 assistant 消息
+        let mut merge_into: Option<usize> = None;
+
+        for msg in &session.messages {
+            let ts = msg.timestamp.as_str();
+            match msg.role {
+                IrRole::User => {
+                    merge_into = None;
+                    let mut texts = Vec::new();
+                    for block in &msg.blocks {
+                        match block {
+                            IrBlock::Text(text) | IrBlock::Annotation(text) => {
+                                texts.push(text.clone())
+                            }
+                            IrBlock::ToolResult {
+                                call_id,
+                                output,
+                                is_error,
+                            } => {
+                                if let Some(&(mi, ci)) = call_slots.get(call_id) {
+                                    let call = &mut out[mi]["toolCalls"][ci];
+                                    let name = call["name"].clone();
+                                    let response_key = if *is_error { "error" } else { "output" };
+                                    call["result"] = serde_json::json!([{
+                                        "functionResponse": {
+                                            "id": call_id,
+                                            "name": name,
+                                            "response": { response_key: output }
+                                        }
+                                    }]);
+                                    call["resultDisplay"] = Value::String(output.clone());
+                                    call["status"] = Value::String(
+                                        (if *is_error { "error" } else { "success" }).to_string(),
+                                    );
+                                } else {
+                                    tracker.lossy(
+                                        msg.source_index,
+                                        "tool results without a matching Gemini tool call are kept as plain text",
+                                    );
+                                    texts.push(tool_result_annotation(call_id, output, *is_error));
+                                }
+                            }
+                            IrBlock::Thinking(text) => {
+                                tracker.lossy(
+                                    msg.source_index,
+                                    "user thinking blocks are kept as plain text",
+                                );
+                                texts.push(text.clone());
+                            }
+                            IrBlock::ToolCall { name, input, .. } => {
+                                tracker.lossy(
+                                    msg.source_index,
+                                    "tool calls in user messages are kept as plain text",
+                                );
+                                texts.push(tool_call_annotation(name, input));
+                            }
+                        }
+                    }
+
+                    if !texts.is_empty() {
+                        out.push(serde_json::json!({
+                            "id": uuid::Uuid::new_v4().to_string(),
+                            "timestamp": ts,
+                            "type": "user",
+                            "content": texts.join("\n\n")
+                        }));
+                    }
+                }
+                IrRole::Assistant => {
+                    let target = match merge_into {
+                        Some(target) => target,
+                        None => {
+                            out.push(serde_json::json!({
+                                "id": uuid::Uuid::new_v4().to_string(),
+                                "timestamp": ts,
+                                "type": "gemini",
+                                "content": "",
+                                "thoughts": [],
+                                "toolCalls": [],
+                                "model": session.model
+                            }));
+                            out.len() - 1
+                        }
+                    };
+                    merge_into = Some(target);
+
+                    for block in &msg.blocks {
+                        match block {
+                            IrBlock::Text(text) | IrBlock::Annotation(text) => {
+                                let entry = &mut out[target]["content"];
+                                let merged = match entry.as_str() {
+                                    Some(existing) if !existing.is_empty() => {
+                                        format!("{}\n\n{}", existing, text)
+                                    }
+                                    _ => text.clone(),
+                                };
+                                *entry = Value::String(merged);
+                            }
+                            IrBlock::Thinking(thinking) => {
+                                if let Some(thoughts) = out[target]["thoughts"].as_array_mut() {
+                                    thoughts.push(serde_json::json!({
+                                        "subject": "",
+                                        "description": thinking,
+                                        "timestamp": ts
+                                    }));
+                                }
+                            }
+                            IrBlock::ToolCall { id, name, input } => {
+                                if !has_tool_mapping(&CLAUDE_TO_GEMINI_TOOL_MAP, name) {
+                                    tracker.lossy(
+                                        msg.source_index,
+                                        format!(
+                                            "tool '{}' has no Gemini equivalent (name kept as-is)",
+                                            name
+                                        ),
+                                    );
+                                }
+                                if let Some(calls) = out[target]["toolCalls"].as_array_mut() {
+                                    calls.push(serde_json::json!({
+                                        "id": id,
+                                        "name": map_claude_to_gemini_tool(name),
+                                        "args": input,
+                                        "status": "success",
+                                        "timestamp": ts
+                                    }));
+                                    call_slots.insert(id.clone(), (target, calls.len() - 1));
+                                }
+                            }
+                            IrBlock::ToolResult {
+                                call_id,
+                                output,
+                                is_error,
+                            } => {
+                                tracker.lossy(
+                                    msg.source_index,
+                                    "tool results without a matching Gemini tool call are kept as plain text",
+                                );
+                                let entry = &mut out[target]["content"];
+                                let annotation = tool_result_annotation(call_id, output, *is_error);
+                                let merged = match entry.as_str() {
+                                    Some(existing) if !existing.is_empty() => {
+                                        format!("{}\n\n{}", existing, annotation)
+                                    }
+                                    _ => annotation,
+                                };
+                                *entry = Value::String(merged);
+                            }
+                        }
+                    }
+                }
+                IrRole::System => {
+                    merge_into = None;
+                    let texts: Vec<String> = msg
+                        .blocks
+                        .iter()
+                        .map(|block| match block {
+                            IrBlock::Text(text)
+                            | IrBlock::Annotation(text)
+                            | IrBlock::Thinking(text) => text.clone(),
+                            IrBlock::ToolCall { name, input, .. } => {
+                                tool_call_annotation(name, input)
+                            }
+                            IrBlock::ToolResult {
+                                call_id,
+                                output,
+                                is_error,
+                            } => tool_result_annotation(call_id, output, *is_error),
+                        })
+                        .collect();
+                    out.push(serde_json::json!({
+                        "id": uuid::Uuid::new_v4().to_string(),
+                        "timestamp": ts,
+                        "type": "info",
+                        "content": texts.join("\n\n")
+                    }));
+                }
+            }
+        }
+
+        // 去掉空的 thoughts / toolCalls 字段，与原生 Gemini 记录保持一致
+        for entry in &mut out {
+            if let Some(obj) = entry.as_object_mut() {
+                for key in ["thoughts", "toolCalls"] {
+                    if obj
+                        .get(key)
+                        .and_then(|v| v.as_array())
+                        .is_some_and(|a| a.is_empty())
+                    {
+                        obj.remove(key);
+                    }
+                }
+                if obj.get("model").is_some_and(|m| m.is_null()) {
+                    obj.remove("model");
+                }
+            }
+        }
+
+        RenderedSession {
+            file_name: format!(
+                "session-{}-{}.json",
+                now.format("%Y-%m-%dT%H-%M"),
+                &new_session_id[..8]
+            ),
+            session_id: new_session_id,
+            entries: out,
+            source: session.conversion_source(),
+            start_time: session.first_timestamp(),
+            last_updated: session.last_timestamp(),
+        }
+    }
+
+    fn write(&self, rendered: &RenderedSession) -> Result<String, String> {
+        let chats_dir = gemini_config::get_project_session_dir(&self.project_path)?.join("chats");
+        std::fs::create_dir_all(&chats_dir)
+            .map_err(|e| format!("Failed to create chats directory: {}", e))?;
+
+        let session = serde_json::json!({
+            "sessionId": rendered.session_id,
+            "projectHash": gemini_config::hash_project_path(&self.project_path),
+            "startTime": rendered.start_time,
+            "lastUpdated": rendered.last_updated,
+            "messages": rendered.entries,
+            "conversionSource": rendered.source
+        });
+
+        let content = serde_json::to_string_pretty(&session)
+            .map_err(|e| format!("Failed to serialize session: {}", e))?;
+
+        let file_path = chats_dir.join(&rendered.file_name);
+        std::fs::write(&file_path, content)
+            .map_err(|e| format!("Failed to write session file: {}", e))?;
+
+        Ok(file_path.to_string_lossy().to_string())
+    }
+}
+
+// ================================
+// 转换入口
+// ================================
+
+/// 经由 IR 执行转换并写入目标文件
+pub fn convert_via_ir(
+    session_id: &str,
+    source_engine: &str,
+    target_engine: &str,
+    project_id: &str,
+    project_path: &str,
+) -> Result<ConversionResult, String> {
+    let source = adapter_for(source_engine, project_id, project_path)?;
+    let target = adapter_for(target_engine, project_id, project_path)?;
+
+    log::info!(
+        "Converting {} session {} to {} via IR",
+        source.engine(),
+        session_id,
+        target.engine()
+    );
+
+    let mut tracker = IrTracker::default();
+    let session = source.read(session_id, &mut tracker)?;
+    let rendered = target.render(&session, &mut tracker);
+    let target_path = target.write(&rendered)?;
+
+    log::info!(
+        "Successfully converted {} IR messages to {} session {}",
+        session.messages.len(),
+        target.engine(),
+        rendered.session_id
+    );
+
+    Ok(ConversionResult {
+        success: true,
+        new_session_id: rendered.session_id,
+        target_engine: target.engine().to_string(),
+        message_count: rendered.entries.len(),
+        source: rendered.source,
+        target_path,
+        error: None,
+        preview: None,
+    })
+}
+
+/// 经由 IR 在内存中预览转换结果，不写入文件
+pub fn preview_via_ir(
+    session_id: &str,
+    source_engine: &str,
+    target_engine: &str,
+    project_id: &str,
+    project_path: &str,
+) -> Result<ConversionPreview, String> {
+    let source = adapter_for(source_engine, project_id, project_path)?;
+    let target = adapter_for(target_engine, project_id, project_path)?;
+
+    let mut tracker = IrTracker::default();
+    let session = source.read(session_id, &mut tracker)?;
+    let rendered = target.render(&session, &mut tracker);
+
+    Ok(tracker
+        .into_stats()
+        .into_preview(source.engine(), target.engine(), &rendered.entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ir_message(role: IrRole, blocks: Vec<IrBlock>, source_index: usize) -> IrMessage {
+        IrMessage {
+            role,
+            blocks,
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            source_index,
+        }
+    }
+
+    fn ir_session(messages: Vec<IrMessage>) -> IrSession {
+        IrSession {
+            engine: "claude",
+            session_id: "source".to_string(),
+            project_path: "/tmp/project".to_string(),
+            model: None,
+            messages,
+        }
+    }
+
+    #[test]
+    fn gemini_render_attaches_tool_results_and_annotates_orphans() {
+        let session = ir_session(vec![
+            ir_message(IrRole::User, vec![IrBlock::Text("hi".to_string())], 0),
+            ir_message(
+                IrRole::Assistant,
+                vec![IrBlock::ToolCall {
+                    id: "t1".to_string(),
+                    name: "bash".to_string(),
+                    input: serde_json::json!({"command": "ls"}),
+                }],
+                1,
+            ),
+            ir_message(
+                IrRole::User,
+                vec![
+                    IrBlock::ToolResult {
+                        call_id: "t1".to_string(),
+                        output: "a.txt".to_string(),
+                        is_error: false,
+                    },
+                    IrBlock::ToolResult {
+                        call_id: "missing".to_string(),
+                        output: "orphan".to_string(),
+                        is_error: true,
+                    },
+                ],
+                2,
+            ),
+        ]);
+
+        let mut tracker = IrTracker::default();
+        for _ in 0..3 {
+            tracker.source(ConversionOutcome::Clean);
+        }
+        let adapter = GeminiAdapter {
+            project_path: "/tmp/project".to_string(),
+        };
+        let rendered = adapter.render(&session, &mut tracker);
+
+        assert_eq!(rendered.entries.len(), 3);
+        let call = &rendered.entries[1]["toolCalls"][0];
+        assert_eq!(call["name"], "run_shell_command");
+        assert_eq!(call["resultDisplay"], "a.txt");
+        assert_eq!(rendered.entries[2]["type"], "user");
+        assert!(rendered.entries[2]["content"]
+            .as_str()
+            .unwrap()
+            .contains("orphan"));
+
+        let stats = tracker.into_stats();
+        assert_eq!(stats.lossy, 1);
+    }
+
+    #[test]
+    fn codex_render_keeps_text_and_tool_call_order() {
+        let session = ir_session(vec![
+            ir_message(
+                IrRole::User,
+                vec![IrBlock::Text("list files".to_string())],
+                0,
+            ),
+            ir_message(
+                IrRole::Assistant,
+                vec![
+                    IrBlock::Text("Listing".to_string()),
+                    IrBlock::ToolCall {
+                        id: "t1".to_string(),
+                        name: "Bash".to_string(),
+                        input: serde_json::json!({"command": "ls"}),
+                    },
+                    IrBlock::Text("Done".to_string()),
+                ],
+                1,
+            ),
+            ir_message(
+                IrRole::User,
+                vec![IrBlock::ToolResult {
+                    call_id: "t1".to_string(),
+                    output: "a.txt".to_string(),
+                    is_error: false,
+                }],
+                2,
+            ),
+        ]);
+
+        let mut tracker = IrTracker::default();
+        for _ in 0..3 {
+            tracker.source(ConversionOutcome::Clean);
+        }
+        let adapter = CodexAdapter {
+            project_path: "/tmp/project".to_string(),
+        };
+        let rendered = adapter.render(&session, &mut tracker);
+
+        let payloads: Vec<&Value> = rendered.entries.iter().map(|e| &e["payload"]).collect();
+        assert_eq!(payloads[0]["conversion_source"]["engine"], "claude");
+        assert_eq!(payloads[1]["content"][0]["type"], "input_text");
+        assert_eq!(payloads[2]["content"][0]["text"], "Listing");
+        assert_eq!(payloads[3]["type"], "function_call");
+        assert_eq!(payloads[3]["name"], "shell_command");
+        assert_eq!(payloads[4]["content"][0]["text"], "Done");
+        assert_eq!(payloads[5]["type"], "function_call_output");
+        assert_eq!(payloads[5]["call_id"], "t1");
+        assert_eq!(tracker.into_stats().clean, 3);
+    }
+
+    #[test]
+    fn codex_events_convert_to_claude_tool_use_and_result() {
+        let call: CodexEvent = serde_json::from_value(serde_json::json!({
+            "type": "response_item",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "payload": {
+                "type": "function_call",
+                "name": "shell_command",
+                "arguments": "{\"command\":\"ls\"}",
+                "call_id": "c1"
+            }
+        }))
+        .unwrap();
+        let output: CodexEvent = serde_json::from_value(serde_json::json!({
+            "type": "response_item",
+            "timestamp": "2025-01-01T00:00:01Z",
+            "payload": {"type": "function_call_output", "call_id": "c1", "output": "a.txt"}
+        }))
+        .unwrap();
+
+        let mut tracker = IrTracker::default();
+        let first = tracker.source(ConversionOutcome::Clean);
+        let call = CodexAdapter::event_to_ir(&call, first, &mut tracker).unwrap();
+        let second = tracker.source(ConversionOutcome::Clean);
+        let output = CodexAdapter::event_to_ir(&output, second, &mut tracker).unwrap();
+
+        let adapter = ClaudeAdapter {
+            project_id: "-tmp-project".to_string(),
+            project_path: "/tmp/project".to_string(),
+        };
+        let mut session = ir_session(vec![call, output]);
+        session.engine = "codex";
+        let rendered = adapter.render(&session, &mut tracker);
+
+        // file-history-snapshot、init、tool_use、tool_result
+        assert_eq!(rendered.entries.len(), 4);
+        assert_eq!(rendered.entries[0]["type"], "file-history-snapshot");
+        assert_eq!(rendered.entries[1]["conversionSource"]["engine"], "codex");
+        let tool_use = &rendered.entries[2]["message"]["content"][0];
+        assert_eq!(tool_use["name"], "bash");
+        assert_eq!(tool_use["input"]["command"], "ls");
+        assert_eq!(rendered.entries[3]["type"], "user");
+        assert_eq!(
+            rendered.entries[3]["message"]["content"][0]["tool_use_id"],
+            "c1"
+        );
+        assert_eq!(
+            rendered.entries[3]["parentUuid"],
+            rendered.entries[2]["uuid"]
+        );
+    }
+
+    #[test]
+    fn gemini_message_splits_tool_results_into_user_message() {
+        let value = serde_json::json!({
+            "id": "m1",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "type": "gemini",
+            "content": "done",
+            "thoughts": [{"subject": "Plan", "description": "list files"}],
+            "toolCalls": [{
+                "id": "c1",
+                "name": "list_directory",
+                "args": {"path": "."},
+                "status": "success",
+                "result": [{"functionResponse": {"id": "c1", "name": "list_directory", "response": {"output": "a.txt"}}}]
+            }]
+        });
+
+        let mut tracker = IrTracker::default();
+        let index = tracker.source(ConversionOutcome::Clean);
+        let messages = GeminiAdapter::message_to_ir(&value, index, &mut tracker).unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, IrRole::Assistant);
+        assert!(matches!(
+            &messages[0].blocks[2],
+            IrBlock::ToolCall { name, .. } if name == "ls"
+        ));
+        assert!(matches!(
+            &messages[1].blocks[0],
+            IrBlock::ToolResult { output, is_error: false, .. } if output == "a.txt"
+        ));
+    }
+}
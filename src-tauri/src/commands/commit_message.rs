@@ -0,0 +1,160 @@
+//! 提交信息生成
+//!
+//! 收集项目已暂存的 diff，交给选定的 CLI 按 Conventional Commits 规范生成提交信息，
+//! 并支持直接用生成（或编辑后）的信息提交已暂存的内容；暂存由用户自己完成（见 git_stage_files）。
+
+use tauri::AppHandle;
+
+use super::git_stats::{commit_staged, staged_diff};
+use super::session_summary::{build_prompt_command, run_prompt_command};
+use super::simple_git::is_git_repo;
+
+/// 发送给模型的 diff 最大字符数
+const MAX_DIFF_CHARS: usize = 60_000;
+
+const COMMIT_INSTRUCTIONS: &str = "Write a git commit message for the following diff using \
+the Conventional Commits format: a `type(scope): summary` subject line of at most 72 characters, \
+a blank line, then a short body explaining what changed and why. Reply with the commit message \
+only, without code fences or commentary.";
+
+/// 截断过长的 diff，返回截断后的内容以及是否发生了截断
+fn cap_diff(diff: &str, max_chars: usize) -> (String, bool) {
+    if diff.chars().count() <= max_chars {
+        return (diff.to_string(), false);
+    }
+    (diff.chars().take(max_chars).collect(), true)
+}
+
+/// 去掉模型可能包裹在提交信息外的代码块标记
+fn clean_commit_message(output: &str) -> String {
+    let trimmed = output.trim();
+    let Some(inner) = trimmed.strip_prefix("```") else {
+        return trimmed.to_string();
+    };
+    let inner = inner.split_once('\n').map_or("", |(_, rest)| rest);
+    inner.trim_end().trim_end_matches("```").trim().to_string()
+}
+
+/// Generate a Conventional Commits message for the staged changes using the chosen CLI (defaults to claude)
+#[tauri::command]
+pub async fn generate_commit_message(
+    app: AppHandle,
+    project_path: String,
+    model: Option<String>,
+    engine: Option<String>,
+) -> Result<String, String> {
+    if !is_git_repo(&project_path) {
+        return Err(format!("Not a git repository: {}", project_path));
+    }
+    let engine = engine.unwrap_or_else(|| "claude".to_string());
+    let model = model.filter(|m| !m.trim().is_empty());
+
+    let diff = staged_diff(&project_path)?;
+    if diff.trim().is_empty() {
+        return Err("No staged changes to describe".to_string());
+    }
+    let total_chars = diff.chars().count();
+    let (diff, truncated) = cap_diff(&diff, MAX_DIFF_CHARS);
+    let note = if truncated {
+        log::warn!(
+            "Diff for {} truncated from {} to {} characters",
+            project_path,
+            total_chars,
+            MAX_DIFF_CHARS
+        );
+        format!(
+            "\n\nNote: the diff was truncated to the first {} of {} characters; \
+describe the change from what is shown and the file list.",
+            MAX_DIFF_CHARS, total_chars
+        )
+    } else {
+        String::new()
+    };
+    let prompt = format!(
+        "{}{}\n\n<diff>\n{}\n</diff>",
+        COMMIT_INSTRUCTIONS, note, diff
+    );
+
+    log::info!(
+        "Generating commit message for {} with {} (model: {:?})",
+        project_path,
+        engine,
+        model
+    );
+    let cmd = build_prompt_command(&app, &engine, model.as_deref()).await?;
+    let message = clean_commit_message(&run_prompt_command(cmd, prompt).await?);
+    if message.is_empty() {
+        return Err("CLI returned an empty commit message".to_string());
+    }
+    Ok(message)
+}
+
+/// Commit the staged changes with the given message; returns the new commit hash.
+/// Unstaged and untracked files are left untouched.
+#[tauri::command]
+pub async fn create_commit(project_path: String, message: String) -> Result<String, String> {
+    if message.trim().is_empty() {
+        return Err("Commit message must not be empty".to_string());
+    }
+    if !is_git_repo(&project_path) {
+        return Err(format!("Not a git repository: {}", project_path));
+    }
+    if staged_diff(&project_path)?.trim().is_empty() {
+        return Err("No staged changes to commit".to_string());
+    }
+
+    let commit = commit_staged(&project_path, message.trim())?;
+    log::info!("Created commit {} in {}", commit, project_path);
+    Ok(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_code_fences_from_commit_messages() {
+        assert_eq!(
+            clean_commit_message("```text\nfeat: add x\n\nbody\n```\n"),
+            "feat: add x\n\nbody"
+        );
+        assert_eq!(clean_commit_message("  fix: y  \n"), "fix: y");
+    }
+
+    #[tokio::test]
+    async fn commits_only_staged_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_string_lossy().to_string();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(status.status.success(), "git {:?} failed", args);
+            String::from_utf8_lossy(&status.stdout).to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "commit.gpgsign", "false"]);
+        std::fs::write(dir.path().join("staged.txt"), "a").unwrap();
+        std::fs::write(dir.path().join("untracked.txt"), "b").unwrap();
+
+        // 没有暂存内容时不创建（空）提交
+        assert!(create_commit(repo.clone(), "feat: nothing".into())
+            .await
+            .is_err());
+
+        git(&["add", "staged.txt"]);
+        let commit = create_commit(repo.clone(), "feat: add staged".into())
+            .await
+            .unwrap();
+        assert_eq!(git(&["rev-parse", "HEAD"]).trim(), commit);
+        assert_eq!(
+            git(&["show", "--name-only", "--format=", "HEAD"]).trim(),
+            "staged.txt"
+        );
+        assert_eq!(git(&["status", "--porcelain"]).trim(), "?? untracked.txt");
+    }
+}
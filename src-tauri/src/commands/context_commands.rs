@@ -3,8 +3,10 @@
 /// These commands integrate the AutoCompactManager with the frontend,
 /// providing comprehensive context window management capabilities.
 use crate::commands::context_manager::{
-    AutoCompactConfig, AutoCompactManager, AutoCompactState, SessionContext,
+    query_compaction_history, AutoCompactConfig, AutoCompactManager, AutoCompactState,
+    CompactionRecord, CompactionTrigger, SessionContext,
 };
+use crate::commands::storage::AgentDb;
 use log::{error, info};
 use tauri::{command, AppHandle, Manager, State};
 
@@ -57,7 +59,10 @@ pub async fn update_session_context(
         let manager = state.0.clone();
         let session_id_clone = session_id.clone();
         tokio::spawn(async move {
-            if let Err(e) = manager.execute_compaction(app, &session_id_clone).await {
+            if let Err(e) = manager
+                .execute_compaction(app, &session_id_clone, CompactionTrigger::Auto)
+                .await
+            {
                 error!("Background auto-compaction failed: {}", e);
             }
         });
@@ -83,10 +88,24 @@ pub async fn trigger_manual_compaction(
         state.0.update_config(config)?;
     }
 
-    state.0.execute_compaction(app, &session_id).await?;
+    state
+        .0
+        .execute_compaction(app, &session_id, CompactionTrigger::Manual)
+        .await?;
     Ok(())
 }
 
+/// Get the recorded compactions of a session, newest first
+#[command]
+pub async fn get_compaction_history(
+    db: State<'_, AgentDb>,
+    session_id: String,
+) -> Result<Vec<CompactionRecord>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    query_compaction_history(&conn, &session_id)
+        .map_err(|e| format!("Failed to load compaction history: {}", e))
+}
+
 /// Get auto-compact configuration
 #[command]
 pub async fn get_auto_compact_config(
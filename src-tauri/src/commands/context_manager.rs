@@ -1,4 +1,5 @@
 use log::{error, info};
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 /// Auto-compact context management system for Claude Code SDK integration
@@ -7,9 +8,11 @@ use std::collections::HashMap;
 /// based on Claude Code SDK best practices and the official documentation.
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 use tokio::time::sleep;
 
+use crate::commands::storage::AgentDb;
+
 /// Event payload for compaction status changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactionEvent {
@@ -30,6 +33,114 @@ pub enum CompactionEventType {
     Failed,
 }
 
+/// What started a compaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionTrigger {
+    Auto,
+    Manual,
+}
+
+impl CompactionTrigger {
+    fn as_str(self) -> &'static str {
+        match self {
+            CompactionTrigger::Auto => "auto",
+            CompactionTrigger::Manual => "manual",
+        }
+    }
+}
+
+/// A compaction as recorded in the `compaction_history` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionRecord {
+    pub session_id: String,
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// "auto" or "manual"
+    pub trigger: String,
+    pub strategy: String,
+    pub tokens_before: usize,
+    /// Estimated; None when the compaction failed
+    pub tokens_after: Option<usize>,
+    /// Estimated number of messages folded into the summary; None when the compaction failed
+    pub messages_removed: Option<usize>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Insert a compaction record
+pub fn insert_compaction_record(
+    conn: &Connection,
+    record: &CompactionRecord,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO compaction_history
+            (session_id, timestamp, trigger_kind, strategy, tokens_before, tokens_after, messages_removed, success, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            record.session_id,
+            record.timestamp,
+            record.trigger,
+            record.strategy,
+            record.tokens_before as i64,
+            record.tokens_after.map(|t| t as i64),
+            record.messages_removed.map(|t| t as i64),
+            record.success,
+            record.error,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Compaction records for a session, newest first
+pub fn query_compaction_history(
+    conn: &Connection,
+    session_id: &str,
+) -> rusqlite::Result<Vec<CompactionRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id, timestamp, trigger_kind, strategy, tokens_before, tokens_after, messages_removed, success, error
+         FROM compaction_history WHERE session_id = ?1 ORDER BY timestamp DESC, id DESC",
+    )?;
+    let records = stmt
+        .query_map(params![session_id], |row| {
+            Ok(CompactionRecord {
+                session_id: row.get(0)?,
+                timestamp: row.get(1)?,
+                trigger: row.get(2)?,
+                strategy: row.get(3)?,
+                tokens_before: row.get::<_, i64>(4)? as usize,
+                tokens_after: row.get::<_, Option<i64>>(5)?.map(|t| t as usize),
+                messages_removed: row.get::<_, Option<i64>>(6)?.map(|t| t as usize),
+                success: row.get(7)?,
+                error: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+/// Persist a compaction record; failures are logged and never fail the compaction itself
+fn record_compaction(app: &tauri::AppHandle, record: &CompactionRecord) {
+    let Some(db) = app.try_state::<AgentDb>() else {
+        log::warn!(
+            "Database unavailable, compaction for {} not recorded",
+            record.session_id
+        );
+        return;
+    };
+    let result =
+        db.0.lock()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| insert_compaction_record(&conn, record).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!(
+            "Failed to record compaction for {}: {}",
+            record.session_id,
+            e
+        );
+    }
+}
+
 /// Configuration for auto-compact behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoCompactConfig {
@@ -64,6 +175,17 @@ pub enum CompactionStrategy {
     Custom(String),
 }
 
+impl CompactionStrategy {
+    fn name(&self) -> &'static str {
+        match self {
+            CompactionStrategy::Smart => "smart",
+            CompactionStrategy::Aggressive => "aggressive",
+            CompactionStrategy::Conservative => "conservative",
+            CompactionStrategy::Custom(_) => "custom",
+        }
+    }
+}
+
 /// Session context tracking information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionContext {
@@ -219,15 +341,20 @@ impl AutoCompactManager {
         Ok(false)
     }
 
-    /// Execute compaction for a session
+    /// Execute compaction for a session and record it in the compaction history
     pub async fn execute_compaction(
         &self,
         app: tauri::AppHandle,
         session_id: &str,
+        trigger: CompactionTrigger,
     ) -> Result<(), String> {
-        info!("Executing auto-compaction for session {}", session_id);
+        info!(
+            "Executing {} compaction for session {}",
+            trigger.as_str(),
+            session_id
+        );
 
-        let (project_path, custom_instructions, tokens_before) = {
+        let (project_path, custom_instructions, tokens_before, strategy, preserved_messages) = {
             let sessions = self.sessions.lock().map_err(|e| e.to_string())?;
             let config = self.config.lock().map_err(|e| e.to_string())?;
 
@@ -235,12 +362,30 @@ impl AutoCompactManager {
                 .get(session_id)
                 .ok_or_else(|| format!("Session {} not found", session_id))?;
 
+            let preserved_messages = if config.preserve_recent_messages {
+                config.preserve_message_count
+            } else {
+                0
+            };
             (
                 session.project_path.clone(),
                 config.custom_instructions.clone(),
                 session.current_tokens,
+                config.compaction_strategy.name(),
+                preserved_messages,
             )
         };
+        let mut record = CompactionRecord {
+            session_id: session_id.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            trigger: trigger.as_str().to_string(),
+            strategy: strategy.to_string(),
+            tokens_before,
+            tokens_after: None,
+            messages_removed: None,
+            success: false,
+            error: None,
+        };
 
         // Emit compaction started event
         let _ = app.emit("auto-compact-event", CompactionEvent {
@@ -273,20 +418,32 @@ impl AutoCompactManager {
             Ok(_) => {
                 // Update session state after successful compaction
                 let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
-                let tokens_after = if let Some(session) = sessions.get_mut(session_id) {
+                let (tokens_after, messages_removed) = if let Some(session) =
+                    sessions.get_mut(session_id)
+                {
                     session.last_compaction = Some(SystemTime::now());
                     session.compaction_count += 1;
                     session.status = SessionStatus::Active;
                     session.current_tokens = session.current_tokens / 3; // Estimated token reduction
 
+                    // Everything except the preserved recent messages is folded into the summary
+                    let messages_removed = session.message_count.saturating_sub(preserved_messages);
+                    session.message_count -= messages_removed;
+
                     info!(
                         "Auto-compaction completed for session {}: compaction #{}, estimated tokens: {}",
                         session_id, session.compaction_count, session.current_tokens
                     );
-                    session.current_tokens
+                    (session.current_tokens, messages_removed)
                 } else {
-                    tokens_before / 3
+                    (tokens_before / 3, 0)
                 };
+                drop(sessions);
+
+                record.success = true;
+                record.tokens_after = Some(tokens_after);
+                record.messages_removed = Some(messages_removed);
+                record_compaction(&app, &record);
 
                 // Emit compaction completed event
                 let _ = app.emit("auto-compact-event", CompactionEvent {
@@ -306,7 +463,10 @@ impl AutoCompactManager {
                 if let Some(session) = sessions.get_mut(session_id) {
                     session.status = SessionStatus::CompactionFailed(e.clone());
                 }
+                drop(sessions);
                 error!("Auto-compaction failed for session {}: {}", session_id, e);
+                record.error = Some(e.clone());
+                record_compaction(&app, &record);
 
                 // Emit compaction failed event
                 let _ = app.emit("auto-compact-event", CompactionEvent {
@@ -469,7 +629,11 @@ impl AutoCompactManager {
 
                         tokio::spawn(async move {
                             if let Err(e) = manager
-                                .execute_compaction(app_clone, &session_id_clone)
+                                .execute_compaction(
+                                    app_clone,
+                                    &session_id_clone,
+                                    CompactionTrigger::Auto,
+                                )
                                 .await
                             {
                                 error!(
@@ -534,3 +698,41 @@ impl AutoCompactManager {
 /// State wrapper for AutoCompactManager
 #[derive(Clone)]
 pub struct AutoCompactState(pub Arc<AutoCompactManager>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compaction_history_round_trips_newest_first() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::commands::storage::run_migrations(&conn).unwrap();
+
+        let mut record = CompactionRecord {
+            session_id: "s1".to_string(),
+            timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+            trigger: CompactionTrigger::Auto.as_str().to_string(),
+            strategy: CompactionStrategy::Smart.name().to_string(),
+            tokens_before: 120_000,
+            tokens_after: Some(40_000),
+            messages_removed: Some(14),
+            success: true,
+            error: None,
+        };
+        insert_compaction_record(&conn, &record).unwrap();
+        record.timestamp = "2025-01-02T00:00:00+00:00".to_string();
+        record.trigger = CompactionTrigger::Manual.as_str().to_string();
+        record.tokens_after = None;
+        record.messages_removed = None;
+        record.success = false;
+        record.error = Some("boom".to_string());
+        insert_compaction_record(&conn, &record).unwrap();
+
+        let history = query_compaction_history(&conn, "s1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].trigger, "manual");
+        assert_eq!(history[0].error.as_deref(), Some("boom"));
+        assert_eq!(history[1].messages_removed, Some(14));
+        assert!(query_compaction_history(&conn, "other").unwrap().is_empty());
+    }
+}
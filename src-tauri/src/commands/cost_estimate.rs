@@ -0,0 +1,143 @@
+//! 请求费用预估
+//!
+//! 发送前按字符数粗略估算 prompt（以及可选的已有会话上下文）的 token 数，套用定价表给出费用区间。
+//! 结果只是估算：实际费用取决于分词、缓存是否命中以及输出长度。
+
+use serde::Serialize;
+
+use crate::commands::claude::{estimate_tokens, get_claude_dir, validate_path_component};
+use crate::commands::usage::{pricing_for_model, ModelPricing};
+
+/// 低 / 高估算假设的输出 token 数
+const LOW_OUTPUT_TOKENS: u64 = 500;
+const HIGH_OUTPUT_TOKENS: u64 = 8_000;
+/// 字符启发式的误差范围
+const LOW_TOKEN_FACTOR: f64 = 0.8;
+const HIGH_TOKEN_FACTOR: f64 = 1.25;
+
+const ESTIMATE_NOTE: &str = "Rough estimate: tokens are approximated from character counts and \
+output length is unknown. Resumed context is priced as a cache read (low) or cache write (high).";
+
+/// Estimated cost range for a request; never exact
+#[derive(Debug, Clone, Serialize)]
+pub struct CostEstimate {
+    pub model: String,
+    /// Always true; the numbers come from a character-based heuristic
+    pub is_estimate: bool,
+    pub prompt_tokens: u64,
+    /// Context tokens of the resumed session (0 when not included)
+    pub context_tokens: u64,
+    pub low_output_tokens: u64,
+    pub high_output_tokens: u64,
+    pub low_usd: f64,
+    pub high_usd: f64,
+    pub note: String,
+}
+
+/// 按定价计算 (低, 高) 费用
+fn cost_range(pricing: &ModelPricing, prompt_tokens: u64, context_tokens: u64) -> (f64, f64) {
+    let per_token = |price: f64| price / 1_000_000.0;
+    let prompt_tokens = prompt_tokens as f64;
+    let context_tokens = context_tokens as f64;
+
+    let low = prompt_tokens * LOW_TOKEN_FACTOR * per_token(pricing.input)
+        + context_tokens * per_token(pricing.cache_read)
+        + LOW_OUTPUT_TOKENS as f64 * per_token(pricing.output);
+    let high = prompt_tokens * HIGH_TOKEN_FACTOR * per_token(pricing.input)
+        + context_tokens * per_token(pricing.cache_write)
+        + HIGH_OUTPUT_TOKENS as f64 * per_token(pricing.output);
+    (low, high)
+}
+
+/// 会话最后一条带 usage 的消息的上下文大小（输入 + 缓存写入 + 缓存读取）
+fn session_context_tokens(session_id: &str, project_id: &str) -> Result<u64, String> {
+    validate_path_component(project_id, "project id")?;
+    validate_path_component(session_id, "session id")?;
+    let session_path = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("projects")
+        .join(project_id)
+        .join(format!("{}.jsonl", session_id));
+    let content = std::fs::read_to_string(&session_path)
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+
+    let context = content.lines().rev().find_map(|line| {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        let usage = value["message"]["usage"].as_object()?;
+        let tokens = |key: &str| usage.get(key).and_then(|t| t.as_u64()).unwrap_or(0);
+        Some(
+            tokens("input_tokens")
+                + tokens("cache_creation_input_tokens")
+                + tokens("cache_read_input_tokens"),
+        )
+    });
+    Ok(context.unwrap_or(0))
+}
+
+/// Estimate the cost of sending a prompt (optionally on top of a resumed session's context); the result is a low/high range, not an exact price
+#[tauri::command]
+pub async fn estimate_request_cost(
+    prompt: String,
+    model: String,
+    include_context: bool,
+    session_id: Option<String>,
+    project_id: Option<String>,
+) -> Result<CostEstimate, String> {
+    let pricing = pricing_for_model(&model)
+        .ok_or_else(|| format!("No pricing known for model: {}", model))?;
+    let prompt_tokens = estimate_tokens(&prompt) as u64;
+
+    let context_tokens = if include_context {
+        let (Some(session_id), Some(project_id)) = (session_id, project_id) else {
+            return Err("session_id and project_id are required to include context".to_string());
+        };
+        session_context_tokens(&session_id, &project_id)?
+    } else {
+        0
+    };
+
+    let (low_usd, high_usd) = cost_range(&pricing, prompt_tokens, context_tokens);
+    Ok(CostEstimate {
+        model,
+        is_estimate: true,
+        prompt_tokens,
+        context_tokens,
+        low_output_tokens: LOW_OUTPUT_TOKENS,
+        high_output_tokens: HIGH_OUTPUT_TOKENS,
+        low_usd,
+        high_usd,
+        note: ESTIMATE_NOTE.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_range_brackets_context_pricing() {
+        let pricing = pricing_for_model("opus").unwrap();
+        let (low, high) = cost_range(&pricing, 1_000, 0);
+        assert!(low > 0.0 && low < high);
+
+        let (low_ctx, high_ctx) = cost_range(&pricing, 1_000, 100_000);
+        assert!((low_ctx - low - 100_000.0 * pricing.cache_read / 1_000_000.0).abs() < 1e-9);
+        assert!((high_ctx - high - 100_000.0 * pricing.cache_write / 1_000_000.0).abs() < 1e-9);
+
+        assert!(pricing_for_model("gpt-4o").is_none());
+    }
+
+    #[test]
+    fn context_lookup_rejects_path_traversal() {
+        for (session_id, project_id) in [
+            ("../../secrets", "project"),
+            ("session", ".."),
+            ("session", "a/b"),
+            ("session", "a\\b"),
+            ("", "project"),
+        ] {
+            let err = session_context_tokens(session_id, project_id).unwrap_err();
+            assert!(err.starts_with("Invalid"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,610 @@
+//! 自检（doctor）
+//!
+//! 把分散的检测（CLI 版本、数据库、配置目录、WSL、PATH 中的 node、磁盘空间）汇总成一次调用，
+//! 每项给出 pass / warn / fail 以及修复建议，方便用户在反馈问题时直接附上报告。
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::claude::check_claude_version;
+use crate::commands::codex::check_codex_availability;
+use crate::commands::gemini::check_gemini_installed;
+use crate::commands::storage::{latest_schema_version, schema_version, AgentDb};
+
+/// 可用空间低于该值时警告
+const LOW_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+/// 可用空间低于该值时判定失败
+const CRITICAL_DISK_SPACE_BYTES: u64 = 100 * 1024 * 1024;
+/// `--version` 探测的超时时间
+const VERSION_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 单项检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    /// 稳定的检查标识，如 "claude_cli"
+    pub id: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// 未通过时的修复建议
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn pass(id: &str, message: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            status: CheckStatus::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(id: &str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            status: CheckStatus::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(id: &str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            status: CheckStatus::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// 自检报告
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub checks: Vec<DiagnosticCheck>,
+    /// 所有检查中最差的状态
+    pub overall: CheckStatus,
+    pub generated_at: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+}
+
+/// Runs all self-diagnostic checks and returns a structured report
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Result<Diagnostics, String> {
+    log::info!("Running diagnostics");
+
+    let (claude, codex, gemini) = tokio::join!(
+        check_claude_cli(&app),
+        check_codex_cli(),
+        check_gemini_cli()
+    );
+    let mut checks = vec![claude, codex, gemini, check_database(&app)];
+    checks.extend(check_directories());
+
+    let blocking_checks = tokio::task::spawn_blocking({
+        let app_data_dir = app.path().app_data_dir().ok();
+        move || {
+            let mut checks = Vec::new();
+            if cfg!(target_os = "windows") {
+                checks.push(check_wsl());
+            }
+            checks.push(check_node_on_path());
+            checks.push(check_disk_space(app_data_dir.as_deref()));
+            checks
+        }
+    })
+    .await
+    .map_err(|e| format!("Diagnostics failed: {}", e))?;
+    checks.extend(blocking_checks);
+
+    Ok(Diagnostics {
+        overall: overall_status(&checks),
+        checks,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+    })
+}
+
+fn overall_status(checks: &[DiagnosticCheck]) -> CheckStatus {
+    if checks.iter().any(|c| c.status == CheckStatus::Fail) {
+        CheckStatus::Fail
+    } else if checks.iter().any(|c| c.status == CheckStatus::Warn) {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    }
+}
+
+async fn check_claude_cli(app: &AppHandle) -> DiagnosticCheck {
+    const ID: &str = "claude_cli";
+    match check_claude_version(app.clone()).await {
+        Ok(status) if status.is_installed => DiagnosticCheck::pass(
+            ID,
+            format!(
+                "Claude CLI {}",
+                status.version.unwrap_or_else(|| "(unknown version)".to_string())
+            ),
+        ),
+        Ok(status) => DiagnosticCheck::fail(
+            ID,
+            format!("Claude CLI not working: {}", status.output.trim()),
+            "Install it with `npm install -g @anthropic-ai/claude-code`, or set a custom path in Settings",
+        ),
+        Err(e) => DiagnosticCheck::fail(
+            ID,
+            format!("Claude CLI check failed: {}", e),
+            "Set a custom Claude CLI path in Settings",
+        ),
+    }
+}
+
+async fn check_codex_cli() -> DiagnosticCheck {
+    const ID: &str = "codex_cli";
+    match check_codex_availability().await {
+        Ok(availability) if availability.available => DiagnosticCheck::pass(
+            ID,
+            format!(
+                "Codex CLI {}",
+                availability
+                    .version
+                    .unwrap_or_else(|| "(unknown version)".to_string())
+            ),
+        ),
+        Ok(availability) => DiagnosticCheck::warn(
+            ID,
+            format!(
+                "Codex CLI not available: {}",
+                availability.error.unwrap_or_default()
+            ),
+            "Install it with `npm install -g @openai/codex` if you want to use Codex",
+        ),
+        Err(e) => DiagnosticCheck::warn(
+            ID,
+            format!("Codex CLI check failed: {}", e),
+            "Set a custom Codex path in Settings",
+        ),
+    }
+}
+
+async fn check_gemini_cli() -> DiagnosticCheck {
+    const ID: &str = "gemini_cli";
+    match check_gemini_installed().await {
+        Ok(status) if status.installed => DiagnosticCheck::pass(
+            ID,
+            format!(
+                "Gemini CLI {}",
+                status
+                    .version
+                    .unwrap_or_else(|| "(unknown version)".to_string())
+            ),
+        ),
+        Ok(status) => DiagnosticCheck::warn(
+            ID,
+            format!(
+                "Gemini CLI not installed: {}",
+                status.error.unwrap_or_default()
+            ),
+            "Install it with `npm install -g @google/gemini-cli` if you want to use Gemini",
+        ),
+        Err(e) => DiagnosticCheck::warn(
+            ID,
+            format!("Gemini CLI check failed: {}", e),
+            "Set GEMINI_CLI_PATH to the Gemini CLI executable",
+        ),
+    }
+}
+
+fn check_database(app: &AppHandle) -> DiagnosticCheck {
+    const ID: &str = "database";
+    const HINT: &str =
+        "Restart the app; if it persists, back up and remove agents.db from the app data directory";
+
+    let Some(db) = app.try_state::<AgentDb>() else {
+        return DiagnosticCheck::fail(ID, "Database was not initialized", HINT);
+    };
+    let conn = match db.0.lock() {
+        Ok(conn) => conn,
+        Err(_) => return DiagnosticCheck::fail(ID, "Database connection is poisoned", HINT),
+    };
+
+    let integrity = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string());
+    match integrity {
+        Ok(result) if result == "ok" => {}
+        Ok(result) => {
+            return DiagnosticCheck::fail(ID, format!("Integrity check failed: {}", result), HINT)
+        }
+        Err(e) => return DiagnosticCheck::fail(ID, format!("Database not readable: {}", e), HINT),
+    }
+
+    let latest = latest_schema_version();
+    match schema_version(&conn) {
+        Ok(version) if version >= latest => DiagnosticCheck::pass(
+            ID,
+            format!("Database is readable, schema version {}", version),
+        ),
+        Ok(version) => DiagnosticCheck::fail(
+            ID,
+            format!(
+                "Database schema version {} is behind the expected version {}",
+                version, latest
+            ),
+            HINT,
+        ),
+        Err(e) => DiagnosticCheck::fail(
+            ID,
+            format!("Database migrations were not applied: {}", e),
+            HINT,
+        ),
+    }
+}
+
+fn check_directories() -> Vec<DiagnosticCheck> {
+    let home = dirs::home_dir();
+    let codex_sessions = crate::commands::codex::get_codex_sessions_dir().ok();
+    let codex_dir = codex_sessions
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| home.as_ref().map(|h| h.join(".codex")));
+
+    vec![
+        check_directory(
+            "claude_dir",
+            home.as_ref().map(|h| h.join(".claude")),
+            "Run the Claude CLI once so it creates ~/.claude",
+        ),
+        check_directory(
+            "claude_projects_dir",
+            home.as_ref().map(|h| h.join(".claude").join("projects")),
+            "Start a Claude session once so it creates ~/.claude/projects",
+        ),
+        check_directory(
+            "codex_dir",
+            codex_dir,
+            "Run the Codex CLI once so it creates ~/.codex",
+        ),
+    ]
+}
+
+/// 不存在只警告（对应 CLI 可能尚未使用过），存在但无法读取则失败
+fn check_directory(id: &str, path: Option<PathBuf>, missing_hint: &str) -> DiagnosticCheck {
+    let Some(path) = path else {
+        return DiagnosticCheck::fail(
+            id,
+            "Home directory not found",
+            "Make sure HOME (or USERPROFILE on Windows) is set",
+        );
+    };
+
+    if !path.exists() {
+        return DiagnosticCheck::warn(
+            id,
+            format!("{} does not exist", path.display()),
+            missing_hint,
+        );
+    }
+    match std::fs::read_dir(&path) {
+        Ok(_) => DiagnosticCheck::pass(id, format!("{} is readable", path.display())),
+        Err(e) => DiagnosticCheck::fail(
+            id,
+            format!("{} is not readable: {}", path.display(), e),
+            format!("Fix the permissions of {}", path.display()),
+        ),
+    }
+}
+
+fn check_wsl() -> DiagnosticCheck {
+    const ID: &str = "wsl";
+    let diagnostics = crate::commands::wsl_utils::collect_wsl_diagnostics();
+    let uses_wsl = diagnostics.codex_configured_distro.is_some()
+        || diagnostics.claude_configured_distro.is_some()
+        || diagnostics.codex_active_distro.is_some();
+
+    if !diagnostics.wsl_available {
+        return if uses_wsl {
+            DiagnosticCheck::fail(
+                ID,
+                "WSL is configured but not available",
+                "Install WSL with `wsl --install`, or switch Claude/Codex to native mode",
+            )
+        } else {
+            DiagnosticCheck::pass(ID, "WSL not installed (not used)")
+        };
+    }
+
+    if diagnostics.suggestions.is_empty() {
+        DiagnosticCheck::pass(
+            ID,
+            format!(
+                "WSL {} available",
+                diagnostics.wsl_version.unwrap_or_default()
+            ),
+        )
+    } else {
+        DiagnosticCheck::warn(
+            ID,
+            "WSL setup has issues",
+            diagnostics.suggestions.join("; "),
+        )
+    }
+}
+
+fn check_node_on_path() -> DiagnosticCheck {
+    const ID: &str = "node_on_path";
+    let node_name = if cfg!(target_os = "windows") {
+        "node.exe"
+    } else {
+        "node"
+    };
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    let found = std::env::split_paths(&path_var)
+        .map(|dir| dir.join(node_name))
+        .find(|candidate| candidate.is_file());
+
+    match found {
+        Some(node) => DiagnosticCheck::pass(ID, format!("node found at {}", node.display())),
+        None => DiagnosticCheck::warn(
+            ID,
+            "node was not found on PATH",
+            "npm-installed CLIs need Node.js; install it or add its bin directory to PATH",
+        ),
+    }
+}
+
+fn check_disk_space(app_data_dir: Option<&Path>) -> DiagnosticCheck {
+    const ID: &str = "disk_space";
+    let Some(dir) = app_data_dir else {
+        return DiagnosticCheck::fail(
+            ID,
+            "App data directory not found",
+            "Make sure the home directory is writable",
+        );
+    };
+
+    let Some(available) = available_disk_space(dir) else {
+        return DiagnosticCheck::warn(
+            ID,
+            format!("Could not determine free space for {}", dir.display()),
+            "Check free disk space manually",
+        );
+    };
+
+    let message = format!(
+        "{} MB free for {}",
+        available / (1024 * 1024),
+        dir.display()
+    );
+    if available < CRITICAL_DISK_SPACE_BYTES {
+        DiagnosticCheck::fail(
+            ID,
+            message,
+            "Free up disk space; sessions and logs may fail to save",
+        )
+    } else if available < LOW_DISK_SPACE_BYTES {
+        DiagnosticCheck::warn(ID, message, "Free up disk space soon")
+    } else {
+        DiagnosticCheck::pass(ID, message)
+    }
+}
+
+/// 目录所在分区的可用字节数（目录不存在时向上查找已存在的父目录）
+fn available_disk_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|p| p.exists())?;
+
+    #[cfg(unix)]
+    {
+        let output = std::process::Command::new("df")
+            .args(["-Pk"])
+            .arg(existing)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_df_available(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+
+        let script = format!(
+            "(Get-Item -LiteralPath '{}').PSDrive.Free",
+            existing.display().to_string().replace('\'', "''")
+        );
+        let output = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+}
+
+/// 解析 `df -Pk` 输出中的可用空间（第 4 列，单位 KiB）
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_df_available(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let kib: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// 自定义 CLI 路径的诊断结果，帮助用户在保存前定位路径不可用的原因
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryDiagnostics {
+    pub tool: String,
+    /// 展开 `~` 和相对路径后的完整路径
+    pub path: String,
+    pub exists: bool,
+    pub is_file: bool,
+    pub is_executable: bool,
+    pub is_cmd_wrapper: bool,
+    /// `.cmd` 包装实际执行的命令（node + 脚本）
+    pub resolved_command: Option<String>,
+    pub exit_code: Option<i32>,
+    /// `--version` 的标准输出与标准错误
+    pub stdout: String,
+    pub stderr: String,
+    pub version: Option<String>,
+    /// 无法执行时的错误信息
+    pub error: Option<String>,
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["exe", "cmd", "bat", "com", "ps1"]
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// 按应用实际启动 CLI 的方式（含 `.cmd` 包装解析）运行 `--version`
+async fn probe_version(path: &str, diagnostics: &mut BinaryDiagnostics) {
+    let mut cmd =
+        tokio::process::Command::from(crate::claude_binary::create_command_with_env(path));
+    cmd.arg("--version")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    match tokio::time::timeout(VERSION_PROBE_TIMEOUT, cmd.output()).await {
+        Ok(Ok(output)) => {
+            diagnostics.exit_code = output.status.code();
+            diagnostics.stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            diagnostics.stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            diagnostics.version = crate::claude_binary::extract_version_from_output(&output.stdout)
+                .or_else(|| crate::claude_binary::extract_version_from_output(&output.stderr));
+            if !output.status.success() {
+                diagnostics.error = Some(format!("`--version` exited with {}", output.status));
+            }
+        }
+        Ok(Err(e)) => diagnostics.error = Some(format!("Failed to run: {}", e)),
+        Err(_) => {
+            diagnostics.error = Some(format!(
+                "`--version` did not finish within {} seconds",
+                VERSION_PROBE_TIMEOUT.as_secs()
+            ))
+        }
+    }
+}
+
+/// Tests a candidate claude / codex / gemini binary path and reports why it is or is not usable:
+/// existence, file type, executable bit, `.cmd` wrapper resolution and `--version` output
+#[tauri::command]
+pub async fn diagnose_binary_path(tool: String, path: String) -> Result<BinaryDiagnostics, String> {
+    if !matches!(tool.as_str(), "claude" | "codex" | "gemini") {
+        return Err(format!("Unknown tool: {}", tool));
+    }
+    let expanded = crate::commands::codex::config::expand_user_path(&path)?;
+    let path_str = expanded.to_string_lossy().to_string();
+    log::info!("Diagnosing {} binary path: {}", tool, path_str);
+
+    let is_cmd_wrapper = expanded
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cmd"));
+    let mut diagnostics = BinaryDiagnostics {
+        tool,
+        path: path_str.clone(),
+        exists: expanded.exists(),
+        is_file: expanded.is_file(),
+        is_executable: is_executable(&expanded),
+        is_cmd_wrapper,
+        resolved_command: is_cmd_wrapper
+            .then(|| crate::claude_binary::resolve_cmd_wrapper(&path_str))
+            .flatten()
+            .map(|(node, script)| format!("{} {}", node, script)),
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        version: None,
+        error: None,
+    };
+
+    if !diagnostics.exists {
+        diagnostics.error = Some("Path does not exist".to_string());
+    } else if !diagnostics.is_file {
+        diagnostics.error = Some("Path is not a file".to_string());
+    } else if !diagnostics.is_executable {
+        diagnostics.error = Some("File is not executable".to_string());
+    } else {
+        probe_version(&path_str, &mut diagnostics).await;
+    }
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_df_output_and_aggregates_status() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/disk3s5     971350180 612345678 310000000      67% /System/Volumes/Data\n";
+        assert_eq!(parse_df_available(output), Some(310000000 * 1024));
+        assert_eq!(parse_df_available("garbage"), None);
+
+        let mut checks = vec![DiagnosticCheck::pass("a", "ok")];
+        assert_eq!(overall_status(&checks), CheckStatus::Pass);
+        checks.push(DiagnosticCheck::warn("b", "meh", "fix b"));
+        assert_eq!(overall_status(&checks), CheckStatus::Warn);
+        checks.push(DiagnosticCheck::fail("c", "bad", "fix c"));
+        assert_eq!(overall_status(&checks), CheckStatus::Fail);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn diagnoses_binary_paths() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let report = diagnose_binary_path("codex".into(), missing.to_string_lossy().into())
+            .await
+            .unwrap();
+        assert!(!report.exists);
+        assert_eq!(report.error.as_deref(), Some("Path does not exist"));
+
+        let script = dir.path().join("codex");
+        std::fs::write(&script, "#!/bin/sh\necho 'codex-cli 0.47.1'\n").unwrap();
+        let report = diagnose_binary_path("codex".into(), script.to_string_lossy().into())
+            .await
+            .unwrap();
+        assert!(report.is_file && !report.is_executable);
+
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let report = diagnose_binary_path("codex".into(), script.to_string_lossy().into())
+            .await
+            .unwrap();
+        assert_eq!(report.exit_code, Some(0));
+        assert_eq!(report.stdout, "codex-cli 0.47.1");
+        assert_eq!(report.version.as_deref(), Some("0.47.1"));
+        assert!(report.error.is_none());
+    }
+}
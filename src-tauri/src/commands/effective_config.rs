@@ -0,0 +1,261 @@
+//! 生效配置诊断
+//!
+//! 路径、环境变量、权限和模型分散在 ~/.claude/settings.json、execution_config.json、
+//! binaries.json、agents.db 以及各工具自己的配置文件中。这里按应用实际使用的优先级
+//! 合并出最终生效的配置，并为每个值标注来源，便于排查“到底哪个配置在生效”。
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tauri::AppHandle;
+
+use crate::commands::claude::{
+    get_claude_dir, get_claude_execution_config, get_claude_wsl_mode_config, inherited_env,
+    is_secret_env_key, load_settings_env, FALLBACK_MODEL,
+};
+use crate::commands::codex::config::{
+    get_binary_override, get_codex_mode_config, get_codex_path, get_current_codex_config,
+    read_custom_codex_path_from_db,
+};
+use crate::commands::gemini::config::{build_gemini_env, load_gemini_config};
+use crate::commands::storage::read_app_setting;
+
+/// 值和来源
+fn annotated(value: impl Serialize, source: &str) -> Value {
+    json!({ "value": value, "source": source })
+}
+
+/// Returns the fully merged configuration the app uses for a tool, with the source of each value
+#[tauri::command]
+pub async fn get_effective_config(app: AppHandle, tool: String) -> Result<Value, String> {
+    match tool.as_str() {
+        "claude" => Ok(claude_effective_config(&app).await),
+        "codex" => Ok(codex_effective_config(&app).await),
+        "gemini" => Ok(gemini_effective_config()),
+        other => Err(format!("Unknown tool: {}", other)),
+    }
+}
+
+async fn claude_effective_config(app: &AppHandle) -> Value {
+    // 路径：数据库中的自定义路径 → binaries.json override_path → 自动检测
+    let (path, path_source) = if let Some(path) = read_app_setting(app, "claude_binary_path") {
+        (Some(path), "app_settings (custom path)".to_string())
+    } else if let Some(path) = get_binary_override("claude") {
+        (Some(path), "binaries.json override_path".to_string())
+    } else {
+        match crate::claude_binary::find_claude_binary(app) {
+            Ok(path) => (Some(path), "auto-detected".to_string()),
+            Err(e) => (None, format!("not found: {}", e)),
+        }
+    };
+
+    // 模型：前端未指定时使用全局默认模型
+    let model = match read_app_setting(app, "default_model") {
+        Some(model) => annotated(model, "app_settings.default_model"),
+        None => annotated(FALLBACK_MODEL, "built-in fallback"),
+    };
+
+    let execution_source = match get_claude_dir() {
+        Ok(dir) if dir.join("execution_config.json").exists() => "execution_config.json",
+        _ => "default",
+    };
+    let execution_config = get_claude_execution_config(app.clone(), None)
+        .await
+        .unwrap_or_default();
+
+    let env = merge_env_layers(vec![
+        (
+            "process",
+            inherited_env(path.as_deref().unwrap_or_default()),
+        ),
+        ("settings.json", load_settings_env()),
+        (
+            "binaries.json",
+            crate::claude_binary::load_tool_env("claude")
+                .into_iter()
+                .collect(),
+        ),
+    ]);
+
+    let wsl = match get_claude_wsl_mode_config().await {
+        Ok(info) => annotated(info, "claude wsl mode config"),
+        Err(e) => annotated(Value::Null, &format!("unavailable: {}", e)),
+    };
+
+    json!({
+        "tool": "claude",
+        "path": annotated(path, &path_source),
+        "model": model,
+        "permissions": annotated(&execution_config.permissions, execution_source),
+        "execution": annotated(&execution_config, execution_source),
+        "env": env,
+        "wsl": wsl,
+    })
+}
+
+async fn codex_effective_config(app: &AppHandle) -> Value {
+    let path = if let Some(path) = get_binary_override("codex") {
+        annotated(path, "binaries.json override_path")
+    } else if let Some(path) = read_custom_codex_path_from_db(app) {
+        annotated(path, "app_settings (custom path)")
+    } else {
+        match get_codex_path(app.clone()).await {
+            Ok(path) => annotated(path, "auto-detected"),
+            Err(e) => annotated(Value::Null, &format!("not found: {}", e)),
+        }
+    };
+
+    let (model, provider) = match get_current_codex_config().await {
+        Ok(config) => (
+            annotated(config.model, "codex config.toml"),
+            json!({
+                "base_url": annotated(config.base_url, "codex config.toml"),
+                "api_key": annotated(
+                    config.api_key.map(|key| mask_secret("API_KEY", &key)),
+                    "codex auth.json",
+                ),
+            }),
+        ),
+        Err(e) => {
+            let source = format!("unavailable: {}", e);
+            (
+                annotated(Value::Null, &source),
+                annotated(Value::Null, &source),
+            )
+        }
+    };
+
+    let mode = match get_codex_mode_config().await {
+        Ok(info) => annotated(info, "codex mode config"),
+        Err(e) => annotated(Value::Null, &format!("unavailable: {}", e)),
+    };
+
+    json!({
+        "tool": "codex",
+        "path": path,
+        "model": model,
+        "provider": provider,
+        "mode": mode,
+    })
+}
+
+fn gemini_effective_config() -> Value {
+    let path = match crate::commands::gemini::session::find_gemini_binary() {
+        Ok(path) => annotated(path, "auto-detected"),
+        Err(e) => annotated(Value::Null, &format!("not found: {}", e)),
+    };
+
+    let (config, source) = match load_gemini_config() {
+        Ok(config) => (config, "~/.anycode/gemini.json"),
+        Err(e) => {
+            log::warn!("Failed to load Gemini config, using default: {}", e);
+            (Default::default(), "default")
+        }
+    };
+    let env = merge_env_layers(vec![(
+        "~/.anycode/gemini.json",
+        build_gemini_env(&config).into_iter().collect(),
+    )]);
+
+    json!({
+        "tool": "gemini",
+        "path": path,
+        "model": annotated(&config.default_model, source),
+        "permissions": annotated(&config.approval_mode, source),
+        "auth_method": annotated(&config.auth_method, source),
+        "env": env,
+    })
+}
+
+/// 按顺序叠加各层环境变量（后者覆盖前者），记录每个变量的最终来源，敏感值打码
+fn merge_env_layers(layers: Vec<(&str, Vec<(String, String)>)>) -> Value {
+    let mut merged = Map::new();
+    for (source, vars) in layers {
+        for (key, value) in vars {
+            let masked = mask_secret(&key, &value);
+            merged.insert(key, annotated(masked, source));
+        }
+    }
+
+    // 按键排序输出，便于对比
+    let mut entries: Vec<_> = merged.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Value::Object(entries.into_iter().collect())
+}
+
+/// 递归打码 JSON 中键名看起来是密钥的字符串值
+pub(crate) fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                match child {
+                    Value::String(text) => *text = mask_secret(key, text),
+                    _ => redact_json(child),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// 键名看起来是密钥时只保留前 4 个字符
+pub(crate) fn mask_secret(key: &str, value: &str) -> String {
+    if !is_secret_env_key(key) || value.is_empty() {
+        return value.to_string();
+    }
+
+    let prefix: String = value.chars().take(4).collect();
+    if value.chars().count() > 8 {
+        format!("{}***", prefix)
+    } else {
+        "***".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_env_layers_win_and_secrets_are_masked() {
+        let env = merge_env_layers(vec![
+            (
+                "process",
+                vec![
+                    ("PATH".to_string(), "/usr/bin".to_string()),
+                    ("ANTHROPIC_BASE_URL".to_string(), "https://a".to_string()),
+                ],
+            ),
+            (
+                "settings.json",
+                vec![
+                    ("ANTHROPIC_BASE_URL".to_string(), "https://b".to_string()),
+                    (
+                        "ANTHROPIC_AUTH_TOKEN".to_string(),
+                        "sk-ant-1234567890".to_string(),
+                    ),
+                ],
+            ),
+        ]);
+
+        assert_eq!(env["PATH"]["source"], "process");
+        assert_eq!(env["ANTHROPIC_BASE_URL"]["value"], "https://b");
+        assert_eq!(env["ANTHROPIC_BASE_URL"]["source"], "settings.json");
+        assert_eq!(env["ANTHROPIC_AUTH_TOKEN"]["value"], "sk-a***");
+        assert_eq!(mask_secret("API_KEY", "short"), "***");
+        assert_eq!(
+            mask_secret("GOOGLE_APPLICATION_CREDENTIALS", "/home/me/creds.json"),
+            "/hom***"
+        );
+
+        let mut config = json!({
+            "env": { "ANTHROPIC_API_KEY": "sk-ant-1234567890", "DEBUG": "1" },
+            "providers": [{ "token": "abcdef", "name": "proxy" }],
+        });
+        redact_json(&mut config);
+        assert_eq!(config["env"]["ANTHROPIC_API_KEY"], "sk-a***");
+        assert_eq!(config["env"]["DEBUG"], "1");
+        assert_eq!(config["providers"][0]["token"], "***");
+        assert_eq!(config["providers"][0]["name"], "proxy");
+    }
+}
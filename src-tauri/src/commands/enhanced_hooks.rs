@@ -48,6 +48,25 @@ impl HookEvent {
             HookEvent::OnTabSwitch => "OnTabSwitch",
         }
     }
+
+    /// 所有事件类型（用于示例上下文等需要遍历的场景）
+    pub const ALL: [HookEvent; 11] = [
+        HookEvent::PreToolUse,
+        HookEvent::PostToolUse,
+        HookEvent::Notification,
+        HookEvent::Stop,
+        HookEvent::SubagentStop,
+        HookEvent::OnContextCompact,
+        HookEvent::OnAgentSwitch,
+        HookEvent::OnFileChange,
+        HookEvent::OnSessionStart,
+        HookEvent::OnSessionEnd,
+        HookEvent::OnTabSwitch,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
 }
 
 /// Hook执行上下文
@@ -80,10 +99,183 @@ pub struct HookChainResult {
     pub should_continue: bool, // 是否应该继续后续操作
 }
 
+/// 结构化条件节点，例如 `{"all": [{"file_glob": "**/*.rs"}, {"tool": "Edit"}]}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionNode {
+    All(Vec<ConditionNode>),
+    Any(Vec<ConditionNode>),
+    Not(Box<ConditionNode>),
+    /// 匹配上下文中的文件路径（项目内的文件按相对路径匹配）
+    FileGlob(String),
+    /// 匹配工具名，可用 `|` 分隔多个候选，如 `Edit|MultiEdit`
+    Tool(String),
+    /// 原有的简单表达式
+    Expression(String),
+}
+
+/// Hook条件：字符串为原有的简单表达式（隐式单条件），对象为结构化条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HookCondition {
+    Expression(String),
+    Structured(ConditionNode),
+}
+
+impl HookCondition {
+    /// 检查条件能否解析（glob 是否合法、表达式字段是否已知），不依赖上下文
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            HookCondition::Expression(expression) => validate_expression(expression),
+            HookCondition::Structured(node) => validate_node(node),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            HookCondition::Expression(expression) => expression.clone(),
+            HookCondition::Structured(node) => serde_json::to_string(node).unwrap_or_default(),
+        }
+    }
+}
+
+fn validate_expression(expression: &str) -> Result<(), String> {
+    if !expression.contains("==") {
+        return Ok(());
+    }
+    let parts: Vec<&str> = expression.split("==").collect();
+    if parts.len() != 2 {
+        return Err(format!("Invalid expression: {}", expression));
+    }
+    match parts[0].trim() {
+        "event" | "session_id" => Ok(()),
+        field => Err(format!(
+            "Unknown field '{}' in expression (expected event or session_id)",
+            field
+        )),
+    }
+}
+
+fn validate_node(node: &ConditionNode) -> Result<(), String> {
+    match node {
+        ConditionNode::All(nodes) | ConditionNode::Any(nodes) => {
+            if nodes.is_empty() {
+                return Err("'all' / 'any' must contain at least one condition".to_string());
+            }
+            nodes.iter().try_for_each(validate_node)
+        }
+        ConditionNode::Not(node) => validate_node(node),
+        ConditionNode::FileGlob(pattern) => glob::Pattern::new(pattern)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid file glob '{}': {}", pattern, e)),
+        ConditionNode::Tool(tool) if tool.trim().is_empty() => {
+            Err("Tool condition must not be empty".to_string())
+        }
+        ConditionNode::Tool(_) => Ok(()),
+        ConditionNode::Expression(expression) => validate_expression(expression),
+    }
+}
+
+/// 上下文中的工具名：`data.tool_name` 或 `data.tool`
+fn context_tool_name(context: &HookContext) -> Option<&str> {
+    context
+        .data
+        .get("tool_name")
+        .or_else(|| context.data.get("tool"))
+        .and_then(|t| t.as_str())
+}
+
+/// 上下文中的文件路径：`data.file_path` 或工具参数中的路径
+fn context_file_path(context: &HookContext) -> Option<&str> {
+    let input = context.data.get("tool_input");
+    [
+        context.data.get("file_path"),
+        input.and_then(|i| i.get("file_path")),
+        input.and_then(|i| i.get("notebook_path")),
+        input.and_then(|i| i.get("path")),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|value| value.as_str())
+}
+
+fn matches_file_glob(pattern: &str, context: &HookContext) -> Result<bool, String> {
+    let Some(file_path) = context_file_path(context) else {
+        return Ok(false);
+    };
+    let pattern = glob::Pattern::new(pattern)
+        .map_err(|e| format!("Invalid file glob '{}': {}", pattern, e))?;
+    let file_path = file_path.replace('\\', "/");
+    let project_path = context.project_path.replace('\\', "/");
+    let relative = file_path
+        .strip_prefix(&format!("{}/", project_path.trim_end_matches('/')))
+        .unwrap_or(&file_path);
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    Ok(pattern.matches_with(relative, options))
+}
+
+fn evaluate_node(node: &ConditionNode, context: &HookContext) -> Result<bool, String> {
+    match node {
+        ConditionNode::All(nodes) => {
+            for node in nodes {
+                if !evaluate_node(node, context)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        ConditionNode::Any(nodes) => {
+            for node in nodes {
+                if evaluate_node(node, context)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        ConditionNode::Not(node) => Ok(!evaluate_node(node, context)?),
+        ConditionNode::FileGlob(pattern) => matches_file_glob(pattern, context),
+        ConditionNode::Tool(tools) => Ok(context_tool_name(context)
+            .is_some_and(|name| tools.split('|').any(|tool| tool.trim() == name))),
+        ConditionNode::Expression(expression) => evaluate_expression(expression, context),
+    }
+}
+
+/// 评估简单条件表达式
+fn evaluate_expression(condition: &str, context: &HookContext) -> Result<bool, String> {
+    // 简单的条件评估实现
+    // 支持的格式：
+    // - "session_id == 'xyz'"
+    // - "data.tokens > 100000"
+    // - "event == 'OnContextCompact'"
+
+    // 这里使用简单的字符串匹配，未来可以集成更强大的表达式引擎
+    if condition.contains("==") {
+        let parts: Vec<&str> = condition.split("==").collect();
+        if parts.len() == 2 {
+            let left = parts[0].trim();
+            let right = parts[1].trim().trim_matches(|c| c == '\'' || c == '"');
+
+            match left {
+                "event" => Ok(context.event == right),
+                "session_id" => Ok(context.session_id == right),
+                _ => Ok(false),
+            }
+        } else {
+            Ok(false)
+        }
+    } else {
+        // 默认返回true
+        Ok(true)
+    }
+}
+
 /// 条件触发配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConditionalTrigger {
-    pub condition: String, // 条件表达式
+    pub condition: HookCondition, // 条件表达式或结构化条件
     pub enabled: bool,
     pub priority: Option<i32>, // 执行优先级
 }
@@ -316,32 +508,15 @@ impl HookExecutor {
         Ok(())
     }
 
-    /// 评估条件表达式
-    fn evaluate_condition(&self, condition: &str, context: &HookContext) -> Result<bool, String> {
-        // 简单的条件评估实现
-        // 支持的格式：
-        // - "session_id == 'xyz'"
-        // - "data.tokens > 100000"
-        // - "event == 'OnContextCompact'"
-
-        // 这里使用简单的字符串匹配，未来可以集成更强大的表达式引擎
-        if condition.contains("==") {
-            let parts: Vec<&str> = condition.split("==").collect();
-            if parts.len() == 2 {
-                let left = parts[0].trim();
-                let right = parts[1].trim().trim_matches(|c| c == '\'' || c == '"');
-
-                match left {
-                    "event" => Ok(context.event == right),
-                    "session_id" => Ok(context.session_id == right),
-                    _ => Ok(false),
-                }
-            } else {
-                Ok(false)
-            }
-        } else {
-            // 默认返回true
-            Ok(true)
+    /// 评估条件（简单表达式或结构化条件）
+    fn evaluate_condition(
+        &self,
+        condition: &HookCondition,
+        context: &HookContext,
+    ) -> Result<bool, String> {
+        match condition {
+            HookCondition::Expression(expression) => evaluate_expression(expression, context),
+            HookCondition::Structured(node) => evaluate_node(node, context),
         }
     }
 }
@@ -401,6 +576,44 @@ impl HookManager {
 
 // ============ Tauri Commands ============
 
+/// Hooks配置的作用域，按Claude的加载顺序排列（后者追加在前者之后）
+const HOOK_SCOPES: [&str; 3] = ["user", "project", "local"];
+
+/// 从各作用域的hooks配置中取出指定事件的配置项，并附带来源作用域
+fn hook_entries_for_event(
+    configs: &[(&'static str, serde_json::Value)],
+    event: &HookEvent,
+) -> Vec<(&'static str, serde_json::Value)> {
+    configs
+        .iter()
+        .flat_map(|(scope, config)| {
+            config
+                .get(event.as_str())
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .map(move |entry| (*scope, entry.clone()))
+        })
+        .collect()
+}
+
+/// 读取用户、项目和本地三个作用域中指定事件的hook配置项
+async fn load_hook_entries(
+    event: &HookEvent,
+    project_path: &str,
+) -> Result<Vec<(&'static str, serde_json::Value)>, String> {
+    let mut configs = Vec::with_capacity(HOOK_SCOPES.len());
+    for scope in HOOK_SCOPES {
+        let config = crate::commands::claude::get_hooks_config(
+            scope.to_string(),
+            Some(project_path.to_string()),
+        )
+        .await?;
+        configs.push((scope, config));
+    }
+    Ok(hook_entries_for_event(&configs, event))
+}
+
 /// 触发Hook事件
 #[tauri::command]
 pub async fn trigger_hook_event(
@@ -408,32 +621,15 @@ pub async fn trigger_hook_event(
     event: String,
     context: HookContext,
 ) -> Result<HookChainResult, String> {
-    let event_enum = match event.as_str() {
-        "OnContextCompact" => HookEvent::OnContextCompact,
-        "OnAgentSwitch" => HookEvent::OnAgentSwitch,
-        "OnFileChange" => HookEvent::OnFileChange,
-        "OnSessionStart" => HookEvent::OnSessionStart,
-        "OnSessionEnd" => HookEvent::OnSessionEnd,
-        "OnTabSwitch" => HookEvent::OnTabSwitch,
-        _ => return Err(format!("Unknown hook event: {}", event)),
-    };
+    let event_enum =
+        HookEvent::parse(&event).ok_or_else(|| format!("Unknown hook event: {}", event))?;
 
-    // 从配置中加载hooks
-    let hooks_config = crate::commands::claude::get_hooks_config(
-        "project".to_string(),
-        Some(context.project_path.clone()),
-    )
-    .await?;
-
-    let hooks_array = hooks_config
-        .get(&event)
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| serde_json::from_value::<EnhancedHook>(v.clone()).ok())
-                .collect()
-        })
-        .unwrap_or_default();
+    // 从各作用域的配置中加载hooks
+    let hooks_array = load_hook_entries(&event_enum, &context.project_path)
+        .await?
+        .into_iter()
+        .filter_map(|(_, entry)| serde_json::from_value::<EnhancedHook>(entry).ok())
+        .collect();
 
     let executor = HookExecutor::new(app);
     executor
@@ -445,13 +641,213 @@ pub async fn trigger_hook_event(
 #[tauri::command]
 pub async fn test_hook_condition(
     app: tauri::AppHandle,
-    condition: String,
+    condition: HookCondition,
     context: HookContext,
 ) -> Result<bool, String> {
+    condition.validate()?;
     let executor = HookExecutor::new(app);
     executor.evaluate_condition(&condition, &context)
 }
 
+/// 模拟执行中单个hook的匹配结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedHook {
+    /// 配置来源作用域：user / project / local
+    pub scope: String,
+    pub command: String,
+    pub matched: bool,
+    pub reason: String,
+    pub timeout: Option<u64>,
+    pub retry: Option<u32>,
+    pub on_success: Vec<String>,
+    pub on_failure: Vec<String>,
+}
+
+/// Hook事件模拟结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookSimulationResult {
+    pub event: String,
+    pub total_hooks: usize,
+    pub matched: usize,
+    pub hooks: Vec<SimulatedHook>,
+}
+
+/// 模拟单个配置项：增强型hook按条件匹配，Claude原生格式（matcher + hooks）按工具名正则匹配
+fn simulate_hook_entry(
+    executor: &HookExecutor,
+    scope: &str,
+    entry: &serde_json::Value,
+    context: &HookContext,
+) -> Result<Vec<SimulatedHook>, String> {
+    if let Ok(hook) = serde_json::from_value::<EnhancedHook>(entry.clone()) {
+        let (matched, reason) = match &hook.condition {
+            Some(condition) if condition.enabled => {
+                if executor.evaluate_condition(&condition.condition, context)? {
+                    (
+                        true,
+                        format!("Condition matched: {}", condition.condition.describe()),
+                    )
+                } else {
+                    (
+                        false,
+                        format!("Condition not met: {}", condition.condition.describe()),
+                    )
+                }
+            }
+            Some(_) => (true, "Condition disabled".to_string()),
+            None => (true, "No condition".to_string()),
+        };
+        return Ok(vec![SimulatedHook {
+            scope: scope.to_string(),
+            command: hook.command,
+            matched,
+            reason,
+            timeout: hook.timeout,
+            retry: hook.retry,
+            on_success: hook.on_success.unwrap_or_default(),
+            on_failure: hook.on_failure.unwrap_or_default(),
+        }]);
+    }
+
+    let matcher = entry.get("matcher").and_then(|m| m.as_str()).unwrap_or("");
+    let tool_name = context
+        .data
+        .get("tool_name")
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
+    let (matched, reason) = if matcher.is_empty() || matcher == "*" {
+        (true, "Matcher matches all tools".to_string())
+    } else {
+        let regex = regex::Regex::new(&format!("^(?:{})$", matcher))
+            .map_err(|e| format!("Invalid matcher '{}': {}", matcher, e))?;
+        if regex.is_match(tool_name) {
+            (true, format!("Tool '{}' matches '{}'", tool_name, matcher))
+        } else {
+            (
+                false,
+                format!("Tool '{}' does not match '{}'", tool_name, matcher),
+            )
+        }
+    };
+
+    let hooks = entry
+        .get("hooks")
+        .and_then(|h| h.as_array())
+        .map(|hooks| hooks.as_slice())
+        .unwrap_or_default();
+    Ok(hooks
+        .iter()
+        .filter_map(|hook| {
+            Some(SimulatedHook {
+                scope: scope.to_string(),
+                command: hook.get("command")?.as_str()?.to_string(),
+                matched,
+                reason: reason.clone(),
+                timeout: hook.get("timeout").and_then(|t| t.as_u64()),
+                retry: None,
+                on_success: Vec::new(),
+                on_failure: Vec::new(),
+            })
+        })
+        .collect())
+}
+
+/// 用示例上下文模拟Hook事件（只评估匹配，不执行任何命令）
+#[tauri::command]
+pub async fn simulate_hook_event(
+    app: AppHandle,
+    event_type: String,
+    sample_context: HookContext,
+) -> Result<HookSimulationResult, String> {
+    let event = HookEvent::parse(&event_type)
+        .ok_or_else(|| format!("Unknown hook event: {}", event_type))?;
+
+    let entries = load_hook_entries(&event, &sample_context.project_path).await?;
+
+    let executor = HookExecutor::new(app);
+    let mut hooks = Vec::new();
+    for (scope, entry) in &entries {
+        hooks.extend(simulate_hook_entry(
+            &executor,
+            scope,
+            entry,
+            &sample_context,
+        )?);
+    }
+    let matched = hooks.iter().filter(|hook| hook.matched).count();
+    info!(
+        "Simulated {} hook event: {}/{} hooks matched",
+        event.as_str(),
+        matched,
+        hooks.len()
+    );
+
+    Ok(HookSimulationResult {
+        event: event.as_str().to_string(),
+        total_hooks: hooks.len(),
+        matched,
+        hooks,
+    })
+}
+
+/// 获取各事件类型的示例上下文，作为模拟Hook事件的模板
+#[tauri::command]
+pub async fn get_sample_hook_contexts() -> Result<Vec<HookContext>, String> {
+    let sample = |event: HookEvent, data: serde_json::Value| HookContext {
+        event: event.as_str().to_string(),
+        session_id: "00000000-0000-0000-0000-000000000000".to_string(),
+        project_path: "/path/to/project".to_string(),
+        data,
+    };
+
+    Ok(HookEvent::ALL
+        .into_iter()
+        .map(|event| {
+            let data = match event {
+                HookEvent::PreToolUse => serde_json::json!({
+                    "tool_name": "Edit",
+                    "tool_input": {
+                        "file_path": "/path/to/project/src/main.rs",
+                        "old_string": "foo",
+                        "new_string": "bar"
+                    }
+                }),
+                HookEvent::PostToolUse => serde_json::json!({
+                    "tool_name": "Bash",
+                    "tool_input": { "command": "cargo test" },
+                    "tool_response": { "exit_code": 0 }
+                }),
+                HookEvent::Notification => serde_json::json!({
+                    "message": "Claude needs your permission to use Bash"
+                }),
+                HookEvent::Stop | HookEvent::SubagentStop => serde_json::json!({
+                    "stop_hook_active": false
+                }),
+                HookEvent::OnContextCompact => serde_json::json!({
+                    "tokens": 150000,
+                    "threshold": 0.85
+                }),
+                HookEvent::OnAgentSwitch => serde_json::json!({
+                    "from": "general-purpose",
+                    "to": "code-reviewer"
+                }),
+                HookEvent::OnFileChange => serde_json::json!({
+                    "file_path": "/path/to/project/src/lib.rs",
+                    "change_type": "modified"
+                }),
+                HookEvent::OnSessionStart | HookEvent::OnSessionEnd => serde_json::json!({
+                    "model": "sonnet"
+                }),
+                HookEvent::OnTabSwitch => serde_json::json!({
+                    "from_tab": "tab-1",
+                    "to_tab": "tab-2"
+                }),
+            };
+            sample(event, data)
+        })
+        .collect())
+}
+
 // ============ 智能化自动化场景实现 ============
 
 /// 提交前代码审查Hook配置
@@ -540,3 +936,98 @@ pub async fn execute_pre_commit_review(
         suggestions: vec![],
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_event_name() {
+        for event in HookEvent::ALL {
+            assert_eq!(HookEvent::parse(event.as_str()), Some(event));
+        }
+        assert_eq!(HookEvent::parse("onSessionStart"), None);
+    }
+
+    #[test]
+    fn collects_hook_entries_from_all_scopes_in_order() {
+        let configs = vec![
+            (
+                "user",
+                serde_json::json!({ "OnSessionStart": [{ "command": "echo user" }] }),
+            ),
+            (
+                "project",
+                serde_json::json!({ "OnSessionEnd": [{ "command": "echo other event" }] }),
+            ),
+            (
+                "local",
+                serde_json::json!({
+                    "OnSessionStart": [{ "command": "echo local 1" }, { "command": "echo local 2" }]
+                }),
+            ),
+        ];
+
+        let entries = hook_entries_for_event(&configs, &HookEvent::OnSessionStart);
+        let summary: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|(scope, entry)| (*scope, entry["command"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("user", "echo user"),
+                ("local", "echo local 1"),
+                ("local", "echo local 2"),
+            ]
+        );
+    }
+
+    fn edit_context(file_path: &str) -> HookContext {
+        HookContext {
+            event: "PostToolUse".to_string(),
+            session_id: "session".to_string(),
+            project_path: "/work/project".to_string(),
+            data: serde_json::json!({
+                "tool_name": "Edit",
+                "tool_input": { "file_path": file_path }
+            }),
+        }
+    }
+
+    #[test]
+    fn evaluates_structured_conditions() {
+        let condition: HookCondition = serde_json::from_value(serde_json::json!({
+            "all": [{ "file_glob": "**/*.rs" }, { "tool": "Edit|MultiEdit" }]
+        }))
+        .unwrap();
+        assert!(condition.validate().is_ok());
+
+        let matches = |path: &str| match &condition {
+            HookCondition::Structured(node) => evaluate_node(node, &edit_context(path)).unwrap(),
+            HookCondition::Expression(_) => unreachable!(),
+        };
+        assert!(matches("/work/project/src/main.rs"));
+        assert!(!matches("/work/project/README.md"));
+    }
+
+    #[test]
+    fn keeps_simple_expressions_working() {
+        let condition: HookCondition =
+            serde_json::from_value(serde_json::json!("event == 'PostToolUse'")).unwrap();
+        let HookCondition::Expression(expression) = &condition else {
+            panic!("expected a simple expression");
+        };
+        assert!(evaluate_expression(expression, &edit_context("a.rs")).unwrap());
+
+        assert!(HookCondition::Expression("tokens == 1".to_string())
+            .validate()
+            .is_err());
+        assert!(serde_json::from_value::<HookCondition>(serde_json::json!({
+            "file_glob": "src/[.rs"
+        }))
+        .unwrap()
+        .validate()
+        .is_err());
+    }
+}
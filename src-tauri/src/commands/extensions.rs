@@ -5,7 +5,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-use super::claude::get_claude_dir;
+use super::claude::{get_claude_dir, validate_path_component};
 
 /// Represents a Plugin
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -781,6 +781,170 @@ description: {}
     })
 }
 
+/// Resolve the `.claude/<sub_dir>` directory for the given scope
+fn resolve_extension_dir(
+    sub_dir: &str,
+    scope: &str,
+    project_path: Option<String>,
+) -> Result<PathBuf, String> {
+    if scope == "project" {
+        let proj_path = project_path.ok_or("Project path is required for project scope")?;
+        Ok(Path::new(&proj_path).join(".claude").join(sub_dir))
+    } else {
+        Ok(get_claude_dir().map_err(|e| e.to_string())?.join(sub_dir))
+    }
+}
+
+/// Rewrite (or insert) the `name` field in YAML frontmatter
+/// Content without frontmatter gets a new frontmatter block containing only the name
+fn rewrite_frontmatter_name(content: &str, new_name: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let name_line = format!("name: {}", new_name);
+
+    let frontmatter_end = if lines.first() == Some(&"---") {
+        lines
+            .iter()
+            .skip(1)
+            .position(|line| *line == "---")
+            .map(|i| i + 1)
+    } else {
+        None
+    };
+
+    let mut result: Vec<String> = match frontmatter_end {
+        Some(end) => {
+            let mut out = Vec::with_capacity(lines.len() + 1);
+            out.push("---".to_string());
+            let mut replaced = false;
+            for line in &lines[1..end] {
+                if !replaced && line.starts_with("name:") {
+                    out.push(name_line.clone());
+                    replaced = true;
+                } else {
+                    out.push(line.to_string());
+                }
+            }
+            if !replaced {
+                out.insert(1, name_line);
+            }
+            out.extend(lines[end..].iter().map(|l| l.to_string()));
+            out
+        }
+        None => {
+            let mut out = vec![
+                "---".to_string(),
+                name_line,
+                "---".to_string(),
+                String::new(),
+            ];
+            out.extend(lines.iter().map(|l| l.to_string()));
+            out
+        }
+    };
+
+    if content.ends_with('\n') {
+        result.push(String::new());
+    }
+    result.join("\n")
+}
+
+/// Duplicate an existing subagent under a new name
+/// The copy is written next to the source (same scope) with its frontmatter `name` rewritten
+#[tauri::command]
+pub async fn duplicate_subagent(
+    source_name: String,
+    new_name: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<String, String> {
+    info!(
+        "Duplicating subagent: {} -> {} (scope: {})",
+        source_name, new_name, scope
+    );
+
+    validate_path_component(&source_name, "agent name")?;
+    validate_path_component(&new_name, "agent name")?;
+    // Same rule as creation: the name is also written verbatim into the frontmatter
+    if !new_name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(
+            "Agent name can only contain letters, numbers, hyphens, and underscores".into(),
+        );
+    }
+
+    let agents_dir = resolve_extension_dir("agents", &scope, project_path)?;
+    let source_path = agents_dir.join(format!("{}.md", source_name));
+    let target_path = agents_dir.join(format!("{}.md", new_name));
+
+    if !source_path.exists() {
+        return Err(format!("Subagent '{}' not found", source_name));
+    }
+    if target_path.exists() {
+        return Err(format!("Subagent '{}' already exists", new_name));
+    }
+
+    let content = read_subagent(source_path.to_string_lossy().to_string()).await?;
+    let new_content = rewrite_frontmatter_name(&content, &new_name);
+
+    fs::write(&target_path, new_content)
+        .map_err(|e| format!("Failed to write subagent file: {}", e))?;
+
+    info!("Duplicated subagent at: {:?}", target_path);
+    Ok(target_path.to_string_lossy().to_string())
+}
+
+/// Duplicate an existing Agent Skill under a new name
+/// Only SKILL.md is copied; the new skill lives in .claude/skills/<new-name>/SKILL.md
+#[tauri::command]
+pub async fn duplicate_skill(
+    source_name: String,
+    new_name: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<String, String> {
+    info!(
+        "Duplicating skill: {} -> {} (scope: {})",
+        source_name, new_name, scope
+    );
+
+    validate_path_component(&source_name, "skill name")?;
+    validate_path_component(&new_name, "skill name")?;
+    // Same rule as creation: the name is also written verbatim into the frontmatter
+    if !new_name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(
+            "Skill name can only contain letters, numbers, hyphens, and underscores".into(),
+        );
+    }
+
+    let skills_dir = resolve_extension_dir("skills", &scope, project_path)?;
+    let source_path = skills_dir.join(&source_name).join("SKILL.md");
+    let target_dir = skills_dir.join(&new_name);
+    let target_path = target_dir.join("SKILL.md");
+
+    if !source_path.exists() {
+        return Err(format!("Skill '{}' not found", source_name));
+    }
+    if target_dir.exists() {
+        return Err(format!("Skill '{}' already exists", new_name));
+    }
+
+    let content = read_skill(source_path.to_string_lossy().to_string()).await?;
+    let new_content = rewrite_frontmatter_name(&content, &new_name);
+
+    fs::create_dir_all(&target_dir)
+        .map_err(|e| format!("Failed to create skill directory: {}", e))?;
+    fs::write(&target_path, new_content)
+        .map_err(|e| format!("Failed to write skill file: {}", e))?;
+
+    info!("Duplicated skill at: {:?}", target_path);
+    Ok(target_path.to_string_lossy().to_string())
+}
+
 // ============================================================================
 // Custom Slash Commands
 // ============================================================================
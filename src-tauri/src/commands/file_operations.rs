@@ -1,5 +1,11 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::Command as StdCommand;
 
+#[cfg(target_os = "windows")]
+use crate::claude_mcp::find_command_in_path;
+use crate::claude_mcp::validate_command_in_path;
+
 /// Open a directory in the system file explorer (cross-platform)
 #[tauri::command]
 pub async fn open_directory_in_explorer(directory_path: String) -> Result<(), String> {
@@ -64,3 +70,308 @@ pub async fn open_file_with_default_app(file_path: String) -> Result<(), String>
 
     Ok(())
 }
+
+/// How long to wait for the file manager to answer the D-Bus ShowItems call
+#[cfg(target_os = "linux")]
+const REVEAL_DBUS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Percent-encoded `file://` URI for an absolute path. Every component is encoded,
+/// so spaces, `%`, `#` and commas (which would split a dbus-send array) are safe
+#[cfg(any(target_os = "linux", test))]
+fn file_uri(path: &Path) -> String {
+    let encoded: Vec<String> = path
+        .to_string_lossy()
+        .split('/')
+        .map(|component| urlencoding::encode(component).into_owned())
+        .collect();
+    format!("file://{}", encoded.join("/"))
+}
+
+/// Reveal a file (or directory) selected in the system file manager (cross-platform)
+#[tauri::command]
+pub async fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let target = Path::new(&path);
+    if !target.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer expects the path glued to "/select," as a single argument
+        let mut cmd = StdCommand::new("explorer");
+        cmd.raw_arg(format!("/select,\"{}\"", path));
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd.spawn()
+            .map_err(|e| format!("Failed to reveal file: {}", e))?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        StdCommand::new("open")
+            .arg("-R")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to reveal file: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Prefer the FileManager1 D-Bus interface, which selects the item in
+        // Nautilus/Dolphin/Nemo etc. Fall back to opening the parent directory.
+        let absolute = target
+            .canonicalize()
+            .unwrap_or_else(|_| target.to_path_buf());
+        let uri = file_uri(&absolute);
+        let show_items = tokio::task::spawn_blocking(move || {
+            StdCommand::new("dbus-send")
+                .args([
+                    "--session",
+                    "--print-reply",
+                    &format!("--reply-timeout={}", REVEAL_DBUS_TIMEOUT.as_millis()),
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                    &format!("array:string:{}", uri),
+                    "string:",
+                ])
+                .output()
+        });
+        let revealed = match tokio::time::timeout(REVEAL_DBUS_TIMEOUT, show_items).await {
+            Ok(Ok(Ok(output))) => output.status.success(),
+            Ok(Ok(Err(e))) => {
+                log::debug!("dbus-send unavailable, falling back to xdg-open: {}", e);
+                false
+            }
+            Ok(Err(e)) => {
+                log::warn!("FileManager1.ShowItems task failed: {}", e);
+                false
+            }
+            Err(_) => {
+                log::warn!("FileManager1.ShowItems timed out, falling back to xdg-open");
+                false
+            }
+        };
+
+        if !revealed {
+            let dir = if absolute.is_dir() {
+                absolute.as_path()
+            } else {
+                absolute.parent().unwrap_or(absolute.as_path())
+            };
+            StdCommand::new("xdg-open")
+                .arg(dir)
+                .spawn()
+                .map_err(|e| format!("Failed to reveal file: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Editors that understand a `path:line:column` jump target, in detection order
+const KNOWN_EDITORS: &[&str] = &["cursor", "code", "code-insiders", "windsurf", "zed", "subl"];
+
+/// Editor preference used by `open_file_at_line`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorConfig {
+    /// Preferred editor command (e.g. "code", "cursor") or absolute path.
+    /// When unset, the first known editor found in PATH is used.
+    #[serde(default)]
+    pub preferred_editor: Option<String>,
+}
+
+/// Get the editor config path (~/.anycode/editor.json)
+fn get_editor_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("editor.json"))
+}
+
+fn load_editor_config() -> Result<EditorConfig, String> {
+    crate::utils::config_utils::load_json_config(get_editor_config_path()?)
+}
+
+/// Build the arguments for jumping to `path:line:column` in the given editor
+fn build_editor_goto_args(editor: &str, target: &str) -> Vec<String> {
+    let name = Path::new(editor)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(editor)
+        .to_lowercase();
+
+    match name.as_str() {
+        // VS Code family uses `-g file:line:col`
+        "code" | "code-insiders" | "cursor" | "windsurf" | "codium" => {
+            vec!["-g".to_string(), target.to_string()]
+        }
+        // Zed and Sublime accept `file:line:col` directly
+        _ => vec![target.to_string()],
+    }
+}
+
+/// Characters cmd.exe treats as syntax even inside quoted batch arguments
+#[cfg(any(target_os = "windows", test))]
+fn has_cmd_metacharacters(arg: &str) -> bool {
+    arg.contains(['&', '|', '<', '>', '^', '%', '!', '"', '\r', '\n'])
+}
+
+/// VS Code family editors ship `bin\<name>.cmd` shims; resolve them to the main executable
+/// in the install directory so no batch file is involved
+#[cfg(target_os = "windows")]
+fn resolve_known_editor_wrapper(wrapper: &Path) -> Option<PathBuf> {
+    let exe = match wrapper.file_stem()?.to_str()?.to_lowercase().as_str() {
+        "code" => "Code.exe",
+        "code-insiders" => "Code - Insiders.exe",
+        "cursor" => "Cursor.exe",
+        "windsurf" => "Windsurf.exe",
+        "codium" => "VSCodium.exe",
+        _ => return None,
+    };
+    wrapper
+        .ancestors()
+        .skip(1)
+        .take(4)
+        .map(|dir| dir.join(exe))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Resolve the editor command to the program to spawn on Windows
+///
+/// Executables are launched as-is and known `.cmd` shims are swapped for their executable.
+/// Other batch files are still run by cmd.exe internally, so arguments containing cmd
+/// metacharacters are refused for them.
+#[cfg(target_os = "windows")]
+fn resolve_editor_program(editor: &str, args: &[String]) -> Result<PathBuf, String> {
+    let resolved = find_command_in_path(editor)
+        .ok_or_else(|| format!("Editor '{}' not found in PATH", editor))?;
+    let is_batch = resolved
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cmd") || ext.eq_ignore_ascii_case("bat"));
+    if !is_batch {
+        return Ok(resolved);
+    }
+    if let Some(exe) = resolve_known_editor_wrapper(&resolved) {
+        return Ok(exe);
+    }
+    if args.iter().any(|arg| has_cmd_metacharacters(arg)) {
+        return Err(format!(
+            "Cannot open this path with '{}': it contains characters that are unsafe to pass to a batch file",
+            editor
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Pick the editor to launch: configured preference first, then known editors in PATH
+fn detect_editor(config: &EditorConfig) -> Option<String> {
+    if let Some(preferred) = config
+        .preferred_editor
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        if validate_command_in_path(preferred).unwrap_or(false) {
+            return Some(preferred.to_string());
+        }
+        log::warn!(
+            "Preferred editor '{}' not found, falling back to auto-detection",
+            preferred
+        );
+    }
+
+    KNOWN_EDITORS
+        .iter()
+        .find(|editor| validate_command_in_path(editor).unwrap_or(false))
+        .map(|editor| editor.to_string())
+}
+
+/// Open a file at a specific line (and optional column) in the user's editor
+///
+/// Falls back to `open_file_with_default_app` when no supporting editor is found.
+#[tauri::command]
+pub async fn open_file_at_line(path: String, line: u32, column: Option<u32>) -> Result<(), String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist: {}", path));
+    }
+
+    let config = load_editor_config().unwrap_or_default();
+    let Some(editor) = detect_editor(&config) else {
+        log::info!("No line-jump capable editor found, opening with default app");
+        return open_file_with_default_app(path).await;
+    };
+
+    let line = line.max(1);
+    let target = format!("{}:{}:{}", path, line, column.unwrap_or(1).max(1));
+    let args = build_editor_goto_args(&editor, &target);
+    log::info!("Opening {} with {} {:?}", path, editor, args);
+
+    // Never go through `cmd /C`: the jump target comes from the frontend and cmd.exe would
+    // interpret `&`, `|`, `^` etc. in it. Launch the resolved executable directly instead.
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = StdCommand::new(resolve_editor_program(&editor, &args)?);
+        cmd.args(&args);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = StdCommand::new(&editor);
+        cmd.args(&args);
+        cmd
+    };
+
+    cmd.spawn()
+        .map_err(|e| format!("Failed to launch editor '{}': {}", editor, e))?;
+
+    Ok(())
+}
+
+/// Get the editor preference
+#[tauri::command]
+pub async fn get_editor_config() -> Result<EditorConfig, String> {
+    load_editor_config()
+}
+
+/// Update the editor preference
+#[tauri::command]
+pub async fn update_editor_config(config: EditorConfig) -> Result<(), String> {
+    crate::utils::config_utils::save_json_config(&config, get_editor_config_path()?)?;
+    log::info!("Updated editor config: {:?}", config.preferred_editor);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goto_args_and_cmd_metacharacters() {
+        assert_eq!(
+            build_editor_goto_args("/usr/local/bin/code", "a.rs:3:1"),
+            vec!["-g", "a.rs:3:1"]
+        );
+        assert_eq!(build_editor_goto_args("zed", "a.rs:3:1"), vec!["a.rs:3:1"]);
+
+        assert!(!has_cmd_metacharacters(
+            "C:\\work\\my project\\main.rs:10:2"
+        ));
+        for unsafe_target in ["a.rs & calc.exe", "a|b", "%PATH%", "a^b", "x\"y"] {
+            assert!(has_cmd_metacharacters(unsafe_target), "{}", unsafe_target);
+        }
+    }
+
+    #[test]
+    fn file_uri_percent_encodes_each_component() {
+        assert_eq!(
+            file_uri(Path::new("/home/me/My Docs/100% done #1, final.txt")),
+            "file:///home/me/My%20Docs/100%25%20done%20%231%2C%20final.txt"
+        );
+        assert_eq!(file_uri(Path::new("/tmp/a.rs")), "file:///tmp/a.rs");
+    }
+}
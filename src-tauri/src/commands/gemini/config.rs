@@ -307,10 +307,19 @@ pub fn list_session_files(project_path: &str) -> Result<Vec<GeminiSessionInfo>,
                     }
                 }
 
+                let last_message_time = detail
+                    .messages
+                    .last()
+                    .and_then(|m| m.get("timestamp"))
+                    .and_then(|t| t.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or(detail.last_updated);
+
                 sessions.push(GeminiSessionInfo {
                     session_id: detail.session_id,
                     file_name,
                     start_time: detail.start_time,
+                    last_message_time,
                     first_message,
                 });
             }
@@ -323,6 +332,18 @@ pub fn list_session_files(project_path: &str) -> Result<Vec<GeminiSessionInfo>,
     Ok(sessions)
 }
 
+/// Most recently active session, compared by the actual time of its last message
+pub fn most_recent_session(sessions: Vec<GeminiSessionInfo>) -> Option<GeminiSessionInfo> {
+    sessions
+        .into_iter()
+        .filter_map(|session| {
+            let last = chrono::DateTime::parse_from_rfc3339(&session.last_message_time).ok()?;
+            Some((last, session))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, session)| session)
+}
+
 /// Read a complete session detail from chats/session-*.json
 pub fn read_session_detail(
     project_path: &str,
@@ -583,3 +604,40 @@ pub async fn set_gemini_wsl_mode_config(mode: String, wsl_distro: Option<String>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, start_time: &str, last_message_time: &str) -> GeminiSessionInfo {
+        GeminiSessionInfo {
+            session_id: id.to_string(),
+            file_name: format!("session-{}.json", id),
+            start_time: start_time.to_string(),
+            last_message_time: last_message_time.to_string(),
+            first_message: None,
+        }
+    }
+
+    #[test]
+    fn most_recent_session_uses_last_message_time() {
+        let sessions = vec![
+            // 最晚开始，但最后一条消息更早
+            session("late-start", "2025-01-02T00:00:00Z", "2025-01-02T00:05:00Z"),
+            // 较早开始，最近仍有消息
+            session(
+                "still-active",
+                "2025-01-01T00:00:00Z",
+                "2025-01-02T09:00:00+08:00",
+            ),
+            session("broken", "2025-01-03T00:00:00Z", "not a timestamp"),
+        ];
+        assert_eq!(
+            most_recent_session(sessions)
+                .map(|s| s.session_id)
+                .as_deref(),
+            Some("still-active")
+        );
+        assert!(most_recent_session(Vec::new()).is_none());
+    }
+}
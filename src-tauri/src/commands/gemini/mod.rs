@@ -38,7 +38,10 @@ pub use config::{
     set_gemini_wsl_mode_config,
     update_gemini_config,
 };
-pub use session::{cancel_gemini, check_gemini_installed, execute_gemini};
+pub use session::{
+    cancel_gemini, check_gemini_installed, execute_gemini, get_gemini_binary_info,
+    resume_last_gemini,
+};
 
 // Re-export Gemini Rewind commands
 pub use git_ops::{
@@ -49,8 +52,9 @@ pub use git_ops::{
 // Re-export Gemini Provider commands
 pub use provider::{
     add_gemini_provider_config, clear_gemini_provider_config, delete_gemini_provider_config,
-    get_current_gemini_provider_config, get_gemini_provider_presets, reorder_gemini_provider_configs,
-    switch_gemini_provider, test_gemini_provider_connection, update_gemini_provider_config,
+    get_current_gemini_provider_config, get_gemini_provider_presets,
+    reorder_gemini_provider_configs, switch_gemini_provider, test_gemini_provider_connection,
+    update_gemini_provider_config, validate_gemini_provider_config,
 };
 
 // Re-export Gemini Usage Statistics commands
@@ -60,7 +60,7 @@ fn get_gemini_settings_path() -> Result<PathBuf, String> {
 }
 
 /// Get Gemini providers.json path (for custom presets storage)
-fn get_gemini_providers_path() -> Result<PathBuf, String> {
+pub(crate) fn get_gemini_providers_path() -> Result<PathBuf, String> {
     let home = dirs::home_dir().ok_or("Failed to get home directory")?;
     Ok(home.join(".anycode").join("gemini_providers.json"))
 }
@@ -253,6 +253,12 @@ pub async fn switch_gemini_provider(config: GeminiProviderConfig) -> Result<Stri
             .map_err(|e| format!("Failed to create .gemini directory at {:?}: {}", gemini_dir, e))?;
     }
 
+    // Keys stored in the OS keychain are resolved before they are written to .env
+    let mut config = config;
+    for value in config.env.values_mut() {
+        *value = crate::commands::keychain::resolve_secret(value)?;
+    }
+
     // Read existing settings to preserve mcpServers and other user configs
     let mut settings = read_settings_file(&settings_path)?;
 
@@ -301,10 +307,96 @@ pub async fn switch_gemini_provider(config: GeminiProviderConfig) -> Result<Stri
     Ok(format!("成功切换到 Gemini 供应商: {}{}", config.name, mode_info))
 }
 
+/// Check a Gemini provider config, returning (errors, warnings)
+fn gemini_config_issues(config: &GeminiProviderConfig) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    // Keys are written to ~/.gemini/.env as KEY=value lines
+    for (key, value) in &config.env {
+        let valid_key = !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            errors.push(format!(
+                "'{}' is not a valid environment variable name",
+                key
+            ));
+        }
+        if value.contains('\n') {
+            errors.push(format!("{} must not contain line breaks", key));
+        }
+    }
+
+    let base_url = config.env.get("GOOGLE_GEMINI_BASE_URL");
+    if let Some(url) = base_url.filter(|url| !url.trim().is_empty()) {
+        if let Err(e) = crate::commands::url_utils::validate_http_url(url) {
+            errors.push(format!("GOOGLE_GEMINI_BASE_URL: {}", e));
+        }
+    }
+
+    if !config.is_official.unwrap_or(false) {
+        let has_api_key = ["GEMINI_API_KEY", "GOOGLE_API_KEY"]
+            .iter()
+            .any(|key| config.env.get(*key).is_some_and(|v| !v.trim().is_empty()));
+        if !has_api_key {
+            warnings.push("env has no API key (GEMINI_API_KEY)".to_string());
+        }
+        let has_model = config
+            .env
+            .get("GEMINI_MODEL")
+            .is_some_and(|v| !v.trim().is_empty());
+        if !has_model {
+            warnings.push("GEMINI_MODEL is not set".to_string());
+        }
+    }
+
+    (errors, warnings)
+}
+
+/// Move API keys into the OS keychain when keychain storage is enabled
+fn protect_gemini_secrets(mut config: GeminiProviderConfig) -> GeminiProviderConfig {
+    for key in ["GEMINI_API_KEY", "GOOGLE_API_KEY"] {
+        if let Some(value) = config.env.get_mut(key) {
+            *value = crate::commands::keychain::protect_secret("gemini", &config.id, key, value);
+        }
+    }
+    config
+}
+
+/// Reject configs with validation errors before they are saved
+fn ensure_valid_gemini_config(config: &GeminiProviderConfig) -> Result<(), String> {
+    let (errors, _) = gemini_config_issues(config);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid Gemini provider config: {}",
+            errors.join("; ")
+        ))
+    }
+}
+
+/// Validate a Gemini provider config without saving or switching to it.
+/// Returns issues prefixed with "error:" (config would be rejected) or "warning:"
+#[tauri::command]
+pub async fn validate_gemini_provider_config(
+    config: GeminiProviderConfig,
+) -> Result<Vec<String>, String> {
+    let (errors, warnings) = gemini_config_issues(&config);
+    Ok(errors
+        .into_iter()
+        .map(|e| format!("error: {}", e))
+        .chain(warnings.into_iter().map(|w| format!("warning: {}", w)))
+        .collect())
+}
+
 /// Add a new Gemini provider configuration
 #[tauri::command]
 pub async fn add_gemini_provider_config(config: GeminiProviderConfig) -> Result<String, String> {
     log::info!("[Gemini Provider] Adding provider: {}", config.name);
+    ensure_valid_gemini_config(&config)?;
+    let config = protect_gemini_secrets(config);
 
     let providers_path = get_gemini_providers_path()?;
 
@@ -348,6 +440,8 @@ pub async fn add_gemini_provider_config(config: GeminiProviderConfig) -> Result<
 #[tauri::command]
 pub async fn update_gemini_provider_config(config: GeminiProviderConfig) -> Result<String, String> {
     log::info!("[Gemini Provider] Updating provider: {}", config.name);
+    ensure_valid_gemini_config(&config)?;
+    let config = protect_gemini_secrets(config);
 
     let providers_path = get_gemini_providers_path()?;
 
@@ -398,18 +492,18 @@ pub async fn delete_gemini_provider_config(id: String) -> Result<String, String>
         .map_err(|e| format!("Failed to parse providers.json: {}", e))?;
 
     // Find and remove the provider
-    let initial_len = providers.len();
-    providers.retain(|p| p.id != id);
-
-    if providers.len() == initial_len {
-        return Err(format!("Provider with ID '{}' not found", id));
-    }
+    let index = providers
+        .iter()
+        .position(|p| p.id == id)
+        .ok_or_else(|| format!("Provider with ID '{}' not found", id))?;
+    let deleted = providers.remove(index);
 
     // Save providers
     let content = serde_json::to_string_pretty(&providers)
         .map_err(|e| format!("Failed to serialize providers: {}", e))?;
     fs::write(&providers_path, content)
         .map_err(|e| format!("Failed to write providers.json: {}", e))?;
+    crate::commands::keychain::delete_provider_secrets(&deleted);
 
     log::info!("[Gemini Provider] Successfully deleted provider: {}", id);
     Ok(format!("成功删除 Gemini 供应商: {}", id))
@@ -496,6 +590,7 @@ pub async fn test_gemini_provider_connection(
 
     if let Some(key) = api_key {
         // Gemini API uses x-goog-api-key header
+        let key = crate::commands::keychain::resolve_secret(&key)?;
         request = request.header("x-goog-api-key", key);
     }
 
@@ -5,20 +5,28 @@
 
 use std::process::Stdio;
 
+use once_cell::sync::Lazy;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::OnceCell;
+use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 
-use super::config::{build_gemini_env, load_gemini_config, read_session_detail};
+use super::config::{
+    build_gemini_env, list_session_files, load_gemini_config, most_recent_session,
+    read_session_detail,
+};
 use super::parser::{
     convert_raw_to_unified_message, convert_to_unified_message, parse_gemini_line,
     parse_gemini_line_flexible,
 };
 use super::types::{GeminiExecutionOptions, GeminiInstallStatus, GeminiProcessHandle, GeminiProcessState, GeminiSessionDetail, TokenUsage};
-use crate::claude_binary::detect_binary_for_tool;
-use crate::commands::claude::apply_no_window_async;
+use crate::claude_binary::{
+    detect_binary_for_tool, infer_installation_source, ClaudeInstallation, InstallationType,
+};
+use crate::commands::agent::{self, AgentTool};
+use crate::commands::auth_detection::{self, AuthRequiredDetector};
+use crate::commands::claude::{apply_no_window_async, LaunchCommand};
 use crate::commands::wsl_utils;
 use crate::process::JobObject;
 
@@ -38,8 +46,9 @@ fn is_slash_command(prompt: &str) -> bool {
 }
 
 /// 全局 Gemini 安装状态缓存
-/// 避免重复创建 WSL 进程检测安装状态
-static GEMINI_INSTALL_STATUS_CACHE: OnceCell<GeminiInstallStatus> = OnceCell::const_new();
+/// 避免重复创建 WSL 进程检测安装状态；登录 / 更新后清空以重新检测
+static GEMINI_INSTALL_STATUS_CACHE: Lazy<Mutex<Option<GeminiInstallStatus>>> =
+    Lazy::new(|| Mutex::new(None));
 
 fn token_usage_has_data(usage: &TokenUsage) -> bool {
     usage.prompt_token_count.unwrap_or(0) > 0
@@ -283,6 +292,28 @@ pub fn find_gemini_binary() -> Result<String, String> {
     Err("Gemini CLI not found. Install with: npm install -g @google/gemini-cli".to_string())
 }
 
+/// Get the selected Gemini CLI installation (path, version, source and installation type)
+#[tauri::command]
+pub async fn get_gemini_binary_info() -> Result<ClaudeInstallation, String> {
+    let path = find_gemini_binary()?;
+    // find_gemini_binary 只返回路径，这里按相同的查找顺序还原来源
+    let source = if path.starts_with("WSL:") {
+        "wsl".to_string()
+    } else if std::env::var("GEMINI_CLI_PATH").is_ok_and(|env_path| env_path == path) {
+        "GEMINI_CLI_PATH".to_string()
+    } else {
+        infer_installation_source(&path)
+            .unwrap_or("PATH")
+            .to_string()
+    };
+    Ok(ClaudeInstallation {
+        version: get_gemini_version(&path),
+        path,
+        source,
+        installation_type: InstallationType::System,
+    })
+}
+
 /// Get Gemini CLI version
 pub fn get_gemini_version(gemini_path: &str) -> Option<String> {
     // Check if this is a WSL path
@@ -340,15 +371,24 @@ fn test_gemini_binary(path: &str) -> bool {
 #[tauri::command]
 pub async fn check_gemini_installed() -> Result<GeminiInstallStatus, String> {
     // 使用缓存避免重复检测
-    let result = GEMINI_INSTALL_STATUS_CACHE
-        .get_or_init(|| async {
+    let mut cache = GEMINI_INSTALL_STATUS_CACHE.lock().await;
+    let result = match cache.as_ref() {
+        Some(result) => result.clone(),
+        None => {
             log::info!("[Gemini] Checking installation status (first time)...");
-            do_check_gemini_installed()
-        })
-        .await;
+            let result = do_check_gemini_installed();
+            *cache = Some(result.clone());
+            result
+        }
+    };
 
     log::debug!("[Gemini] Returning cached install status: {:?}", result);
-    Ok(result.clone())
+    Ok(result)
+}
+
+/// 清空安装状态缓存，下次检查时重新检测
+pub(crate) async fn invalidate_gemini_install_cache() {
+    *GEMINI_INSTALL_STATUS_CACHE.lock().await = None;
 }
 
 /// 实际执行 Gemini 安装检测（内部函数）
@@ -407,6 +447,110 @@ pub async fn execute_gemini(
         options.prompt.len()
     );
 
+    let (cmd, model) = build_gemini_command(&options)?;
+
+    // Execute process with prompt via stdin
+    execute_gemini_process(
+        cmd,
+        options.project_path,
+        model.clone(),
+        Some(options.prompt),
+        app_handle,
+    )
+    .await
+}
+
+/// Resume the most recent Gemini session of a project (starts a new one when none exists); returns the resumed session id
+#[tauri::command]
+pub async fn resume_last_gemini(
+    mut options: GeminiExecutionOptions,
+    app_handle: AppHandle,
+) -> Result<Option<String>, String> {
+    // 按最后一条消息的时间选择，而不是会话开始时间
+    let last_session = most_recent_session(list_session_files(&options.project_path)?)
+        .map(|session| session.session_id);
+
+    match &last_session {
+        Some(session_id) => log::info!(
+            "Resuming last Gemini session {} in {}",
+            session_id,
+            options.project_path
+        ),
+        None => log::info!(
+            "No previous Gemini session in {}, starting a new one",
+            options.project_path
+        ),
+    }
+    options.session_id = last_session.clone();
+    execute_gemini(options, app_handle).await?;
+    Ok(last_session)
+}
+
+/// Cancel a running Gemini execution
+#[tauri::command]
+pub async fn cancel_gemini(
+    session_id: Option<String>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    log::info!("cancel_gemini called for session: {:?}", session_id);
+
+    let state: tauri::State<'_, GeminiProcessState> = app_handle.state();
+    let mut processes = state.processes.lock().await;
+
+    if let Some(sid) = session_id {
+        // Cancel specific session
+        if let Some(mut handle) = processes.remove(&sid) {
+            // Kill the process - JobObject will automatically terminate all child processes when dropped
+            handle
+                .child
+                .kill()
+                .await
+                .map_err(|e| format!("Failed to kill process: {}", e))?;
+            log::info!(
+                "Killed Gemini process for session: {} (PID: {})",
+                sid,
+                handle.pid
+            );
+
+            // JobObject is dropped here, killing all child processes (MCP servers, node.exe, etc.)
+            drop(handle.job_object);
+
+            // Emit cancellation event
+            let _ = app_handle.emit(&format!("gemini-cancelled:{}", sid), true);
+            let _ = app_handle.emit("gemini-cancelled", true);
+        } else {
+            log::warn!("No running process found for session: {}", sid);
+        }
+    } else {
+        // Cancel all processes
+        for (sid, mut handle) in processes.drain() {
+            if let Err(e) = handle.child.kill().await {
+                log::error!("Failed to kill process for session {}: {}", sid, e);
+            } else {
+                log::info!(
+                    "Killed Gemini process for session: {} (PID: {})",
+                    sid,
+                    handle.pid
+                );
+            }
+            // JobObject is dropped here, killing all child processes
+            drop(handle.job_object);
+        }
+        let _ = app_handle.emit("gemini-cancelled", true);
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Process Execution
+// ============================================================================
+
+/// Builds the Gemini command (native or WSL) for the given options without spawning it;
+/// returns the command and the resolved model
+pub(crate) fn build_gemini_command(
+    options: &GeminiExecutionOptions,
+) -> Result<(Command, String), String> {
     // Find Gemini binary
     let gemini_path = find_gemini_binary()?;
     let is_wsl = gemini_path.starts_with("WSL:");
@@ -426,8 +570,15 @@ pub async fn execute_gemini(
         args.push("latest".to_string());
     }
 
-    // Add model if specified (or use default from config)
-    let model = options.model.as_ref().unwrap_or(&config.default_model);
+    // Add model if specified (or project override, or default from config)
+    let model = options
+        .model
+        .clone()
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| {
+            crate::commands::claude::project_model_override(&options.project_path, "gemini")
+        })
+        .unwrap_or_else(|| config.default_model.clone());
     args.push("--model".to_string());
     args.push(model.clone());
 
@@ -531,69 +682,22 @@ pub async fn execute_gemini(
         cmd
     };
 
-    // Execute process with prompt via stdin
-    execute_gemini_process(
-        cmd,
-        options.project_path,
-        model.clone(),
-        Some(options.prompt),
-        app_handle,
-    )
-    .await
+    Ok((cmd, model))
 }
 
-/// Cancel a running Gemini execution
-#[tauri::command]
-pub async fn cancel_gemini(
-    session_id: Option<String>,
-    app_handle: AppHandle,
-) -> Result<(), String> {
-    log::info!("cancel_gemini called for session: {:?}", session_id);
-
-    let state: tauri::State<'_, GeminiProcessState> = app_handle.state();
-    let mut processes = state.processes.lock().await;
-
-    if let Some(sid) = session_id {
-        // Cancel specific session
-        if let Some(mut handle) = processes.remove(&sid) {
-            // Kill the process - JobObject will automatically terminate all child processes when dropped
-            handle
-                .child
-                .kill()
-                .await
-                .map_err(|e| format!("Failed to kill process: {}", e))?;
-            log::info!("Killed Gemini process for session: {} (PID: {})", sid, handle.pid);
-
-            // JobObject is dropped here, killing all child processes (MCP servers, node.exe, etc.)
-            drop(handle.job_object);
-
-            // Emit cancellation event
-            let _ = app_handle.emit(&format!("gemini-cancelled:{}", sid), true);
-            let _ = app_handle.emit("gemini-cancelled", true);
-        } else {
-            log::warn!("No running process found for session: {}", sid);
-        }
-    } else {
-        // Cancel all processes
-        for (sid, mut handle) in processes.drain() {
-            if let Err(e) = handle.child.kill().await {
-                log::error!("Failed to kill process for session {}: {}", sid, e);
-            } else {
-                log::info!("Killed Gemini process for session: {} (PID: {})", sid, handle.pid);
-            }
-            // JobObject is dropped here, killing all child processes
-            drop(handle.job_object);
-        }
-        let _ = app_handle.emit("gemini-cancelled", true);
+/// Passes slash commands via `-p` so Gemini CLI parses them; returns whether it did
+pub(crate) fn apply_slash_command_flag(cmd: &mut Command, prompt: &str) -> bool {
+    // 🔥 关键修复：检测斜杠命令，通过 -p 参数传递以触发命令解析
+    // Gemini CLI 在非交互模式下支持斜杠命令（自 v0.1.59 起，PR #8305）
+    if !is_slash_command(prompt) {
+        return false;
     }
-
-    Ok(())
+    log::info!("Detected slash command, using -p flag: {}", prompt.trim());
+    cmd.arg("-p");
+    cmd.arg(prompt);
+    true
 }
 
-// ============================================================================
-// Process Execution
-// ============================================================================
-
 /// Execute a Gemini process and stream output to frontend
 ///
 /// 🔥 斜杠命令支持：斜杠命令通过 -p 参数传递（触发命令解析），普通 prompt 通过 stdin 管道传递
@@ -605,23 +709,9 @@ async fn execute_gemini_process(
     prompt: Option<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
-    // 🔥 关键修复：检测斜杠命令，通过 -p 参数传递以触发命令解析
-    // Gemini CLI 在非交互模式下支持斜杠命令（自 v0.1.59 起，PR #8305）
     let use_p_flag = prompt
-        .as_ref()
-        .map(|p| is_slash_command(p))
-        .unwrap_or(false);
-
-    if use_p_flag {
-        if let Some(ref prompt_text) = prompt {
-            log::info!(
-                "Detected slash command, using -p flag: {}",
-                prompt_text.trim()
-            );
-            cmd.arg("-p");
-            cmd.arg(prompt_text);
-        }
-    }
+        .as_deref()
+        .is_some_and(|prompt_text| apply_slash_command_flag(&mut cmd, prompt_text));
 
     // Setup stdio - use piped stdin to pass prompt (supports multiline content)
     cmd.stdin(Stdio::piped());
@@ -631,6 +721,19 @@ async fn execute_gemini_process(
     // Apply platform-specific no-window configuration
     apply_no_window_async(&mut cmd);
 
+    // 记录最终的启动命令，随 init 事件一起发送便于排查参数问题
+    let launch = LaunchCommand::from_command(&cmd);
+
+    // 并发限制：名额在进程退出（或被取消）后的完成任务结束时释放
+    let session_permit = crate::commands::session_limits::acquire_session_permit(
+        &app_handle,
+        "gemini",
+        &project_path,
+        None,
+        None,
+    )
+    .await?;
+
     // Spawn process
     let mut child = cmd
         .spawn()
@@ -710,6 +813,7 @@ async fn execute_gemini_process(
             child,
             pid,
             job_object,
+            project_path: project_path.clone(),
         };
         processes.insert(session_id.clone(), handle);
 
@@ -724,6 +828,9 @@ async fn execute_gemini_process(
         "session_id": session_id,
         "model": model,
         "project_path": project_path,
+        "binary": launch.binary,
+        "args": launch.args,
+        "env": launch.env,
         "geminiMetadata": {
             "provider": "gemini",
             "eventType": "session_init"
@@ -733,11 +840,25 @@ async fn execute_gemini_process(
     if let Err(e) = app_handle.emit("gemini-session-init", &init_payload) {
         log::error!("Failed to emit gemini-session-init: {}", e);
     }
+    agent::emit_agent_session_state(
+        &app_handle,
+        AgentTool::Gemini,
+        Some(&session_id),
+        &init_payload,
+    );
 
     // Also emit as gemini-output for unified handling
     let init_line = serde_json::to_string(&init_payload).unwrap_or_default();
     let _ = app_handle.emit(&format!("gemini-output:{}", session_id), &init_line);
     let _ = app_handle.emit("gemini-output", &init_line);
+    agent::emit_agent_output(
+        &app_handle,
+        AgentTool::Gemini,
+        Some(&session_id),
+        None,
+        None,
+        &init_line,
+    );
 
     log::info!("Gemini session initialized with ID: {}", session_id);
 
@@ -752,6 +873,11 @@ async fn execute_gemini_process(
     let session_id_stdout = session_id.clone();
     let session_id_stderr = session_id.clone();
     let session_id_complete = session_id.clone();
+    // stdout 与 stderr 共用一个登录提示检测器
+    let auth_detector = std::sync::Arc::new(std::sync::Mutex::new(AuthRequiredDetector::new(
+        AgentTool::Gemini,
+    )));
+    let auth_detector_for_stderr = auth_detector.clone();
 
     // Spawn task to read stdout (JSONL events)
     let model_for_messages = model.clone();
@@ -771,6 +897,16 @@ async fn execute_gemini_process(
 
             // Use trace level to avoid flooding logs in debug mode
             log::trace!("Gemini output: {}", line);
+            if auth_detector.lock().unwrap().check(&line) {
+                auth_detection::handle_auth_required(
+                    &app_handle_stdout,
+                    AgentTool::Gemini,
+                    Some(&session_id_stdout),
+                    None,
+                    pid,
+                    &line,
+                );
+            }
 
             // Try to parse and convert to unified format
             let mut unified_message = if let Ok(mut event) = parse_gemini_line(&line) {
@@ -967,6 +1103,14 @@ async fn execute_gemini_process(
             if let Err(e) = app_handle_stdout.emit("gemini-output", &unified_line) {
                 log::error!("Failed to emit gemini-output (global): {}", e);
             }
+            agent::emit_agent_output(
+                &app_handle_stdout,
+                AgentTool::Gemini,
+                Some(&session_id_stdout),
+                None,
+                None,
+                &unified_line,
+            );
         }
 
         log::info!("[Gemini] Stdout closed for session: {}", session_id_stdout);
@@ -981,6 +1125,16 @@ async fn execute_gemini_process(
         while let Ok(Some(line)) = reader.next_line().await {
             if !line.trim().is_empty() {
                 log::warn!("Gemini stderr: {}", line);
+                if auth_detector_for_stderr.lock().unwrap().check(&line) {
+                    auth_detection::handle_auth_required(
+                        &app_handle_stderr,
+                        AgentTool::Gemini,
+                        Some(&session_id_stderr),
+                        None,
+                        pid,
+                        &line,
+                    );
+                }
 
                 // Emit stderr as error event
                 let error_message = serde_json::json!({
@@ -1000,6 +1154,13 @@ async fn execute_gemini_process(
                 let _ = app_handle_stderr
                     .emit(&format!("gemini-error:{}", session_id_stderr), &error_line);
                 let _ = app_handle_stderr.emit("gemini-error", &error_line);
+                agent::emit_agent_error(
+                    &app_handle_stderr,
+                    AgentTool::Gemini,
+                    Some(&session_id_stderr),
+                    None,
+                    &error_line,
+                );
             }
         }
 
@@ -1014,6 +1175,7 @@ async fn execute_gemini_process(
     let processes_complete = state_complete.processes.clone();
 
     tokio::spawn(async move {
+        let _session_permit = session_permit;
         // Wait for both stdout and stderr to close
         let _ = tokio::join!(stdout_done_rx, stderr_done_rx);
         log::info!(
@@ -1094,10 +1256,35 @@ async fn execute_gemini_process(
             &complete_line,
         );
         let _ = app_handle_complete.emit("gemini-output", &complete_line);
+        agent::emit_agent_output(
+            &app_handle_complete,
+            AgentTool::Gemini,
+            Some(&session_id_complete),
+            None,
+            None,
+            &complete_line,
+        );
 
         let _ =
             app_handle_complete.emit(&format!("gemini-complete:{}", session_id_complete), success);
         let _ = app_handle_complete.emit("gemini-complete", success);
+        agent::emit_agent_session_state(
+            &app_handle_complete,
+            AgentTool::Gemini,
+            Some(&session_id_complete),
+            &serde_json::json!({
+                "session_id": session_id_complete,
+                "status": "stopped",
+                "success": success,
+            }),
+        );
+        agent::emit_agent_complete(
+            &app_handle_complete,
+            AgentTool::Gemini,
+            Some(&session_id_complete),
+            None,
+            success,
+        );
     });
 
     Ok(())
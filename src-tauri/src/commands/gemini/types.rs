@@ -342,6 +342,8 @@ pub struct GeminiProcessHandle {
     pub pid: u32,
     /// Windows Job Object (kills all child processes when dropped); no-op on non-Windows.
     pub job_object: Option<JobObject>,
+    /// Project the session was started in
+    pub project_path: String,
 }
 
 /// Global state to track Gemini processes
@@ -407,5 +409,7 @@ pub struct GeminiSessionInfo {
     pub session_id: String,
     pub file_name: String,
     pub start_time: String,
+    /// Timestamp of the last message (falls back to the file's lastUpdated)
+    pub last_message_time: String,
     pub first_message: Option<String>,
 }
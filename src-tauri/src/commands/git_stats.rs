@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command as StdCommand;
 
+use super::simple_git::is_git_repo;
+
 /// Git 代码变更统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -84,3 +87,254 @@ pub async fn get_session_code_changes(
 ) -> Result<GitDiffStats, String> {
     get_git_diff_stats(project_path, session_start_commit, None).await
 }
+
+/// 项目的 Git 状态（用于项目标题栏显示）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// 项目目录是否位于 Git 仓库中；为 false 时其余字段均为默认值
+    pub is_repo: bool,
+    /// 当前分支；detached HEAD 时为 None
+    pub branch: Option<String>,
+    /// 是否处于 detached HEAD
+    pub detached: bool,
+    /// 当前 HEAD 的 commit（尚无提交时为 None）
+    pub head_commit: Option<String>,
+    /// 上游分支；未设置时为 None
+    pub upstream: Option<String>,
+    /// 领先上游的提交数
+    pub ahead: usize,
+    /// 落后上游的提交数
+    pub behind: usize,
+    /// 已暂存的文件数
+    pub staged: usize,
+    /// 未暂存的修改文件数
+    pub unstaged: usize,
+    /// 未跟踪的文件数
+    pub untracked: usize,
+    /// 存在冲突的文件数
+    pub conflicted: usize,
+    /// 是否正在 rebase
+    pub rebase_in_progress: bool,
+    /// 是否正在 merge
+    pub merge_in_progress: bool,
+}
+
+/// 在项目目录执行 git 命令并返回 stdout
+fn run_git(project_path: &str, args: &[&str]) -> Result<String, String> {
+    let mut cmd = StdCommand::new("git");
+    cmd.current_dir(project_path);
+    cmd.args(args);
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute git {}: {}", args[0], e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 已暂存（index）相对 HEAD 的 diff，即下一次提交将包含的内容
+pub(crate) fn staged_diff(project_path: &str) -> Result<String, String> {
+    run_git(project_path, &["diff", "--cached"])
+}
+
+/// 只提交已暂存的内容（不执行 add，也不创建空提交），返回新提交的 hash
+pub(crate) fn commit_staged(project_path: &str, message: &str) -> Result<String, String> {
+    run_git(project_path, &["commit", "-m", message])?;
+    Ok(run_git(project_path, &["rev-parse", "HEAD"])?
+        .trim()
+        .to_string())
+}
+
+/// 解析 `git status --porcelain=v2 --branch` 的输出
+fn parse_porcelain_status(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => {
+                    status.head_commit = Some(value.to_string())
+                }
+                "branch.head" if value == "(detached)" => status.detached = true,
+                "branch.head" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    // 格式：+<ahead> -<behind>
+                    for part in value.split_whitespace() {
+                        if let Some(ahead) = part.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = part.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let mut fields = line.splitn(3, ' ');
+        match (fields.next(), fields.next()) {
+            (Some("1" | "2"), Some(xy)) => {
+                let mut flags = xy.chars();
+                if flags.next().is_some_and(|x| x != '.') {
+                    status.staged += 1;
+                }
+                if flags.next().is_some_and(|y| y != '.') {
+                    status.unstaged += 1;
+                }
+            }
+            (Some("u"), _) => status.conflicted += 1,
+            (Some("?"), _) => status.untracked += 1,
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// 获取项目的 Git 状态：分支、与上游的领先/落后、文件变更计数以及 rebase/merge 状态
+///
+/// 只读取状态，不会初始化仓库或创建提交；不在仓库中时返回 `is_repo: false`
+#[tauri::command]
+pub async fn get_git_status(project_path: String) -> Result<GitStatus, String> {
+    let inside_repo = is_git_repo(&project_path)
+        || run_git(&project_path, &["rev-parse", "--is-inside-work-tree"]).is_ok();
+    if !inside_repo {
+        return Ok(GitStatus::default());
+    }
+    read_git_status(&project_path)
+}
+
+fn read_git_status(project_path: &str) -> Result<GitStatus, String> {
+    let output = run_git(project_path, &["status", "--porcelain=v2", "--branch"])?;
+    let mut status = parse_porcelain_status(&output);
+    status.is_repo = true;
+
+    let git_dir = run_git(project_path, &["rev-parse", "--git-dir"])?;
+    let git_dir = Path::new(project_path).join(git_dir.trim());
+    status.rebase_in_progress =
+        git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists();
+    status.merge_in_progress = git_dir.join("MERGE_HEAD").exists();
+
+    Ok(status)
+}
+
+/// 将路径解析为绝对路径（按词法处理 `..`，文件可以不存在），并确认其位于仓库内
+fn resolve_repo_paths(project_path: &str, paths: &[String]) -> Result<Vec<String>, String> {
+    if paths.is_empty() {
+        return Err("No paths given".to_string());
+    }
+    let base = std::fs::canonicalize(project_path)
+        .map_err(|e| format!("Invalid project path {}: {}", project_path, e))?;
+    let toplevel = run_git(project_path, &["rev-parse", "--show-toplevel"])?;
+    let repo_root = std::fs::canonicalize(toplevel.trim())
+        .map_err(|e| format!("Failed to resolve repository root: {}", e))?;
+
+    paths
+        .iter()
+        .map(|path| {
+            let mut resolved = PathBuf::new();
+            for component in base.join(path).components() {
+                match component {
+                    Component::CurDir => {}
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    other => resolved.push(other),
+                }
+            }
+            if !resolved.starts_with(&repo_root) {
+                return Err(format!("Path is outside the repository: {}", path));
+            }
+            Ok(resolved.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+/// 暂存指定文件（包括删除），返回暂存后的 Git 状态
+#[tauri::command]
+pub async fn git_stage_files(
+    project_path: String,
+    paths: Vec<String>,
+) -> Result<GitStatus, String> {
+    let paths = resolve_repo_paths(&project_path, &paths)?;
+    let mut args = vec!["add", "-A", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    run_git(&project_path, &args)?;
+    read_git_status(&project_path)
+}
+
+/// 取消暂存指定文件（工作区内容保持不变），返回更新后的 Git 状态
+#[tauri::command]
+pub async fn git_unstage_files(
+    project_path: String,
+    paths: Vec<String>,
+) -> Result<GitStatus, String> {
+    let paths = resolve_repo_paths(&project_path, &paths)?;
+    // 尚无提交时没有 HEAD 可供 reset，直接从索引中移除
+    let mut args = if run_git(&project_path, &["rev-parse", "--verify", "HEAD"]).is_ok() {
+        vec!["reset", "-q", "HEAD", "--"]
+    } else {
+        vec!["rm", "--cached", "-r", "-q", "--"]
+    };
+    args.extend(paths.iter().map(String::as_str));
+    run_git(&project_path, &args)?;
+    read_git_status(&project_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_porcelain_v2_status() {
+        let output = "# branch.oid 1234abcd\n\
+# branch.head main\n\
+# branch.upstream origin/main\n\
+# branch.ab +2 -1\n\
+1 M. N... 100644 100644 100644 aaa bbb src/lib.rs\n\
+1 .M N... 100644 100644 100644 aaa bbb src/main.rs\n\
+1 MM N... 100644 100644 100644 aaa bbb README.md\n\
+u UU N... 100644 100644 100644 100644 aaa bbb ccc Cargo.lock\n\
+? notes.txt\n";
+        let status = parse_porcelain_status(output);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!((status.staged, status.unstaged), (2, 2));
+        assert_eq!((status.untracked, status.conflicted), (1, 1));
+
+        let detached = parse_porcelain_status("# branch.oid 1234abcd\n# branch.head (detached)\n");
+        assert!(detached.detached);
+        assert!(detached.branch.is_none() && detached.upstream.is_none());
+    }
+
+    #[tokio::test]
+    async fn status_of_non_repository_does_not_init() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("file.txt"), "x").unwrap();
+
+        let status = get_git_status(dir.path().to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert!(!status.is_repo);
+        assert!(!dir.path().join(".git").exists());
+    }
+}
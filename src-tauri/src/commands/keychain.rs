@@ -0,0 +1,298 @@
+//! OS keychain storage for provider API keys
+//!
+//! When enabled, provider configs keep a `keychain:<tool>/<provider_id>/<field>`
+//! reference instead of the plaintext key; the key is resolved when it is used.
+//! Platforms without a usable keychain fall back to plaintext with a warning.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// Keychain service name under which all provider keys are stored
+const KEYCHAIN_SERVICE: &str = "any-code";
+/// Prefix of the references written to provider configs
+const KEYCHAIN_REF_PREFIX: &str = "keychain:";
+
+/// Secret-bearing fields per tool: (parent object, keys); `None` means top-level fields
+const CLAUDE_SECRET_FIELDS: (Option<&str>, &[&str]) = (None, &["auth_token", "api_key"]);
+const CODEX_SECRET_FIELDS: (Option<&str>, &[&str]) =
+    (Some("auth"), &["OPENAI_API_KEY", "OPENAI_KEY", "API_KEY"]);
+const GEMINI_SECRET_FIELDS: (Option<&str>, &[&str]) =
+    (Some("env"), &["GEMINI_API_KEY", "GOOGLE_API_KEY"]);
+
+/// Keychain preference (~/.anycode/keychain.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Keychain preference together with whether the OS keychain can be used
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainStatus {
+    pub enabled: bool,
+    pub available: bool,
+}
+
+/// Number of keys moved into the keychain per tool
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainMigrationReport {
+    pub claude: usize,
+    pub codex: usize,
+    pub gemini: usize,
+}
+
+fn get_keychain_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("keychain.json"))
+}
+
+fn load_keychain_config() -> KeychainConfig {
+    get_keychain_config_path()
+        .and_then(load_json_config)
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to load keychain config: {}", e);
+            KeychainConfig::default()
+        })
+}
+
+fn entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, account)
+        .map_err(|e| format!("Failed to open keychain entry '{}': {}", account, e))
+}
+
+/// Whether the OS keychain backend can be reached
+fn keychain_available() -> bool {
+    match entry("__availability_probe__").map(|e| e.get_password()) {
+        Ok(Ok(_)) | Ok(Err(keyring::Error::NoEntry)) => true,
+        Ok(Err(e)) => {
+            log::warn!("OS keychain unavailable: {}", e);
+            false
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            false
+        }
+    }
+}
+
+/// Whether `value` is a keychain reference rather than a plaintext secret
+pub(crate) fn is_keychain_ref(value: &str) -> bool {
+    value.starts_with(KEYCHAIN_REF_PREFIX)
+}
+
+/// Store `secret` in the keychain and return the reference to keep in the config
+fn store_secret(
+    tool: &str,
+    provider_id: &str,
+    field: &str,
+    secret: &str,
+) -> Result<String, String> {
+    let account = format!("{}/{}/{}", tool, provider_id, field);
+    entry(&account)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store '{}' in keychain: {}", account, e))?;
+    Ok(format!("{}{}", KEYCHAIN_REF_PREFIX, account))
+}
+
+/// Whether a plaintext value should be moved into the keychain
+/// (`${VAR}` references stay as they are; they hold no secret)
+fn should_protect(value: &str) -> bool {
+    !value.trim().is_empty() && !is_keychain_ref(value) && !value.contains("${")
+}
+
+/// Replace a plaintext secret with a keychain reference when the keychain is enabled.
+/// Falls back to the plaintext value (with a warning) if the keychain fails
+pub(crate) fn protect_secret(tool: &str, provider_id: &str, field: &str, value: &str) -> String {
+    if !should_protect(value) || !load_keychain_config().enabled {
+        return value.to_string();
+    }
+    store_secret(tool, provider_id, field, value).unwrap_or_else(|e| {
+        log::warn!("{}; keeping the key in plaintext", e);
+        value.to_string()
+    })
+}
+
+/// Resolve a keychain reference to the stored secret; other values are returned unchanged
+pub(crate) fn resolve_secret(value: &str) -> Result<String, String> {
+    let Some(account) = value.strip_prefix(KEYCHAIN_REF_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    entry(account)?
+        .get_password()
+        .map_err(|e| format!("Failed to read '{}' from keychain: {}", account, e))
+}
+
+/// Keychain references anywhere in a provider config (e.g. a deleted provider)
+pub(crate) fn keychain_refs(config: &Value) -> Vec<String> {
+    match config {
+        Value::String(value) if is_keychain_ref(value) => vec![value.clone()],
+        Value::Array(items) => items.iter().flat_map(keychain_refs).collect(),
+        Value::Object(fields) => fields.values().flat_map(keychain_refs).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Remove the keychain entries referenced by a provider config that is being deleted.
+/// Failures are logged; a leftover entry must not block deleting the provider
+pub(crate) fn delete_provider_secrets(config: &impl Serialize) {
+    let Ok(config) = serde_json::to_value(config) else {
+        return;
+    };
+    for reference in keychain_refs(&config) {
+        let Some(account) = reference.strip_prefix(KEYCHAIN_REF_PREFIX) else {
+            continue;
+        };
+        match entry(account).map(|e| e.delete_credential()) {
+            Ok(Ok(())) | Ok(Err(keyring::Error::NoEntry)) => {
+                log::info!("Removed '{}' from keychain", account)
+            }
+            Ok(Err(e)) => log::warn!("Failed to remove '{}' from keychain: {}", account, e),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+}
+
+/// Move plaintext secrets of every provider in a providers file into the keychain
+fn migrate_providers_file(
+    tool: &str,
+    path: PathBuf,
+    (parent, keys): (Option<&str>, &[&str]),
+) -> Result<usize, String> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut providers: Vec<Value> = load_json_config(&path)?;
+    let mut migrated = 0;
+
+    for provider in providers.iter_mut() {
+        let Some(id) = provider
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let fields = match parent {
+            Some(parent) => provider.get_mut(parent),
+            None => Some(provider),
+        };
+        let Some(fields) = fields.and_then(|v| v.as_object_mut()) else {
+            continue;
+        };
+        for key in keys {
+            let Some(Value::String(value)) = fields.get_mut(*key) else {
+                continue;
+            };
+            if should_protect(value) {
+                *value = store_secret(tool, &id, key, value)?;
+                migrated += 1;
+            }
+        }
+    }
+
+    if migrated > 0 {
+        save_json_config(&providers, &path)?;
+    }
+    Ok(migrated)
+}
+
+/// Get the keychain preference and whether the OS keychain is usable
+#[tauri::command]
+pub async fn get_keychain_status() -> Result<KeychainStatus, String> {
+    Ok(KeychainStatus {
+        enabled: load_keychain_config().enabled,
+        available: keychain_available(),
+    })
+}
+
+/// Enable or disable storing new provider API keys in the OS keychain
+#[tauri::command]
+pub async fn set_keychain_enabled(enabled: bool) -> Result<KeychainStatus, String> {
+    let available = keychain_available();
+    if enabled && !available {
+        log::warn!("Keychain enabled but unavailable; API keys will stay in plaintext");
+    }
+    save_json_config(&KeychainConfig { enabled }, get_keychain_config_path()?)?;
+    Ok(KeychainStatus { enabled, available })
+}
+
+/// Move existing plaintext provider API keys (Claude, Codex, Gemini) into the OS keychain
+/// and enable keychain storage for new keys
+#[tauri::command]
+pub async fn migrate_keys_to_keychain() -> Result<KeychainMigrationReport, String> {
+    if !keychain_available() {
+        return Err("OS keychain is not available on this system".to_string());
+    }
+
+    let report = KeychainMigrationReport {
+        claude: migrate_providers_file(
+            "claude",
+            crate::commands::provider::get_legacy_providers_path()?,
+            CLAUDE_SECRET_FIELDS,
+        )?,
+        codex: migrate_providers_file(
+            "codex",
+            crate::commands::codex::config::get_codex_providers_path()?,
+            CODEX_SECRET_FIELDS,
+        )?,
+        gemini: migrate_providers_file(
+            "gemini",
+            crate::commands::gemini::provider::get_gemini_providers_path()?,
+            GEMINI_SECRET_FIELDS,
+        )?,
+    };
+    save_json_config(
+        &KeychainConfig { enabled: true },
+        get_keychain_config_path()?,
+    )?;
+
+    log::info!("Migrated provider keys to keychain: {:?}", report);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_plaintext_secrets_are_protected() {
+        assert!(should_protect("sk-plain"));
+        assert!(!should_protect(""));
+        assert!(!should_protect("keychain:codex/p/OPENAI_API_KEY"));
+        assert!(!should_protect("${OPENAI_API_KEY}"));
+        assert_eq!(resolve_secret("sk-plain").unwrap(), "sk-plain");
+        assert_eq!(
+            resolve_secret("${OPENAI_API_KEY}").unwrap(),
+            "${OPENAI_API_KEY}"
+        );
+    }
+
+    #[test]
+    fn finds_keychain_refs_in_provider_configs() {
+        let claude = serde_json::json!({
+            "id": "p1",
+            "auth_token": "keychain:claude/p1/auth_token",
+            "api_key": "sk-plain",
+        });
+        assert_eq!(keychain_refs(&claude), ["keychain:claude/p1/auth_token"]);
+
+        let codex = serde_json::json!({
+            "id": "c1",
+            "auth": { "OPENAI_API_KEY": "keychain:codex/c1/OPENAI_API_KEY" },
+            "config": "model = \"gpt-5\"",
+        });
+        assert_eq!(keychain_refs(&codex), ["keychain:codex/c1/OPENAI_API_KEY"]);
+
+        let gemini = serde_json::json!({
+            "id": "g1",
+            "env": { "GEMINI_API_KEY": "${GEMINI_API_KEY}" },
+        });
+        assert!(keychain_refs(&gemini).is_empty());
+    }
+}
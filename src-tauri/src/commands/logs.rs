@@ -0,0 +1,145 @@
+//! 日志查看与诊断包导出
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::LevelFilter;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::effective_config::{get_effective_config, redact_json};
+use crate::logging;
+
+/// `get_recent_logs` 未指定行数时返回的行数
+const DEFAULT_RECENT_LOG_LINES: usize = 200;
+
+/// Returns the last lines of the application log (default 200)
+#[tauri::command]
+pub async fn get_recent_logs(lines: Option<usize>) -> Result<Vec<String>, String> {
+    let count = lines.unwrap_or(DEFAULT_RECENT_LOG_LINES);
+    tokio::task::spawn_blocking(move || logging::recent_lines(count))
+        .await
+        .map_err(|e| format!("Failed to read logs: {}", e))
+}
+
+/// Opens the log directory in the system file explorer
+#[tauri::command]
+pub async fn open_log_directory() -> Result<(), String> {
+    let dir = logging::log_dir().ok_or("Log directory is not initialized".to_string())?;
+    crate::commands::file_operations::open_directory_in_explorer(dir.to_string_lossy().to_string())
+        .await
+}
+
+/// Changes the log level at runtime ("error", "warn", "info", "debug", "trace" or "off")
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<String, String> {
+    let filter =
+        LevelFilter::from_str(level.trim()).map_err(|_| format!("Invalid log level: {}", level))?;
+    logging::set_level(filter);
+    log::info!("Log level set to {}", filter);
+    Ok(filter.to_string().to_lowercase())
+}
+
+/// Returns the current log level
+#[tauri::command]
+pub async fn get_log_level() -> Result<String, String> {
+    Ok(logging::level().to_string().to_lowercase())
+}
+
+/// Writes a zip with the logs, redacted config files and a diagnostics report; returns its path
+#[tauri::command]
+pub async fn create_diagnostics_bundle(app: AppHandle) -> Result<String, String> {
+    let diagnostics = crate::commands::diagnostics::run_diagnostics(app.clone())
+        .await
+        .map_err(|e| format!("Failed to run diagnostics: {}", e))?;
+    let mut effective_config = serde_json::Map::new();
+    for tool in ["claude", "codex", "gemini"] {
+        if let Ok(config) = get_effective_config(app.clone(), tool.to_string()).await {
+            effective_config.insert(tool.to_string(), config);
+        }
+    }
+
+    let mut entries: Vec<(String, String)> = vec![
+        (
+            "diagnostics.json".to_string(),
+            serde_json::to_string_pretty(&diagnostics).map_err(|e| e.to_string())?,
+        ),
+        (
+            "effective_config.json".to_string(),
+            serde_json::to_string_pretty(&Value::Object(effective_config))
+                .map_err(|e| e.to_string())?,
+        ),
+    ];
+    for (name, path) in config_files() {
+        if let Some(content) = read_redacted_json(&path) {
+            entries.push((format!("config/{}", name), content));
+        }
+    }
+    if let Some(dir) = logging::log_dir() {
+        for path in logging::log_files(&dir) {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                entries.push((format!("logs/{}", name), logging::redact_secrets(&content)));
+            }
+        }
+    }
+
+    let output_dir = dirs::download_dir()
+        .or_else(|| app.path().app_data_dir().ok())
+        .ok_or("Failed to find an output directory".to_string())?;
+    let output_path = output_dir.join(format!(
+        "anycode-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let path_for_write = output_path.clone();
+    tokio::task::spawn_blocking(move || write_zip(&path_for_write, &entries))
+        .await
+        .map_err(|e| format!("Failed to write diagnostics bundle: {}", e))??;
+
+    log::info!("Diagnostics bundle written to {:?}", output_path);
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// 随诊断包导出的配置文件
+fn config_files() -> Vec<(&'static str, PathBuf)> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let claude_dir = home.join(".claude");
+    vec![
+        ("claude-settings.json", claude_dir.join("settings.json")),
+        (
+            "execution_config.json",
+            claude_dir.join("execution_config.json"),
+        ),
+        ("binaries.json", claude_dir.join("binaries.json")),
+        ("gemini.json", home.join(".anycode").join("gemini.json")),
+    ]
+}
+
+fn read_redacted_json(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut json: Value = serde_json::from_str(&content).ok()?;
+    redact_json(&mut json);
+    serde_json::to_string_pretty(&json).ok()
+}
+
+fn write_zip(path: &Path, entries: &[(String, String)]) -> Result<(), String> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish zip: {}", e))?;
+    Ok(())
+}
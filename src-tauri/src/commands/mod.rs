@@ -1,22 +1,39 @@
 pub mod acemcp;
+pub mod agent;
+pub mod auth_detection;
+pub mod backup;
 pub mod claude;
 pub mod clipboard;
 pub mod codex; // OpenAI Codex integration
+pub mod commit_message;
 pub mod context_commands;
 pub mod context_manager;
+pub mod cost_estimate;
+pub mod diagnostics;
+pub mod effective_config;
 pub mod enhanced_hooks;
 pub mod extensions;
 pub mod file_operations;
 pub mod gemini; // Google Gemini CLI integration
 pub mod git_stats;
+pub mod keychain;
+pub mod logs;
 pub mod mcp;
+pub mod notifications;
 pub mod permission_config;
+pub mod project_command;
 pub mod prompt_tracker;
 pub mod provider;
+pub mod roundtrip;
+pub mod session_limits;
+pub mod session_summary;
 pub mod simple_git;
 pub mod storage;
+pub mod tool_login;
+pub mod tool_updates;
 pub mod translator;
 pub mod url_utils; // API URL 规范化工具
 pub mod usage;
+pub mod usage_budget;
 pub mod window; // 多窗口管理
 pub mod wsl_utils; // WSL 兼容性工具
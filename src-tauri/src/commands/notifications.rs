@@ -0,0 +1,131 @@
+//! 会话事件的系统通知
+//!
+//! 长时间运行的会话结束（或出错、被限流、等待权限审批）时弹出系统通知，
+//! 会话所在窗口处于焦点时不通知。各类事件是否通知由 `NotificationPreferences` 控制，默认全部关闭。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::storage::{load_app_setting, store_app_setting};
+use crate::error::AppResult;
+
+/// app_settings 中保存通知偏好（JSON）的键
+const NOTIFICATION_PREFERENCES_SETTING: &str = "notification_preferences";
+
+/// Which session events trigger an OS notification
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    /// Session finished successfully
+    pub notify_on_complete: bool,
+    /// Session finished with a failure
+    pub notify_on_error: bool,
+    /// Request hit a rate limit / overload
+    pub notify_on_rate_limit: bool,
+    /// A tool call is waiting for approval
+    pub notify_on_permission_request: bool,
+}
+
+/// 触发通知的会话事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SessionNotification {
+    Complete,
+    Error,
+    RateLimited,
+    PermissionRequest,
+}
+
+impl SessionNotification {
+    fn enabled(self, preferences: &NotificationPreferences) -> bool {
+        match self {
+            Self::Complete => preferences.notify_on_complete,
+            Self::Error => preferences.notify_on_error,
+            Self::RateLimited => preferences.notify_on_rate_limit,
+            Self::PermissionRequest => preferences.notify_on_permission_request,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Complete => "Session completed",
+            Self::Error => "Session failed",
+            Self::RateLimited => "Rate limited",
+            Self::PermissionRequest => "Permission required",
+        }
+    }
+}
+
+/// 读取持久化的通知偏好，不存在时返回默认值（全部关闭）
+pub fn load_notification_preferences(app: &AppHandle) -> NotificationPreferences {
+    load_app_setting(app, NOTIFICATION_PREFERENCES_SETTING).unwrap_or_default()
+}
+
+/// 会话所在窗口（独立窗口或主窗口）是否处于焦点
+fn is_session_window_focused(app: &AppHandle, tab_id: Option<&str>) -> bool {
+    let detached = tab_id.and_then(|id| app.get_webview_window(&format!("session-window-{}", id)));
+    detached
+        .or_else(|| app.get_webview_window("main"))
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// 按偏好发送会话事件的系统通知；`detail` 作为通知正文
+pub(crate) fn notify_session_event(
+    app: &AppHandle,
+    event: SessionNotification,
+    project_path: &str,
+    tab_id: Option<&str>,
+    detail: &str,
+) {
+    if !event.enabled(&load_notification_preferences(app)) {
+        return;
+    }
+    if is_session_window_focused(app, tab_id) {
+        log::debug!(
+            "Skipping {:?} notification: session window is focused",
+            event
+        );
+        return;
+    }
+
+    let project_name = Path::new(project_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| project_path.to_string());
+    let title = if project_name.is_empty() {
+        event.title().to_string()
+    } else {
+        format!("{} · {}", project_name, event.title())
+    };
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(detail)
+        .show()
+    {
+        log::warn!("Failed to show {:?} notification: {}", event, e);
+    }
+}
+
+/// Get which session events trigger an OS notification
+#[tauri::command]
+pub async fn get_notification_preferences(app: AppHandle) -> AppResult<NotificationPreferences> {
+    Ok(load_notification_preferences(&app))
+}
+
+/// Set which session events (complete, error, rate-limited, permission request) trigger an OS notification
+#[tauri::command]
+pub async fn set_notification_preferences(
+    app: AppHandle,
+    preferences: NotificationPreferences,
+) -> AppResult<NotificationPreferences> {
+    store_app_setting(&app, NOTIFICATION_PREFERENCES_SETTING, &preferences)?;
+
+    log::info!("Notification preferences updated: {:?}", preferences);
+    Ok(preferences)
+}
@@ -16,6 +16,8 @@ pub enum PermissionMode {
     AcceptEdits,
     ReadOnly,
     Plan, // Claude CLI 原生支持的 Plan Mode
+    /// 由应用逐个审批工具调用（通过 claude-permission-request 事件）
+    AppApproval,
 }
 
 impl Default for ClaudePermissionConfig {
@@ -42,6 +44,7 @@ impl std::fmt::Display for PermissionMode {
             PermissionMode::AcceptEdits => write!(f, "acceptEdits"),
             PermissionMode::ReadOnly => write!(f, "bypassPermissions"), // 使用 CLI 正确的参数
             PermissionMode::Plan => write!(f, "plan"),                  // Plan Mode
+            PermissionMode::AppApproval => write!(f, "default"),        // 审批请求经 stdio 交给应用
         }
     }
 }
@@ -59,6 +62,142 @@ pub const ALL_TOOLS: &[&str] = &[
     "TodoWrite",
 ];
 
+/// `ALL_TOOLS` 之外 Claude CLI 可识别的内置工具；第二项为首次提供该工具的 CLI 版本
+/// （None 表示早期版本即已存在）
+const OTHER_CLAUDE_TOOLS: &[(&str, Option<&str>)] = &[
+    ("BashOutput", Some("1.0.71")),
+    ("ExitPlanMode", None),
+    ("Glob", None),
+    ("Grep", None),
+    ("KillShell", Some("1.0.71")),
+    ("LS", None),
+    ("MultiEdit", None),
+    ("NotebookEdit", None),
+    ("WebSearch", None),
+];
+
+/// Claude CLI 可识别的内置工具：`ALL_TOOLS` 中的工具加上 `OTHER_CLAUDE_TOOLS`
+pub fn known_claude_tools() -> impl Iterator<Item = (&'static str, Option<&'static str>)> {
+    ALL_TOOLS
+        .iter()
+        .map(|tool| (*tool, None))
+        .chain(OTHER_CLAUDE_TOOLS.iter().copied())
+}
+
+/// 导出的执行配置格式版本；格式变化时递增并在 `migrate_exported_config` 中补充迁移
+pub const EXECUTION_CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// 把执行配置包装为带格式版本的可移植 JSON
+pub fn export_config_blob(config: &ClaudeExecutionConfig) -> Result<String, String> {
+    serde_json::to_string_pretty(&serde_json::json!({
+        "schema_version": EXECUTION_CONFIG_SCHEMA_VERSION,
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "config": config,
+    }))
+    .map_err(|e| format!("Failed to serialize execution config: {}", e))
+}
+
+/// 解析导出的配置并迁移到当前格式，返回其中的执行配置 JSON
+///
+/// 没有 `schema_version` 的 JSON 视为直接复制的 execution_config.json（版本 0）。
+pub fn migrate_exported_config(blob: &str) -> Result<serde_json::Value, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(blob).map_err(|e| format!("Invalid config export: {}", e))?;
+
+    let Some(version) = value.get("schema_version") else {
+        if value.get("permissions").is_some() {
+            return Ok(value);
+        }
+        return Err("Not an execution config export: missing schema_version".to_string());
+    };
+    let version = version
+        .as_u64()
+        .ok_or_else(|| "Invalid schema_version in config export".to_string())?;
+    if version > EXECUTION_CONFIG_SCHEMA_VERSION as u64 {
+        return Err(format!(
+            "Config export uses schema version {}, but this version of the app supports up to {}",
+            version, EXECUTION_CONFIG_SCHEMA_VERSION
+        ));
+    }
+
+    value
+        .get("config")
+        .filter(|config| config.is_object())
+        .cloned()
+        .ok_or_else(|| "Config export has no config object".to_string())
+}
+
+/// 把 `overlay` 递归合并进 `base`：对象按键合并，其它值（包括数组）直接覆盖
+pub fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 工具名交叉检查的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct ToolNameCheck {
+    pub warnings: Vec<String>,
+    pub unrecognized: Vec<String>,
+}
+
+/// 检查允许/禁止列表中的工具名是否能被当前版本的 Claude CLI 识别
+///
+/// `Bash(git:*)` 之类的规则按括号前的工具名检查，`mcp__` 开头的 MCP 工具不做检查。
+pub fn check_tool_names(tools: &[String], claude_version: Option<&str>) -> ToolNameCheck {
+    let mut check = ToolNameCheck::default();
+
+    for tool in tools {
+        let name = tool.split('(').next().unwrap_or(tool).trim();
+        if name.is_empty() || name.starts_with("mcp__") || check.unrecognized.contains(tool) {
+            continue;
+        }
+
+        match known_claude_tools().find(|(known, _)| *known == name) {
+            Some((_, Some(since))) => {
+                if let Some(version) = claude_version {
+                    if crate::claude_binary::compare_versions(version, since)
+                        == std::cmp::Ordering::Less
+                    {
+                        check.warnings.push(format!(
+                            "当前 Claude CLI {} 不支持工具 {}（需要 {} 及以上版本）",
+                            version, name, since
+                        ));
+                        check.unrecognized.push(tool.clone());
+                    }
+                }
+            }
+            Some((_, None)) => {}
+            None => {
+                let suggestion =
+                    known_claude_tools().find(|(known, _)| known.eq_ignore_ascii_case(name));
+                check.warnings.push(match suggestion {
+                    Some((known, _)) => {
+                        format!(
+                            "未识别的工具名 {}，是否应为 {}（工具名区分大小写）",
+                            name, known
+                        )
+                    }
+                    None => format!("Claude CLI 无法识别工具 {}", name),
+                });
+                check.unrecognized.push(tool.clone());
+            }
+        }
+    }
+
+    check
+}
+
 /// Claude执行配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeExecutionConfig {
@@ -70,6 +209,93 @@ pub struct ClaudeExecutionConfig {
     pub permissions: ClaudePermissionConfig,
     #[serde(default)]
     pub disable_rewind_git_operations: bool,
+    /// 默认附加的 CLI 参数（用于应用尚未支持的新 Claude 参数）
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// 发送前是否由运行器清理 prompt 中的控制字符
+    #[serde(default)]
+    pub sanitize_prompt: bool,
+    /// prompt 估算 token 超过该值时给出警告（0 表示不警告）
+    #[serde(default = "default_prompt_warning_tokens")]
+    pub prompt_warning_tokens: usize,
+    /// 额外的已知斜杠命令（多行 prompt 以这些命令开头时仍按命令传递）
+    #[serde(default)]
+    pub known_slash_commands: Vec<String>,
+    /// 是否允许会话工作目录位于项目路径之外
+    #[serde(default)]
+    pub allow_working_dir_outside_project: bool,
+    /// 遇到限流 / 过载错误时的自动重试策略
+    #[serde(default)]
+    pub rate_limit_retry: RateLimitRetryConfig,
+    /// 危险跳过模式的自动关闭保护
+    #[serde(default)]
+    pub dangerous_skip_safeguard: DangerousSkipSafeguard,
+    /// stdout 中需要过滤的纯文本噪声（升级提示、横幅等）的正则，匹配的行只写日志
+    #[serde(default = "default_output_noise_patterns")]
+    pub output_noise_patterns: Vec<String>,
+    /// 在伪终端中运行 CLI（需要编译时启用 `pty` feature，默认使用管道）
+    #[serde(default)]
+    pub use_pty: bool,
+    /// 转发输出前去掉 ANSI 颜色、光标移动等控制序列
+    #[serde(default = "default_strip_ansi")]
+    pub strip_ansi: bool,
+}
+
+/// 限流自动重试配置（默认关闭）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitRetryConfig {
+    pub enabled: bool,
+    /// 每次请求最多自动重试的次数
+    pub max_retries: u32,
+    /// 没有 retry-after 提示时的首次等待秒数，之后按指数退避
+    pub base_delay_secs: u64,
+    /// 单次等待的上限秒数
+    pub max_delay_secs: u64,
+}
+
+impl Default for RateLimitRetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 3,
+            base_delay_secs: 10,
+            max_delay_secs: 300,
+        }
+    }
+}
+
+/// 危险跳过模式的自动关闭保护（默认不限制）
+///
+/// 启用 `enable_dangerous_skip` 后，超过任一限制时运行器会自动关闭它
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DangerousSkipSafeguard {
+    /// 启用后最多允许启动的会话数
+    pub max_sessions: Option<u32>,
+    /// 启用后最多保持的小时数
+    pub max_hours: Option<u32>,
+}
+
+fn default_prompt_warning_tokens() -> usize {
+    50_000
+}
+
+fn default_strip_ansi() -> bool {
+    true
+}
+
+fn default_output_noise_patterns() -> Vec<String> {
+    [
+        r"(?i)update available",
+        r"(?i)new version of claude",
+        r"(?i)\bclaude (?:update|migrate-installer)\b",
+        r"(?i)^\s*npm (?:warn|notice)\b",
+        r"^\s*\(node:\d+\) \w*Warning:",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +315,16 @@ impl Default for ClaudeExecutionConfig {
             verbose: true,
             permissions: ClaudePermissionConfig::default(),
             disable_rewind_git_operations: false,
+            extra_args: Vec::new(),
+            sanitize_prompt: false,
+            prompt_warning_tokens: default_prompt_warning_tokens(),
+            known_slash_commands: Vec::new(),
+            allow_working_dir_outside_project: false,
+            rate_limit_retry: RateLimitRetryConfig::default(),
+            dangerous_skip_safeguard: DangerousSkipSafeguard::default(),
+            output_noise_patterns: default_output_noise_patterns(),
+            use_pty: false,
+            strip_ansi: default_strip_ansi(),
         }
     }
 }
@@ -129,6 +365,12 @@ pub fn build_permission_args(config: &ClaudePermissionConfig) -> Vec<String> {
     args.push("--permission-mode".to_string());
     args.push(config.permission_mode.to_string());
 
+    // 应用审批模式：CLI 通过 stdout 的 control_request 询问权限，应用从 stdin 回复
+    if config.permission_mode == PermissionMode::AppApproval {
+        args.push("--permission-prompt-tool".to_string());
+        args.push("stdio".to_string());
+    }
+
     args
 }
 
@@ -181,11 +423,90 @@ pub fn build_execution_args(config: &ClaudeExecutionConfig, model: &str) -> Vec<
     // 添加权限参数
     args.extend(build_permission_args(&config.permissions));
 
+    // 应用审批模式需要保持 stdin 打开并以 stream-json 交换消息
+    if config.permissions.uses_app_approval() {
+        args.push("--input-format".to_string());
+        args.push("stream-json".to_string());
+    }
+
     args
 }
 
+/// 由应用自身管理、不允许通过附加参数覆盖的参数
+///
+/// 权限相关参数只能通过权限配置设置（危险跳过会被记录审计），附加参数或导入的配置都不能绕过
+const RESERVED_FLAGS: &[&str] = &[
+    "-p",
+    "--print",
+    "-c",
+    "--continue",
+    "-r",
+    "--resume",
+    "--dangerously-skip-permissions",
+    "--permission-mode",
+];
+
+/// 将用户自定义参数追加到已生成的参数之后
+///
+/// 每个以 `-` 开头的参数连同其后的取值作为一组；与已有参数（或保留参数）重复的组会被跳过并记录警告，
+/// 没有前置参数名的取值也会被跳过（否则会被 CLI 当作 prompt）。
+pub fn append_extra_args(args: &mut Vec<String>, extra_args: &[String]) {
+    fn flag_name(arg: &str) -> &str {
+        arg.split('=').next().unwrap_or(arg)
+    }
+
+    let mut seen: Vec<String> = args
+        .iter()
+        .filter(|arg| arg.starts_with('-'))
+        .map(|arg| flag_name(arg).to_string())
+        .collect();
+    seen.extend(RESERVED_FLAGS.iter().map(|flag| flag.to_string()));
+
+    // None: 尚未遇到参数名；Some(true): 当前参数组被接受；Some(false): 当前参数组被跳过
+    let mut current_group: Option<bool> = None;
+    for arg in extra_args.iter().map(|arg| arg.trim()) {
+        if arg.is_empty() {
+            continue;
+        }
+
+        if arg.starts_with('-') {
+            let name = flag_name(arg);
+            if seen.iter().any(|flag| flag == name) {
+                log::warn!("Skipping extra argument {} (already set by the app)", arg);
+                current_group = Some(false);
+                continue;
+            }
+            seen.push(name.to_string());
+            args.push(arg.to_string());
+            current_group = Some(true);
+        } else {
+            match current_group {
+                Some(true) => args.push(arg.to_string()),
+                Some(false) => log::warn!("Skipping value {} of a duplicate extra argument", arg),
+                None => log::warn!("Skipping extra argument {} (value without a flag)", arg),
+            }
+        }
+    }
+}
+
 /// 预设权限配置
 impl ClaudePermissionConfig {
+    /// 是否由应用审批每个工具调用（危险跳过模式优先）
+    pub fn uses_app_approval(&self) -> bool {
+        self.permission_mode == PermissionMode::AppApproval && !self.enable_dangerous_skip
+    }
+
+    /// 应用审批模式 - 每个工具调用都需要应用确认，超时默认拒绝
+    pub fn app_approval_mode() -> Self {
+        Self {
+            allowed_tools: vec![],
+            disallowed_tools: vec![],
+            permission_mode: PermissionMode::AppApproval,
+            auto_approve_edits: false,
+            enable_dangerous_skip: false,
+        }
+    }
+
     /// 开发模式 - 允许所有常用开发工具
     pub fn development_mode() -> Self {
         Self {
@@ -249,3 +570,98 @@ impl ClaudePermissionConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_unknown_miscased_and_too_new_tools() {
+        let tools: Vec<String> = [
+            "Read",
+            "bash",
+            "Bash(git:*)",
+            "Search",
+            "BashOutput",
+            "mcp__github__search",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let check = check_tool_names(&tools, Some("1.0.50"));
+        assert_eq!(check.unrecognized, vec!["bash", "Search", "BashOutput"]);
+        assert!(check.warnings[0].contains("Bash"));
+
+        let check = check_tool_names(&tools, Some("2.0.0"));
+        assert_eq!(check.unrecognized, vec!["bash", "Search"]);
+    }
+
+    #[test]
+    fn preset_tool_groups_are_known_tools() {
+        let tools: Vec<String> = ALL_TOOLS
+            .iter()
+            .chain(DEVELOPMENT_TOOLS)
+            .chain(&["KillShell", "MultiEdit"])
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            check_tool_names(&tools, Some("2.0.0")),
+            ToolNameCheck::default()
+        );
+    }
+
+    #[test]
+    fn extra_args_cannot_override_reserved_or_permission_flags() {
+        let mut args = vec!["--model".to_string(), "sonnet".to_string()];
+        let extra: Vec<String> = [
+            "--dangerously-skip-permissions",
+            "--permission-mode",
+            "bypassPermissions",
+            "--permission-mode=acceptEdits",
+            "--model",
+            "opus",
+            "--resume",
+            "abc",
+            "--verbose",
+            "--add-dir",
+            "/tmp/shared",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        append_extra_args(&mut args, &extra);
+        assert_eq!(
+            args,
+            vec!["--model", "sonnet", "--verbose", "--add-dir", "/tmp/shared"]
+        );
+    }
+
+    #[test]
+    fn round_trips_and_merges_exported_config() {
+        let mut config = ClaudeExecutionConfig::default();
+        config.permissions.allowed_tools = vec!["Read".to_string()];
+        let blob = export_config_blob(&config).unwrap();
+        let imported = migrate_exported_config(&blob).unwrap();
+        assert_eq!(
+            imported["permissions"]["allowed_tools"],
+            serde_json::json!(["Read"])
+        );
+
+        // 直接复制的 execution_config.json 也可以导入
+        let raw = serde_json::to_string(&config).unwrap();
+        assert!(migrate_exported_config(&raw).is_ok());
+        assert!(migrate_exported_config(r#"{"schema_version": 99, "config": {}}"#).is_err());
+
+        let mut base = serde_json::json!({"verbose": true, "permissions": {"allowed_tools": ["Bash"], "auto_approve_edits": true}});
+        merge_json(
+            &mut base,
+            serde_json::json!({"permissions": {"allowed_tools": ["Read"]}}),
+        );
+        assert_eq!(
+            base,
+            serde_json::json!({"verbose": true, "permissions": {"allowed_tools": ["Read"], "auto_approve_edits": true}})
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! 项目命令
+//!
+//! 在项目目录中直接运行一次性命令（跑测试、查看 git 状态等），不需要启动完整的 Claude 会话。
+//! 输出逐行通过 `project-command-output` 事件推送，进程登记在 ProcessRegistry 中，
+//! 可以按 run_id 取消。
+
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::claude_binary::create_command_with_env;
+use crate::commands::claude::apply_no_window_async;
+use crate::process::ProcessRegistryState;
+
+/// `project-command-output` 事件的负载
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectCommandOutput {
+    pub run_id: i64,
+    pub stream: &'static str, // "stdout" | "stderr"
+    pub line: String,
+}
+
+/// `project-command-complete` 事件的负载
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectCommandExit {
+    pub run_id: i64,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
+
+/// 逐行读取输出流，转发为事件并写入 live output
+fn forward_output<R>(
+    app: AppHandle,
+    run_id: i64,
+    stream: &'static str,
+    reader: R,
+) -> tauri::async_runtime::JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let registry = app.state::<ProcessRegistryState>().0.clone();
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = registry.append_live_output(run_id, &line);
+            let _ = app.emit(
+                "project-command-output",
+                ProjectCommandOutput {
+                    run_id,
+                    stream,
+                    line,
+                },
+            );
+        }
+    })
+}
+
+/// Run a one-off command in a project directory, streaming output via `project-command-output`; returns a run id for cancel_project_command
+#[tauri::command]
+pub async fn run_project_command(
+    app: AppHandle,
+    project_path: String,
+    command: String,
+    args: Option<Vec<String>>,
+) -> Result<i64, String> {
+    if command.trim().is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+    if !Path::new(&project_path).is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+    let args = args.unwrap_or_default();
+    let command_line = std::iter::once(command.as_str())
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut cmd = Command::from(create_command_with_env(&command));
+    cmd.args(&args)
+        .current_dir(&project_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    apply_no_window_async(&mut cmd);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", command_line, e))?;
+    let pid = child.id().unwrap_or(0);
+    let registry = app.state::<ProcessRegistryState>().0.clone();
+    let run_id = registry.register_project_command(pid, project_path, command_line.clone())?;
+    log::info!(
+        "Started project command '{}' (run_id={}, PID={})",
+        command_line,
+        run_id,
+        pid
+    );
+
+    let stdout_task = child
+        .stdout
+        .take()
+        .map(|stdout| forward_output(app.clone(), run_id, "stdout", stdout));
+    let stderr_task = child
+        .stderr
+        .take()
+        .map(|stderr| forward_output(app.clone(), run_id, "stderr", stderr));
+
+    tauri::async_runtime::spawn(async move {
+        for task in [stdout_task, stderr_task].into_iter().flatten() {
+            let _ = task.await;
+        }
+        let status = child.wait().await;
+        let exit_code = status.as_ref().ok().and_then(|s| s.code());
+        let success = status.as_ref().is_ok_and(|s| s.success());
+        log::info!(
+            "Project command {} finished (exit code: {:?})",
+            run_id,
+            exit_code
+        );
+
+        // 被取消时 kill_process 已经移除了登记
+        let _ = registry.unregister_process(run_id);
+        let _ = app.emit(
+            "project-command-complete",
+            ProjectCommandExit {
+                run_id,
+                exit_code,
+                success,
+            },
+        );
+    });
+
+    Ok(run_id)
+}
+
+/// Cancel a command started with run_project_command
+#[tauri::command]
+pub async fn cancel_project_command(app: AppHandle, run_id: i64) -> Result<bool, String> {
+    let registry = app.state::<ProcessRegistryState>();
+    registry.0.kill_process(run_id).await
+}
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-use super::claude::get_claude_dir;
+use super::claude::{get_claude_dir, validate_path_component};
 use super::permission_config::ClaudeExecutionConfig;
 use super::simple_git;
 
@@ -1388,3 +1388,179 @@ pub async fn get_unified_prompt_list(
 
     Ok(prompts)
 }
+
+/// Lineage of a forked session (stored next to its git records)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLineage {
+    /// Session the fork was created from
+    pub parent_session_id: String,
+    /// Last prompt index (inclusive) copied from the parent
+    pub forked_at_prompt: usize,
+    /// Timestamp when the fork was created
+    pub created_at: i64,
+}
+
+/// Get path to lineage file
+fn get_lineage_path(session_id: &str, project_id: &str) -> Result<PathBuf> {
+    let records_path = get_git_records_path(session_id, project_id)?;
+    Ok(records_path.with_file_name(format!("{}.lineage.json", session_id)))
+}
+
+/// 保留到 `cut_at_line`（不含）为止的行，并把每行的 sessionId 改为新会话 ID；无法解析的行原样保留
+fn fork_session_lines(content: &str, cut_at_line: Option<usize>, new_session_id: &str) -> String {
+    let mut forked = String::new();
+    for line in content.lines().take(cut_at_line.unwrap_or(usize::MAX)) {
+        match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(mut msg) if msg.get("sessionId").is_some() => {
+                msg["sessionId"] = serde_json::Value::String(new_session_id.to_string());
+                forked.push_str(&msg.to_string());
+            }
+            _ => forked.push_str(line),
+        }
+        forked.push('\n');
+    }
+    forked
+}
+
+/// Fork a session into a new session id, keeping the conversation up to and including the given prompt
+#[tauri::command]
+pub async fn fork_session(
+    session_id: String,
+    project_id: String,
+    up_to_prompt_id: usize,
+) -> Result<String, String> {
+    log::info!(
+        "Forking session {} at prompt #{}",
+        session_id,
+        up_to_prompt_id
+    );
+    validate_path_component(&project_id, "project id")?;
+    validate_path_component(&session_id, "session id")?;
+
+    let prompts = extract_prompts_from_jsonl(&session_id, &project_id)
+        .map_err(|e| format!("Failed to extract prompts from JSONL: {}", e))?;
+    if up_to_prompt_id >= prompts.len() {
+        return Err(format!(
+            "Prompt #{} not found in session (only {} prompts found)",
+            up_to_prompt_id,
+            prompts.len()
+        ));
+    }
+
+    let claude_dir = get_claude_dir().map_err(|e| format!("Failed to get claude dir: {}", e))?;
+    let project_dir = claude_dir.join("projects").join(&project_id);
+    let content = fs::read_to_string(project_dir.join(format!("{}.jsonl", session_id)))
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+
+    // 截断到下一条提示词之前，这样目标提示词的回复也会保留；目标是最后一条时复制整个文件
+    let cut_at_line = prompts
+        .get(up_to_prompt_id + 1)
+        .map(|prompt| prompt.line_number);
+    let new_session_id = uuid::Uuid::new_v4().to_string();
+    let forked = fork_session_lines(&content, cut_at_line, &new_session_id);
+    fs::write(
+        project_dir.join(format!("{}.jsonl", new_session_id)),
+        forked,
+    )
+    .map_err(|e| format!("Failed to write forked session: {}", e))?;
+
+    // 复制保留部分的 git 记录，分叉后可以独立撤回
+    let records: HashMap<usize, GitRecord> = load_git_records(&session_id, &project_id)
+        .map_err(|e| format!("Failed to load git records: {}", e))?
+        .into_iter()
+        .filter(|(index, _)| *index <= up_to_prompt_id)
+        .collect();
+    save_git_records(&new_session_id, &project_id, &records)
+        .map_err(|e| format!("Failed to save git records: {}", e))?;
+
+    let lineage = SessionLineage {
+        parent_session_id: session_id.clone(),
+        forked_at_prompt: up_to_prompt_id,
+        created_at: Utc::now().timestamp(),
+    };
+    let lineage_path = get_lineage_path(&new_session_id, &project_id)
+        .map_err(|e| format!("Failed to get lineage path: {}", e))?;
+    let lineage_json = serde_json::to_string_pretty(&lineage)
+        .map_err(|e| format!("Failed to serialize lineage: {}", e))?;
+    fs::write(&lineage_path, lineage_json)
+        .map_err(|e| format!("Failed to write lineage file: {}", e))?;
+
+    log::info!(
+        "Forked session {} into {} ({} prompts, {} git records)",
+        session_id,
+        new_session_id,
+        up_to_prompt_id + 1,
+        records.len()
+    );
+    Ok(new_session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 三轮对话：每轮一条用户提示词和一条回复，中间夹一行无法解析的内容
+    fn session_content() -> String {
+        let mut lines = Vec::new();
+        for turn in 0..3 {
+            lines.push(
+                serde_json::json!({
+                    "type": "user",
+                    "sessionId": "parent",
+                    "message": { "role": "user", "content": format!("prompt {}", turn) }
+                })
+                .to_string(),
+            );
+            lines.push(
+                serde_json::json!({
+                    "type": "assistant",
+                    "sessionId": "parent",
+                    "message": { "role": "assistant", "content": format!("reply {}", turn) }
+                })
+                .to_string(),
+            );
+        }
+        lines.insert(1, "not json".to_string());
+        lines.join("\n")
+    }
+
+    fn forked_lines(cut_at_line: Option<usize>) -> Vec<String> {
+        let forked = fork_session_lines(&session_content(), cut_at_line, "child");
+        assert!(forked.ends_with('\n'));
+        let lines: Vec<String> = forked.lines().map(str::to_string).collect();
+        for line in &lines {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
+                assert_eq!(value["sessionId"], "child");
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn fork_at_first_prompt_keeps_just_the_opener() {
+        // 第二条提示词的行下标为 3，截断到它之前
+        let lines = forked_lines(Some(3));
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("prompt 0"));
+        assert_eq!(lines[1], "not json");
+        assert!(lines[2].contains("reply 0"));
+    }
+
+    #[test]
+    fn fork_mid_session_keeps_earlier_turns() {
+        let lines = forked_lines(Some(5));
+        assert_eq!(lines.len(), 5);
+        assert!(lines[3].contains("prompt 1"));
+        assert!(lines[4].contains("reply 1"));
+        assert!(!lines.iter().any(|line| line.contains("prompt 2")));
+    }
+
+    #[test]
+    fn fork_at_last_prompt_copies_the_whole_session() {
+        let lines = forked_lines(None);
+        assert_eq!(lines.len(), 7);
+        assert!(lines[6].contains("reply 2"));
+        assert!(!lines.iter().any(|line| line.contains("parent")));
+    }
+}
@@ -4,7 +4,14 @@ use std::fs;
 use std::path::PathBuf;
 use tauri::{command, AppHandle};
 
+use super::keychain::{delete_provider_secrets, protect_secret, resolve_secret};
 use super::url_utils::{normalize_api_url, normalize_base_url, ApiEndpointType};
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// 表示"未配置代理商（官方端点）"的 id
+const OFFICIAL_PROVIDER_ID: &str = "official";
+/// 代理商切换记录的最大保留条数
+const MAX_SWITCH_HISTORY: usize = 500;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProviderConfig {
@@ -47,7 +54,7 @@ fn get_settings_path() -> Result<PathBuf, String> {
 }
 
 // 获取遗留的providers.json路径（用于迁移）
-fn get_legacy_providers_path() -> Result<PathBuf, String> {
+pub(crate) fn get_legacy_providers_path() -> Result<PathBuf, String> {
     let home_dir = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
     Ok(home_dir.join(".claude").join("providers.json"))
 }
@@ -91,6 +98,70 @@ fn save_settings(settings: &Value) -> Result<(), String> {
     Ok(())
 }
 
+/// 一次代理商切换（清理配置时记为 official）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProviderSwitchEvent {
+    provider_id: String,
+    switched_at: String,
+}
+
+/// 代理商切换记录，用于按时间段把本地用量归属到代理商
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProviderSwitchHistory {
+    events: Vec<ProviderSwitchEvent>,
+}
+
+fn get_switch_history_path() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    Ok(home_dir
+        .join(".claude")
+        .join("provider_switch_history.json"))
+}
+
+// 记录代理商切换；失败只记日志，不影响切换本身
+fn record_provider_switch(provider_id: &str) {
+    let result = get_switch_history_path().and_then(|path| {
+        let mut history: ProviderSwitchHistory = load_json_config(&path)?;
+        history.events.push(ProviderSwitchEvent {
+            provider_id: provider_id.to_string(),
+            switched_at: chrono::Utc::now().to_rfc3339(),
+        });
+        if history.events.len() > MAX_SWITCH_HISTORY {
+            let excess = history.events.len() - MAX_SWITCH_HISTORY;
+            history.events.drain(..excess);
+        }
+        save_json_config(&history, &path)
+    });
+    if let Err(e) = result {
+        log::warn!("记录代理商切换失败: {}", e);
+    }
+}
+
+/// 代理商生效的时间段 [开始, 结束)，结束为 None 表示至今仍生效
+type ActiveWindow = (
+    chrono::DateTime<chrono::Utc>,
+    Option<chrono::DateTime<chrono::Utc>>,
+);
+
+fn active_windows(events: &[ProviderSwitchEvent], provider_id: &str) -> Vec<ActiveWindow> {
+    let mut switches: Vec<_> = events
+        .iter()
+        .filter_map(|e| {
+            chrono::DateTime::parse_from_rfc3339(&e.switched_at)
+                .ok()
+                .map(|at| (at.with_timezone(&chrono::Utc), e.provider_id.as_str()))
+        })
+        .collect();
+    switches.sort_by_key(|(at, _)| *at);
+
+    switches
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, id))| *id == provider_id)
+        .map(|(i, (at, _))| (*at, switches.get(i + 1).map(|(next, _)| *next)))
+        .collect()
+}
+
 // 从遗留的providers.json加载预设配置
 fn load_legacy_providers() -> Result<Vec<ProviderConfig>, String> {
     let legacy_path = get_legacy_providers_path()?;
@@ -112,6 +183,31 @@ fn load_legacy_providers() -> Result<Vec<ProviderConfig>, String> {
     Ok(providers)
 }
 
+// 启用钥匙串时把认证信息存入系统钥匙串，配置文件中只保留引用
+fn protect_claude_secrets(mut config: ProviderConfig) -> ProviderConfig {
+    let id = config.id.clone();
+    for (field, value) in [
+        ("auth_token", &mut config.auth_token),
+        ("api_key", &mut config.api_key),
+    ] {
+        if let Some(value) = value {
+            *value = protect_secret("claude", &id, field, value);
+        }
+    }
+    config
+}
+
+// 把钥匙串引用还原为实际的认证信息
+fn resolve_claude_secrets(mut config: ProviderConfig) -> Result<ProviderConfig, String> {
+    for value in [&mut config.auth_token, &mut config.api_key]
+        .into_iter()
+        .flatten()
+    {
+        *value = resolve_secret(value)?;
+    }
+    Ok(config)
+}
+
 // CRUD 操作 - 获取所有代理商预设（从遗留文件读取）
 #[command]
 pub fn get_provider_presets() -> Result<Vec<ProviderConfig>, String> {
@@ -121,6 +217,7 @@ pub fn get_provider_presets() -> Result<Vec<ProviderConfig>, String> {
 // CRUD 操作 - 添加代理商预设（写入遗留文件，保持兼容性）
 #[command]
 pub fn add_provider_config(config: ProviderConfig) -> Result<String, String> {
+    let config = protect_claude_secrets(config);
     let mut providers = load_legacy_providers()?;
 
     // 检查ID是否已存在
@@ -143,6 +240,7 @@ pub fn add_provider_config(config: ProviderConfig) -> Result<String, String> {
 // CRUD 操作 - 更新代理商预设
 #[command]
 pub fn update_provider_config(config: ProviderConfig) -> Result<String, String> {
+    let config = protect_claude_secrets(config);
     let mut providers = load_legacy_providers()?;
 
     let index = providers
@@ -180,6 +278,8 @@ pub fn delete_provider_config(id: String) -> Result<String, String> {
         serde_json::to_string_pretty(&providers).map_err(|e| format!("序列化配置失败: {}", e))?;
 
     fs::write(&legacy_path, content).map_err(|e| format!("写入配置文件失败: {}", e))?;
+    // 同时删除钥匙串中保存的认证信息
+    delete_provider_secrets(&deleted_config);
 
     Ok(format!("成功删除代理商配置: {}", deleted_config.name))
 }
@@ -282,6 +382,7 @@ pub async fn switch_provider_config(
     _app: AppHandle,
     config: ProviderConfig,
 ) -> Result<String, String> {
+    let config = resolve_claude_secrets(config)?;
     log::info!(
         "开始切换代理商配置: {} - {}",
         config.name,
@@ -410,6 +511,7 @@ pub async fn switch_provider_config(
 
     // 保存设置
     save_settings(&settings)?;
+    record_provider_switch(&config.id);
 
     log::info!("代理商配置切换完成: {}", config.name);
 
@@ -481,6 +583,7 @@ pub async fn clear_provider_config(_app: AppHandle) -> Result<String, String> {
 
     // 保存设置
     save_settings(&settings)?;
+    record_provider_switch(OFFICIAL_PROVIDER_ID);
 
     log::info!("代理商配置清理完成");
 
@@ -500,6 +603,39 @@ pub fn test_provider_connection(base_url: String) -> Result<String, String> {
     Ok(format!("连接测试完成：{}", test_url))
 }
 
+// 调用 billing/usage 接口查询指定日期区间的使用情况
+async fn fetch_billing_usage(
+    client: &reqwest::Client,
+    normalized_base: &str,
+    api_key: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Value, String> {
+    let usage_url = format!(
+        "{}/v1/dashboard/billing/usage?start_date={}&end_date={}",
+        normalized_base, start_date, end_date
+    );
+    log::info!("查询使用情况: {}", usage_url);
+
+    let usage_response = client
+        .get(&usage_url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| format!("请求使用情况失败: {}", e))?;
+
+    if !usage_response.status().is_success() {
+        let status = usage_response.status();
+        let body = usage_response.text().await.unwrap_or_default();
+        return Err(format!("使用情况查询失败: {} - {}", status, body));
+    }
+
+    usage_response
+        .json()
+        .await
+        .map_err(|e| format!("解析使用情况失败: {}", e))
+}
+
 /// API Key 用量查询结果
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiKeyUsage {
@@ -529,6 +665,8 @@ pub async fn query_provider_usage(
     use reqwest::Client;
 
     log::info!("开始查询 API Key 用量: {}", base_url);
+    // 钥匙串引用还原为实际的 API Key
+    let api_key = resolve_secret(&api_key)?;
 
     // 规范化基础 URL
     let normalized_base = normalize_base_url(&base_url);
@@ -579,29 +717,8 @@ pub async fn query_provider_usage(
     let start_date = start.format("%Y-%m-%d").to_string();
     let end_date = now.format("%Y-%m-%d").to_string();
 
-    let usage_url = format!(
-        "{}/v1/dashboard/billing/usage?start_date={}&end_date={}",
-        normalized_base, start_date, end_date
-    );
-    log::info!("查询使用情况: {}", usage_url);
-
-    let usage_response = client
-        .get(&usage_url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .send()
-        .await
-        .map_err(|e| format!("请求使用情况失败: {}", e))?;
-
-    if !usage_response.status().is_success() {
-        let status = usage_response.status();
-        let body = usage_response.text().await.unwrap_or_default();
-        return Err(format!("使用情况查询失败: {} - {}", status, body));
-    }
-
-    let usage_data: Value = usage_response
-        .json()
-        .await
-        .map_err(|e| format!("解析使用情况失败: {}", e))?;
+    let usage_data =
+        fetch_billing_usage(&client, &normalized_base, &api_key, &start_date, &end_date).await?;
 
     // total_usage 是以美分为单位，需要除以100转换为美元
     let total_usage_cents = usage_data
@@ -635,3 +752,563 @@ pub async fn query_provider_usage(
         query_end_date: end_date,
     })
 }
+
+/// 某一天的用量
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderUsageDay {
+    pub date: String,
+    /// 费用（美元）
+    pub cost: f64,
+    /// 代理商接口不提供 token 数时为 None
+    pub tokens: Option<u64>,
+}
+
+/// 按日期区间查询的代理商用量
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderUsageRange {
+    pub provider_id: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// 数据来源："provider"（代理商用量接口）或 "local_estimate"（本地会话记录估算）
+    pub source: String,
+    /// 未能使用代理商接口的原因
+    pub fallback_reason: Option<String>,
+    /// 总费用（美元）
+    pub total_cost: f64,
+    pub total_tokens: Option<u64>,
+    pub by_date: Vec<ProviderUsageDay>,
+}
+
+// 解析 billing/usage 返回的 daily_costs（费用单位为美分）
+fn parse_daily_costs(usage_data: &Value) -> Vec<ProviderUsageDay> {
+    let Some(days) = usage_data.get("daily_costs").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    days.iter()
+        .filter_map(|day| {
+            let timestamp = day.get("timestamp").and_then(|v| v.as_f64())? as i64;
+            let date = chrono::DateTime::from_timestamp(timestamp, 0)?.date_naive();
+            let cents: f64 = day
+                .get("line_items")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.get("cost").and_then(|v| v.as_f64()))
+                        .sum()
+                })
+                .unwrap_or(0.0);
+            Some(ProviderUsageDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                cost: cents / 100.0,
+                tokens: None,
+            })
+        })
+        .collect()
+}
+
+// 从代理商的 billing 接口获取区间用量
+async fn provider_usage_from_api(
+    base_url: &str,
+    api_key: &str,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+) -> Result<(f64, Vec<ProviderUsageDay>), String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+
+    // end_date 为开区间，多取一天以包含结束日
+    let usage_data = fetch_billing_usage(
+        &client,
+        &normalize_base_url(base_url),
+        api_key,
+        &start.format("%Y-%m-%d").to_string(),
+        &(end + chrono::Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string(),
+    )
+    .await?;
+
+    let total_cents = usage_data
+        .get("total_usage")
+        .and_then(|v| v.as_f64())
+        .ok_or("用量接口未返回 total_usage")?;
+    Ok((total_cents / 100.0, parse_daily_costs(&usage_data)))
+}
+
+// 用本地会话记录估算区间用量：只统计该代理商生效期间产生的用量
+fn provider_usage_from_local(
+    provider: &ProviderConfig,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+) -> Result<(Vec<ProviderUsageDay>, Option<String>), String> {
+    let history: ProviderSwitchHistory = load_json_config(get_switch_history_path()?)?;
+    let mut windows = active_windows(&history.events, &provider.id);
+    let mut note = None;
+
+    if windows.is_empty() {
+        let current = get_current_provider_config()?;
+        if same_base_url(
+            current.anthropic_base_url.as_deref(),
+            Some(&normalize_base_url(&provider.base_url)),
+        ) {
+            windows.push((chrono::DateTime::<chrono::Utc>::MIN_UTC, None));
+            note = Some("没有切换记录，按当前生效的代理商估算全部本地用量".to_string());
+        } else {
+            return Ok((
+                Vec::new(),
+                Some("没有该代理商的切换记录，无法估算本地用量".to_string()),
+            ));
+        }
+    }
+
+    let daily = crate::commands::usage::local_daily_usage(start, end, |at| {
+        windows
+            .iter()
+            .any(|(from, until)| at >= *from && !matches!(until, Some(until) if at >= *until))
+    })?;
+    let days = daily
+        .into_iter()
+        .map(|(date, (tokens, cost))| ProviderUsageDay {
+            date: date.format("%Y-%m-%d").to_string(),
+            cost,
+            tokens: Some(tokens),
+        })
+        .collect();
+    Ok((days, note))
+}
+
+/// Query a provider's usage and cost for a date range (inclusive, YYYY-MM-DD).
+/// Uses the provider's billing API when available, otherwise estimates from local session logs
+#[command]
+pub async fn query_provider_usage_range(
+    provider_id: String,
+    start_date: String,
+    end_date: String,
+) -> Result<ProviderUsageRange, String> {
+    use crate::commands::usage::parse_date_arg;
+
+    let start = parse_date_arg(&start_date, "start")?;
+    let end = parse_date_arg(&end_date, "end")?;
+    if start > end {
+        return Err("开始日期不能晚于结束日期".to_string());
+    }
+
+    let provider = load_legacy_providers()?
+        .into_iter()
+        .find(|p| p.id == provider_id)
+        .ok_or_else(|| format!("未找到ID为 '{}' 的代理商配置", provider_id))?;
+
+    let mut result = ProviderUsageRange {
+        provider_id,
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+        source: "provider".to_string(),
+        fallback_reason: None,
+        total_cost: 0.0,
+        total_tokens: None,
+        by_date: Vec::new(),
+    };
+
+    let api_key = non_empty(provider.auth_token.clone())
+        .or_else(|| non_empty(provider.api_key.clone()))
+        .map(|key| resolve_secret(&key))
+        .transpose()?;
+    let fallback_reason = match api_key {
+        Some(_) if normalize_base_url(&provider.base_url) == "https://api.anthropic.com" => {
+            "官方 API 不提供用量查询接口".to_string()
+        }
+        Some(api_key) => {
+            match provider_usage_from_api(&provider.base_url, &api_key, start, end).await {
+                Ok((total_cost, by_date)) => {
+                    result.total_cost = total_cost;
+                    result.by_date = by_date;
+                    return Ok(result);
+                }
+                Err(e) => {
+                    log::warn!("代理商用量接口查询失败，改用本地估算: {}", e);
+                    format!("代理商用量接口不可用: {}", e)
+                }
+            }
+        }
+        None => "代理商配置未包含认证令牌或API密钥".to_string(),
+    };
+
+    let (by_date, note) = provider_usage_from_local(&provider, start, end)?;
+    result.source = "local_estimate".to_string();
+    result.fallback_reason = Some(match note {
+        Some(note) => format!("{}；{}", fallback_reason, note),
+        None => fallback_reason,
+    });
+    result.total_cost = by_date.iter().map(|d| d.cost).sum();
+    result.total_tokens = Some(by_date.iter().filter_map(|d| d.tokens).sum());
+    result.by_date = by_date;
+    Ok(result)
+}
+
+/// 代理商列表中的一项；`id` 为 "official" 的条目表示未配置代理商（官方端点）
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderListEntry {
+    pub id: String,
+    pub name: String,
+    pub base_url: Option<String>,
+    pub is_official: bool,
+    /// 与当前生效的配置一致（base_url 相同，且预设带密钥时密钥也相同）
+    pub is_active: bool,
+    /// 保存的原始预设（官方条目为 null）
+    pub config: Value,
+}
+
+/// 预设的匹配信息：(id, name, base_url, 密钥, 是否官方预设, 原始配置)
+type PresetSummary = (String, String, Option<String>, Option<String>, bool, Value);
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn same_base_url(a: Option<&str>, b: Option<&str>) -> bool {
+    let normalize = |url: Option<&str>| url.map(|u| u.trim().trim_end_matches('/').to_lowercase());
+    normalize(a) == normalize(b)
+}
+
+/// 生成统一的代理商列表：官方条目在前，第一个与当前配置一致的条目标记为生效
+fn build_provider_list(
+    presets: Vec<PresetSummary>,
+    current_base_url: Option<String>,
+    current_key: Option<String>,
+) -> Vec<ProviderListEntry> {
+    let current_base_url = non_empty(current_base_url);
+    let current_key = non_empty(current_key);
+    let mut active_found = current_base_url.is_none();
+
+    let mut entries = vec![ProviderListEntry {
+        id: OFFICIAL_PROVIDER_ID.to_string(),
+        name: "Official".to_string(),
+        base_url: None,
+        is_official: true,
+        is_active: active_found,
+        config: Value::Null,
+    }];
+    for (id, name, base_url, key, is_official, config) in presets {
+        // 官方预设并入官方条目，避免列表中出现两个"官方"
+        if is_official {
+            if entries[0].config.is_null() {
+                entries[0].name = name;
+                entries[0].config = config;
+            }
+            continue;
+        }
+        let base_url = non_empty(base_url);
+        // 钥匙串引用还原后再比较；读取失败时按原值比较（不会匹配）
+        let key = non_empty(key).map(|key| resolve_secret(&key).unwrap_or(key));
+        let matches = base_url.is_some()
+            && same_base_url(base_url.as_deref(), current_base_url.as_deref())
+            && (key.is_none() || key == current_key);
+        let is_active = matches && !active_found;
+        active_found |= is_active;
+        entries.push(ProviderListEntry {
+            id,
+            name,
+            base_url,
+            is_official: false,
+            is_active,
+            config,
+        });
+    }
+    entries
+}
+
+fn to_value<T: Serialize>(config: &T) -> Value {
+    serde_json::to_value(config).unwrap_or(Value::Null)
+}
+
+async fn claude_provider_list() -> Result<Vec<ProviderListEntry>, String> {
+    let current = get_current_provider_config()?;
+    let presets = load_legacy_providers()?
+        .into_iter()
+        .map(|p| {
+            let key = non_empty(p.auth_token.clone()).or_else(|| p.api_key.clone());
+            let config = to_value(&p);
+            (p.id, p.name, Some(p.base_url), key, false, config)
+        })
+        .collect();
+    let current_key = non_empty(current.anthropic_auth_token).or(current.anthropic_api_key);
+    Ok(build_provider_list(
+        presets,
+        current.anthropic_base_url,
+        current_key,
+    ))
+}
+
+async fn codex_provider_list() -> Result<Vec<ProviderListEntry>, String> {
+    use crate::commands::codex::config::{
+        expand_env_refs, extract_api_key_from_auth, extract_base_url_from_config,
+        get_codex_provider_presets, get_current_codex_config,
+    };
+    // ${VAR} 引用按切换时的规则展开后再比较；无法展开时保留原值
+    let expand = |value: Option<String>| value.map(|v| expand_env_refs(&v).unwrap_or(v));
+
+    let current = get_current_codex_config().await?;
+    let presets = get_codex_provider_presets()
+        .await?
+        .into_iter()
+        .map(|p| {
+            let base_url = expand(extract_base_url_from_config(&p.config));
+            let key = expand(extract_api_key_from_auth(&p.auth));
+            let is_official = p.is_official.unwrap_or(false);
+            let config = to_value(&p);
+            (p.id, p.name, base_url, key, is_official, config)
+        })
+        .collect();
+    Ok(build_provider_list(
+        presets,
+        current.base_url,
+        current.api_key,
+    ))
+}
+
+async fn gemini_provider_list() -> Result<Vec<ProviderListEntry>, String> {
+    use crate::commands::gemini::provider::{
+        get_current_gemini_provider_config, get_gemini_provider_presets,
+    };
+
+    let current = get_current_gemini_provider_config().await?;
+    let presets = get_gemini_provider_presets()
+        .await?
+        .into_iter()
+        .map(|p| {
+            let base_url = p.env.get("GOOGLE_GEMINI_BASE_URL").cloned();
+            let key = p
+                .env
+                .get("GEMINI_API_KEY")
+                .or_else(|| p.env.get("GOOGLE_API_KEY"))
+                .cloned();
+            let is_official = p.is_official.unwrap_or(false);
+            let config = to_value(&p);
+            (p.id, p.name, base_url, key, is_official, config)
+        })
+        .collect();
+    Ok(build_provider_list(
+        presets,
+        current.base_url,
+        current.api_key,
+    ))
+}
+
+// 列出某个工具（claude / codex / gemini）保存的全部代理商配置，并标记当前生效的一项
+#[command]
+pub async fn list_provider_configs(tool: String) -> Result<Vec<ProviderListEntry>, String> {
+    match tool.as_str() {
+        "claude" => claude_provider_list().await,
+        "codex" => codex_provider_list().await,
+        "gemini" => gemini_provider_list().await,
+        other => Err(format!("Unknown tool: {}", other)),
+    }
+}
+
+// 复制 Claude 代理商配置，清空认证信息（apiKeyHelper 通常包含令牌，一并清空）
+fn clone_claude_provider(source_id: &str, new_id: &str, new_name: &str) -> Result<Value, String> {
+    let source = load_legacy_providers()?
+        .into_iter()
+        .find(|p| p.id == source_id)
+        .ok_or_else(|| format!("未找到ID为 '{}' 的配置", source_id))?;
+    let config = ProviderConfig {
+        id: new_id.to_string(),
+        name: new_name.to_string(),
+        auth_token: None,
+        api_key: None,
+        api_key_helper: None,
+        ..source
+    };
+    add_provider_config(config.clone())?;
+    Ok(to_value(&config))
+}
+
+async fn clone_codex_provider(
+    source_id: &str,
+    new_id: &str,
+    new_name: &str,
+) -> Result<Value, String> {
+    use crate::commands::codex::config::{add_codex_provider_config, get_codex_provider_presets};
+
+    let source = get_codex_provider_presets()
+        .await?
+        .into_iter()
+        .find(|p| p.id == source_id)
+        .ok_or_else(|| format!("Provider with ID '{}' not found", source_id))?;
+    let mut auth = source.auth.clone();
+    if let Some(auth) = auth.as_object_mut() {
+        for key in ["OPENAI_API_KEY", "OPENAI_KEY", "API_KEY"] {
+            if let Some(value) = auth.get_mut(key) {
+                *value = Value::String(String::new());
+            }
+        }
+    }
+    let config = crate::commands::codex::config::CodexProviderConfig {
+        id: new_id.to_string(),
+        name: new_name.to_string(),
+        auth,
+        is_official: Some(false),
+        created_at: Some(chrono::Utc::now().timestamp_millis()),
+        ..source
+    };
+    add_codex_provider_config(config.clone()).await?;
+    Ok(to_value(&config))
+}
+
+async fn clone_gemini_provider(
+    source_id: &str,
+    new_id: &str,
+    new_name: &str,
+) -> Result<Value, String> {
+    use crate::commands::gemini::provider::{
+        add_gemini_provider_config, get_gemini_provider_presets,
+    };
+
+    let source = get_gemini_provider_presets()
+        .await?
+        .into_iter()
+        .find(|p| p.id == source_id)
+        .ok_or_else(|| format!("Provider with ID '{}' not found", source_id))?;
+    let mut env = source.env.clone();
+    for key in ["GEMINI_API_KEY", "GOOGLE_API_KEY"] {
+        if let Some(value) = env.get_mut(key) {
+            value.clear();
+        }
+    }
+    let config = crate::commands::gemini::provider::GeminiProviderConfig {
+        id: new_id.to_string(),
+        name: new_name.to_string(),
+        env,
+        is_official: Some(false),
+        created_at: Some(chrono::Utc::now().timestamp_millis()),
+        ..source
+    };
+    add_gemini_provider_config(config.clone()).await?;
+    Ok(to_value(&config))
+}
+
+// 以已有代理商配置为模板复制出新配置（新 id / 名称，清空 API 密钥），返回新配置
+#[command]
+pub async fn clone_provider_config(
+    tool: String,
+    source_id: String,
+    new_id: String,
+    new_name: String,
+) -> Result<Value, String> {
+    let new_id = new_id.trim();
+    let new_name = new_name.trim();
+    if new_id.is_empty() || new_name.is_empty() {
+        return Err("新配置的ID和名称不能为空".to_string());
+    }
+    if new_id == OFFICIAL_PROVIDER_ID {
+        return Err(format!("ID '{}' 为保留值", OFFICIAL_PROVIDER_ID));
+    }
+
+    log::info!("复制代理商配置 ({}): {} -> {}", tool, source_id, new_id);
+    match tool.as_str() {
+        "claude" => clone_claude_provider(&source_id, new_id, new_name),
+        "codex" => clone_codex_provider(&source_id, new_id, new_name).await,
+        "gemini" => clone_gemini_provider(&source_id, new_id, new_name).await,
+        other => Err(format!("Unknown tool: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(id: &str, base_url: &str, key: Option<&str>) -> PresetSummary {
+        (
+            id.to_string(),
+            id.to_string(),
+            Some(base_url.to_string()),
+            key.map(str::to_string),
+            false,
+            Value::Null,
+        )
+    }
+
+    #[test]
+    fn marks_the_matching_provider_or_official_as_active() {
+        let presets = || {
+            vec![
+                preset("a", "https://a.example.com", Some("key-a")),
+                preset("b", "https://b.example.com/", Some("key-b")),
+                preset("b2", "https://b.example.com", None),
+            ]
+        };
+        let active = |entries: Vec<ProviderListEntry>| -> Vec<String> {
+            entries
+                .into_iter()
+                .filter(|e| e.is_active)
+                .map(|e| e.id)
+                .collect()
+        };
+
+        let entries = build_provider_list(
+            presets(),
+            Some("https://B.example.com".to_string()),
+            Some("key-b".to_string()),
+        );
+        assert_eq!(active(entries), vec!["b"]);
+
+        let entries = build_provider_list(presets(), None, None);
+        assert_eq!(active(entries), vec!["official"]);
+
+        let entries = build_provider_list(
+            presets(),
+            Some("https://a.example.com".to_string()),
+            Some("other-key".to_string()),
+        );
+        assert!(active(entries).is_empty());
+    }
+
+    #[test]
+    fn active_windows_end_at_the_next_switch() {
+        let event = |id: &str, at: &str| ProviderSwitchEvent {
+            provider_id: id.to_string(),
+            switched_at: at.to_string(),
+        };
+        let events = vec![
+            event("b", "2025-01-03T00:00:00Z"),
+            event("a", "2025-01-01T00:00:00Z"),
+            event("a", "2025-01-05T00:00:00Z"),
+        ];
+        let at = |s: &str| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+
+        assert_eq!(
+            active_windows(&events, "a"),
+            vec![
+                (at("2025-01-01T00:00:00Z"), Some(at("2025-01-03T00:00:00Z"))),
+                (at("2025-01-05T00:00:00Z"), None),
+            ]
+        );
+        assert!(active_windows(&events, "c").is_empty());
+    }
+
+    #[test]
+    fn parses_daily_costs_in_dollars() {
+        let data = serde_json::json!({
+            "total_usage": 350.0,
+            "daily_costs": [
+                { "timestamp": 1735689600.0, "line_items": [{ "cost": 100.0 }, { "cost": 50.0 }] },
+                { "timestamp": 1735776000.0, "line_items": [{ "cost": 200.0 }] }
+            ]
+        });
+        let days = parse_daily_costs(&data);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "2025-01-01");
+        assert!((days[0].cost - 1.5).abs() < f64::EPSILON);
+        assert_eq!(days[1].date, "2025-01-02");
+        assert!(days[1].tokens.is_none());
+    }
+}
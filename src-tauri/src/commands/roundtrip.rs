@@ -0,0 +1,396 @@
+//! 端到端往返测试
+//!
+//! 版本检测只能说明 CLI 可执行；这里用一个极小的 prompt 真正发起一次请求，
+//! 从而发现认证、代理或模型配置上的问题。每个引擎同一时间只允许一个测试，
+//! 超时或被 `cancel_roundtrip_test` 取消时会结束测试进程。
+
+use std::collections::HashMap;
+use std::process::{ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+use crate::claude_binary::detect_binary_for_tool;
+use crate::commands::claude::{apply_no_window_async, create_roundtrip_command, ScratchProjectDir};
+use crate::commands::gemini::config::{build_gemini_env, load_gemini_config};
+use crate::commands::gemini::session::find_gemini_binary;
+
+const ROUNDTRIP_PROMPT: &str = "Reply with OK and nothing else.";
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+/// 结果中保留的响应 / 错误输出的最大字符数
+const MAX_OUTPUT_CHARS: usize = 500;
+
+/// 正在进行的测试（引擎 -> 取消信号）
+static RUNNING_TESTS: Lazy<Mutex<HashMap<&'static str, oneshot::Sender<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundtripResult {
+    pub engine: String,
+    pub model: Option<String>,
+    pub success: bool,
+    /// 启动进程到进程退出的总耗时
+    pub latency_ms: u64,
+    /// 启动进程到收到第一段输出的耗时
+    pub first_output_ms: Option<u64>,
+    /// 响应中的第一个词
+    pub first_token: Option<String>,
+    pub response: String,
+    pub error: Option<String>,
+    pub timed_out: bool,
+    pub cancelled: bool,
+}
+
+impl RoundtripResult {
+    fn failed(engine: &str, model: Option<String>, error: String) -> Self {
+        Self {
+            engine: engine.to_string(),
+            model,
+            success: false,
+            latency_ms: 0,
+            first_output_ms: None,
+            first_token: None,
+            response: String::new(),
+            error: Some(error),
+            timed_out: false,
+            cancelled: false,
+        }
+    }
+}
+
+/// 测试结束（包括 future 被丢弃）时移除取消信号
+struct RunningGuard(&'static str);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = RUNNING_TESTS.lock() {
+            running.remove(self.0);
+        }
+    }
+}
+
+enum Outcome {
+    Finished(std::io::Result<CollectedOutput>),
+    TimedOut,
+    Cancelled,
+}
+
+struct CollectedOutput {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
+    first_output_ms: Option<u64>,
+}
+
+/// 读取输出直到进程退出，记录第一段输出到达的时间
+async fn collect_output(child: &mut Child, started: Instant) -> std::io::Result<CollectedOutput> {
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+
+    let read_stdout = async {
+        let mut output = Vec::new();
+        let mut first_output_ms = None;
+        if let Some(stdout) = stdout.as_mut() {
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stdout.read(&mut buf).await?;
+                if n == 0 {
+                    break;
+                }
+                first_output_ms.get_or_insert(started.elapsed().as_millis() as u64);
+                output.extend_from_slice(&buf[..n]);
+            }
+        }
+        Ok::<_, std::io::Error>((output, first_output_ms))
+    };
+    let read_stderr = async {
+        let mut output = Vec::new();
+        if let Some(stderr) = stderr.as_mut() {
+            stderr.read_to_end(&mut output).await?;
+        }
+        Ok::<_, std::io::Error>(output)
+    };
+
+    let (stdout, stderr) = tokio::try_join!(read_stdout, read_stderr)?;
+    let status = child.wait().await?;
+    Ok(CollectedOutput {
+        status,
+        stdout: String::from_utf8_lossy(&stdout.0).trim().to_string(),
+        stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        first_output_ms: stdout.1,
+    })
+}
+
+/// 截断到最多 `MAX_OUTPUT_CHARS` 个字符；`tail` 为 true 时保留末尾（错误信息通常在最后）
+fn truncate_output(text: &str, tail: bool) -> String {
+    let count = text.chars().count();
+    if count <= MAX_OUTPUT_CHARS {
+        return text.to_string();
+    }
+    if tail {
+        text.chars().skip(count - MAX_OUTPUT_CHARS).collect()
+    } else {
+        text.chars().take(MAX_OUTPUT_CHARS).collect()
+    }
+}
+
+fn build_result(
+    engine: &str,
+    model: Option<String>,
+    latency_ms: u64,
+    outcome: Outcome,
+) -> RoundtripResult {
+    let mut result = RoundtripResult::failed(engine, model, String::new());
+    result.latency_ms = latency_ms;
+    match outcome {
+        Outcome::Finished(Ok(output)) => {
+            result.first_output_ms = output.first_output_ms;
+            result.first_token = output.stdout.split_whitespace().next().map(str::to_string);
+            result.response = truncate_output(&output.stdout, false);
+            result.success = output.status.success() && !output.stdout.is_empty();
+            result.error = if result.success {
+                None
+            } else if !output.stderr.is_empty() {
+                Some(truncate_output(&output.stderr, true))
+            } else if !output.status.success() {
+                Some(format!("Process exited with {}", output.status))
+            } else {
+                Some("Process exited without a response".to_string())
+            };
+        }
+        Outcome::Finished(Err(e)) => {
+            result.error = Some(format!("Failed to read process output: {}", e));
+        }
+        Outcome::TimedOut => {
+            result.timed_out = true;
+            result.error = Some(format!("No response within {} ms", latency_ms));
+        }
+        Outcome::Cancelled => {
+            result.cancelled = true;
+            result.error = Some("Cancelled".to_string());
+        }
+    }
+    result
+}
+
+/// 启动测试进程并等待结果；超时或取消时结束进程
+async fn run_roundtrip(
+    engine: &'static str,
+    model: Option<String>,
+    cmd: Result<Command, String>,
+    timeout_secs: Option<u64>,
+) -> Result<RoundtripResult, String> {
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    {
+        let mut running = RUNNING_TESTS
+            .lock()
+            .map_err(|e| format!("Roundtrip test lock poisoned: {}", e))?;
+        if running.contains_key(engine) {
+            return Err(format!("A {} roundtrip test is already running", engine));
+        }
+        running.insert(engine, cancel_tx);
+    }
+    let _guard = RunningGuard(engine);
+
+    let mut cmd = match cmd {
+        Ok(cmd) => cmd,
+        Err(e) => return Ok(RoundtripResult::failed(engine, model, e)),
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    apply_no_window_async(&mut cmd);
+
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+    log::info!(
+        "Starting {} roundtrip test (model: {:?}, timeout: {:?})",
+        engine,
+        model,
+        timeout
+    );
+
+    let started = Instant::now();
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return Ok(RoundtripResult::failed(
+                engine,
+                model,
+                format!("Failed to spawn {}: {}", engine, e),
+            ))
+        }
+    };
+
+    let outcome = tokio::select! {
+        output = collect_output(&mut child, started) => Outcome::Finished(output),
+        _ = tokio::time::sleep(timeout) => Outcome::TimedOut,
+        _ = cancel_rx => Outcome::Cancelled,
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    if !matches!(outcome, Outcome::Finished(_)) {
+        if let Err(e) = child.kill().await {
+            log::warn!("Failed to kill {} roundtrip process: {}", engine, e);
+        }
+    }
+
+    let result = build_result(engine, model, latency_ms, outcome);
+    log::info!(
+        "{} roundtrip test finished: success={}, latency={}ms, error={:?}",
+        engine,
+        result.success,
+        result.latency_ms,
+        result.error
+    );
+    Ok(result)
+}
+
+fn codex_roundtrip_command(model: Option<&str>) -> Result<Command, String> {
+    #[cfg(target_os = "windows")]
+    if crate::commands::wsl_utils::get_wsl_config().enabled {
+        return Err("The roundtrip test does not support Codex in WSL mode".to_string());
+    }
+
+    let (_env_info, detected) = detect_binary_for_tool("codex", "CODEX_PATH", "codex");
+    let codex_path = detected
+        .map(|inst| inst.path)
+        .unwrap_or_else(|| "codex".to_string());
+    let mut cmd = Command::new(codex_path);
+    cmd.args(["exec", "--skip-git-repo-check"]);
+    if let Some(model) = model {
+        cmd.args(["--model", model]);
+    }
+    cmd.arg(ROUNDTRIP_PROMPT);
+    cmd.current_dir(std::env::temp_dir());
+    Ok(cmd)
+}
+
+fn gemini_roundtrip_command(model: &str) -> Result<Command, String> {
+    let gemini_path = find_gemini_binary()?;
+    if gemini_path.starts_with("WSL:") {
+        return Err("The roundtrip test does not support Gemini in WSL mode".to_string());
+    }
+
+    let config = load_gemini_config().unwrap_or_default();
+    let mut cmd = Command::new(gemini_path);
+    cmd.args([
+        "--output-format",
+        "text",
+        "--model",
+        model,
+        "-p",
+        ROUNDTRIP_PROMPT,
+    ]);
+    cmd.envs(build_gemini_env(&config));
+    cmd.current_dir(std::env::temp_dir());
+    Ok(cmd)
+}
+
+fn non_empty(model: Option<String>) -> Option<String> {
+    model.filter(|m| !m.trim().is_empty())
+}
+
+/// Sends a tiny prompt through Claude and reports success, latency and the first response token
+#[tauri::command]
+pub async fn test_claude_roundtrip(
+    app: AppHandle,
+    model: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<RoundtripResult, String> {
+    let model = non_empty(model);
+    // `claude -p` 会记录会话；临时目录在测试结束时连同该会话一起删除
+    let scratch = ScratchProjectDir::new();
+    let cmd = scratch.as_ref().map_err(Clone::clone).and_then(|scratch| {
+        create_roundtrip_command(&app, model.as_deref(), ROUNDTRIP_PROMPT, scratch.path())
+    });
+    run_roundtrip("claude", model, cmd, timeout_secs).await
+}
+
+/// Sends a tiny prompt through Codex and reports success, latency and the first response token
+#[tauri::command]
+pub async fn test_codex_roundtrip(
+    model: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<RoundtripResult, String> {
+    let model = non_empty(model);
+    let cmd = codex_roundtrip_command(model.as_deref());
+    run_roundtrip("codex", model, cmd, timeout_secs).await
+}
+
+/// Sends a tiny prompt through Gemini and reports success, latency and the first response token
+#[tauri::command]
+pub async fn test_gemini_roundtrip(
+    model: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<RoundtripResult, String> {
+    let model =
+        non_empty(model).unwrap_or_else(|| load_gemini_config().unwrap_or_default().default_model);
+    let cmd = gemini_roundtrip_command(&model);
+    run_roundtrip("gemini", Some(model), cmd, timeout_secs).await
+}
+
+/// Cancels the running roundtrip test of an engine ("claude", "codex" or "gemini"); returns whether one was running
+#[tauri::command]
+pub async fn cancel_roundtrip_test(engine: String) -> Result<bool, String> {
+    let sender = RUNNING_TESTS
+        .lock()
+        .map_err(|e| format!("Roundtrip test lock poisoned: {}", e))?
+        .remove(engine.as_str());
+    Ok(sender.map(|tx| tx.send(()).is_ok()).unwrap_or(false))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_response_timeout_and_cancellation() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo 'OK done'"]);
+        let result = run_roundtrip("test-ok", None, Ok(cmd), Some(5))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.first_token.as_deref(), Some("OK"));
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo 'auth failed' >&2; exit 1"]);
+        let result = run_roundtrip("test-fail", None, Ok(cmd), Some(5))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("auth failed"));
+
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let result = run_roundtrip("test-timeout", None, Ok(cmd), Some(1))
+            .await
+            .unwrap();
+        assert!(result.timed_out);
+
+        let run = tokio::spawn(async {
+            let mut cmd = Command::new("sleep");
+            cmd.arg("30");
+            run_roundtrip("test-cancel", None, Ok(cmd), Some(30)).await
+        });
+        let mut cancelled = false;
+        for _ in 0..40 {
+            if cancel_roundtrip_test("test-cancel".to_string())
+                .await
+                .unwrap()
+            {
+                cancelled = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(cancelled);
+        assert!(run.await.unwrap().unwrap().cancelled);
+    }
+}
@@ -0,0 +1,93 @@
+//! CLI 会话并发限制
+//!
+//! Claude / Codex / Gemini 共用一个 `SessionLimiter`：达到上限后根据配置排队或直接拒绝，
+//! 进程退出（包括被取消）时释放名额。
+
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::storage::{load_app_setting, store_app_setting};
+use crate::error::{AppError, AppResult};
+use crate::process::concurrency::SessionPermit;
+use crate::process::{SessionLimiterState, SessionLimits, SessionQueueStatus};
+
+/// app_settings 中保存并发限制（JSON）的键
+const SESSION_LIMITS_SETTING: &str = "session_concurrency";
+
+/// 读取持久化的并发限制
+pub fn load_session_limits(app: &AppHandle) -> Option<SessionLimits> {
+    load_app_setting(app, SESSION_LIMITS_SETTING)
+}
+
+/// 启动 CLI 进程前获取名额；需要排队时先发送 `session-queued` 事件，便于前端显示排队状态。
+/// 返回的 permit 需要一直持有到进程退出；排队中被取消返回 `AppError::Cancelled`，
+/// 达到上限且不排队时返回 `AppError::QueueFull`。
+pub(crate) async fn acquire_session_permit(
+    app: &AppHandle,
+    engine: &str,
+    project_path: &str,
+    session_id: Option<String>,
+    tab_id: Option<String>,
+) -> AppResult<SessionPermit> {
+    let limiter = app.state::<SessionLimiterState>().0.clone();
+    if limiter.is_full() {
+        let payload = serde_json::json!({
+            "engine": engine,
+            "project_path": project_path,
+            "session_id": session_id,
+            "tab_id": tab_id,
+            "queue_when_full": limiter.limits().queue_when_full,
+        });
+        let _ = app.emit("session-queued", payload);
+    }
+    limiter
+        .acquire(engine, project_path, session_id, tab_id)
+        .await
+}
+
+/// Returns the running and queued CLI sessions together with the current limit
+#[tauri::command]
+pub async fn get_session_queue_status(
+    limiter: State<'_, SessionLimiterState>,
+) -> AppResult<SessionQueueStatus> {
+    Ok(limiter.0.status())
+}
+
+/// Get the max-concurrent-sessions limit and whether extra sessions are queued
+#[tauri::command]
+pub async fn get_session_concurrency_config(
+    limiter: State<'_, SessionLimiterState>,
+) -> AppResult<SessionLimits> {
+    Ok(limiter.0.limits())
+}
+
+/// Set the max-concurrent-sessions limit (0 = unlimited) and persist it
+#[tauri::command]
+pub async fn set_session_concurrency_config(
+    app: AppHandle,
+    limiter: State<'_, SessionLimiterState>,
+    max_concurrent_sessions: usize,
+    queue_when_full: bool,
+) -> AppResult<SessionLimits> {
+    let limits = SessionLimits {
+        max_concurrent_sessions,
+        queue_when_full,
+    };
+    limiter.0.set_limits(limits).map_err(AppError::External)?;
+    store_app_setting(&app, SESSION_LIMITS_SETTING, &limits)?;
+
+    log::info!(
+        "Session concurrency set to {} (queue when full: {})",
+        max_concurrent_sessions,
+        queue_when_full
+    );
+    Ok(limits)
+}
+
+/// Removes a session from the queue before it starts; returns false if it was not queued
+#[tauri::command]
+pub async fn cancel_queued_session(
+    limiter: State<'_, SessionLimiterState>,
+    queue_id: u64,
+) -> AppResult<bool> {
+    Ok(limiter.0.cancel_queued(Some(queue_id), None))
+}
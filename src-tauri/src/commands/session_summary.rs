@@ -0,0 +1,275 @@
+//! 会话摘要
+//!
+//! 读取 Claude 会话的对话记录，拼成摘要提示词后交给选定的 CLI（Claude / Codex / Gemini）
+//! 以非流式方式执行并返回输出。摘要按会话 JSONL 的修改时间缓存，会话没有变化时直接返回缓存。
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use once_cell::sync::Lazy;
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::claude_binary::create_command_with_env;
+use crate::commands::claude::{
+    get_claude_dir, load_session_history_structured, ScratchProjectDir, SessionEntry,
+};
+
+/// 未指定时对话记录的最大输入 token 数（按 4 字符 ≈ 1 token 估算）
+const DEFAULT_MAX_INPUT_TOKENS: usize = 50_000;
+const CHARS_PER_TOKEN: usize = 4;
+/// 单个工具调用参数在记录中保留的最大字符数
+const TOOL_INPUT_PREVIEW_CHARS: usize = 200;
+const CLI_TIMEOUT: Duration = Duration::from_secs(300);
+
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the following coding session transcript. \
+Start with a one-paragraph TL;DR, then list what was done, the files that were changed and any \
+open issues or next steps as short bullet points. Reply with the summary only.";
+
+/// (session_id, engine, model, 输入预算) -> (JSONL 修改时间, 摘要)
+type SummaryCacheKey = (String, String, String, usize);
+static SUMMARY_CACHE: Lazy<Mutex<HashMap<SummaryCacheKey, (SystemTime, String)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 把结构化条目渲染为纯文本记录；跳过子代理消息和工具结果（通常很长且对摘要帮助不大）
+fn render_transcript(entries: &[SessionEntry]) -> String {
+    let mut transcript = String::new();
+    for entry in entries {
+        match entry {
+            SessionEntry::UserMessage { meta, text } if meta.parent_tool_use_id.is_none() => {
+                transcript.push_str(&format!("User: {}\n\n", text.trim()));
+            }
+            SessionEntry::AssistantMessage { meta, text, .. }
+                if meta.parent_tool_use_id.is_none() =>
+            {
+                transcript.push_str(&format!("Assistant: {}\n\n", text.trim()));
+            }
+            SessionEntry::ToolUse {
+                meta, name, input, ..
+            } if meta.parent_tool_use_id.is_none() => {
+                let input: String = input
+                    .to_string()
+                    .chars()
+                    .take(TOOL_INPUT_PREVIEW_CHARS)
+                    .collect();
+                transcript.push_str(&format!("[Tool call: {} {}]\n\n", name, input));
+            }
+            SessionEntry::ToolResult { is_error: true, .. } => {
+                transcript.push_str("[Tool call failed]\n\n");
+            }
+            _ => {}
+        }
+    }
+    transcript
+}
+
+/// 超过 `max_chars` 时保留开头和结尾，省略中间部分
+fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let head = max_chars / 2;
+    let tail = max_chars - head;
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(total - tail).collect();
+    format!(
+        "{}\n\n[... {} characters omitted ...]\n\n{}",
+        start,
+        total - max_chars,
+        end
+    )
+}
+
+/// 单次提示词命令及其临时工作目录；命令运行结束、该值被丢弃时删除目录和 CLI 记录的会话
+pub(crate) struct PromptCommand {
+    cmd: Command,
+    _scratch: ScratchProjectDir,
+}
+
+/// 构建执行单次提示词的非交互命令；提示词通过 stdin 传入
+///
+/// 也被提交信息生成等其它一次性任务复用
+pub(crate) async fn build_prompt_command(
+    app: &AppHandle,
+    engine: &str,
+    model: Option<&str>,
+) -> Result<PromptCommand, String> {
+    let (program, mut args) = match engine {
+        "claude" => {
+            let path = crate::claude_binary::find_claude_binary(app)?;
+            (
+                path,
+                vec![
+                    "-p".to_string(),
+                    "--output-format".to_string(),
+                    "text".to_string(),
+                ],
+            )
+        }
+        "codex" => {
+            let path = crate::commands::codex::get_codex_binary_info(app.clone())
+                .await?
+                .path;
+            (
+                path,
+                vec!["exec".to_string(), "--skip-git-repo-check".to_string()],
+            )
+        }
+        "gemini" => {
+            let path = crate::commands::gemini::session::find_gemini_binary()?;
+            if path.starts_with("WSL:") {
+                return Err("Gemini prompts are not supported in WSL mode".to_string());
+            }
+            (path, Vec::new())
+        }
+        other => return Err(format!("Unsupported engine: {}", other)),
+    };
+
+    if let Some(model) = model {
+        args.push("--model".to_string());
+        args.push(model.to_string());
+    }
+    if engine == "codex" {
+        args.push("-".to_string());
+    }
+
+    // 在独立的临时目录执行，结束后连同 CLI 为它记录的会话一起删除，
+    // 避免这次调用本身作为新会话留在 ~/.claude/projects 下
+    let scratch = ScratchProjectDir::new()?;
+    let mut cmd = Command::from(create_command_with_env(&program));
+    cmd.args(args)
+        .current_dir(scratch.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    Ok(PromptCommand {
+        cmd,
+        _scratch: scratch,
+    })
+}
+
+pub(crate) async fn run_prompt_command(
+    mut command: PromptCommand,
+    prompt: String,
+) -> Result<String, String> {
+    let mut child = command
+        .cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start CLI: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        tokio::spawn(async move {
+            if let Err(e) = stdin.write_all(prompt.as_bytes()).await {
+                log::warn!("Failed to write prompt to CLI: {}", e);
+            }
+        });
+    }
+
+    let output = tokio::time::timeout(CLI_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("CLI timed out after {}s", CLI_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run CLI: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "CLI exited with {}: {}",
+            output.status,
+            stderr.trim()
+        ));
+    }
+    if stdout.is_empty() {
+        return Err("CLI returned no output".to_string());
+    }
+    Ok(stdout)
+}
+
+/// Summarize a Claude session with the chosen CLI (claude, codex or gemini; defaults to claude); cached until the session changes
+#[tauri::command]
+pub async fn summarize_session(
+    app: AppHandle,
+    session_id: String,
+    project_id: String,
+    model: Option<String>,
+    engine: Option<String>,
+    max_input_tokens: Option<usize>,
+) -> Result<String, String> {
+    let engine = engine.unwrap_or_else(|| "claude".to_string());
+    let model = model.filter(|m| !m.trim().is_empty());
+    let max_input_tokens = max_input_tokens.unwrap_or(DEFAULT_MAX_INPUT_TOKENS);
+
+    let session_path = get_claude_dir()
+        .map_err(|e| e.to_string())?
+        .join("projects")
+        .join(&project_id)
+        .join(format!("{}.jsonl", session_id));
+    let modified = std::fs::metadata(&session_path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("Failed to read session file: {}", e))?;
+
+    let cache_key = (
+        session_id.clone(),
+        engine.clone(),
+        model.clone().unwrap_or_default(),
+        max_input_tokens,
+    );
+    if let Some((cached_at, summary)) = SUMMARY_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&cache_key).cloned())
+    {
+        if cached_at == modified {
+            log::debug!("Using cached summary for session {}", session_id);
+            return Ok(summary);
+        }
+    }
+
+    let entries = load_session_history_structured(session_id.clone(), project_id).await?;
+    let transcript = render_transcript(&entries);
+    if transcript.trim().is_empty() {
+        return Err("Session has no messages to summarize".to_string());
+    }
+    let transcript = truncate_middle(
+        &transcript,
+        max_input_tokens.saturating_mul(CHARS_PER_TOKEN),
+    );
+    let prompt = format!(
+        "{}\n\n<transcript>\n{}\n</transcript>",
+        SUMMARY_INSTRUCTIONS, transcript
+    );
+
+    log::info!(
+        "Summarizing session {} with {} (model: {:?}, prompt_len={})",
+        session_id,
+        engine,
+        model,
+        prompt.len()
+    );
+    let cmd = build_prompt_command(&app, &engine, model.as_deref()).await?;
+    let summary = run_prompt_command(cmd, prompt).await?;
+
+    if let Ok(mut cache) = SUMMARY_CACHE.lock() {
+        cache.insert(cache_key, (modified, summary.clone()));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_the_middle_of_long_transcripts() {
+        assert_eq!(truncate_middle("short", 10), "short");
+
+        let truncated = truncate_middle("abcdefghij", 4);
+        assert!(truncated.starts_with("ab\n"));
+        assert!(truncated.ends_with("\nij"));
+        assert!(truncated.contains("[... 6 characters omitted ...]"));
+    }
+}
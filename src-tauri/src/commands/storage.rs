@@ -1,11 +1,14 @@
 use anyhow::Result;
 use rusqlite::{params, types::ValueRef, Connection, Result as SqliteResult};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
 use std::collections::HashMap;
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager, State};
 
+use crate::error::{AppError, AppResult};
+
 // Database wrapper for storage operations
 pub struct AgentDb(pub Mutex<Connection>);
 
@@ -30,72 +33,127 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
 
     log::info!("✅ SQLite WAL mode enabled with performance optimizations");
 
-    // Create usage_entries table for token usage tracking
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS usage_entries (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            session_id TEXT NOT NULL,
-            timestamp TEXT NOT NULL,
-            model TEXT NOT NULL,
-            input_tokens INTEGER DEFAULT 0,
-            output_tokens INTEGER DEFAULT 0,
-            cache_creation_tokens INTEGER DEFAULT 0,
-            cache_read_tokens INTEGER DEFAULT 0,
-            total_tokens INTEGER DEFAULT 0,
-            cost REAL DEFAULT 0.0,
-            project_path TEXT,
-            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
-        [],
-    )?;
-
-    // ========== 🚀 性能优化：添加数据库索引 ==========
-
-    // 1. 会话查询索引（最常用的查询模式）
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_session_id
-         ON usage_entries(session_id)",
-        [],
-    )?;
+    run_migrations(&conn)?;
 
-    // 2. 时间范围查询索引（按时间排序和过滤）
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_timestamp
-         ON usage_entries(timestamp DESC)",
-        [],
-    )?;
+    Ok(conn)
+}
 
-    // 3. 项目路径索引（跨会话统计）
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_project_path
-         ON usage_entries(project_path)",
-        [],
-    )?;
+/// 一次 schema 迁移：SQL 需可重复执行（IF NOT EXISTS），以兼容迁移表出现之前创建的数据库
+struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
 
-    // 4. 复合索引：模型 + 时间（按模型统计成本趋势）
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_model_timestamp
-         ON usage_entries(model, timestamp DESC)",
-        [],
-    )?;
+/// 所有 schema 定义集中在这里，按版本号递增追加，已发布的迁移不要修改
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "usage_entries",
+        sql: "CREATE TABLE IF NOT EXISTS usage_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                model TEXT NOT NULL,
+                input_tokens INTEGER DEFAULT 0,
+                output_tokens INTEGER DEFAULT 0,
+                cache_creation_tokens INTEGER DEFAULT 0,
+                cache_read_tokens INTEGER DEFAULT 0,
+                total_tokens INTEGER DEFAULT 0,
+                cost REAL DEFAULT 0.0,
+                project_path TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            -- 会话查询索引（最常用的查询模式）
+            CREATE INDEX IF NOT EXISTS idx_usage_session_id
+                ON usage_entries(session_id);
+            -- 时间范围查询索引（按时间排序和过滤）
+            CREATE INDEX IF NOT EXISTS idx_usage_timestamp
+                ON usage_entries(timestamp DESC);
+            -- 项目路径索引（跨会话统计）
+            CREATE INDEX IF NOT EXISTS idx_usage_project_path
+                ON usage_entries(project_path);
+            -- 复合索引：模型 + 时间（按模型统计成本趋势）
+            CREATE INDEX IF NOT EXISTS idx_usage_model_timestamp
+                ON usage_entries(model, timestamp DESC);
+            -- 复合索引：项目 + 会话（项目级详细统计）
+            CREATE INDEX IF NOT EXISTS idx_usage_project_session
+                ON usage_entries(project_path, session_id);
+            -- 成本查询索引（用于成本排序和统计）
+            CREATE INDEX IF NOT EXISTS idx_usage_cost
+                ON usage_entries(cost DESC);",
+    },
+    Migration {
+        version: 2,
+        name: "app_settings",
+        sql: "CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+    },
+    Migration {
+        version: 3,
+        name: "compaction_history",
+        sql: "CREATE TABLE IF NOT EXISTS compaction_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                trigger_kind TEXT NOT NULL,
+                strategy TEXT NOT NULL,
+                tokens_before INTEGER NOT NULL,
+                tokens_after INTEGER,
+                messages_removed INTEGER,
+                success INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_compaction_session_timestamp
+                ON compaction_history(session_id, timestamp DESC);",
+    },
+];
+
+/// 最新的 schema 版本
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
 
-    // 5. 复合索引：项目 + 会话（项目级详细统计）
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_project_session
-         ON usage_entries(project_path, session_id)",
+/// 数据库当前的 schema 版本（未执行过迁移时为 0）
+pub fn schema_version(conn: &Connection) -> SqliteResult<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM _migrations",
         [],
-    )?;
+        |row| row.get(0),
+    )
+}
 
-    // 6. 成本查询索引（用于成本排序和统计）
+/// 执行尚未应用的迁移，每个迁移在独立事务中执行并记录到 `_migrations`
+pub fn run_migrations(conn: &Connection) -> SqliteResult<()> {
     conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_usage_cost
-         ON usage_entries(cost DESC)",
+        "CREATE TABLE IF NOT EXISTS _migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
         [],
     )?;
 
-    log::info!("✅ Database indexes created successfully (6 indexes)");
+    let current = schema_version(conn)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration.sql)?;
+        tx.execute(
+            "INSERT INTO _migrations (version, name) VALUES (?1, ?2)",
+            params![migration.version, migration.name],
+        )?;
+        tx.commit()?;
+        log::info!(
+            "Applied database migration {} ({})",
+            migration.version,
+            migration.name
+        );
+    }
 
-    Ok(conn)
+    log::info!("✅ Database schema at version {}", latest_schema_version());
+    Ok(())
 }
 
 /// Represents metadata about a database table
@@ -789,3 +847,103 @@ pub async fn storage_analyze_query(
 
     Ok(result)
 }
+
+/// 读取 agents.db 中 app_settings 表的值
+pub(crate) fn read_app_setting(app: &AppHandle, key: &str) -> Option<String> {
+    let db_path = app.path().app_data_dir().ok()?.join("agents.db");
+    if !db_path.exists() {
+        return None;
+    }
+    let conn = rusqlite::Connection::open(&db_path).ok()?;
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        [key],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .filter(|value| !value.trim().is_empty())
+}
+
+/// 读取 app_settings 中以 JSON 保存的值；未设置或无法解析时返回 None
+pub(crate) fn load_app_setting<T: DeserializeOwned>(app: &AppHandle, key: &str) -> Option<T> {
+    let value = read_app_setting(app, key)?;
+    match serde_json::from_str(&value) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!("Ignoring invalid app setting {}: {}", key, e);
+            None
+        }
+    }
+}
+
+/// 写入 app_settings 的原始值；`value` 为 None 时删除该键
+pub(crate) fn write_app_setting(app: &AppHandle, key: &str, value: Option<&str>) -> AppResult<()> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::io(format!("Failed to get app data directory: {}", e)))?;
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| AppError::from_io("Failed to create app data directory", e))?;
+    let conn = rusqlite::Connection::open(app_data_dir.join("agents.db"))
+        .map_err(|e| AppError::io(format!("Failed to open database: {}", e)))?;
+
+    let result = match value {
+        Some(value) => conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+            rusqlite::params![key, value],
+        ),
+        None => conn.execute("DELETE FROM app_settings WHERE key = ?1", [key]),
+    };
+    result
+        .map(|_| ())
+        .map_err(|e| AppError::io(format!("Failed to store {}: {}", key, e)))
+}
+
+/// 以 JSON 保存到 app_settings
+pub(crate) fn store_app_setting<T: Serialize>(
+    app: &AppHandle,
+    key: &str,
+    value: &T,
+) -> AppResult<()> {
+    let value = serde_json::to_string(value)
+        .map_err(|e| AppError::external(format!("Failed to serialize {}: {}", key, e)))?;
+    write_app_setting(app, key, Some(&value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_recorded_and_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        // A database created before migrations existed already has the tables
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES ('default_model', 'opus')",
+            [],
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+        let recorded: i64 = conn
+            .query_row("SELECT COUNT(*) FROM _migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(recorded, MIGRATIONS.len() as i64);
+        let model: String = conn
+            .query_row(
+                "SELECT value FROM app_settings WHERE key = 'default_model'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(model, "opus");
+    }
+}
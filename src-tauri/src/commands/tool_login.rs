@@ -0,0 +1,315 @@
+//! 在终端中运行 CLI 登录命令
+//!
+//! 运行器的 stdin 是管道，无法完成交互式登录。这里打开系统终端，用检测到的 CLI 路径运行
+//! `claude login` / `codex login` 等命令，命令结束后把退出码写入临时标记文件，应用轮询该文件
+//! 判断登录是否完成。找不到可用终端时返回手动操作说明。
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::claude_binary::{claude_override_path, clear_cached_claude_path, find_claude_binary};
+use crate::commands::agent::AgentTool;
+use crate::commands::auth_detection::login_command;
+use crate::commands::tool_updates::detect_installation_async;
+
+/// 等待登录完成的最长时间
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// 轮询标记文件的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolLoginStatus {
+    Success,
+    Failed,
+    TimedOut,
+    /// 没有可用的终端，需要用户自行运行登录命令
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolLoginResult {
+    pub tool: String,
+    pub status: ToolLoginStatus,
+    pub login_command: String,
+    pub exit_code: Option<i32>,
+    pub message: String,
+}
+
+/// 各工具的登录参数（Gemini 交互启动即可登录）
+fn login_args(tool: AgentTool) -> &'static [&'static str] {
+    match tool {
+        AgentTool::Claude | AgentTool::Codex => &["login"],
+        AgentTool::Gemini => &[],
+    }
+}
+
+/// 在终端中运行的 CLI：Claude 使用 `find_claude_binary`，其余工具使用安装检测结果；
+/// 都找不到时退回到 PATH 中的命令名
+async fn login_program(app: &AppHandle, tool: AgentTool) -> String {
+    let detected = match tool {
+        AgentTool::Claude => {
+            let app = app.clone();
+            tokio::task::spawn_blocking(move || find_claude_binary(&app).ok())
+                .await
+                .ok()
+                .flatten()
+        }
+        AgentTool::Codex | AgentTool::Gemini => detect_installation_async(app, tool)
+            .await
+            .map(|installation| installation.path),
+    };
+    detected.unwrap_or_else(|| {
+        log::warn!(
+            "{} binary not detected, falling back to PATH lookup",
+            tool.as_str()
+        );
+        tool.as_str().to_string()
+    })
+}
+
+/// 单引号包裹，供 sh 使用
+#[cfg(not(target_os = "windows"))]
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// 在终端中运行的脚本：执行登录命令后把退出码写入标记文件
+#[cfg(not(target_os = "windows"))]
+fn login_script(program: &str, args: &[&str], marker: &Path) -> String {
+    let command = std::iter::once(program)
+        .chain(args.iter().copied())
+        .map(sh_quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "{}; echo $? > {}",
+        command,
+        sh_quote(&marker.to_string_lossy())
+    )
+}
+
+/// `cmd /C` 的参数：用 `start` 打开新窗口，在开启延迟展开的 cmd 中运行登录命令，
+/// 结束后把 `!errorlevel!` 写入标记文件。
+///
+/// 程序和标记文件路径各自加引号（可能含空格），整条脚本再包一层引号：内层 cmd 会去掉
+/// 首尾两个引号，外层 cmd 则把 `&` 和 `>` 视为引号内的内容，不会提前拆分命令
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn windows_start_args(program: &str, args: &[&str], marker: &Path) -> String {
+    let mut command = format!("\"{}\"", program);
+    for arg in args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+    format!(
+        "/C start \"AnyCode login\" cmd /V:ON /C \"{} & echo !errorlevel! > \"{}\"\"",
+        command,
+        marker.to_string_lossy()
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn open_terminal(program: &str, args: &[&str], marker: &Path) -> Result<(), String> {
+    let script = login_script(program, args, marker)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    std::process::Command::new("osascript")
+        .arg("-e")
+        .arg("tell application \"Terminal\" to activate")
+        .arg("-e")
+        .arg(format!(
+            "tell application \"Terminal\" to do script \"{}\"",
+            script
+        ))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open Terminal.app: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn open_terminal(program: &str, args: &[&str], marker: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    // 延迟展开 !errorlevel!，在登录命令结束后才取值
+    std::process::Command::new("cmd")
+        .raw_arg(windows_start_args(program, args, marker))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open a command prompt: {}", e))
+}
+
+/// 常见终端模拟器及其“执行命令”参数，按优先级排列
+#[cfg(all(unix, not(target_os = "macos")))]
+const LINUX_TERMINALS: &[(&str, &[&str])] = &[
+    ("x-terminal-emulator", &["-e"]),
+    ("gnome-terminal", &["--"]),
+    ("konsole", &["-e"]),
+    ("xfce4-terminal", &["-x"]),
+    ("alacritty", &["-e"]),
+    ("kitty", &[]),
+    ("xterm", &["-e"]),
+];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn open_terminal(program: &str, args: &[&str], marker: &Path) -> Result<(), String> {
+    let (terminal, exec_args) = LINUX_TERMINALS
+        .iter()
+        .find_map(|(name, args)| find_in_path(name).map(|path| (path, *args)))
+        .ok_or("No terminal emulator found")?;
+    std::process::Command::new(&terminal)
+        .args(exec_args)
+        .args(["sh", "-c", &login_script(program, args, marker)])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", terminal.display(), e))
+}
+
+/// 标记文件写完后返回退出码；文件尚未出现或内容不完整时返回 None
+fn read_exit_code(marker: &Path) -> Option<i32> {
+    std::fs::read_to_string(marker).ok()?.trim().parse().ok()
+}
+
+/// 登录 / 更新成功后清空可用性缓存，下次检查时重新检测
+pub(crate) async fn invalidate_tool_caches(app: &AppHandle, tool: AgentTool) {
+    match tool {
+        // 用户手动设置的路径保留，其余缓存路径清除后重新检测
+        AgentTool::Claude if claude_override_path().is_none() => {
+            if let Err(e) = clear_cached_claude_path(app) {
+                log::warn!("Failed to clear cached Claude path: {}", e);
+            }
+        }
+        AgentTool::Claude => {}
+        AgentTool::Codex => {
+            crate::commands::codex::config::invalidate_codex_availability_cache().await
+        }
+        AgentTool::Gemini => {
+            crate::commands::gemini::session::invalidate_gemini_install_cache().await
+        }
+    }
+}
+
+fn marker_path(tool: AgentTool) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "anycode-login-{}-{}.status",
+        tool.as_str(),
+        uuid::Uuid::new_v4()
+    ))
+}
+
+/// Opens a terminal running the tool's login command ("claude", "codex" or "gemini") and
+/// waits until it finishes or times out; returns instructions when no terminal is available
+#[tauri::command]
+pub async fn start_tool_login(app: AppHandle, tool: String) -> Result<ToolLoginResult, String> {
+    let tool = AgentTool::parse(&tool)?;
+    let command = login_command(tool);
+    let program = login_program(&app, tool).await;
+    let marker = marker_path(tool);
+    let result = |status, exit_code, message: String| ToolLoginResult {
+        tool: tool.as_str().to_string(),
+        status,
+        login_command: command.to_string(),
+        exit_code,
+        message,
+    };
+
+    if let Err(e) = open_terminal(&program, login_args(tool), &marker) {
+        log::warn!("Cannot open a terminal for {} login: {}", tool.as_str(), e);
+        return Ok(result(
+            ToolLoginStatus::Manual,
+            None,
+            format!("{}. Run `{}` in a terminal, then retry.", e, command),
+        ));
+    }
+    log::info!("Started `{}` ({}) in a terminal window", command, program);
+
+    let deadline = tokio::time::Instant::now() + LOGIN_TIMEOUT;
+    let exit_code = loop {
+        if let Some(code) = read_exit_code(&marker) {
+            break Some(code);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            break None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    let _ = std::fs::remove_file(&marker);
+
+    Ok(match exit_code {
+        Some(0) => {
+            invalidate_tool_caches(&app, tool).await;
+            result(
+                ToolLoginStatus::Success,
+                Some(0),
+                format!("`{}` completed", command),
+            )
+        }
+        Some(code) => result(
+            ToolLoginStatus::Failed,
+            Some(code),
+            format!("`{}` exited with code {}", command, code),
+        ),
+        None => result(
+            ToolLoginStatus::TimedOut,
+            None,
+            format!(
+                "Timed out waiting for `{}` to finish; retry once login is complete",
+                command
+            ),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_code_is_read_from_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("login.status");
+        assert_eq!(read_exit_code(&marker), None);
+        std::fs::write(&marker, "").unwrap();
+        assert_eq!(read_exit_code(&marker), None);
+        std::fs::write(&marker, "0\n").unwrap();
+        assert_eq!(read_exit_code(&marker), Some(0));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn login_script_quotes_program_and_marker_path() {
+        let script = login_script(
+            "/opt/my tools/codex",
+            &["login"],
+            Path::new("/tmp/it's.status"),
+        );
+        assert_eq!(
+            script,
+            r"'/opt/my tools/codex' 'login'; echo $? > '/tmp/it'\''s.status'"
+        );
+    }
+
+    #[test]
+    fn windows_start_args_quote_paths_with_spaces() {
+        let args = windows_start_args(
+            r"C:\Program Files\nodejs\claude.cmd",
+            &["login"],
+            Path::new(r"C:\Users\Jane Doe\Temp\login.status"),
+        );
+        assert_eq!(
+            args,
+            r#"/C start "AnyCode login" cmd /V:ON /C ""C:\Program Files\nodejs\claude.cmd" login & echo !errorlevel! > "C:\Users\Jane Doe\Temp\login.status"""#
+        );
+    }
+}
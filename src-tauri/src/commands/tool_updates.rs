@@ -0,0 +1,672 @@
+//! CLI 工具更新检查
+//!
+//! 把检测到的 Claude / Codex / Gemini 版本与 npm registry 上发布的最新版本比较。
+//! 最新版本缓存一天，保存在 ~/.anycode/tool_updates.json；离线或请求失败时最新版本记为 "unknown"，
+//! 不写入缓存，下次检查时重试。已安装版本每次都重新检测，更新后立即反映。
+//!
+//! `update_tool` 根据检测到的安装来源选择包管理器执行更新，输出通过 `tool-update-progress` 事件发送。
+//! Homebrew、系统目录、WSL 等无法安全自动更新的安装方式会被拒绝，并返回应手动执行的命令。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::claude_binary::{
+    compare_versions, detect_binary_for_tool, infer_installation_source, ClaudeInstallation,
+    InstallationType,
+};
+use crate::commands::agent::AgentTool;
+use crate::commands::tool_login::invalidate_tool_caches;
+use crate::utils::config_utils::{load_json_config, save_json_config};
+
+/// 最新版本缓存的有效期
+const CACHE_TTL_HOURS: i64 = 24;
+/// 无法获取最新版本时返回的占位值
+const UNKNOWN_VERSION: &str = "unknown";
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const ALL_TOOLS: [AgentTool; 3] = [AgentTool::Claude, AgentTool::Codex, AgentTool::Gemini];
+
+/// 正在更新的工具，避免同一个包管理器命令并发执行
+static UPDATING_TOOLS: Lazy<std::sync::Mutex<HashSet<&'static str>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashSet::new()));
+
+/// 单个工具的更新状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolUpdateStatus {
+    pub tool: String,
+    pub package: String,
+    pub installed: bool,
+    pub current_version: Option<String>,
+    /// 最新发布的版本；离线时为 "unknown"
+    pub latest_version: String,
+    pub update_available: bool,
+    /// 检测到的安装来源（nvm、homebrew、npm-global 等）
+    pub source: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedLatestVersion {
+    version: String,
+    fetched_at: DateTime<Utc>,
+}
+
+/// 按 npm 包名缓存的最新版本
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct LatestVersionCache {
+    packages: HashMap<String, CachedLatestVersion>,
+}
+
+impl LatestVersionCache {
+    fn fresh(&self, package: &str, now: DateTime<Utc>) -> Option<&str> {
+        self.packages
+            .get(package)
+            .filter(|entry| now - entry.fetched_at < chrono::Duration::hours(CACHE_TTL_HOURS))
+            .map(|entry| entry.version.as_str())
+    }
+}
+
+/// 各工具发布到 npm 的包名
+pub(crate) fn npm_package(tool: AgentTool) -> &'static str {
+    match tool {
+        AgentTool::Claude => "@anthropic-ai/claude-code",
+        AgentTool::Codex => "@openai/codex",
+        AgentTool::Gemini => "@google/gemini-cli",
+    }
+}
+
+fn get_tool_updates_cache_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".anycode").join("tool_updates.json"))
+}
+
+fn load_latest_version_cache() -> LatestVersionCache {
+    get_tool_updates_cache_path()
+        .and_then(load_json_config)
+        .unwrap_or_default()
+}
+
+/// 检测工具当前使用的安装（路径、版本、来源）；会执行 `--version`，需在阻塞线程中调用
+pub(crate) fn detect_installation(app: &AppHandle, tool: AgentTool) -> Option<ClaudeInstallation> {
+    match tool {
+        AgentTool::Claude => crate::claude_binary::find_claude_installation(app).ok(),
+        AgentTool::Codex => detect_binary_for_tool("codex", "CODEX_PATH", "codex").1,
+        AgentTool::Gemini => detect_binary_for_tool("gemini", "GEMINI_CLI_PATH", "gemini").1,
+    }
+}
+
+async fn fetch_latest_version(client: &reqwest::Client, package: &str) -> Result<String, String> {
+    let response = client
+        .get(format!("{}/{}/latest", NPM_REGISTRY, package))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?;
+    let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    body["version"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("No version in registry response for {}", package))
+}
+
+fn build_status(
+    tool: AgentTool,
+    installation: Option<ClaudeInstallation>,
+    latest_version: Option<&str>,
+) -> ToolUpdateStatus {
+    let current_version = installation.as_ref().and_then(|i| i.version.clone());
+    let update_available = match (&current_version, latest_version) {
+        (Some(current), Some(latest)) => {
+            compare_versions(latest, current) == std::cmp::Ordering::Greater
+        }
+        _ => false,
+    };
+    ToolUpdateStatus {
+        tool: tool.as_str().to_string(),
+        package: npm_package(tool).to_string(),
+        installed: installation.is_some(),
+        current_version,
+        latest_version: latest_version.unwrap_or(UNKNOWN_VERSION).to_string(),
+        update_available,
+        source: installation.as_ref().map(|i| i.source.clone()),
+        path: installation.map(|i| i.path),
+    }
+}
+
+/// Compares the installed claude / codex / gemini CLI versions with the latest npm releases;
+/// latest versions are cached for a day and reported as "unknown" when offline
+#[tauri::command]
+pub async fn check_tool_updates(app: AppHandle) -> Result<Vec<ToolUpdateStatus>, String> {
+    let installations =
+        tokio::task::spawn_blocking(move || ALL_TOOLS.map(|tool| detect_installation(&app, tool)))
+            .await
+            .map_err(|e| format!("Failed to detect installed tools: {}", e))?;
+
+    let mut cache = load_latest_version_cache();
+    let now = Utc::now();
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut cache_changed = false;
+    let mut statuses = Vec::with_capacity(ALL_TOOLS.len());
+    for (tool, installation) in ALL_TOOLS.into_iter().zip(installations) {
+        let package = npm_package(tool);
+        let latest = match cache.fresh(package, now) {
+            Some(version) => Some(version.to_string()),
+            None => match fetch_latest_version(&client, package).await {
+                Ok(version) => {
+                    cache.packages.insert(
+                        package.to_string(),
+                        CachedLatestVersion {
+                            version: version.clone(),
+                            fetched_at: now,
+                        },
+                    );
+                    cache_changed = true;
+                    Some(version)
+                }
+                Err(e) => {
+                    log::warn!("Failed to fetch latest version of {}: {}", package, e);
+                    None
+                }
+            },
+        };
+        statuses.push(build_status(tool, installation, latest.as_deref()));
+    }
+
+    if cache_changed {
+        if let Err(e) =
+            get_tool_updates_cache_path().and_then(|path| save_json_config(&cache, path))
+        {
+            log::warn!("Failed to save tool update cache: {}", e);
+        }
+    }
+    Ok(statuses)
+}
+
+/// 能够自动执行更新的安装方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+    Volta,
+    /// Claude 原生安装器（~/.local/share/claude、~/.claude/local），使用 `claude update`
+    ClaudeNative,
+}
+
+/// 更新结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolUpdateOutcome {
+    Updated,
+    Failed,
+    /// 安装方式无法安全地自动更新，需要用户手动执行 `command`
+    Refused,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolUpdateResult {
+    pub tool: String,
+    pub outcome: ToolUpdateOutcome,
+    /// 执行的更新命令；被拒绝时为建议手动执行的命令
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub previous_version: Option<String>,
+    pub current_version: Option<String>,
+    pub message: String,
+}
+
+/// `tool-update-progress` 事件的负载
+#[derive(Debug, Clone, Serialize)]
+struct ToolUpdateProgress<'a> {
+    tool: &'static str,
+    stream: &'static str,
+    line: &'a str,
+}
+
+fn npm_install_command(tool: AgentTool) -> String {
+    format!("npm install -g {}@latest", npm_package(tool))
+}
+
+fn brew_upgrade_command(tool: AgentTool) -> &'static str {
+    match tool {
+        AgentTool::Claude => "brew upgrade --cask claude-code",
+        AgentTool::Codex => "brew upgrade codex",
+        AgentTool::Gemini => "brew upgrade gemini-cli",
+    }
+}
+
+/// 根据安装来源和（解析符号链接后的）路径判断更新方式；无法自动更新时返回 (建议命令, 原因)
+fn select_package_manager(
+    tool: AgentTool,
+    installation: &ClaudeInstallation,
+) -> Result<PackageManager, (String, String)> {
+    let manual = npm_install_command(tool);
+    if installation.installation_type == InstallationType::Bundled {
+        return Err((
+            manual,
+            format!(
+                "{} is bundled with the app; update the app instead",
+                tool.as_str()
+            ),
+        ));
+    }
+    if installation.path.starts_with("WSL:") || installation.source.contains("wsl") {
+        return Err((
+            manual,
+            format!(
+                "{} is installed inside WSL; update it from a WSL shell",
+                tool.as_str()
+            ),
+        ));
+    }
+
+    let resolved = std::fs::canonicalize(&installation.path)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| installation.path.clone())
+        .replace('\\', "/")
+        .to_lowercase();
+    let inferred = infer_installation_source(&installation.path).unwrap_or_default();
+    let source = installation.source.to_lowercase();
+    let has = |marker: &str| source.contains(marker) || inferred.contains(marker);
+
+    // Homebrew 的 node 公式把全局包装在 Cellar/.../lib/node_modules 下，需先于 npm 判断
+    if has("homebrew") || resolved.contains("/cellar/") || resolved.contains("/caskroom/") {
+        return Err((
+            brew_upgrade_command(tool).to_string(),
+            format!("{} was installed with Homebrew", tool.as_str()),
+        ));
+    }
+    if tool == AgentTool::Claude
+        && (has("claude-local") || resolved.contains("/.local/share/claude/"))
+    {
+        return Ok(PackageManager::ClaudeNative);
+    }
+    if has("bun") {
+        return Ok(PackageManager::Bun);
+    }
+    if has("pnpm") {
+        return Ok(PackageManager::Pnpm);
+    }
+    if has("yarn") {
+        return Ok(PackageManager::Yarn);
+    }
+    if has("volta") {
+        return Ok(PackageManager::Volta);
+    }
+    if has("nvm") || has("fnm") || has("npm") || resolved.contains("/node_modules/") {
+        return Ok(PackageManager::Npm);
+    }
+    if has("system") {
+        return Err((
+            manual,
+            format!(
+                "{} is installed in a system location ({}); update it with your system package manager or with administrator rights",
+                tool.as_str(),
+                installation.path
+            ),
+        ));
+    }
+    Err((
+        manual,
+        format!(
+            "Cannot tell how {} was installed ({})",
+            tool.as_str(),
+            installation.source
+        ),
+    ))
+}
+
+/// 包管理器的可执行文件；npm 优先使用工具所在目录下的 npm（nvm 等多版本 Node 环境）
+fn manager_program(manager: PackageManager, installation: &ClaudeInstallation) -> String {
+    let windows = cfg!(target_os = "windows");
+    match manager {
+        PackageManager::Npm => {
+            let sibling = Path::new(&installation.path)
+                .parent()
+                .map(|dir| dir.join(if windows { "npm.cmd" } else { "npm" }))
+                .filter(|npm| npm.is_file());
+            match sibling {
+                Some(npm) => npm.to_string_lossy().to_string(),
+                None if windows => "npm.cmd".to_string(),
+                None => "npm".to_string(),
+            }
+        }
+        PackageManager::Pnpm if windows => "pnpm.cmd".to_string(),
+        PackageManager::Pnpm => "pnpm".to_string(),
+        PackageManager::Yarn if windows => "yarn.cmd".to_string(),
+        PackageManager::Yarn => "yarn".to_string(),
+        PackageManager::Bun => "bun".to_string(),
+        PackageManager::Volta => "volta".to_string(),
+        PackageManager::ClaudeNative => installation.path.clone(),
+    }
+}
+
+fn manager_args(manager: PackageManager, package: &str) -> Vec<String> {
+    let latest = format!("{}@latest", package);
+    let args: &[&str] = match manager {
+        PackageManager::Npm => &["install", "-g", &latest],
+        PackageManager::Pnpm | PackageManager::Bun => &["add", "-g", &latest],
+        PackageManager::Yarn => &["global", "add", &latest],
+        PackageManager::Volta => &["install", &latest],
+        PackageManager::ClaudeNative => &["update"],
+    };
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn forward_update_output(
+    app: AppHandle,
+    tool: AgentTool,
+    stream: &'static str,
+    reader: impl tokio::io::AsyncRead + Unpin + Send + 'static,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log::info!("[{} update] {}", tool.as_str(), line);
+            let progress = ToolUpdateProgress {
+                tool: tool.as_str(),
+                stream,
+                line: &line,
+            };
+            let _ = app.emit("tool-update-progress", &progress);
+        }
+    })
+}
+
+/// 执行更新命令并逐行发送输出，返回退出码
+async fn run_update_command(
+    app: &AppHandle,
+    tool: AgentTool,
+    program: &str,
+    args: &[String],
+) -> Result<Option<i32>, String> {
+    let mut cmd =
+        tokio::process::Command::from(crate::claude_binary::create_command_with_env(program));
+    cmd.args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    let stdout_task = child
+        .stdout
+        .take()
+        .map(|stdout| forward_update_output(app.clone(), tool, "stdout", stdout));
+    let stderr_task = child
+        .stderr
+        .take()
+        .map(|stderr| forward_update_output(app.clone(), tool, "stderr", stderr));
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    for task in [stdout_task, stderr_task].into_iter().flatten() {
+        let _ = task.await;
+    }
+    Ok(status.code())
+}
+
+pub(crate) async fn detect_installation_async(
+    app: &AppHandle,
+    tool: AgentTool,
+) -> Option<ClaudeInstallation> {
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || detect_installation(&app, tool))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Updates the claude / codex / gemini CLI with the package manager it was installed with,
+/// streaming output as `tool-update-progress` events; Homebrew and system installs are refused
+/// with the command to run manually
+#[tauri::command]
+pub async fn update_tool(app: AppHandle, tool: String) -> Result<ToolUpdateResult, String> {
+    let tool = AgentTool::parse(&tool)?;
+    let installation = detect_installation_async(&app, tool)
+        .await
+        .ok_or_else(|| format!("{} is not installed", tool.as_str()))?;
+    let previous_version = installation.version.clone();
+    let result = |outcome, command: String, exit_code, current_version, message| ToolUpdateResult {
+        tool: tool.as_str().to_string(),
+        outcome,
+        command,
+        exit_code,
+        previous_version: previous_version.clone(),
+        current_version,
+        message,
+    };
+
+    let manager = match select_package_manager(tool, &installation) {
+        Ok(manager) => manager,
+        Err((command, reason)) => {
+            log::info!("Not updating {}: {}", tool.as_str(), reason);
+            return Ok(result(
+                ToolUpdateOutcome::Refused,
+                command.clone(),
+                None,
+                previous_version.clone(),
+                format!("{}. Run `{}` yourself.", reason, command),
+            ));
+        }
+    };
+    let program = manager_program(manager, &installation);
+    let args = manager_args(manager, npm_package(tool));
+    let command = format!("{} {}", program, args.join(" "));
+
+    if !UPDATING_TOOLS.lock().unwrap().insert(tool.as_str()) {
+        return Err(format!("{} is already being updated", tool.as_str()));
+    }
+    log::info!("Updating {} with `{}`", tool.as_str(), command);
+    let run_result = run_update_command(&app, tool, &program, &args).await;
+    UPDATING_TOOLS.lock().unwrap().remove(tool.as_str());
+    let exit_code = run_result?;
+
+    // 更新后重新检测：清除缓存的路径（用户自定义路径除外）和可用性缓存
+    invalidate_tool_caches(&app, tool).await;
+    let current_version = detect_installation_async(&app, tool)
+        .await
+        .and_then(|installation| installation.version);
+
+    let update = if exit_code == Some(0) {
+        result(
+            ToolUpdateOutcome::Updated,
+            command,
+            exit_code,
+            current_version.clone(),
+            format!(
+                "{} updated: {} -> {}",
+                tool.as_str(),
+                previous_version.as_deref().unwrap_or("unknown"),
+                current_version.as_deref().unwrap_or("unknown")
+            ),
+        )
+    } else {
+        let message = format!(
+            "`{}` failed with exit code {}",
+            command,
+            exit_code.map_or_else(|| "unknown".to_string(), |code| code.to_string())
+        );
+        result(
+            ToolUpdateOutcome::Failed,
+            command,
+            exit_code,
+            current_version,
+            message,
+        )
+    };
+    let _ = app.emit("tool-update-complete", &update);
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installation(version: Option<&str>) -> ClaudeInstallation {
+        ClaudeInstallation {
+            path: "/usr/local/bin/codex".to_string(),
+            version: version.map(str::to_string),
+            source: "npm-global".to_string(),
+            installation_type: InstallationType::System,
+        }
+    }
+
+    #[test]
+    fn reports_update_only_when_latest_is_newer() {
+        let status = build_status(
+            AgentTool::Codex,
+            Some(installation(Some("0.46.0"))),
+            Some("0.47.1"),
+        );
+        assert!(status.update_available);
+        assert_eq!(status.package, "@openai/codex");
+
+        let status = build_status(
+            AgentTool::Codex,
+            Some(installation(Some("0.47.1"))),
+            Some("0.47.1"),
+        );
+        assert!(!status.update_available);
+
+        let offline = build_status(AgentTool::Codex, Some(installation(Some("0.46.0"))), None);
+        assert_eq!(offline.latest_version, UNKNOWN_VERSION);
+        assert!(!offline.update_available);
+
+        let missing = build_status(AgentTool::Gemini, None, Some("0.9.0"));
+        assert!(!missing.installed && !missing.update_available);
+    }
+
+    #[test]
+    fn cache_expires_after_a_day() {
+        let now = Utc::now();
+        let mut cache = LatestVersionCache::default();
+        cache.packages.insert(
+            "@openai/codex".to_string(),
+            CachedLatestVersion {
+                version: "0.47.1".to_string(),
+                fetched_at: now - chrono::Duration::hours(2),
+            },
+        );
+        assert_eq!(cache.fresh("@openai/codex", now), Some("0.47.1"));
+        assert_eq!(
+            cache.fresh("@openai/codex", now + chrono::Duration::hours(23)),
+            None
+        );
+        assert_eq!(cache.fresh("@google/gemini-cli", now), None);
+    }
+
+    #[test]
+    fn picks_package_manager_from_install_source() {
+        let at = |path: &str, source: &str| ClaudeInstallation {
+            path: path.to_string(),
+            version: Some("1.0.0".to_string()),
+            source: source.to_string(),
+            installation_type: InstallationType::System,
+        };
+        let pick = |tool, installation| select_package_manager(tool, &installation);
+
+        assert_eq!(
+            pick(
+                AgentTool::Codex,
+                at("/home/u/.nvm/versions/node/v20/bin/codex", "nvm (v20)")
+            ),
+            Ok(PackageManager::Npm)
+        );
+        assert_eq!(
+            pick(
+                AgentTool::Gemini,
+                at("/home/u/.bun/bin/gemini", "PATH (bun)")
+            ),
+            Ok(PackageManager::Bun)
+        );
+        assert_eq!(
+            pick(
+                AgentTool::Claude,
+                at("/home/u/.claude/local/claude", "PATH (claude-local)")
+            ),
+            Ok(PackageManager::ClaudeNative)
+        );
+
+        let (command, _) =
+            pick(AgentTool::Codex, at("/opt/homebrew/bin/codex", "homebrew")).unwrap_err();
+        assert_eq!(command, "brew upgrade codex");
+        let (command, reason) =
+            pick(AgentTool::Claude, at("/usr/bin/claude", "system")).unwrap_err();
+        assert_eq!(command, "npm install -g @anthropic-ai/claude-code@latest");
+        assert!(reason.contains("system location"));
+        assert!(pick(AgentTool::Gemini, at("WSL:/usr/bin/gemini", "wsl-host")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolves_symlinks_before_classifying() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let link_to = |name: &str, target: &std::path::Path| {
+            std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+            std::fs::write(target, "#!/usr/bin/env node\n").unwrap();
+            let link = bin.join(name);
+            std::os::unix::fs::symlink(target, &link).unwrap();
+            ClaudeInstallation {
+                path: link.to_string_lossy().to_string(),
+                version: Some("1.0.0".to_string()),
+                source: "PATH".to_string(),
+                installation_type: InstallationType::System,
+            }
+        };
+
+        let brew_node = link_to(
+            "codex",
+            &dir.path()
+                .join("Cellar/node/22.1.0/lib/node_modules/@openai/codex/bin/codex.js"),
+        );
+        let (command, reason) = select_package_manager(AgentTool::Codex, &brew_node).unwrap_err();
+        assert_eq!(command, "brew upgrade codex");
+        assert!(reason.contains("Homebrew"));
+
+        let npm_global = link_to(
+            "gemini",
+            &dir.path()
+                .join("lib/node_modules/@google/gemini-cli/dist/index.js"),
+        );
+        assert_eq!(
+            select_package_manager(AgentTool::Gemini, &npm_global),
+            Ok(PackageManager::Npm)
+        );
+    }
+
+    #[test]
+    fn builds_update_arguments() {
+        assert_eq!(
+            manager_args(PackageManager::Npm, "@openai/codex"),
+            ["install", "-g", "@openai/codex@latest"]
+        );
+        assert_eq!(
+            manager_args(PackageManager::Yarn, "@google/gemini-cli"),
+            ["global", "add", "@google/gemini-cli@latest"]
+        );
+        assert_eq!(manager_args(PackageManager::ClaudeNative, "x"), ["update"]);
+    }
+}
@@ -46,14 +46,18 @@ impl Default for TranslationConfig {
 struct CacheEntry {
     translated_text: String,
     created_at: Instant,
+    /// 最近一次命中的时间，用于 LRU 淘汰
+    last_accessed: Instant,
     ttl: Duration,
 }
 
 impl CacheEntry {
     fn new(translated_text: String, ttl: Duration) -> Self {
+        let now = Instant::now();
         Self {
             translated_text,
-            created_at: Instant::now(),
+            created_at: now,
+            last_accessed: now,
             ttl,
         }
     }
@@ -63,7 +67,16 @@ impl CacheEntry {
     }
 }
 
-/// 翻译服务
+/// 缓存占用的字节数（键 + 译文）
+fn cache_size_bytes(cache: &HashMap<String, CacheEntry>) -> usize {
+    cache
+        .iter()
+        .map(|(key, entry)| key.len() + entry.translated_text.len())
+        .sum()
+}
+
+/// 翻译服务；克隆出的实例与原实例共享 HTTP 客户端和缓存
+#[derive(Clone)]
 pub struct TranslationService {
     config: TranslationConfig,
     client: Client,
@@ -180,9 +193,10 @@ impl TranslationService {
     async fn get_cached_translation(&self, cache_key: &str) -> Option<String> {
         let mut cache = self.cache.lock().await;
 
-        if let Some(entry) = cache.get(cache_key) {
+        if let Some(entry) = cache.get_mut(cache_key) {
             if !entry.is_expired() {
                 debug!("Cache hit for key: {}", cache_key);
+                entry.last_accessed = Instant::now();
                 return Some(entry.translated_text.clone());
             } else {
                 debug!("Cache expired for key: {}", cache_key);
@@ -405,8 +419,49 @@ impl TranslationService {
             total_entries,
             expired_entries,
             active_entries: total_entries - expired_entries,
+            total_size_bytes: cache_size_bytes(&cache),
         }
     }
+
+    /// 按年龄和条目数裁剪缓存：先移除超过 `max_age` 的条目，
+    /// 再按最近访问时间淘汰最久未使用的条目，直到不超过 `max_entries`
+    pub async fn prune_cache(
+        &self,
+        max_entries: Option<usize>,
+        max_age: Option<Duration>,
+    ) -> PruneResult {
+        let mut cache = self.cache.lock().await;
+        let before = cache.len();
+
+        if let Some(max_age) = max_age {
+            cache.retain(|_, entry| entry.created_at.elapsed() <= max_age);
+        }
+
+        if let Some(max_entries) = max_entries {
+            if cache.len() > max_entries {
+                let mut by_access: Vec<(String, Instant)> = cache
+                    .iter()
+                    .map(|(key, entry)| (key.clone(), entry.last_accessed))
+                    .collect();
+                by_access.sort_by_key(|(_, last_accessed)| *last_accessed);
+                let excess = cache.len() - max_entries;
+                for (key, _) in by_access.into_iter().take(excess) {
+                    cache.remove(&key);
+                }
+            }
+        }
+
+        let result = PruneResult {
+            removed_entries: before - cache.len(),
+            remaining_entries: cache.len(),
+            total_size_bytes: cache_size_bytes(&cache),
+        };
+        info!(
+            "Pruned translation cache: removed {}, remaining {}",
+            result.removed_entries, result.remaining_entries
+        );
+        result
+    }
 }
 
 /// 缓存统计信息
@@ -415,6 +470,16 @@ pub struct CacheStats {
     pub total_entries: usize,
     pub expired_entries: usize,
     pub active_entries: usize,
+    /// 缓存占用的字节数（键 + 译文）
+    pub total_size_bytes: usize,
+}
+
+/// 缓存裁剪结果
+#[derive(Debug, Serialize)]
+pub struct PruneResult {
+    pub removed_entries: usize,
+    pub remaining_entries: usize,
+    pub total_size_bytes: usize,
 }
 
 /// 全局翻译服务实例
@@ -454,13 +519,65 @@ fn get_translation_service() -> Arc<Mutex<TranslationService>> {
     TRANSLATION_SERVICE.clone()
 }
 
+/// 复制当前翻译服务后立即释放全局锁，网络请求期间不阻塞其他翻译和配置更新
+async fn current_translation_service() -> TranslationService {
+    get_translation_service().lock().await.clone()
+}
+
 /// 翻译文本（公共接口）
 pub async fn translate_text(text: &str, target_lang: Option<&str>) -> Result<String> {
-    let service_arc = get_translation_service();
-    let service = service_arc.lock().await;
+    let service = current_translation_service().await;
     service.translate(text, target_lang).await
 }
 
+/// 按 ``` 围栏切分 Markdown：返回 (是否代码块, 片段)，未闭合的围栏视为代码块直到文本结束
+fn split_code_blocks(text: &str) -> Vec<(bool, String)> {
+    let mut segments: Vec<(bool, String)> = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+
+    for line in text.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with("```");
+        if is_fence && !in_code {
+            if !current.is_empty() {
+                segments.push((false, std::mem::take(&mut current)));
+            }
+            in_code = true;
+            current.push_str(line);
+        } else if is_fence && in_code {
+            current.push_str(line);
+            segments.push((true, std::mem::take(&mut current)));
+            in_code = false;
+        } else {
+            current.push_str(line);
+        }
+    }
+    if !current.is_empty() {
+        segments.push((in_code, current));
+    }
+    segments
+}
+
+/// 翻译 Markdown 文本，代码块原样保留，只翻译代码块之间的文字
+pub async fn translate_preserving_code(text: &str, target_lang: Option<&str>) -> Result<String> {
+    let service = current_translation_service().await;
+
+    let mut result = String::with_capacity(text.len());
+    for (is_code, segment) in split_code_blocks(text) {
+        if is_code || segment.trim().is_empty() {
+            result.push_str(&segment);
+            continue;
+        }
+        // 保留片段首尾的空白，避免翻译结果把段落和代码块粘在一起
+        let trimmed = segment.trim();
+        let start = segment.find(trimmed).unwrap_or(0);
+        result.push_str(&segment[..start]);
+        result.push_str(&service.translate(trimmed, target_lang).await?);
+        result.push_str(&segment[start + trimmed.len()..]);
+    }
+    Ok(result)
+}
+
 /// Tauri命令：翻译文本
 #[tauri::command]
 pub async fn translate(text: String, target_lang: Option<String>) -> Result<String, String> {
@@ -477,8 +594,7 @@ pub async fn translate_batch(
     texts: Vec<String>,
     target_lang: Option<String>,
 ) -> Result<Vec<String>, String> {
-    let service_arc = get_translation_service();
-    let service = service_arc.lock().await;
+    let service = current_translation_service().await;
     let target = target_lang.as_deref();
 
     service
@@ -538,6 +654,18 @@ pub async fn get_translation_cache_stats() -> Result<CacheStats, String> {
     Ok(service.get_cache_stats().await)
 }
 
+/// Tauri命令：按条目数上限和最长保留天数裁剪翻译缓存（LRU 淘汰）
+#[tauri::command]
+pub async fn prune_translation_cache(
+    max_entries: Option<usize>,
+    max_age_days: Option<u64>,
+) -> Result<PruneResult, String> {
+    let service_arc = get_translation_service();
+    let service = service_arc.lock().await;
+    let max_age = max_age_days.map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60)));
+    Ok(service.prune_cache(max_entries, max_age).await)
+}
+
 /// Tauri命令：检测文本语言
 #[tauri::command]
 pub async fn detect_text_language(text: String) -> Result<String, String> {
@@ -597,3 +725,47 @@ pub async fn init_translation_service_command(
     init_translation_service(final_config).await;
     Ok("Translation service initialized successfully".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_code_blocks_keeps_fences_together() {
+        let text = "Intro\n```rust\nfn main() {}\n```\nOutro\n```\nunclosed";
+        let segments = split_code_blocks(text);
+        assert_eq!(
+            segments,
+            vec![
+                (false, "Intro\n".to_string()),
+                (true, "```rust\nfn main() {}\n```\n".to_string()),
+                (false, "Outro\n".to_string()),
+                (true, "```\nunclosed".to_string()),
+            ]
+        );
+        let joined: String = segments.into_iter().map(|(_, s)| s).collect();
+        assert_eq!(joined, text);
+    }
+
+    #[tokio::test]
+    async fn prune_cache_evicts_least_recently_used() {
+        let service = TranslationService::new(TranslationConfig::default());
+        for key in ["a", "b", "c"] {
+            service
+                .cache_translation(key.to_string(), format!("{}-zh", key))
+                .await;
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        // 命中 "a" 后它成为最近使用的条目
+        assert!(service.get_cached_translation("a").await.is_some());
+
+        let result = service.prune_cache(Some(2), None).await;
+        assert_eq!(result.removed_entries, 1);
+        assert_eq!(result.remaining_entries, 2);
+        assert!(service.get_cached_translation("b").await.is_none());
+        assert_eq!(result.total_size_bytes, ("a".len() + "a-zh".len()) * 2);
+
+        let result = service.prune_cache(None, Some(Duration::ZERO)).await;
+        assert_eq!(result.remaining_entries, 0);
+    }
+}
@@ -184,6 +184,22 @@ pub fn needs_normalization(url: &str, endpoint_type: ApiEndpointType) -> bool {
     }
 }
 
+/// 校验 URL 是否为合法的 http(s) 地址
+///
+/// # 返回
+/// 合法时返回 `Ok(())`，否则返回错误说明
+pub fn validate_http_url(url: &str) -> Result<(), String> {
+    let parsed =
+        reqwest::Url::parse(url.trim()).map_err(|e| format!("无效的 URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("URL '{}' 必须以 http:// 或 https:// 开头", url));
+    }
+    if parsed.host_str().unwrap_or_default().is_empty() {
+        return Err(format!("URL '{}' 缺少主机名", url));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -337,4 +353,13 @@ mod tests {
             ApiEndpointType::Anthropic
         ));
     }
+
+    #[test]
+    fn test_validate_http_url() {
+        assert!(validate_http_url("https://api.example.com/v1").is_ok());
+        assert!(validate_http_url(" http://localhost:3001 ").is_ok());
+        assert!(validate_http_url("ftp://example.com").is_err());
+        assert!(validate_http_url("api.example.com").is_err());
+        assert!(validate_http_url("").is_err());
+    }
 }
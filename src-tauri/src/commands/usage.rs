@@ -4,9 +4,9 @@
 use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::command;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -74,11 +74,11 @@ pub struct ProjectUsage {
 
 /// Model pricing structure (prices per million tokens)
 #[derive(Debug, Clone, Copy)]
-struct ModelPricing {
-    input: f64,
-    output: f64,
-    cache_write: f64,
-    cache_read: f64,
+pub(crate) struct ModelPricing {
+    pub(crate) input: f64,
+    pub(crate) output: f64,
+    pub(crate) cache_write: f64,
+    pub(crate) cache_read: f64,
 }
 
 /// Model family enumeration for categorization
@@ -183,6 +183,14 @@ fn parse_model_family(model: &str) -> ModelFamily {
     ModelFamily::Unknown
 }
 
+/// Pricing for a model name or alias, or None when the model is not recognized
+pub(crate) fn pricing_for_model(model: &str) -> Option<ModelPricing> {
+    match parse_model_family(model) {
+        ModelFamily::Unknown => None,
+        family => Some(ModelPricing::for_family(family)),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct JsonlEntry {
     timestamp: String,
@@ -353,9 +361,8 @@ fn get_earliest_timestamp(path: &PathBuf) -> Option<String> {
     None
 }
 
-fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
-    let mut all_entries = Vec::new();
-    let mut processed_hashes = HashSet::new();
+/// 所有会话 JSONL 文件及其所属项目目录名，按最早时间戳排序，保证去重结果确定
+fn usage_files(claude_path: &Path) -> Vec<(PathBuf, String)> {
     let projects_dir = claude_path.join("projects");
 
     let mut files_to_process: Vec<(PathBuf, String)> = Vec::new();
@@ -380,8 +387,14 @@ fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
     // Sort files by their earliest timestamp to ensure chronological processing
     // and deterministic deduplication
     files_to_process.sort_by_cached_key(|(path, _)| get_earliest_timestamp(path));
+    files_to_process
+}
+
+fn get_all_usage_entries(claude_path: &PathBuf) -> Vec<UsageEntry> {
+    let mut all_entries = Vec::new();
+    let mut processed_hashes = HashSet::new();
 
-    for (path, project_name) in files_to_process {
+    for (path, project_name) in usage_files(claude_path) {
         let entries = parse_jsonl_file(&path, &project_name, &mut processed_hashes);
         all_entries.extend(entries);
     }
@@ -561,6 +574,15 @@ pub fn get_usage_stats(days: Option<u32>) -> Result<UsageStats, String> {
     })
 }
 
+/// 解析 YYYY-MM-DD 或 RFC3339 格式的日期参数
+pub(crate) fn parse_date_arg(value: &str, label: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").or_else(|_| {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.naive_local().date())
+            .map_err(|e| format!("Invalid {} date: {}", label, e))
+    })
+}
+
 #[command]
 pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<UsageStats, String> {
     let claude_path = dirs::home_dir()
@@ -570,16 +592,8 @@ pub fn get_usage_by_date_range(start_date: String, end_date: String) -> Result<U
     let all_entries = get_all_usage_entries(&claude_path);
 
     // Parse dates
-    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").or_else(|_| {
-        DateTime::parse_from_rfc3339(&start_date)
-            .map(|dt| dt.naive_local().date())
-            .map_err(|e| format!("Invalid start date: {}", e))
-    })?;
-    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d").or_else(|_| {
-        DateTime::parse_from_rfc3339(&end_date)
-            .map(|dt| dt.naive_local().date())
-            .map_err(|e| format!("Invalid end date: {}", e))
-    })?;
+    let start = parse_date_arg(&start_date, "start")?;
+    let end = parse_date_arg(&end_date, "end")?;
 
     // Filter entries by date range
     // 🚀 修复时区问题：转换为本地时区后进行日期比较
@@ -802,3 +816,419 @@ pub fn get_session_stats(
 
     Ok(by_session)
 }
+
+/// One bucket of the usage trend time series
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageTrendBucket {
+    /// First day of the bucket (YYYY-MM-DD; weeks start on Monday)
+    period_start: String,
+    total_cost: f64,
+    total_tokens: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    request_count: u64,
+    /// Tokens per estimated dollar; None for buckets without cost
+    tokens_per_dollar: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageTrends {
+    granularity: String,
+    /// "local" or "utc": the timezone used to assign usage to buckets
+    timezone: String,
+    buckets: Vec<UsageTrendBucket>,
+}
+
+/// 把使用记录的时间戳换算为指定时区（local / utc）的日期
+fn entry_date(timestamp: &str, use_utc: bool) -> Option<NaiveDate> {
+    let dt = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some(if use_utc {
+        dt.with_timezone(&chrono::Utc).date_naive()
+    } else {
+        dt.with_timezone(&Local).date_naive()
+    })
+}
+
+/// 日期所在桶的起始日：按天为当天，按周为周一，按月为 1 号
+fn bucket_start(date: NaiveDate, granularity: &str) -> NaiveDate {
+    use chrono::Datelike;
+    match granularity {
+        "week" => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+        "month" => date.with_day(1).unwrap_or(date),
+        _ => date,
+    }
+}
+
+fn next_bucket(start: NaiveDate, granularity: &str) -> NaiveDate {
+    match granularity {
+        "week" => start + chrono::Duration::days(7),
+        "month" => start
+            .checked_add_months(chrono::Months::new(1))
+            .unwrap_or(start + chrono::Duration::days(31)),
+        _ => start + chrono::Duration::days(1),
+    }
+}
+
+/// 趋势最多包含的桶数，避免过大的日期范围生成海量空桶
+const MAX_TREND_BUCKETS: usize = 1000;
+
+/// 区间内（含首尾）所有桶的起始日；桶数超过 `MAX_TREND_BUCKETS` 时返回错误
+fn trend_bucket_starts(
+    start: NaiveDate,
+    end: NaiveDate,
+    granularity: &str,
+) -> Result<Vec<NaiveDate>, String> {
+    let mut starts = Vec::new();
+    let mut current = bucket_start(start, granularity);
+    while current <= end {
+        if starts.len() == MAX_TREND_BUCKETS {
+            return Err(format!(
+                "Date range too large: more than {} {} buckets; narrow the range or use a coarser granularity",
+                MAX_TREND_BUCKETS, granularity
+            ));
+        }
+        starts.push(current);
+        current = next_bucket(current, granularity);
+    }
+    Ok(starts)
+}
+
+/// Get token and cost trends per day / week / month with a tokens-per-dollar efficiency metric.
+/// Dates are bucketed in local time by default (like the other usage stats); pass timezone "utc" for UTC
+#[command]
+pub fn get_usage_trends(
+    granularity: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    timezone: Option<String>,
+) -> Result<UsageTrends, String> {
+    if !matches!(granularity.as_str(), "day" | "week" | "month") {
+        return Err(format!(
+            "Invalid granularity: {} (expected day, week or month)",
+            granularity
+        ));
+    }
+    let timezone = timezone.unwrap_or_else(|| "local".to_string());
+    let use_utc = match timezone.as_str() {
+        "utc" => true,
+        "local" => false,
+        other => {
+            return Err(format!(
+                "Invalid timezone: {} (expected local or utc)",
+                other
+            ))
+        }
+    };
+
+    let today = if use_utc {
+        chrono::Utc::now().date_naive()
+    } else {
+        Local::now().date_naive()
+    };
+    let parse_date = |value: &str| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date {}: {}", value, e))
+    };
+    let end = end_date
+        .as_deref()
+        .map(parse_date)
+        .transpose()?
+        .unwrap_or(today);
+    let start = match start_date.as_deref() {
+        Some(value) => parse_date(value)?,
+        None => end - chrono::Duration::days(30),
+    };
+    if start > end {
+        return Err("start_date must not be after end_date".to_string());
+    }
+
+    let claude_path = dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude");
+
+    // 先生成范围内的所有桶，没有使用记录的桶保持为 0
+    let starts = trend_bucket_starts(start, end, &granularity)?;
+    let index_by_start: HashMap<NaiveDate, usize> = starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| (*start, index))
+        .collect();
+    let mut buckets: Vec<UsageTrendBucket> = starts
+        .iter()
+        .map(|start| UsageTrendBucket {
+            period_start: start.format("%Y-%m-%d").to_string(),
+            total_cost: 0.0,
+            total_tokens: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+            request_count: 0,
+            tokens_per_dollar: None,
+        })
+        .collect();
+
+    for entry in get_all_usage_entries(&claude_path) {
+        let Some(date) = entry_date(&entry.timestamp, use_utc) else {
+            continue;
+        };
+        if date < start || date > end {
+            continue;
+        }
+        let Some(&index) = index_by_start.get(&bucket_start(date, &granularity)) else {
+            continue;
+        };
+        let bucket = &mut buckets[index];
+        bucket.total_cost += entry.cost;
+        bucket.input_tokens += entry.input_tokens;
+        bucket.output_tokens += entry.output_tokens;
+        bucket.cache_creation_tokens += entry.cache_creation_tokens;
+        bucket.cache_read_tokens += entry.cache_read_tokens;
+        bucket.total_tokens += entry.input_tokens
+            + entry.output_tokens
+            + entry.cache_creation_tokens
+            + entry.cache_read_tokens;
+        bucket.request_count += 1;
+    }
+
+    for bucket in &mut buckets {
+        if bucket.total_cost > 0.0 {
+            bucket.tokens_per_dollar = Some(bucket.total_tokens as f64 / bucket.total_cost);
+        }
+    }
+
+    Ok(UsageTrends {
+        granularity,
+        timezone,
+        buckets,
+    })
+}
+
+/// 本地时间当月 1 号至今的估算费用（美元）
+pub(crate) fn month_to_date_cost() -> Result<f64, String> {
+    use chrono::Datelike;
+    let claude_path = dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude");
+    let today = Local::now().date_naive();
+    let month_start = today.with_day(1).unwrap_or(today);
+
+    Ok(get_all_usage_entries(&claude_path)
+        .iter()
+        .filter(|entry| entry_date(&entry.timestamp, false).is_some_and(|date| date >= month_start))
+        .map(|entry| entry.cost)
+        .sum())
+}
+
+/// 按本地日期汇总区间内（含首尾）的本地用量：日期 -> (tokens, 费用)。
+/// `include` 按记录的 UTC 时间进一步筛选（如只统计某代理商生效期间的用量）
+pub(crate) fn local_daily_usage(
+    start: NaiveDate,
+    end: NaiveDate,
+    include: impl Fn(DateTime<chrono::Utc>) -> bool,
+) -> Result<BTreeMap<NaiveDate, (u64, f64)>, String> {
+    let claude_path = dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude");
+
+    let mut daily = BTreeMap::new();
+    for entry in get_all_usage_entries(&claude_path) {
+        let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) else {
+            continue;
+        };
+        let date = dt.with_timezone(&Local).date_naive();
+        if date < start || date > end || !include(dt.with_timezone(&chrono::Utc)) {
+            continue;
+        }
+        let day = daily.entry(date).or_insert((0u64, 0.0f64));
+        day.0 += entry.input_tokens
+            + entry.output_tokens
+            + entry.cache_creation_tokens
+            + entry.cache_read_tokens;
+        day.1 += entry.cost;
+    }
+    Ok(daily)
+}
+
+/// CSV 字段转义：含逗号、引号或换行时加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Result of a CSV usage export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageCsvExport {
+    rows: usize,
+    path: String,
+}
+
+/// 单个会话文件内按 (date, session, project, model) 汇总的 token 与费用
+type CsvRows = BTreeMap<(NaiveDate, String, String, String), ([u64; 4], f64)>;
+
+fn write_csv_rows(writer: &mut impl std::io::Write, rows: CsvRows) -> std::io::Result<usize> {
+    let count = rows.len();
+    for ((date, session_id, project, model), (tokens, cost)) in rows {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{:.6}",
+            date.format("%Y-%m-%d"),
+            csv_field(&session_id),
+            csv_field(&project),
+            csv_field(&model),
+            tokens[0],
+            tokens[1],
+            tokens[2],
+            tokens[3],
+            tokens.iter().sum::<u64>(),
+            cost
+        )?;
+    }
+    Ok(count)
+}
+
+/// Export per-session usage rows (one row per date / session / model) in the given
+/// local date range to a CSV file. Session files are read and written one at a time,
+/// so only a single session's rows are held in memory
+#[command]
+pub fn export_usage_csv(
+    start: String,
+    end: String,
+    dest_path: String,
+) -> Result<UsageCsvExport, String> {
+    use std::io::Write;
+
+    let start = parse_date_arg(&start, "start")?;
+    let end = parse_date_arg(&end, "end")?;
+    if start > end {
+        return Err("start date must not be after end date".to_string());
+    }
+    let claude_path = dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude");
+
+    let file = fs::File::create(&dest_path)
+        .map_err(|e| format!("Failed to create {}: {}", dest_path, e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", dest_path, e);
+
+    writeln!(
+        writer,
+        "date,session_id,project,model,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,total_tokens,estimated_cost_usd"
+    )
+    .map_err(write_err)?;
+
+    let mut row_count = 0;
+    let mut processed_hashes = HashSet::new();
+    for (path, project_name) in usage_files(&claude_path) {
+        let mut rows = CsvRows::new();
+        for entry in parse_jsonl_file(&path, &project_name, &mut processed_hashes) {
+            let Some(date) = entry_date(&entry.timestamp, false) else {
+                continue;
+            };
+            if date < start || date > end {
+                continue;
+            }
+            let row = rows
+                .entry((date, entry.session_id, entry.project_path, entry.model))
+                .or_insert(([0; 4], 0.0));
+            row.0[0] += entry.input_tokens;
+            row.0[1] += entry.output_tokens;
+            row.0[2] += entry.cache_creation_tokens;
+            row.0[3] += entry.cache_read_tokens;
+            row.1 += entry.cost;
+        }
+        row_count += write_csv_rows(&mut writer, rows).map_err(write_err)?;
+    }
+    writer.flush().map_err(write_err)?;
+
+    log::info!("Exported {} usage rows to {}", row_count, dest_path);
+    Ok(UsageCsvExport {
+        rows: row_count,
+        path: dest_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_special_characters() {
+        assert_eq!(csv_field("claude-sonnet-4"), "claude-sonnet-4");
+        assert_eq!(csv_field("/a,b"), "\"/a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+    #[test]
+    fn csv_rows_are_written_with_totals() {
+        let mut rows = CsvRows::new();
+        rows.insert(
+            (
+                NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+                "s1".to_string(),
+                "/a,b".to_string(),
+                "claude-sonnet-4".to_string(),
+            ),
+            ([1, 2, 3, 4], 0.5),
+        );
+        let mut out = Vec::new();
+        assert_eq!(write_csv_rows(&mut out, rows).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "2025-03-01,s1,\"/a,b\",claude-sonnet-4,1,2,3,4,10,0.500000\n"
+        );
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn buckets_start_on_day_monday_or_first_of_month() {
+        // 2025-03-13 是周四
+        assert_eq!(bucket_start(date(2025, 3, 13), "day"), date(2025, 3, 13));
+        assert_eq!(bucket_start(date(2025, 3, 13), "week"), date(2025, 3, 10));
+        assert_eq!(bucket_start(date(2025, 3, 10), "week"), date(2025, 3, 10));
+        assert_eq!(bucket_start(date(2025, 3, 13), "month"), date(2025, 3, 1));
+
+        assert_eq!(next_bucket(date(2025, 12, 31), "day"), date(2026, 1, 1));
+        assert_eq!(next_bucket(date(2025, 3, 10), "week"), date(2025, 3, 17));
+        assert_eq!(next_bucket(date(2025, 1, 1), "month"), date(2025, 2, 1));
+    }
+
+    #[test]
+    fn trend_buckets_cover_the_range_and_are_capped() {
+        assert_eq!(
+            trend_bucket_starts(date(2025, 1, 30), date(2025, 3, 2), "month").unwrap(),
+            vec![date(2025, 1, 1), date(2025, 2, 1), date(2025, 3, 1)]
+        );
+        assert_eq!(
+            trend_bucket_starts(date(2025, 3, 13), date(2025, 3, 17), "week").unwrap(),
+            vec![date(2025, 3, 10), date(2025, 3, 17)]
+        );
+        assert_eq!(
+            trend_bucket_starts(date(2025, 3, 1), date(2025, 3, 3), "day")
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let start = date(2020, 1, 1);
+        let last_allowed = start + chrono::Duration::days(MAX_TREND_BUCKETS as i64 - 1);
+        assert_eq!(
+            trend_bucket_starts(start, last_allowed, "day")
+                .unwrap()
+                .len(),
+            MAX_TREND_BUCKETS
+        );
+        assert!(
+            trend_bucket_starts(start, last_allowed + chrono::Duration::days(1), "day").is_err()
+        );
+        assert!(trend_bucket_starts(date(1, 1, 1), date(9999, 12, 31), "month").is_err());
+    }
+}
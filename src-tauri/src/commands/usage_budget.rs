@@ -0,0 +1,274 @@
+//! 月度 API 预算提醒
+//!
+//! 后台定期用使用统计估算本月（本地时间）至今的费用，达到配置的百分比阈值时发送
+//! `usage-budget-warning` 事件，每个阈值每月只提醒一次（已提醒的阈值按月份保存在
+//! app_settings 中，重启后不会重复提醒）。月度上限为 0 时关闭。
+
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::storage::{load_app_setting, store_app_setting};
+use crate::commands::usage::month_to_date_cost;
+use crate::error::{AppError, AppResult};
+
+/// app_settings 中保存预算（JSON）的键
+const USAGE_BUDGET_SETTING: &str = "usage_budget";
+/// 后台检查间隔（解析全部会话文件，不宜过于频繁）
+const BUDGET_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// app_settings 中保存本月已提醒阈值（JSON）的键
+const FIRED_THRESHOLDS_SETTING: &str = "usage_budget_fired_thresholds";
+
+/// 某个月份已提醒过的阈值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct FiredThresholds {
+    /// "YYYY-MM"
+    month: String,
+    thresholds: Vec<u32>,
+}
+
+/// Monthly spending limit and the percentages at which to warn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageBudget {
+    /// 0 disables the budget
+    pub monthly_limit_usd: f64,
+    pub warning_thresholds: Vec<u32>,
+}
+
+impl Default for UsageBudget {
+    fn default() -> Self {
+        Self {
+            monthly_limit_usd: 0.0,
+            warning_thresholds: vec![80, 100],
+        }
+    }
+}
+
+/// Current month-to-date spend against the budget
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub monthly_limit_usd: f64,
+    pub month_to_date_usd: f64,
+    /// Month-to-date spend extrapolated to the whole month at the current daily run rate
+    pub projected_month_usd: f64,
+    /// Month-to-date spend as a percentage of the limit (None when no budget is set)
+    pub percent_used: Option<f64>,
+    pub days_elapsed: u32,
+    pub days_in_month: u32,
+}
+
+/// 读取持久化的预算，不存在时返回默认值（关闭）
+pub fn load_usage_budget(app: &AppHandle) -> UsageBudget {
+    load_app_setting(app, USAGE_BUDGET_SETTING).unwrap_or_default()
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    first
+        .checked_add_months(chrono::Months::new(1))
+        .map(|next| (next - first).num_days() as u32)
+        .unwrap_or(30)
+}
+
+fn budget_status(budget: &UsageBudget, month_to_date_usd: f64, today: NaiveDate) -> BudgetStatus {
+    let days_elapsed = today.day();
+    let days_in_month = days_in_month(today);
+    BudgetStatus {
+        monthly_limit_usd: budget.monthly_limit_usd,
+        month_to_date_usd,
+        projected_month_usd: month_to_date_usd / days_elapsed as f64 * days_in_month as f64,
+        percent_used: (budget.monthly_limit_usd > 0.0)
+            .then(|| month_to_date_usd / budget.monthly_limit_usd * 100.0),
+        days_elapsed,
+        days_in_month,
+    }
+}
+
+async fn current_status(budget: &UsageBudget) -> Result<BudgetStatus, String> {
+    let month_to_date_usd = tokio::task::spawn_blocking(month_to_date_cost)
+        .await
+        .map_err(|e| format!("Usage calculation failed: {}", e))??;
+    Ok(budget_status(
+        budget,
+        month_to_date_usd,
+        Local::now().date_naive(),
+    ))
+}
+
+/// 返回本次新达到、且 `month` 尚未提醒过的阈值，并记入 `fired`；换月后重新计数
+fn newly_crossed_thresholds(
+    budget: &UsageBudget,
+    status: &BudgetStatus,
+    fired: &mut FiredThresholds,
+    month: &str,
+) -> Vec<u32> {
+    let Some(percent_used) = status.percent_used else {
+        return Vec::new();
+    };
+    if fired.month != month {
+        *fired = FiredThresholds {
+            month: month.to_string(),
+            thresholds: Vec::new(),
+        };
+    }
+
+    let mut crossed: Vec<u32> = budget
+        .warning_thresholds
+        .iter()
+        .copied()
+        .filter(|threshold| percent_used >= *threshold as f64)
+        .filter(|threshold| !fired.thresholds.contains(threshold))
+        .collect();
+    crossed.sort_unstable();
+    crossed.dedup();
+    fired.thresholds.extend(&crossed);
+    crossed
+}
+
+/// 启动后台预算检查；未设置预算时每轮直接跳过
+pub fn spawn_budget_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(BUDGET_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let budget = load_usage_budget(&app);
+            if budget.monthly_limit_usd <= 0.0 {
+                continue;
+            }
+            let status = match current_status(&budget).await {
+                Ok(status) => status,
+                Err(e) => {
+                    log::warn!("Budget check failed: {}", e);
+                    continue;
+                }
+            };
+
+            let month = Local::now().format("%Y-%m").to_string();
+            let mut fired: FiredThresholds =
+                load_app_setting(&app, FIRED_THRESHOLDS_SETTING).unwrap_or_default();
+            let crossed = newly_crossed_thresholds(&budget, &status, &mut fired, &month);
+            if crossed.is_empty() {
+                continue;
+            }
+            if let Err(e) = store_app_setting(&app, FIRED_THRESHOLDS_SETTING, &fired) {
+                log::warn!("Failed to save fired budget thresholds: {}", e);
+            }
+
+            // 同时越过多个阈值时只提醒最高的一个
+            if let Some(threshold) = crossed.last() {
+                log::warn!(
+                    "Usage budget {}% reached: ${:.2} of ${:.2}",
+                    threshold,
+                    status.month_to_date_usd,
+                    status.monthly_limit_usd
+                );
+                let payload = serde_json::json!({
+                    "threshold": threshold,
+                    "status": status,
+                });
+                let _ = app.emit("usage-budget-warning", payload);
+            }
+        }
+    });
+}
+
+/// Get the monthly budget, month-to-date spend and projected end-of-month spend
+#[tauri::command]
+pub async fn get_budget_status(app: AppHandle) -> AppResult<BudgetStatus> {
+    let budget = load_usage_budget(&app);
+    current_status(&budget).await.map_err(AppError::External)
+}
+
+/// Set the monthly budget in USD (0 = off) and the warning thresholds in percent (default 80, 100)
+#[tauri::command]
+pub async fn set_usage_budget(
+    app: AppHandle,
+    monthly_limit_usd: f64,
+    warning_thresholds: Option<Vec<u32>>,
+) -> AppResult<UsageBudget> {
+    if !monthly_limit_usd.is_finite() || monthly_limit_usd < 0.0 {
+        return Err(AppError::invalid_config(
+            "monthly_limit_usd must be a non-negative number",
+        ));
+    }
+    let mut budget = UsageBudget {
+        monthly_limit_usd,
+        ..load_usage_budget(&app)
+    };
+    if let Some(mut thresholds) = warning_thresholds {
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        budget.warning_thresholds = thresholds;
+    }
+
+    store_app_setting(&app, USAGE_BUDGET_SETTING, &budget)?;
+
+    // 预算变化后重新计算已提醒的阈值
+    store_app_setting(&app, FIRED_THRESHOLDS_SETTING, &FiredThresholds::default())?;
+
+    log::info!(
+        "Usage budget set to ${:.2}/month (warn at {:?}%)",
+        budget.monthly_limit_usd,
+        budget.warning_thresholds
+    );
+    Ok(budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projects_spend_from_daily_run_rate() {
+        let budget = UsageBudget {
+            monthly_limit_usd: 100.0,
+            ..UsageBudget::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 4, 10).unwrap();
+        let status = budget_status(&budget, 40.0, today);
+        assert_eq!(status.days_in_month, 30);
+        assert_eq!(status.days_elapsed, 10);
+        assert!((status.projected_month_usd - 120.0).abs() < 1e-9);
+        assert_eq!(status.percent_used, Some(40.0));
+
+        let disabled = budget_status(&UsageBudget::default(), 5.0, today);
+        assert_eq!(disabled.percent_used, None);
+    }
+
+    #[test]
+    fn thresholds_fire_once_per_month() {
+        let budget = UsageBudget {
+            monthly_limit_usd: 100.0,
+            ..UsageBudget::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 4, 20).unwrap();
+        let mut fired = FiredThresholds::default();
+
+        let status = budget_status(&budget, 85.0, today);
+        assert_eq!(
+            newly_crossed_thresholds(&budget, &status, &mut fired, "2025-04"),
+            vec![80]
+        );
+        assert!(newly_crossed_thresholds(&budget, &status, &mut fired, "2025-04").is_empty());
+
+        // 重新加载持久化的记录后仍然不会重复提醒
+        let mut reloaded: FiredThresholds =
+            serde_json::from_str(&serde_json::to_string(&fired).unwrap()).unwrap();
+        let over = budget_status(&budget, 120.0, today);
+        assert_eq!(
+            newly_crossed_thresholds(&budget, &over, &mut reloaded, "2025-04"),
+            vec![100]
+        );
+
+        // 换月后重新计数
+        assert_eq!(
+            newly_crossed_thresholds(&budget, &over, &mut reloaded, "2025-05"),
+            vec![80, 100]
+        );
+        assert_eq!(reloaded.month, "2025-05");
+    }
+}
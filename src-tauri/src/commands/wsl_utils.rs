@@ -658,6 +658,29 @@ pub fn is_wsl_available() -> bool {
     false
 }
 
+/// 解码 wsl.exe 自身的输出
+///
+/// wsl.exe 默认输出 UTF-16 LE；设置了 WSL_UTF8=1 时输出 UTF-8
+#[cfg(target_os = "windows")]
+pub fn decode_wsl_output(raw: &[u8]) -> String {
+    let looks_utf16 = raw.len() >= 2 && raw.iter().skip(1).step_by(2).all(|b| *b == 0);
+    if !looks_utf16 {
+        return String::from_utf8_lossy(raw).to_string();
+    }
+
+    String::from_utf16_lossy(
+        &raw.chunks(2)
+            .filter_map(|c| {
+                if c.len() == 2 {
+                    Some(u16::from_le_bytes([c[0], c[1]]))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<u16>>(),
+    )
+}
+
 /// 获取可用的 WSL 发行版列表
 #[cfg(target_os = "windows")]
 pub fn get_wsl_distros() -> Vec<String> {
@@ -667,19 +690,7 @@ pub fn get_wsl_distros() -> Vec<String> {
 
     match cmd.output() {
         Ok(output) if output.status.success() => {
-            // WSL 输出是 UTF-16 LE 编码
-            let raw = output.stdout;
-            let decoded = String::from_utf16_lossy(
-                &raw.chunks(2)
-                    .filter_map(|c| {
-                        if c.len() == 2 {
-                            Some(u16::from_le_bytes([c[0], c[1]]))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<u16>>(),
-            );
+            let decoded = decode_wsl_output(&output.stdout);
 
             let distros: Vec<String> = decoded
                 .lines()
@@ -700,8 +711,35 @@ pub fn get_wsl_distros() -> Vec<String> {
 }
 
 /// 获取默认 WSL 发行版名称
+///
+/// 优先使用 `wsl --list --verbose` 中标记为 * 的发行版，否则取列表第一个
 pub fn get_default_wsl_distro() -> Option<String> {
-    get_wsl_distros().into_iter().next()
+    get_wsl_distro_details()
+        .into_iter()
+        .find(|d| d.is_default)
+        .map(|d| d.name)
+        .or_else(|| get_wsl_distros().into_iter().next())
+}
+
+/// WSL 模式未指定发行版时自动选择默认发行版
+///
+/// 没有任何发行版时返回可操作的错误信息
+pub fn auto_select_wsl_distro(tool: &str) -> Result<String, String> {
+    match get_default_wsl_distro() {
+        Some(distro) => {
+            log::info!(
+                "[{} WSL] No distro configured, auto-selected default distro: {}",
+                tool,
+                distro
+            );
+            Ok(distro)
+        }
+        None => Err(format!(
+            "No WSL distro found for {} WSL mode. Install one with `wsl --install -d Ubuntu`, \
+             or choose 'native' / 'auto' mode instead.",
+            tool
+        )),
+    }
 }
 
 /// 获取 WSL 用户的 home 目录（在 WSL 内的路径）
@@ -1993,6 +2031,268 @@ pub fn build_wsl_command_async(
     cmd
 }
 
+// ============================================================================
+// WSL 诊断
+// ============================================================================
+
+/// `wsl --list --verbose` 中的一个发行版
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WslDistroInfo {
+    /// 发行版名称
+    pub name: String,
+    /// 运行状态（Running / Stopped）
+    pub state: String,
+    /// WSL 版本（1 或 2）
+    pub version: Option<u8>,
+    /// 是否为默认发行版（wsl.exe 中标记为 *）
+    pub is_default: bool,
+}
+
+/// WSL 环境诊断结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslDiagnostics {
+    /// 是否为 Windows 平台
+    pub is_windows: bool,
+    /// WSL 是否可用
+    pub wsl_available: bool,
+    /// `wsl --version` 报告的 WSL 版本
+    pub wsl_version: Option<String>,
+    /// 已安装的发行版
+    pub distros: Vec<WslDistroInfo>,
+    /// 默认发行版
+    pub default_distro: Option<String>,
+    /// Codex 配置中的发行版
+    pub codex_configured_distro: Option<String>,
+    /// Claude 配置中的发行版
+    pub claude_configured_distro: Option<String>,
+    /// 当前进程缓存的 Codex WSL 发行版（启动时检测，修改配置后需重启）
+    pub codex_active_distro: Option<String>,
+    /// 按当前配置重新解析出的 Codex 发行版
+    pub codex_effective_distro: Option<String>,
+    /// Codex 会话目录的 UNC 路径
+    pub codex_sessions_dir: Option<String>,
+    /// Codex 会话目录是否可访问
+    pub codex_sessions_dir_reachable: bool,
+    /// WSL 内 Codex 的路径
+    pub codex_path_in_wsl: Option<String>,
+    /// WSL 内 Claude 的路径
+    pub claude_path_in_wsl: Option<String>,
+    /// 修复建议
+    pub suggestions: Vec<String>,
+}
+
+/// 解析 `wsl --list --verbose` 输出
+///
+/// ```text
+///   NAME            STATE           VERSION
+/// * Ubuntu-22.04    Running         2
+///   Debian          Stopped         2
+/// ```
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_wsl_list_verbose(output: &str) -> Vec<WslDistroInfo> {
+    output
+        .lines()
+        .map(|line| line.trim_matches('\0').trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let is_default = line.starts_with('*');
+            let tokens: Vec<&str> = line.trim_start_matches('*').split_whitespace().collect();
+            if tokens.len() < 3 {
+                return None;
+            }
+
+            let version_token = tokens[tokens.len() - 1];
+            let state = tokens[tokens.len() - 2];
+            // 表头行
+            if version_token.eq_ignore_ascii_case("VERSION") && state.eq_ignore_ascii_case("STATE")
+            {
+                return None;
+            }
+
+            Some(WslDistroInfo {
+                name: tokens[..tokens.len() - 2].join(" "),
+                state: state.to_string(),
+                version: version_token.parse().ok(),
+                is_default,
+            })
+        })
+        .collect()
+}
+
+/// 获取发行版详细信息（名称、状态、WSL 版本、是否默认）
+#[cfg(target_os = "windows")]
+pub fn get_wsl_distro_details() -> Vec<WslDistroInfo> {
+    let mut cmd = Command::new("wsl");
+    cmd.args(["--list", "--verbose"]);
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            parse_wsl_list_verbose(&decode_wsl_output(&output.stdout))
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_wsl_distro_details() -> Vec<WslDistroInfo> {
+    vec![]
+}
+
+/// 获取 `wsl --version` 的首行（旧版 WSL 不支持该参数时返回 None）
+#[cfg(target_os = "windows")]
+fn get_wsl_version() -> Option<String> {
+    let mut cmd = Command::new("wsl");
+    cmd.arg("--version");
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let output = cmd.output().ok().filter(|o| o.status.success())?;
+    decode_wsl_output(&output.stdout)
+        .lines()
+        .map(|l| l.trim_matches('\0').trim().to_string())
+        .find(|l| !l.is_empty())
+}
+
+/// 检查配置的发行版是否仍存在，不存在时生成修复建议
+fn check_configured_distro(
+    tool: &str,
+    configured: Option<&str>,
+    distros: &[WslDistroInfo],
+) -> Option<String> {
+    let configured = configured?;
+    if distros.iter().any(|d| d.name == configured) {
+        return None;
+    }
+
+    let available: Vec<&str> = distros.iter().map(|d| d.name.as_str()).collect();
+    Some(format!(
+        "{}: distro '{}' not found; available: {}",
+        tool,
+        configured,
+        if available.is_empty() {
+            "(none)".to_string()
+        } else {
+            available.join(", ")
+        }
+    ))
+}
+
+/// 汇总 WSL 相关检测，生成一份诊断报告
+///
+/// 与 `get_wsl_config()` 不同，这里每次都重新检测，不使用启动时的缓存
+pub fn collect_wsl_diagnostics() -> WslDiagnostics {
+    let codex_config = get_codex_config();
+    let claude_config = get_claude_wsl_config();
+
+    let mut diagnostics = WslDiagnostics {
+        is_windows: cfg!(target_os = "windows"),
+        codex_configured_distro: codex_config.wsl_distro.clone(),
+        claude_configured_distro: claude_config.wsl_distro.clone(),
+        ..Default::default()
+    };
+
+    if !diagnostics.is_windows {
+        return diagnostics;
+    }
+
+    diagnostics.wsl_available = is_wsl_available();
+    if !diagnostics.wsl_available {
+        diagnostics
+            .suggestions
+            .push("WSL is not available; install it with `wsl --install`".to_string());
+        return diagnostics;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        diagnostics.wsl_version = get_wsl_version();
+    }
+    diagnostics.distros = get_wsl_distro_details();
+    diagnostics.default_distro = diagnostics
+        .distros
+        .iter()
+        .find(|d| d.is_default)
+        .map(|d| d.name.clone());
+    diagnostics.codex_active_distro = get_wsl_config().distro.clone();
+
+    if diagnostics.distros.is_empty() {
+        diagnostics.suggestions.push(
+            "No WSL distro is installed; install one with `wsl --install -d Ubuntu`".to_string(),
+        );
+        return diagnostics;
+    }
+
+    for (tool, configured) in [
+        ("Codex", codex_config.wsl_distro.as_deref()),
+        ("Claude", claude_config.wsl_distro.as_deref()),
+    ] {
+        if let Some(message) = check_configured_distro(tool, configured, &diagnostics.distros) {
+            diagnostics.suggestions.push(message);
+        }
+    }
+
+    // 按当前配置解析 Codex 实际应使用的发行版（不存在时回退到默认）
+    let codex_effective = codex_config
+        .wsl_distro
+        .clone()
+        .filter(|d| diagnostics.distros.iter().any(|info| &info.name == d))
+        .or_else(|| diagnostics.default_distro.clone());
+    diagnostics.codex_effective_distro = codex_effective.clone();
+
+    if let Some(distro) = codex_effective.as_deref() {
+        let home = get_wsl_home_dir(Some(distro)).unwrap_or_else(|| "/root".to_string());
+        let sessions_dir = build_wsl_unc_path(&format!("{}/.codex/sessions", home), distro);
+        diagnostics.codex_sessions_dir_reachable = sessions_dir.exists();
+        diagnostics.codex_sessions_dir = Some(sessions_dir.to_string_lossy().to_string());
+        diagnostics.codex_path_in_wsl = check_wsl_codex(Some(distro));
+
+        if !diagnostics.codex_sessions_dir_reachable {
+            diagnostics.suggestions.push(format!(
+                "Codex sessions directory is not reachable in '{}'; run codex once inside WSL to create it",
+                distro
+            ));
+        }
+        if diagnostics.codex_path_in_wsl.is_none() {
+            diagnostics.suggestions.push(format!(
+                "Codex CLI not found in '{}'; install it with `npm i -g @openai/codex`",
+                distro
+            ));
+        }
+
+        if diagnostics.codex_active_distro.is_some()
+            && diagnostics.codex_active_distro.as_deref() != Some(distro)
+        {
+            diagnostics.suggestions.push(format!(
+                "Codex is currently using distro '{}' but the configuration resolves to '{}'; restart the app to apply",
+                diagnostics.codex_active_distro.as_deref().unwrap_or_default(),
+                distro
+            ));
+        }
+    }
+
+    let claude_distro = claude_config
+        .wsl_distro
+        .clone()
+        .filter(|d| diagnostics.distros.iter().any(|info| &info.name == d))
+        .or_else(|| diagnostics.default_distro.clone());
+    if let Some(distro) = claude_distro.as_deref() {
+        diagnostics.claude_path_in_wsl = check_wsl_claude(Some(distro));
+    }
+
+    diagnostics
+}
+
+/// 测试 WSL 配置：发行版、UNC 会话目录、WSL 内的 codex/claude 以及 WSL 版本
+#[tauri::command]
+pub async fn test_wsl_setup() -> Result<WslDiagnostics, String> {
+    // 检测会创建多个 wsl.exe 进程，放到阻塞线程池中执行
+    tokio::task::spawn_blocking(collect_wsl_diagnostics)
+        .await
+        .map_err(|e| format!("WSL diagnostics failed: {}", e))
+}
+
 // ============================================================================
 // 测试
 // ============================================================================
@@ -2030,6 +2330,36 @@ mod tests {
         assert_eq!(wsl_to_windows_path("/mnt/c"), "C:\\"); // 边界情况
     }
 
+    #[test]
+    fn test_parse_wsl_list_verbose() {
+        let output = "  NAME            STATE           VERSION\n\
+                      * Ubuntu-22.04    Running         2\n\
+                        Debian          Stopped         1\n";
+        let distros = parse_wsl_list_verbose(output);
+        assert_eq!(distros.len(), 2);
+        assert_eq!(distros[0].name, "Ubuntu-22.04");
+        assert!(distros[0].is_default);
+        assert_eq!(distros[0].version, Some(2));
+        assert_eq!(distros[1].name, "Debian");
+        assert_eq!(distros[1].state, "Stopped");
+        assert!(!distros[1].is_default);
+        assert_eq!(distros[1].version, Some(1));
+    }
+
+    #[test]
+    fn test_check_configured_distro() {
+        let distros = parse_wsl_list_verbose("* Ubuntu-22.04 Running 2\n");
+        assert_eq!(
+            check_configured_distro("Codex", Some("Ubuntu-20.04"), &distros),
+            Some("Codex: distro 'Ubuntu-20.04' not found; available: Ubuntu-22.04".to_string())
+        );
+        assert_eq!(
+            check_configured_distro("Codex", Some("Ubuntu-22.04"), &distros),
+            None
+        );
+        assert_eq!(check_configured_distro("Codex", None, &distros), None);
+    }
+
     #[test]
     fn test_build_wsl_unc_path() {
         let path = build_wsl_unc_path("/root/.codex/sessions", "Debian");
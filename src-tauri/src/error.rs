@@ -0,0 +1,175 @@
+//! 统一的命令错误类型
+//!
+//! 命令返回 `Result<T, AppError>` 时，前端收到的是带标签的 JSON 对象
+//! `{ "code": "not_found", "message": "..." }`，可以根据 `code` 区分错误类别，
+//! `message` 保持为可直接展示的可读文本。
+
+use serde::Serialize;
+use std::fmt;
+
+/// 命令层错误
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", content = "message", rename_all = "snake_case")]
+pub enum AppError {
+    /// 资源不存在（二进制、文件、会话等）
+    NotFound(String),
+    /// 操作超时
+    Timeout(String),
+    /// 权限不足
+    PermissionDenied(String),
+    /// 配置缺失或非法
+    InvalidConfig(String),
+    /// 本地 I/O 失败
+    Io(String),
+    /// 外部进程 / 服务返回的错误
+    External(String),
+    /// 操作被用户取消（如排队中的会话被移出队列）
+    Cancelled(String),
+    /// 并发会话已达上限且未启用排队
+    QueueFull(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn invalid_config(message: impl Into<String>) -> Self {
+        Self::InvalidConfig(message.into())
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::Io(message.into())
+    }
+
+    pub fn external(message: impl Into<String>) -> Self {
+        Self::External(message.into())
+    }
+
+    /// 可读的错误信息
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(message)
+            | Self::Timeout(message)
+            | Self::PermissionDenied(message)
+            | Self::InvalidConfig(message)
+            | Self::Io(message)
+            | Self::External(message)
+            | Self::Cancelled(message)
+            | Self::QueueFull(message) => message,
+        }
+    }
+
+    /// 根据 I/O 错误类型归类，并在信息前加上上下文
+    pub fn from_io(context: &str, err: std::io::Error) -> Self {
+        Self::classify_io(err.kind(), format!("{}: {}", context, err))
+    }
+
+    fn classify_io(kind: std::io::ErrorKind, message: String) -> Self {
+        match kind {
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied(message),
+            std::io::ErrorKind::TimedOut => Self::Timeout(message),
+            _ => Self::Io(message),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        Self::classify_io(err.kind(), err.to_string())
+    }
+}
+
+/// 尚未迁移的辅助函数仍返回 `String` 错误，统一归为 External
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::External(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::External(message.to_string())
+    }
+}
+
+/// 允许仍返回 `Result<T, String>` 的命令直接用 `?` 调用已迁移的函数
+impl From<AppError> for String {
+    fn from(err: AppError) -> Self {
+        err.message().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_tagged_code_and_message() {
+        let err = AppError::not_found("Claude CLI not found");
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "code": "not_found", "message": "Claude CLI not found" })
+        );
+
+        let value = serde_json::to_value(AppError::invalid_config("bad")).unwrap();
+        assert_eq!(value["code"], "invalid_config");
+    }
+
+    #[test]
+    fn every_variant_serializes_its_code() {
+        let errors = [
+            (AppError::not_found("a"), "not_found"),
+            (AppError::Timeout("b".to_string()), "timeout"),
+            (
+                AppError::PermissionDenied("c".to_string()),
+                "permission_denied",
+            ),
+            (AppError::invalid_config("d"), "invalid_config"),
+            (AppError::io("e"), "io"),
+            (AppError::external("f"), "external"),
+            (AppError::Cancelled("g".to_string()), "cancelled"),
+            (AppError::QueueFull("h".to_string()), "queue_full"),
+        ];
+        for (err, code) in errors {
+            let value = serde_json::to_value(&err).unwrap();
+            assert_eq!(value["code"], code);
+            assert_eq!(value["message"], err.message());
+        }
+    }
+
+    #[test]
+    fn io_errors_are_classified_by_kind() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        assert!(matches!(AppError::from(not_found), AppError::NotFound(_)));
+
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "nope");
+        let err = AppError::from_io("Failed to spawn Claude", denied);
+        assert!(matches!(err, AppError::PermissionDenied(_)));
+        assert_eq!(err.message(), "Failed to spawn Claude: nope");
+
+        let timed_out = std::io::Error::new(std::io::ErrorKind::TimedOut, "slow");
+        assert!(matches!(AppError::from(timed_out), AppError::Timeout(_)));
+
+        let other = std::io::Error::other("boom");
+        assert!(matches!(AppError::from(other), AppError::Io(_)));
+    }
+
+    #[test]
+    fn converts_to_plain_string_message() {
+        let message: String = AppError::Timeout("timed out after 30s".to_string()).into();
+        assert_eq!(message, "timed out after 30s");
+    }
+}
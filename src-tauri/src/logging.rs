@@ -0,0 +1,286 @@
+//! 应用日志
+//!
+//! stderr 输出沿用 env_logger（仍受 RUST_LOG 控制），同时把本 crate 的日志（以及依赖的 warn/error）
+//! 写入应用数据目录下的滚动日志文件，打包后的应用也能从 UI 查看或随问题反馈导出。
+//! 日志级别可在运行时通过 `set_level` 调整，无需重启。
+//! 写入文件前会打码形如 `XXX_TOKEN=value` 的赋值和 `sk-...` 密钥，日志文件中不保留明文密钥。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+
+/// 当前日志文件名，轮转后依次为 anycode.log.1 … anycode.log.N
+pub const LOG_FILE_NAME: &str = "anycode.log";
+/// 单个日志文件的最大字节数
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// 保留的历史日志文件数
+const MAX_ROTATED_FILES: usize = 3;
+/// 日志目录确定前最多缓存的行数
+const MAX_PENDING_LINES: usize = 1000;
+/// 未设置 RUST_LOG 时文件日志的默认级别
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOGGER: OnceCell<AppLogger> = OnceCell::new();
+
+/// 日志中形如 `XXX_KEY=value` / `token: value` 的敏感值
+static SECRET_ASSIGNMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(\b[\w.-]*(?:key|token|secret|password)[\w.-]*"?\s*[=:]\s*"?)[^\s",}]+"#)
+        .expect("valid secret regex")
+});
+/// API key 形式的字面量（sk-...）
+static SECRET_LITERAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bsk-[A-Za-z0-9_-]{8,}").expect("valid key regex"));
+
+struct AppLogger {
+    stderr: env_logger::Logger,
+    file: Mutex<FileSink>,
+}
+
+#[derive(Default)]
+struct FileSink {
+    dir: Option<PathBuf>,
+    file: Option<File>,
+    size: u64,
+    /// 日志目录确定之前产生的日志行
+    pending: Vec<String>,
+}
+
+/// 安装全局日志器（替代 `env_logger::init()`）
+pub fn init() {
+    let stderr = env_logger::Builder::from_default_env().build();
+    let level = stderr.filter().max(DEFAULT_LEVEL);
+
+    let logger = LOGGER.get_or_init(|| AppLogger {
+        stderr,
+        file: Mutex::new(FileSink::default()),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// 设置日志目录，并写入目录确定前缓存的日志
+pub fn set_log_dir(dir: PathBuf) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create log directory {:?}: {}", dir, e);
+        return;
+    }
+    if let Ok(mut sink) = logger.file.lock() {
+        sink.dir = Some(dir);
+        sink.file = None;
+        let pending = std::mem::take(&mut sink.pending);
+        for line in pending {
+            sink.write_line(&line);
+        }
+    }
+}
+
+/// 当前日志目录
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGER.get()?.file.lock().ok()?.dir.clone()
+}
+
+/// 运行时调整日志级别
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+pub fn level() -> LevelFilter {
+    log::max_level()
+}
+
+/// 读取最近的日志行（当前文件不足时向前读取轮转文件）
+pub fn recent_lines(count: usize) -> Vec<String> {
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    for path in log_files(&dir) {
+        if lines.len() >= count {
+            break;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut older: Vec<String> = content.lines().map(str::to_string).collect();
+        older.append(&mut lines);
+        lines = older;
+    }
+
+    let skip = lines.len().saturating_sub(count);
+    lines.split_off(skip)
+}
+
+/// 打码文本中的密钥赋值与 API key 字面量
+pub fn redact_secrets(content: &str) -> String {
+    let redacted = SECRET_ASSIGNMENT.replace_all(content, "${1}***");
+    SECRET_LITERAL.replace_all(&redacted, "sk-***").into_owned()
+}
+
+/// 日志目录中的日志文件，从新到旧
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    std::iter::once(dir.join(LOG_FILE_NAME))
+        .chain((1..=MAX_ROTATED_FILES).map(|i| rotated_path(dir, i)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("{}.{}", LOG_FILE_NAME, index))
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if !should_write_to_file(record) {
+            return;
+        }
+
+        let line = format!(
+            "{} [{:<5}] {}: {}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            record.level(),
+            record.target(),
+            redact_secrets(&record.args().to_string())
+        );
+        if let Ok(mut sink) = self.file.lock() {
+            sink.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Ok(mut sink) = self.file.lock() {
+            if let Some(file) = sink.file.as_mut() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// 本 crate 的日志全部写入，依赖库只记录 warn 及以上，避免文件被刷屏
+fn should_write_to_file(record: &Record) -> bool {
+    record.level() <= log::Level::Warn
+        || record
+            .target()
+            .split("::")
+            .next()
+            .is_some_and(|root| root == env!("CARGO_CRATE_NAME"))
+}
+
+impl FileSink {
+    fn write_line(&mut self, line: &str) {
+        let Some(dir) = self.dir.clone() else {
+            if self.pending.len() < MAX_PENDING_LINES {
+                self.pending.push(line.to_string());
+            }
+            return;
+        };
+
+        let line_len = line.len() as u64 + 1;
+        if self.file.is_some() && self.size + line_len > MAX_LOG_FILE_BYTES {
+            self.rotate(&dir);
+        }
+        if self.file.is_none() {
+            let path = dir.join(LOG_FILE_NAME);
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => {
+                    self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                    self.file = Some(file);
+                }
+                Err(e) => {
+                    eprintln!("Failed to open log file {:?}: {}", path, e);
+                    return;
+                }
+            }
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            if writeln!(file, "{}", line).is_ok() {
+                self.size += line_len;
+            }
+        }
+    }
+
+    /// anycode.log → anycode.log.1 → … → anycode.log.N（最旧的被删除）
+    fn rotate(&mut self, dir: &Path) {
+        self.file = None;
+        let _ = fs::remove_file(rotated_path(dir, MAX_ROTATED_FILES));
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let _ = fs::rename(rotated_path(dir, index), rotated_path(dir, index + 1));
+        }
+        let _ = fs::rename(dir.join(LOG_FILE_NAME), rotated_path(dir, 1));
+        self.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_sink_buffers_until_dir_is_set_and_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = FileSink::default();
+
+        sink.write_line("early line");
+        assert_eq!(sink.pending.len(), 1);
+
+        sink.dir = Some(dir.path().to_path_buf());
+        for line in std::mem::take(&mut sink.pending) {
+            sink.write_line(&line);
+        }
+        assert!(fs::read_to_string(dir.path().join(LOG_FILE_NAME))
+            .unwrap()
+            .contains("early line"));
+
+        // Force a rotation by pretending the current file is full
+        sink.size = MAX_LOG_FILE_BYTES;
+        sink.write_line("after rotation");
+
+        let files = log_files(dir.path());
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap().trim(),
+            "after rotation"
+        );
+        assert!(fs::read_to_string(&files[1])
+            .unwrap()
+            .contains("early line"));
+    }
+
+    #[test]
+    fn redacts_secrets_in_log_lines() {
+        let log = "Setting custom env var: ANTHROPIC_AUTH_TOKEN=abc123\n\
+                   {\"api_key\": \"xyz\", \"model\": \"sonnet\"}\n\
+                   using key sk-ant-api03-abcdefghijk\n\
+                   Loaded 3 environment variables";
+        let redacted = redact_secrets(log);
+
+        assert!(redacted.contains("ANTHROPIC_AUTH_TOKEN=***"));
+        assert!(redacted.contains("\"api_key\": \"***"));
+        assert!(redacted.contains("\"model\": \"sonnet\""));
+        assert!(redacted.contains("sk-***"));
+        assert!(!redacted.contains("abc123"));
+        assert!(!redacted.contains("xyz"));
+        assert!(redacted.contains("Loaded 3 environment variables"));
+    }
+}
@@ -3,6 +3,8 @@
 
 mod claude_binary;
 mod commands;
+mod error;
+mod logging;
 mod process;
 mod utils; // 新增：通用工具模块
 
@@ -17,8 +19,9 @@ use claude_binary::init_shell_environment;
 use std::sync::{Arc, Mutex};
 
 use commands::acemcp::{
-    enhance_prompt_with_context, export_acemcp_sidecar, get_extracted_sidecar_path,
-    load_acemcp_config, preindex_project, save_acemcp_config, test_acemcp_availability,
+    cancel_preindex, enhance_prompt_with_context, export_acemcp_sidecar,
+    get_extracted_sidecar_path, get_preindex_status, load_acemcp_config, preindex_project,
+    preview_enhanced_prompt, save_acemcp_config, test_acemcp_availability,
 };
 use commands::claude::{
     cancel_claude_execution, check_claude_version, clear_custom_claude_path, continue_claude_code,
@@ -36,6 +39,26 @@ use commands::claude::{
     get_claude_wsl_mode_config, set_claude_wsl_mode_config,
     ClaudeProcessState,
 };
+use commands::claude::{
+    apply_system_prompt_preset, cancel_all_running_sessions, cancel_project_sessions,
+    cancel_session_search, cleanup_sessions, clear_project_execution_config, create_workspace,
+    delete_system_prompt_preset, delete_workspace, detect_project_type, export_execution_config,
+    get_claude_binary_info, get_dangerous_skip_audit, get_default_model, get_effective_env,
+    get_effective_execution_config, get_idle_timeouts, get_last_plan, get_live_output_limits,
+    get_model_aliases, get_output_translation, get_project_disk_usage, get_project_env_status,
+    get_project_model, get_session_file_activity, get_workspace_sessions, import_execution_config,
+    import_project, import_session_jsonl, list_claude_md_templates, list_known_slash_commands,
+    list_resumable_sessions, list_running_sessions_by_project, list_sessions_by_tag,
+    list_system_prompt_presets, list_workspaces, load_session_history_structured, move_session,
+    pin_project, pin_session, prepare_prompt, preview_merged_claude_md, reap_orphaned_processes,
+    repair_session_file, resize_claude_pty, respond_to_permission_request, resume_last_claude,
+    save_system_prompt_preset, scaffold_claude_md, search_all_sessions, search_sessions_content,
+    set_active_workspace, set_default_model, set_idle_timeouts, set_live_output_limits,
+    set_model_aliases, set_output_translation, set_project_env_trust, set_project_execution_config,
+    set_project_model, set_session_tags, subscribe_session_output, unpin_project, unpin_session,
+    unsubscribe_session_output, unwatch_session, validate_permission_config_for_version,
+    validate_session_file, watch_session, write_claude_pty,
+};
 use commands::mcp::{
     mcp_add, mcp_add_from_claude_desktop, mcp_add_json, mcp_export_config, mcp_get,
     mcp_get_server_status, mcp_list, mcp_read_project_config, mcp_remove,
@@ -50,15 +73,18 @@ use commands::mcp::{
 };
 use commands::storage::{init_database, AgentDb};
 
-use commands::clipboard::{read_from_clipboard, save_clipboard_image, write_to_clipboard};
+use commands::clipboard::{
+    read_clipboard_files, read_from_clipboard, save_clipboard_image, write_to_clipboard,
+};
 use commands::prompt_tracker::{
-    check_rewind_capabilities, get_prompt_list, get_unified_prompt_list, mark_prompt_completed,
-    record_prompt_sent, revert_to_prompt,
+    check_rewind_capabilities, fork_session, get_prompt_list, get_unified_prompt_list,
+    mark_prompt_completed, record_prompt_sent, revert_to_prompt,
 };
 use commands::provider::{
-    add_provider_config, clear_provider_config, delete_provider_config,
-    get_current_provider_config, get_provider_config, get_provider_presets, query_provider_usage,
-    reorder_provider_configs, switch_provider_config, test_provider_connection, update_provider_config,
+    add_provider_config, clear_provider_config, clone_provider_config, delete_provider_config,
+    get_current_provider_config, get_provider_config, get_provider_presets, list_provider_configs,
+    query_provider_usage, query_provider_usage_range, reorder_provider_configs,
+    switch_provider_config, test_provider_connection, update_provider_config,
 };
 use commands::simple_git::{check_and_init_git, check_reset_safety, precise_revert_code};
 use commands::storage::{
@@ -68,15 +94,21 @@ use commands::storage::{
 };
 use commands::translator::{
     clear_translation_cache, detect_text_language, get_translation_cache_stats,
-    get_translation_config, init_translation_service_command, translate, translate_batch,
-    update_translation_config,
+    get_translation_config, init_translation_service_command, prune_translation_cache, translate,
+    translate_batch, update_translation_config,
 };
-use commands::usage::{get_session_stats, get_usage_by_date_range, get_usage_stats};
+use commands::usage::{
+    export_usage_csv, get_session_stats, get_usage_by_date_range, get_usage_stats, get_usage_trends,
+};
+use commands::usage_budget::{get_budget_status, set_usage_budget};
 use commands::window::{
     broadcast_to_session_windows, close_session_window, create_session_window, emit_to_window,
     focus_session_window, list_session_windows, set_titlebar_theme,
 };
 
+use commands::agent::{cancel_agent, dry_run_launch, execute_agent, get_agent_event_names};
+use commands::auth_detection::{get_auth_detection_config, set_auth_detection_config};
+use commands::backup::{backup_app_data, restore_app_data};
 use commands::codex::{
     add_codex_provider_config,
     cancel_codex,
@@ -85,12 +117,17 @@ use commands::codex::{
     clear_codex_provider_config,
     clear_custom_codex_path,
     convert_claude_to_codex,
+    convert_claude_to_gemini,
     convert_codex_to_claude,
+    convert_codex_to_gemini,
+    convert_gemini_to_claude,
+    convert_gemini_to_codex,
     // Session conversion
     convert_session,
     delete_codex_provider_config,
     delete_codex_session,
     execute_codex,
+    get_codex_binary_info,
     // Codex mode configuration
     get_codex_mode_config,
     get_codex_path,
@@ -102,6 +139,7 @@ use commands::codex::{
     get_current_codex_config,
     list_codex_sessions,
     load_codex_session_history,
+    preview_session_conversion,
     record_codex_prompt_completed,
     // Codex rewind commands
     record_codex_prompt_sent,
@@ -116,18 +154,27 @@ use commands::codex::{
     update_codex_provider_config,
     update_codex_reasoning_level,
     validate_codex_path_cmd,
+    validate_codex_provider_config,
     CodexProcessState,
 };
+use commands::commit_message::{create_commit, generate_commit_message};
+use commands::cost_estimate::estimate_request_cost;
+use commands::diagnostics::{diagnose_binary_path, run_diagnostics};
+use commands::effective_config::get_effective_config;
 use commands::enhanced_hooks::{
-    execute_pre_commit_review, test_hook_condition, trigger_hook_event,
+    execute_pre_commit_review, get_sample_hook_contexts, simulate_hook_event, test_hook_condition,
+    trigger_hook_event,
 };
 use commands::extensions::{
-    create_skill, create_subagent, list_agent_skills, list_custom_slash_commands,
-    list_gemini_custom_slash_commands, list_plugins, list_subagents, open_agents_directory,
-    open_commands_directory, open_plugins_directory, open_skills_directory, read_skill,
-    read_subagent,
+    create_skill, create_subagent, duplicate_skill, duplicate_subagent, list_agent_skills,
+    list_custom_slash_commands, list_gemini_custom_slash_commands, list_plugins, list_subagents,
+    open_agents_directory, open_commands_directory, open_plugins_directory, open_skills_directory,
+    read_skill, read_subagent,
+};
+use commands::file_operations::{
+    get_editor_config, open_directory_in_explorer, open_file_at_line, open_file_with_default_app,
+    reveal_in_file_manager, update_editor_config,
 };
-use commands::file_operations::{open_directory_in_explorer, open_file_with_default_app};
 use commands::gemini::{
     add_gemini_provider_config,
     cancel_gemini,
@@ -138,6 +185,7 @@ use commands::gemini::{
     delete_gemini_session,
     execute_gemini,
     get_current_gemini_provider_config,
+    get_gemini_binary_info,
     get_gemini_config,
     get_gemini_models,
     // Gemini Rewind commands
@@ -155,6 +203,7 @@ use commands::gemini::{
     record_gemini_prompt_completed,
     record_gemini_prompt_sent,
     reorder_gemini_provider_configs,
+    resume_last_gemini,
     revert_gemini_to_prompt,
     save_gemini_system_prompt,
     set_gemini_wsl_mode_config,
@@ -162,16 +211,37 @@ use commands::gemini::{
     test_gemini_provider_connection,
     update_gemini_config,
     update_gemini_provider_config,
+    validate_gemini_provider_config,
     GeminiProcessState,
 };
-use commands::git_stats::{get_git_diff_stats, get_session_code_changes};
-use process::ProcessRegistryState;
-use tauri::{Manager, WindowEvent};
+use commands::git_stats::{
+    get_git_diff_stats, get_git_status, get_session_code_changes, git_stage_files,
+    git_unstage_files,
+};
+use commands::keychain::{get_keychain_status, migrate_keys_to_keychain, set_keychain_enabled};
+use commands::logs::{
+    create_diagnostics_bundle, get_log_level, get_recent_logs, open_log_directory, set_log_level,
+};
+use commands::notifications::{get_notification_preferences, set_notification_preferences};
+use commands::project_command::{cancel_project_command, run_project_command};
+use commands::roundtrip::{
+    cancel_roundtrip_test, test_claude_roundtrip, test_codex_roundtrip, test_gemini_roundtrip,
+};
+use commands::session_limits::{
+    cancel_queued_session, get_session_concurrency_config, get_session_queue_status,
+    set_session_concurrency_config,
+};
+use commands::session_summary::summarize_session;
+use commands::tool_login::start_tool_login;
+use commands::tool_updates::{check_tool_updates, update_tool};
+use commands::wsl_utils::test_wsl_setup;
+use process::{ProcessRegistryState, SessionLimiterState};
+use tauri::{Emitter, Manager, WindowEvent};
 use tauri_plugin_window_state::Builder as WindowStatePlugin;
 
 fn main() {
-    // Initialize logger
-    env_logger::init();
+    // Initialize logger (stderr + rotating log file once the app data dir is known)
+    logging::init();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -182,12 +252,17 @@ fn main() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(
             WindowStatePlugin::default()
                 .with_state_flags(tauri_plugin_window_state::StateFlags::all())
                 .build(),
         )
         .setup(|app| {
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                logging::set_log_dir(app_data_dir.join("logs"));
+            }
+
             // Initialize shell environment for macOS GUI applications
             // This must be done early to ensure CLI tools (claude, codex, etc.) can be found
             init_shell_environment();
@@ -197,7 +272,54 @@ fn main() {
             app.manage(AgentDb(Mutex::new(conn)));
 
             // Initialize process registry
-            app.manage(ProcessRegistryState::default());
+            let process_registry = ProcessRegistryState::default();
+            if let Some(limits) = commands::claude::load_live_output_limits(app.handle()) {
+                if let Err(e) = process_registry.0.set_live_output_limits(limits) {
+                    log::warn!("Failed to apply live output limits: {}", e);
+                }
+            }
+            if let Some(timeouts) = commands::claude::load_idle_timeouts(app.handle()) {
+                if let Err(e) = process_registry.0.set_idle_timeouts(timeouts) {
+                    log::warn!("Failed to apply idle timeouts: {}", e);
+                }
+            }
+
+            // Restore the process journal and look for processes left behind by a crash
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                process_registry
+                    .0
+                    .journal()
+                    .set_path(app_data_dir.join("process_journal.json"));
+
+                let registry = process_registry.0.clone();
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    let orphans = match registry.reap_orphaned_processes(&[], false) {
+                        Ok(orphans) => orphans,
+                        Err(e) => {
+                            log::warn!("Failed to scan for orphaned processes: {}", e);
+                            return;
+                        }
+                    };
+                    if !orphans.is_empty() {
+                        log::warn!(
+                            "Found {} orphaned process(es) from a previous run",
+                            orphans.len()
+                        );
+                        let _ = app_handle.emit("orphaned-processes-detected", &orphans);
+                    }
+                });
+            }
+            app.manage(process_registry);
+            commands::claude::spawn_idle_watchdog(app.handle().clone());
+            commands::usage_budget::spawn_budget_monitor(app.handle().clone());
+
+            // Initialize the concurrent session limiter shared by all CLI engines
+            let session_limits =
+                commands::session_limits::load_session_limits(app.handle()).unwrap_or_default();
+            app.manage(SessionLimiterState(Arc::new(
+                process::concurrency::SessionLimiter::new(session_limits),
+            )));
 
             // Initialize Claude process state
             app.manage(ClaudeProcessState::default());
@@ -284,15 +406,34 @@ fn main() {
                     }
                 }
             }
+
+            // Release session output followers and file watchers bound to a destroyed window
+            if let WindowEvent::Destroyed = event {
+                commands::claude::unsubscribe_window_output(window.label());
+                commands::claude::unwatch_window_sessions(window.label());
+            }
         })
         .invoke_handler(tauri::generate_handler![
             // Claude & Project Management
             list_projects,
+            create_workspace,
+            list_workspaces,
+            delete_workspace,
+            set_active_workspace,
+            get_workspace_sessions,
             get_project_sessions,
             delete_session,
             delete_sessions_batch,
+            move_session,
+            set_session_tags,
+            list_sessions_by_tag,
+            pin_session,
+            unpin_session,
+            pin_project,
+            unpin_project,
             delete_project,
             restore_project,
+            import_project,
             list_hidden_projects,
             delete_project_permanently,
             get_claude_settings,
@@ -301,19 +442,94 @@ fn main() {
             get_codex_system_prompt,
             check_claude_version,
             save_system_prompt,
+            list_system_prompt_presets,
+            save_system_prompt_preset,
+            apply_system_prompt_preset,
+            delete_system_prompt_preset,
             save_codex_system_prompt,
             save_claude_settings,
             update_thinking_mode,
             find_claude_md_files,
             read_claude_md_file,
+            preview_merged_claude_md,
             save_claude_md_file,
+            list_claude_md_templates,
+            scaffold_claude_md,
             load_session_history,
+            load_session_history_structured,
+            get_session_file_activity,
+            search_sessions_content,
+            search_all_sessions,
+            cancel_session_search,
+            import_session_jsonl,
+            watch_session,
+            unwatch_session,
             execute_claude_code,
             continue_claude_code,
             resume_claude_code,
+            resume_last_claude,
             cancel_claude_execution,
+            resize_claude_pty,
+            write_claude_pty,
+            execute_agent,
+            cancel_agent,
+            get_agent_event_names,
+            dry_run_launch,
+            get_auth_detection_config,
+            set_auth_detection_config,
+            start_tool_login,
+            check_tool_updates,
+            update_tool,
+            cancel_all_running_sessions,
+            cancel_project_sessions,
+            list_running_sessions_by_project,
+            respond_to_permission_request,
+            get_last_plan,
+            set_output_translation,
+            get_output_translation,
+            prepare_prompt,
+            list_known_slash_commands,
+            get_effective_env,
+            get_project_env_status,
+            set_project_env_trust,
+            get_effective_config,
+            run_diagnostics,
+            diagnose_binary_path,
+            test_claude_roundtrip,
+            test_codex_roundtrip,
+            test_gemini_roundtrip,
+            cancel_roundtrip_test,
+            get_recent_logs,
+            open_log_directory,
+            set_log_level,
+            get_log_level,
+            create_diagnostics_bundle,
+            backup_app_data,
+            restore_app_data,
+            reap_orphaned_processes,
+            get_session_queue_status,
+            get_session_concurrency_config,
+            set_session_concurrency_config,
+            get_notification_preferences,
+            set_notification_preferences,
+            cancel_queued_session,
+            summarize_session,
+            run_project_command,
+            cancel_project_command,
+            list_resumable_sessions,
+            get_project_disk_usage,
+            detect_project_type,
+            cleanup_sessions,
+            validate_session_file,
+            repair_session_file,
             list_running_claude_sessions,
             get_claude_session_output,
+            subscribe_session_output,
+            unsubscribe_session_output,
+            get_live_output_limits,
+            set_live_output_limits,
+            get_idle_timeouts,
+            set_idle_timeouts,
             list_directory_contents,
             search_files,
             get_hooks_config,
@@ -323,32 +539,58 @@ fn main() {
             get_claude_execution_config,
             update_claude_execution_config,
             reset_claude_execution_config,
+            set_project_execution_config,
+            clear_project_execution_config,
+            get_effective_execution_config,
+            export_execution_config,
+            import_execution_config,
+            get_dangerous_skip_audit,
             get_claude_permission_config,
             update_claude_permission_config,
             get_permission_presets,
             get_available_tools,
             validate_permission_config,
+            validate_permission_config_for_version,
             set_custom_claude_path,
             get_claude_path,
+            get_claude_binary_info,
             clear_custom_claude_path,
+            // Model defaults
+            set_default_model,
+            get_default_model,
+            get_model_aliases,
+            set_model_aliases,
+            set_project_model,
+            get_project_model,
             // Claude WSL Mode Configuration
             get_claude_wsl_mode_config,
             set_claude_wsl_mode_config,
+            test_wsl_setup,
             // Acemcp Integration
             enhance_prompt_with_context,
+            preview_enhanced_prompt,
             test_acemcp_availability,
             save_acemcp_config,
             load_acemcp_config,
             preindex_project,
+            cancel_preindex,
+            get_preindex_status,
             export_acemcp_sidecar,
             get_extracted_sidecar_path,
             // Enhanced Hooks Automation
             trigger_hook_event,
             test_hook_condition,
+            simulate_hook_event,
+            get_sample_hook_contexts,
             execute_pre_commit_review,
             // Usage & Analytics (Simplified from opcode)
             get_usage_stats,
             get_usage_by_date_range,
+            get_usage_trends,
+            export_usage_csv,
+            get_budget_status,
+            set_usage_budget,
+            estimate_request_cost,
             get_session_stats,
             // MCP (Model Context Protocol)
             mcp_add,
@@ -394,6 +636,7 @@ fn main() {
             save_clipboard_image,
             write_to_clipboard,
             read_from_clipboard,
+            read_clipboard_files,
             // Provider Management
             get_provider_presets,
             get_current_provider_config,
@@ -405,13 +648,20 @@ fn main() {
             delete_provider_config,
             get_provider_config,
             query_provider_usage,
+            query_provider_usage_range,
             reorder_provider_configs,
+            list_provider_configs,
+            clone_provider_config,
+            get_keychain_status,
+            set_keychain_enabled,
+            migrate_keys_to_keychain,
             // Translation
             translate,
             translate_batch,
             get_translation_config,
             update_translation_config,
             clear_translation_cache,
+            prune_translation_cache,
             get_translation_cache_stats,
             detect_text_language,
             init_translation_service_command,
@@ -420,6 +670,7 @@ fn main() {
             commands::context_commands::register_auto_compact_session,
             commands::context_commands::update_session_context,
             commands::context_commands::trigger_manual_compaction,
+            commands::context_commands::get_compaction_history,
             commands::context_commands::get_auto_compact_config,
             commands::context_commands::update_auto_compact_config,
             commands::context_commands::get_session_context_stats,
@@ -437,6 +688,7 @@ fn main() {
             revert_to_prompt,
             get_prompt_list,
             get_unified_prompt_list,
+            fork_session,
             check_rewind_capabilities,
             // Claude Extensions (Plugins, Subagents, Skills & Custom Commands)
             list_plugins,
@@ -448,6 +700,8 @@ fn main() {
             read_skill,
             create_subagent,
             create_skill,
+            duplicate_subagent,
+            duplicate_skill,
             open_plugins_directory,
             open_agents_directory,
             open_skills_directory,
@@ -455,8 +709,17 @@ fn main() {
             // File Operations
             open_directory_in_explorer,
             open_file_with_default_app,
+            open_file_at_line,
+            reveal_in_file_manager,
+            get_editor_config,
+            update_editor_config,
             // Git Statistics
             get_git_diff_stats,
+            get_git_status,
+            git_stage_files,
+            git_unstage_files,
+            generate_commit_message,
+            create_commit,
             get_session_code_changes,
             // OpenAI Codex Integration
             execute_codex,
@@ -480,6 +743,7 @@ fn main() {
             validate_codex_path_cmd,
             set_custom_codex_path,
             get_codex_path,
+            get_codex_binary_info,
             clear_custom_codex_path,
             // Codex Provider Management
             get_codex_provider_presets,
@@ -487,6 +751,7 @@ fn main() {
             switch_codex_provider,
             add_codex_provider_config,
             update_codex_provider_config,
+            validate_codex_provider_config,
             delete_codex_provider_config,
             clear_codex_provider_config,
             test_codex_provider_connection,
@@ -494,10 +759,15 @@ fn main() {
             reorder_codex_provider_configs,
             // Codex Usage Statistics
             get_codex_usage_stats,
-            // Session Conversion (Claude ↔ Codex)
+            // Session Conversion (Claude ↔ Codex ↔ Gemini)
             convert_session,
             convert_claude_to_codex,
             convert_codex_to_claude,
+            convert_claude_to_gemini,
+            convert_gemini_to_claude,
+            convert_codex_to_gemini,
+            convert_gemini_to_codex,
+            preview_session_conversion,
             // Window Management (Multi-window support)
             create_session_window,
             close_session_window,
@@ -508,8 +778,10 @@ fn main() {
             set_titlebar_theme,
             // Google Gemini CLI Integration
             execute_gemini,
+            resume_last_gemini,
             cancel_gemini,
             check_gemini_installed,
+            get_gemini_binary_info,
             get_gemini_config,
             update_gemini_config,
             get_gemini_models,
@@ -533,6 +805,7 @@ fn main() {
             switch_gemini_provider,
             add_gemini_provider_config,
             update_gemini_provider_config,
+            validate_gemini_provider_config,
             delete_gemini_provider_config,
             clear_gemini_provider_config,
             test_gemini_provider_connection,
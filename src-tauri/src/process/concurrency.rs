@@ -0,0 +1,339 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::error::{AppError, AppResult};
+
+/// Limit on the number of CLI sessions (Claude / Codex / Gemini) running at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionLimits {
+    /// 0 means unlimited
+    pub max_concurrent_sessions: usize,
+    /// Queue new sessions when the limit is reached instead of rejecting them
+    pub queue_when_full: bool,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_sessions: 0,
+            queue_when_full: true,
+        }
+    }
+}
+
+/// A running or queued session slot
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSlot {
+    pub id: u64,
+    pub engine: String,
+    pub project_path: String,
+    pub session_id: Option<String>,
+    pub tab_id: Option<String>,
+    pub since: DateTime<Utc>,
+}
+
+/// Snapshot of running vs queued sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionQueueStatus {
+    pub limits: SessionLimits,
+    pub running: Vec<SessionSlot>,
+    pub queued: Vec<SessionSlot>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    limits: SessionLimits,
+    next_id: u64,
+    running: HashMap<u64, SessionSlot>,
+    queued: VecDeque<SessionSlot>,
+}
+
+impl LimiterState {
+    fn has_capacity(&self) -> bool {
+        self.limits.max_concurrent_sessions == 0
+            || self.running.len() < self.limits.max_concurrent_sessions
+    }
+}
+
+/// Counting semaphore shared by all engines; the limit can be changed at runtime
+/// and waiters are served in FIFO order
+#[derive(Default)]
+pub struct SessionLimiter {
+    state: Mutex<LimiterState>,
+    notify: Notify,
+}
+
+/// Held for the lifetime of a CLI process; dropping it frees the slot
+pub struct SessionPermit {
+    limiter: Arc<SessionLimiter>,
+    id: u64,
+}
+
+impl Drop for SessionPermit {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.limiter.state.lock() {
+            state.running.remove(&self.id);
+        }
+        self.limiter.notify.notify_waiters();
+    }
+}
+
+/// Removes a queued entry when the waiting future is dropped or cancelled
+struct QueueGuard<'a> {
+    limiter: &'a SessionLimiter,
+    id: u64,
+    armed: bool,
+}
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        if let Ok(mut state) = self.limiter.state.lock() {
+            state.queued.retain(|slot| slot.id != self.id);
+        }
+        // The head of the queue may have changed
+        self.limiter.notify.notify_waiters();
+    }
+}
+
+impl SessionLimiter {
+    pub fn new(limits: SessionLimits) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                limits,
+                ..Default::default()
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn limits(&self) -> SessionLimits {
+        self.state
+            .lock()
+            .map(|state| state.limits)
+            .unwrap_or_default()
+    }
+
+    /// Update the limit; raising it starts queued sessions right away
+    pub fn set_limits(&self, limits: SessionLimits) -> Result<(), String> {
+        self.state.lock().map_err(|e| e.to_string())?.limits = limits;
+        self.notify.notify_waiters();
+        Ok(())
+    }
+
+    /// Whether a new session would have to wait (or be rejected)
+    pub fn is_full(&self) -> bool {
+        self.state
+            .lock()
+            .map(|state| !state.queued.is_empty() || !state.has_capacity())
+            .unwrap_or(false)
+    }
+
+    pub fn status(&self) -> SessionQueueStatus {
+        let Ok(state) = self.state.lock() else {
+            return SessionQueueStatus {
+                limits: SessionLimits::default(),
+                running: Vec::new(),
+                queued: Vec::new(),
+            };
+        };
+        let mut running: Vec<SessionSlot> = state.running.values().cloned().collect();
+        running.sort_by_key(|slot| slot.id);
+        SessionQueueStatus {
+            limits: state.limits,
+            running,
+            queued: state.queued.iter().cloned().collect(),
+        }
+    }
+
+    /// Remove a queued session (by queue id or session id); its launch fails with a
+    /// cancellation error. Returns false if nothing was queued under that id.
+    pub fn cancel_queued(&self, queue_id: Option<u64>, session_id: Option<&str>) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let before = state.queued.len();
+        state.queued.retain(|slot| {
+            let by_id = queue_id.is_some_and(|id| id == slot.id);
+            let by_session = session_id.is_some() && slot.session_id.as_deref() == session_id;
+            !(by_id || by_session)
+        });
+        let cancelled = state.queued.len() != before;
+        drop(state);
+        if cancelled {
+            self.notify.notify_waiters();
+        }
+        cancelled
+    }
+
+    /// Wait for a free slot (or fail right away when queueing is disabled)
+    pub async fn acquire(
+        self: &Arc<Self>,
+        engine: &str,
+        project_path: &str,
+        session_id: Option<String>,
+        tab_id: Option<String>,
+    ) -> AppResult<SessionPermit> {
+        let id = {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|e| AppError::external(e.to_string()))?;
+            state.next_id += 1;
+            let slot = SessionSlot {
+                id: state.next_id,
+                engine: engine.to_string(),
+                project_path: project_path.to_string(),
+                session_id,
+                tab_id,
+                since: Utc::now(),
+            };
+            let id = slot.id;
+
+            if state.queued.is_empty() && state.has_capacity() {
+                state.running.insert(id, slot);
+                return Ok(self.permit(id));
+            }
+            if !state.limits.queue_when_full {
+                return Err(AppError::QueueFull(format!(
+                    "Maximum of {} concurrent sessions reached; wait for a running session to finish or raise the limit",
+                    state.limits.max_concurrent_sessions
+                )));
+            }
+            log::info!(
+                "Session limit ({}) reached, queueing {} session #{} ({} ahead)",
+                state.limits.max_concurrent_sessions,
+                engine,
+                id,
+                state.queued.len()
+            );
+            state.queued.push_back(slot);
+            id
+        };
+
+        let mut guard = QueueGuard {
+            limiter: self,
+            id,
+            armed: true,
+        };
+        loop {
+            // Register for wakeups before checking, so a release in between is not missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self
+                    .state
+                    .lock()
+                    .map_err(|e| AppError::external(e.to_string()))?;
+                match state.queued.iter().position(|slot| slot.id == id) {
+                    None => {
+                        guard.armed = false;
+                        return Err(AppError::Cancelled(
+                            "Session was cancelled while waiting in the queue".to_string(),
+                        ));
+                    }
+                    Some(0) if state.has_capacity() => {
+                        let slot = state.queued.pop_front().expect("queue head exists");
+                        state.running.insert(id, slot);
+                        guard.armed = false;
+                        log::info!("Queued {} session #{} is starting", engine, id);
+                        return Ok(self.permit(id));
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    fn permit(self: &Arc<Self>, id: u64) -> SessionPermit {
+        SessionPermit {
+            limiter: self.clone(),
+            id,
+        }
+    }
+}
+
+/// Tauri state wrapper for the shared limiter
+#[derive(Clone, Default)]
+pub struct SessionLimiterState(pub Arc<SessionLimiter>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter(max: usize, queue: bool) -> Arc<SessionLimiter> {
+        Arc::new(SessionLimiter::new(SessionLimits {
+            max_concurrent_sessions: max,
+            queue_when_full: queue,
+        }))
+    }
+
+    #[tokio::test]
+    async fn rejects_when_full_and_queueing_is_disabled() {
+        let limiter = limiter(1, false);
+        let permit = limiter.acquire("claude", "/p", None, None).await.unwrap();
+        let err = limiter
+            .acquire("codex", "/p", None, None)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::QueueFull(_)), "{:?}", err);
+        assert!(err.message().contains("Maximum of 1"), "{}", err);
+
+        drop(permit);
+        assert!(limiter.acquire("codex", "/p", None, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn queued_sessions_start_when_a_permit_is_released_or_get_cancelled() {
+        let limiter = limiter(1, true);
+        let permit = limiter.acquire("claude", "/p", None, None).await.unwrap();
+
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("gemini", "/p", None, None).await })
+        };
+        let cancelled = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                limiter
+                    .acquire("claude", "/p", Some("abc".to_string()), None)
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let status = limiter.status();
+        assert_eq!(status.running.len(), 1);
+        assert_eq!(status.queued.len(), 2);
+
+        assert!(limiter.cancel_queued(None, Some("abc")));
+        assert!(matches!(
+            cancelled.await.unwrap(),
+            Err(AppError::Cancelled(_))
+        ));
+
+        drop(permit);
+        let next = tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("queued session should start")
+            .unwrap()
+            .unwrap();
+        let status = limiter.status();
+        assert_eq!(status.running.len(), 1);
+        assert_eq!(status.running[0].engine, "gemini");
+        assert!(status.queued.is_empty());
+        drop(next);
+        assert!(limiter.status().running.is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! Persistent journal of spawned CLI processes
+//!
+//! The in-memory ProcessRegistry is lost when the app crashes, leaving spawned
+//! Claude/Codex processes running with nobody tracking them. Every register /
+//! unregister is mirrored into a small JSON file so the next launch can find
+//! processes that outlived the previous instance.
+//!
+//! Each entry stores the OS-reported start time and command line captured at
+//! registration. A pid is only treated as an orphan when both still match, so a
+//! pid that has been reused by an unrelated process is never killed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A process recorded in the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub pid: u32,
+    /// "claude" / "codex" / "agent"
+    pub engine: String,
+    pub session_id: Option<String>,
+    /// When the app registered the process
+    pub registered_at: DateTime<Utc>,
+    /// OS-reported process start time, used to detect pid reuse
+    pub process_started_at: String,
+    /// OS-reported command line at registration
+    pub command: String,
+}
+
+/// A journaled process that is still alive after the app restarted
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedProcess {
+    #[serde(flatten)]
+    pub entry: JournalEntry,
+    /// Whether the process was killed by this call
+    pub killed: bool,
+}
+
+/// Start time and command line of a live process, as reported by the OS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessIdentity {
+    pub started_at: String,
+    pub command: String,
+}
+
+/// File-backed list of the processes currently tracked by the registry
+#[derive(Default)]
+pub struct ProcessJournal {
+    path: Mutex<Option<PathBuf>>,
+    entries: Mutex<Vec<JournalEntry>>,
+}
+
+impl ProcessJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the backing file and load the entries left by the previous run
+    pub fn set_path(&self, path: PathBuf) {
+        let previous = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<JournalEntry>>(&content).ok())
+            .unwrap_or_default();
+
+        if let Ok(mut entries) = self.entries.lock() {
+            // Keep entries recorded before the path was known (pids are unique while alive)
+            for entry in previous {
+                if !entries.iter().any(|e| e.pid == entry.pid) {
+                    entries.push(entry);
+                }
+            }
+        }
+        if let Ok(mut guard) = self.path.lock() {
+            *guard = Some(path);
+        }
+        self.flush();
+    }
+
+    /// Record a freshly spawned process; silently skipped if its identity can't be read
+    pub fn record(&self, pid: u32, engine: &str, session_id: Option<String>) {
+        if pid == 0 {
+            return;
+        }
+        let Some(identity) = process_identity(pid) else {
+            log::debug!(
+                "Skipping journal entry for PID {}: identity unavailable",
+                pid
+            );
+            return;
+        };
+
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|e| e.pid != pid);
+            entries.push(JournalEntry {
+                pid,
+                engine: engine.to_string(),
+                session_id,
+                registered_at: Utc::now(),
+                process_started_at: identity.started_at,
+                command: identity.command,
+            });
+        }
+        self.flush();
+    }
+
+    /// Remove a process that completed or was killed
+    pub fn forget(&self, pid: u32) {
+        let removed = match self.entries.lock() {
+            Ok(mut entries) => {
+                let before = entries.len();
+                entries.retain(|e| e.pid != pid);
+                before != entries.len()
+            }
+            Err(_) => false,
+        };
+        if removed {
+            self.flush();
+        }
+    }
+
+    /// Find journaled processes that are still alive with the same identity
+    ///
+    /// Entries whose pid is gone or now belongs to a different process are pruned.
+    /// When `kill` is true, verified orphans are killed and removed from the journal.
+    /// Pids tracked by the current session (`live_pids`) are never reported.
+    pub fn reap(&self, live_pids: &[u32], kill: bool) -> Vec<OrphanedProcess> {
+        let snapshot = match self.entries.lock() {
+            Ok(entries) => entries.clone(),
+            Err(_) => return Vec::new(),
+        };
+
+        let mut orphans = Vec::new();
+        let mut stale_pids = Vec::new();
+        for entry in snapshot {
+            if live_pids.contains(&entry.pid) {
+                continue;
+            }
+
+            match process_identity(entry.pid) {
+                Some(identity) if identity_matches(&entry, &identity) => {
+                    let killed = kill && kill_orphan(entry.pid);
+                    if killed {
+                        stale_pids.push(entry.pid);
+                    }
+                    orphans.push(OrphanedProcess { entry, killed });
+                }
+                Some(_) => {
+                    log::info!(
+                        "PID {} was reused by another process, dropping journal entry",
+                        entry.pid
+                    );
+                    stale_pids.push(entry.pid);
+                }
+                None => stale_pids.push(entry.pid),
+            }
+        }
+
+        if !stale_pids.is_empty() {
+            if let Ok(mut entries) = self.entries.lock() {
+                entries.retain(|e| !stale_pids.contains(&e.pid));
+            }
+            self.flush();
+        }
+        orphans
+    }
+
+    fn flush(&self) {
+        let Some(path) = self.path.lock().ok().and_then(|p| p.clone()) else {
+            return;
+        };
+        let content = match self.entries.lock() {
+            Ok(entries) => serde_json::to_string_pretty(&*entries),
+            Err(_) => return,
+        };
+        let Ok(content) = content else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        if let Err(e) =
+            std::fs::write(&tmp_path, content).and_then(|_| std::fs::rename(&tmp_path, &path))
+        {
+            log::warn!("Failed to write process journal {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Both the start time and the command line must match what was recorded
+fn identity_matches(entry: &JournalEntry, identity: &ProcessIdentity) -> bool {
+    entry.process_started_at == identity.started_at && entry.command == identity.command
+}
+
+fn kill_orphan(pid: u32) -> bool {
+    match crate::commands::claude::kill_process_tree(pid) {
+        Ok(_) => {
+            log::info!("Killed orphaned process {}", pid);
+            true
+        }
+        Err(e) => {
+            log::warn!("Failed to kill orphaned process {}: {}", pid, e);
+            false
+        }
+    }
+}
+
+/// Read the start time and command line of a live process (None if it doesn't exist)
+#[cfg(unix)]
+pub fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    let query = |field: &str| -> Option<String> {
+        let output = std::process::Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", field])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+
+    Some(ProcessIdentity {
+        started_at: query("lstart=")?,
+        command: query("command=")?,
+    })
+}
+
+/// Read the start time and command line of a live process (None if it doesn't exist)
+#[cfg(windows)]
+pub fn process_identity(pid: u32) -> Option<ProcessIdentity> {
+    use std::os::windows::process::CommandExt;
+
+    let script = format!(
+        "$p = Get-CimInstance Win32_Process -Filter 'ProcessId={}'; if ($p) {{ $p.CreationDate.ToUniversalTime().ToString('o'); $p.CommandLine }}",
+        pid
+    );
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(str::trim).filter(|l| !l.is_empty());
+    Some(ProcessIdentity {
+        started_at: lines.next()?.to_string(),
+        command: lines.next().unwrap_or_default().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: u32, started_at: &str, command: &str) -> JournalEntry {
+        JournalEntry {
+            pid,
+            engine: "claude".to_string(),
+            session_id: Some("session".to_string()),
+            registered_at: Utc::now(),
+            process_started_at: started_at.to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn identity_requires_matching_start_time_and_command() {
+        let recorded = entry(42, "Mon Jan  1 10:00:00 2024", "node claude --print");
+        let same = ProcessIdentity {
+            started_at: "Mon Jan  1 10:00:00 2024".to_string(),
+            command: "node claude --print".to_string(),
+        };
+        assert!(identity_matches(&recorded, &same));
+
+        let reused = ProcessIdentity {
+            started_at: "Tue Jan  2 09:00:00 2024".to_string(),
+            command: "node claude --print".to_string(),
+        };
+        assert!(!identity_matches(&recorded, &reused));
+
+        let other_command = ProcessIdentity {
+            started_at: "Mon Jan  1 10:00:00 2024".to_string(),
+            command: "vim notes.txt".to_string(),
+        };
+        assert!(!identity_matches(&recorded, &other_command));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn journal_reports_live_process_and_prunes_dead_or_reused_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("process_journal.json");
+
+        let own_pid = std::process::id();
+        let own = process_identity(own_pid).expect("current process should be visible to ps");
+        let previous = vec![
+            entry(own_pid, &own.started_at, &own.command),
+            entry(own_pid + 1_000_000, "Mon Jan  1 10:00:00 2024", "claude"),
+        ];
+        std::fs::write(&path, serde_json::to_string(&previous).unwrap()).unwrap();
+
+        let journal = ProcessJournal::new();
+        journal.set_path(path.clone());
+
+        let orphans = journal.reap(&[], false);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].entry.pid, own_pid);
+        assert!(!orphans[0].killed);
+
+        // The dead pid was pruned from the file, the live one is kept
+        let persisted: Vec<JournalEntry> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(persisted.len(), 1);
+        assert_eq!(persisted[0].pid, own_pid);
+
+        // Pids owned by the current session are never reported
+        assert!(journal.reap(&[own_pid], false).is_empty());
+
+        journal.forget(own_pid);
+        let persisted: Vec<JournalEntry> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(persisted.is_empty());
+    }
+}
@@ -1,5 +1,9 @@
+pub mod concurrency;
 pub mod job_object;
+pub mod journal;
 pub mod registry;
 
+pub use concurrency::{SessionLimiterState, SessionLimits, SessionQueueStatus};
 pub use job_object::JobObject;
+pub use journal::OrphanedProcess;
 pub use registry::*;
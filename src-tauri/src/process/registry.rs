@@ -1,15 +1,137 @@
+use super::journal::{OrphanedProcess, ProcessJournal};
 use super::JobObject;
+use crate::commands::claude::normalize_path_for_comparison;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use tokio::process::Child;
+use tokio::sync::broadcast;
+
+/// Capacity of the per-process live output broadcast channel
+const LIVE_OUTPUT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Caps for the per-process live output buffer; the oldest lines are dropped first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveOutputLimits {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for LiveOutputLimits {
+    fn default() -> Self {
+        Self {
+            max_lines: 5000,
+            max_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Idle thresholds for running Claude sessions, in seconds; 0 disables a threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleTimeouts {
+    /// Warn once a session has produced no output for this long
+    pub warn_after_secs: u64,
+    /// Cancel a session that has produced no output for this long
+    pub cancel_after_secs: u64,
+}
+
+/// What the idle watchdog should do with a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    Warn,
+    Cancel,
+}
+
+/// A session that crossed one of the idle thresholds
+#[derive(Debug, Clone)]
+pub struct IdleSession {
+    pub info: ProcessInfo,
+    pub idle_secs: u64,
+    pub action: IdleAction,
+}
+
+/// A running Claude session together with its output activity
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningSessionInfo {
+    #[serde(flatten)]
+    pub info: ProcessInfo,
+    /// Time of the last output line (process start when nothing was printed yet)
+    pub last_output_at: DateTime<Utc>,
+    /// Seconds since the last output line
+    pub idle_secs: u64,
+}
+
+/// Output activity of a running process, tracked for the idle watchdog
+#[derive(Debug, Clone, Copy)]
+struct OutputActivity {
+    last_output_at: DateTime<Utc>,
+    /// Whether the idle warning was already sent since the last output
+    warned: bool,
+}
+
+/// Bounded ring buffer of output lines for a running process
+#[derive(Debug, Default)]
+pub struct LiveOutputBuffer {
+    lines: VecDeque<String>,
+    bytes: usize,
+    dropped_lines: usize,
+}
+
+impl LiveOutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a line, then drop the oldest lines until the buffer fits the limits
+    pub fn push_line(&mut self, line: &str, limits: LiveOutputLimits) {
+        self.bytes += line.len() + 1;
+        self.lines.push_back(line.to_string());
+        self.enforce(limits);
+    }
+
+    /// Drop the oldest lines until the buffer fits the limits
+    /// The most recent line is always kept, even if it alone exceeds `max_bytes`.
+    pub fn enforce(&mut self, limits: LiveOutputLimits) {
+        while self.lines.len() > 1
+            && (self.lines.len() > limits.max_lines || self.bytes > limits.max_bytes)
+        {
+            if let Some(dropped) = self.lines.pop_front() {
+                self.bytes -= dropped.len() + 1;
+                self.dropped_lines += 1;
+            }
+        }
+    }
+
+    /// Number of lines dropped since the process started
+    pub fn dropped_lines(&self) -> usize {
+        self.dropped_lines
+    }
+
+    /// Render the buffered output, prefixed with a marker when older lines were dropped
+    pub fn render(&self) -> String {
+        let mut output = String::with_capacity(self.bytes + 64);
+        if self.dropped_lines > 0 {
+            output.push_str(&format!(
+                "[... {} earlier lines truncated ...]\n",
+                self.dropped_lines
+            ));
+        }
+        for line in &self.lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output
+    }
+}
 
 /// Type of process being tracked
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessType {
     AgentRun { agent_id: i64, agent_name: String },
     ClaudeSession { session_id: String },
+    ProjectCommand { command: String },
 }
 
 /// Information about a running agent process
@@ -29,7 +151,7 @@ pub struct ProcessInfo {
 pub struct ProcessHandle {
     pub info: ProcessInfo,
     pub child: Arc<Mutex<Option<Child>>>,
-    pub live_output: Arc<Mutex<String>>,
+    pub live_output: Arc<Mutex<LiveOutputBuffer>>,
     #[cfg(windows)]
     pub job_object: Option<Arc<JobObject>>, // Job object for automatic cleanup on Windows
 }
@@ -38,6 +160,11 @@ pub struct ProcessHandle {
 pub struct ProcessRegistry {
     processes: Arc<Mutex<HashMap<i64, ProcessHandle>>>, // run_id -> ProcessHandle
     next_id: Arc<Mutex<i64>>, // Auto-incrementing ID for non-agent processes
+    output_channels: Arc<Mutex<HashMap<i64, broadcast::Sender<String>>>>, // run_id -> live output followers
+    live_output_limits: Arc<Mutex<LiveOutputLimits>>,
+    journal: Arc<ProcessJournal>, // Persisted copy used to find orphans after a crash
+    output_activity: Arc<Mutex<HashMap<i64, OutputActivity>>>, // run_id -> last output time
+    idle_timeouts: Arc<Mutex<IdleTimeouts>>,
 }
 
 impl ProcessRegistry {
@@ -45,7 +172,75 @@ impl ProcessRegistry {
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
             next_id: Arc::new(Mutex::new(1000000)), // Start at high number to avoid conflicts
+            output_channels: Arc::new(Mutex::new(HashMap::new())),
+            live_output_limits: Arc::new(Mutex::new(LiveOutputLimits::default())),
+            journal: Arc::new(ProcessJournal::new()),
+            output_activity: Arc::new(Mutex::new(HashMap::new())),
+            idle_timeouts: Arc::new(Mutex::new(IdleTimeouts::default())),
+        }
+    }
+
+    /// Persistent journal mirroring the registered processes
+    pub fn journal(&self) -> &Arc<ProcessJournal> {
+        &self.journal
+    }
+
+    /// Record a spawned process in the journal without blocking the caller
+    /// (reading the process identity shells out to ps / PowerShell)
+    pub fn journal_process(&self, pid: u32, engine: &'static str, session_id: Option<String>) {
+        let journal = self.journal.clone();
+        std::thread::spawn(move || journal.record(pid, engine, session_id));
+    }
+
+    /// Find processes from a previous run that are still alive, optionally killing them
+    pub fn reap_orphaned_processes(
+        &self,
+        extra_live_pids: &[u32],
+        kill: bool,
+    ) -> Result<Vec<OrphanedProcess>, String> {
+        let mut live_pids: Vec<u32> = {
+            let processes = self.processes.lock().map_err(|e| e.to_string())?;
+            processes.values().map(|handle| handle.info.pid).collect()
+        };
+        live_pids.extend_from_slice(extra_live_pids);
+        Ok(self.journal.reap(&live_pids, kill))
+    }
+
+    /// Current live output caps
+    pub fn live_output_limits(&self) -> LiveOutputLimits {
+        self.live_output_limits
+            .lock()
+            .map(|limits| *limits)
+            .unwrap_or_default()
+    }
+
+    /// Update live output caps and trim the buffers of running processes right away
+    pub fn set_live_output_limits(&self, limits: LiveOutputLimits) -> Result<(), String> {
+        *self.live_output_limits.lock().map_err(|e| e.to_string())? = limits;
+
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        for handle in processes.values() {
+            handle
+                .live_output
+                .lock()
+                .map_err(|e| e.to_string())?
+                .enforce(limits);
         }
+        Ok(())
+    }
+
+    /// Current idle thresholds of the watchdog
+    pub fn idle_timeouts(&self) -> IdleTimeouts {
+        self.idle_timeouts
+            .lock()
+            .map(|timeouts| *timeouts)
+            .unwrap_or_default()
+    }
+
+    /// Update the idle thresholds of the watchdog
+    pub fn set_idle_timeouts(&self, timeouts: IdleTimeouts) -> Result<(), String> {
+        *self.idle_timeouts.lock().map_err(|e| e.to_string())? = timeouts;
+        Ok(())
     }
 
     /// Generate a unique ID for non-agent processes
@@ -129,7 +324,9 @@ impl ProcessRegistry {
 
         let process_info = ProcessInfo {
             run_id,
-            process_type: ProcessType::ClaudeSession { session_id },
+            process_type: ProcessType::ClaudeSession {
+                session_id: session_id.clone(),
+            },
             pid,
             started_at: Utc::now(),
             project_path,
@@ -178,11 +375,12 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(None)),
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: Arc::new(Mutex::new(LiveOutputBuffer::new())),
             job_object,
         };
 
         processes.insert(run_id, process_handle);
+        self.journal_process(pid, "claude", Some(session_id));
         Ok(run_id)
     }
 
@@ -201,7 +399,9 @@ impl ProcessRegistry {
 
         let process_info = ProcessInfo {
             run_id,
-            process_type: ProcessType::ClaudeSession { session_id },
+            process_type: ProcessType::ClaudeSession {
+                session_id: session_id.clone(),
+            },
             pid,
             started_at: Utc::now(),
             project_path,
@@ -214,10 +414,48 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(None)),
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: Arc::new(Mutex::new(LiveOutputBuffer::new())),
         };
 
         processes.insert(run_id, process_handle);
+        self.journal_process(pid, "claude", Some(session_id));
+        Ok(run_id)
+    }
+
+    /// Register a one-off project command; the caller keeps the child to read its output
+    pub fn register_project_command(
+        &self,
+        pid: u32,
+        project_path: String,
+        command: String,
+    ) -> Result<i64, String> {
+        let run_id = self.generate_id()?;
+
+        let process_info = ProcessInfo {
+            run_id,
+            process_type: ProcessType::ProjectCommand {
+                command: command.clone(),
+            },
+            pid,
+            started_at: Utc::now(),
+            project_path,
+            task: command,
+            model: String::new(),
+        };
+
+        let process_handle = ProcessHandle {
+            info: process_info,
+            child: Arc::new(Mutex::new(None)),
+            live_output: Arc::new(Mutex::new(LiveOutputBuffer::new())),
+            #[cfg(windows)]
+            job_object: None,
+        };
+
+        self.processes
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(run_id, process_handle);
+        self.journal_process(pid, "command", None);
         Ok(run_id)
     }
 
@@ -262,12 +500,14 @@ impl ProcessRegistry {
         let process_handle = ProcessHandle {
             info: process_info,
             child: Arc::new(Mutex::new(Some(child))),
-            live_output: Arc::new(Mutex::new(String::new())),
+            live_output: Arc::new(Mutex::new(LiveOutputBuffer::new())),
             #[cfg(windows)]
             job_object,
         };
 
+        let pid = process_handle.info.pid;
         processes.insert(run_id, process_handle);
+        self.journal_process(pid, "agent", None);
         Ok(())
     }
 
@@ -283,6 +523,90 @@ impl ProcessRegistry {
             .collect())
     }
 
+    /// Get running Claude sessions registered for a project path
+    /// Paths are compared normalized (case, separators, trailing slashes)
+    pub fn get_running_claude_sessions_for_project(
+        &self,
+        project_path: &str,
+    ) -> Result<Vec<ProcessInfo>, String> {
+        let target = normalize_path_for_comparison(project_path);
+        Ok(self
+            .get_running_claude_sessions()?
+            .into_iter()
+            .filter(|info| normalize_path_for_comparison(&info.project_path) == target)
+            .collect())
+    }
+
+    /// Get all running Claude sessions with the time since their last output
+    pub fn get_running_claude_sessions_with_activity(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<RunningSessionInfo>, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        let activity = self.output_activity.lock().map_err(|e| e.to_string())?;
+        Ok(processes
+            .iter()
+            .filter(|(_, handle)| {
+                matches!(handle.info.process_type, ProcessType::ClaudeSession { .. })
+            })
+            .map(|(run_id, handle)| {
+                let last_output_at = activity
+                    .get(run_id)
+                    .map(|entry| entry.last_output_at)
+                    .unwrap_or(handle.info.started_at);
+                RunningSessionInfo {
+                    info: handle.info.clone(),
+                    last_output_at,
+                    idle_secs: (now - last_output_at).num_seconds().max(0) as u64,
+                }
+            })
+            .collect())
+    }
+
+    /// Find Claude sessions past an idle threshold
+    ///
+    /// A session is reported for `Warn` once per idle period (new output re-arms the
+    /// warning) and for `Cancel` on every check until it is gone.
+    pub fn collect_idle_sessions(&self, now: DateTime<Utc>) -> Result<Vec<IdleSession>, String> {
+        let timeouts = self.idle_timeouts();
+        if timeouts.warn_after_secs == 0 && timeouts.cancel_after_secs == 0 {
+            return Ok(Vec::new());
+        }
+
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        let mut activity = self.output_activity.lock().map_err(|e| e.to_string())?;
+        let mut idle = Vec::new();
+        for (run_id, handle) in processes.iter() {
+            if !matches!(handle.info.process_type, ProcessType::ClaudeSession { .. }) {
+                continue;
+            }
+            let entry = activity.entry(*run_id).or_insert(OutputActivity {
+                last_output_at: handle.info.started_at,
+                warned: false,
+            });
+            let idle_secs = (now - entry.last_output_at).num_seconds().max(0) as u64;
+
+            let action =
+                if timeouts.cancel_after_secs > 0 && idle_secs >= timeouts.cancel_after_secs {
+                    IdleAction::Cancel
+                } else if timeouts.warn_after_secs > 0
+                    && idle_secs >= timeouts.warn_after_secs
+                    && !entry.warned
+                {
+                    entry.warned = true;
+                    IdleAction::Warn
+                } else {
+                    continue;
+                };
+            idle.push(IdleSession {
+                info: handle.info.clone(),
+                idle_secs,
+                action,
+            });
+        }
+        Ok(idle)
+    }
+
     /// Get a specific Claude session by session ID
     pub fn get_claude_session_by_id(
         &self,
@@ -302,7 +626,18 @@ impl ProcessRegistry {
     #[allow(dead_code)]
     pub fn unregister_process(&self, run_id: i64) -> Result<(), String> {
         let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
-        processes.remove(&run_id);
+        if let Some(handle) = processes.remove(&run_id) {
+            self.journal.forget(handle.info.pid);
+        }
+        self.output_activity
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(&run_id);
+        // Dropping the sender closes every follower of this process
+        self.output_channels
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(&run_id);
         Ok(())
     }
 
@@ -673,21 +1008,70 @@ impl ProcessRegistry {
 
     /// Append to live output for a process
     pub fn append_live_output(&self, run_id: i64, output: &str) -> Result<(), String> {
+        let limits = self.live_output_limits();
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         if let Some(handle) = processes.get(&run_id) {
+            self.output_activity
+                .lock()
+                .map_err(|e| e.to_string())?
+                .insert(
+                    run_id,
+                    OutputActivity {
+                        last_output_at: Utc::now(),
+                        warned: false,
+                    },
+                );
+
             let mut live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
-            live_output.push_str(output);
-            live_output.push('\n');
+            live_output.push_line(output, limits);
+
+            // Forward to followers while still holding the buffer lock, so a new
+            // subscriber never misses or duplicates a line between snapshot and follow
+            let output_channels = self.output_channels.lock().map_err(|e| e.to_string())?;
+            if let Some(sender) = output_channels.get(&run_id) {
+                let _ = sender.send(output.to_string());
+            }
         }
         Ok(())
     }
 
+    /// Snapshot the live output of a process and follow subsequent lines
+    ///
+    /// Returns `None` when the process is not (or no longer) registered.
+    pub fn subscribe_live_output(
+        &self,
+        run_id: i64,
+    ) -> Result<Option<(String, broadcast::Receiver<String>)>, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        let Some(handle) = processes.get(&run_id) else {
+            return Ok(None);
+        };
+
+        let live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
+        let mut output_channels = self.output_channels.lock().map_err(|e| e.to_string())?;
+        let receiver = output_channels
+            .entry(run_id)
+            .or_insert_with(|| broadcast::channel(LIVE_OUTPUT_CHANNEL_CAPACITY).0)
+            .subscribe();
+
+        Ok(Some((live_output.render(), receiver)))
+    }
+
     /// Get live output for a process
+    /// When older lines were dropped by the buffer caps, the output starts with a
+    /// `[... N earlier lines truncated ...]` marker line.
     pub fn get_live_output(&self, run_id: i64) -> Result<String, String> {
         let processes = self.processes.lock().map_err(|e| e.to_string())?;
         if let Some(handle) = processes.get(&run_id) {
             let live_output = handle.live_output.lock().map_err(|e| e.to_string())?;
-            Ok(live_output.clone())
+            if live_output.dropped_lines() > 0 {
+                log::debug!(
+                    "Live output for run {} truncated ({} lines dropped)",
+                    run_id,
+                    live_output.dropped_lines()
+                );
+            }
+            Ok(live_output.render())
         } else {
             Ok(String::new())
         }
@@ -715,8 +1099,12 @@ impl ProcessRegistry {
         // Then remove them from the registry
         {
             let mut processes = processes_lock.lock().map_err(|e| e.to_string())?;
+            let mut output_channels = self.output_channels.lock().map_err(|e| e.to_string())?;
             for run_id in &finished_runs {
-                processes.remove(run_id);
+                if let Some(handle) = processes.remove(run_id) {
+                    self.journal.forget(handle.info.pid);
+                }
+                output_channels.remove(run_id);
             }
         }
 
@@ -865,3 +1253,177 @@ impl Drop for ProcessRegistryState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_lines: usize, max_bytes: usize) -> LiveOutputLimits {
+        LiveOutputLimits {
+            max_lines,
+            max_bytes,
+        }
+    }
+
+    #[test]
+    fn live_output_buffer_drops_oldest_lines_past_line_cap() {
+        let mut buffer = LiveOutputBuffer::new();
+        for i in 0..10 {
+            buffer.push_line(&format!("line {}", i), limits(3, usize::MAX));
+        }
+
+        assert_eq!(buffer.dropped_lines(), 7);
+        assert_eq!(
+            buffer.render(),
+            "[... 7 earlier lines truncated ...]\nline 7\nline 8\nline 9\n"
+        );
+    }
+
+    #[test]
+    fn live_output_buffer_respects_byte_cap_but_keeps_latest_line() {
+        let mut buffer = LiveOutputBuffer::new();
+        buffer.push_line("aaaa", limits(100, 10));
+        buffer.push_line("bbbb", limits(100, 10));
+        assert_eq!(buffer.render(), "aaaa\nbbbb\n");
+
+        buffer.push_line("cccc", limits(100, 10));
+        assert_eq!(
+            buffer.render(),
+            "[... 1 earlier lines truncated ...]\nbbbb\ncccc\n"
+        );
+
+        buffer.push_line(&"x".repeat(64), limits(100, 10));
+        assert_eq!(buffer.dropped_lines(), 3);
+        assert!(buffer.render().ends_with(&format!("{}\n", "x".repeat(64))));
+    }
+
+    #[test]
+    fn untruncated_output_has_no_marker() {
+        let mut buffer = LiveOutputBuffer::new();
+        buffer.push_line("hello", LiveOutputLimits::default());
+        assert_eq!(buffer.render(), "hello\n");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn get_live_output_returns_most_recent_lines_after_truncation() {
+        let registry = ProcessRegistry::new();
+        registry
+            .set_live_output_limits(limits(2, usize::MAX))
+            .unwrap();
+        let run_id = registry
+            .register_claude_session(
+                "session-1".to_string(),
+                0,
+                "/tmp/project".to_string(),
+                "task".to_string(),
+                "sonnet".to_string(),
+            )
+            .unwrap();
+
+        for line in ["first", "second", "third"] {
+            registry.append_live_output(run_id, line).unwrap();
+        }
+
+        let output = registry.get_live_output(run_id).unwrap();
+        assert_eq!(
+            output,
+            "[... 1 earlier lines truncated ...]\nsecond\nthird\n"
+        );
+
+        // Tightening the cap trims running buffers immediately
+        registry
+            .set_live_output_limits(limits(1, usize::MAX))
+            .unwrap();
+        let output = registry.get_live_output(run_id).unwrap();
+        assert_eq!(output, "[... 2 earlier lines truncated ...]\nthird\n");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn claude_sessions_filter_by_normalized_project_path() {
+        let registry = ProcessRegistry::new();
+        for (session, path) in [
+            ("a", "/tmp/Project"),
+            ("b", "/tmp/other"),
+            ("c", "/tmp/project/"),
+        ] {
+            registry
+                .register_claude_session(
+                    session.to_string(),
+                    0,
+                    path.to_string(),
+                    "task".to_string(),
+                    "sonnet".to_string(),
+                )
+                .unwrap();
+        }
+
+        let mut sessions: Vec<_> = registry
+            .get_running_claude_sessions_for_project("/tmp/project")
+            .unwrap()
+            .into_iter()
+            .map(|info| match info.process_type {
+                ProcessType::ClaudeSession { session_id } => session_id,
+                _ => unreachable!(),
+            })
+            .collect();
+        sessions.sort();
+        // 只有 Windows / macOS 忽略路径大小写
+        if cfg!(target_os = "macos") {
+            assert_eq!(sessions, vec!["a", "c"]);
+        } else {
+            assert_eq!(sessions, vec!["c"]);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn idle_sessions_warn_once_and_cancel_past_second_threshold() {
+        let registry = ProcessRegistry::new();
+        let run_id = registry
+            .register_claude_session(
+                "session-1".to_string(),
+                0,
+                "/tmp/project".to_string(),
+                "task".to_string(),
+                "sonnet".to_string(),
+            )
+            .unwrap();
+        let started = registry.get_process(run_id).unwrap().unwrap().started_at;
+        let at = |secs: i64| started + chrono::Duration::seconds(secs);
+
+        // Off by default
+        assert!(registry.collect_idle_sessions(at(3600)).unwrap().is_empty());
+
+        registry
+            .set_idle_timeouts(IdleTimeouts {
+                warn_after_secs: 60,
+                cancel_after_secs: 300,
+            })
+            .unwrap();
+        assert!(registry.collect_idle_sessions(at(30)).unwrap().is_empty());
+
+        let idle = registry.collect_idle_sessions(at(90)).unwrap();
+        assert_eq!(idle.len(), 1);
+        assert_eq!(idle[0].action, IdleAction::Warn);
+        assert_eq!(idle[0].idle_secs, 90);
+        // The warning is sent once per idle period
+        assert!(registry.collect_idle_sessions(at(120)).unwrap().is_empty());
+
+        let idle = registry.collect_idle_sessions(at(300)).unwrap();
+        assert_eq!(idle[0].action, IdleAction::Cancel);
+
+        let sessions = registry
+            .get_running_claude_sessions_with_activity(at(200))
+            .unwrap();
+        assert_eq!(sessions[0].idle_secs, 200);
+
+        // New output resets the idle time
+        registry.append_live_output(run_id, "line").unwrap();
+        let sessions = registry
+            .get_running_claude_sessions_with_activity(Utc::now())
+            .unwrap();
+        assert!(sessions[0].idle_secs < 5);
+    }
+}
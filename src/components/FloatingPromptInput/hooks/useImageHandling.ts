@@ -163,7 +163,7 @@ export function useImageHandling({
             try {
               const result = await api.saveClipboardImage(base64Data);
               
-              if (result.success && result.file_path) {
+              if (result.file_path) {
                 const base64Content = base64Data.split(',')[1];
                 const binaryData = atob(base64Content);
                 const bytes = new Uint8Array(binaryData.length);
@@ -183,7 +183,7 @@ export function useImageHandling({
                 
                 setImageAttachments(prev => [...prev, newAttachment]);
               } else {
-                console.error('Failed to save clipboard image:', result.error);
+                console.error('Failed to save clipboard image: no file path returned');
                 alert('保存剪贴板图片失败，请重试');
               }
             } catch (error) {
@@ -551,9 +551,11 @@ export interface MCPServerConfig {
  * Result of saving clipboard image
  */
 export interface SavedImageResult {
-  success: boolean;
   file_path?: string;
-  error?: string;
+  width?: number;
+  height?: number;
+  format?: string;
+  byte_size?: number;
 }
 
 /**
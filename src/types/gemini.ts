@@ -268,5 +268,7 @@ export interface GeminiSessionInfo {
   sessionId: string;
   fileName: string;
   startTime: string;
+  /** Timestamp of the last message (falls back to the file's lastUpdated) */
+  lastMessageTime: string;
   firstMessage?: string;
 }